@@ -0,0 +1,805 @@
+//! Everything the user can do to the `App`, as messages: the UI turns key presses into
+//! `Action`s (see `ui::keys`) and `App::update` applies them. Background results still
+//! arrive through the `poll_*` methods.
+
+use super::{App, ConflictChoice, InputMode, SyncSource, TextInput, View};
+use crate::domain::todo::Priority;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+
+    // Crash-recovery offer.
+    RestoreSession,
+    DiscardSession,
+
+    // Lock screen: typing the unlock phrase.
+    UnlockPush(char),
+    UnlockPop,
+    ConfirmUnlock,
+
+    // Error details (`E`) and the status log (`L`).
+    ToggleErrorView,
+    ScrollErrorView(i32),
+    CopyError,
+    ToggleStatusLog,
+    ScrollStatusLog(i32),
+
+    // Help overlay; the UI owns the help text, so it resolves search matches to lines.
+    ToggleHelpQuick,
+    ToggleHelpFull,
+    CloseHelp,
+    StartHelpSearch,
+    StopHelpSearch,
+    /// Leave the search prompt, scrolling to the first matching line if there is one.
+    ConfirmHelpSearch {
+        first_match: Option<usize>,
+    },
+    HelpSearchPush(char),
+    HelpSearchPop,
+    /// Scroll to match number `index`, found on `line`.
+    ShowHelpMatch {
+        index: usize,
+        line: usize,
+    },
+    ScrollHelp(i16),
+    ScrollHelpTop,
+
+    // PR review draft (`R`).
+    OpenReview,
+    CancelReview,
+    SubmitReview,
+    CycleReviewEvent,
+    ReviewPush(char),
+    ReviewPop,
+
+    // Title conflicts (`x`).
+    ReviewConflicts,
+    PostponeConflict,
+    CycleConflictChoice {
+        forward: bool,
+    },
+    ResolveConflict(ConflictChoice),
+
+    // Sync menu (`S`) and syncs.
+    OpenSyncMenu,
+    CloseSyncMenu,
+    MoveSyncMenu(isize),
+    ToggleSyncSource,
+    RunSyncMenu,
+    StartSync(SyncSource),
+    /// `g`: sync on the second press, or jump to the top.
+    PressG,
+    FlushPendingG,
+
+    // CI checks (`C`) and the link picker.
+    OpenCiChecks,
+    CloseCiChecks,
+    MoveCiCheck(isize),
+    OpenCiCheck,
+    CloseLinkPicker,
+    MoveLinkPicker(isize),
+    OpenPickedLink,
+
+    // Month calendar (`M`).
+    OpenCalendar,
+    CloseCalendar,
+    MoveCalendar(i64),
+    MoveCalendarMonth {
+        forward: bool,
+    },
+    ApplyCalendarDay,
+
+    // Jump finder (`Ctrl-p`).
+    OpenJump,
+    CloseJump,
+    JumpPush(char),
+    JumpPop,
+    MoveJump(isize),
+    ConfirmJump,
+
+    // Views and the inbox.
+    SetView(View),
+    MoveInbox(isize),
+    InboxTop,
+    OpenNotification,
+    MarkNotificationRead,
+    AddNotificationTodo,
+    RefreshInbox,
+
+    // Moving around the list.
+    SelectNext,
+    SelectPrevious,
+    SelectFirst,
+    SelectLast,
+    ScrollPage {
+        down: bool,
+        half: bool,
+    },
+    ToggleDetail,
+    CollapseGroup,
+    ToggleAllGroups,
+    CycleRequestFilter,
+    ToggleHideBots,
+    ToggleShowScheduled,
+    ToggleSort,
+    Reload,
+
+    // Editing the selected todo.
+    /// Enter: open the todo's link, or toggle it when it has none.
+    Activate,
+    ToggleSelected,
+    /// Step the selected todo to its next status.
+    CycleStatus,
+    CyclePriority,
+    /// `p`: the next key picks the priority.
+    StartPriorityPrefix,
+    CancelPriorityPrefix,
+    SetPriority(Priority),
+    /// `b`: the selected todo waits for the marked ones (none marked: stops waiting).
+    BlockSelected,
+    /// Board: select in the column to the left (-1) / right (1).
+    MoveBoardColumn(isize),
+    /// Board: move the selected todo to the column to the left (-1) / right (1).
+    ShiftBoard(isize),
+    ToggleBoardLayout,
+    ShiftDue(i64),
+    ClearDue,
+    ToggleTimer,
+    UpdateBranch,
+    MarkSeen,
+    MoveSelected {
+        down: bool,
+    },
+    Copy {
+        title_only: bool,
+    },
+    RequestDelete,
+    ConfirmDelete,
+    CancelPendingDelete,
+    ClearDone,
+
+    // Marks (`v`) and filters.
+    ToggleMark,
+    ToggleMarkAll,
+    ClearMarks,
+    ClearSearch,
+    ClearDayFilter,
+
+    // Prompts. `InputPush` / `InputPop` / `SubmitInput` / `CancelInput` act on whichever
+    // prompt is open; the cursor actions on every prompt but search.
+    StartAdd,
+    StartAddSubtask,
+    EditTitle,
+    EditDue,
+    StartTagging,
+    StartWatching,
+    StartSearch,
+    CycleSearchScope,
+    InputPush(char),
+    /// Alt-Enter while adding: the following lines become notes.
+    InputNewline,
+    InputPop,
+    /// Delete: the char under the cursor.
+    InputDelete,
+    /// Ctrl-w.
+    InputDeleteWord,
+    InputLeft,
+    InputRight,
+    InputHome,
+    InputEnd,
+    SubmitInput,
+    CancelInput,
+}
+
+impl App {
+    /// Apply one action. Returns true when the app should exit.
+    pub fn update(&mut self, action: Action) -> bool {
+        match action {
+            Action::Quit => return true,
+
+            Action::RestoreSession => self.restore_session(),
+            Action::DiscardSession => self.discard_session(),
+
+            Action::UnlockPush(c) => self.unlock_input.push(c),
+            Action::UnlockPop => {
+                self.unlock_input.pop();
+            }
+            Action::ConfirmUnlock => self.confirm_unlock(),
+
+            Action::ToggleErrorView => self.toggle_error_view(),
+            Action::ScrollErrorView(delta) => self.scroll_error_view(delta),
+            Action::CopyError => self.copy_error(),
+            Action::ToggleStatusLog => self.toggle_status_log(),
+            Action::ScrollStatusLog(delta) => self.scroll_status_log(delta),
+
+            Action::ToggleHelpQuick => self.toggle_help_quick(),
+            Action::ToggleHelpFull => self.toggle_help_full(),
+            Action::CloseHelp => self.close_help(),
+            Action::StartHelpSearch => {
+                self.help_searching = true;
+                self.help_search_query.clear();
+                self.help_search_match = 0;
+            }
+            Action::StopHelpSearch => self.help_searching = false,
+            Action::ConfirmHelpSearch { first_match } => {
+                if let Some(line) = first_match {
+                    self.help_scroll = line.saturating_sub(1) as u16;
+                    self.help_search_match = 0;
+                }
+                self.help_searching = false;
+            }
+            Action::HelpSearchPush(c) => {
+                self.help_search_query.push(c);
+                self.help_search_match = 0;
+            }
+            Action::HelpSearchPop => {
+                self.help_search_query.pop();
+                self.help_search_match = 0;
+            }
+            Action::ShowHelpMatch { index, line } => {
+                self.help_search_match = index;
+                self.help_scroll = line.saturating_sub(1) as u16;
+            }
+            Action::ScrollHelp(delta) => {
+                self.help_scroll = self.help_scroll.saturating_add_signed(delta);
+            }
+            Action::ScrollHelpTop => self.help_scroll = 0,
+
+            Action::OpenReview => self.open_review(),
+            Action::CancelReview => self.cancel_review(),
+            Action::SubmitReview => self.submit_review(),
+            Action::CycleReviewEvent => {
+                if let Some(draft) = &mut self.review {
+                    draft.event = draft.event.next();
+                }
+            }
+            Action::ReviewPush(c) => {
+                if let Some(draft) = &mut self.review {
+                    draft.body.push(c);
+                }
+            }
+            Action::ReviewPop => {
+                if let Some(draft) = &mut self.review {
+                    draft.body.pop();
+                }
+            }
+
+            Action::ReviewConflicts => self.review_conflicts(),
+            Action::PostponeConflict => self.postpone_conflict(),
+            Action::CycleConflictChoice { forward } => {
+                if let Some(conflict) = &mut self.conflict {
+                    conflict.choice = if forward {
+                        conflict.choice.next()
+                    } else {
+                        conflict.choice.next().next()
+                    };
+                }
+            }
+            Action::ResolveConflict(choice) => self.resolve_conflict(choice),
+
+            Action::OpenSyncMenu => self.open_sync_menu(),
+            Action::CloseSyncMenu => self.close_sync_menu(),
+            Action::MoveSyncMenu(delta) => self.move_sync_menu(delta),
+            Action::ToggleSyncSource => self.toggle_sync_source(),
+            Action::RunSyncMenu => self.run_sync_menu(),
+            Action::StartSync(source) => self.start_sync(source),
+            Action::PressG => self.press_g(),
+            Action::FlushPendingG => self.flush_pending_g(),
+
+            Action::OpenCiChecks => self.open_ci_checks(),
+            Action::CloseCiChecks => self.close_ci_checks(),
+            Action::MoveCiCheck(delta) => self.move_ci_check(delta),
+            Action::OpenCiCheck => self.open_selected_ci_check(),
+            Action::CloseLinkPicker => self.close_link_picker(),
+            Action::MoveLinkPicker(delta) => self.move_link_picker(delta),
+            Action::OpenPickedLink => self.open_picked_link(),
+
+            Action::OpenJump => self.open_jump(),
+            Action::CloseJump => self.jump = None,
+            Action::JumpPush(c) => self.jump_push(c),
+            Action::JumpPop => self.jump_pop(),
+            Action::MoveJump(delta) => self.move_jump(delta),
+            Action::ConfirmJump => self.confirm_jump(),
+
+            Action::OpenCalendar => self.open_calendar(),
+            Action::CloseCalendar => self.close_calendar(),
+            Action::MoveCalendar(days) => self.move_calendar(days),
+            Action::MoveCalendarMonth { forward } => self.move_calendar_month(forward),
+            Action::ApplyCalendarDay => self.apply_calendar_day(),
+
+            Action::SetView(view) => self.set_view(view),
+            Action::MoveInbox(delta) => self.move_inbox(delta),
+            Action::InboxTop => self.inbox_selected = 0,
+            Action::OpenNotification => self.open_selected_notification(),
+            Action::MarkNotificationRead => self.mark_notification_read(),
+            Action::AddNotificationTodo => self.add_notification_todo(),
+            Action::RefreshInbox => self.refresh_inbox(),
+
+            Action::SelectNext => self.select_next(),
+            Action::SelectPrevious => self.select_previous(),
+            Action::SelectFirst => self.select_first(),
+            Action::SelectLast => self.select_last(),
+            Action::ScrollPage { down, half } => self.scroll_page(down, half),
+            Action::ToggleDetail => self.toggle_detail(),
+            Action::CollapseGroup => self.collapse_selected_group(),
+            Action::ToggleAllGroups => self.toggle_all_groups(),
+            Action::CycleRequestFilter => self.cycle_request_filter(),
+            Action::ToggleHideBots => self.toggle_hide_bots(),
+            Action::ToggleShowScheduled => self.toggle_show_scheduled(),
+            Action::ToggleSort => self.toggle_sort(),
+            Action::Reload => {
+                self.reload();
+                self.set_status("Reloaded");
+            }
+
+            Action::Activate => {
+                if !self.open_selected_link() {
+                    self.toggle_selected();
+                }
+            }
+            Action::ToggleSelected => self.toggle_selected(),
+            Action::CycleStatus => self.cycle_status(),
+            Action::CyclePriority => self.cycle_priority_selected(),
+            Action::StartPriorityPrefix => self.start_priority_prefix(),
+            Action::CancelPriorityPrefix => self.pending_priority = false,
+            Action::SetPriority(priority) => self.set_priority_selected(priority),
+            Action::BlockSelected => self.block_selected(),
+            Action::MoveBoardColumn(delta) => self.move_board_column(delta),
+            Action::ShiftBoard(delta) => self.shift_board(delta),
+            Action::ToggleBoardLayout => self.toggle_board_layout(),
+            Action::ShiftDue(days) => self.shift_due_selected(days),
+            Action::ClearDue => self.clear_due_selected(),
+            Action::ToggleTimer => self.toggle_timer_selected(),
+            Action::UpdateBranch => self.update_branch_selected(),
+            Action::MarkSeen => self.mark_seen_selected(),
+            Action::MoveSelected { down } => self.move_selected(down),
+            Action::Copy { title_only } => self.copy_selected(title_only),
+            Action::RequestDelete => self.request_delete_selected(),
+            Action::ConfirmDelete => self.confirm_pending_delete(),
+            Action::CancelPendingDelete => self.cancel_pending_delete(),
+            Action::ClearDone => self.clear_done(),
+
+            Action::ToggleMark => self.toggle_mark_selected(),
+            Action::ToggleMarkAll => self.toggle_mark_all(),
+            Action::ClearMarks => self.clear_marks(),
+            Action::ClearSearch => self.clear_search(),
+            Action::ClearDayFilter => self.clear_day_filter(),
+
+            Action::StartAdd => {
+                self.mode = InputMode::Editing;
+                self.input.clear();
+                self.set_status("Type new task and press Enter");
+            }
+            Action::StartAddSubtask => self.start_add_subtask(),
+            Action::EditTitle => self.edit_title(),
+            Action::EditDue => self.edit_due(),
+            Action::StartTagging => self.start_tagging(),
+            Action::StartWatching => self.start_watching(),
+            Action::StartSearch => self.start_search(),
+            Action::CycleSearchScope => self.cycle_search_scope(),
+            Action::InputPush(c) => match self.mode {
+                InputMode::Search => self.search_push(c),
+                InputMode::Normal => {}
+                _ => self.input.insert(c),
+            },
+            Action::InputNewline => {
+                if self.mode == InputMode::Editing {
+                    self.input.insert('\n');
+                }
+            }
+            Action::InputPop => match self.mode {
+                InputMode::Search => self.search_pop(),
+                InputMode::Normal => {}
+                _ => self.input.delete_back(),
+            },
+            Action::InputDelete => self.edit_input(TextInput::delete_forward),
+            Action::InputDeleteWord => self.edit_input(TextInput::delete_word),
+            Action::InputLeft => self.edit_input(TextInput::left),
+            Action::InputRight => self.edit_input(TextInput::right),
+            Action::InputHome => self.edit_input(TextInput::home),
+            Action::InputEnd => self.edit_input(TextInput::end),
+            Action::SubmitInput => match self.mode {
+                InputMode::Normal => {}
+                InputMode::Editing => self.add_todo(),
+                InputMode::EditingDue => self.apply_due_edit(),
+                InputMode::EditingTitle => self.apply_title_edit(),
+                InputMode::Tagging => self.apply_tagging(),
+                InputMode::Watching => self.apply_watching(),
+                InputMode::Search => self.confirm_search(),
+            },
+            Action::CancelInput => match self.mode {
+                InputMode::Normal => {}
+                InputMode::EditingTitle => self.cancel_title_edit(),
+                InputMode::Search => self.clear_search(),
+                InputMode::Editing
+                | InputMode::EditingDue
+                | InputMode::Tagging
+                | InputMode::Watching => {
+                    self.mode = InputMode::Normal;
+                    self.input.clear();
+                    self.adding_under = None;
+                    self.set_status("Canceled");
+                }
+            },
+        }
+        false
+    }
+
+    /// Search keeps its own query, so the cursor keys only edit the other prompts.
+    fn edit_input(&mut self, edit: fn(&mut TextInput)) {
+        if !matches!(self.mode, InputMode::Normal | InputMode::Search) {
+            edit(&mut self.input);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Reducer snapshots: each test applies a script of actions to a fresh `App` and
+    //! compares the state after every step with `snapshots/<test>.snap`. Regenerate after
+    //! an intended change with `UPDATE_GOLDEN=1 cargo test`, then review the diff.
+
+    use std::fmt::Write as _;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::config::Config;
+    use crate::repo::memory::InMemoryTodoRepo;
+    use crate::worker::Worker;
+
+    fn app() -> App {
+        App::new(
+            Box::new(InMemoryTodoRepo::default()),
+            Config::default(),
+            Vec::new(),
+            Worker::start().unwrap(),
+        )
+    }
+
+    fn typed(text: &str) -> Vec<Action> {
+        text.chars().map(Action::InputPush).collect()
+    }
+
+    /// What the user would see, minus styling: the prompt, the list and the status line.
+    fn render(app: &App) -> String {
+        let mut out = format!("mode: {:?}", app.mode);
+        match (&app.mode, &app.search) {
+            (InputMode::Normal, _) => {}
+            (InputMode::Search, Some(query)) => write!(out, " {:?}", query.text).unwrap(),
+            _ => write!(out, " {:?}", app.input.as_str()).unwrap(),
+        }
+        out.push('\n');
+        for (idx, todo) in app.todos.iter().enumerate() {
+            writeln!(
+                out,
+                "{} [{}] {:?} {}{}{}",
+                if idx == app.selected { '>' } else { ' ' },
+                if todo.is_closed() { 'x' } else { ' ' },
+                todo.priority,
+                todo.title,
+                if todo.due.is_some() { " (due)" } else { "" },
+                if app.blockers.contains_key(&todo.id) {
+                    " (blocked)"
+                } else {
+                    ""
+                },
+            )
+            .unwrap();
+        }
+        writeln!(out, "status: {}", app.status.as_deref().unwrap_or("")).unwrap();
+        out
+    }
+
+    fn assert_script(name: &str, steps: &[(&str, Vec<Action>)]) {
+        let mut app = app();
+        let mut actual = String::new();
+        for (label, actions) in steps {
+            for action in actions {
+                assert!(!app.update(*action), "{label}: {action:?} quit");
+            }
+            write!(actual, "## {label}\n{}\n", render(&app)).unwrap();
+        }
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/app/snapshots")
+            .join(format!("{name}.snap"));
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, &actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("{}: {e} (run with UPDATE_GOLDEN=1)", path.display()));
+        assert!(
+            actual == expected,
+            "{name} no longer matches {}:\n{actual}",
+            path.display()
+        );
+    }
+
+    #[test]
+    fn adding_completing_and_canceling() {
+        assert_script(
+            "adding_completing_and_canceling",
+            &[
+                ("start adding", vec![Action::StartAdd]),
+                (
+                    "type with priority and due tokens",
+                    typed("Write report p1 tomorrow"),
+                ),
+                ("submit", vec![Action::SubmitInput]),
+                (
+                    "add a second one, fixing a typo",
+                    [
+                        vec![Action::StartAdd],
+                        typed("Call Bobb"),
+                        vec![Action::InputPop, Action::SubmitInput],
+                    ]
+                    .concat(),
+                ),
+                (
+                    "complete the second",
+                    vec![Action::SelectLast, Action::ToggleSelected],
+                ),
+                (
+                    "a canceled prompt adds nothing",
+                    [
+                        vec![Action::StartAdd],
+                        typed("never mind"),
+                        vec![Action::CancelInput],
+                    ]
+                    .concat(),
+                ),
+                (
+                    "an empty title is refused",
+                    vec![Action::StartAdd, Action::SubmitInput],
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn search_and_rename() {
+        assert_script(
+            "search_and_rename",
+            &[
+                (
+                    "two todos",
+                    [
+                        vec![Action::StartAdd],
+                        typed("Review the budget"),
+                        vec![Action::SubmitInput, Action::StartAdd],
+                        typed("Book flights"),
+                        vec![Action::SubmitInput],
+                    ]
+                    .concat(),
+                ),
+                (
+                    "search narrows the list",
+                    [vec![Action::StartSearch], typed("budg")].concat(),
+                ),
+                ("confirm keeps the filter", vec![Action::SubmitInput]),
+                (
+                    "rename the match",
+                    [
+                        vec![Action::EditTitle],
+                        (0.."budget".len()).map(|_| Action::InputPop).collect(),
+                        typed("roadmap"),
+                        vec![Action::SubmitInput],
+                    ]
+                    .concat(),
+                ),
+                (
+                    "clearing the search shows everything",
+                    vec![Action::ClearSearch],
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn scheduled_todos_wait_for_their_start_date() {
+        assert_script(
+            "scheduled_todos_wait_for_their_start_date",
+            &[
+                (
+                    "a start token hides the new todo",
+                    [
+                        vec![Action::StartAdd],
+                        typed("Plan offsite s:+3"),
+                        vec![Action::SubmitInput],
+                    ]
+                    .concat(),
+                ),
+                (
+                    "one without is listed",
+                    [
+                        vec![Action::StartAdd],
+                        typed("Book rooms"),
+                        vec![Action::SubmitInput],
+                    ]
+                    .concat(),
+                ),
+                ("s reveals it", vec![Action::ToggleShowScheduled]),
+                ("and hides it again", vec![Action::ToggleShowScheduled]),
+            ],
+        );
+    }
+
+    #[test]
+    fn blocked_todos_wait_for_their_blockers() {
+        let add = |title: &str| {
+            [
+                vec![Action::StartAdd],
+                typed(title),
+                vec![Action::SubmitInput],
+            ]
+            .concat()
+        };
+        assert_script(
+            "blocked_todos_wait_for_their_blockers",
+            &[
+                (
+                    "two todos",
+                    [add("Write draft"), add("Review draft")].concat(),
+                ),
+                (
+                    "the review waits for the draft",
+                    vec![
+                        Action::SelectFirst,
+                        Action::ToggleMark,
+                        Action::BlockSelected,
+                    ],
+                ),
+                (
+                    "the reverse link would be a cycle",
+                    vec![
+                        Action::ToggleMark,
+                        Action::SelectFirst,
+                        Action::BlockSelected,
+                    ],
+                ),
+                (
+                    "finishing the draft unblocks the review",
+                    vec![
+                        Action::ClearMarks,
+                        Action::SelectFirst,
+                        Action::ToggleSelected,
+                    ],
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn repeated_deletes_ask_before_deleting_more() {
+        let add = |title: &str| {
+            [
+                vec![Action::StartAdd],
+                typed(title),
+                vec![Action::SubmitInput],
+            ]
+            .concat()
+        };
+        assert_script(
+            "repeated_deletes_ask_before_deleting_more",
+            &[
+                (
+                    "three todos",
+                    [add("Water plants"), add("Pay rent"), add("Call mom")].concat(),
+                ),
+                ("the first press deletes", vec![Action::RequestDelete]),
+                ("a quick repeat asks instead", vec![Action::RequestDelete]),
+                ("any other key cancels", vec![Action::CancelPendingDelete]),
+                (
+                    "y deletes the one asked about",
+                    vec![Action::RequestDelete, Action::ConfirmDelete],
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn moving_todos_keeps_a_manual_order() {
+        let add = |title: &str| {
+            [
+                vec![Action::StartAdd],
+                typed(title),
+                vec![Action::SubmitInput],
+            ]
+            .concat()
+        };
+        assert_script(
+            "moving_todos_keeps_a_manual_order",
+            &[
+                (
+                    "three todos by priority",
+                    [
+                        add("Sweep porch p3"),
+                        add("Fix the leak p1"),
+                        add("Buy bulbs"),
+                        vec![Action::ToggleSort],
+                    ]
+                    .concat(),
+                ),
+                (
+                    "K starts a manual order from the one on screen",
+                    vec![Action::SelectLast, Action::MoveSelected { down: false }],
+                ),
+                ("K again", vec![Action::MoveSelected { down: false }]),
+                (
+                    "the top of the group stops it",
+                    vec![Action::MoveSelected { down: false }],
+                ),
+                (
+                    "a full turn of o comes back to the same order",
+                    vec![Action::ToggleSort; 4],
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn the_selection_follows_its_todo_through_a_sort_change() {
+        let add = |title: &str| {
+            [
+                vec![Action::StartAdd],
+                typed(title),
+                vec![Action::SubmitInput],
+            ]
+            .concat()
+        };
+        assert_script(
+            "the_selection_follows_its_todo_through_a_sort_change",
+            &[
+                (
+                    "a new todo is selected where the sort puts it",
+                    [add("Fix the leak p1 +3"), add("Sweep porch p3 tomorrow")].concat(),
+                ),
+                ("select the other one", vec![Action::SelectLast]),
+                ("o keeps it selected", vec![Action::ToggleSort]),
+                ("and again", vec![Action::ToggleSort]),
+            ],
+        );
+    }
+
+    #[test]
+    fn approving_a_pr_completes_its_review_todo_unless_opted_out() {
+        use crate::app::{PrAction, PrActionOutcome};
+        use crate::repo::github::model::ReviewEvent;
+
+        for done_on_approve in [true, false] {
+            let mut app = app();
+            app.config.github.done_on_approve = done_on_approve;
+            for action in [vec![Action::StartAdd], typed("Review acme/api#7")]
+                .concat()
+                .into_iter()
+                .chain([Action::SubmitInput])
+            {
+                app.update(action);
+            }
+            let (tx, rx) = std::sync::mpsc::channel();
+            app.pr_action_rx = Some(rx);
+            tx.send(PrActionOutcome {
+                todo_id: app.todos[0].id,
+                pr_key: "acme/api#7".into(),
+                action: PrAction::Review {
+                    event: ReviewEvent::Approve,
+                    body: String::new(),
+                },
+                result: Ok(()),
+            })
+            .unwrap();
+            assert!(app.poll_pr_action());
+
+            let expected = if done_on_approve {
+                "> [x] Medium Review acme/api#7\n\
+                 status: Approve submitted on acme/api#7; marked done\n"
+            } else {
+                "> [ ] Medium Review acme/api#7\n\
+                 status: Approve submitted on acme/api#7\n"
+            };
+            assert_eq!(render(&app), format!("mode: Normal\n{expected}"));
+        }
+    }
+}
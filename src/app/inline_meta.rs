@@ -0,0 +1,162 @@
+//! Tokens typed into a new todo's title: `p1` / `!` for the priority, due dates such as
+//! `d:fri` or `tomorrow`, start dates such as `s:+5` and estimates such as `e:30`, taken
+//! out of the title.
+
+use std::time::SystemTime;
+
+use crate::domain::clock;
+use crate::domain::due::Due;
+use crate::domain::due_parse::{self, DueSpec};
+use crate::domain::todo::Priority;
+
+/// A new todo's title with its priority (`p1`, `!`), due (`d:fri`), start (`s:+5`) and
+/// estimate (`e:30`) tokens taken out.
+pub fn parse_inline_meta(input: &str) -> Result<InlineMeta, String> {
+    let mut title_parts: Vec<&str> = Vec::new();
+    let mut priority = Priority::Medium;
+    let mut due: Option<Due> = None;
+    let mut start: Option<SystemTime> = None;
+    let mut estimate: Option<u32> = None;
+
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let mut i = 0;
+    'words: while i < words.len() {
+        let raw = words[i];
+        let lower = raw.to_lowercase();
+        i += 1;
+        if let Some(p) = parse_priority_token(&lower) {
+            priority = p;
+            continue;
+        }
+        // Phrases first, so `next monday` is not read as `next` (tomorrow) + "monday".
+        for len in [3, 2] {
+            let end = i - 1 + len;
+            if end > words.len() {
+                continue;
+            }
+            let phrase = words[i - 1..end].join(" ");
+            if let Ok(Some(s)) = parse_start_token(&phrase) {
+                start = Some(s);
+                i = end;
+                continue 'words;
+            }
+            if let Ok(Some(d)) = parse_due_token(&phrase) {
+                due = Some(d);
+                i = end;
+                continue 'words;
+            }
+        }
+        if let Some(s) = parse_start_token(&lower)? {
+            start = Some(s);
+            continue;
+        }
+        if let Some(minutes) = parse_estimate_token(&lower)? {
+            estimate = Some(minutes);
+            continue;
+        }
+        // A bare weekday may just be a word ("sun"); it needs `d:` in a title.
+        if due_parse::weekday(&lower).is_some() {
+            title_parts.push(raw);
+            continue;
+        }
+        if let Some(d) = parse_due_token(&lower)? {
+            due = Some(d);
+            continue;
+        }
+        title_parts.push(raw);
+    }
+
+    let title = title_parts.join(" ").trim().to_string();
+    if title.is_empty() {
+        return Err("Title is empty".into());
+    }
+    Ok(InlineMeta {
+        title,
+        priority,
+        due,
+        start,
+        estimate,
+    })
+}
+
+/// What `parse_inline_meta` read from an add prompt.
+#[derive(Debug)]
+pub struct InlineMeta {
+    pub title: String,
+    pub priority: Priority,
+    pub due: Option<Due>,
+    pub start: Option<SystemTime>,
+    pub estimate: Option<u32>,
+}
+
+fn parse_priority_token(token: &str) -> Option<Priority> {
+    match token {
+        "p1" | "p:1" | "!" | "high" | "h" | "hi" => Some(Priority::High),
+        "p3" | "p:3" | "!!!" | "low" | "l" => Some(Priority::Low),
+        "p2" | "p:2" | "!!" | "m" | "med" | "mid" | "medium" => Some(Priority::Medium),
+        _ => None,
+    }
+}
+
+/// `s:` / `start:` and a date as for due dates: the todo shows up from that day on.
+fn parse_start_token(token: &str) -> Result<Option<SystemTime>, String> {
+    let lower = token.to_lowercase();
+    let Some(date) = lower
+        .strip_prefix("s:")
+        .or_else(|| lower.strip_prefix("start:"))
+    else {
+        return Ok(None);
+    };
+    Ok(due_parse::parse(date, clock::today())?.map(DueSpec::start))
+}
+
+/// `e:` / `est:` and minutes (`e:30`), hours (`e:2h`) or both (`e:1h30`, `e:1h30m`).
+fn parse_estimate_token(token: &str) -> Result<Option<u32>, String> {
+    let Some(text) = token
+        .strip_prefix("e:")
+        .or_else(|| token.strip_prefix("est:"))
+    else {
+        return Ok(None);
+    };
+    let bad = || format!("Bad estimate \"{token}\" (try e:30, e:2h or e:1h30)");
+    let number = |s: &str| s.parse::<u32>().map_err(|_| bad());
+    let minutes = match text.split_once('h') {
+        Some((hours, rest)) => {
+            let rest = rest.strip_suffix('m').unwrap_or(rest);
+            let extra = if rest.is_empty() { 0 } else { number(rest)? };
+            number(hours)?
+                .checked_mul(60)
+                .and_then(|m| m.checked_add(extra))
+                .ok_or_else(bad)?
+        }
+        None => number(text.strip_suffix('m').unwrap_or(text))?,
+    };
+    if minutes == 0 {
+        return Err(bad());
+    }
+    Ok(Some(minutes))
+}
+
+/// A due date typed at `now`; see [`due_parse::parse`].
+pub(super) fn parse_due_token(token: &str) -> Result<Option<Due>, String> {
+    Ok(due_parse::parse(token, clock::today())?.map(DueSpec::due))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_tokens_read_minutes_and_hours() {
+        let meta = parse_inline_meta("Write RFC e:1h30 p1").unwrap();
+        assert_eq!(meta.title, "Write RFC");
+        assert_eq!(meta.estimate, Some(90));
+        assert_eq!(meta.priority, Priority::High);
+        assert_eq!(parse_estimate_token("e:45"), Ok(Some(45)));
+        assert_eq!(parse_estimate_token("est:2h"), Ok(Some(120)));
+        assert_eq!(parse_estimate_token("e:1h05m"), Ok(Some(65)));
+        assert_eq!(parse_estimate_token("email"), Ok(None));
+        assert!(parse_estimate_token("e:soon").is_err());
+        assert!(parse_estimate_token("e:0").is_err());
+    }
+}
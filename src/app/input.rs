@@ -0,0 +1,143 @@
+//! The text of the open prompt (add, rename, due date, tag, watch) and where the cursor
+//! is in it.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Prompt text with a cursor; edits happen at the cursor, a byte offset that always sits
+/// between grapheme clusters, so a character with combining marks (or an emoji sequence)
+/// moves and deletes as one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextInput {
+    text: String,
+    cursor: usize,
+}
+
+impl TextInput {
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Replace the text, with the cursor at its end (editing an existing title).
+    pub fn set(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.set(String::new());
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Backspace: the character before the cursor.
+    pub fn delete_back(&mut self) {
+        let end = self.cursor;
+        self.left();
+        self.text.replace_range(self.cursor..end, "");
+    }
+
+    /// Delete: the character under the cursor.
+    pub fn delete_forward(&mut self) {
+        let len = self.next_len();
+        self.text.replace_range(self.cursor..self.cursor + len, "");
+    }
+
+    /// Ctrl-w: the word before the cursor and the spaces after it, like a shell.
+    pub fn delete_word(&mut self) {
+        let before = &self.text[..self.cursor];
+        let trimmed = before.trim_end_matches([' ', '\t']);
+        let start = if trimmed.len() == before.len() && trimmed.ends_with('\n') {
+            // Right after a line break: join the lines.
+            trimmed.len() - 1
+        } else {
+            trimmed.trim_end_matches(|c: char| !c.is_whitespace()).len()
+        };
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    pub fn left(&mut self) {
+        if let Some(grapheme) = self.text[..self.cursor].graphemes(true).next_back() {
+            self.cursor -= grapheme.len();
+        }
+    }
+
+    pub fn right(&mut self) {
+        self.cursor += self.next_len();
+    }
+
+    /// Bytes of the character under the cursor (0 at the end).
+    fn next_len(&self) -> usize {
+        self.text[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map_or(0, str::len)
+    }
+
+    /// Home: the start of the cursor's line.
+    pub fn home(&mut self) {
+        self.cursor = self.text[..self.cursor].rfind('\n').map_or(0, |at| at + 1);
+    }
+
+    /// End: the end of the cursor's line.
+    pub fn end(&mut self) {
+        self.cursor += self.text[self.cursor..]
+            .find('\n')
+            .unwrap_or(self.text.len() - self.cursor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_happen_at_the_cursor() {
+        let mut input = TextInput::default();
+        input.set("buy milk d:fri");
+        input.home();
+        "ü ".chars().for_each(|c| input.insert(c));
+        assert_eq!(input.as_str(), "ü buy milk d:fri");
+        input.end();
+        input.delete_word();
+        assert_eq!(input.as_str(), "ü buy milk ");
+        input.delete_word();
+        assert_eq!(input.as_str(), "ü buy ");
+        input.left();
+        input.left();
+        input.delete_back();
+        input.right();
+        input.delete_forward();
+        assert_eq!(input.as_str(), "ü by");
+        input.home();
+        input.delete_forward();
+        input.right();
+        input.delete_back();
+        assert_eq!((input.as_str(), input.cursor()), ("by", 0));
+
+        // "が" typed as か plus a combining mark is one character to the cursor.
+        input.set("か\u{3099}き");
+        input.left();
+        input.left();
+        assert_eq!(input.cursor(), 0);
+        input.delete_forward();
+        assert_eq!(input.as_str(), "き");
+        input.end();
+        input.delete_back();
+        assert_eq!(input.as_str(), "");
+
+        // Ctrl-w takes the last word of a notes line, then the line break.
+        input.set("title\nnote");
+        input.delete_word();
+        assert_eq!(input.as_str(), "title\n");
+        input.delete_word();
+        assert_eq!(input.as_str(), "title");
+    }
+}
@@ -0,0 +1,272 @@
+//! Work `App` hands to the background worker (provider syncs, PR writes, the inbox), the
+//! settings it runs with and the messages it answers with.
+
+use std::sync::{Arc, Mutex};
+
+use crate::domain::clock;
+use crate::domain::todo::TodoId;
+use crate::repo::TodoRepository;
+use crate::repo::github::QueryError;
+use crate::repo::github::model::{
+    AttentionPrs, FetchOptions, RepoFilter, RetryPolicy, ReviewEvent, SearchSpec, SyncProgress,
+};
+use crate::repo::github::notifications::Notification;
+use crate::repo::jira::JiraIssues;
+use crate::usecase::caldav::{self, CaldavOutcome};
+use crate::usecase::sync;
+
+/// Write operation on a PR, run on the background runtime like a sync.
+#[derive(Debug, Clone)]
+pub enum PrAction {
+    Review { event: ReviewEvent, body: String },
+    UpdateBranch,
+}
+
+#[derive(Debug)]
+pub struct PrActionOutcome {
+    pub todo_id: TodoId,
+    pub pr_key: String,
+    pub action: PrAction,
+    pub result: Result<(), String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GithubConfig {
+    pub token: String,
+    pub api_base: Option<String>,
+    pub days: u64,
+    pub include_team_requests: bool,
+    pub page_size: i32,
+    pub retry: RetryPolicy,
+    /// `[[github.searches]]`; only the main account runs them.
+    pub searches: Vec<SearchSpec>,
+    /// `include_repos` / `exclude_repos`, applied to every account.
+    pub repos: RepoFilter,
+    /// Host prefixed to PR keys of an additional account; `None` for the main one.
+    pub key_host: Option<String>,
+    /// Last successful sync of this host (unix seconds); set for an incremental sync.
+    pub since: Option<i64>,
+    /// Watched PRs on this account's host, keyed without the host; set by `plan_sync`.
+    pub watched: Vec<String>,
+}
+
+impl GithubConfig {
+    pub fn fetch_options(&self) -> FetchOptions {
+        let window = crate::now_unix().saturating_sub((self.days as i64) * 86_400);
+        let since = self
+            .since
+            .map(|at| at.saturating_sub(sync::WATERMARK_OVERLAP_SECS));
+        FetchOptions {
+            cutoff_ts: since.map_or(window, |since| since.max(window)),
+            utc_offset_secs: clock::local_offset().whole_seconds(),
+            include_team_requests: self.include_team_requests,
+            page_size: self.page_size.clamp(1, 100),
+            retry: self.retry,
+        }
+    }
+
+    /// Host this account syncs with, e.g. `github.com`; keys its sync watermark.
+    pub fn host(&self) -> String {
+        let api = self
+            .api_base
+            .as_deref()
+            .unwrap_or(crate::repo::github::endpoint::GITHUB_COM_API);
+        crate::repo::github::endpoint::gh_hostname(api)
+    }
+
+    /// Whether PRs keyed `pr_key` belong to this account rather than another configured one.
+    pub(super) fn owns(&self, pr_key: &str, all: &[GithubConfig]) -> bool {
+        let claims = |cfg: &GithubConfig| {
+            cfg.key_host
+                .as_ref()
+                .is_some_and(|host| pr_key.starts_with(&format!("{host}/")))
+        };
+        match self.key_host {
+            Some(_) => claims(self),
+            None => !all.iter().any(claims),
+        }
+    }
+
+    async fn fetch_attention(
+        &self,
+        progress: &(dyn Fn(SyncProgress) + Sync),
+    ) -> anyhow::Result<AttentionPrs> {
+        let octo = crate::repo::github::graphql_client(self.token.clone(), self.api_base.clone())?;
+        let mut attention = crate::repo::github::fetch_attention_prs(
+            &octo,
+            &self.fetch_options(),
+            &self.searches,
+            &self.repos,
+            &self.watched,
+            progress,
+        )
+        .await?;
+        if let Some(host) = &self.key_host {
+            attention.qualify(host);
+        }
+        Ok(attention)
+    }
+}
+
+/// Prepare `accounts` for a sync: incremental from each host's watermark unless `full`
+/// is asked for, the periodic full sync is due (`sync::full_sync_due`) or some host has
+/// never been synced (then everyone does a full sync), and hand each account the watched
+/// PRs on its host.
+pub fn plan_sync(
+    accounts: &mut [GithubConfig],
+    repo: &dyn TodoRepository,
+    full: bool,
+) -> anyhow::Result<()> {
+    let watermarks = accounts
+        .iter()
+        .map(|cfg| sync::watermark(repo, &cfg.host()))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let incremental = !full
+        && !sync::full_sync_due(repo, crate::now_unix())?
+        && watermarks.iter().all(Option::is_some);
+    let watched = sync::watched_prs(repo)?;
+    let assigned: Vec<Vec<String>> = accounts
+        .iter()
+        .map(|cfg| {
+            watched
+                .iter()
+                .filter(|key| cfg.owns(key, accounts))
+                .map(|key| match &cfg.key_host {
+                    Some(host) => key
+                        .strip_prefix(&format!("{host}/"))
+                        .unwrap_or(key)
+                        .to_string(),
+                    None => key.clone(),
+                })
+                .collect()
+        })
+        .collect();
+    for ((cfg, since), watched) in accounts.iter_mut().zip(watermarks).zip(assigned) {
+        cfg.since = since.filter(|_| incremental);
+        cfg.watched = watched;
+    }
+    Ok(())
+}
+
+/// Sync every account in parallel (one task each) and merge the results; fails if any
+/// account fails, so a flaky host never wipes its PRs from the cache. `progress` gets the
+/// totals over all accounts after every page.
+pub async fn fetch_attention_all(
+    accounts: Vec<GithubConfig>,
+    progress: Arc<dyn Fn(SyncProgress) + Send + Sync>,
+) -> anyhow::Result<AttentionPrs> {
+    let per_account = Arc::new(Mutex::new(vec![SyncProgress::default(); accounts.len()]));
+    let handles: Vec<_> = accounts
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(|(idx, cfg)| {
+            let per_account = Arc::clone(&per_account);
+            let progress = Arc::clone(&progress);
+            tokio::spawn(async move {
+                let report = move |p: SyncProgress| {
+                    let mut all = per_account.lock().unwrap_or_else(|e| e.into_inner());
+                    all[idx] = p;
+                    progress(all.iter().fold(SyncProgress::default(), |a, b| a.plus(*b)));
+                };
+                cfg.fetch_attention(&report).await
+            })
+        })
+        .collect();
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        results.push(
+            handle
+                .await
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("sync task panicked"))),
+        );
+    }
+    let mut merged = AttentionPrs {
+        viewer_login: String::new(),
+        prs: Vec::new(),
+        stats: Vec::new(),
+        incremental: accounts.iter().all(|cfg| cfg.since.is_some()),
+        failed_queries: Vec::new(),
+        searches: Vec::new(),
+        finished_watches: Vec::new(),
+    };
+    for (cfg, result) in accounts.iter().zip(results) {
+        let host = cfg.key_host.as_deref().unwrap_or("github");
+        merged.merge(result.map_err(|e| {
+            let e = QueryError::from_anyhow(e);
+            QueryError {
+                kind: e.kind,
+                message: format!("{host}: {}", e.message),
+            }
+        })?);
+    }
+    Ok(merged)
+}
+
+/// A provider `g` and the `S` menu can sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyncSource {
+    Github,
+    /// Issues matching `[jira] jql`; also synced on its own with `I`.
+    Jira,
+    /// The `[caldav]` task list, both ways.
+    Caldav,
+}
+
+impl SyncSource {
+    pub const ALL: [SyncSource; 3] = [SyncSource::Github, SyncSource::Jira, SyncSource::Caldav];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SyncSource::Github => "GitHub",
+            SyncSource::Jira => "Jira",
+            SyncSource::Caldav => "CalDAV",
+        }
+    }
+
+    pub(super) fn enabled_meta_key(self) -> String {
+        let id = match self {
+            SyncSource::Github => "github",
+            SyncSource::Jira => "jira",
+            SyncSource::Caldav => "caldav",
+        };
+        format!("sync.enabled:{id}")
+    }
+
+    pub(super) fn synced_at_meta_key(self) -> &'static str {
+        match self {
+            SyncSource::Github => sync::META_PRS_SYNCED_AT,
+            SyncSource::Jira => sync::META_JIRA_SYNCED_AT,
+            SyncSource::Caldav => caldav::META_CALDAV_SYNCED_AT,
+        }
+    }
+}
+
+/// Message from a sync worker: progress while it runs, then the outcome.
+#[derive(Debug)]
+pub enum SyncEvent {
+    Progress(SyncProgress),
+    Done(SyncOutcome),
+    /// The Jira worker's only message.
+    Jira(Result<JiraIssues, String>),
+    /// The CalDAV worker's only message: the server's side is done, the local one is left.
+    Caldav(Result<CaldavOutcome, String>),
+}
+
+/// Reply of an inbox job.
+#[derive(Debug)]
+pub enum InboxEvent {
+    Fetched(Result<Vec<Notification>, String>),
+    MarkedRead {
+        thread_id: String,
+        result: Result<(), String>,
+    },
+}
+
+#[derive(Debug)]
+pub struct SyncOutcome {
+    /// The error keeps its kind, so the footer can say what went wrong and what to do.
+    pub result: Result<AttentionPrs, QueryError>,
+    /// Becomes the hosts' watermark once the result is stored.
+    pub started_at: i64,
+}
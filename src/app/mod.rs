@@ -0,0 +1,3437 @@
+mod action;
+mod inline_meta;
+mod input;
+mod jobs;
+
+pub use action::Action;
+use inline_meta::parse_due_token;
+pub use inline_meta::{InlineMeta, parse_inline_meta};
+pub use input::TextInput;
+pub use jobs::{
+    GithubConfig, InboxEvent, PrAction, PrActionOutcome, SyncEvent, SyncOutcome, SyncSource,
+    fetch_attention_all, plan_sync,
+};
+
+use crate::clipboard;
+use crate::config::{Config, DeleteGuard};
+use crate::daemon::{self, DaemonClient};
+use crate::domain::clock;
+use crate::domain::dependency;
+use crate::domain::due::{Due, DueBucket};
+use crate::domain::fuzzy;
+use crate::domain::query::TodoQuery;
+use crate::domain::subtask::{self, Hierarchy};
+use crate::domain::time_entry::{self, TimeEntry};
+use crate::domain::todo::{GITHUB_NOTIFICATION_KEY_PREFIX, Priority, Status, Todo, TodoId};
+use crate::hooks::{self, HookEvent, SyncCompleted};
+use crate::repo::archive::{Archive, ArchiveReason};
+use crate::repo::caldav::CaldavConfig;
+use crate::repo::github::model::{CiCheck, Pr, ReviewEvent, SyncProgress};
+use crate::repo::github::notifications::Notification;
+use crate::repo::jira::{JiraConfig, JiraIssues};
+use crate::repo::session::{Draft, SessionFile, SessionSnapshot};
+use crate::repo::{StoreBusy, TodoRepository};
+use crate::usecase::caldav::{self, CaldavOutcome};
+use crate::usecase::{attention, sync};
+use crate::worker::{Notify, SyncJob, Worker};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration as StdDuration, Instant, SystemTime};
+
+use time::{Date, Duration};
+
+/// How long a newly added todo stays highlighted.
+const FLASH_DURATION: StdDuration = StdDuration::from_millis(1500);
+
+/// How long `g` waits for a second `g` (jump to top) before it syncs.
+const G_PREFIX_TIMEOUT: StdDuration = StdDuration::from_millis(500);
+
+/// How often unsent input is written to the session snapshot (when it changed).
+const SNAPSHOT_INTERVAL: StdDuration = StdDuration::from_secs(2);
+
+/// How many status messages `L` can recall.
+const STATUS_LOG_CAPACITY: usize = 50;
+
+/// How often the store is checked for writes from other processes.
+const EXTERNAL_CHANGE_INTERVAL: StdDuration = StdDuration::from_secs(1);
+
+/// How often due dates are checked for the `on_overdue` hook.
+const OVERDUE_CHECK_INTERVAL: StdDuration = StdDuration::from_secs(30);
+
+/// Unix time up to which `on_overdue` has fired, so due dates that pass while koto is
+/// closed are reported on the next start (once).
+const META_OVERDUE_CHECKED_AT: &str = "hooks.overdue_checked_at";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputMode {
+    Normal,
+    Editing,
+    EditingDue,
+    /// Renaming a todo (`e`), or writing a merged title for a conflict.
+    EditingTitle,
+    /// `#`: typing a tag to add to every marked todo.
+    Tagging,
+    /// `W`: typing a PR to pin into every sync.
+    Watching,
+    Search,
+}
+
+/// A failure as the error popup shows it.
+#[derive(Debug, Clone)]
+pub struct ErrorReport {
+    pub at: SystemTime,
+    /// What the footer showed.
+    pub message: String,
+    /// Everything known about it, possibly many lines.
+    pub details: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    List,
+    Agenda,
+    /// Sections per repository / source (`Todo::source_group`), foldable with `z`.
+    Repos,
+    /// GitHub notifications of the main account (`Inbox`), not todos.
+    Inbox,
+    /// Columns side by side (`board_layout`); j / k move within the selected todo's column.
+    Board,
+}
+
+/// How the board splits the todos into columns; `Tab` switches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoardLayout {
+    /// Backlog / Doing / Done, from `Todo::state` and `done`.
+    #[default]
+    State,
+    /// High / Medium / Low.
+    Priority,
+}
+
+/// Month calendar pane; `focused` routes arrow keys to the calendar instead of the table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CalendarState {
+    pub cursor: Date,
+    pub focused: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HelpMode {
+    None,
+    Quick,
+    Full,
+}
+
+pub struct App {
+    /// Background runtime for syncs and PR actions.
+    worker: Worker,
+    repo: Box<dyn TodoRepository>,
+    pub config: Config,
+    pub todos: Vec<Todo>,
+    pub time_entries: Vec<TimeEntry>,
+    pub selected: usize,
+    pub mode: InputMode,
+    pub view: View,
+    pub board_layout: BoardLayout,
+    /// Text of the open prompt (not search, which keeps its own).
+    pub input: TextInput,
+    pub status: Option<String>,
+    /// `status` reports a failure.
+    pub status_is_error: bool,
+    /// The most recent failure, in full; `E` shows it.
+    pub last_error: Option<ErrorReport>,
+    /// `Some(scroll)` while the error popup is open.
+    pub error_view: Option<u16>,
+    /// Most recent status messages, newest last; `L` shows them.
+    pub status_log: VecDeque<(SystemTime, String)>,
+    /// `Some(scroll)` while the status log is open.
+    pub status_log_view: Option<u16>,
+    pub help_mode: HelpMode,
+    pub help_scroll: u16,
+    pub help_searching: bool,
+    pub help_search_query: String,
+    pub help_search_match: usize,
+    pub show_detail: bool,
+    pub ci_checks: Option<CiChecksView>,
+    pub link_picker: Option<LinkPicker>,
+    pub jump: Option<JumpFinder>,
+    pub review: Option<ReviewDraft>,
+    pub conflict: Option<TitleConflict>,
+    /// Todo `EditingTitle` renames; `true` when the new title settles a conflict.
+    title_target: Option<(TodoId, bool)>,
+    pr_action_rx: Option<Receiver<PrActionOutcome>>,
+    /// Active title search; while set, `todos` holds the matches instead of the full list.
+    pub search: Option<TodoQuery>,
+    /// Local calendar date the due labels were last computed for.
+    pub today: Date,
+    pub calendar: Option<CalendarState>,
+    /// When set, only todos due on this local date are listed.
+    pub day_filter: Option<Date>,
+    pub request_filter: RequestFilter,
+    /// `B`: leave out todos of bot-authored PRs.
+    pub hide_bots: bool,
+    /// Todos `hide_bots` left out on the last reload.
+    pub hidden_bot_todos: usize,
+    /// `s`: also list todos whose start date is still ahead.
+    pub show_scheduled: bool,
+    /// Scheduled todos left out on the last reload.
+    pub hidden_scheduled: usize,
+    pub sort: SortMode,
+    /// Repository-view groups folded away (`z`), by `Todo::source_group`.
+    pub collapsed_groups: HashSet<String>,
+    /// Open todos per local due date, computed before `day_filter` narrows the list.
+    pub due_counts: HashMap<Date, usize>,
+    /// Estimated minutes of the open todos due today or overdue.
+    pub planned_today: u32,
+    /// Parent links of all open todos, for inherited due dates and indentation.
+    pub hierarchy: Hierarchy,
+    /// Open todos each blocked todo still waits for (`b`).
+    pub blockers: HashMap<TodoId, Vec<TodoId>>,
+    /// Todo to select after the next reload, wherever sorting puts it.
+    focus: Option<TodoId>,
+    /// Newly added todo, highlighted for `FLASH_DURATION`.
+    flash: Option<(TodoId, Instant)>,
+    /// `A`: the task the todo being typed will become a subtask of.
+    pub adding_under: Option<TodoId>,
+    /// Delete waiting for `d` (double mode) or `y` (window mode); see `DeleteGuard`.
+    pub pending_delete: Option<TodoId>,
+    last_delete_at: Option<Instant>,
+    /// Where deleted todos are kept for `koto archive grep`; `None` for the in-memory store.
+    pub archive: Option<Archive>,
+    /// `koto daemon` serving this store; GitHub syncs are left to it while attached.
+    pub daemon: Option<DaemonClient>,
+    /// The daemon announced a sync that has not finished yet.
+    daemon_syncing: bool,
+    /// Main account first, then `[[github.accounts]]`; empty when no token was found.
+    pub github: Vec<GithubConfig>,
+    /// Account the GitHub token belongs to, as reported by the last successful sync.
+    pub github_login: Option<String>,
+    /// `[jira]` site and token; `None` when Jira sync is off.
+    pub jira: Option<JiraConfig>,
+    /// `[caldav]` list and password; `None` when CalDAV sync is off.
+    pub caldav: Option<CaldavConfig>,
+    /// PR snapshot from the last sync, keyed by `owner/repo#number`.
+    pub prs: HashMap<String, Pr>,
+    /// One background worker per source being synced.
+    sync_workers: HashMap<SyncSource, Receiver<SyncEvent>>,
+    /// Latest progress of each running worker, for the header.
+    pub sync_progress: HashMap<SyncSource, SyncProgress>,
+    /// `S` menu: selected row while open (0 = all enabled sources).
+    pub sync_menu: Option<usize>,
+    /// Last key press, for `[privacy] lock_after_minutes`.
+    last_input: Instant,
+    /// The list is hidden behind the lock screen until the unlock phrase is typed.
+    pub locked: bool,
+    /// Typed so far on the lock screen.
+    pub unlock_input: String,
+    /// First visible table row; `draw` writes back where the table actually scrolled to.
+    pub table_offset: usize,
+    /// Todo rows the table had room for on the last draw: the step of page moves.
+    pub page_rows: usize,
+    /// `g` pressed, waiting for `G_PREFIX_TIMEOUT` to see whether `gg` follows.
+    pending_g: Option<Instant>,
+    /// `p` pressed: the next `1` / `2` / `3` sets the priority, any other key cancels.
+    pub pending_priority: bool,
+    /// Todos marked with `v`; while any are, P, [ / ], d, c and # act on all of them.
+    pub marked: HashSet<TodoId>,
+    /// `d` with marks waits for `y`.
+    pending_batch_delete: bool,
+    /// Crash-recovery snapshot of unsent input; `None` for the in-memory store.
+    session: Option<SessionFile>,
+    /// Draft as last written to `session`, and when.
+    saved_draft: Option<(Option<Draft>, Instant)>,
+    /// Snapshot left by a session that did not exit cleanly, waiting for y / n.
+    pub restore_offer: Option<SessionSnapshot>,
+    /// Last `on_overdue` check.
+    overdue_checked: Option<Instant>,
+    /// The store's data version at the last check for outside writes, and when.
+    data_version: Option<(Option<i64>, Instant)>,
+    /// Notifications inbox (`4`); `None` until first fetched.
+    pub inbox: Option<Vec<Notification>>,
+    pub inbox_selected: usize,
+    /// A fetch of the inbox is running.
+    pub inbox_loading: bool,
+    inbox_tx: Sender<InboxEvent>,
+    inbox_rx: Receiver<InboxEvent>,
+}
+
+/// `f`: which review-request PRs the list shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RequestFilter {
+    #[default]
+    All,
+    /// Hide PRs requested only from one of my teams.
+    Direct,
+    /// Only PRs requested from one of my teams.
+    Team,
+}
+
+impl RequestFilter {
+    pub fn next(self) -> Self {
+        match self {
+            RequestFilter::All => RequestFilter::Direct,
+            RequestFilter::Direct => RequestFilter::Team,
+            RequestFilter::Team => RequestFilter::All,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RequestFilter::All => "all",
+            RequestFilter::Direct => "direct requests",
+            RequestFilter::Team => "team requests",
+        }
+    }
+}
+
+/// `o`: how the list is ordered; remembered in the `sort_mode` meta key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortMode {
+    /// Earliest due date first (inherited dates count), then priority.
+    #[default]
+    Due,
+    /// Positions set with `J` / `K`.
+    Manual,
+    /// Highest PR attention score first (`attention::score`), then as `Due`.
+    Attention,
+    /// High priority first, then as `Due`.
+    Priority,
+}
+
+impl SortMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Due => "due",
+            SortMode::Manual => "manual",
+            SortMode::Attention => "attention",
+            SortMode::Priority => "priority",
+        }
+    }
+
+    fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "due" => Some(SortMode::Due),
+            "manual" => Some(SortMode::Manual),
+            "attention" => Some(SortMode::Attention),
+            "priority" => Some(SortMode::Priority),
+            _ => None,
+        }
+    }
+}
+
+/// Meta key holding the last `SortMode`.
+const META_SORT_MODE: &str = "sort_mode";
+/// Meta key holding the folded repository-view groups, one per line.
+const META_COLLAPSED_GROUPS: &str = "collapsed_groups";
+
+/// `C` modal listing the CI checks of the selected todo's PR.
+#[derive(Debug, Clone)]
+pub struct CiChecksView {
+    pub pr_key: String,
+    pub selected: usize,
+}
+
+/// Enter on a todo with more than one link: choose which to open.
+#[derive(Debug, Clone)]
+pub struct LinkPicker {
+    /// (label, url), the todo's own link first.
+    pub links: Vec<(String, String)>,
+    pub selected: usize,
+}
+
+/// `Ctrl-p` overlay: type part of a title to move the selection to that todo.
+#[derive(Debug, Clone, Default)]
+pub struct JumpFinder {
+    pub query: String,
+    /// Row in `App::jump_matches`.
+    pub selected: usize,
+}
+
+/// `R` modal: review being written for the selected todo's PR.
+#[derive(Debug, Clone)]
+pub struct ReviewDraft {
+    pub todo_id: TodoId,
+    pub pr_key: String,
+    pub event: ReviewEvent,
+    pub body: String,
+}
+
+/// Chooser for a todo renamed both locally and by its provider since the last sync.
+#[derive(Debug, Clone)]
+pub struct TitleConflict {
+    pub todo_id: TodoId,
+    pub local: String,
+    pub remote: String,
+    pub choice: ConflictChoice,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictChoice {
+    KeepLocal,
+    TakeRemote,
+    /// Edit a title starting from the local one.
+    Merge,
+}
+
+impl ConflictChoice {
+    pub const ALL: [ConflictChoice; 3] = [
+        ConflictChoice::KeepLocal,
+        ConflictChoice::TakeRemote,
+        ConflictChoice::Merge,
+    ];
+
+    pub fn next(self) -> Self {
+        match self {
+            ConflictChoice::KeepLocal => ConflictChoice::TakeRemote,
+            ConflictChoice::TakeRemote => ConflictChoice::Merge,
+            ConflictChoice::Merge => ConflictChoice::KeepLocal,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ConflictChoice::KeepLocal => "Keep local",
+            ConflictChoice::TakeRemote => "Take remote",
+            ConflictChoice::Merge => "Merge",
+        }
+    }
+}
+
+impl App {
+    pub fn new(
+        repo: Box<dyn TodoRepository>,
+        config: Config,
+        github: Vec<GithubConfig>,
+        worker: Worker,
+    ) -> Self {
+        let (inbox_tx, inbox_rx) = mpsc::channel();
+        let mut app = Self {
+            repo,
+            worker,
+            config,
+            todos: Vec::new(),
+            time_entries: Vec::new(),
+            selected: 0,
+            mode: InputMode::Normal,
+            view: View::List,
+            board_layout: BoardLayout::default(),
+            input: TextInput::default(),
+            status: None,
+            status_is_error: false,
+            last_error: None,
+            error_view: None,
+            status_log: VecDeque::with_capacity(STATUS_LOG_CAPACITY),
+            status_log_view: None,
+            help_mode: HelpMode::None,
+            help_scroll: 0,
+            help_searching: false,
+            help_search_query: String::new(),
+            help_search_match: 0,
+            show_detail: false,
+            ci_checks: None,
+            link_picker: None,
+            jump: None,
+            review: None,
+            conflict: None,
+            title_target: None,
+            pr_action_rx: None,
+            search: None,
+            today: clock::today(),
+            calendar: None,
+            day_filter: None,
+            request_filter: RequestFilter::All,
+            hide_bots: false,
+            show_scheduled: false,
+            hidden_scheduled: 0,
+            hidden_bot_todos: 0,
+            sort: SortMode::Due,
+            due_counts: HashMap::new(),
+            planned_today: 0,
+            blockers: HashMap::new(),
+            hierarchy: Hierarchy::default(),
+            adding_under: None,
+            focus: None,
+            flash: None,
+            pending_delete: None,
+            last_delete_at: None,
+            archive: None,
+            daemon: None,
+            daemon_syncing: false,
+            github,
+            github_login: None,
+            jira: None,
+            caldav: None,
+            prs: HashMap::new(),
+            sync_workers: HashMap::new(),
+            sync_progress: HashMap::new(),
+            sync_menu: None,
+            last_input: Instant::now(),
+            locked: false,
+            unlock_input: String::new(),
+            table_offset: 0,
+            page_rows: 0,
+            pending_g: None,
+            pending_priority: false,
+            marked: HashSet::new(),
+            pending_batch_delete: false,
+            session: None,
+            saved_draft: None,
+            restore_offer: None,
+            overdue_checked: None,
+            data_version: None,
+            inbox: None,
+            inbox_selected: 0,
+            inbox_loading: false,
+            inbox_tx,
+            inbox_rx,
+            collapsed_groups: HashSet::new(),
+        };
+        app.github_login = app
+            .store(|repo| repo.meta(sync::META_GITHUB_LOGIN))
+            .flatten();
+        app.sort = app
+            .store(|repo| repo.meta(META_SORT_MODE))
+            .flatten()
+            .and_then(|label| SortMode::from_label(&label))
+            .unwrap_or_default();
+        app.collapsed_groups = app
+            .store(|repo| repo.meta(META_COLLAPSED_GROUPS))
+            .flatten()
+            .map(|groups| groups.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+        app.hide_bots = app.config.github.hide_bots;
+        app.load_cached_prs();
+        app.reload();
+        app
+    }
+
+    pub fn toggle_help_quick(&mut self) {
+        self.help_mode = match self.help_mode {
+            HelpMode::Quick => HelpMode::None,
+            _ => HelpMode::Quick,
+        };
+        if self.help_mode != HelpMode::None {
+            self.help_scroll = 0;
+            self.help_searching = false;
+            self.help_search_query.clear();
+            self.help_search_match = 0;
+            self.status = None;
+        }
+    }
+
+    pub fn toggle_help_full(&mut self) {
+        self.help_mode = match self.help_mode {
+            HelpMode::Full => HelpMode::None,
+            _ => HelpMode::Full,
+        };
+        if self.help_mode != HelpMode::None {
+            self.help_scroll = 0;
+            self.help_searching = false;
+            self.help_search_query.clear();
+            self.help_search_match = 0;
+            self.status = None;
+        }
+    }
+
+    pub fn close_help(&mut self) {
+        self.help_mode = HelpMode::None;
+        self.help_scroll = 0;
+        self.help_searching = false;
+        self.help_search_query.clear();
+        self.help_search_match = 0;
+    }
+
+    pub fn toggle_detail(&mut self) {
+        self.show_detail = !self.show_detail;
+    }
+
+    /// `C`: list the CI checks of the selected todo's PR.
+    pub fn open_ci_checks(&mut self) {
+        let Some(pr) = self.todos.get(self.selected).and_then(|t| self.pr_for(t)) else {
+            self.set_status("No synced PR for this todo");
+            return;
+        };
+        if pr.ci_checks.is_empty() {
+            self.set_status("No CI checks reported for this PR");
+            return;
+        }
+        self.ci_checks = Some(CiChecksView {
+            pr_key: pr.pr_key.clone(),
+            selected: 0,
+        });
+    }
+
+    pub fn close_ci_checks(&mut self) {
+        self.ci_checks = None;
+    }
+
+    pub fn move_ci_check(&mut self, delta: isize) {
+        let len = self.current_ci_checks().len();
+        if let Some(view) = self.ci_checks.as_mut()
+            && len > 0
+        {
+            view.selected = (view.selected as isize + delta).clamp(0, len as isize - 1) as usize;
+        }
+    }
+
+    /// Checks shown in the `C` modal, in the order GitHub reported them.
+    pub fn current_ci_checks(&self) -> &[CiCheck] {
+        self.ci_checks
+            .as_ref()
+            .and_then(|view| self.prs.get(&view.pr_key))
+            .map(|pr| pr.ci_checks.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn open_selected_ci_check(&mut self) {
+        let Some(view) = &self.ci_checks else { return };
+        let url = self
+            .current_ci_checks()
+            .get(view.selected)
+            .and_then(|c| c.url.clone());
+        match url {
+            Some(url) => self.open_url(&url),
+            None => self.set_status("This check has no details URL"),
+        }
+    }
+
+    /// `R`: start writing a review for the selected todo's PR.
+    pub fn open_review(&mut self) {
+        if self.github.is_empty() {
+            self.set_status("GitHub sync not configured");
+            return;
+        }
+        if self.pr_action_rx.is_some() {
+            self.set_status("Another PR action is still running");
+            return;
+        }
+        let Some(todo) = self.todos.get(self.selected) else {
+            return;
+        };
+        let Some(pr) = self.pr_for(todo) else {
+            self.set_status("No synced PR for this todo");
+            return;
+        };
+        self.review = Some(ReviewDraft {
+            todo_id: todo.id,
+            pr_key: pr.pr_key.clone(),
+            event: ReviewEvent::Approve,
+            body: String::new(),
+        });
+    }
+
+    pub fn cancel_review(&mut self) {
+        self.review = None;
+    }
+
+    pub fn submit_review(&mut self) {
+        let Some(draft) = self.review.clone() else {
+            return;
+        };
+        if draft.event != ReviewEvent::Approve && draft.body.trim().is_empty() {
+            self.set_status(&format!("{} needs a message", draft.event.label()));
+            return;
+        }
+        self.review = None;
+        self.spawn_pr_action(
+            draft.todo_id,
+            &draft.pr_key,
+            PrAction::Review {
+                event: draft.event,
+                body: draft.body,
+            },
+        );
+    }
+
+    /// `U` in the detail view: merge the base branch into the selected PR's branch.
+    pub fn update_branch_selected(&mut self) {
+        let Some(todo) = self.todos.get(self.selected) else {
+            return;
+        };
+        let Some(pr) = self.pr_for(todo) else {
+            self.set_status("No synced PR for this todo");
+            return;
+        };
+        if !pr.merge_blockers.as_ref().is_some_and(|b| b.is_behind_base) {
+            self.set_status("Branch is up to date with its base (as of the last sync)");
+            return;
+        }
+        let (todo_id, pr_key) = (todo.id, pr.pr_key.clone());
+        self.spawn_pr_action(todo_id, &pr_key, PrAction::UpdateBranch);
+    }
+
+    fn spawn_pr_action(&mut self, todo_id: TodoId, pr_key: &str, action: PrAction) {
+        let Some(cfg) = self
+            .github
+            .iter()
+            .find(|cfg| cfg.owns(pr_key, &self.github))
+            .cloned()
+        else {
+            self.set_status("No configured GitHub account for this PR");
+            return;
+        };
+        let Some(pr) = self.prs.get(pr_key).cloned() else {
+            return;
+        };
+        if self.pr_action_rx.is_some() {
+            self.set_status("Another PR action is still running");
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        self.pr_action_rx = Some(rx);
+        let verb = match &action {
+            PrAction::Review { .. } => "Submitting review on",
+            PrAction::UpdateBranch => "Updating branch of",
+        };
+        self.set_status(&format!("{verb} {pr_key}..."));
+        self.worker.submit(SyncJob::PrAction {
+            cfg,
+            pr: Box::new(pr),
+            todo_id,
+            action,
+            reply: tx,
+        });
+    }
+
+    /// Apply a finished PR action, if any. Returns true when state changed.
+    pub fn poll_pr_action(&mut self) -> bool {
+        let Some(rx) = &self.pr_action_rx else {
+            return false;
+        };
+        let outcome = match rx.try_recv() {
+            Ok(outcome) => outcome,
+            Err(mpsc::TryRecvError::Empty) => return false,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.pr_action_rx = None;
+                self.set_status("PR action channel closed");
+                return true;
+            }
+        };
+        self.pr_action_rx = None;
+        let PrActionOutcome {
+            todo_id,
+            pr_key,
+            action,
+            result,
+        } = outcome;
+        match (action, result) {
+            (PrAction::Review { event, .. }, Ok(())) => {
+                // An approval settles the review request, so the todo is done.
+                let done_now = (event == ReviewEvent::Approve
+                    && self.config.github.done_on_approve
+                    && self.todos.iter().any(|t| t.id == todo_id && !t.is_closed()))
+                .then(|| self.store(|repo| repo.toggle(todo_id)))
+                .flatten()
+                .flatten();
+                let settings = self.config.github.clone();
+                let follow_up = event == ReviewEvent::Approve
+                    && self.prs.get(&pr_key).cloned().is_some_and(|pr| {
+                        self.store(|repo| sync::add_follow_up(repo, &settings, &pr))
+                            .unwrap_or(false)
+                    });
+                self.reload();
+                let suffix = match (done_now.is_some(), follow_up) {
+                    (true, true) => "; marked done, follow-up added",
+                    (true, false) => "; marked done",
+                    (false, true) => "; follow-up added",
+                    (false, false) => "",
+                };
+                self.set_status(&format!("{} submitted on {pr_key}{suffix}", event.label()));
+                if let Some(todo) = done_now {
+                    self.fire_hook(HookEvent::Done, &todo);
+                }
+            }
+            (PrAction::UpdateBranch, Ok(())) => {
+                // GitHub merges asynchronously; the badge clears on the next sync.
+                if let Some(blockers) = self
+                    .prs
+                    .get_mut(&pr_key)
+                    .and_then(|pr| pr.merge_blockers.as_mut())
+                {
+                    blockers.is_behind_base = false;
+                }
+                self.set_status(&format!("Branch update requested for {pr_key}"));
+            }
+            (PrAction::Review { .. }, Err(e)) => self.set_error(&format!("Review failed: {e}")),
+            (PrAction::UpdateBranch, Err(e)) => {
+                self.set_error(&format!("Update branch failed: {e}"))
+            }
+        }
+        true
+    }
+
+    /// Record a key press, for the idle lock.
+    pub fn register_input(&mut self) {
+        self.last_input = Instant::now();
+    }
+
+    /// Enter on the lock screen: unlock if the typed text is `[privacy] unlock_phrase`,
+    /// otherwise start over.
+    fn confirm_unlock(&mut self) {
+        let typed = std::mem::take(&mut self.unlock_input);
+        if typed == self.config.privacy.unlock_phrase() {
+            self.locked = false;
+        }
+    }
+
+    /// Called on every tick; locks after idle time, and re-sorts and refreshes due labels
+    /// once the local day rolls over. Returns true when something visible changed.
+    pub fn on_tick(&mut self) -> bool {
+        let mut changed = false;
+        let lock_after = self.config.privacy.lock_after_minutes;
+        if lock_after > 0
+            && !self.locked
+            && self.last_input.elapsed() >= StdDuration::from_secs(lock_after * 60)
+        {
+            self.locked = true;
+            self.unlock_input.clear();
+            changed = true;
+        }
+        self.snapshot_session();
+        if self
+            .pending_g
+            .is_some_and(|at| at.elapsed() >= G_PREFIX_TIMEOUT)
+        {
+            self.pending_g = None;
+            self.start_sync_enabled();
+            changed = true;
+        }
+        let today = clock::today();
+        if today != self.today {
+            self.today = today;
+            self.reload();
+            self.set_status("New day: due dates refreshed");
+            changed = true;
+        }
+        if self.check_external_changes() {
+            changed = true;
+        }
+        self.check_overdue_hook();
+        changed
+    }
+
+    /// Reload when another process (`koto add`, the daemon, `koto serve`) wrote to the
+    /// store since the last check, keeping the selected todo.
+    fn check_external_changes(&mut self) -> bool {
+        let last = match self.data_version {
+            Some((_, at)) if at.elapsed() < EXTERNAL_CHANGE_INTERVAL => return false,
+            Some((version, _)) => Some(version),
+            None => None,
+        };
+        let version = match self.repo.data_version() {
+            Ok(version) => version,
+            Err(e) => {
+                // Checked every second: a lasting failure is shown once, not each time.
+                let msg = format!("Storage error: {e:#}");
+                if self
+                    .last_error
+                    .as_ref()
+                    .is_none_or(|last| last.message != msg)
+                {
+                    self.report_error(&msg, format!("{e:?}"));
+                }
+                return false;
+            }
+        };
+        self.data_version = Some((version, Instant::now()));
+        if last.is_none_or(|last| last == version) {
+            return false;
+        }
+        self.focus = self.selected_id();
+        self.reload();
+        true
+    }
+
+    /// Fire `on_overdue` for each open todo whose due date passed since the last check.
+    fn check_overdue_hook(&mut self) {
+        if self.config.hooks.on_overdue.is_none()
+            || self
+                .overdue_checked
+                .is_some_and(|at| at.elapsed() < OVERDUE_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.overdue_checked = Some(Instant::now());
+        let until = crate::now_unix();
+        let Some((checked_at, todos)) =
+            self.store(|repo| Ok((repo.meta(META_OVERDUE_CHECKED_AT)?, repo.all()?)))
+        else {
+            return;
+        };
+        let since = checked_at
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(until as u64);
+        let since = SystemTime::UNIX_EPOCH + StdDuration::from_secs(since);
+        let now = SystemTime::now();
+        let passed: Vec<Todo> = todos
+            .into_iter()
+            .filter(|t| !t.is_closed() && t.due.is_some_and(|due| due > since && due <= now))
+            .collect();
+        for todo in &passed {
+            self.fire_hook(HookEvent::Overdue, todo);
+        }
+        self.store(|repo| repo.set_meta(META_OVERDUE_CHECKED_AT, &until.to_string()));
+    }
+
+    /// Run the `[hooks]` command for `event`; one that cannot start shows in the status line.
+    fn fire_hook(&mut self, event: HookEvent, payload: &impl serde::Serialize) {
+        if let Err(e) = hooks::fire(&self.config.hooks, event, payload) {
+            self.set_error(&format!("{e:#}"));
+        }
+    }
+
+    /// Use `session` for crash recovery; a snapshot left behind by the previous run is
+    /// offered for restoring first.
+    pub fn attach_session(&mut self, session: SessionFile) {
+        self.restore_offer = session.load();
+        self.session = Some(session);
+    }
+
+    /// Unsent input worth keeping across a crash, if any.
+    fn current_draft(&self) -> Option<Draft> {
+        if let Some(review) = &self.review {
+            return (!review.body.trim().is_empty()).then(|| Draft::Review {
+                todo_id: review.todo_id,
+                pr_key: review.pr_key.clone(),
+                event: review.event,
+                body: review.body.clone(),
+            });
+        }
+        if self.input.as_str().trim().is_empty() {
+            return None;
+        }
+        let text = self.input.as_str().to_string();
+        match self.mode {
+            InputMode::Editing => Some(Draft::NewTodo {
+                text,
+                parent: self.adding_under,
+            }),
+            InputMode::EditingDue => Some(Draft::Due {
+                todo_id: self.selected_id()?,
+                text,
+            }),
+            InputMode::EditingTitle => {
+                let (todo_id, resolving) = self.title_target?;
+                Some(Draft::Rename {
+                    todo_id,
+                    resolving,
+                    text,
+                })
+            }
+            InputMode::Tagging => Some(Draft::Tag {
+                marked: self.marked_ids(),
+                text,
+            }),
+            InputMode::Normal | InputMode::Watching | InputMode::Search => None,
+        }
+    }
+
+    /// Write the current draft to the session file every `SNAPSHOT_INTERVAL` while it
+    /// changes, and remove the file once nothing is left unsent.
+    fn snapshot_session(&mut self) {
+        // Keep the previous session's snapshot until the user has answered the offer.
+        if self.restore_offer.is_some() {
+            return;
+        }
+        let Some(session) = &self.session else { return };
+        let draft = self.current_draft();
+        let due = match &self.saved_draft {
+            Some((saved, at)) => *saved != draft && at.elapsed() >= SNAPSHOT_INTERVAL,
+            None => draft.is_some(),
+        };
+        if !due {
+            return;
+        }
+        let result = match &draft {
+            Some(draft) => session.save(draft),
+            None => session.clear(),
+        };
+        // Recorded even on failure, so a broken disk does not retry every tick.
+        self.saved_draft = Some((draft, Instant::now()));
+        if let Err(e) = result {
+            self.set_status(&format!("Could not save the session snapshot: {e:#}"));
+        }
+    }
+
+    /// `y` on the restore prompt: reopen the input (or review) the last session lost.
+    pub fn restore_session(&mut self) {
+        let Some(snapshot) = self.restore_offer.take() else {
+            return;
+        };
+        // The restored input is written out again on the next tick.
+        if let Some(session) = &self.session {
+            let _ = session.clear();
+        }
+        let known = |app: &Self, id: TodoId| app.todos.iter().any(|t| t.id == id);
+        match snapshot.draft {
+            Draft::NewTodo { text, parent } => {
+                self.adding_under = parent.filter(|&p| known(self, p));
+                self.input.set(text);
+                self.mode = InputMode::Editing;
+            }
+            Draft::Due { todo_id, text } if known(self, todo_id) => {
+                self.focus = Some(todo_id);
+                self.reload();
+                self.input.set(text);
+                self.mode = InputMode::EditingDue;
+            }
+            Draft::Rename {
+                todo_id,
+                resolving,
+                text,
+            } if known(self, todo_id) => {
+                self.focus = Some(todo_id);
+                self.reload();
+                self.title_target = Some((todo_id, resolving));
+                self.input.set(text);
+                self.mode = InputMode::EditingTitle;
+            }
+            Draft::Tag { marked, text } => {
+                self.marked = marked.into_iter().filter(|&id| known(self, id)).collect();
+                self.input.set(text);
+                self.mode = InputMode::Tagging;
+            }
+            Draft::Review {
+                todo_id,
+                pr_key,
+                event,
+                body,
+            } if known(self, todo_id) => {
+                self.focus = Some(todo_id);
+                self.reload();
+                self.review = Some(ReviewDraft {
+                    todo_id,
+                    pr_key,
+                    event,
+                    body,
+                });
+            }
+            draft => {
+                // Its todo is gone; at least show the text so it can be copied by hand.
+                self.set_status(&format!("Todo no longer exists; lost {}", draft.summary()));
+                return;
+            }
+        }
+        self.set_status("Restored unsent input from the last session");
+    }
+
+    /// `n` on the restore prompt.
+    pub fn discard_session(&mut self) {
+        if self.restore_offer.take().is_some() {
+            self.saved_draft = None;
+            if let Some(session) = &self.session
+                && let Err(e) = session.clear()
+            {
+                self.set_status(&format!("Could not remove the session snapshot: {e:#}"));
+                return;
+            }
+            self.set_status("Discarded the last session's input");
+        }
+    }
+
+    /// Clean exit: nothing to recover next time, unless a restore offer is still open.
+    pub fn end_session(&mut self) {
+        if self.restore_offer.is_none()
+            && let Some(session) = &self.session
+        {
+            let _ = session.clear();
+        }
+    }
+
+    /// Something on screen changes by itself (running timer, fading highlight), so the
+    /// UI has to keep redrawing without input.
+    pub fn is_animating(&self) -> bool {
+        self.running_entry().is_some()
+            || self.pending_g.is_some()
+            || self.flash.is_some_and(|(_, at)| {
+                at.elapsed() < FLASH_DURATION + StdDuration::from_millis(200)
+            })
+    }
+
+    /// Wake the UI loop whenever a background job has news (see `poll_sync`).
+    pub fn set_notify(&self, notify: Notify) {
+        if let Some(daemon) = &self.daemon {
+            daemon.set_notify(Arc::clone(&notify));
+        }
+        self.worker.set_notify(notify);
+    }
+
+    pub fn start_search(&mut self) {
+        self.mode = InputMode::Search;
+        self.search.get_or_insert_with(TodoQuery::default);
+        self.selected = 0;
+        self.reload();
+        self.set_status("Type to search ; Tab scope ; Enter keep ; Esc clear");
+    }
+
+    pub fn search_push(&mut self, c: char) {
+        if let Some(q) = self.search.as_mut() {
+            q.text.push(c);
+            self.selected = 0;
+            self.reload();
+        }
+    }
+
+    pub fn search_pop(&mut self) {
+        if let Some(q) = self.search.as_mut() {
+            q.text.pop();
+            self.selected = 0;
+            self.reload();
+        }
+    }
+
+    pub fn cycle_search_scope(&mut self) {
+        if let Some(q) = self.search.as_mut() {
+            q.scope = q.scope.next();
+            let label = q.scope.label();
+            self.selected = 0;
+            self.reload();
+            self.set_status(&format!("Search scope: {label}"));
+        }
+    }
+
+    /// Leave the search prompt but keep the results on screen.
+    pub fn confirm_search(&mut self) {
+        self.mode = InputMode::Normal;
+        let found = self.todos.len();
+        self.set_status(&format!("{found} match(es) ; / refine ; Esc clear"));
+    }
+
+    pub fn clear_search(&mut self) {
+        self.mode = InputMode::Normal;
+        let selected = self.selected_id();
+        self.search = None;
+        self.reload();
+        if let Some(pos) = selected.and_then(|id| self.todos.iter().position(|t| t.id == id)) {
+            self.selected = pos;
+        }
+        self.set_status("Search cleared");
+    }
+
+    /// Re-read the list from the store; on a read error the previous list stays up.
+    pub fn reload(&mut self) {
+        let search = self.search.clone();
+        let Some((todos, time_entries, all, links)) = self.store(|repo| {
+            let todos = match &search {
+                Some(query) => repo.search(query)?,
+                None => repo.all()?,
+            };
+            // Search results may leave out a match's parent, so resolve links on the full list.
+            let all = match search {
+                Some(_) => Some(repo.all()?),
+                None => None,
+            };
+            Ok((todos, repo.time_entries(None)?, all, repo.dependencies()?))
+        }) else {
+            return;
+        };
+        self.todos = todos;
+        self.time_entries = time_entries;
+        self.hierarchy = Hierarchy::new(all.as_deref().unwrap_or(&self.todos));
+        self.blockers = dependency::open_blockers(&links, all.as_deref().unwrap_or(&self.todos));
+        self.due_counts = count_open_by_due_date(&self.todos, &self.hierarchy);
+        self.planned_today = planned_minutes(&self.todos, &self.hierarchy, self.today);
+        if let Some(day) = self.day_filter {
+            let hierarchy = &self.hierarchy;
+            self.todos.retain(|t| {
+                hierarchy
+                    .effective_due(t.id)
+                    .is_some_and(|d| clock::to_local(d.at).date() == day)
+            });
+        }
+        if self.request_filter != RequestFilter::All {
+            let prs = &self.prs;
+            let is_team = |t: &Todo| {
+                t.github_pr_key()
+                    .and_then(|key| prs.get(key))
+                    .is_some_and(|pr| pr.is_team_request())
+            };
+            match self.request_filter {
+                RequestFilter::Direct => self.todos.retain(|t| !is_team(t)),
+                RequestFilter::Team => self.todos.retain(is_team),
+                RequestFilter::All => {}
+            }
+        }
+        self.hidden_bot_todos = 0;
+        if self.hide_bots {
+            let before = self.todos.len();
+            let prs = &self.prs;
+            self.todos.retain(|t| {
+                !t.github_pr_key()
+                    .and_then(|key| prs.get(key))
+                    .is_some_and(|pr| pr.author_is_bot)
+            });
+            self.hidden_bot_todos = before - self.todos.len();
+        }
+        self.hidden_scheduled = 0;
+        // Search reaches scheduled todos like archived ones.
+        if !self.show_scheduled && self.search.is_none() {
+            let before = self.todos.len();
+            let now = SystemTime::now();
+            self.todos.retain(|t| !t.is_scheduled(now));
+            self.hidden_scheduled = before - self.todos.len();
+        }
+        self.sort_todos();
+        // Only act on what is on screen: marks of todos that left the list are dropped.
+        if !self.marked.is_empty() {
+            let listed: HashSet<TodoId> = self.todos.iter().map(|t| t.id).collect();
+            self.marked.retain(|id| listed.contains(id));
+        }
+        if let Some(id) = self.focus.take()
+            && let Some(idx) = self.todos.iter().position(|t| t.id == id)
+        {
+            self.selected = idx;
+        }
+        if self.selected >= self.todos.len() && !self.todos.is_empty() {
+            self.selected = self.todos.len() - 1;
+        }
+    }
+
+    fn load_cached_prs(&mut self) {
+        let Some(prs) = self.store(|repo| repo.cached_prs()) else {
+            return;
+        };
+        self.prs = prs.into_iter().map(|pr| (pr.pr_key.clone(), pr)).collect();
+    }
+
+    /// Minutes by which today's estimates exceed `[time] daily_capacity_minutes`.
+    pub fn over_capacity(&self) -> Option<u32> {
+        let capacity = self.config.time.daily_capacity_minutes;
+        (capacity > 0 && self.planned_today > capacity).then(|| self.planned_today - capacity)
+    }
+
+    /// Due date shown for `todo`: its own, or inherited from the nearest parent that has one.
+    pub fn effective_due(&self, todo: &Todo) -> Option<subtask::EffectiveDue> {
+        self.hierarchy.effective_due(todo.id)
+    }
+
+    /// `A`: start typing a subtask of the selected task.
+    pub fn start_add_subtask(&mut self) {
+        let Some(parent) = self.todos.get(self.selected) else {
+            self.set_status("No task selected");
+            return;
+        };
+        self.adding_under = Some(parent.id);
+        self.mode = InputMode::Editing;
+        self.input.clear();
+        let msg = format!("Type subtask of \"{}\" and press Enter", parent.title);
+        self.set_status(&msg);
+    }
+
+    /// Cached PR behind a GitHub-synced todo.
+    pub fn pr_for(&self, todo: &Todo) -> Option<&Pr> {
+        self.prs
+            .get(todo.github_pr_key().or_else(|| todo.own_pr_key())?)
+    }
+
+    /// Key / action pairs that apply to the selected todo, most relevant first, for the
+    /// hint line under the table.
+    pub fn selected_hints(&self) -> Vec<(&'static str, &'static str)> {
+        if self.view == View::Inbox {
+            return vec![
+                ("Enter", "open"),
+                ("m", "mark read"),
+                ("a", "add as todo"),
+                ("r", "refresh"),
+                ("Esc", "back to list"),
+            ];
+        }
+        if !self.marked.is_empty() {
+            return vec![
+                ("v", "mark / unmark"),
+                ("P", "priority"),
+                ("[ / ]", "shift due"),
+                ("#", "tag"),
+                ("c", "archive"),
+                ("d", "delete"),
+                ("Esc", "clear marks"),
+            ];
+        }
+        let Some(todo) = self.todos.get(self.selected) else {
+            return vec![("a", "add a task")];
+        };
+        let mut hints = Vec::new();
+        if todo.incoming_title.is_some() {
+            hints.push(("x", "resolve title conflict"));
+        }
+        if self.is_unread(todo) {
+            hints.push(("u", "mark seen"));
+        }
+        match self.pr_for(todo) {
+            Some(pr) => {
+                let several = self.links_for(todo).len() > 1;
+                hints.push(("Enter", if several { "links" } else { "open PR" }));
+                if !pr.is_viewer_author && !todo.is_closed() {
+                    hints.push(("R", "review"));
+                }
+                if !pr.ci_checks.is_empty() {
+                    hints.push(("C", "CI checks"));
+                }
+                if pr.merge_blockers.as_ref().is_some_and(|b| b.is_behind_base) {
+                    hints.push(("i U", "update branch"));
+                }
+            }
+            None if todo.external_url.is_some() => hints.push(("Enter", "open link")),
+            None => {}
+        }
+        hints.push(("Space", if todo.is_closed() { "reopen" } else { "done" }));
+        if todo.is_closed() {
+            hints.push(("c", "archive done"));
+        } else {
+            let timing = self.running_entry().is_some_and(|e| e.todo_id == todo.id);
+            hints.push(("T", if timing { "stop timer" } else { "start timer" }));
+            hints.push(("t", "due"));
+            hints.push(("A", "subtask"));
+        }
+        hints.push(("e", "rename"));
+        hints.push(("i", "details"));
+        hints
+    }
+
+    /// `f`: cycle all / direct review requests / team review requests.
+    pub fn cycle_request_filter(&mut self) {
+        self.request_filter = self.request_filter.next();
+        self.reload();
+        self.set_status(&format!("Showing {}", self.request_filter.label()));
+    }
+
+    /// `B`: hide / show todos of PRs opened by bots.
+    pub fn toggle_hide_bots(&mut self) {
+        self.hide_bots = !self.hide_bots;
+        self.reload();
+        if self.hide_bots {
+            self.set_status(&format!(
+                "Hiding bot PRs ({} hidden)",
+                self.hidden_bot_todos
+            ));
+        } else {
+            self.set_status("Showing bot PRs");
+        }
+    }
+
+    /// `s`: show / hide todos whose start date has not come yet.
+    pub fn toggle_show_scheduled(&mut self) {
+        self.show_scheduled = !self.show_scheduled;
+        self.focus = self.selected_id();
+        self.reload();
+        if self.show_scheduled {
+            self.set_status("Showing scheduled todos");
+        } else {
+            self.set_status(&format!(
+                "Hiding scheduled todos ({} hidden)",
+                self.hidden_scheduled
+            ));
+        }
+    }
+
+    /// `o`: cycle the due-date, priority, attention and manual orders.
+    pub fn toggle_sort(&mut self) {
+        let sort = match self.sort {
+            SortMode::Due => SortMode::Priority,
+            SortMode::Priority => SortMode::Attention,
+            SortMode::Attention => SortMode::Manual,
+            SortMode::Manual => SortMode::Due,
+        };
+        self.set_sort(sort);
+        self.focus = self.selected_id();
+        self.reload();
+        self.set_status(match sort {
+            SortMode::Due => "Sorted by due date",
+            SortMode::Priority => "Sorted by priority, then due date",
+            SortMode::Manual => "Manual order (J / K move the selected task)",
+            SortMode::Attention => "Most urgent PRs first (attention score, see i)",
+        });
+    }
+
+    fn set_sort(&mut self, sort: SortMode) {
+        self.sort = sort;
+        self.store(|repo| repo.set_meta(META_SORT_MODE, sort.label()));
+    }
+
+    /// `J` / `K`: move the selected todo down / up among its siblings (same parent,
+    /// same done state). Switches to the manual order, starting from the one on screen.
+    pub fn move_selected(&mut self, down: bool) {
+        if self.view != View::List
+            || self.search.is_some()
+            || self.day_filter.is_some()
+            || self.request_filter != RequestFilter::All
+            || self.hidden_bot_todos > 0
+        {
+            self.set_status("Reorder in the list view without a search or filter");
+            return;
+        }
+        let Some(todo) = self.todos.get(self.selected) else {
+            self.set_status("No task selected");
+            return;
+        };
+        let (id, parent, done) = (todo.id, todo.parent_id, todo.is_closed());
+        let mut group: Vec<TodoId> = self
+            .todos
+            .iter()
+            .filter(|t| t.parent_id == parent && t.is_closed() == done)
+            .map(|t| t.id)
+            .collect();
+        let pos = group.iter().position(|g| *g == id).unwrap_or_default();
+        let target = if down { pos + 1 } else { pos.wrapping_sub(1) };
+        if target >= group.len() {
+            self.set_status(if down {
+                "Already last in its group"
+            } else {
+                "Already first in its group"
+            });
+            return;
+        }
+        group.swap(pos, target);
+        let orders: Vec<(TodoId, i64)> = group
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i as i64))
+            .collect();
+        if self.store(|repo| repo.set_sort_orders(&orders)).is_none() {
+            return;
+        }
+        if self.sort != SortMode::Manual {
+            self.set_sort(SortMode::Manual);
+            self.set_status("Manual order (o switches back to due date)");
+        }
+        self.focus = Some(id);
+        self.reload();
+    }
+
+    /// `M`: open the calendar (or re-focus it when it is already visible).
+    pub fn open_calendar(&mut self) {
+        match self.calendar.as_mut() {
+            Some(cal) if !cal.focused => cal.focused = true,
+            Some(_) => self.close_calendar(),
+            None => {
+                self.calendar = Some(CalendarState {
+                    cursor: self.day_filter.unwrap_or(self.today),
+                    focused: true,
+                });
+                self.set_status("Calendar: arrows move ; </> month ; Enter filter ; Esc close");
+            }
+        }
+    }
+
+    pub fn close_calendar(&mut self) {
+        self.calendar = None;
+        self.clear_day_filter();
+    }
+
+    pub fn move_calendar(&mut self, days: i64) {
+        if let Some(cal) = self.calendar.as_mut() {
+            cal.cursor = cal.cursor.saturating_add(Duration::days(days));
+        }
+    }
+
+    pub fn move_calendar_month(&mut self, forward: bool) {
+        if let Some(cal) = self.calendar.as_mut() {
+            cal.cursor = shift_month(cal.cursor, forward);
+        }
+    }
+
+    /// Filter the table to the day under the calendar cursor and hand focus back to the table.
+    pub fn apply_calendar_day(&mut self) {
+        let Some(cal) = self.calendar.as_mut() else {
+            return;
+        };
+        cal.focused = false;
+        let day = cal.cursor;
+        self.day_filter = Some(day);
+        self.selected = 0;
+        self.reload();
+        let n = self.todos.len();
+        self.set_status(&format!(
+            "Showing {n} item(s) due {day} (Esc clear, M calendar)"
+        ));
+    }
+
+    pub fn clear_day_filter(&mut self) {
+        if self.day_filter.take().is_some() {
+            self.reload();
+            self.set_status("Day filter cleared");
+        }
+    }
+
+    pub fn set_view(&mut self, view: View) {
+        self.view = view;
+        self.table_offset = 0;
+        let order = self.visible_order();
+        if !order.contains(&self.selected)
+            && let Some(first) = order.first()
+        {
+            self.selected = *first;
+        }
+        self.set_status(match view {
+            View::List => "List view",
+            View::Agenda => "Agenda view (open items due within a week)",
+            View::Repos => "Repository view (z fold group ; Z fold / unfold all)",
+            View::Inbox => "Inbox (Enter open ; m mark read ; a add as todo ; r refresh)",
+            View::Board => "Board (h / l column ; H / L move the todo ; Tab by state / priority)",
+        });
+        if view == View::Inbox && self.inbox.is_none() {
+            self.refresh_inbox();
+        }
+    }
+
+    /// Fetch the main account's unread notifications in the background.
+    pub fn refresh_inbox(&mut self) {
+        let Some(cfg) = self.github.first().cloned() else {
+            self.set_status("No GitHub token; the inbox needs one");
+            return;
+        };
+        if self.inbox_loading {
+            return;
+        }
+        self.inbox_loading = true;
+        self.worker.submit(SyncJob::Notifications {
+            cfg,
+            reply: self.inbox_tx.clone(),
+        });
+    }
+
+    pub fn move_inbox(&mut self, delta: isize) {
+        let len = self.inbox.as_ref().map_or(0, Vec::len);
+        if len > 0 {
+            self.inbox_selected = self
+                .inbox_selected
+                .saturating_add_signed(delta)
+                .min(len - 1);
+        }
+    }
+
+    fn selected_notification(&self) -> Option<&Notification> {
+        self.inbox.as_ref()?.get(self.inbox_selected)
+    }
+
+    pub fn open_selected_notification(&mut self) {
+        if let Some(url) = self.selected_notification().map(|n| n.url.clone()) {
+            self.open_url(&url);
+        }
+    }
+
+    /// `m` in the inbox: mark the notification read on GitHub. It stays listed, dimmed,
+    /// until the next refresh.
+    pub fn mark_notification_read(&mut self) {
+        let Some(cfg) = self.github.first().cloned() else {
+            return;
+        };
+        let Some(thread_id) = self
+            .selected_notification()
+            .filter(|n| n.unread)
+            .map(|n| n.thread_id.clone())
+        else {
+            return;
+        };
+        if let Some(n) = self
+            .inbox
+            .as_mut()
+            .and_then(|i| i.get_mut(self.inbox_selected))
+        {
+            n.unread = false;
+        }
+        self.worker.submit(SyncJob::MarkNotificationRead {
+            cfg,
+            thread_id,
+            reply: self.inbox_tx.clone(),
+        });
+    }
+
+    /// `a` in the inbox: turn the notification into a todo (again: the existing one).
+    pub fn add_notification_todo(&mut self) {
+        let Some(n) = self.selected_notification().cloned() else {
+            return;
+        };
+        let title = format!("{}: {}", n.repo, n.title);
+        let key = format!("{GITHUB_NOTIFICATION_KEY_PREFIX}{}:{}", n.repo, n.thread_id);
+        let Some(todo) = self.store(|repo| {
+            repo.add(
+                title,
+                Priority::Medium,
+                None,
+                Some(n.url.clone()),
+                Some(key),
+            )
+        }) else {
+            return;
+        };
+        self.focus = Some(todo.id);
+        self.reload();
+        self.set_status(&format!("Todo added: {}", todo.title));
+    }
+
+    /// Apply finished inbox jobs. Returns true when state changed.
+    pub fn poll_inbox(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok(event) = self.inbox_rx.try_recv() {
+            changed = true;
+            match event {
+                InboxEvent::Fetched(Ok(notifications)) => {
+                    self.inbox_loading = false;
+                    self.inbox_selected = self
+                        .inbox_selected
+                        .min(notifications.len().saturating_sub(1));
+                    self.set_status(&format!("{} unread notifications", notifications.len()));
+                    self.inbox = Some(notifications);
+                }
+                InboxEvent::Fetched(Err(e)) => {
+                    self.inbox_loading = false;
+                    self.inbox.get_or_insert_with(Vec::new);
+                    self.set_error(&format!("Inbox: {e}"));
+                }
+                InboxEvent::MarkedRead { result: Ok(()), .. } => {}
+                InboxEvent::MarkedRead {
+                    thread_id,
+                    result: Err(e),
+                } => {
+                    if let Some(n) = self
+                        .inbox
+                        .iter_mut()
+                        .flatten()
+                        .find(|n| n.thread_id == thread_id)
+                    {
+                        n.unread = true;
+                    }
+                    self.set_error(&format!("Mark read failed: {e}"));
+                }
+            }
+        }
+        changed
+    }
+
+    /// Todos grouped by `Todo::source_group`, as indices into `todos`; groups appear in
+    /// the order of their first todo, so the current sort still decides what comes first.
+    pub fn repo_groups(&self) -> Vec<(String, Vec<usize>)> {
+        let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+        for (idx, todo) in self.todos.iter().enumerate() {
+            let group = todo.source_group();
+            match groups.iter_mut().find(|(name, _)| *name == group) {
+                Some((_, items)) => items.push(idx),
+                None => groups.push((group, vec![idx])),
+            }
+        }
+        groups
+    }
+
+    /// `z` in the repository view: fold the selected todo's group.
+    pub fn collapse_selected_group(&mut self) {
+        if self.view != View::Repos {
+            self.set_status("Groups fold in the repository view (3)");
+            return;
+        }
+        let Some(group) = self.todos.get(self.selected).map(Todo::source_group) else {
+            return;
+        };
+        // Keep the selection on a visible row: the next one after the group, or before it.
+        let order = self.visible_order();
+        let pos = order.iter().position(|&i| i == self.selected);
+        let in_group = |i: &usize| self.todos[*i].source_group() == group;
+        let next = pos.and_then(|pos| {
+            order[pos..]
+                .iter()
+                .find(|i| !in_group(i))
+                .or_else(|| order[..pos].iter().rev().find(|i| !in_group(i)))
+                .copied()
+        });
+        if let Some(next) = next {
+            self.selected = next;
+        }
+        self.set_status(&format!("Folded {group} (Z unfolds)"));
+        self.collapsed_groups.insert(group);
+        self.save_collapsed_groups();
+    }
+
+    /// `Z` in the repository view: unfold every group, or fold them all when none is.
+    pub fn toggle_all_groups(&mut self) {
+        if self.view != View::Repos {
+            self.set_status("Groups fold in the repository view (3)");
+            return;
+        }
+        if self.collapsed_groups.is_empty() {
+            self.collapsed_groups = self
+                .repo_groups()
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect();
+            self.set_status("Folded all groups");
+        } else {
+            self.collapsed_groups.clear();
+            self.set_status("Unfolded all groups");
+        }
+        self.save_collapsed_groups();
+    }
+
+    fn save_collapsed_groups(&mut self) {
+        let mut groups: Vec<&str> = self.collapsed_groups.iter().map(String::as_str).collect();
+        groups.sort_unstable();
+        let value = groups.join("\n");
+        self.store(|repo| repo.set_meta(META_COLLAPSED_GROUPS, &value));
+    }
+
+    /// Open todos due within the next week grouped by agenda section, as indices into `todos`.
+    pub fn agenda(&self) -> Vec<(DueBucket, Vec<usize>)> {
+        let mut sections: Vec<(DueBucket, Vec<usize>)> = [
+            DueBucket::Overdue,
+            DueBucket::Today,
+            DueBucket::Tomorrow,
+            DueBucket::ThisWeek,
+        ]
+        .into_iter()
+        .map(|b| (b, Vec::new()))
+        .collect();
+        for (idx, todo) in self.todos.iter().enumerate() {
+            if todo.is_closed() {
+                continue;
+            }
+            let Some(due) = self.effective_due(todo) else {
+                continue;
+            };
+            let bucket = DueBucket::of(due.at, self.today);
+            if let Some((_, items)) = sections.iter_mut().find(|(b, _)| *b == bucket) {
+                items.push(idx);
+            }
+        }
+        sections
+    }
+
+    /// Indices of `todos` in the order the current view shows them.
+    pub fn visible_order(&self) -> Vec<usize> {
+        match self.view {
+            View::List => (0..self.todos.len()).collect(),
+            View::Agenda => self
+                .agenda()
+                .into_iter()
+                .flat_map(|(_, items)| items)
+                .collect(),
+            View::Repos => self
+                .repo_groups()
+                .into_iter()
+                .filter(|(name, _)| !self.collapsed_groups.contains(name))
+                .flat_map(|(_, items)| items)
+                .collect(),
+            View::Inbox => Vec::new(),
+            View::Board => match self.todos.get(self.selected) {
+                Some(todo) => {
+                    let column = self.board_column(todo);
+                    self.board_columns().swap_remove(column).1
+                }
+                None => Vec::new(),
+            },
+        }
+    }
+
+    /// Board column titles and the `todos` indices in each, in list order.
+    pub fn board_columns(&self) -> Vec<(&'static str, Vec<usize>)> {
+        let titles = match self.board_layout {
+            BoardLayout::State => ["Backlog", "Doing", "Done"],
+            BoardLayout::Priority => ["High", "Medium", "Low"],
+        };
+        let mut columns: Vec<(&'static str, Vec<usize>)> =
+            titles.into_iter().map(|t| (t, Vec::new())).collect();
+        for (idx, todo) in self.todos.iter().enumerate() {
+            columns[self.board_column(todo)].1.push(idx);
+        }
+        columns
+    }
+
+    fn board_column(&self, todo: &Todo) -> usize {
+        match self.board_layout {
+            BoardLayout::State => match todo.status {
+                Status::Todo => 0,
+                Status::InProgress | Status::Blocked => 1,
+                Status::Done | Status::Cancelled => 2,
+            },
+            BoardLayout::Priority => todo.priority as usize - 1,
+        }
+    }
+
+    /// `h` / `l` on the board: select in the next non-empty column that way, keeping the
+    /// row where possible.
+    pub fn move_board_column(&mut self, delta: isize) {
+        let Some(todo) = self.todos.get(self.selected) else {
+            return;
+        };
+        let columns = self.board_columns();
+        let from = self.board_column(todo);
+        let row = columns[from]
+            .1
+            .iter()
+            .position(|&idx| idx == self.selected)
+            .unwrap_or(0);
+        let mut column = from as isize + delta;
+        while let Some((_, items)) = usize::try_from(column).ok().and_then(|c| columns.get(c)) {
+            if let Some(&idx) = items.get(row).or(items.last()) {
+                self.selected = idx;
+                return;
+            }
+            column += delta;
+        }
+    }
+
+    /// `H` / `L` on the board: move the selected todo to the column on that side,
+    /// reopening, starting or completing it (or changing its priority).
+    pub fn shift_board(&mut self, delta: isize) {
+        let Some(todo) = self.todos.get(self.selected).cloned() else {
+            return;
+        };
+        let Some(target) = self
+            .board_column(&todo)
+            .checked_add_signed(delta)
+            .filter(|c| *c < 3)
+        else {
+            return;
+        };
+        let title = self.board_columns()[target].0;
+        let id = todo.id;
+        let layout = self.board_layout;
+        let moved = self.store(|repo| match layout {
+            BoardLayout::State => {
+                let status = [Status::Todo, Status::InProgress, Status::Done][target];
+                repo.set_status(id, status)
+            }
+            BoardLayout::Priority => {
+                let priority = Priority::from_level(target as u8 + 1);
+                repo.update_meta(id, priority, todo.due_value())
+            }
+        });
+        let Some(moved) = moved else { return };
+        self.focus = Some(id);
+        self.reload();
+        self.set_status(&format!("Moved to {title}"));
+        if let Some(done) = moved.filter(|t| t.status == Status::Done && !todo.is_closed()) {
+            self.fire_hook(HookEvent::Done, &done);
+        }
+    }
+
+    /// `Tab` on the board: columns by state or by priority.
+    pub fn toggle_board_layout(&mut self) {
+        self.board_layout = match self.board_layout {
+            BoardLayout::State => BoardLayout::Priority,
+            BoardLayout::Priority => BoardLayout::State,
+        };
+        self.set_status(match self.board_layout {
+            BoardLayout::State => "Board by state (Backlog / Doing / Done)",
+            BoardLayout::Priority => "Board by priority (High / Medium / Low)",
+        });
+    }
+
+    pub fn select_next(&mut self) {
+        self.move_selection(1);
+    }
+
+    pub fn select_previous(&mut self) {
+        self.move_selection(-1);
+    }
+
+    /// `gg` / Home.
+    pub fn select_first(&mut self) {
+        if let Some(&first) = self.visible_order().first() {
+            self.selected = first;
+        }
+    }
+
+    /// `G` / End.
+    pub fn select_last(&mut self) {
+        if let Some(&last) = self.visible_order().last() {
+            self.selected = last;
+        }
+    }
+
+    /// PageDown / PageUp, or half a page for Ctrl-d / Ctrl-u: the selection and the
+    /// viewport move together, so the selected row keeps its place on screen.
+    pub fn scroll_page(&mut self, down: bool, half: bool) {
+        let rows = if half {
+            self.page_rows / 2
+        } else {
+            self.page_rows
+        };
+        let delta = rows.max(1) as isize * if down { 1 } else { -1 };
+        self.move_selection(delta);
+        self.table_offset = self.table_offset.saturating_add_signed(delta);
+    }
+
+    /// `g`: the first press waits briefly for a second one (`gg` jumps to the top); a
+    /// lone `g` syncs from `on_tick`, or right before the next other key is handled.
+    pub fn press_g(&mut self) {
+        if self.pending_g.take().is_some() {
+            self.select_first();
+        } else {
+            self.pending_g = Some(Instant::now());
+        }
+    }
+
+    /// A key other than `g` arrived: a waiting `g` was meant as sync.
+    pub fn flush_pending_g(&mut self) {
+        if self.pending_g.take().is_some() {
+            self.start_sync_enabled();
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let order = self.visible_order();
+        if order.is_empty() {
+            return;
+        }
+        let next = match order.iter().position(|&i| i == self.selected) {
+            Some(pos) => pos.saturating_add_signed(delta).min(order.len() - 1),
+            None => 0,
+        };
+        self.selected = order[next];
+    }
+
+    pub fn cycle_priority_selected(&mut self) {
+        let Some(id) = self.selected_id() else { return };
+        let current = self.todos[self.selected].priority;
+        let next = match current {
+            Priority::High => Priority::Medium,
+            Priority::Medium => Priority::Low,
+            Priority::Low => Priority::High,
+        };
+        if !self.marked.is_empty() {
+            // Every marked todo gets the priority that follows the selected one's.
+            let ids = self.marked_ids();
+            let Some(changed) = self.store(|repo| repo.set_priority_many(&ids, next)) else {
+                return;
+            };
+            self.focus = Some(id);
+            self.reload();
+            self.set_status(&format!("Priority of {changed} marked todo(s) set"));
+            return;
+        }
+        let due = self.todos[self.selected].due_value();
+        if self.store(|repo| repo.update_meta(id, next, due)).is_none() {
+            return;
+        }
+        self.focus = Some(id);
+        self.reload();
+        self.set_status("Priority cycled");
+    }
+
+    /// `p`: wait for the priority to set with `1` / `2` / `3`.
+    pub fn start_priority_prefix(&mut self) {
+        if self.selected_id().is_none() {
+            return;
+        }
+        self.pending_priority = true;
+        self.set_status("Priority: 1 high, 2 medium, 3 low");
+    }
+
+    /// `p1` / `p2` / `p3`: set the priority of the marked todos, or of the selected one.
+    pub fn set_priority_selected(&mut self, priority: Priority) {
+        self.pending_priority = false;
+        let Some(id) = self.selected_id() else { return };
+        let label = match priority {
+            Priority::High => "high",
+            Priority::Medium => "medium",
+            Priority::Low => "low",
+        };
+        if !self.marked.is_empty() {
+            let ids = self.marked_ids();
+            let Some(changed) = self.store(|repo| repo.set_priority_many(&ids, priority)) else {
+                return;
+            };
+            self.focus = Some(id);
+            self.reload();
+            self.set_status(&format!(
+                "Priority of {changed} marked todo(s) set to {label}"
+            ));
+            return;
+        }
+        let due = self.todos[self.selected].due_value();
+        if self
+            .store(|repo| repo.update_meta(id, priority, due))
+            .is_none()
+        {
+            return;
+        }
+        self.focus = Some(id);
+        self.reload();
+        self.set_status(&format!("Priority set to {label}"));
+    }
+
+    pub fn shift_due_selected(&mut self, days: i64) {
+        let Some(id) = self.selected_id() else { return };
+        if !self.marked.is_empty() {
+            let dues: Vec<(TodoId, Option<Due>)> = self
+                .todos
+                .iter()
+                .filter(|t| self.marked.contains(&t.id))
+                .map(|t| (t.id, Some(shift_due(t.due_value(), days))))
+                .collect();
+            let Some(changed) = self.store(|repo| repo.set_due_many(&dues)) else {
+                return;
+            };
+            self.focus = Some(id);
+            self.reload();
+            self.set_status(&format!("Due of {changed} marked todo(s) moved by {days}d"));
+            return;
+        }
+        let new_due = Some(shift_due(self.todos[self.selected].due_value(), days));
+        let priority = self.todos[self.selected].priority;
+        if self
+            .store(|repo| repo.update_meta(id, priority, new_due))
+            .is_none()
+        {
+            return;
+        }
+        self.focus = Some(id);
+        self.reload();
+        self.set_status(&format!(
+            "Due {} by {}d",
+            if days >= 0 { "moved" } else { "moved back" },
+            days.abs()
+        ));
+    }
+
+    pub fn clear_due_selected(&mut self) {
+        let Some(id) = self.selected_id() else { return };
+        let priority = self.todos[self.selected].priority;
+        if self
+            .store(|repo| repo.update_meta(id, priority, None))
+            .is_none()
+        {
+            return;
+        }
+        self.focus = Some(id);
+        self.reload();
+        self.set_status("Due cleared");
+    }
+
+    /// True while `id` was just added and should stand out.
+    pub fn is_flashing(&self, id: TodoId) -> bool {
+        self.flash
+            .is_some_and(|(flash_id, at)| flash_id == id && at.elapsed() < FLASH_DURATION)
+    }
+
+    fn selected_id(&self) -> Option<TodoId> {
+        self.todos.get(self.selected).map(|t| t.id)
+    }
+
+    pub fn toggle_selected(&mut self) {
+        if let Some(id) = self.selected_id() {
+            let was_blocked: Vec<TodoId> = self.blockers.keys().copied().collect();
+            let Some(toggled) = self.store(|repo| repo.toggle(id)) else {
+                return;
+            };
+            self.reload();
+            let unblocked: Vec<&str> = was_blocked
+                .iter()
+                .filter(|id| !self.blockers.contains_key(id))
+                .filter_map(|id| self.todos.iter().find(|t| t.id == *id))
+                .map(|t| t.title.as_str())
+                .collect();
+            if unblocked.is_empty() {
+                self.set_status("Toggled completion");
+            } else {
+                let msg = format!("Done; unblocked: {}", unblocked.join(", "));
+                self.set_status(&msg);
+            }
+            if let Some(todo) = toggled.filter(|t| t.is_closed()) {
+                self.fire_hook(HookEvent::Done, &todo);
+            }
+        }
+    }
+
+    /// `w`: step the selected todo to its next status (todo → in progress → blocked → done
+    /// → cancelled → todo).
+    pub fn cycle_status(&mut self) {
+        let Some(todo) = self.todos.get(self.selected).cloned() else {
+            return;
+        };
+        let status = todo.status.cycle();
+        let Some(updated) = self.store(|repo| repo.set_status(todo.id, status)) else {
+            return;
+        };
+        self.focus = Some(todo.id);
+        self.reload();
+        self.set_status(&format!("Status: {}", status.label().replace('_', " ")));
+        if let Some(done) = updated.filter(|t| t.status == Status::Done) {
+            self.fire_hook(HookEvent::Done, &done);
+        }
+    }
+
+    /// `b`: the selected todo waits for the marked ones; without marks, it stops waiting.
+    pub fn block_selected(&mut self) {
+        let Some(id) = self.selected_id() else { return };
+        if self.marked.is_empty() {
+            match self.store(|repo| repo.clear_dependencies(id)) {
+                Some(0) => {
+                    self.set_status("Mark the todos this one waits for with v, then press b on it")
+                }
+                Some(_) => {
+                    self.focus = Some(id);
+                    self.reload();
+                    self.set_status("No longer blocked");
+                }
+                None => {}
+            }
+            return;
+        }
+        let blockers: Vec<TodoId> = self.marked_ids().into_iter().filter(|b| *b != id).collect();
+        let Some(links) = self.store(|repo| repo.dependencies()) else {
+            return;
+        };
+        if let Some(cycle) = blockers
+            .iter()
+            .find(|b| dependency::creates_cycle(&links, id, **b))
+        {
+            let title = self
+                .todos
+                .iter()
+                .find(|t| t.id == *cycle)
+                .map_or("?", |t| t.title.as_str());
+            let msg = format!("\"{title}\" already waits for this todo; not linked");
+            self.set_status(&msg);
+            return;
+        }
+        if blockers.is_empty() {
+            self.set_status("Mark the todos this one waits for, not the todo itself");
+            return;
+        }
+        let added = self.store(|repo| {
+            for blocker in &blockers {
+                repo.add_dependency(id, *blocker)?;
+            }
+            Ok(blockers.len())
+        });
+        let Some(added) = added else { return };
+        self.marked.clear();
+        self.focus = Some(id);
+        self.reload();
+        self.set_status(&format!(
+            "Blocked by {added} todo(s) (🔒 until they are done)"
+        ));
+    }
+
+    /// `d` / `Delete`: delete the selected todo, subject to the configured delete guard.
+    pub fn request_delete_selected(&mut self) {
+        let Some(id) = self.selected_id() else { return };
+        if !self.marked.is_empty() {
+            // Too much to lose to a stray key: a batch delete always asks.
+            self.pending_batch_delete = true;
+            self.set_status(&format!(
+                "Delete {} marked todo(s)? y to confirm, any other key cancels",
+                self.marked.len()
+            ));
+            return;
+        }
+        let window = StdDuration::from_millis(self.config.keys.delete_guard_window_ms);
+        let recent = self.last_delete_at.is_some_and(|t| t.elapsed() < window);
+        match self.config.keys.delete_guard {
+            DeleteGuard::Off => self.delete_selected(),
+            DeleteGuard::Double => {
+                if self.pending_delete == Some(id) && recent {
+                    self.pending_delete = None;
+                    self.delete_selected();
+                } else {
+                    self.pending_delete = Some(id);
+                    self.last_delete_at = Some(Instant::now());
+                    self.set_status("Press d again to delete");
+                }
+            }
+            DeleteGuard::Window => {
+                if self.pending_delete.is_some() || recent {
+                    // Likely key repeat: keep asking instead of deleting more.
+                    self.pending_delete = Some(id);
+                    self.last_delete_at = Some(Instant::now());
+                    let title = self.todos[self.selected].title.clone();
+                    self.set_status(&format!(
+                        "Delete \"{title}\"? y to confirm, any other key cancels"
+                    ));
+                } else {
+                    self.delete_selected();
+                }
+            }
+        }
+    }
+
+    /// A delete is waiting for confirmation.
+    pub fn delete_pending(&self) -> bool {
+        self.pending_delete.is_some() || self.pending_batch_delete
+    }
+
+    pub fn confirm_pending_delete(&mut self) {
+        if std::mem::take(&mut self.pending_batch_delete) {
+            self.delete_marked();
+            return;
+        }
+        let Some(id) = self.pending_delete.take() else {
+            return;
+        };
+        if self.selected_id() == Some(id) {
+            self.delete_selected();
+        }
+    }
+
+    pub fn cancel_pending_delete(&mut self) {
+        if self.pending_delete.take().is_some() || std::mem::take(&mut self.pending_batch_delete) {
+            self.set_status("Delete canceled");
+        }
+    }
+
+    pub fn delete_selected(&mut self) {
+        if let Some(id) = self.selected_id() {
+            if let Some(archive) = &self.archive
+                && let Some(todo) = self.todos.iter().find(|t| t.id == id)
+                && let Err(e) = archive.append(std::slice::from_ref(todo), ArchiveReason::Deleted)
+            {
+                self.set_error(&format!("Not deleted: {e:#}"));
+                return;
+            }
+            if self.store(|repo| repo.delete(id)).is_none() {
+                return;
+            }
+            self.last_delete_at = Some(Instant::now());
+            if self.selected > 0 {
+                self.selected -= 1;
+            }
+            self.reload();
+            self.set_status("Deleted");
+        }
+    }
+
+    fn delete_marked(&mut self) {
+        let ids = self.marked_ids();
+        if let Some(archive) = &self.archive {
+            let todos: Vec<Todo> = self
+                .todos
+                .iter()
+                .filter(|t| self.marked.contains(&t.id))
+                .cloned()
+                .collect();
+            if let Err(e) = archive.append(&todos, ArchiveReason::Deleted) {
+                self.set_error(&format!("Not deleted: {e:#}"));
+                return;
+            }
+        }
+        let Some(deleted) = self.store(|repo| repo.delete_many(&ids)) else {
+            return;
+        };
+        self.marked.clear();
+        self.last_delete_at = Some(Instant::now());
+        self.reload();
+        self.set_status(&format!("Deleted {} todo(s)", deleted.len()));
+    }
+
+    /// Marked todos in list order.
+    fn marked_ids(&self) -> Vec<TodoId> {
+        self.todos
+            .iter()
+            .filter(|t| self.marked.contains(&t.id))
+            .map(|t| t.id)
+            .collect()
+    }
+
+    /// `v`: mark / unmark the selected todo and move on to the next one.
+    pub fn toggle_mark_selected(&mut self) {
+        let Some(id) = self.selected_id() else { return };
+        if !self.marked.remove(&id) {
+            self.marked.insert(id);
+        }
+        self.select_next();
+        self.set_status(&format!(
+            "{} marked (P priority ; [/] due ; # tag ; c archive ; d delete ; Esc unmark)",
+            self.marked.len()
+        ));
+    }
+
+    /// `V`: mark every listed todo, or clear the marks when there are any.
+    pub fn toggle_mark_all(&mut self) {
+        if self.marked.is_empty() {
+            self.marked = self
+                .visible_order()
+                .iter()
+                .map(|&i| self.todos[i].id)
+                .collect();
+            self.set_status(&format!("{} marked", self.marked.len()));
+        } else {
+            self.clear_marks();
+        }
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+        self.set_status("Marks cleared");
+    }
+
+    /// `#`: ask for a tag to add to the marked todos.
+    pub fn start_tagging(&mut self) {
+        if self.marked.is_empty() {
+            self.set_status("Mark todos with v first");
+            return;
+        }
+        self.mode = InputMode::Tagging;
+        self.input.clear();
+        self.set_status("Tag to add to the marked todos (Enter to apply)");
+    }
+
+    /// Prefix each marked title with `#tag`, the form saved searches use; titles that
+    /// already carry the tag are left alone.
+    pub fn apply_tagging(&mut self) {
+        let tag = self
+            .input
+            .as_str()
+            .trim()
+            .trim_start_matches('#')
+            .replace(' ', "-");
+        if tag.is_empty() {
+            self.set_status("Input is empty");
+            return;
+        }
+        let label = format!("#{tag}");
+        let titles: Vec<(TodoId, String)> = self
+            .todos
+            .iter()
+            .filter(|t| self.marked.contains(&t.id))
+            .filter(|t| !t.title.split_whitespace().any(|word| word == label))
+            .map(|t| (t.id, format!("{label} {}", t.title)))
+            .collect();
+        let Some(changed) = self.store(|repo| repo.set_title_many(&titles)) else {
+            return;
+        };
+        self.mode = InputMode::Normal;
+        self.input.clear();
+        self.reload();
+        self.set_status(&format!("Tagged {changed} todo(s) {label}"));
+    }
+
+    /// `W`: ask for a PR to watch, starting from the selected todo's PR.
+    pub fn start_watching(&mut self) {
+        let pr = self
+            .todos
+            .get(self.selected)
+            .and_then(|t| t.github_pr_key())
+            .unwrap_or_default()
+            .to_string();
+        self.input.set(pr);
+        self.mode = InputMode::Watching;
+        self.set_status("PR to watch: owner/repo#123 or its URL (Enter to watch)");
+    }
+
+    pub fn apply_watching(&mut self) {
+        let Some(key) = sync::parse_pr_ref(self.input.as_str()) else {
+            self.set_status("Not a PR: expected owner/repo#123 or a PR URL");
+            return;
+        };
+        let Some(added) = self.store(|repo| sync::watch(repo, &key)) else {
+            return;
+        };
+        self.mode = InputMode::Normal;
+        self.input.clear();
+        if added {
+            self.set_status(&format!("Watching {key} (synced with the next g)"));
+        } else {
+            self.set_status(&format!("Already watching {key}"));
+        }
+    }
+
+    /// The session that is currently being tracked, if any.
+    pub fn running_entry(&self) -> Option<&TimeEntry> {
+        self.time_entries.iter().find(|e| e.is_running())
+    }
+
+    pub fn toggle_timer_selected(&mut self) {
+        let Some(id) = self.selected_id() else { return };
+        let running_here = self.running_entry().is_some_and(|e| e.todo_id == id);
+        if running_here {
+            let Some(stopped) = self.store(|repo| repo.stop_timer()) else {
+                return;
+            };
+            self.reload();
+            let spent = stopped
+                .map(|e| time_entry::format_duration(e.duration(SystemTime::now())))
+                .unwrap_or_default();
+            self.set_status(&format!("Timer stopped ({spent})"));
+        } else {
+            if self.store(|repo| repo.start_timer(id)).is_none() {
+                return;
+            }
+            self.reload();
+            self.set_status("Timer started (T again to stop)");
+        }
+    }
+
+    pub fn add_todo(&mut self) {
+        // The first line holds the title and tokens; lines added with Alt-Enter are notes.
+        let (first, notes) = self
+            .input
+            .as_str()
+            .split_once('\n')
+            .unwrap_or((self.input.as_str(), ""));
+        let input = first.trim();
+        let notes = notes.trim();
+        let notes = (!notes.is_empty()).then(|| notes.to_string());
+        if input.is_empty() {
+            self.set_status("Cannot add an empty task");
+            return;
+        }
+        let parse = parse_inline_meta(input);
+        let meta = match parse {
+            Ok(v) => v,
+            Err(msg) => {
+                self.set_status(&msg);
+                return;
+            }
+        };
+        let parent = self.adding_under;
+        let start = meta.start;
+        // The input stays put when the store refuses it, so nothing typed is lost.
+        let Some(todo) = self.store(|repo| {
+            let todo = repo.add(meta.title, meta.priority, meta.due, None, None)?;
+            if start.is_some() {
+                repo.set_start(todo.id, start)?;
+            }
+            if meta.estimate.is_some() {
+                repo.set_estimate(todo.id, meta.estimate)?;
+            }
+            if notes.is_some() {
+                repo.set_notes(todo.id, notes)?;
+            }
+            if parent.is_some() {
+                repo.set_parent(todo.id, parent)?;
+            }
+            Ok(todo)
+        }) else {
+            return;
+        };
+        self.adding_under = None;
+        self.input.clear();
+        self.mode = InputMode::Normal;
+        self.focus = Some(todo.id);
+        self.flash = Some((todo.id, Instant::now()));
+        self.reload();
+        if self.hierarchy.due_after_parent(todo.id) {
+            self.set_status("Added; warning: due is later than the parent's");
+        } else if !self.show_scheduled && start.is_some_and(|s| s > SystemTime::now()) {
+            self.set_status("Added; hidden until its start date (s: show scheduled)");
+        } else if let Some(over) = self.over_capacity() {
+            self.set_status(&format!(
+                "Added; today's estimates exceed the daily capacity by {}",
+                format_minutes(over)
+            ));
+        } else if parent.is_some() {
+            self.set_status("Added subtask");
+        } else {
+            self.set_status("Added");
+        }
+        let added = self
+            .todos
+            .iter()
+            .find(|t| t.id == todo.id)
+            .cloned()
+            .unwrap_or(todo);
+        self.fire_hook(HookEvent::Add, &added);
+    }
+
+    /// `e`: rename the selected todo.
+    pub fn edit_title(&mut self) {
+        let Some(todo) = self.todos.get(self.selected) else {
+            self.set_status("No task selected");
+            return;
+        };
+        self.title_target = Some((todo.id, false));
+        self.input.set(todo.title.clone());
+        self.mode = InputMode::EditingTitle;
+        self.set_status("Edit the title and press Enter");
+    }
+
+    pub fn apply_title_edit(&mut self) {
+        let title = self.input.as_str().trim().to_string();
+        if title.is_empty() {
+            self.set_status("Input is empty");
+            return;
+        }
+        let Some((id, resolving)) = self.title_target.take() else {
+            self.mode = InputMode::Normal;
+            return;
+        };
+        let renamed = self.store(|repo| {
+            if resolving {
+                repo.resolve_title(id, title)
+            } else {
+                repo.set_title(id, title)
+            }
+        });
+        if renamed.is_none() {
+            self.title_target = Some((id, resolving));
+            return;
+        }
+        self.mode = InputMode::Normal;
+        self.input.clear();
+        self.focus = Some(id);
+        self.reload();
+        self.set_status("Title updated");
+        if resolving {
+            self.open_next_conflict();
+        }
+    }
+
+    pub fn cancel_title_edit(&mut self) {
+        self.title_target = None;
+        self.mode = InputMode::Normal;
+        self.input.clear();
+        self.set_status("Canceled");
+    }
+
+    /// Show the chooser for the next todo whose remote title clashes with a local rename.
+    /// Returns false when there is none.
+    pub fn open_next_conflict(&mut self) -> bool {
+        let Some(todos) = self.store(|repo| repo.all()) else {
+            return false;
+        };
+        let next = todos.into_iter().find_map(|t| {
+            Some(TitleConflict {
+                remote: t.incoming_title?,
+                todo_id: t.id,
+                local: t.title,
+                choice: ConflictChoice::KeepLocal,
+            })
+        });
+        self.conflict = next;
+        self.conflict.is_some()
+    }
+
+    /// `x`: open the title conflict chooser.
+    pub fn review_conflicts(&mut self) {
+        if !self.open_next_conflict() {
+            self.set_status("No title conflicts");
+        }
+    }
+
+    /// Esc in the chooser: leave the conflict for later (`x` brings it back).
+    pub fn postpone_conflict(&mut self) {
+        self.conflict = None;
+        self.set_status("Conflict kept for later (x to resolve)");
+    }
+
+    pub fn resolve_conflict(&mut self, choice: ConflictChoice) {
+        let Some(conflict) = self.conflict.take() else {
+            return;
+        };
+        let title = match choice {
+            ConflictChoice::KeepLocal => conflict.local,
+            ConflictChoice::TakeRemote => conflict.remote,
+            ConflictChoice::Merge => {
+                self.title_target = Some((conflict.todo_id, true));
+                self.input.set(conflict.local);
+                self.mode = InputMode::EditingTitle;
+                self.set_status(&format!("Remote title: {}", conflict.remote));
+                return;
+            }
+        };
+        if self
+            .store(|repo| repo.resolve_title(conflict.todo_id, title))
+            .is_none()
+        {
+            return;
+        }
+        self.reload();
+        self.set_status(&format!("Conflict resolved: {}", choice.label()));
+        self.open_next_conflict();
+    }
+
+    pub fn edit_due(&mut self) {
+        self.mode = InputMode::EditingDue;
+        self.input.clear();
+        self.set_status("Enter due (e.g. fri / next monday@9am / in 2 weeks / +3 / 2025-01-05)");
+    }
+
+    pub fn apply_due_edit(&mut self) {
+        let val = self.input.as_str().trim();
+        if val.is_empty() {
+            self.set_status("Input is empty");
+            return;
+        }
+        let Some(id) = self.selected_id() else {
+            self.set_status("No task selected");
+            return;
+        };
+        match parse_due_token(val) {
+            Ok(Some(due)) => {
+                let pri = self.todos[self.selected].priority;
+                if self
+                    .store(|repo| repo.update_meta(id, pri, Some(due)))
+                    .is_none()
+                {
+                    return;
+                }
+                self.mode = InputMode::Normal;
+                self.input.clear();
+                self.focus = Some(id);
+                self.reload();
+                if self.hierarchy.due_after_parent(id) {
+                    self.set_status("Due date updated; warning: later than the parent's");
+                } else {
+                    self.set_status("Due date updated");
+                }
+            }
+            Ok(None) => self.set_status("Could not parse due token"),
+            Err(e) => self.set_status(&e),
+        }
+    }
+
+    pub fn clear_done(&mut self) {
+        if !self.marked.is_empty() {
+            let ids = self.marked_ids();
+            let Some(archived) = self.store(|repo| repo.archive_many(&ids)) else {
+                return;
+            };
+            self.marked.clear();
+            self.reload();
+            self.set_status(&format!(
+                "Archived {archived} marked todo(s) (/ then Tab to search them)"
+            ));
+            return;
+        }
+        let Some(removed) = self.store(|repo| repo.clear_done()) else {
+            return;
+        };
+        self.reload();
+        if removed > 0 {
+            self.set_status(&format!(
+                "Archived {removed} completed (/ then Tab to search them)"
+            ));
+        } else {
+            self.set_status("No completed items");
+        }
+    }
+
+    pub fn set_status(&mut self, msg: &str) {
+        if self.status_log.len() == STATUS_LOG_CAPACITY {
+            self.status_log.pop_front();
+        }
+        self.status_log
+            .push_back((SystemTime::now(), msg.to_string()));
+        self.status = Some(msg.to_string());
+        self.status_is_error = false;
+    }
+
+    /// `set_status` for failures; the footer shows them in red.
+    pub fn set_error(&mut self, msg: &str) {
+        self.report_error(msg, msg.to_string());
+    }
+
+    /// Show `msg` in the footer and open the error popup with `details` (a cause chain,
+    /// a server's error list), unless the user is typing.
+    pub fn report_error(&mut self, msg: &str, details: String) {
+        self.set_status(msg);
+        self.status_is_error = true;
+        self.last_error = Some(ErrorReport {
+            at: SystemTime::now(),
+            message: msg.to_string(),
+            details,
+        });
+        if self.mode == InputMode::Normal && self.review.is_none() {
+            self.error_view = Some(0);
+        }
+    }
+
+    /// `E`: show / hide the last error.
+    pub fn toggle_error_view(&mut self) {
+        if self.last_error.is_none() {
+            self.set_status("No errors so far");
+            return;
+        }
+        self.error_view = match self.error_view {
+            Some(_) => None,
+            None => Some(0),
+        };
+    }
+
+    pub fn scroll_error_view(&mut self, delta: i32) {
+        let lines = self
+            .last_error
+            .as_ref()
+            .map_or(0, |e| e.details.lines().count());
+        if let Some(scroll) = self.error_view.as_mut() {
+            let max = lines.saturating_sub(1) as u16;
+            *scroll = scroll.saturating_add_signed(delta as i16).min(max);
+        }
+    }
+
+    /// `y` in the error popup: copy the message and details, e.g. for a bug report.
+    pub fn copy_error(&mut self) {
+        let Some(report) = &self.last_error else {
+            return;
+        };
+        let text = if report.details == report.message {
+            report.message.clone()
+        } else {
+            format!("{}\n\n{}", report.message, report.details)
+        };
+        match clipboard::copy(self.config.clipboard.backend, &text) {
+            Ok(backend) => self.set_status(&format!("Copied the error ({backend})")),
+            // Not `set_error`: that would replace the report being copied.
+            Err(e) => self.set_status(&format!("Copy failed: {e}")),
+        }
+    }
+
+    /// Run `op` against the store. A failure (locked or damaged file, full disk) is shown
+    /// as a status error and gives `None`; the caller leaves its state as it was.
+    fn store<T>(
+        &mut self,
+        op: impl FnOnce(&mut dyn TodoRepository) -> anyhow::Result<T>,
+    ) -> Option<T> {
+        match op(self.repo.as_mut()) {
+            Ok(value) => Some(value),
+            Err(e) if e.downcast_ref::<StoreBusy>().is_some() => {
+                self.report_error(
+                    "Store busy (another koto is writing); try again",
+                    format!("{e:?}"),
+                );
+                None
+            }
+            Err(e) => {
+                self.report_error(&format!("Storage error: {e:#}"), format!("{e:?}"));
+                None
+            }
+        }
+    }
+
+    /// `L`: show / hide the recent status messages.
+    pub fn toggle_status_log(&mut self) {
+        self.status_log_view = match self.status_log_view {
+            Some(_) => None,
+            None => Some(0),
+        };
+    }
+
+    pub fn scroll_status_log(&mut self, delta: i32) {
+        if let Some(scroll) = self.status_log_view.as_mut() {
+            let max = self.status_log.len().saturating_sub(1) as u16;
+            *scroll = scroll.saturating_add_signed(delta as i16).min(max);
+        }
+    }
+
+    /// Links of `todo`: its own, then for a synced PR the repository and every CI check
+    /// with a details page.
+    pub fn links_for(&self, todo: &Todo) -> Vec<(String, String)> {
+        let mut links = Vec::new();
+        let pr = self.pr_for(todo);
+        if let Some(url) = &todo.external_url {
+            let label = if pr.is_some() { "Pull request" } else { "Link" };
+            links.push((label.to_string(), url.clone()));
+        }
+        if let Some(pr) = pr {
+            links.push((
+                format!("Repository {}/{}", pr.owner, pr.repo),
+                pr.repo_url(),
+            ));
+            for check in &pr.ci_checks {
+                if let Some(url) = &check.url
+                    && !links.iter().any(|(_, known)| known == url)
+                {
+                    links.push((format!("Check: {}", check.name), url.clone()));
+                }
+            }
+        }
+        links
+    }
+
+    /// Enter: open the selected todo's link, or the link picker when it has several.
+    /// Returns false when there is nothing to open.
+    pub fn open_selected_link(&mut self) -> bool {
+        let Some(todo) = self.todos.get(self.selected) else {
+            return false;
+        };
+        let mut links = self.links_for(todo);
+        match links.len() {
+            0 => return false,
+            1 => {
+                let (_, url) = links.remove(0);
+                self.open_url(&url);
+                self.mark_seen(self.selected);
+            }
+            _ => self.link_picker = Some(LinkPicker { links, selected: 0 }),
+        }
+        true
+    }
+
+    pub fn close_link_picker(&mut self) {
+        self.link_picker = None;
+    }
+
+    pub fn move_link_picker(&mut self, delta: isize) {
+        if let Some(picker) = self.link_picker.as_mut() {
+            picker.selected = picker
+                .selected
+                .saturating_add_signed(delta)
+                .min(picker.links.len().saturating_sub(1));
+        }
+    }
+
+    /// Enter in the picker: open the chosen link and close it.
+    pub fn open_picked_link(&mut self) {
+        let Some(picker) = self.link_picker.take() else {
+            return;
+        };
+        if let Some((_, url)) = picker.links.get(picker.selected) {
+            self.open_url(url);
+            self.mark_seen(self.selected);
+        }
+    }
+
+    /// `Ctrl-p`: only from a view of todos.
+    pub fn open_jump(&mut self) {
+        if self.view != View::Inbox {
+            self.jump = Some(JumpFinder::default());
+        }
+    }
+
+    /// Todos the current view shows that match the jump query, best first: (index into
+    /// `todos`, matched char indices of the title). All of them, in view order, before
+    /// anything is typed.
+    pub fn jump_matches(&self) -> Vec<(usize, Vec<usize>)> {
+        let Some(jump) = &self.jump else {
+            return Vec::new();
+        };
+        let candidates = match self.view {
+            // Every column is on screen, not just the selected todo's.
+            View::Board => (0..self.todos.len()).collect(),
+            _ => self.visible_order(),
+        };
+        let mut matches: Vec<(i64, usize, Vec<usize>)> = candidates
+            .into_iter()
+            .filter_map(|idx| {
+                let (score, positions) = fuzzy::score(&jump.query, &self.todos[idx].title)?;
+                Some((score, idx, positions))
+            })
+            .collect();
+        // Stable: equal scores keep the view's order.
+        matches.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+        matches
+            .into_iter()
+            .map(|(_, idx, positions)| (idx, positions))
+            .collect()
+    }
+
+    fn jump_push(&mut self, c: char) {
+        if let Some(jump) = self.jump.as_mut() {
+            jump.query.push(c);
+            jump.selected = 0;
+        }
+    }
+
+    fn jump_pop(&mut self) {
+        if let Some(jump) = self.jump.as_mut() {
+            jump.query.pop();
+            jump.selected = 0;
+        }
+    }
+
+    fn move_jump(&mut self, delta: isize) {
+        let count = self.jump_matches().len();
+        if let Some(jump) = self.jump.as_mut() {
+            jump.selected = jump
+                .selected
+                .saturating_add_signed(delta)
+                .min(count.saturating_sub(1));
+        }
+    }
+
+    /// Enter: select the chosen todo and close the finder (stays open without a match).
+    fn confirm_jump(&mut self) {
+        let Some(selected) = self.jump.as_ref().map(|jump| jump.selected) else {
+            return;
+        };
+        if let Some((idx, _)) = self.jump_matches().into_iter().nth(selected) {
+            self.selected = idx;
+            self.jump = None;
+        }
+    }
+
+    /// `y`: copy the selected todo's link, or its title when it has none. `Y`: always the title.
+    pub fn copy_selected(&mut self, title_only: bool) {
+        let Some(todo) = self.todos.get(self.selected) else {
+            return;
+        };
+        let (what, text) = match todo.external_url.as_deref() {
+            Some(url) if !title_only => ("link", url.to_string()),
+            _ => ("title", todo.title.clone()),
+        };
+        match clipboard::copy(self.config.clipboard.backend, &text) {
+            Ok(backend) => self.set_status(&format!("Copied {what} ({backend})")),
+            Err(e) => self.set_error(&format!("Copy failed: {e}")),
+        }
+    }
+
+    fn open_url(&mut self, url: &str) {
+        let result = match self.config.open.command_for(url) {
+            // Detach from the terminal so the launcher cannot draw over the TUI.
+            Some(argv) => std::process::Command::new(&argv[0])
+                .args(&argv[1..])
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .map(|_| ()),
+            None => open::that(url),
+        };
+        match result {
+            Ok(_) => self.set_status("Opened link"),
+            Err(e) => self.set_error(&format!("Failed to open link: {e}")),
+        }
+    }
+
+    /// `g`: sync every source that is switched on in the sync menu.
+    pub fn start_sync_enabled(&mut self) {
+        let sources: Vec<SyncSource> = SyncSource::ALL
+            .into_iter()
+            .filter(|s| self.source_enabled(*s))
+            .collect();
+        if sources.is_empty() {
+            self.set_status("Every sync source is switched off (S to choose)");
+            return;
+        }
+        for source in sources {
+            self.start_sync(source);
+        }
+    }
+
+    /// Sync one source on its own worker; other sources keep running independently.
+    pub fn start_sync(&mut self, source: SyncSource) {
+        if !self.source_configured(source) {
+            self.set_status(&format!("{} sync not configured", source.label()));
+            return;
+        }
+        if source == SyncSource::Github
+            && let Some(daemon) = &mut self.daemon
+        {
+            if daemon.request_sync() {
+                self.set_status("Asked koto daemon to sync GitHub");
+                return;
+            }
+            self.daemon = None;
+            self.daemon_syncing = false;
+            if self.github.is_empty() {
+                self.set_status("koto daemon stopped; GitHub sync not configured");
+                return;
+            }
+        }
+        if self.sync_workers.contains_key(&source) {
+            self.set_status(&format!("{} sync already in progress", source.label()));
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        self.sync_workers.insert(source, rx);
+        self.set_status(&format!(
+            "Syncing {}... (press g again to ignore)",
+            source.label()
+        ));
+
+        match source {
+            SyncSource::Github => {
+                let mut accounts = self.github.clone();
+                if self
+                    .store(|repo| plan_sync(&mut accounts, repo, false))
+                    .is_none()
+                {
+                    return;
+                }
+                self.worker.submit(SyncJob::Github {
+                    accounts,
+                    started_at: crate::now_unix(),
+                    events: tx,
+                });
+            }
+            SyncSource::Jira => {
+                if let Some(cfg) = self.jira.clone() {
+                    self.worker.submit(SyncJob::Jira { cfg, events: tx });
+                }
+            }
+            SyncSource::Caldav => {
+                let Some(cfg) = self.caldav.clone() else {
+                    return;
+                };
+                if let Some((todos, state)) = self.store(|repo| caldav::snapshot(repo)) {
+                    self.worker.submit(SyncJob::Caldav {
+                        cfg,
+                        todos,
+                        state,
+                        events: tx,
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn is_syncing(&self) -> bool {
+        !self.sync_workers.is_empty() || self.daemon_syncing
+    }
+
+    /// Sources with a worker still running, for the header.
+    pub fn syncing_sources(&self) -> Vec<SyncSource> {
+        SyncSource::ALL
+            .into_iter()
+            .filter(|s| {
+                self.sync_workers.contains_key(s)
+                    || (*s == SyncSource::Github && self.daemon_syncing)
+            })
+            .collect()
+    }
+
+    pub fn source_configured(&self, source: SyncSource) -> bool {
+        match source {
+            SyncSource::Github => !self.github.is_empty() || self.daemon.is_some(),
+            SyncSource::Jira => self.jira.is_some(),
+            SyncSource::Caldav => self.caldav.is_some(),
+        }
+    }
+
+    /// Whether `g` includes `source` (toggled with Space in the sync menu, default on).
+    pub fn source_enabled(&self, source: SyncSource) -> bool {
+        // An unreadable setting counts as the default; writing it reports the error.
+        self.source_configured(source)
+            && self
+                .repo
+                .meta(&source.enabled_meta_key())
+                .ok()
+                .flatten()
+                .as_deref()
+                != Some("0")
+    }
+
+    /// Unix time of the last successful sync of `source`.
+    pub fn source_synced_at(&self, source: SyncSource) -> Option<i64> {
+        self.repo
+            .meta(source.synced_at_meta_key())
+            .ok()
+            .flatten()?
+            .parse()
+            .ok()
+    }
+
+    /// `S`: choose which sources to sync now.
+    pub fn open_sync_menu(&mut self) {
+        self.sync_menu = Some(0);
+    }
+
+    pub fn close_sync_menu(&mut self) {
+        self.sync_menu = None;
+    }
+
+    /// Row 0 is "all enabled sources", then one row per source.
+    pub fn move_sync_menu(&mut self, delta: isize) {
+        if let Some(row) = self.sync_menu.as_mut() {
+            *row = row.saturating_add_signed(delta).min(SyncSource::ALL.len());
+        }
+    }
+
+    fn sync_menu_source(&self) -> Option<SyncSource> {
+        let row = self.sync_menu?;
+        SyncSource::ALL.get(row.checked_sub(1)?).copied()
+    }
+
+    /// Space: include / exclude the selected source from `g`.
+    pub fn toggle_sync_source(&mut self) {
+        let Some(source) = self.sync_menu_source() else {
+            return;
+        };
+        if !self.source_configured(source) {
+            self.set_status(&format!("{} sync not configured", source.label()));
+            return;
+        }
+        let enabled = !self.source_enabled(source);
+        let key = source.enabled_meta_key();
+        if self
+            .store(|repo| repo.set_meta(&key, if enabled { "1" } else { "0" }))
+            .is_none()
+        {
+            return;
+        }
+        let state = if enabled { "on" } else { "off" };
+        self.set_status(&format!("{} sync for g: {state}", source.label()));
+    }
+
+    /// Enter: sync the selected row now.
+    pub fn run_sync_menu(&mut self) {
+        let source = self.sync_menu_source();
+        self.sync_menu = None;
+        match source {
+            Some(source) => self.start_sync(source),
+            None => self.start_sync_enabled(),
+        }
+    }
+
+    /// Drain progress and results of running syncs. Returns true when anything arrived.
+    /// Pick up syncs the daemon finished: its results are already in the store.
+    pub fn poll_daemon(&mut self) -> bool {
+        let Some(daemon) = &self.daemon else {
+            return false;
+        };
+        let events = daemon.poll();
+        let changed = !events.is_empty();
+        for event in events {
+            match event {
+                daemon::Event::Syncing => self.daemon_syncing = true,
+                daemon::Event::Synced {
+                    added,
+                    prs,
+                    partial,
+                } => {
+                    self.daemon_syncing = false;
+                    self.load_cached_prs();
+                    self.reload();
+                    self.set_status(&format!(
+                        "koto daemon synced GitHub{}: {prs} PRs, {added} tasks added",
+                        if partial { " partially" } else { "" }
+                    ));
+                }
+                daemon::Event::Failed { message } => {
+                    self.daemon_syncing = false;
+                    self.report_error("koto daemon: GitHub sync failed", message);
+                }
+            }
+        }
+        changed
+    }
+
+    pub fn poll_sync(&mut self) -> bool {
+        let mut changed = false;
+        let running: Vec<SyncSource> = self.sync_workers.keys().copied().collect();
+        for source in running {
+            let received = loop {
+                match self.sync_workers[&source].try_recv() {
+                    Ok(SyncEvent::Progress(p)) => {
+                        self.sync_progress.insert(source, p);
+                        changed = true;
+                    }
+                    Ok(event) => break Some(Ok(event)),
+                    Err(mpsc::TryRecvError::Empty) => break None,
+                    Err(mpsc::TryRecvError::Disconnected) => break Some(Err(())),
+                }
+            };
+            let Some(received) = received else {
+                continue;
+            };
+            changed = true;
+            self.sync_workers.remove(&source);
+            self.sync_progress.remove(&source);
+            match (source, received) {
+                (_, Ok(SyncEvent::Done(outcome))) => self.finish_github_sync(outcome),
+                (_, Ok(SyncEvent::Jira(result))) => self.finish_jira_sync(result),
+                (_, Ok(SyncEvent::Caldav(result))) => self.finish_caldav_sync(result),
+                (_, Ok(SyncEvent::Progress(_))) => {}
+                (_, Err(())) => self.set_status(&format!("{} sync channel closed", source.label())),
+            }
+        }
+        changed
+    }
+
+    fn finish_github_sync(&mut self, outcome: SyncOutcome) {
+        match outcome.result {
+            Ok(attention) => {
+                let settings = self.config.github.clone();
+                // A partial sync must not move the watermark past PRs a failed query missed.
+                let hosts: Vec<String> = if attention.is_partial() {
+                    Vec::new()
+                } else {
+                    self.github.iter().map(|cfg| cfg.host()).collect()
+                };
+                let Some(summary) = self.store(|repo| {
+                    let summary = sync::apply_attention(repo, &settings, &attention)?;
+                    sync::record_watermarks(repo, &hosts, outcome.started_at)?;
+                    Ok(summary)
+                }) else {
+                    return;
+                };
+                self.github_login = Some(attention.viewer_login.clone());
+                self.load_cached_prs();
+                self.reload();
+                let mut extra = String::new();
+                if summary.follow_ups > 0 {
+                    extra += &format!(", {} merge follow-ups", summary.follow_ups);
+                }
+                if summary.own_prs > 0 {
+                    extra += &format!(", {} own PRs need action", summary.own_prs);
+                }
+                if !attention.searches.is_empty() {
+                    extra += &format!(
+                        ", searches: {} new, {} no longer matching",
+                        summary.search_added, summary.unmatched
+                    );
+                }
+                if summary.watched > 0 {
+                    extra += &format!(", {} watched", summary.watched);
+                }
+                if summary.watches_finished > 0 {
+                    extra += &format!(", {} watches finished", summary.watches_finished);
+                }
+                let failures: Vec<&str> = attention
+                    .failed_queries
+                    .iter()
+                    .map(String::as_str)
+                    .chain(
+                        attention
+                            .searches
+                            .iter()
+                            .filter_map(|s| s.hits.as_ref().err())
+                            .map(String::as_str),
+                    )
+                    .collect();
+                if !failures.is_empty() {
+                    self.set_status(&format!(
+                        "Synced GitHub partially ({} tasks added{extra}): {}",
+                        summary.added,
+                        failures.join("; ")
+                    ));
+                } else {
+                    self.set_status(&format!(
+                        "Synced GitHub as {}: {} tasks added{extra}",
+                        attention.viewer_login, summary.added
+                    ));
+                }
+                self.open_next_conflict();
+                self.fire_hook(
+                    HookEvent::SyncComplete,
+                    &SyncCompleted {
+                        source: "github",
+                        added: summary.added
+                            + summary.follow_ups
+                            + summary.own_prs
+                            + summary.search_added
+                            + summary.watched,
+                        closed: summary.unmatched + summary.watches_finished,
+                    },
+                );
+            }
+            Err(e) => {
+                // Failed queries and GraphQL errors are joined with "; ": one per line.
+                let mut details = e.message.replace("; ", "\n");
+                if let Some(hint) = e.kind.hint() {
+                    details += &format!("\n\nHint: {hint}");
+                }
+                self.report_error(
+                    &format!("GitHub sync failed ({}): {}", e.kind.label(), e.message),
+                    details,
+                );
+            }
+        }
+    }
+
+    fn finish_jira_sync(&mut self, result: Result<JiraIssues, String>) {
+        match result {
+            Ok(fetched) => {
+                let Some(summary) = self.store(|repo| sync::apply_jira(repo, &fetched)) else {
+                    return;
+                };
+                self.reload();
+                self.set_status(&format!(
+                    "Synced Jira: {} issues, {} tasks added, {} closed",
+                    fetched.issues.len(),
+                    summary.added,
+                    summary.closed
+                ));
+                self.fire_hook(
+                    HookEvent::SyncComplete,
+                    &SyncCompleted {
+                        source: "jira",
+                        added: summary.added,
+                        closed: summary.closed,
+                    },
+                );
+            }
+            Err(e) => self.set_error(&format!("Jira sync failed: {e}")),
+        }
+    }
+
+    fn finish_caldav_sync(&mut self, result: Result<CaldavOutcome, String>) {
+        match result {
+            Ok(outcome) => {
+                let Some(summary) = self.store(|repo| caldav::apply(repo, &outcome)) else {
+                    return;
+                };
+                self.reload();
+                let mut msg = format!(
+                    "Synced CalDAV: {} tasks, {} added, {} updated, {} pushed",
+                    outcome.tasks, summary.added, summary.updated, summary.pushed
+                );
+                if summary.conflicts > 0 {
+                    msg.push_str(&format!(", {} conflicts (server kept)", summary.conflicts));
+                }
+                self.set_status(&msg);
+                self.fire_hook(
+                    HookEvent::SyncComplete,
+                    &SyncCompleted {
+                        source: "caldav",
+                        added: summary.added,
+                        closed: summary.archived,
+                    },
+                );
+            }
+            Err(e) => self.set_error(&format!("CalDAV sync failed: {e}")),
+        }
+    }
+
+    /// The todo's PR changed since it was last looked at (● in the list).
+    pub fn is_unread(&self, todo: &Todo) -> bool {
+        self.pr_for(todo)
+            .is_some_and(|pr| attention::is_unread(todo, pr))
+    }
+
+    /// `u`: clear the updated badge of the selected todo.
+    pub fn mark_seen_selected(&mut self) {
+        let Some(todo) = self.todos.get(self.selected) else {
+            return;
+        };
+        if !self.is_unread(todo) {
+            self.set_status("Nothing new on this todo");
+            return;
+        }
+        if self.mark_seen(self.selected) {
+            self.set_status("Marked as seen");
+        }
+    }
+
+    /// Remember the PR of `todos[idx]` as looked at, at its current head commit.
+    fn mark_seen(&mut self, idx: usize) -> bool {
+        let Some(todo) = self.todos.get(idx) else {
+            return false;
+        };
+        let Some(sha) = self.pr_for(todo).map(|pr| pr.last_commit_sha.clone()) else {
+            return false;
+        };
+        let id = todo.id;
+        let Some(Some(seen)) = self.store(|repo| repo.mark_seen(id, sha, SystemTime::now())) else {
+            return false;
+        };
+        self.todos[idx] = seen;
+        true
+    }
+
+    /// Attention score of the todo's PR; 0 for todos without a synced PR.
+    pub fn attention_score(&self, todo: &Todo) -> u32 {
+        self.pr_for(todo)
+            .map_or(0, |pr| attention::score(pr, crate::now_unix()))
+    }
+
+    fn sort_todos(&mut self) {
+        let hierarchy = &self.hierarchy;
+        let manual = self.sort == SortMode::Manual;
+        let by_priority = self.sort == SortMode::Priority;
+        let scores: HashMap<TodoId, u32> = if self.sort == SortMode::Attention {
+            self.todos
+                .iter()
+                .map(|t| (t.id, self.attention_score(t)))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        let sort = |a: &Todo, b: &Todo| {
+            // done items go last
+            if a.is_closed() != b.is_closed() {
+                return a.is_closed().cmp(&b.is_closed());
+            }
+            if manual {
+                return (a.sort_order.is_none(), a.sort_order, a.created_at).cmp(&(
+                    b.sort_order.is_none(),
+                    b.sort_order,
+                    b.created_at,
+                ));
+            }
+            let (a_score, b_score) = (scores.get(&a.id), scores.get(&b.id));
+            if a_score != b_score {
+                return b_score.cmp(&a_score);
+            }
+            if by_priority && a.priority != b.priority {
+                return a.priority.cmp(&b.priority);
+            }
+            // earliest due first (inherited dates count); None goes last
+            let a_due = hierarchy.effective_due(a.id).map(|d| d.at);
+            let b_due = hierarchy.effective_due(b.id).map(|d| d.at);
+            match (&a_due, &b_due) {
+                (Some(ad), Some(bd)) => {
+                    if ad != bd {
+                        return ad.cmp(bd);
+                    }
+                }
+                (Some(_), None) => return std::cmp::Ordering::Less,
+                (None, Some(_)) => return std::cmp::Ordering::Greater,
+                (None, None) => {}
+            }
+            // priority high(1) < med(2) < low(3)
+            if a.priority != b.priority {
+                return a.priority.cmp(&b.priority);
+            }
+            a.created_at.cmp(&b.created_at)
+        };
+        // Search results keep the store's ranking, best match first.
+        if self.search.is_none() {
+            self.todos.sort_by(sort);
+        }
+        self.todos = subtask::nest(std::mem::take(&mut self.todos), hierarchy);
+    }
+}
+
+/// Move `due` by whole days, keeping its time of day (date-only dues stay date-only).
+fn shift_days(due: Due, days: i64) -> Due {
+    let odt = clock::to_local(due.at);
+    let shifted = odt.date().saturating_add(time::Duration::days(days));
+    if due.has_time {
+        Due::timed(clock::at_local(shifted, odt.time()))
+    } else {
+        Due::day(shifted)
+    }
+}
+
+/// `[` / `]`: move a due date by `days`; an undated todo starts from today.
+fn shift_due(due: Option<Due>, days: i64) -> Due {
+    match due {
+        Some(due) => shift_days(due, days),
+        None => Due::day(clock::today().saturating_add(time::Duration::days(days.max(0)))),
+    }
+}
+
+fn count_open_by_due_date(todos: &[Todo], hierarchy: &Hierarchy) -> HashMap<Date, usize> {
+    let mut counts = HashMap::new();
+    for due in todos
+        .iter()
+        .filter(|t| !t.is_closed())
+        .filter_map(|t| hierarchy.effective_due(t.id))
+    {
+        *counts.entry(clock::to_local(due.at).date()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Estimated minutes of the open todos due on or before `today`.
+fn planned_minutes(todos: &[Todo], hierarchy: &Hierarchy, today: Date) -> u32 {
+    todos
+        .iter()
+        .filter(|t| !t.is_closed())
+        .filter(|t| {
+            hierarchy
+                .effective_due(t.id)
+                .is_some_and(|due| clock::to_local(due.at).date() <= today)
+        })
+        .filter_map(|t| t.estimate_minutes)
+        .sum()
+}
+
+/// `90` as `1h30m`, the way estimates are typed.
+pub fn format_minutes(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{m}m"),
+        (h, 0) => format!("{h}h"),
+        (h, m) => format!("{h}h{m:02}m"),
+    }
+}
+
+/// Same day-of-month in the previous/next month, clamped to that month's length.
+fn shift_month(date: Date, forward: bool) -> Date {
+    let (year, month) = if forward {
+        match date.month() {
+            time::Month::December => (date.year() + 1, time::Month::January),
+            m => (date.year(), m.next()),
+        }
+    } else {
+        match date.month() {
+            time::Month::January => (date.year() - 1, time::Month::December),
+            m => (date.year(), m.previous()),
+        }
+    };
+    let day = date.day().min(month.length(year));
+    Date::from_calendar_date(year, month, day).unwrap_or(date)
+}
@@ -0,0 +1,126 @@
+//! `koto add --popup`: a one-line prompt meant for a global hotkey (a terminal window
+//! opened on `koto add --popup`). It writes to the store and exits, so it also works
+//! while a TUI is open on the same store; the TUI shows the todo on its next reload.
+
+use std::io::Write;
+
+use anyhow::Result;
+use crossterm::{
+    cursor::{MoveToColumn, MoveUp},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    queue,
+    style::Print,
+    terminal::{self, Clear, ClearType},
+};
+
+use crate::app::{InlineMeta, parse_inline_meta};
+
+const PROMPT: &str = "koto add › ";
+
+/// What a key did to the line being typed.
+#[derive(Debug, PartialEq, Eq)]
+enum Edit {
+    Typing,
+    Submit,
+    Cancel,
+}
+
+fn edit(line: &mut String, key: KeyEvent) -> Edit {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Enter => return Edit::Submit,
+        KeyCode::Esc => return Edit::Cancel,
+        KeyCode::Char('c' | 'd') if ctrl => return Edit::Cancel,
+        KeyCode::Char('u') if ctrl => line.clear(),
+        KeyCode::Char('w') if ctrl => {
+            let kept = line.trim_end().rfind(' ').map_or(0, |at| at + 1);
+            line.truncate(kept);
+        }
+        KeyCode::Char(c) if !ctrl => line.push(c),
+        KeyCode::Backspace => {
+            line.pop();
+        }
+        _ => {}
+    }
+    Edit::Typing
+}
+
+/// Ask for a todo until one parses; `None` when cancelled (`Esc`, `Ctrl-C`) or left
+/// empty. Token errors (`d:someday`) are shown under the prompt and the line is kept.
+pub fn prompt() -> Result<Option<InlineMeta>> {
+    let mut out = std::io::stderr();
+    terminal::enable_raw_mode()?;
+    let mut line = String::new();
+    let mut error: Option<String> = None;
+    let result = loop {
+        queue!(
+            out,
+            MoveToColumn(0),
+            Clear(ClearType::FromCursorDown),
+            Print(PROMPT),
+            Print(&line),
+        )?;
+        if let Some(msg) = &error {
+            queue!(
+                out,
+                Print("\r\n"),
+                Print(format!("  {msg}")),
+                MoveUp(1),
+                MoveToColumn((PROMPT.chars().count() + line.chars().count()) as u16),
+            )?;
+        }
+        out.flush()?;
+        let key = match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => key,
+            Ok(_) => continue,
+            Err(e) => break Err(e.into()),
+        };
+        match edit(&mut line, key) {
+            Edit::Typing => {}
+            Edit::Cancel => break Ok(None),
+            Edit::Submit if line.trim().is_empty() => break Ok(None),
+            Edit::Submit => match parse_inline_meta(line.trim()) {
+                Ok(meta) => break Ok(Some(meta)),
+                Err(msg) => error = Some(msg),
+            },
+        }
+    };
+    queue!(out, MoveToColumn(0), Clear(ClearType::FromCursorDown))?;
+    out.flush()?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(line: &mut String, code: KeyCode, modifiers: KeyModifiers) -> Edit {
+        edit(line, KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn the_prompt_edits_one_line() {
+        let mut line = String::new();
+        for c in "Call the bank p1".chars() {
+            assert_eq!(
+                press(&mut line, KeyCode::Char(c), KeyModifiers::NONE),
+                Edit::Typing
+            );
+        }
+        press(&mut line, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(line, "Call the bank ");
+        press(&mut line, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(line, "Call the bank");
+        assert_eq!(
+            press(&mut line, KeyCode::Enter, KeyModifiers::NONE),
+            Edit::Submit
+        );
+        press(&mut line, KeyCode::Char('u'), KeyModifiers::CONTROL);
+        assert_eq!(line, "");
+        assert_eq!(
+            press(&mut line, KeyCode::Char('c'), KeyModifiers::CONTROL),
+            Edit::Cancel
+        );
+    }
+}
@@ -0,0 +1,1000 @@
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand, ValueEnum};
+use serde::Serialize;
+use time::macros::format_description;
+
+use crate::app::{InlineMeta, parse_inline_meta};
+use crate::config::{self, Config};
+use crate::daemon::{self, DaemonOptions};
+use crate::domain::activity::ActivityKind;
+use crate::domain::clock;
+use crate::domain::query::{SearchScope, TodoQuery};
+use crate::domain::todo::Todo;
+use crate::hooks::{self, HookEvent, SyncCompleted};
+use crate::repo::TodoRepository;
+use crate::repo::archive::{Archive, ArchiveReason};
+use crate::repo::backup::Backups;
+use crate::repo::github::model::{AttentionPrs, Pr};
+use crate::repo::lock::{LockHolder, StoreLock};
+use crate::repo::sqlite::SqliteTodoRepo;
+use crate::usecase::sync::{self, META_PRS_SYNCED_AT};
+use crate::usecase::{caldav, ics, report, widget};
+
+mod capture;
+mod serve;
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Print summaries from the local store; without a subcommand, a weekly review
+    /// in markdown (completed, reviewed PRs, slipped due dates)
+    #[command(args_conflicts_with_subcommands = true)]
+    Report {
+        #[command(subcommand)]
+        kind: Option<ReportKind>,
+        /// Period of the weekly review: `7d`, `2w` or a date (`2025-01-06`)
+        #[arg(long, default_value = "7d")]
+        since: String,
+    },
+    /// Add a todo; the title takes the add prompt's tokens (`p1`, `d:fri`, `s:+5`, `e:30`)
+    Add {
+        #[arg(required_unless_present = "popup", conflicts_with = "popup")]
+        title: Vec<String>,
+        /// Ask on a one-line prompt and exit, for binding to a global hotkey
+        #[arg(long, default_value_t = false)]
+        popup: bool,
+    },
+    /// Export todos with due dates for other tools
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Ics)]
+        format: ExportFormat,
+        /// Emit calendar events (VEVENT) instead of tasks (VTODO)
+        #[arg(long, default_value_t = false)]
+        events: bool,
+        /// Write to a file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Summary of open and due todos for menu-bar / desktop widgets
+    Widget {
+        #[arg(long, value_enum, default_value_t = WidgetFormat::Json)]
+        format: WidgetFormat,
+    },
+    /// One line for status bars (tmux, starship, polybar): todos due today, pending PR
+    /// reviews and failing CI on your PRs, from the local store without network calls
+    Status,
+    /// Fetch GitHub PRs that need attention and `[jira]` issues, and sync the `[caldav]`
+    /// list (same as `g` in the TUI)
+    Sync(SyncArgs),
+    /// Pin a PR (`owner/repo#123` or its URL) into every sync until it is merged or
+    /// closed, review request or not; without one, list the watched PRs
+    Watch {
+        pr: Option<String>,
+        /// Stop watching the PR instead
+        #[arg(long, default_value_t = false)]
+        remove: bool,
+    },
+    /// Share settings between machines
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Full-text search over titles and notes, best match first
+    Search {
+        query: String,
+        /// Which todos to search
+        #[arg(long, value_enum, default_value_t = ScopeArg::All)]
+        scope: ScopeArg,
+    },
+    /// Activity log: every add, edit, completion, archive, delete and sync, oldest first
+    Log {
+        /// Show this many of the newest entries
+        #[arg(long, short = 'n', default_value_t = 50)]
+        limit: usize,
+        /// One JSON object per line instead of text
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    /// History of deleted and purged todos (`<db>.archive.jsonl.gz` next to the store)
+    Archive {
+        #[command(subcommand)]
+        action: ArchiveAction,
+    },
+    /// Snapshot the SQLite store (safe while koto is running)
+    Backup {
+        /// Destination file (default: `backups/` next to the store, timestamped)
+        path: Option<PathBuf>,
+    },
+    /// Replace the store with a backup; the current store is backed up first
+    Restore {
+        path: PathBuf,
+        /// Do not ask for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Sync GitHub in the background, notify about new review requests and let TUIs on
+    /// the same store attach (`<db>.sock`) instead of syncing themselves
+    Daemon {
+        /// Minutes between syncs
+        #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+        /// No desktop notifications
+        #[arg(long, default_value_t = false)]
+        no_notify: bool,
+    },
+    /// Serve a JSON API on localhost for scripts and browser extensions (see README,
+    /// "HTTP API")
+    Serve {
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ArchiveAction {
+    /// Print archived todos whose title, notes or link contain the pattern (case-insensitive)
+    Grep { pattern: String },
+    /// Remove todos archived with `c` from the store, after appending them to the archive file
+    Purge {
+        /// Only purge todos created more than this many days ago
+        #[arg(long)]
+        older_than: Option<u64>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Write the shareable settings ([github] triage) as a profile (no tokens or commands)
+    Export {
+        /// Write to a file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Apply a profile over the current settings (the old file is kept as .bak)
+    Import { file: PathBuf },
+    /// Print where the config file is read from
+    Path,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ScopeArg {
+    /// Not done and not archived
+    Open,
+    All,
+    Archived,
+}
+
+impl From<ScopeArg> for SearchScope {
+    fn from(scope: ScopeArg) -> Self {
+        match scope {
+            ScopeArg::Open => SearchScope::Open,
+            ScopeArg::All => SearchScope::All,
+            ScopeArg::Archived => SearchScope::Archived,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ExportFormat {
+    /// iCalendar (.ics)
+    Ics,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum WidgetFormat {
+    /// Versioned JSON (see README, "Widget output")
+    Json,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ReportKind {
+    /// Tracked time per todo
+    Time {
+        /// Only count time tracked since Monday of the current week (local time)
+        #[arg(long, default_value_t = false)]
+        week: bool,
+    },
+    /// Pending review requests from the last GitHub sync
+    Reviews {
+        #[arg(long, value_enum, default_value_t = ReviewsFormat::Md)]
+        format: ReviewsFormat,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ReviewsFormat {
+    /// Markdown table, e.g. for a standup doc
+    Md,
+}
+
+#[derive(Args, Debug, Default)]
+pub struct SyncArgs {
+    /// Print per-query cost (pages, nodes, rate-limit points, time) with tuning hints
+    #[arg(long, default_value_t = false)]
+    explain: bool,
+    /// Override `[github] days` for this run
+    #[arg(long)]
+    days: Option<u64>,
+    /// Override `[github] page_size` for this run
+    #[arg(long)]
+    page_size: Option<i32>,
+    /// Include review requests made to your teams (`[github] include_team_requests`)
+    #[arg(long, default_value_t = false)]
+    team: bool,
+    /// Add no todos for draft PRs (`[github] ignore_drafts`)
+    #[arg(long, default_value_t = false)]
+    ignore_drafts: bool,
+    /// Fetch the whole `days` window instead of only PRs updated since the last sync
+    #[arg(long, default_value_t = false)]
+    full_sync: bool,
+    /// Print the fetched PRs and the open GitHub / Jira todos as JSON instead of a summary
+    #[arg(long, default_value_t = false, conflicts_with = "explain")]
+    json: bool,
+}
+
+impl Command {
+    /// Backups and restores work on the store file itself, without opening the store.
+    pub fn works_on_file(&self) -> bool {
+        matches!(self, Command::Backup { .. } | Command::Restore { .. })
+    }
+}
+
+/// Run a command that `works_on_file`: opening the store first would migrate it (and ask
+/// an encrypted one's passphrase) before it is copied or replaced.
+pub fn run_on_file(command: Command, store_path: Option<&Path>) -> Result<()> {
+    match command {
+        Command::Backup { path } => {
+            let Some(store_path) = store_path else {
+                anyhow::bail!("only a SQLite store can be backed up; drop --memory / --demo");
+            };
+            let backups = Backups::for_db(store_path);
+            let dest = path.unwrap_or_else(|| backups.new_path("manual"));
+            backups.snapshot(&dest)?;
+            println!("Backed up {} to {}", store_path.display(), dest.display());
+            Ok(())
+        }
+        Command::Restore { path, yes } => {
+            let Some(store_path) = store_path else {
+                anyhow::bail!("only a SQLite store can be restored; drop --memory / --demo");
+            };
+            restore(store_path, &path, yes)
+        }
+        _ => anyhow::bail!("this command needs an open store"),
+    }
+}
+
+pub fn run(
+    command: Command,
+    mut repo: Box<dyn TodoRepository>,
+    config: &Config,
+    config_path: Option<&Path>,
+    github_api_url: Option<&str>,
+    store_path: Option<&Path>,
+) -> Result<()> {
+    match command {
+        Command::Report { kind, since } => match kind {
+            None => report_weekly(repo.as_ref(), &since),
+            Some(ReportKind::Time { week }) => report_time(repo.as_ref(), week),
+            Some(ReportKind::Reviews { format }) => report_reviews(repo.as_ref(), format),
+        },
+        Command::Add { title, popup } => add(repo.as_mut(), config, title, popup),
+        Command::Export {
+            format,
+            events,
+            output,
+        } => export(repo.as_ref(), format, events, output),
+        Command::Widget { format } => widget(repo.as_ref(), format),
+        Command::Status => status(repo.as_ref()),
+        Command::Search { query, scope } => search(repo.as_ref(), query, scope),
+        Command::Log { limit, json } => activity_log(repo.as_ref(), limit, json),
+        Command::Config { action } => config_command(action, config_path),
+        Command::Sync(args) => sync(repo.as_mut(), config, github_api_url, args),
+        Command::Watch { pr, remove } => watch(repo.as_mut(), pr, remove),
+        Command::Archive { action } => {
+            let Some(store_path) = store_path else {
+                anyhow::bail!("the archive belongs to a SQLite store; drop --memory / --demo");
+            };
+            archive_command(repo.as_mut(), store_path, action)
+        }
+        Command::Backup { .. } | Command::Restore { .. } => {
+            drop(repo);
+            run_on_file(command, store_path)
+        }
+        Command::Daemon {
+            interval,
+            no_notify,
+        } => {
+            let Some(store_path) = store_path else {
+                anyhow::bail!("the daemon serves a SQLite store; drop --memory / --demo");
+            };
+            let accounts = crate::build_github_configs(&config.github, github_api_url)?;
+            if accounts.is_empty() {
+                anyhow::bail!("GitHub token is required (env GITHUB_TOKEN, or `gh auth login`)");
+            }
+            let opts = DaemonOptions {
+                interval: Duration::from_secs(interval * 60),
+                notify: !no_notify,
+            };
+            daemon::run(repo.as_mut(), config, accounts, store_path, &opts)
+        }
+        Command::Serve { port } => {
+            let _lock = match store_path {
+                Some(path) => Some(StoreLock::acquire(path, LockHolder::Server)?),
+                None => None,
+            };
+            serve::run(repo.as_mut(), config, github_api_url, port)
+        }
+    }
+}
+
+fn sync(
+    repo: &mut dyn TodoRepository,
+    config: &Config,
+    github_api_url: Option<&str>,
+    args: SyncArgs,
+) -> Result<()> {
+    let report = sync_report(repo, config, github_api_url, &args)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
+
+/// Sync every configured provider; the summaries are printed unless `args.json` is set.
+fn sync_report(
+    repo: &mut dyn TodoRepository,
+    config: &Config,
+    github_api_url: Option<&str>,
+    args: &SyncArgs,
+) -> Result<SyncReport> {
+    let accounts = crate::build_github_configs(&config.github, github_api_url)?;
+    let jira = crate::build_jira_config(&config.jira)?;
+    let caldav_cfg = crate::build_caldav_config(&config.caldav)?;
+    if accounts.is_empty() && jira.is_none() && caldav_cfg.is_none() {
+        anyhow::bail!(
+            "GitHub token is required (env GITHUB_TOKEN, or `gh auth login`), or set up [jira] or [caldav]"
+        );
+    }
+    let mut report = SyncReport::default();
+    if !accounts.is_empty() {
+        let (attention, summary) = sync_github(repo, config, accounts, args)?;
+        report.github = Some(GithubSyncReport {
+            viewer_login: attention.viewer_login,
+            incremental: attention.incremental,
+            failed_queries: attention.failed_queries,
+            summary,
+            prs: attention.prs,
+        });
+    }
+    if let Some(jira) = jira {
+        let fetched = crate::worker::block_on(crate::repo::jira::fetch_assigned(&jira))??;
+        let summary = sync::apply_jira(repo, &fetched)?;
+        run_sync_hook(config, "jira", summary.added, summary.closed)?;
+        if !args.json {
+            println!(
+                "Synced Jira: {} issues{}, {} tasks added, {} closed",
+                fetched.issues.len(),
+                if fetched.complete {
+                    ""
+                } else {
+                    " (max_results reached)"
+                },
+                summary.added,
+                summary.closed
+            );
+        }
+        report.jira = Some(JiraSyncReport {
+            issues: fetched.issues.len(),
+            complete: fetched.complete,
+            summary,
+        });
+    }
+    if let Some(cfg) = caldav_cfg {
+        let (todos, state) = caldav::snapshot(repo)?;
+        let outcome = crate::worker::block_on(caldav::exchange(&cfg, todos, state))??;
+        let summary = caldav::apply(repo, &outcome)?;
+        run_sync_hook(config, "caldav", summary.added, summary.archived)?;
+        if !args.json {
+            println!(
+                "Synced CalDAV: {} tasks, {} added, {} updated, {} pushed{}",
+                outcome.tasks,
+                summary.added,
+                summary.updated,
+                summary.pushed,
+                match summary.conflicts {
+                    0 => String::new(),
+                    n => format!(", {n} conflicts (server kept)"),
+                }
+            );
+        }
+        report.caldav = Some(CaldavSyncReport {
+            tasks: outcome.tasks,
+            summary,
+        });
+    }
+    report.todos = repo
+        .all()?
+        .into_iter()
+        .filter(|t| !t.is_closed() && t.external_key.is_some())
+        .collect();
+    Ok(report)
+}
+
+/// `koto sync --json`: what the sync fetched and the provider todos open afterwards.
+#[derive(Debug, Default, Serialize)]
+struct SyncReport {
+    github: Option<GithubSyncReport>,
+    jira: Option<JiraSyncReport>,
+    caldav: Option<CaldavSyncReport>,
+    /// Open todos that came from GitHub, Jira or the CalDAV list.
+    todos: Vec<Todo>,
+}
+
+#[derive(Debug, Serialize)]
+struct GithubSyncReport {
+    viewer_login: String,
+    incremental: bool,
+    /// Queries that failed; the PRs they would have returned are missing.
+    failed_queries: Vec<String>,
+    summary: sync::SyncSummary,
+    prs: Vec<Pr>,
+}
+
+#[derive(Debug, Serialize)]
+struct JiraSyncReport {
+    issues: usize,
+    /// False when `max_results` cut the result short.
+    complete: bool,
+    summary: sync::JiraSummary,
+}
+
+#[derive(Debug, Serialize)]
+struct CaldavSyncReport {
+    tasks: usize,
+    summary: caldav::CaldavSummary,
+}
+
+/// Fetch and store the PRs of every account; prints the summary unless `--json`.
+fn sync_github(
+    repo: &mut dyn TodoRepository,
+    config: &Config,
+    mut accounts: Vec<crate::app::GithubConfig>,
+    args: &SyncArgs,
+) -> Result<(AttentionPrs, sync::SyncSummary)> {
+    for github in &mut accounts {
+        if let Some(days) = args.days {
+            github.days = days;
+        }
+        if let Some(page_size) = args.page_size {
+            github.page_size = page_size;
+        }
+        github.include_team_requests |= args.team;
+    }
+    crate::app::plan_sync(&mut accounts, repo, args.full_sync)?;
+    let opts = accounts[0].fetch_options();
+    let started_at = crate::now_unix();
+    // Live page / PR counter on a terminal; plain output when piped.
+    let interactive = std::io::stderr().is_terminal();
+    let attention = crate::worker::block_on(crate::app::fetch_attention_all(
+        accounts.clone(),
+        std::sync::Arc::new(move |p| {
+            if interactive {
+                eprint!("\rfetching: {} pages, {} PRs", p.pages, p.prs);
+            }
+        }),
+    ))?;
+    if interactive {
+        eprint!("\r\x1b[2K");
+    }
+    let attention = attention.map_err(|e| {
+        let e = crate::repo::github::QueryError::from_anyhow(e);
+        match e.kind.hint() {
+            Some(hint) => anyhow::anyhow!("{e}\nhint: {hint}"),
+            None => e.into(),
+        }
+    })?;
+    let mut settings = config.github.clone();
+    settings.ignore_drafts |= args.ignore_drafts;
+    let summary = sync::apply_attention(repo, &settings, &attention)?;
+    // A partial sync must not move the watermark past PRs the failed query missed.
+    if !attention.is_partial() {
+        let hosts: Vec<String> = accounts.iter().map(|cfg| cfg.host()).collect();
+        sync::record_watermarks(repo, &hosts, started_at)?;
+    }
+    for failure in &attention.failed_queries {
+        eprintln!("warning: {failure} (results are partial)");
+    }
+    for failure in attention
+        .searches
+        .iter()
+        .filter_map(|s| s.hits.as_ref().err())
+    {
+        eprintln!("warning: saved search {failure} (its todos were left as they are)");
+    }
+    run_sync_hook(
+        config,
+        "github",
+        summary.added
+            + summary.follow_ups
+            + summary.own_prs
+            + summary.search_added
+            + summary.watched,
+        summary.unmatched + summary.watches_finished,
+    )?;
+    if args.json {
+        return Ok((attention, summary));
+    }
+    println!(
+        "Synced GitHub as {} ({}): {} PRs, {} tasks added, {} merge follow-ups, {} own PRs need action",
+        attention.viewer_login,
+        if attention.incremental {
+            "incremental"
+        } else {
+            "full"
+        },
+        attention.prs.len(),
+        summary.added,
+        summary.follow_ups,
+        summary.own_prs
+    );
+    if !attention.searches.is_empty() {
+        println!(
+            "Saved searches: {} todos added, {} closed as no longer matching",
+            summary.search_added, summary.unmatched
+        );
+    }
+    if summary.watched + summary.watches_finished > 0 {
+        println!(
+            "Watched PRs: {} todos added, {} merged or closed and no longer watched",
+            summary.watched, summary.watches_finished
+        );
+    }
+    if args.explain {
+        println!();
+        print!(
+            "{}",
+            sync::explain(&attention.stats, accounts[0].days, opts.page_size)
+        );
+    }
+    Ok((attention, summary))
+}
+
+/// `on_sync_complete`, waited for so it is not cut off when koto exits.
+pub(crate) fn run_sync_hook(
+    config: &Config,
+    source: &str,
+    added: usize,
+    closed: usize,
+) -> Result<()> {
+    let completed = SyncCompleted {
+        source,
+        added,
+        closed,
+    };
+    if let Some(handle) = hooks::fire(&config.hooks, HookEvent::SyncComplete, &completed)? {
+        let _ = handle.join();
+    }
+    Ok(())
+}
+
+fn add(
+    repo: &mut dyn TodoRepository,
+    config: &Config,
+    title: Vec<String>,
+    popup: bool,
+) -> Result<()> {
+    let meta = if popup {
+        match capture::prompt()? {
+            Some(meta) => meta,
+            None => return Ok(()),
+        }
+    } else {
+        parse_inline_meta(&title.join(" ")).map_err(anyhow::Error::msg)?
+    };
+    let todo = add_parsed(repo, meta, None)?;
+    println!("Added: {}", todo.title);
+    if let Some(handle) = hooks::fire(&config.hooks, HookEvent::Add, &todo)? {
+        let _ = handle.join();
+    }
+    Ok(())
+}
+
+/// Store a todo read from an add prompt, with its start date and estimate.
+fn add_parsed(
+    repo: &mut dyn TodoRepository,
+    meta: InlineMeta,
+    url: Option<String>,
+) -> Result<Todo> {
+    let mut todo = repo.add(meta.title, meta.priority, meta.due, url, None)?;
+    if meta.start.is_some() {
+        todo = repo.set_start(todo.id, meta.start)?.unwrap_or(todo);
+    }
+    if meta.estimate.is_some() {
+        todo = repo.set_estimate(todo.id, meta.estimate)?.unwrap_or(todo);
+    }
+    Ok(todo)
+}
+
+fn watch(repo: &mut dyn TodoRepository, pr: Option<String>, remove: bool) -> Result<()> {
+    let Some(pr) = pr else {
+        let watched = sync::watched_prs(repo)?;
+        if watched.is_empty() {
+            println!("No watched PRs.");
+        }
+        for key in &watched {
+            println!("{key}");
+        }
+        return Ok(());
+    };
+    let Some(key) = sync::parse_pr_ref(&pr) else {
+        anyhow::bail!("not a PR: {pr} (expected owner/repo#123 or a PR URL)");
+    };
+    let changed = if remove {
+        sync::unwatch(repo, &key)?
+    } else {
+        sync::watch(repo, &key)?
+    };
+    match (remove, changed) {
+        (false, true) => println!("Watching {key}; the next sync adds it"),
+        (false, false) => println!("Already watching {key}"),
+        (true, true) => println!("Stopped watching {key}"),
+        (true, false) => println!("{key} is not watched"),
+    }
+    Ok(())
+}
+
+fn search(repo: &dyn TodoRepository, text: String, scope: ScopeArg) -> Result<()> {
+    let hits = repo.search(&TodoQuery {
+        text,
+        scope: scope.into(),
+    })?;
+    if hits.is_empty() {
+        println!("No matches.");
+        return Ok(());
+    }
+    for todo in &hits {
+        let state = if todo.archived {
+            "archived"
+        } else {
+            todo.status.label()
+        };
+        println!("{state:<11}  {}", todo.title);
+    }
+    Ok(())
+}
+
+fn activity_log(repo: &dyn TodoRepository, limit: usize, json: bool) -> Result<()> {
+    let entries = repo.activity(limit)?;
+    if json {
+        for entry in &entries {
+            println!("{}", serde_json::to_string(entry)?);
+        }
+        return Ok(());
+    }
+    if entries.is_empty() {
+        println!("No activity yet.");
+        return Ok(());
+    }
+    // Edits only describe the change; name the todo from the store when it still exists.
+    let titles: std::collections::HashMap<_, _> = repo
+        .search(&TodoQuery {
+            text: String::new(),
+            scope: SearchScope::All,
+        })?
+        .into_iter()
+        .map(|t| (t.id, t.title))
+        .collect();
+    for entry in &entries {
+        let at = UNIX_EPOCH + Duration::from_secs(entry.at.max(0) as u64);
+        let stamp = clock::to_local(at)
+            .format(format_description!("[year]-[month]-[day] [hour]:[minute]"))
+            .unwrap_or_else(|_| "?".into());
+        let title = match entry.kind {
+            ActivityKind::Edit => entry.todo_id.and_then(|id| titles.get(&id)),
+            _ => None,
+        };
+        match title {
+            Some(title) => println!(
+                "{stamp}  {:<7}  {title} — {}",
+                entry.kind.as_str(),
+                entry.detail
+            ),
+            None => println!("{stamp}  {:<7}  {}", entry.kind.as_str(), entry.detail),
+        }
+    }
+    Ok(())
+}
+
+fn config_command(action: ConfigAction, config_path: Option<&Path>) -> Result<()> {
+    let path = match config_path {
+        Some(p) => p.to_path_buf(),
+        None => config::default_config_path().context("failed to resolve config dir")?,
+    };
+    match action {
+        ConfigAction::Export { output } => {
+            let body = Config::load(Some(&path))?.export_profile()?;
+            write_output(output, body)
+        }
+        ConfigAction::Import { file } => {
+            let raw = std::fs::read_to_string(&file)
+                .with_context(|| format!("failed to read {}", file.display()))?;
+            let current = match std::fs::read_to_string(&path) {
+                Ok(current) => current,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+                Err(e) => {
+                    return Err(e).with_context(|| format!("failed to read {}", path.display()));
+                }
+            };
+            let imported = Config::import_profile(&current, &raw)
+                .with_context(|| format!("failed to import {}", file.display()))?;
+            imported.save(&path)?;
+            println!("Imported settings into {}", path.display());
+            Ok(())
+        }
+        ConfigAction::Path => {
+            println!("{}", path.display());
+            Ok(())
+        }
+    }
+}
+
+fn archive_command(
+    repo: &mut dyn TodoRepository,
+    store_path: &Path,
+    action: ArchiveAction,
+) -> Result<()> {
+    // The archive file is plaintext JSON, so an encrypted store does not keep one.
+    let archive = (!SqliteTodoRepo::is_encrypted(store_path)?).then(|| Archive::for_db(store_path));
+    match action {
+        ArchiveAction::Grep { pattern } => {
+            let Some(archive) = archive else {
+                anyhow::bail!("an encrypted store keeps no archive of deleted todos");
+            };
+            for entry in archive.grep(&pattern)? {
+                let at = UNIX_EPOCH + Duration::from_secs(entry.archived_at.max(0) as u64);
+                let stamp = clock::to_local(at)
+                    .format(format_description!("[year]-[month]-[day]"))
+                    .unwrap_or_else(|_| "?".into());
+                let reason = match entry.reason {
+                    ArchiveReason::Deleted => "deleted",
+                    ArchiveReason::Purged => "purged",
+                };
+                let mark = if entry.todo.is_closed() { "x" } else { " " };
+                print!("{stamp} {reason:<7} [{mark}] {}", entry.todo.title);
+                match entry.todo.external_url {
+                    Some(url) => println!("  {url}"),
+                    None => println!(),
+                }
+            }
+            Ok(())
+        }
+        ArchiveAction::Purge { older_than } => {
+            // Deleting rows under a running TUI would be undone by its next write.
+            let _lock = StoreLock::acquire(store_path, LockHolder::Tui)?;
+            let cutoff = older_than.map(|days| {
+                SystemTime::now()
+                    .checked_sub(Duration::from_secs(days * 86_400))
+                    .unwrap_or(UNIX_EPOCH)
+            });
+            let query = TodoQuery {
+                scope: SearchScope::Archived,
+                ..TodoQuery::default()
+            };
+            let purge: Vec<Todo> = repo
+                .search(&query)?
+                .into_iter()
+                .filter(|t| cutoff.is_none_or(|c| t.created_at < c))
+                .collect();
+            if let Some(archive) = &archive {
+                archive.append(&purge, ArchiveReason::Purged)?;
+            }
+            for todo in &purge {
+                repo.delete(todo.id)?;
+            }
+            match archive {
+                Some(archive) => println!(
+                    "Purged {} archived todos (kept in {})",
+                    purge.len(),
+                    archive.path().display()
+                ),
+                None => println!("Purged {} archived todos", purge.len()),
+            }
+            Ok(())
+        }
+    }
+}
+
+fn restore(store_path: &Path, backup: &Path, yes: bool) -> Result<()> {
+    // A running TUI would keep writing its own view over the restored store.
+    let _lock = StoreLock::acquire(store_path, LockHolder::Tui)?;
+    if !yes {
+        if !std::io::stdin().is_terminal() {
+            anyhow::bail!("restoring replaces the store; pass --yes to confirm");
+        }
+        eprint!(
+            "Replace {} with {}? The current store is backed up first. [y/N] ",
+            store_path.display(),
+            backup.display()
+        );
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            println!("Not restored");
+            return Ok(());
+        }
+    }
+    let previous = Backups::for_db(store_path).restore(backup)?;
+    println!(
+        "Restored {} from {} (the replaced store is in {})",
+        store_path.display(),
+        backup.display(),
+        previous.display()
+    );
+    Ok(())
+}
+
+fn write_output(output: Option<PathBuf>, body: String) -> Result<()> {
+    match output {
+        Some(path) => std::fs::write(&path, body)
+            .with_context(|| format!("failed to write {}", path.display())),
+        None => {
+            print!("{body}");
+            Ok(())
+        }
+    }
+}
+
+fn export(
+    repo: &dyn TodoRepository,
+    format: ExportFormat,
+    events: bool,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let todos = repo.all()?;
+    let body = match format {
+        ExportFormat::Ics => ics::to_ics(&todos, events, SystemTime::now()),
+    };
+    write_output(output, body)
+}
+
+fn widget(repo: &dyn TodoRepository, format: WidgetFormat) -> Result<()> {
+    let summary = widget::widget(&repo.all()?, SystemTime::now(), clock::today());
+    match format {
+        WidgetFormat::Json => println!("{}", serde_json::to_string(&summary)?),
+    }
+    Ok(())
+}
+
+fn status(repo: &dyn TodoRepository) -> Result<()> {
+    // Before the first sync there is no PR cache to report on.
+    let prs = match repo.meta(META_PRS_SYNCED_AT)? {
+        Some(_) => Some(repo.cached_prs()?),
+        None => None,
+    };
+    println!(
+        "{}",
+        widget::status_line(
+            &repo.all()?,
+            prs.as_deref(),
+            SystemTime::now(),
+            clock::today()
+        )
+    );
+    Ok(())
+}
+
+fn report_time(repo: &dyn TodoRepository, week: bool) -> Result<()> {
+    let now = SystemTime::now();
+    let since = week.then(start_of_week);
+    let entries = repo.time_entries(since)?;
+    let todos = repo.all()?;
+
+    match since {
+        Some(s) => {
+            let odt = clock::to_local(s);
+            let date = odt
+                .format(format_description!("[year]-[month]-[day]"))
+                .unwrap_or_else(|_| "?".into());
+            println!("Tracked time since {date}");
+        }
+        None => println!("Tracked time (all)"),
+    }
+    print!("{}", report::time_report(&todos, &entries, since, now));
+    Ok(())
+}
+
+fn report_reviews(repo: &dyn TodoRepository, format: ReviewsFormat) -> Result<()> {
+    let Some(synced_at) = repo
+        .meta(META_PRS_SYNCED_AT)?
+        .and_then(|v| v.parse::<i64>().ok())
+    else {
+        anyhow::bail!("no cached PR data yet; run a GitHub sync (g) in the TUI first");
+    };
+    let prs = repo.cached_prs()?;
+    match format {
+        ReviewsFormat::Md => {
+            let synced = UNIX_EPOCH + Duration::from_secs(synced_at.max(0) as u64);
+            let stamp = clock::to_local(synced)
+                .format(format_description!("[year]-[month]-[day] [hour]:[minute]"))
+                .unwrap_or_else(|_| "?".into());
+            println!("### Review requests (as of {stamp})\n");
+            print!("{}", report::reviews_markdown(&prs, crate::now_unix()));
+        }
+    }
+    Ok(())
+}
+
+fn report_weekly(repo: &dyn TodoRepository, since: &str) -> Result<()> {
+    let now = SystemTime::now();
+    let since = parse_since(since, now)?;
+    // Archived todos count too: `c` may already have cleared this week's work.
+    let todos = repo.search(&TodoQuery {
+        text: String::new(),
+        scope: SearchScope::All,
+    })?;
+    print!("{}", report::weekly_markdown(&todos, since, now));
+    Ok(())
+}
+
+/// `7d`, `2w` (counted back from `now`) or a local date (`2025-01-06`, from midnight).
+fn parse_since(raw: &str, now: SystemTime) -> Result<SystemTime> {
+    let raw = raw.trim();
+    let span = |count: &str, unit: u64| {
+        count
+            .parse::<u64>()
+            .ok()
+            .map(|n| now - Duration::from_secs(n * unit))
+    };
+    let since = if let Some(days) = raw.strip_suffix('d') {
+        span(days, 86_400)
+    } else if let Some(weeks) = raw.strip_suffix('w') {
+        span(weeks, 7 * 86_400)
+    } else {
+        time::Date::parse(raw, format_description!("[year]-[month]-[day]"))
+            .ok()
+            .map(|date| {
+                let ts = date
+                    .midnight()
+                    .assume_offset(clock::local_offset())
+                    .unix_timestamp();
+                UNIX_EPOCH + Duration::from_secs(ts.max(0) as u64)
+            })
+    };
+    since.with_context(|| format!("invalid --since {raw:?} (use e.g. 7d, 2w or 2025-01-06)"))
+}
+
+fn start_of_week() -> SystemTime {
+    let today = clock::today();
+    let monday = today.saturating_sub(time::Duration::days(
+        today.weekday().number_days_from_monday() as i64,
+    ));
+    let ts = monday
+        .midnight()
+        .assume_offset(clock::local_offset())
+        .unix_timestamp();
+    UNIX_EPOCH + Duration::from_secs(ts.max(0) as u64)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::repo::memory::InMemoryTodoRepo;
+
+    #[test]
+    fn koto_add_fires_the_on_add_hook() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("added.json");
+        let mut config = Config::default();
+        config.hooks.on_add = Some(format!("cat > '{}'", out.display()));
+        let mut repo = InMemoryTodoRepo::default();
+
+        let title = vec!["Ship".to_string(), "release".to_string()];
+        add(&mut repo, &config, title, false).unwrap();
+        let added: Todo = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+        assert_eq!(added.title, "Ship release");
+        assert_eq!(repo.all().unwrap()[0].id, added.id);
+    }
+}
@@ -0,0 +1,372 @@
+//! `koto serve`: a small JSON API on localhost for scripts, launchers and browser
+//! extensions. Requests are handled one at a time on the calling thread, so the store
+//! needs no sharing.
+//!
+//! Only loopback clients are served. Since any web page can make a browser send requests
+//! to localhost, requests naming another host (DNS rebinding) or coming from a web page
+//! (an `Origin` that is not a browser extension) are refused.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use super::{SyncArgs, sync_report};
+use crate::config::Config;
+use crate::domain::todo::{Todo, TodoId};
+use crate::hooks::{self, HookEvent};
+use crate::repo::TodoRepository;
+
+/// Request bodies are a title and a few fields; anything bigger is a mistake.
+const MAX_BODY: usize = 64 * 1024;
+
+/// Browser extensions may call the API; ordinary web pages may not.
+const EXTENSION_ORIGINS: &[&str] = &[
+    "chrome-extension://",
+    "moz-extension://",
+    "safari-web-extension://",
+];
+
+pub fn run(
+    repo: &mut dyn TodoRepository,
+    config: &Config,
+    github_api_url: Option<&str>,
+    port: u16,
+) -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .with_context(|| format!("failed to listen on 127.0.0.1:{port}"))?;
+    eprintln!("koto serve: listening on http://127.0.0.1:{port}");
+    let mut server = Server {
+        repo,
+        config,
+        github_api_url,
+    };
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        if let Err(e) = server.serve(stream) {
+            eprintln!("koto serve: {e:#}");
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+struct Request {
+    method: String,
+    path: String,
+    query: String,
+    /// Names lowercased.
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Whether `key=true` (or a bare `key`) is in the query string.
+    fn flag(&self, key: &str) -> bool {
+        self.query
+            .split('&')
+            .any(|pair| pair == key || pair == format!("{key}=true") || pair == format!("{key}=1"))
+    }
+}
+
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    body: Option<Value>,
+    /// Set for browser extensions, so they can read the response.
+    allow_origin: Option<String>,
+}
+
+impl Response {
+    fn json(status: u16, body: Value) -> Self {
+        Self {
+            status,
+            body: Some(body),
+            allow_origin: None,
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self::json(status, json!({ "error": message.into() }))
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            201 => "Created",
+            204 => "No Content",
+            400 => "Bad Request",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            502 => "Bad Gateway",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// `POST /todos`.
+#[derive(Debug, Deserialize)]
+struct NewTodo {
+    /// Parsed like the add prompt: `p1` / `!` set the priority, `d:fri` the due date.
+    title: String,
+    #[serde(default)]
+    notes: Option<String>,
+    /// A link, e.g. the page a browser extension was on.
+    #[serde(default)]
+    url: Option<String>,
+}
+
+struct Server<'a> {
+    repo: &'a mut dyn TodoRepository,
+    config: &'a Config,
+    github_api_url: Option<&'a str>,
+}
+
+impl Server<'_> {
+    fn serve(&mut self, stream: TcpStream) -> Result<()> {
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let response = match read_request(&mut reader) {
+            Ok(request) => self.handle(&request),
+            Err(e) => Response::error(400, format!("{e:#}")),
+        };
+        write_response(stream, &response)
+    }
+
+    fn handle(&mut self, request: &Request) -> Response {
+        let host = request.header("host").unwrap_or_default();
+        let hostname = host.rsplit_once(':').map_or(host, |(name, _)| name);
+        if !matches!(hostname, "localhost" | "127.0.0.1") {
+            return Response::error(403, format!("unexpected host {host:?}"));
+        }
+        let origin = request.header("origin");
+        if origin.is_some_and(|o| !EXTENSION_ORIGINS.iter().any(|prefix| o.starts_with(prefix))) {
+            return Response::error(403, "requests from web pages are not allowed");
+        }
+        let mut response = if request.method == "OPTIONS" {
+            Response {
+                status: 204,
+                body: None,
+                allow_origin: None,
+            }
+        } else {
+            self.route(request)
+                .unwrap_or_else(|e| Response::error(500, format!("{e:#}")))
+        };
+        response.allow_origin = origin.map(str::to_string);
+        response
+    }
+
+    fn route(&mut self, request: &Request) -> Result<Response> {
+        let segments: Vec<&str> = request.path.split('/').filter(|s| !s.is_empty()).collect();
+        Ok(match (request.method.as_str(), segments.as_slice()) {
+            ("GET", ["todos"]) => {
+                let all = request.flag("all");
+                let todos: Vec<Todo> = self
+                    .repo
+                    .all()?
+                    .into_iter()
+                    .filter(|t| all || !t.is_closed())
+                    .collect();
+                Response::json(200, serde_json::to_value(todos)?)
+            }
+            ("POST", ["todos"]) => self.add(&request.body)?,
+            ("POST", ["todos", id, "toggle"]) => {
+                let Ok(id) = id.parse::<TodoId>() else {
+                    return Ok(Response::error(400, format!("not a todo id: {id}")));
+                };
+                match self.repo.toggle(id)? {
+                    Some(todo) => {
+                        if todo.is_closed() {
+                            self.fire_hook(HookEvent::Done, &todo);
+                        }
+                        Response::json(200, serde_json::to_value(todo)?)
+                    }
+                    None => Response::error(404, format!("no todo {id}")),
+                }
+            }
+            ("POST", ["sync"]) => {
+                let args = SyncArgs {
+                    json: true,
+                    ..SyncArgs::default()
+                };
+                match sync_report(self.repo, self.config, self.github_api_url, &args) {
+                    Ok(report) => Response::json(200, serde_json::to_value(report)?),
+                    Err(e) => Response::error(502, format!("{e:#}")),
+                }
+            }
+            (_, ["todos"] | ["todos", _, "toggle"] | ["sync"]) => {
+                Response::error(405, format!("{} is not allowed here", request.method))
+            }
+            _ => Response::error(404, format!("no route for {}", request.path)),
+        })
+    }
+
+    fn add(&mut self, body: &[u8]) -> Result<Response> {
+        let new: NewTodo = match serde_json::from_slice(body) {
+            Ok(new) => new,
+            Err(e) => return Ok(Response::error(400, format!("invalid body: {e}"))),
+        };
+        let meta = match crate::app::parse_inline_meta(&new.title) {
+            Ok(parsed) => parsed,
+            Err(msg) => return Ok(Response::error(400, msg)),
+        };
+        let mut todo = super::add_parsed(self.repo, meta, new.url)?;
+        let notes = new.notes.filter(|n| !n.trim().is_empty());
+        if notes.is_some() {
+            todo = self.repo.set_notes(todo.id, notes)?.unwrap_or(todo);
+        }
+        self.fire_hook(HookEvent::Add, &todo);
+        Ok(Response::json(201, serde_json::to_value(todo)?))
+    }
+
+    /// Hooks run in the background; the server outlives them, so they are not waited for.
+    fn fire_hook(&self, event: HookEvent, todo: &Todo) {
+        if let Err(e) = hooks::fire(&self.config.hooks, event, todo) {
+            eprintln!("koto serve: {e:#}");
+        }
+    }
+}
+
+fn read_request(reader: &mut impl BufRead) -> Result<Request> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        anyhow::bail!("malformed request line");
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: query.to_string(),
+        ..Request::default()
+    };
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            request
+                .headers
+                .push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+    let length: usize = match request.header("content-length") {
+        Some(len) => len.parse().context("invalid Content-Length")?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        anyhow::bail!("body larger than {MAX_BODY} bytes");
+    }
+    request.body = vec![0; length];
+    reader.read_exact(&mut request.body)?;
+    Ok(request)
+}
+
+fn write_response(mut stream: TcpStream, response: &Response) -> Result<()> {
+    let body = match &response.body {
+        Some(body) => serde_json::to_vec_pretty(body)?,
+        None => Vec::new(),
+    };
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        response.reason(),
+        body.len()
+    );
+    if response.body.is_some() {
+        head += "Content-Type: application/json\r\n";
+    }
+    if let Some(origin) = &response.allow_origin {
+        head += &format!(
+            "Access-Control-Allow-Origin: {origin}\r\n\
+             Access-Control-Allow-Methods: GET, POST\r\n\
+             Access-Control-Allow-Headers: Content-Type\r\n\
+             Vary: Origin\r\n"
+        );
+    }
+    head += "\r\n";
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(&body)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::memory::InMemoryTodoRepo;
+
+    fn request(method: &str, target: &str, origin: Option<&str>, body: &str) -> Request {
+        let mut raw = format!("{method} {target} HTTP/1.1\r\nHost: localhost:8080\r\n");
+        if let Some(origin) = origin {
+            raw += &format!("Origin: {origin}\r\n");
+        }
+        raw += &format!("Content-Length: {}\r\n\r\n{body}", body.len());
+        read_request(&mut raw.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn todos_are_added_listed_and_toggled_for_local_clients_only() {
+        let mut repo = InMemoryTodoRepo::default();
+        let config = Config::default();
+        let mut server = Server {
+            repo: &mut repo,
+            config: &config,
+            github_api_url: None,
+        };
+
+        let added = server.handle(&request(
+            "POST",
+            "/todos",
+            None,
+            r#"{"title": "Read the RFC p1", "url": "https://example.com/rfc"}"#,
+        ));
+        assert_eq!(added.status, 201, "{:?}", added.body);
+        let todo = added.body.unwrap();
+        assert_eq!(todo["title"], "Read the RFC");
+        assert_eq!(todo["priority"], "High");
+        let id = todo["id"].as_str().unwrap().to_string();
+
+        let toggled = server.handle(&request("POST", &format!("/todos/{id}/toggle"), None, ""));
+        assert_eq!(toggled.body.unwrap()["status"], "done");
+        let open = server.handle(&request("GET", "/todos", None, ""));
+        assert_eq!(open.body.unwrap(), json!([]));
+        let all = server.handle(&request("GET", "/todos?all=true", None, ""));
+        assert_eq!(all.body.unwrap().as_array().unwrap().len(), 1);
+
+        let page = server.handle(&request("GET", "/todos", Some("https://evil.example"), ""));
+        assert_eq!(page.status, 403);
+        let extension = server.handle(&request("GET", "/todos", Some("moz-extension://abc"), ""));
+        assert_eq!(extension.status, 200);
+        assert_eq!(
+            extension.allow_origin.as_deref(),
+            Some("moz-extension://abc")
+        );
+        let mut rebound = request("GET", "/todos", None, "");
+        rebound.headers[0].1 = "attacker.example:8080".to_string();
+        assert_eq!(server.handle(&rebound).status, 403);
+        assert_eq!(
+            server.handle(&request("DELETE", "/todos", None, "")).status,
+            405
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+use crate::config::ClipboardBackend;
+
+/// Copy `text` with the configured backend and return the name of the backend that was used.
+pub fn copy(backend: ClipboardBackend, text: &str) -> Result<&'static str> {
+    match backend {
+        ClipboardBackend::Osc52 => osc52(text).map(|_| "OSC52"),
+        ClipboardBackend::Native => native(text),
+        // Over SSH the native tools would write to the remote machine's clipboard (if any).
+        ClipboardBackend::Auto if is_remote() => osc52(text).map(|_| "OSC52"),
+        ClipboardBackend::Auto => native(text).or_else(|_| osc52(text).map(|_| "OSC52")),
+    }
+}
+
+fn is_remote() -> bool {
+    std::env::var_os("SSH_TTY").is_some() || std::env::var_os("SSH_CONNECTION").is_some()
+}
+
+/// Ask the terminal to set the clipboard. Works through SSH; tmux needs the passthrough wrapper
+/// (and `set -g allow-passthrough on` or `set-clipboard on`).
+fn osc52(text: &str) -> Result<()> {
+    let seq = osc52_sequence(text, std::env::var_os("TMUX").is_some());
+    let mut out = std::io::stdout();
+    out.write_all(seq.as_bytes())
+        .and_then(|_| out.flush())
+        .context("failed to write OSC52 sequence")
+}
+
+/// Inside tmux the sequence goes in a DCS passthrough, with its ESCs doubled.
+fn osc52_sequence(text: &str, tmux: bool) -> String {
+    let seq = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+    if tmux {
+        format!("\x1bPtmux;{}\x1b\\", seq.replace('\x1b', "\x1b\x1b"))
+    } else {
+        seq
+    }
+}
+
+fn native(text: &str) -> Result<&'static str> {
+    let candidates: &[(&'static str, &[&str])] = if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else if cfg!(windows) {
+        &[("clip", &[])]
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        &[("wl-copy", &[]), ("xclip", &["-selection", "clipboard"])]
+    } else {
+        &[
+            ("xclip", &["-selection", "clipboard"]),
+            ("xsel", &["--clipboard", "--input"]),
+        ]
+    };
+    for (program, args) in candidates {
+        let Ok(mut child) = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(program);
+        }
+    }
+    bail!(
+        "no clipboard tool found (tried {})",
+        candidates
+            .iter()
+            .map(|(p, _)| *p)
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc52_carries_base64_and_is_wrapped_for_tmux() {
+        assert_eq!(
+            osc52_sequence("koto ✓", false),
+            "\x1b]52;c;a290byDinJM=\x07"
+        );
+        assert_eq!(
+            osc52_sequence("koto ✓", true),
+            "\x1bPtmux;\x1b\x1b]52;c;a290byDinJM=\x07\x1b\\"
+        );
+    }
+}
@@ -0,0 +1,642 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::todo::Priority;
+use crate::hooks::HookEvent;
+
+/// User settings read from `config.toml`. Every field has a default, so a missing file
+/// (or a missing section) behaves exactly like the built-in defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub keys: KeysConfig,
+    pub open: OpenConfig,
+    pub clipboard: ClipboardConfig,
+    pub github: GithubSettings,
+    pub privacy: PrivacyConfig,
+    pub time: TimeConfig,
+    pub jira: JiraSettings,
+    pub caldav: CaldavSettings,
+    pub hooks: HooksConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GithubSettings {
+    /// Only PRs updated within this many days are fetched.
+    pub days: u64,
+    /// Nodes per GraphQL page (1..=100); smaller pages are cheaper but need more requests.
+    pub page_size: i32,
+    /// Also add PRs whose review was requested from one of my teams (not just from me).
+    pub include_team_requests: bool,
+    /// Add no todos for draft PRs (those already added stay, with a "draft" badge).
+    pub ignore_drafts: bool,
+    /// Also add a todo for each of my own PRs with failing CI, conflicts or changes
+    /// requested; it is completed once a sync sees the PR clear again.
+    pub track_own_prs: bool,
+    /// Mark the review request's todo done once I approve its PR in koto.
+    pub done_on_approve: bool,
+    /// After I approve a PR (in koto, or seen during sync), add "merge owner/repo#N when green".
+    pub follow_up_on_approve: bool,
+    /// Days from today until the follow-up is due.
+    pub follow_up_due_days: i64,
+    /// PRs not updated for this many days get a red age in the list (0 disables).
+    pub stale_after_days: u64,
+    /// Tries per GraphQL request on network errors, 5xx and rate limits (1 = no retry).
+    pub retry_attempts: u32,
+    /// Delay before the first retry; doubled for each further one (with jitter).
+    pub retry_backoff_ms: u64,
+    /// Further hosts synced alongside the main account (`[[github.accounts]]`).
+    pub accounts: Vec<GithubAccount>,
+    /// Custom search queries synced as tagged todos (`[[github.searches]]`, main account).
+    pub searches: Vec<SavedSearch>,
+    /// Only sync PRs of repositories matching one of these `owner/repo` patterns (`*` is
+    /// a wildcard, e.g. `acme/*`); empty syncs every repository.
+    pub include_repos: Vec<String>,
+    /// Never sync PRs of repositories matching one of these patterns.
+    pub exclude_repos: Vec<String>,
+    /// Start with bot-authored PRs (Dependabot, Renovate, …) hidden; `B` toggles.
+    pub hide_bots: bool,
+    /// Give synced todos that would get no due date (`due_days = -1`, saved searches)
+    /// the due date of their milestone.
+    pub milestone_due: bool,
+    /// How review-request PRs become todos (`[[github.rules]]`); the first matching rule
+    /// wins. Defaults to a rule giving Renovate PRs medium priority and a month.
+    pub rules: Vec<PrRule>,
+}
+
+/// One triage rule. Unset conditions match any PR; unset outcomes keep the default of
+/// high priority, due today.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrRule {
+    /// Author login pattern; `*` is a wildcard, case is ignored (e.g. `dependabot*`).
+    pub author: Option<String>,
+    /// `owner/repo` pattern.
+    pub repo: Option<String>,
+    /// Pattern for the whole PR title (e.g. `chore(deps)*`).
+    pub title: Option<String>,
+    /// Only drafts (`true`) or only ready PRs (`false`).
+    pub draft: Option<bool>,
+    /// Pattern one of the PR's labels must match (e.g. `urgent`, `priority/*`).
+    pub label: Option<String>,
+    pub priority: Option<Priority>,
+    /// Due this many days from today; negative leaves the todo without a due date.
+    pub due_days: Option<i64>,
+    /// Prefixed to the title as `#tag`, like `#` does.
+    pub tags: Vec<String>,
+    /// Add no todo for matching PRs.
+    pub skip: bool,
+}
+
+impl PrRule {
+    fn renovate() -> Self {
+        Self {
+            author: Some("renovate*".to_string()),
+            priority: Some(Priority::Medium),
+            due_days: Some(30),
+            ..Self::default()
+        }
+    }
+}
+
+/// A GitHub search whose results become todos tagged `#<tag>`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    /// GitHub search syntax, e.g. `is:open label:oncall org:acme`.
+    pub query: String,
+    /// Prefixed to the todo titles (`#oncall ...`) and remembered as the todo's source.
+    pub tag: String,
+    /// What happens to a todo whose item no longer matches the query.
+    #[serde(default)]
+    pub on_unmatch: UnmatchAction,
+}
+
+impl SavedSearch {
+    /// Tag without a leading `#`; `:` is reserved for the todo's source key.
+    pub fn tag(&self) -> String {
+        self.tag.trim().trim_start_matches('#').replace(':', "-")
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnmatchAction {
+    /// Mark the todo done.
+    #[default]
+    Complete,
+    /// Hide it like `c` does (still searchable).
+    Archive,
+}
+
+/// An additional GitHub host, e.g. a GitHub Enterprise instance next to github.com.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GithubAccount {
+    /// API root, e.g. `https://ghe.example.com/api/v3`.
+    pub api_url: String,
+    /// Env var holding this account's token; unset: `gh auth token --hostname <host>`.
+    #[serde(default)]
+    pub token_env: Option<String>,
+}
+
+impl Default for GithubSettings {
+    fn default() -> Self {
+        Self {
+            days: 30,
+            page_size: 50,
+            include_team_requests: false,
+            ignore_drafts: false,
+            track_own_prs: false,
+            done_on_approve: true,
+            follow_up_on_approve: false,
+            follow_up_due_days: 1,
+            stale_after_days: 7,
+            retry_attempts: 3,
+            retry_backoff_ms: 500,
+            accounts: Vec::new(),
+            searches: Vec::new(),
+            include_repos: Vec::new(),
+            exclude_repos: Vec::new(),
+            hide_bots: false,
+            milestone_due: false,
+            rules: vec![PrRule::renovate()],
+        }
+    }
+}
+
+/// `[jira]`: issues pulled in as todos keyed `jira:PROJ-123`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JiraSettings {
+    /// Site root, e.g. `https://acme.atlassian.net`; unset turns Jira sync off.
+    pub base_url: Option<String>,
+    /// Account email for Jira Cloud API tokens; unset sends the token as a bearer token
+    /// (Server / Data Center personal access tokens).
+    pub email: Option<String>,
+    /// Env var holding the API token.
+    pub token_env: String,
+    /// Which issues become todos.
+    pub jql: String,
+    /// Stop after this many issues.
+    pub max_results: usize,
+}
+
+impl Default for JiraSettings {
+    fn default() -> Self {
+        Self {
+            base_url: None,
+            email: None,
+            token_env: "JIRA_API_TOKEN".to_string(),
+            jql: "assignee = currentUser() AND resolution = Unresolved ORDER BY updated DESC"
+                .to_string(),
+            max_results: 200,
+        }
+    }
+}
+
+/// `[caldav]`: a task list (VTODO collection) synced both ways.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaldavSettings {
+    /// Collection URL, e.g. `https://cloud.example.com/remote.php/dav/calendars/me/tasks/`;
+    /// unset turns CalDAV sync off.
+    pub url: Option<String>,
+    pub username: String,
+    /// Env var holding the password (an app password for Nextcloud or iCloud).
+    pub password_env: String,
+}
+
+impl Default for CaldavSettings {
+    fn default() -> Self {
+        Self {
+            url: None,
+            username: String::new(),
+            password_env: "CALDAV_PASSWORD".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeysConfig {
+    /// How `d` / `Delete` protect against key-repeat mass deletion.
+    pub delete_guard: DeleteGuard,
+    /// Window for `delete_guard` in milliseconds.
+    pub delete_guard_window_ms: u64,
+}
+
+impl Default for KeysConfig {
+    fn default() -> Self {
+        Self {
+            delete_guard: DeleteGuard::Window,
+            delete_guard_window_ms: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeleteGuard {
+    /// Every press deletes immediately.
+    Off,
+    /// The first press deletes; another delete within the window asks for `y` first.
+    Window,
+    /// Deleting requires `dd` (a second press within the window).
+    Double,
+}
+
+/// How links are opened. Unset means the system default (`xdg-open`, `open`, `start`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenConfig {
+    /// Command for every URL, e.g. `"wslview"` or `"firefox --new-tab"`.
+    /// `{url}` is replaced by the link; without it the link is appended as the last argument.
+    pub command: Option<String>,
+    /// Per-scheme overrides keyed by scheme (`https`, `mailto`, ...), same syntax as `command`.
+    pub schemes: HashMap<String, String>,
+}
+
+impl OpenConfig {
+    /// Command line to run for `url`, or `None` to use the system default.
+    pub fn command_for(&self, url: &str) -> Option<Vec<String>> {
+        let scheme = url
+            .split_once(':')
+            .map(|(s, _)| s.to_ascii_lowercase())
+            .unwrap_or_default();
+        let template = self.schemes.get(&scheme).or(self.command.as_ref())?;
+        let mut argv = split_command_line(template);
+        if argv.is_empty() {
+            return None;
+        }
+        if argv.iter().any(|a| a.contains("{url}")) {
+            for arg in &mut argv {
+                *arg = arg.replace("{url}", url);
+            }
+        } else {
+            argv.push(url.to_string());
+        }
+        Some(argv)
+    }
+}
+
+/// Whitespace split honoring single and double quotes (no escapes or expansion).
+fn split_command_line(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClipboardConfig {
+    pub backend: ClipboardBackend,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardBackend {
+    /// OSC52 inside SSH sessions, otherwise the native tool with OSC52 as the fallback.
+    #[default]
+    Auto,
+    /// Terminal escape sequence; works over SSH and in tmux.
+    Osc52,
+    /// `pbcopy`, `wl-copy`, `xclip`/`xsel` or `clip`.
+    Native,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrivacyConfig {
+    /// Hide the list behind a lock screen after this many minutes without a key press
+    /// (0 disables).
+    pub lock_after_minutes: u64,
+    /// Text to type, then Enter, to leave the lock screen (default `unlock`).
+    pub unlock_phrase: Option<String>,
+}
+
+impl PrivacyConfig {
+    pub fn unlock_phrase(&self) -> &str {
+        self.unlock_phrase.as_deref().unwrap_or("unlock")
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeConfig {
+    /// Offset used for "today" and due dates instead of the system's, e.g. `"+09:00"`.
+    pub utc_offset: Option<String>,
+    /// Minutes of estimated work (`e:` tokens) a day holds; more due today is flagged
+    /// (0 disables).
+    pub daily_capacity_minutes: u32,
+}
+
+impl TimeConfig {
+    /// The configured offset; `None` when unset (use the system's).
+    pub fn offset(&self) -> Result<Option<time::UtcOffset>> {
+        let Some(raw) = self.utc_offset.as_deref().map(str::trim) else {
+            return Ok(None);
+        };
+        if raw.eq_ignore_ascii_case("z") || raw.eq_ignore_ascii_case("utc") {
+            return Ok(Some(time::UtcOffset::UTC));
+        }
+        time::UtcOffset::parse(
+            raw,
+            time::macros::format_description!("[offset_hour sign:mandatory]:[offset_minute]"),
+        )
+        .map(Some)
+        .with_context(|| format!("invalid [time] utc_offset {raw:?} (expected e.g. \"+09:00\")"))
+    }
+}
+
+/// `[hooks]`: shell commands run on events with the todo (or sync summary) as JSON on
+/// stdin; see `crate::hooks`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    pub on_add: Option<String>,
+    pub on_done: Option<String>,
+    pub on_overdue: Option<String>,
+    pub on_sync_complete: Option<String>,
+}
+
+impl HooksConfig {
+    pub fn command(&self, event: HookEvent) -> Option<&str> {
+        match event {
+            HookEvent::Add => self.on_add.as_deref(),
+            HookEvent::Done => self.on_done.as_deref(),
+            HookEvent::Overdue => self.on_overdue.as_deref(),
+            HookEvent::SyncComplete => self.on_sync_complete.as_deref(),
+        }
+    }
+}
+
+/// Version tag of `koto config export` files.
+const PROFILE_VERSION: u32 = 1;
+
+/// Sections that run commands; a profile never carries them, since importing one
+/// someone else wrote would run whatever they put there.
+const PROFILE_EXCLUDED: [&str; 2] = ["hooks", "open"];
+
+/// Keys of `[github]` that describe one person's setup (their hosts and token variables)
+/// rather than how a team triages; left out of profiles.
+const PROFILE_PERSONAL_GITHUB: [&str; 1] = ["accounts"];
+
+/// A shareable settings bundle: the team-wide part of the config (`[github]` triage:
+/// rules, saved searches, repository filters) plus a version tag. Personal sections
+/// (`[keys]`, `[jira]`, `[caldav]`, …) stay out, and so do the command-running
+/// `[hooks]` and `[open]`. Credentials never live in `config.toml` (tokens come from the
+/// environment or `gh`), so an exported profile is safe to commit to a team repo.
+#[derive(Debug, Serialize, Deserialize)]
+struct Profile {
+    koto_profile: u32,
+    #[serde(default)]
+    github: GithubSettings,
+}
+
+impl Profile {
+    /// `raw` as a profile table holding only the shareable keys it sets.
+    fn parse(raw: &str) -> Result<toml::Table> {
+        let mut table: toml::Table = toml::from_str(raw).context("not a koto profile")?;
+        if let Some(section) = PROFILE_EXCLUDED.iter().find(|s| table.contains_key(**s)) {
+            anyhow::bail!("a profile cannot set [{section}]; it runs commands");
+        }
+        // Checks the version and the types of what is kept.
+        let profile: Profile = table.clone().try_into().context("not a koto profile")?;
+        if profile.koto_profile > PROFILE_VERSION {
+            anyhow::bail!(
+                "profile version {} is newer than this koto supports ({PROFILE_VERSION})",
+                profile.koto_profile
+            );
+        }
+        table.retain(|key, _| key == "github");
+        drop_personal_keys(&mut table);
+        Ok(table)
+    }
+}
+
+/// Remove [`PROFILE_PERSONAL_GITHUB`] from a profile table.
+fn drop_personal_keys(table: &mut toml::Table) {
+    if let Some(toml::Value::Table(github)) = table.get_mut("github") {
+        github.retain(|key, _| !PROFILE_PERSONAL_GITHUB.contains(&key));
+    }
+}
+
+impl Config {
+    /// Render the shareable settings as a profile for `koto config export`.
+    pub fn export_profile(&self) -> Result<String> {
+        let profile = Profile {
+            koto_profile: PROFILE_VERSION,
+            github: self.github.clone(),
+        };
+        let mut table = toml::Table::try_from(&profile).context("failed to encode profile")?;
+        drop_personal_keys(&mut table);
+        let body = toml::to_string_pretty(&table).context("failed to encode profile")?;
+        Ok(format!(
+            "# koto settings profile; apply with `koto config import <file>`\n{body}"
+        ))
+    }
+
+    /// Apply a file written by [`Config::export_profile`] over `current` (the text of
+    /// `config.toml`): the keys the profile sets win, everything else is kept. A profile
+    /// carrying `[hooks]` or `[open]` is refused.
+    pub fn import_profile(current: &str, raw: &str) -> Result<Self> {
+        let profile = Profile::parse(raw)?;
+        let mut config: toml::Table =
+            toml::from_str(current).context("failed to parse the current config")?;
+        merge_tables(&mut config, profile);
+        config
+            .try_into()
+            .context("the profile does not fit this config")
+    }
+
+    /// Write to `path`, keeping the previous file as `<path>.bak`.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create config dir {}", parent.display()))?;
+        }
+        if path.exists() {
+            let mut backup = path.as_os_str().to_owned();
+            backup.push(".bak");
+            std::fs::copy(path, &backup)
+                .with_context(|| format!("failed to back up {}", path.display()))?;
+        }
+        let body = toml::to_string_pretty(self).context("failed to encode config")?;
+        std::fs::write(path, body).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Load from `path`, or from the default location when `None`. A missing file yields defaults.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(p) => p.to_path_buf(),
+            None => match default_config_path() {
+                Some(p) => p,
+                None => return Ok(Self::default()),
+            },
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(raw) => toml::from_str(&raw)
+                .with_context(|| format!("failed to parse config {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("failed to read config {}", path.display())),
+        }
+    }
+}
+
+/// Copy `over` into `base`, descending into tables both have; other values are replaced.
+fn merge_tables(base: &mut toml::Table, over: toml::Table) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(inner)), toml::Value::Table(value)) => {
+                merge_tables(inner, value)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+pub fn default_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|base| base.join("koto").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_command_prefers_scheme_handler_and_substitutes_url() {
+        let cfg: OpenConfig = toml::from_str(
+            r#"
+command = "firefox --new-tab"
+schemes = { mailto = "'/opt/mail app/compose' --to={url}" }
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            cfg.command_for("https://example.com/a b").unwrap(),
+            ["firefox", "--new-tab", "https://example.com/a b"]
+        );
+        assert_eq!(
+            cfg.command_for("mailto:me@example.com").unwrap(),
+            ["/opt/mail app/compose", "--to=mailto:me@example.com"]
+        );
+        assert!(OpenConfig::default().command_for("https://x").is_none());
+    }
+
+    #[test]
+    fn utc_offset_override_parses_signed_offsets() {
+        let offset = |raw: &str| {
+            TimeConfig {
+                utc_offset: Some(raw.to_string()),
+                ..TimeConfig::default()
+            }
+            .offset()
+        };
+        assert_eq!(TimeConfig::default().offset().unwrap(), None);
+        assert_eq!(offset("+09:00").unwrap().unwrap().whole_seconds(), 9 * 3600);
+        assert_eq!(offset("-05:30").unwrap().unwrap().whole_seconds(), -19_800);
+        assert_eq!(offset("UTC").unwrap(), Some(time::UtcOffset::UTC));
+        assert!(offset("9").is_err());
+    }
+
+    #[test]
+    fn profiles_never_carry_commands() {
+        let mut config = Config::default();
+        config.hooks.on_add = Some("curl -d @- https://example.com".to_string());
+        config.open.command = Some("wslview".to_string());
+        config.github.days = 7;
+        let exported = config.export_profile().unwrap();
+        assert!(!exported.contains("[hooks]") && !exported.contains("[open]"));
+        assert!(!exported.contains("curl") && !exported.contains("wslview"));
+        assert_eq!(
+            Config::import_profile("", &exported).unwrap().github.days,
+            7
+        );
+
+        let hostile = format!("{exported}\n[hooks]\non_add = \"rm -rf ~\"\n");
+        let err = Config::import_profile("", &hostile).unwrap_err();
+        assert!(err.to_string().contains("[hooks]"));
+    }
+
+    #[test]
+    fn importing_a_profile_keeps_personal_settings() {
+        let mine = r#"
+[keys]
+delete_guard_window_ms = 300
+
+[github]
+days = 30
+ignore_drafts = true
+accounts = [{ api_url = "https://ghe.example.com/api/v3" }]
+
+[caldav]
+url = "https://cloud.example.com/dav/tasks/"
+username = "me"
+
+[hooks]
+on_done = "notify-send done"
+"#;
+        let mut team = Config::default();
+        team.github.days = 7;
+        team.github.include_repos = vec!["acme/*".to_string()];
+        team.github.accounts = vec![GithubAccount {
+            api_url: "https://ghe.acme.example/api/v3".to_string(),
+            token_env: None,
+        }];
+        team.caldav.username = "teammate".to_string();
+        let exported = team.export_profile().unwrap();
+        assert!(!exported.contains("teammate") && !exported.contains("ghe.acme"));
+        assert!(!exported.contains("[caldav]") && !exported.contains("[keys]"));
+
+        let merged = Config::import_profile(mine, &exported).unwrap();
+        assert_eq!(merged.github.days, 7);
+        assert_eq!(merged.github.include_repos, ["acme/*"]);
+        // The exported profile writes every [github] key, defaults included.
+        assert!(!merged.github.ignore_drafts);
+        assert_eq!(merged.github.accounts.len(), 1);
+        assert_eq!(
+            merged.github.accounts[0].api_url,
+            "https://ghe.example.com/api/v3"
+        );
+        assert_eq!(merged.caldav.username, "me");
+        assert_eq!(merged.keys.delete_guard_window_ms, 300);
+        assert_eq!(merged.hooks.on_done.as_deref(), Some("notify-send done"));
+
+        // Keys a hand-written profile leaves out are not reset.
+        let partial = "koto_profile = 1\n[github]\ndays = 3\n";
+        let merged = Config::import_profile(mine, partial).unwrap();
+        assert_eq!((merged.github.days, merged.github.ignore_drafts), (3, true));
+        assert!(Config::import_profile(mine, "[github]\ndays = 3\n").is_err());
+    }
+}
@@ -0,0 +1,356 @@
+//! `koto daemon`: syncs GitHub on an interval, raises a desktop notification for each new
+//! review request and announces its syncs on `<db>.sock`, where a TUI attaches instead of
+//! syncing GitHub itself.
+//!
+//! The socket speaks JSON lines: clients send `Request`s, and every client gets every
+//! `Event`.
+
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{GithubConfig, fetch_attention_all, plan_sync};
+use crate::cli::run_sync_hook;
+use crate::config::Config;
+use crate::domain::todo::GITHUB_PR_KEY_PREFIX;
+use crate::repo::TodoRepository;
+use crate::repo::github::QueryError;
+use crate::repo::lock::{LockHolder, StoreLock};
+use crate::usecase::sync;
+use crate::worker::Notify;
+
+/// New review requests announced one by one; more than this get a single summary.
+const NOTIFY_ONE_BY_ONE: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Request {
+    /// Sync now instead of at the next interval.
+    Sync,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    Syncing,
+    /// The results are in the store.
+    Synced {
+        added: usize,
+        prs: usize,
+        /// A query failed, so some PRs may be missing.
+        partial: bool,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+/// Where the daemon of the store at `db_path` listens.
+pub fn socket_path(db_path: &Path) -> PathBuf {
+    let mut name = db_path.as_os_str().to_owned();
+    name.push(".sock");
+    PathBuf::from(name)
+}
+
+pub struct DaemonOptions {
+    pub interval: Duration,
+    /// Desktop notifications for new review requests.
+    pub notify: bool,
+}
+
+/// Sync until killed. Fails right away when another daemon serves the store.
+#[cfg(unix)]
+pub fn run(
+    repo: &mut dyn TodoRepository,
+    config: &Config,
+    accounts: Vec<GithubConfig>,
+    db_path: &Path,
+    opts: &DaemonOptions,
+) -> Result<()> {
+    use std::os::unix::net::UnixListener;
+
+    let _lock = StoreLock::acquire(db_path, LockHolder::Daemon)?;
+    let path = socket_path(db_path);
+    // Left behind by a daemon that was killed; the lock says it is not running.
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)
+        .with_context(|| format!("failed to listen on {}", path.display()))?;
+    let _socket = RemoveOnDrop(path.clone());
+    let clients: Arc<Mutex<Vec<std::os::unix::net::UnixStream>>> = Arc::default();
+    let (requests_tx, requests) = mpsc::channel::<Request>();
+    let accepted = Arc::clone(&clients);
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let Ok(writer) = stream.try_clone() else {
+                continue;
+            };
+            accepted
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(writer);
+            let requests_tx = requests_tx.clone();
+            std::thread::spawn(move || {
+                for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                    if let Ok(request) = serde_json::from_str::<Request>(&line) {
+                        let _ = requests_tx.send(request);
+                    }
+                }
+            });
+        }
+    });
+    eprintln!(
+        "koto daemon: syncing every {} min, listening on {}",
+        opts.interval.as_secs() / 60,
+        path.display()
+    );
+
+    loop {
+        broadcast(&clients, &Event::Syncing);
+        let event = match sync_once(repo, config, &accounts, opts.notify) {
+            Ok(event) => event,
+            Err(e) => Event::Failed {
+                message: format!("{e:#}"),
+            },
+        };
+        match &event {
+            Event::Synced { added, prs, .. } => {
+                eprintln!("koto daemon: {prs} PRs, {added} todos added");
+            }
+            Event::Failed { message } => eprintln!("koto daemon: sync failed: {message}"),
+            Event::Syncing => {}
+        }
+        broadcast(&clients, &event);
+        match requests.recv_timeout(opts.interval) {
+            Ok(Request::Sync) | Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+        // Requests that piled up during the sync are served by the next one.
+        while requests.try_recv().is_ok() {}
+    }
+}
+
+#[cfg(not(unix))]
+pub fn run(
+    _repo: &mut dyn TodoRepository,
+    _config: &Config,
+    _accounts: Vec<GithubConfig>,
+    _db_path: &Path,
+    _opts: &DaemonOptions,
+) -> Result<()> {
+    anyhow::bail!("koto daemon needs Unix domain sockets, which this platform lacks")
+}
+
+struct RemoveOnDrop(PathBuf);
+
+impl Drop for RemoveOnDrop {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Send `event` to every client, forgetting the ones that hung up.
+#[cfg(unix)]
+fn broadcast(clients: &Mutex<Vec<std::os::unix::net::UnixStream>>, event: &Event) {
+    let Ok(mut line) = serde_json::to_string(event) else {
+        return;
+    };
+    line.push('\n');
+    clients
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .retain_mut(|client| client.write_all(line.as_bytes()).is_ok());
+}
+
+/// One GitHub sync, stored like `koto sync` stores it.
+fn sync_once(
+    repo: &mut dyn TodoRepository,
+    config: &Config,
+    accounts: &[GithubConfig],
+    notify: bool,
+) -> Result<Event> {
+    let mut accounts = accounts.to_vec();
+    plan_sync(&mut accounts, repo, false)?;
+    let started_at = crate::now_unix();
+    let attention =
+        crate::worker::block_on(fetch_attention_all(accounts.clone(), Arc::new(|_| {})))?.map_err(
+            |e| {
+                let e = QueryError::from_anyhow(e);
+                match e.kind.hint() {
+                    Some(hint) => anyhow::anyhow!("{e}; hint: {hint}"),
+                    None => e.into(),
+                }
+            },
+        )?;
+    let known: HashSet<String> = repo
+        .all()?
+        .into_iter()
+        .filter_map(|t| t.external_key)
+        .collect();
+    let summary = sync::apply_attention(repo, &config.github, &attention)?;
+    if !attention.is_partial() {
+        let hosts: Vec<String> = accounts.iter().map(|cfg| cfg.host()).collect();
+        sync::record_watermarks(repo, &hosts, started_at)?;
+    }
+    if notify {
+        let requested: Vec<String> = repo
+            .all()?
+            .into_iter()
+            .filter(|t| {
+                t.external_key
+                    .as_ref()
+                    .is_some_and(|k| k.starts_with(GITHUB_PR_KEY_PREFIX) && !known.contains(k))
+            })
+            .map(|t| t.title)
+            .collect();
+        announce(&requested);
+    }
+    let added = summary.added
+        + summary.follow_ups
+        + summary.own_prs
+        + summary.search_added
+        + summary.watched;
+    run_sync_hook(
+        config,
+        "github",
+        added,
+        summary.unmatched + summary.watches_finished,
+    )?;
+    Ok(Event::Synced {
+        added,
+        prs: attention.prs.len(),
+        partial: attention.is_partial(),
+    })
+}
+
+fn announce(requested: &[String]) {
+    if requested.len() > NOTIFY_ONE_BY_ONE {
+        desktop_notification("koto", &format!("{} new review requests", requested.len()));
+        return;
+    }
+    for title in requested {
+        desktop_notification("Review requested", title);
+    }
+}
+
+/// Best effort: `osascript` on macOS, `notify-send` elsewhere; failures are ignored.
+fn desktop_notification(title: &str, body: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut command = std::process::Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {} with title {}",
+            quote(body),
+            quote(title)
+        ));
+        command
+    } else {
+        let mut command = std::process::Command::new("notify-send");
+        command.args(["--app-name=koto", title, body]);
+        command
+    };
+    let _ = command
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+}
+
+/// A TUI's connection to the daemon of its store.
+pub struct DaemonClient {
+    #[cfg(unix)]
+    stream: std::os::unix::net::UnixStream,
+    events: Receiver<Event>,
+    notify: Arc<OnceLock<Notify>>,
+}
+
+impl DaemonClient {
+    /// Attach to the daemon of the store at `db_path`, if one is listening.
+    #[cfg(unix)]
+    pub fn connect(db_path: &Path) -> Option<Self> {
+        let stream = std::os::unix::net::UnixStream::connect(socket_path(db_path)).ok()?;
+        let reader = stream.try_clone().ok()?;
+        let (tx, events) = mpsc::channel();
+        let notify: Arc<OnceLock<Notify>> = Arc::default();
+        let wake = Arc::clone(&notify);
+        std::thread::spawn(move || {
+            for line in BufReader::new(reader).lines().map_while(Result::ok) {
+                let Ok(event) = serde_json::from_str::<Event>(&line) else {
+                    continue;
+                };
+                if tx.send(event).is_err() {
+                    return;
+                }
+                if let Some(notify) = wake.get() {
+                    notify();
+                }
+            }
+        });
+        Some(Self {
+            stream,
+            events,
+            notify,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn connect(_db_path: &Path) -> Option<Self> {
+        None
+    }
+
+    /// Install the wake-up hook; only the first call has an effect.
+    pub fn set_notify(&self, notify: Notify) {
+        let _ = self.notify.set(notify);
+    }
+
+    /// Ask for a sync now; false when the daemon is gone.
+    pub fn request_sync(&mut self) -> bool {
+        #[cfg(unix)]
+        {
+            let Ok(mut line) = serde_json::to_string(&Request::Sync) else {
+                return false;
+            };
+            line.push('\n');
+            self.stream.write_all(line.as_bytes()).is_ok()
+        }
+        #[cfg(not(unix))]
+        false
+    }
+
+    /// Events received since the last call.
+    pub fn poll(&self) -> Vec<Event> {
+        self.events.try_iter().collect()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_and_requests_are_json_lines() {
+        let synced = Event::Synced {
+            added: 2,
+            prs: 5,
+            partial: false,
+        };
+        let line = serde_json::to_string(&synced).unwrap();
+        assert_eq!(
+            line,
+            r#"{"event":"synced","added":2,"prs":5,"partial":false}"#
+        );
+        assert_eq!(serde_json::from_str::<Event>(&line).unwrap(), synced);
+        assert_eq!(
+            serde_json::from_str::<Request>(r#"{"cmd":"sync"}"#).unwrap(),
+            Request::Sync
+        );
+        assert_eq!(
+            socket_path(Path::new("/tmp/todos.sqlite")),
+            Path::new("/tmp/todos.sqlite.sock")
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use time::macros::format_description;
+
+use super::clock;
+use super::todo::{Priority, TodoId};
+
+/// What an activity log entry records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityKind {
+    Add,
+    /// Title, notes, priority, due date, parent or position changed.
+    Edit,
+    Done,
+    Reopen,
+    /// Archived (`c`, or a saved-search item that stopped matching).
+    Archive,
+    Delete,
+    /// A provider sync finished; `todo_id` is unset.
+    Sync,
+}
+
+impl ActivityKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ActivityKind::Add => "add",
+            ActivityKind::Edit => "edit",
+            ActivityKind::Done => "done",
+            ActivityKind::Reopen => "reopen",
+            ActivityKind::Archive => "archive",
+            ActivityKind::Delete => "delete",
+            ActivityKind::Sync => "sync",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        Some(match raw {
+            "add" => ActivityKind::Add,
+            "edit" => ActivityKind::Edit,
+            "done" => ActivityKind::Done,
+            "reopen" => ActivityKind::Reopen,
+            "archive" => ActivityKind::Archive,
+            "delete" => ActivityKind::Delete,
+            "sync" => ActivityKind::Sync,
+            _ => return None,
+        })
+    }
+
+    /// `Done` or `Reopen`, for a toggle that left the todo `done`.
+    pub fn toggled(done: bool) -> Self {
+        if done {
+            ActivityKind::Done
+        } else {
+            ActivityKind::Reopen
+        }
+    }
+}
+
+/// One line of the append-only activity log (`koto log`). Every repository mutation
+/// writes one, so the log can back undo, audits and reports.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Activity {
+    /// Unix seconds.
+    pub at: i64,
+    pub kind: ActivityKind,
+    pub todo_id: Option<TodoId>,
+    /// Short description: the title for add / done / delete, what changed for edits.
+    pub detail: String,
+}
+
+impl Activity {
+    pub fn now(kind: ActivityKind, todo_id: Option<TodoId>, detail: impl Into<String>) -> Self {
+        Self {
+            at: crate::now_unix(),
+            kind,
+            todo_id,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Detail of a priority / due date edit, e.g. `priority High, due 2025-01-05 23:59`.
+pub fn describe_meta(priority: Priority, due: Option<SystemTime>) -> String {
+    format!("priority {priority:?}, due {}", describe_due(due))
+}
+
+pub fn describe_due(due: Option<SystemTime>) -> String {
+    match due {
+        Some(due) => clock::to_local(due)
+            .format(format_description!("[year]-[month]-[day] [hour]:[minute]"))
+            .unwrap_or_else(|_| "?".into()),
+        None => "none".to_string(),
+    }
+}
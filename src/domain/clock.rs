@@ -0,0 +1,57 @@
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use time::{Date, OffsetDateTime, Time, UtcOffset};
+
+static LOCAL_OFFSET: OnceLock<UtcOffset> = OnceLock::new();
+static OFFSET_OVERRIDE: OnceLock<UtcOffset> = OnceLock::new();
+
+/// Resolve the local UTC offset once. Call this early in `main`: on Unix the `time` crate
+/// refuses to read the offset once other threads exist, and we fall back to UTC then.
+pub fn init_local_offset() {
+    local_offset();
+}
+
+/// Use `offset` instead of the system's (`[time] utc_offset`); only the first call counts.
+pub fn override_local_offset(offset: UtcOffset) {
+    let _ = OFFSET_OVERRIDE.set(offset);
+}
+
+/// Offset "today", due dates and sync cutoffs are computed in.
+pub fn local_offset() -> UtcOffset {
+    if let Some(offset) = OFFSET_OVERRIDE.get() {
+        return *offset;
+    }
+    *LOCAL_OFFSET.get_or_init(|| UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC))
+}
+
+pub fn now_local() -> OffsetDateTime {
+    OffsetDateTime::now_utc().to_offset(local_offset())
+}
+
+/// Today's calendar date in local time.
+pub fn today() -> Date {
+    now_local().date()
+}
+
+pub fn to_local(t: std::time::SystemTime) -> OffsetDateTime {
+    OffsetDateTime::from(t).to_offset(local_offset())
+}
+
+/// `date` at `time` of day in local time, for due tokens with a time (`d:+1@9am`).
+pub fn at_local(date: Date, time: Time) -> SystemTime {
+    let ts = date
+        .with_time(time)
+        .assume_offset(local_offset())
+        .unix_timestamp();
+    UNIX_EPOCH + Duration::from_secs(ts.max(0) as u64)
+}
+
+/// Last second of `date` in local time; date-only due dates are stored this way.
+pub fn end_of_day(date: Date) -> SystemTime {
+    let dt = date
+        .with_hms(23, 59, 59)
+        .unwrap_or_else(|_| date.with_hms(0, 0, 0).unwrap());
+    let ts = dt.assume_offset(local_offset()).unix_timestamp();
+    UNIX_EPOCH + Duration::from_secs(ts.max(0) as u64)
+}
@@ -0,0 +1,70 @@
+//! "Blocked by" links between todos: a todo waits until every todo it depends on is done.
+//! Links are `(todo, blocked_by)` pairs.
+
+use std::collections::{HashMap, HashSet};
+
+use super::todo::{Todo, TodoId};
+
+/// Whether making `id` wait for `blocked_by` would, through the existing `links`, make a
+/// todo wait for itself.
+pub fn creates_cycle(links: &[(TodoId, TodoId)], id: TodoId, blocked_by: TodoId) -> bool {
+    let mut stack = vec![blocked_by];
+    let mut seen = HashSet::new();
+    while let Some(current) = stack.pop() {
+        if current == id {
+            return true;
+        }
+        if seen.insert(current) {
+            stack.extend(
+                links
+                    .iter()
+                    .filter(|(todo, _)| *todo == current)
+                    .map(|(_, blocker)| *blocker),
+            );
+        }
+    }
+    false
+}
+
+/// The blockers of each todo that are still open. Blockers outside `todos`, done or
+/// archived no longer hold anything up.
+pub fn open_blockers(links: &[(TodoId, TodoId)], todos: &[Todo]) -> HashMap<TodoId, Vec<TodoId>> {
+    let open: HashSet<TodoId> = todos
+        .iter()
+        .filter(|t| !t.is_closed() && !t.archived)
+        .map(|t| t.id)
+        .collect();
+    let mut blockers: HashMap<TodoId, Vec<TodoId>> = HashMap::new();
+    for (todo, blocker) in links {
+        if open.contains(blocker) {
+            blockers.entry(*todo).or_default().push(*blocker);
+        }
+    }
+    blockers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::todo::{Priority, Status};
+
+    #[test]
+    fn links_may_not_loop_and_done_blockers_release() {
+        let [a, b, c] = ["a", "b", "c"].map(|title| Todo::with_meta(title, Priority::Medium, None));
+        // c waits for b, which waits for a.
+        let links = vec![(b.id, a.id), (c.id, b.id)];
+        assert!(creates_cycle(&links, a.id, c.id));
+        assert!(creates_cycle(&links, a.id, a.id));
+        assert!(!creates_cycle(&links, c.id, a.id));
+
+        let blockers = open_blockers(&links, &[a.clone(), b.clone(), c.clone()]);
+        assert_eq!(blockers[&c.id], [b.id]);
+        assert_eq!(blockers[&b.id], [a.id]);
+        assert!(!blockers.contains_key(&a.id));
+
+        let mut b = b;
+        b.status = Status::Done;
+        let blockers = open_blockers(&links, &[a, b, c.clone()]);
+        assert!(!blockers.contains_key(&c.id));
+    }
+}
@@ -0,0 +1,96 @@
+use std::time::SystemTime;
+
+use time::Date;
+
+use super::clock;
+
+/// Calendar days from `today` until `due`, both in local time (negative when overdue).
+pub fn days_until(due: SystemTime, today: Date) -> i64 {
+    let due_date = clock::to_local(due).date();
+    (due_date.to_julian_day() - today.to_julian_day()) as i64
+}
+
+/// A due date as set: the instant, and whether a time of day was given. Date-only dues
+/// are stored as the last second of their local day, but the flag, not the instant,
+/// says which kind a due is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Due {
+    pub at: SystemTime,
+    pub has_time: bool,
+}
+
+impl Due {
+    /// Due some time on `date`.
+    pub fn day(date: Date) -> Self {
+        Self {
+            at: clock::end_of_day(date),
+            has_time: false,
+        }
+    }
+
+    /// Due at `at`, a time of day.
+    pub fn timed(at: SystemTime) -> Self {
+        Self { at, has_time: true }
+    }
+}
+
+/// Agenda section a due date falls into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DueBucket {
+    Overdue,
+    Today,
+    Tomorrow,
+    /// Within the next seven days, after tomorrow.
+    ThisWeek,
+    Later,
+}
+
+impl DueBucket {
+    pub fn of(due: SystemTime, today: Date) -> Self {
+        match days_until(due, today) {
+            d if d < 0 => DueBucket::Overdue,
+            0 => DueBucket::Today,
+            1 => DueBucket::Tomorrow,
+            d if d < 7 => DueBucket::ThisWeek,
+            _ => DueBucket::Later,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DueBucket::Overdue => "Overdue",
+            DueBucket::Today => "Today",
+            DueBucket::Tomorrow => "Tomorrow",
+            DueBucket::ThisWeek => "This week",
+            DueBucket::Later => "Later",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, time};
+
+    use super::*;
+
+    #[test]
+    fn buckets_roll_over_at_local_midnight() {
+        let today = date!(2026 - 03 - 11);
+        let at = |days: i64, time| clock::at_local(today + time::Duration::days(days), time);
+        let cases = [
+            (at(-30, time!(12:00)), DueBucket::Overdue),
+            (at(-1, time!(23:59:59)), DueBucket::Overdue),
+            (at(0, time!(0:00)), DueBucket::Today),
+            (at(0, time!(23:59:59)), DueBucket::Today),
+            (at(1, time!(0:00)), DueBucket::Tomorrow),
+            (at(1, time!(23:59:59)), DueBucket::Tomorrow),
+            (at(2, time!(0:00)), DueBucket::ThisWeek),
+            (at(6, time!(23:59:59)), DueBucket::ThisWeek),
+            (at(7, time!(0:00)), DueBucket::Later),
+            (Due::day(date!(2026 - 04 - 30)).at, DueBucket::Later),
+        ];
+        for (n, (due, bucket)) in cases.into_iter().enumerate() {
+            assert_eq!(DueBucket::of(due, today), bucket, "case {n}");
+        }
+    }
+}
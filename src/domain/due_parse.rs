@@ -0,0 +1,245 @@
+//! Due dates typed by hand: in the `t` prompt and as tokens in a new todo's title.
+
+use std::time::SystemTime;
+
+use time::{Date, Duration, Month, Time, Weekday, macros::format_description};
+
+use super::clock;
+use super::due::Due;
+
+/// A parsed due: a calendar date and, when one was given, a time of day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DueSpec {
+    pub date: Date,
+    pub time: Option<Time>,
+}
+
+impl DueSpec {
+    /// The due this sets: at the local time of day, or the whole day without one.
+    pub fn due(self) -> Due {
+        match self.time {
+            Some(time) => Due::timed(clock::at_local(self.date, time)),
+            None => Due::day(self.date),
+        }
+    }
+
+    /// Local instant a todo scheduled for this date starts showing; without a time, the
+    /// start of the day.
+    pub fn start(self) -> SystemTime {
+        clock::at_local(self.date, self.time.unwrap_or(Time::MIDNIGHT))
+    }
+}
+
+/// Parse a due relative to `today`. `Ok(None)` means the text is not a due date at all;
+/// `Err` means it looks like one but is malformed (`+x`, `2025-13-01`, `today@25:00`).
+///
+/// Dates: `today`, `tomorrow`, `+3`, `2025-01-05`, weekdays (`fri`, `friday`: the next
+/// one after today), `next monday` (that day of next week, weeks starting on Monday),
+/// `in 3 days` / `in 2 weeks` / `in 1 month`, and `end of month` (`eom`). A `d:` or
+/// `due:` prefix is allowed. A time of day follows `@` (`fri@9am`, `@17:00` for today)
+/// or ISO `T` (`2025-01-05T14:00`).
+pub fn parse(input: &str, today: Date) -> Result<Option<DueSpec>, String> {
+    let lower = input.to_lowercase();
+    let text = lower.split_whitespace().collect::<Vec<_>>().join(" ");
+    let text = text
+        .strip_prefix("d:")
+        .or_else(|| text.strip_prefix("due:"))
+        .unwrap_or(&text);
+
+    let bytes = text.as_bytes();
+    let iso_time = bytes.len() > 11 && bytes[4] == b'-' && bytes[10] == b't';
+    let (date_part, time_part) = match text.split_once('@') {
+        Some((date, time)) => (date.trim_end(), Some(time.trim_start())),
+        None if iso_time => (&text[..10], Some(&text[11..])),
+        None => (text, None),
+    };
+    let date = if date_part.is_empty() && time_part.is_some() {
+        // `@5pm` alone: later today. Other words with an `@` are not due tokens.
+        if parse_time_of_day(time_part.unwrap_or_default()).is_none() {
+            return Ok(None);
+        }
+        today
+    } else {
+        match parse_date(date_part, today)? {
+            Some(date) => date,
+            None => return Ok(None),
+        }
+    };
+    let time = match time_part {
+        None => None,
+        Some(time) => Some(
+            parse_time_of_day(time)
+                .ok_or_else(|| "Use a time like 14:00, 9am or 9:30pm after @".to_string())?,
+        ),
+    };
+    Ok(Some(DueSpec { date, time }))
+}
+
+fn parse_date(text: &str, today: Date) -> Result<Option<Date>, String> {
+    match text {
+        "today" | "tod" | "t" => return Ok(Some(today)),
+        "tomorrow" | "tm" | "next" => return Ok(Some(today.saturating_add(Duration::days(1)))),
+        "end of month" | "eom" => return Ok(Some(end_of_month(today))),
+        _ => {}
+    }
+    if let Some(rest) = text.strip_prefix('+') {
+        let days: i64 = rest
+            .parse()
+            .map_err(|_| "Relative due must be a number (e.g. +3)".to_string())?;
+        return Ok(Some(today.saturating_add(Duration::days(days))));
+    }
+    if let Some(day) = weekday(text) {
+        return Ok(Some(today.next_occurrence(day)));
+    }
+    if let Some(day) = text.strip_prefix("next ").and_then(weekday) {
+        let next_week = today.saturating_add(Duration::days(
+            7 - today.weekday().number_days_from_monday() as i64,
+        ));
+        return Ok(Some(next_week.saturating_add(Duration::days(
+            day.number_days_from_monday() as i64,
+        ))));
+    }
+    if let Some(rest) = text.strip_prefix("in ") {
+        return Ok(parse_offset(rest, today));
+    }
+
+    if text.len() == 10 && text.chars().nth(4) == Some('-') {
+        let fmt = format_description!("[year]-[month]-[day]");
+        let date =
+            Date::parse(text, &fmt).map_err(|_| "Use YYYY-MM-DD for due date".to_string())?;
+        return Ok(Some(date));
+    }
+
+    Ok(None)
+}
+
+/// `3 days`, `2 weeks`, `1 month` (the same day next month, or its last day).
+fn parse_offset(text: &str, today: Date) -> Option<Date> {
+    let (count, unit) = text.split_once(' ')?;
+    let count: i64 = count.parse().ok()?;
+    match unit.trim_end_matches('s') {
+        "day" => Some(today.saturating_add(Duration::days(count))),
+        "week" => Some(today.saturating_add(Duration::weeks(count))),
+        "month" => {
+            let months = today.year() as i64 * 12 + today.month() as i64 - 1 + count;
+            let year = i32::try_from(months.div_euclid(12)).ok()?;
+            let month = Month::try_from(months.rem_euclid(12) as u8 + 1).ok()?;
+            let day = today.day().min(month.length(year));
+            Date::from_calendar_date(year, month, day).ok()
+        }
+        _ => None,
+    }
+}
+
+fn end_of_month(today: Date) -> Date {
+    today
+        .replace_day(today.month().length(today.year()))
+        .unwrap_or(today)
+}
+
+/// `mon` / `monday` … `sun` / `sunday` (plus `tues`, `thur`, `thurs`).
+pub fn weekday(word: &str) -> Option<Weekday> {
+    Some(match word {
+        "mon" | "monday" => Weekday::Monday,
+        "tue" | "tues" | "tuesday" => Weekday::Tuesday,
+        "wed" | "wednesday" => Weekday::Wednesday,
+        "thu" | "thur" | "thurs" | "thursday" => Weekday::Thursday,
+        "fri" | "friday" => Weekday::Friday,
+        "sat" | "saturday" => Weekday::Saturday,
+        "sun" | "sunday" => Weekday::Sunday,
+        _ => return None,
+    })
+}
+
+/// `14:00`, `9`, `9am`, `9:30pm`, `12am` (midnight).
+pub fn parse_time_of_day(text: &str) -> Option<Time> {
+    let (clock_part, meridiem) = if let Some(rest) = text.strip_suffix("am") {
+        (rest, Some(false))
+    } else if let Some(rest) = text.strip_suffix("pm") {
+        (rest, Some(true))
+    } else {
+        (text, None)
+    };
+    let (hour, minute) = match clock_part.split_once(':') {
+        Some((h, m)) if m.len() == 2 => (h.parse::<u8>().ok()?, m.parse::<u8>().ok()?),
+        Some(_) => return None,
+        None => (clock_part.parse::<u8>().ok()?, 0),
+    };
+    let hour = match meridiem {
+        Some(pm) if (1..=12).contains(&hour) => hour % 12 + if pm { 12 } else { 0 },
+        Some(_) => return None,
+        None => hour,
+    };
+    Time::from_hms(hour, minute, 0).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, time};
+
+    use super::*;
+
+    // A Wednesday.
+    const TODAY: Date = date!(2025 - 01 - 15);
+
+    fn day(input: &str) -> Date {
+        parse(input, TODAY).unwrap().expect(input).date
+    }
+
+    #[test]
+    fn relative_and_absolute_dates() {
+        assert_eq!(day("today"), TODAY);
+        assert_eq!(day("d:tomorrow"), date!(2025 - 01 - 16));
+        assert_eq!(day("+3"), date!(2025 - 01 - 18));
+        assert_eq!(day("2025-02-01"), date!(2025 - 02 - 01));
+        assert_eq!(day("in 3 days"), date!(2025 - 01 - 18));
+        assert_eq!(day("in 2 weeks"), date!(2025 - 01 - 29));
+        assert_eq!(day("in  1   week"), date!(2025 - 01 - 22));
+        assert_eq!(day("in 1 month"), date!(2025 - 02 - 15));
+        assert_eq!(
+            parse_offset("1 month", date!(2025 - 01 - 31)),
+            Some(date!(2025 - 02 - 28))
+        );
+        assert_eq!(day("End of Month"), date!(2025 - 01 - 31));
+        assert_eq!(day("eom"), date!(2025 - 01 - 31));
+    }
+
+    #[test]
+    fn weekdays_count_from_tomorrow_and_next_means_next_week() {
+        assert_eq!(day("fri"), date!(2025 - 01 - 17));
+        assert_eq!(day("Monday"), date!(2025 - 01 - 20));
+        // Today is a Wednesday, so a bare `wed` is a week away.
+        assert_eq!(day("wed"), date!(2025 - 01 - 22));
+        assert_eq!(day("next monday"), date!(2025 - 01 - 20));
+        assert_eq!(day("due:next fri"), date!(2025 - 01 - 24));
+        assert_eq!(day("next sun"), date!(2025 - 01 - 26));
+    }
+
+    #[test]
+    fn times_of_day() {
+        let at = |input: &str| parse(input, TODAY).unwrap().unwrap();
+        assert_eq!(
+            at("fri@9am"),
+            DueSpec {
+                date: date!(2025 - 01 - 17),
+                time: Some(time!(9:00))
+            }
+        );
+        assert_eq!(at("next monday @ 14:30").time, Some(time!(14:30)));
+        assert_eq!(at("@5pm").date, TODAY);
+        assert_eq!(at("2025-01-20T08:15").time, Some(time!(8:15)));
+        assert_eq!(parse_time_of_day("12am"), Some(time!(0:00)));
+        assert_eq!(parse_time_of_day("9:30pm"), Some(time!(21:30)));
+        assert_eq!(parse_time_of_day("13pm"), None);
+    }
+
+    #[test]
+    fn non_dates_and_malformed_dates() {
+        for word in ["milk", "in", "in two weeks", "me@example.com", "@home"] {
+            assert_eq!(parse(word, TODAY), Ok(None), "{word}");
+        }
+        assert!(parse("+x", TODAY).is_err());
+        assert!(parse("2025-13-01", TODAY).is_err());
+        assert!(parse("today@25:00", TODAY).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+//! fzf-style matching for the jump finder (`Ctrl-p`): the query's characters must appear
+//! in the text in order, not necessarily next to each other.
+
+/// How well `query` matches `text` (higher is better) and the char indices of `text` it
+/// matched, for highlighting; `None` when it does not match. Case-insensitive. Runs of
+/// consecutive characters and matches at the start of a word score higher, gaps lower.
+pub fn score(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(fold)
+        .collect();
+    let chars: Vec<char> = text.chars().collect();
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+    // The first place the whole query fits, then the shortest match ending there.
+    let mut q = 0;
+    let mut end = None;
+    for (i, &c) in chars.iter().enumerate() {
+        if fold(c) == query[q] {
+            q += 1;
+            if q == query.len() {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+    let end = end?;
+    let mut positions = vec![0; query.len()];
+    let mut q = query.len();
+    for i in (0..=end).rev() {
+        if fold(chars[i]) == query[q - 1] {
+            q -= 1;
+            positions[q] = i;
+            if q == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut score = 0;
+    for (n, &at) in positions.iter().enumerate() {
+        score += 16;
+        if at == 0 || word_start(chars[at - 1], chars[at]) {
+            score += 8;
+        }
+        if n > 0 {
+            let gap = at - positions[n - 1] - 1;
+            score += if gap == 0 { 8 } else { -(gap.min(8) as i64) };
+        }
+    }
+    // Earlier matches first, then shorter titles.
+    score -= positions[0].min(16) as i64;
+    score -= (chars.len() / 16).min(4) as i64;
+    Some((score, positions))
+}
+
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn word_start(before: char, c: char) -> bool {
+    !before.is_alphanumeric() || (before.is_lowercase() && c.is_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn characters_match_in_order_and_word_starts_rank_first() {
+        assert_eq!(
+            score("rvw", "Review PR").map(|(_, at)| at),
+            Some(vec![0, 2, 5])
+        );
+        assert_eq!(score("wvr", "Review PR"), None);
+        assert_eq!(score("", "anything").map(|(s, _)| s), Some(0));
+
+        let rank = |query: &str| score(query, "Write release notes").unwrap().0;
+        let other = |query: &str| score(query, "Reply to the thread on slack").unwrap().0;
+        // "rn" hits the starts of "release notes" here, but only scattered letters there.
+        assert!(rank("rn") > other("rn"));
+        // Consecutive letters beat the same letters spread out.
+        assert!(score("note", "notes").unwrap().0 > score("note", "n o t e").unwrap().0);
+        // The shortest span is highlighted, not the first letters found.
+        assert_eq!(score("ab", "a xab").map(|(_, at)| at), Some(vec![3, 4]));
+    }
+}
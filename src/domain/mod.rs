@@ -1 +1,11 @@
+pub mod activity;
+pub mod clock;
+pub mod dependency;
+pub mod due;
+pub mod due_parse;
+pub mod fuzzy;
+pub mod glob;
+pub mod query;
+pub mod subtask;
+pub mod time_entry;
 pub mod todo;
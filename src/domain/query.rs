@@ -0,0 +1,55 @@
+/// Which part of the store a search looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchScope {
+    /// Not done and not archived.
+    #[default]
+    Open,
+    /// Everything, including completed and archived items.
+    All,
+    /// Only items archived by "clear done".
+    Archived,
+}
+
+impl SearchScope {
+    pub fn next(self) -> Self {
+        match self {
+            SearchScope::Open => SearchScope::All,
+            SearchScope::All => SearchScope::Archived,
+            SearchScope::Archived => SearchScope::Open,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SearchScope::Open => "open",
+            SearchScope::All => "all",
+            SearchScope::Archived => "archived",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TodoQuery {
+    /// Words matched against the title and notes (case-insensitive; each word must
+    /// appear); empty matches everything.
+    pub text: String,
+    pub scope: SearchScope,
+}
+
+impl TodoQuery {
+    pub fn matches_scope(&self, closed: bool, archived: bool) -> bool {
+        match self.scope {
+            SearchScope::Open => !closed && !archived,
+            SearchScope::All => true,
+            SearchScope::Archived => archived,
+        }
+    }
+
+    pub fn matches_text(&self, title: &str, notes: Option<&str>) -> bool {
+        let haystack = format!("{title}\n{}", notes.unwrap_or_default()).to_lowercase();
+        self.text
+            .to_lowercase()
+            .split_whitespace()
+            .all(|word| haystack.contains(word))
+    }
+}
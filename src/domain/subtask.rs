@@ -0,0 +1,171 @@
+use std::collections::{HashMap, HashSet};
+use std::time::SystemTime;
+
+use super::due::Due;
+use super::todo::{Todo, TodoId};
+
+/// Parent chains longer than this are treated as broken (guards against cycles).
+const MAX_DEPTH: usize = 16;
+
+/// A due date as shown for a todo: its own, or the nearest ancestor's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EffectiveDue {
+    pub at: SystemTime,
+    pub has_time: bool,
+    pub inherited: bool,
+}
+
+impl EffectiveDue {
+    pub fn due(self) -> Due {
+        Due {
+            at: self.at,
+            has_time: self.has_time,
+        }
+    }
+}
+
+/// Parent links and explicit due dates of a set of todos.
+#[derive(Debug, Default)]
+pub struct Hierarchy {
+    parents: HashMap<TodoId, TodoId>,
+    dues: HashMap<TodoId, Option<Due>>,
+}
+
+impl Hierarchy {
+    pub fn new(todos: &[Todo]) -> Self {
+        Self {
+            parents: todos
+                .iter()
+                .filter_map(|t| Some((t.id, t.parent_id?)))
+                .collect(),
+            dues: todos.iter().map(|t| (t.id, t.due_value())).collect(),
+        }
+    }
+
+    /// Known parent of `id`; links to todos outside the set are ignored.
+    pub fn parent(&self, id: TodoId) -> Option<TodoId> {
+        self.parents
+            .get(&id)
+            .copied()
+            .filter(|p| self.dues.contains_key(p))
+    }
+
+    pub fn depth(&self, id: TodoId) -> usize {
+        let mut depth = 0;
+        let mut current = id;
+        while let Some(parent) = self.parent(current) {
+            depth += 1;
+            if depth >= MAX_DEPTH {
+                break;
+            }
+            current = parent;
+        }
+        depth
+    }
+
+    /// Own due date, or else the closest ancestor's.
+    pub fn effective_due(&self, id: TodoId) -> Option<EffectiveDue> {
+        let mut current = id;
+        for step in 0..MAX_DEPTH {
+            if let Some(due) = self.dues.get(&current).copied().flatten() {
+                return Some(EffectiveDue {
+                    at: due.at,
+                    has_time: due.has_time,
+                    inherited: step > 0,
+                });
+            }
+            current = self.parent(current)?;
+        }
+        None
+    }
+
+    /// True when `id` has an explicit due date later than its parent's (own or inherited).
+    pub fn due_after_parent(&self, id: TodoId) -> bool {
+        let Some(own) = self.dues.get(&id).copied().flatten() else {
+            return false;
+        };
+        self.parent(id)
+            .and_then(|p| self.effective_due(p))
+            .is_some_and(|parent| own.at > parent.at)
+    }
+}
+
+/// Reorder already sorted todos so each child follows its parent, keeping the
+/// relative order among siblings. Children whose parent is not in the list stay roots.
+pub fn nest(todos: Vec<Todo>, hierarchy: &Hierarchy) -> Vec<Todo> {
+    let present: HashSet<TodoId> = todos.iter().map(|t| t.id).collect();
+    let parent_of = |t: &Todo| hierarchy.parent(t.id).filter(|p| present.contains(p));
+    let mut children: HashMap<TodoId, Vec<usize>> = HashMap::new();
+    let mut roots = Vec::new();
+    for (idx, todo) in todos.iter().enumerate() {
+        match parent_of(todo) {
+            Some(parent) => children.entry(parent).or_default().push(idx),
+            None => roots.push(idx),
+        }
+    }
+
+    let mut order = Vec::with_capacity(todos.len());
+    let mut visited = vec![false; todos.len()];
+    let mut stack: Vec<usize> = roots.into_iter().rev().collect();
+    while let Some(idx) = stack.pop() {
+        if std::mem::replace(&mut visited[idx], true) {
+            continue;
+        }
+        order.push(idx);
+        if let Some(kids) = children.get(&todos[idx].id) {
+            stack.extend(kids.iter().rev());
+        }
+    }
+    // Members of a parent cycle are never reached from a root.
+    order.extend((0..todos.len()).filter(|&idx| !visited[idx]));
+
+    let mut slots: Vec<Option<Todo>> = todos.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .filter_map(|idx| slots[idx].take())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::due::Due;
+    use crate::domain::todo::Priority;
+    use std::time::Duration;
+
+    #[test]
+    fn children_inherit_due_and_follow_their_parent() {
+        let day = |n: u64| SystemTime::UNIX_EPOCH + Duration::from_secs(n * 86_400);
+        let parent = Todo::with_meta("parent", Priority::Medium, Some(Due::timed(day(10))));
+        let mut inherits = Todo::with_meta("inherits", Priority::Medium, None);
+        inherits.parent_id = Some(parent.id);
+        let mut late = Todo::with_meta("late", Priority::Medium, Some(Due::timed(day(12))));
+        late.parent_id = Some(parent.id);
+        let other = Todo::with_meta("other", Priority::Medium, Some(Due::timed(day(11))));
+
+        let todos = vec![
+            inherits.clone(),
+            parent.clone(),
+            other.clone(),
+            late.clone(),
+        ];
+        let hierarchy = Hierarchy::new(&todos);
+        assert_eq!(
+            hierarchy.effective_due(inherits.id),
+            Some(EffectiveDue {
+                at: day(10),
+                has_time: true,
+                inherited: true
+            })
+        );
+        assert!(!hierarchy.due_after_parent(inherits.id));
+        assert!(hierarchy.due_after_parent(late.id));
+        assert_eq!(hierarchy.depth(late.id), 1);
+
+        let titles: Vec<String> = nest(todos, &hierarchy)
+            .into_iter()
+            .map(|t| t.title)
+            .collect();
+        assert_eq!(titles, ["parent", "inherits", "late", "other"]);
+    }
+}
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+
+use super::todo::TodoId;
+
+/// One tracked work session on a todo. `ended_at` is `None` while the timer runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub id: Uuid,
+    pub todo_id: TodoId,
+    pub started_at: SystemTime,
+    pub ended_at: Option<SystemTime>,
+}
+
+impl TimeEntry {
+    pub fn start(todo_id: TodoId) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            todo_id,
+            started_at: SystemTime::now(),
+            ended_at: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.ended_at.is_none()
+    }
+
+    /// Length of the session; a running session counts up to `now`.
+    pub fn duration(&self, now: SystemTime) -> Duration {
+        self.duration_since(None, now)
+    }
+
+    /// Length of the part of the session that lies after `since`.
+    pub fn duration_since(&self, since: Option<SystemTime>, now: SystemTime) -> Duration {
+        let start = match since {
+            Some(s) if s > self.started_at => s,
+            _ => self.started_at,
+        };
+        let end = self.ended_at.unwrap_or(now);
+        end.duration_since(start).unwrap_or_default()
+    }
+}
+
+/// Sum of all sessions of `todo_id`.
+pub fn total_for(entries: &[TimeEntry], todo_id: TodoId, now: SystemTime) -> Duration {
+    entries
+        .iter()
+        .filter(|e| e.todo_id == todo_id)
+        .map(|e| e.duration(now))
+        .sum()
+}
+
+/// Render a duration as `1h 05m` (or `42s` for very short sessions).
+pub fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs < 60 {
+        return format!("{secs}s");
+    }
+    let hours = secs / 3600;
+    let minutes = (secs % 3600) / 60;
+    format!("{hours}h {minutes:02}m")
+}
@@ -2,12 +2,33 @@ use serde::{Deserialize, Serialize};
 use std::time::SystemTime;
 use uuid::Uuid;
 
+use super::due::Due;
+
 pub type TodoId = Uuid;
 
+/// `external_key` prefix for todos created from GitHub PRs; the rest is the PR key.
+pub const GITHUB_PR_KEY_PREFIX: &str = "github_pr:";
+/// `external_key` prefix for "merge when green" follow-ups created after approving a PR.
+pub const GITHUB_FOLLOW_UP_KEY_PREFIX: &str = "github_followup:";
+/// `external_key` prefix for my own PRs that need action (`[github] track_own_prs`).
+pub const GITHUB_OWN_PR_KEY_PREFIX: &str = "github_own:";
+/// `external_key` prefix for results of a saved search: `github_search:<tag>:<owner/repo#N>`.
+pub const GITHUB_SEARCH_KEY_PREFIX: &str = "github_search:";
+/// `external_key` prefix for todos made from the notifications inbox:
+/// `github_notification:<owner/repo>:<thread id>`.
+pub const GITHUB_NOTIFICATION_KEY_PREFIX: &str = "github_notification:";
+/// `external_key` prefix for issues pulled from Jira: `jira:PROJ-123`.
+pub const JIRA_KEY_PREFIX: &str = "jira:";
+/// `external_key` prefix for tasks pulled from a CalDAV list: `caldav:<uid>`.
+pub const CALDAV_KEY_PREFIX: &str = "caldav:";
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Priority {
+    #[serde(alias = "high")]
     High = 1,
+    #[serde(alias = "medium")]
     Medium = 2,
+    #[serde(alias = "low")]
     Low = 3,
 }
 
@@ -21,33 +42,285 @@ impl Priority {
     }
 }
 
+/// Where a todo stands. `Done` and `Cancelled` both close it: it leaves the list, stops
+/// blocking others and goes away on "clear done".
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    #[default]
+    Todo,
+    InProgress,
+    Blocked,
+    Done,
+    Cancelled,
+}
+
+impl Status {
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Todo => "todo",
+            Status::InProgress => "in_progress",
+            Status::Blocked => "blocked",
+            Status::Done => "done",
+            Status::Cancelled => "cancelled",
+        }
+    }
+
+    /// Unknown labels read as `Todo`.
+    pub fn from_label(label: &str) -> Self {
+        match label {
+            "in_progress" => Status::InProgress,
+            "blocked" => Status::Blocked,
+            "done" => Status::Done,
+            "cancelled" => Status::Cancelled,
+            _ => Status::Todo,
+        }
+    }
+
+    pub fn is_closed(self) -> bool {
+        matches!(self, Status::Done | Status::Cancelled)
+    }
+
+    /// The next status for `w`: todo → in progress → blocked → done → cancelled → todo.
+    pub fn cycle(self) -> Self {
+        match self {
+            Status::Todo => Status::InProgress,
+            Status::InProgress => Status::Blocked,
+            Status::Blocked => Status::Done,
+            Status::Done => Status::Cancelled,
+            Status::Cancelled => Status::Todo,
+        }
+    }
+}
+
+/// Reads a status label, or the `done` boolean written before statuses existed (archive
+/// lines, hook payloads).
+fn status_or_done<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Status, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Done(bool),
+        Status(Status),
+    }
+    Ok(match Raw::deserialize(deserializer)? {
+        Raw::Done(true) => Status::Done,
+        Raw::Done(false) => Status::Todo,
+        Raw::Status(status) => status,
+    })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Todo {
     pub id: TodoId,
     pub title: String,
-    pub done: bool,
+    #[serde(alias = "done", deserialize_with = "status_or_done")]
+    pub status: Status,
+    /// Hidden from the main list after "clear done"; still reachable via search.
+    pub archived: bool,
     pub priority: Priority,
     pub due: Option<SystemTime>,
+    /// `due` has a time of day; otherwise only its local date counts. Dues stored before
+    /// the flag existed are date-only.
+    #[serde(default)]
+    pub due_has_time: bool,
     pub created_at: SystemTime,
     pub external_url: Option<String>,
     pub external_key: Option<String>,
+    /// Free-form text entered after the title (Alt-Enter in the add input).
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Set for subtasks; a child without a due date inherits its parent's.
+    #[serde(default)]
+    pub parent_id: Option<TodoId>,
+    /// Title as last received from the provider (e.g. GitHub); the base that tells
+    /// a local rename apart from a remote one.
+    #[serde(default)]
+    pub remote_title: Option<String>,
+    /// Remote title that clashes with a local rename, waiting for the conflict chooser.
+    #[serde(default)]
+    pub incoming_title: Option<String>,
+    /// Position among its siblings in the manual sort (`J` / `K`); todos without one
+    /// follow the ordered ones, oldest first.
+    #[serde(default)]
+    pub sort_order: Option<i64>,
+    /// When the todo was last marked done; cleared on reopen. Unknown (`None`) for
+    /// todos completed before this was recorded.
+    #[serde(default)]
+    pub completed_at: Option<SystemTime>,
+    /// Head commit of the todo's PR when I last looked at it (`u`, or opening it).
+    #[serde(default)]
+    pub last_seen_sha: Option<String>,
+    /// When I last looked at the todo's PR; later PR activity shows it as updated (●).
+    #[serde(default)]
+    pub last_seen_at: Option<SystemTime>,
+    /// "Not before": kept out of the list until then (`s:+5` when adding).
+    #[serde(default)]
+    pub start_at: Option<SystemTime>,
+    /// Expected effort (`e:30`, `e:1h30`), summed up for today's capacity check.
+    #[serde(default)]
+    pub estimate_minutes: Option<u32>,
 }
 
 impl Todo {
-    pub fn with_meta(
-        title: impl Into<String>,
-        priority: Priority,
-        due: Option<SystemTime>,
-    ) -> Self {
+    pub fn with_meta(title: impl Into<String>, priority: Priority, due: Option<Due>) -> Self {
         Self {
             id: Uuid::new_v4(),
             title: title.into(),
-            done: false,
+            status: Status::default(),
+            archived: false,
             priority,
-            due,
+            due: due.map(|d| d.at),
+            due_has_time: due.is_some_and(|d| d.has_time),
             created_at: SystemTime::now(),
             external_url: None,
             external_key: None,
+            notes: None,
+            parent_id: None,
+            remote_title: None,
+            incoming_title: None,
+            sort_order: None,
+            completed_at: None,
+            last_seen_sha: None,
+            last_seen_at: None,
+            start_at: None,
+            estimate_minutes: None,
+        }
+    }
+
+    /// The due date with whether it has a time of day.
+    pub fn due_value(&self) -> Option<Due> {
+        self.due.map(|at| Due {
+            at,
+            has_time: self.due_has_time,
+        })
+    }
+
+    pub fn set_due(&mut self, due: Option<Due>) {
+        self.due = due.map(|d| d.at);
+        self.due_has_time = due.is_some_and(|d| d.has_time);
+    }
+
+    /// Done or cancelled.
+    pub fn is_closed(&self) -> bool {
+        self.status.is_closed()
+    }
+
+    /// Open, with a start date still ahead of `now`.
+    pub fn is_scheduled(&self, now: SystemTime) -> bool {
+        !self.is_closed() && self.start_at.is_some_and(|start| start > now)
+    }
+
+    /// Take a title from a provider sync. Without a local rename the remote title wins;
+    /// if both sides changed it is parked in `incoming_title` instead of overwriting.
+    pub fn apply_remote_title(&mut self, remote: String) {
+        // Rows synced before `remote_title` existed count as not renamed locally.
+        let base = self.remote_title.as_deref().unwrap_or(&self.title);
+        if self.title == base || remote == self.title {
+            self.title = remote.clone();
+            self.remote_title = Some(remote);
+            self.incoming_title = None;
+        } else if remote != base {
+            self.incoming_title = Some(remote);
+        }
+    }
+
+    /// `owner/repo#number` when this todo was created from a GitHub PR.
+    pub fn github_pr_key(&self) -> Option<&str> {
+        self.external_key
+            .as_deref()?
+            .strip_prefix(GITHUB_PR_KEY_PREFIX)
+    }
+
+    /// `owner/repo#number` when this todo tracks one of my own PRs needing action.
+    pub fn own_pr_key(&self) -> Option<&str> {
+        self.external_key
+            .as_deref()?
+            .strip_prefix(GITHUB_OWN_PR_KEY_PREFIX)
+    }
+
+    /// `(tag, owner/repo#number)` when this todo came from a saved search.
+    pub fn github_search_source(&self) -> Option<(&str, &str)> {
+        self.external_key
+            .as_deref()?
+            .strip_prefix(GITHUB_SEARCH_KEY_PREFIX)?
+            .split_once(':')
+    }
+
+    /// Section of the repository view: `owner/repo` for GitHub todos, `Jira PROJ` for
+    /// Jira issues and `Local` for the rest.
+    pub fn source_group(&self) -> String {
+        let key = self.external_key.as_deref().unwrap_or_default();
+        let pr_key = key
+            .strip_prefix(GITHUB_PR_KEY_PREFIX)
+            .or_else(|| key.strip_prefix(GITHUB_FOLLOW_UP_KEY_PREFIX))
+            .or_else(|| key.strip_prefix(GITHUB_OWN_PR_KEY_PREFIX))
+            .or_else(|| self.github_search_source().map(|(_, pr_key)| pr_key));
+        if let Some((repo, _)) = pr_key.and_then(|k| k.split_once('#')) {
+            return repo.to_string();
         }
+        if let Some((repo, _)) = key
+            .strip_prefix(GITHUB_NOTIFICATION_KEY_PREFIX)
+            .and_then(|rest| rest.rsplit_once(':'))
+        {
+            return repo.to_string();
+        }
+        if let Some(issue) = key.strip_prefix(JIRA_KEY_PREFIX) {
+            let project = issue.split_once('-').map_or(issue, |(p, _)| p);
+            return format!("Jira {project}");
+        }
+        "Local".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn todos_written_with_a_done_flag_still_read() {
+        let mut json = serde_json::to_value(Todo::with_meta("a", Priority::Medium, None)).unwrap();
+        assert_eq!(json["status"], "todo");
+        json.as_object_mut().unwrap().remove("status");
+        json["done"] = true.into();
+        let todo: Todo = serde_json::from_value(json).unwrap();
+        assert_eq!(todo.status, Status::Done);
+    }
+
+    #[test]
+    fn remote_title_conflicts_only_when_both_sides_changed() {
+        let mut todo = Todo::with_meta("a", Priority::Medium, None);
+        todo.remote_title = Some("a".to_string());
+
+        todo.apply_remote_title("b".to_string());
+        assert_eq!(
+            (todo.title.as_str(), todo.incoming_title.as_deref()),
+            ("b", None)
+        );
+
+        todo.title = "mine".to_string();
+        todo.apply_remote_title("b".to_string());
+        assert_eq!(
+            (todo.title.as_str(), todo.incoming_title.as_deref()),
+            ("mine", None)
+        );
+
+        todo.apply_remote_title("c".to_string());
+        assert_eq!(todo.title, "mine");
+        assert_eq!(todo.incoming_title.as_deref(), Some("c"));
+        assert_eq!(todo.remote_title.as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn source_group_comes_from_the_external_key() {
+        let group = |key: Option<&str>| {
+            let mut todo = Todo::with_meta("a", Priority::Medium, None);
+            todo.external_key = key.map(str::to_string);
+            todo.source_group()
+        };
+        assert_eq!(group(Some("github_pr:acme/api#12")), "acme/api");
+        assert_eq!(group(Some("github_followup:acme/web#3")), "acme/web");
+        assert_eq!(group(Some("github_search:bugs:acme/cli#7")), "acme/cli");
+        assert_eq!(group(Some("jira:OPS-7")), "Jira OPS");
+        assert_eq!(group(None), "Local");
     }
 }
@@ -0,0 +1,113 @@
+//! `[hooks]`: user commands run on todo events, for automation outside koto.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread::JoinHandle;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::config::HooksConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    /// A todo was added in the TUI, by `koto add` or through `koto serve` (sync-created
+    /// todos are covered by `SyncComplete`).
+    Add,
+    /// A todo was marked done in the TUI or through `koto serve`.
+    Done,
+    /// An open todo's due date passed while koto was running, or since it last ran.
+    Overdue,
+    /// A provider sync finished successfully.
+    SyncComplete,
+}
+
+impl HookEvent {
+    /// The config key, also passed to the command as `KOTO_EVENT`.
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::Add => "on_add",
+            HookEvent::Done => "on_done",
+            HookEvent::Overdue => "on_overdue",
+            HookEvent::SyncComplete => "on_sync_complete",
+        }
+    }
+}
+
+/// `on_sync_complete` payload.
+#[derive(Debug, Serialize)]
+pub struct SyncCompleted<'a> {
+    /// The provider that synced: `github`, `jira` or `caldav`.
+    pub source: &'a str,
+    pub added: usize,
+    /// Todos the sync completed or archived (issue resolved, search no longer matching).
+    pub closed: usize,
+}
+
+/// Run the command configured for `event` through the shell, with `payload` as JSON on
+/// stdin and `KOTO_EVENT` set. Output is discarded so it never draws over the TUI. The
+/// handle finishes once the command exits; `None` when no hook is configured.
+pub fn fire(
+    config: &HooksConfig,
+    event: HookEvent,
+    payload: &impl Serialize,
+) -> Result<Option<JoinHandle<()>>> {
+    let Some(command) = config.command(event).filter(|c| !c.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let json = serde_json::to_vec(payload)?;
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut child = Command::new(shell)
+        .arg(flag)
+        .arg(command)
+        .env("KOTO_EVENT", event.name())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to run {} hook", event.name()))?;
+    let stdin = child.stdin.take();
+    // Feed and reap the command off the UI thread; a hook that ignores stdin just
+    // closes the pipe early.
+    Ok(Some(std::thread::spawn(move || {
+        if let Some(mut stdin) = stdin {
+            let _ = stdin.write_all(&json);
+        }
+        let _ = child.wait();
+    })))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::domain::todo::{Priority, Todo};
+
+    #[test]
+    fn hook_gets_the_todo_on_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out.json");
+        let config = HooksConfig {
+            on_done: Some(format!(
+                "{{ cat; echo; echo $KOTO_EVENT; }} > '{}'",
+                out.display()
+            )),
+            ..HooksConfig::default()
+        };
+        let todo = Todo::with_meta("Ship release", Priority::High, None);
+
+        assert!(fire(&config, HookEvent::Add, &todo).unwrap().is_none());
+        fire(&config, HookEvent::Done, &todo)
+            .unwrap()
+            .unwrap()
+            .join()
+            .unwrap();
+        let written = std::fs::read_to_string(&out).unwrap();
+        let (json, event) = written.trim_end().rsplit_once('\n').unwrap();
+        let parsed: Todo = serde_json::from_str(json).unwrap();
+        assert_eq!((parsed.id, event), (todo.id, "on_done"));
+    }
+}
@@ -1,18 +1,32 @@
 mod app;
+mod cli;
+mod clipboard;
+mod config;
+mod daemon;
 mod domain;
+mod hooks;
 mod repo;
 mod ui;
 mod usecase;
+mod worker;
 
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow};
 use clap::Parser;
 
 use app::{App, GithubConfig};
+use domain::due::Due;
 use domain::todo::{Priority, Todo};
+use repo::archive::Archive;
+use repo::caldav::CaldavConfig;
+use repo::github::model::{RepoFilter, RetryPolicy, SearchSpec};
+use repo::jira::{JiraAuth, JiraConfig};
+use repo::lock::{LockHolder, StoreLock};
 use repo::memory::InMemoryTodoRepo;
-use repo::sqlite::SqliteTodoRepo;
+use repo::session::SessionFile;
+use repo::sqlite::{self, SqliteTodoRepo};
+use repo::todotxt::TodoTxtRepo;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "koto — minimal GitHub-aware todo TUI", long_about = None)]
@@ -26,44 +40,223 @@ struct Args {
     demo: bool,
 
     /// Use in-memory store instead of SQLite
-    #[arg(long, default_value_t = false)]
+    #[arg(long, global = true, default_value_t = false)]
     memory: bool,
 
     /// Path to SQLite DB file (default: OS data dir)
-    #[arg(long)]
+    #[arg(long, global = true)]
     db_path: Option<std::path::PathBuf>,
+
+    /// Use a shared PostgreSQL store instead, e.g. postgres://koto@db.lan/koto
+    /// (needs a build with `--features postgres`)
+    #[arg(long, global = true, conflicts_with = "db_path")]
+    db_url: Option<String>,
+
+    /// Keep todos in a todo.txt file instead (archived ones go to done.txt beside it)
+    #[arg(long, global = true, conflicts_with_all = ["db_path", "db_url"])]
+    todo_txt: Option<std::path::PathBuf>,
+
+    /// With --todo-txt in a git repository: commit every change, pull and push
+    #[arg(long, global = true, requires = "todo_txt", default_value_t = false)]
+    git_sync: bool,
+
+    /// Path to config.toml (default: OS config dir)
+    #[arg(long, global = true)]
+    config: Option<std::path::PathBuf>,
+
+    /// GitHub API root for GitHub Enterprise, e.g. https://ghe.example.com/api/v3
+    /// (default: env GITHUB_API_URL, else api.github.com)
+    #[arg(long, global = true)]
+    github_api_url: Option<String>,
+
+    /// Encrypt titles, notes and the activity log with a passphrase (asked on startup,
+    /// or env KOTO_PASSPHRASE); an existing store is encrypted on first use
+    #[arg(long, global = true, default_value_t = false)]
+    encrypt: bool,
+
+    #[command(subcommand)]
+    command: Option<cli::Command>,
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse();
+    domain::clock::init_local_offset();
+    let mut args = Args::parse();
+    let config = config::Config::load(args.config.as_deref())?;
+    if let Some(offset) = config.time.offset()? {
+        domain::clock::override_local_offset(offset);
+    }
+    let api_url = github_api_url(args.github_api_url.as_deref())?;
+    let store_path = store_path(&args)?;
+    if let Some(command) = args.command.take_if(|c| c.works_on_file()) {
+        return cli::run_on_file(command, store_path.as_deref());
+    }
+    let repo = open_repo(&args)?;
+
+    if let Some(command) = args.command {
+        return cli::run(
+            command,
+            repo,
+            &config,
+            args.config.as_deref(),
+            api_url.as_deref(),
+            store_path.as_deref(),
+        );
+    }
+
+    // Held until the TUI exits; one-shot subcommands above only read or append
+    // (`archive purge` takes the lock itself).
+    let _lock = match &store_path {
+        Some(path) => Some(StoreLock::acquire(path, LockHolder::Tui)?),
+        None => None,
+    };
+
+    let github_cfg = build_github_configs(&config.github, api_url.as_deref())?;
+
+    let jira_cfg = build_jira_config(&config.jira)?;
+    let caldav_cfg = build_caldav_config(&config.caldav)?;
+
+    let mut app = App::new(repo, config, github_cfg, worker::Worker::start()?);
+    app.jira = jira_cfg;
+    app.caldav = caldav_cfg;
+    // Both side files are plaintext, so an encrypted store keeps neither.
+    if !args.encrypt {
+        app.archive = store_path.as_deref().map(Archive::for_db);
+    }
+    if let Some(path) = &store_path {
+        if !args.encrypt {
+            app.attach_session(SessionFile::for_db(path));
+        }
+        app.daemon = daemon::DaemonClient::connect(path);
+    }
+    if app.daemon.is_some() {
+        app.set_status("Attached to koto daemon; it syncs GitHub (g: sync now)");
+    } else if !app.github.is_empty() {
+        app.set_status("Press 'g' to sync GitHub PRs");
+    }
+    ui::run(app, Duration::from_millis(args.tick_ms))
+}
+
+/// SQLite file backing this run, or `None` for the other stores.
+fn store_path(args: &Args) -> Result<Option<std::path::PathBuf>> {
+    if args.demo || args.memory || args.db_url.is_some() || args.todo_txt.is_some() {
+        return Ok(None);
+    }
+    match args.db_path.clone() {
+        Some(path) => Ok(Some(path)),
+        None => sqlite::default_db_path().map(Some),
+    }
+}
+
+fn open_repo(args: &Args) -> Result<Box<dyn repo::TodoRepository>> {
     let repo: Box<dyn repo::TodoRepository> = if args.demo {
         Box::new(InMemoryTodoRepo::with_seed(seed_todos()))
     } else if args.memory {
         Box::new(InMemoryTodoRepo::default())
+    } else if let Some(url) = args.db_url.as_deref() {
+        open_postgres(url, args.encrypt)?
+    } else if let Some(path) = args.todo_txt.as_ref() {
+        if args.encrypt {
+            anyhow::bail!("--encrypt only applies to the SQLite store");
+        }
+        let repo = TodoTxtRepo::open(path)?;
+        if args.git_sync {
+            Box::new(repo.with_git_sync()?)
+        } else {
+            Box::new(repo)
+        }
+    } else if args.encrypt {
+        let path = match args.db_path.clone() {
+            Some(path) => path,
+            None => sqlite::default_db_path()?,
+        };
+        let passphrase = passphrase(SqliteTodoRepo::is_encrypted(&path)?)?;
+        Box::new(SqliteTodoRepo::open_encrypted(&path, &passphrase)?)
     } else if let Some(path) = args.db_path.as_ref() {
         Box::new(SqliteTodoRepo::open(path)?)
     } else {
         Box::new(SqliteTodoRepo::open_default()?)
     };
+    Ok(repo)
+}
+
+#[cfg(feature = "postgres")]
+fn open_postgres(url: &str, encrypt: bool) -> Result<Box<dyn repo::TodoRepository>> {
+    if encrypt {
+        anyhow::bail!("--encrypt only applies to the SQLite store");
+    }
+    Ok(Box::new(repo::postgres::PostgresTodoRepo::connect(url)?))
+}
 
-    let github_cfg = build_github_config()?;
+#[cfg(not(feature = "postgres"))]
+fn open_postgres(_url: &str, _encrypt: bool) -> Result<Box<dyn repo::TodoRepository>> {
+    anyhow::bail!(
+        "--db-url needs koto built with the postgres feature (cargo install koto --features postgres)"
+    )
+}
 
-    let mut app = App::new(repo, github_cfg);
-    if app.github.is_some() {
-        app.set_status("Press 'g' to sync GitHub PRs");
+/// `KOTO_PASSPHRASE`, else asked on the terminal (twice when it will encrypt the store).
+fn passphrase(existing: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var("KOTO_PASSPHRASE")
+        && !passphrase.is_empty()
+    {
+        return Ok(passphrase);
     }
-    ui::run(app, Duration::from_millis(args.tick_ms))
+    let passphrase = read_hidden("Passphrase: ")?;
+    if passphrase.is_empty() {
+        anyhow::bail!("the passphrase must not be empty");
+    }
+    if !existing && read_hidden("Repeat passphrase: ")? != passphrase {
+        anyhow::bail!("passphrases do not match");
+    }
+    Ok(passphrase)
+}
+
+/// Read a line from the terminal without echoing it.
+fn read_hidden(prompt: &str) -> Result<String> {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+    use std::io::Write;
+
+    eprint!("{prompt}");
+    std::io::stderr().flush()?;
+    crossterm::terminal::enable_raw_mode()?;
+    let mut line = String::new();
+    let result = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Enter => break Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Err(anyhow!("cancelled"));
+                }
+                KeyCode::Char(c) => line.push(c),
+                KeyCode::Backspace => {
+                    line.pop();
+                }
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(e) => break Err(e.into()),
+        }
+    };
+    crossterm::terminal::disable_raw_mode()?;
+    eprintln!();
+    result.map(|()| line)
 }
 
 fn seed_todos() -> Vec<Todo> {
     let now = std::time::SystemTime::now();
     let days_from_now = |d: u64| {
-        now.checked_add(Duration::from_secs(d * 86_400))
-            .unwrap_or(now)
+        Due::timed(
+            now.checked_add(Duration::from_secs(d * 86_400))
+                .unwrap_or(now),
+        )
     };
 
     vec![
-        Todo::with_meta("Hotfix production error", Priority::High, Some(now)),
+        Todo::with_meta(
+            "Hotfix production error",
+            Priority::High,
+            Some(Due::timed(now)),
+        ),
         Todo::with_meta("Update API spec", Priority::Medium, Some(days_from_now(3))),
         Todo::with_meta("Draft release notes", Priority::Low, Some(days_from_now(7))),
         Todo::with_meta("Refactor backlog grooming", Priority::Low, None),
@@ -87,22 +280,130 @@ pub fn now_unix() -> i64 {
         .as_secs() as i64
 }
 
-fn github_token() -> Result<String> {
-    repo::github::auth::resolve_github_token_env_then_gh().map_err(|e| {
+/// `--github-api-url`, else `GITHUB_API_URL`; validated so a typo fails at startup
+/// rather than on the first sync.
+fn github_api_url(flag: Option<&str>) -> Result<Option<String>> {
+    let env = std::env::var("GITHUB_API_URL").ok();
+    let raw = flag.or(env.as_deref().filter(|v| !v.trim().is_empty()));
+    raw.map(repo::github::endpoint::normalize_api_url)
+        .transpose()
+}
+
+fn github_token(api_url: Option<&str>) -> Result<String> {
+    let hostname = api_url.map(repo::github::endpoint::gh_hostname);
+    repo::github::auth::resolve_github_token_env_then_gh(hostname.as_deref()).map_err(|e| {
         anyhow!(
             "GitHub token is required (env GITHUB_TOKEN, or `gh auth login` then `gh auth token`): {e}"
         )
     })
 }
 
-fn build_github_config() -> Result<Option<GithubConfig>> {
-    match github_token() {
-        Ok(token) => Ok(Some(GithubConfig {
+/// The main account (`api_url`) followed by `[[github.accounts]]`. Accounts without a
+/// token are left out; an empty list means GitHub is off.
+pub fn build_github_configs(
+    settings: &config::GithubSettings,
+    api_url: Option<&str>,
+) -> Result<Vec<GithubConfig>> {
+    let account = |token, api_base, key_host: Option<String>| GithubConfig {
+        token,
+        api_base,
+        days: settings.days,
+        include_team_requests: settings.include_team_requests,
+        page_size: settings.page_size,
+        retry: RetryPolicy {
+            attempts: settings.retry_attempts.max(1),
+            base_delay_ms: settings.retry_backoff_ms,
+        },
+        searches: if key_host.is_none() {
+            settings
+                .searches
+                .iter()
+                .map(|s| SearchSpec {
+                    tag: s.tag(),
+                    query: s.query.clone(),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        },
+        repos: RepoFilter {
+            include: settings.include_repos.clone(),
+            exclude: settings.exclude_repos.clone(),
+        },
+        key_host,
+        since: None,
+        watched: Vec::new(),
+    };
+    let mut configs = Vec::new();
+    // no token in env/flag: operate without the main account
+    if let Ok(token) = github_token(api_url) {
+        configs.push(account(token, api_url.map(str::to_string), None));
+    }
+    for extra in &settings.accounts {
+        let api_url = repo::github::endpoint::normalize_api_url(&extra.api_url)
+            .context("invalid [[github.accounts]] api_url")?;
+        let host = repo::github::endpoint::gh_hostname(&api_url);
+        if let Ok(token) =
+            repo::github::auth::resolve_account_token(extra.token_env.as_deref(), &host)
+        {
+            configs.push(account(token, Some(api_url), Some(host)));
+        }
+    }
+    Ok(configs)
+}
+
+/// `[jira]` with its token; `None` when no site is configured or the token env var is
+/// unset, which turns Jira sync off.
+pub fn build_jira_config(settings: &config::JiraSettings) -> Result<Option<JiraConfig>> {
+    let Some(base_url) = settings.base_url.as_deref().map(str::trim) else {
+        return Ok(None);
+    };
+    if !base_url.starts_with("https://") && !base_url.starts_with("http://") {
+        anyhow::bail!(
+            "invalid [jira] base_url {base_url:?} (expected e.g. https://acme.atlassian.net)"
+        );
+    }
+    let Some(token) = std::env::var(&settings.token_env)
+        .ok()
+        .filter(|t| !t.trim().is_empty())
+    else {
+        return Ok(None);
+    };
+    let auth = match &settings.email {
+        Some(email) => JiraAuth::Basic {
+            email: email.clone(),
             token,
-            api_base: None,
-            days: 30,
-            include_team_requests: false,
-        })),
-        Err(_) => Ok(None), // no token in env/flag: operate without GitHub
+        },
+        None => JiraAuth::Bearer(token),
+    };
+    Ok(Some(JiraConfig {
+        base_url: base_url.trim_end_matches('/').to_string(),
+        auth,
+        jql: settings.jql.clone(),
+        max_results: settings.max_results.max(1),
+    }))
+}
+
+/// `[caldav]` with its password; `None` when no list is configured or the password env
+/// var is unset.
+pub fn build_caldav_config(settings: &config::CaldavSettings) -> Result<Option<CaldavConfig>> {
+    let Some(url) = settings.url.as_deref().map(str::trim) else {
+        return Ok(None);
+    };
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        anyhow::bail!(
+            "invalid [caldav] url {url:?} (expected e.g. https://cloud.example.com/remote.php/dav/calendars/me/tasks/)"
+        );
     }
+    let Some(password) = std::env::var(&settings.password_env)
+        .ok()
+        .filter(|p| !p.is_empty())
+    else {
+        return Ok(None);
+    };
+    Ok(Some(CaldavConfig {
+        url: url.to_string(),
+        username: settings.username.clone(),
+        password,
+    }))
 }
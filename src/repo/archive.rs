@@ -0,0 +1,159 @@
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use flate2::Compression;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::todo::Todo;
+
+/// Why a todo left the store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveReason {
+    /// Deleted from the list (`d`).
+    Deleted,
+    /// Removed by `koto archive purge`.
+    Purged,
+}
+
+/// One line of the archive file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedTodo {
+    /// Unix seconds when the todo was written to the archive.
+    pub archived_at: i64,
+    pub reason: ArchiveReason,
+    pub todo: Todo,
+}
+
+/// Append-only history of todos removed from the store: gzip-compressed JSON lines next to
+/// the database, so nothing is lost when the DB is cleaned up.
+///
+/// Every append writes a separate gzip member; concatenated members are still one valid
+/// `.gz` file (`zcat` reads it as a whole).
+#[derive(Debug, Clone)]
+pub struct Archive {
+    path: PathBuf,
+}
+
+impl Archive {
+    /// Archive belonging to the SQLite store at `db_path`.
+    pub fn for_db(db_path: &Path) -> Self {
+        Self {
+            path: db_path.with_extension("archive.jsonl.gz"),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write `todos` to the archive; callers remove them from the store only after this succeeds.
+    pub fn append(&self, todos: &[Todo], reason: ArchiveReason) -> Result<()> {
+        if todos.is_empty() {
+            return Ok(());
+        }
+        let archived_at = crate::now_unix();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        for todo in todos {
+            let entry = ArchivedTodo {
+                archived_at,
+                reason,
+                todo: todo.clone(),
+            };
+            serde_json::to_writer(&mut encoder, &entry)?;
+            encoder.write_all(b"\n")?;
+        }
+        let bytes = encoder.finish()?;
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create archive dir {}", parent.display()))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("failed to open archive {}", self.path.display()))?;
+        file.write_all(&bytes)
+            .and_then(|()| file.sync_all())
+            .with_context(|| format!("failed to write archive {}", self.path.display()))
+    }
+
+    /// Entries whose title, notes, link or key contain `pattern` (case-insensitive), oldest first.
+    pub fn grep(&self, pattern: &str) -> Result<Vec<ArchivedTodo>> {
+        let file = match std::fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("failed to open archive {}", self.path.display()));
+            }
+        };
+        let needle = pattern.to_lowercase();
+        let mut found = Vec::new();
+        for (idx, line) in BufReader::new(MultiGzDecoder::new(file))
+            .lines()
+            .enumerate()
+        {
+            let line =
+                line.with_context(|| format!("failed to read archive {}", self.path.display()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: ArchivedTodo = serde_json::from_str(&line)
+                .with_context(|| format!("corrupt archive line {}", idx + 1))?;
+            if matches(&entry.todo, &needle) {
+                found.push(entry);
+            }
+        }
+        Ok(found)
+    }
+}
+
+fn matches(todo: &Todo, needle: &str) -> bool {
+    [
+        Some(todo.title.as_str()),
+        todo.notes.as_deref(),
+        todo.external_url.as_deref(),
+        todo.external_key.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .any(|field| field.to_lowercase().contains(needle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::todo::Priority;
+
+    #[test]
+    fn appends_are_readable_as_one_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = Archive::for_db(&dir.path().join("todos.sqlite"));
+        assert_eq!(archive.path(), dir.path().join("todos.archive.jsonl.gz"));
+        assert!(archive.grep("x").unwrap().is_empty());
+
+        let mut release = Todo::with_meta("Draft release notes", Priority::Low, None);
+        release.notes = Some("mention the Archive".into());
+        archive
+            .append(&[release.clone()], ArchiveReason::Deleted)
+            .unwrap();
+        archive
+            .append(
+                &[Todo::with_meta("Security audit", Priority::High, None)],
+                ArchiveReason::Purged,
+            )
+            .unwrap();
+
+        let hits = archive.grep("archive").unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].todo.id, release.id);
+        assert_eq!(hits[0].reason, ArchiveReason::Deleted);
+        assert_eq!(archive.grep("").unwrap().len(), 2);
+    }
+}
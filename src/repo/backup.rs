@@ -0,0 +1,167 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use rusqlite::backup::Progress;
+use rusqlite::{Connection, DatabaseName, OpenFlags};
+use time::macros::format_description;
+
+use crate::domain::clock;
+
+/// Automatic backups taken before a schema migration that are kept; older ones are removed.
+const MIGRATION_BACKUPS_KEPT: usize = 5;
+
+/// Snapshots of a SQLite store in `backups/` next to it, named
+/// `<store>-<label>-<YYYYMMDD-HHMMSS>.sqlite`.
+///
+/// Copies go through SQLite's online backup API, so they are consistent even while the
+/// TUI is writing (a plain file copy could catch a half-written page or miss the WAL).
+#[derive(Debug, Clone)]
+pub struct Backups {
+    db_path: PathBuf,
+    dir: PathBuf,
+}
+
+impl Backups {
+    /// Backups of the SQLite store at `db_path`.
+    pub fn for_db(db_path: &Path) -> Self {
+        Self {
+            db_path: db_path.to_path_buf(),
+            dir: db_path.parent().unwrap_or(Path::new(".")).join("backups"),
+        }
+    }
+
+    /// Where a new backup labelled `label` (`manual`, `pre-restore`, …) goes.
+    pub fn new_path(&self, label: &str) -> PathBuf {
+        let stamp = clock::now_local()
+            .format(format_description!(
+                "[year][month][day]-[hour][minute][second]"
+            ))
+            .unwrap_or_else(|_| crate::now_unix().to_string());
+        self.dir
+            .join(format!("{}-{label}-{stamp}.sqlite", self.stem()))
+    }
+
+    /// Copy the store to `dest`, which must not exist yet.
+    pub fn snapshot(&self, dest: &Path) -> Result<()> {
+        let src = Connection::open_with_flags(&self.db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("failed to open {}", self.db_path.display()))?;
+        copy(&src, dest)
+    }
+
+    /// Replace the store with the backup at `src`, after checking it is a koto store and
+    /// taking a `pre-restore` backup of the current one. Returns that backup's path.
+    pub fn restore(&self, src: &Path) -> Result<PathBuf> {
+        check_store(src)?;
+        let previous = self.new_path("pre-restore");
+        if self.db_path.exists() {
+            self.snapshot(&previous)?;
+        }
+        let mut conn = Connection::open(&self.db_path)
+            .with_context(|| format!("failed to open {}", self.db_path.display()))?;
+        conn.restore(DatabaseName::Main, src, None::<fn(Progress)>)
+            .with_context(|| format!("failed to restore from {}", src.display()))?;
+        Ok(previous)
+    }
+
+    /// Back up an open store before migrating its schema, keeping the newest
+    /// `MIGRATION_BACKUPS_KEPT` of these.
+    pub fn before_migration(&self, conn: &Connection) -> Result<PathBuf> {
+        let dest = self.new_path("pre-migration");
+        copy(conn, &dest)?;
+        let prefix = format!("{}-pre-migration-", self.stem());
+        let mut old: Vec<PathBuf> = std::fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(&prefix))
+            })
+            .collect();
+        // The timestamp in the name sorts chronologically.
+        old.sort();
+        let excess = old.len().saturating_sub(MIGRATION_BACKUPS_KEPT);
+        for path in &old[..excess] {
+            let _ = std::fs::remove_file(path);
+        }
+        Ok(dest)
+    }
+
+    fn stem(&self) -> String {
+        self.db_path
+            .file_stem()
+            .map_or_else(|| "todos".to_string(), |s| s.to_string_lossy().into_owned())
+    }
+}
+
+fn copy(src: &Connection, dest: &Path) -> Result<()> {
+    if dest.exists() {
+        bail!("{} already exists", dest.display());
+    }
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    src.backup(DatabaseName::Main, dest, None)
+        .with_context(|| format!("failed to back up to {}", dest.display()))
+}
+
+/// Refuse to restore anything but an intact koto store.
+fn check_store(path: &Path) -> Result<()> {
+    // Not read-only: checking the FTS5 index needs write access, though it changes nothing.
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_WRITE)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    let integrity: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .with_context(|| format!("{} is not a SQLite database", path.display()))?;
+    if integrity != "ok" {
+        bail!("{} is damaged: {integrity}", path.display());
+    }
+    let has_todos: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'todos')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_todos {
+        bail!("{} is not a koto store (no todos table)", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::todo::Priority;
+    use crate::repo::TodoRepository;
+    use crate::repo::sqlite::SqliteTodoRepo;
+
+    #[test]
+    fn restore_brings_back_a_snapshot_and_keeps_the_replaced_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("todos.sqlite");
+        let mut repo = SqliteTodoRepo::open(&db).unwrap();
+        repo.add("before".to_string(), Priority::Medium, None, None, None)
+            .unwrap();
+
+        let backups = Backups::for_db(&db);
+        let snapshot = backups.new_path("manual");
+        backups.snapshot(&snapshot).unwrap();
+        assert!(backups.snapshot(&snapshot).is_err());
+        repo.add("after".to_string(), Priority::Medium, None, None, None)
+            .unwrap();
+        drop(repo);
+
+        assert!(backups.restore(&db.with_extension("missing")).is_err());
+        let previous = backups.restore(&snapshot).unwrap();
+        let titles = |path: &Path| -> Vec<String> {
+            SqliteTodoRepo::open(path)
+                .unwrap()
+                .all()
+                .unwrap()
+                .into_iter()
+                .map(|t| t.title)
+                .collect()
+        };
+        assert_eq!(titles(&db), ["before"]);
+        assert_eq!(titles(&previous), ["before", "after"]);
+    }
+}
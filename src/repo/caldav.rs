@@ -0,0 +1,668 @@
+//! CalDAV task lists (VTODO), e.g. Nextcloud Tasks or an iCloud Reminders list: fetching
+//! with their etags, writing back with `If-Match`, and the mapping between a VTODO and
+//! the todo fields koto syncs.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, anyhow, bail};
+use base64::Engine;
+use http::{Method, StatusCode, Uri};
+use octocrab::Octocrab;
+use serde::{Deserialize, Serialize};
+use time::macros::format_description;
+use time::{Date, OffsetDateTime, PrimitiveDateTime, Time};
+
+use crate::domain::clock;
+use crate::domain::due::Due;
+use crate::domain::todo::{Priority, Status, Todo};
+
+#[derive(Debug, Clone)]
+pub struct CaldavConfig {
+    /// Calendar collection, e.g. `https://cloud.example.com/remote.php/dav/calendars/me/tasks/`.
+    pub url: String,
+    pub username: String,
+    pub password: String,
+}
+
+/// The todo fields a VTODO carries. Times are unix seconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskFields {
+    pub title: String,
+    pub status: Status,
+    pub priority: Priority,
+    pub due: Option<i64>,
+    /// `due` is a time (`DUE:…T…Z`) rather than a date (`DUE;VALUE=DATE:…`).
+    #[serde(default)]
+    pub due_has_time: bool,
+    pub start: Option<i64>,
+    pub notes: Option<String>,
+}
+
+impl TaskFields {
+    pub fn of(todo: &Todo) -> Self {
+        Self {
+            title: todo.title.clone(),
+            status: todo.status,
+            priority: todo.priority,
+            due: todo.due.map(unix),
+            due_has_time: todo.due_has_time,
+            start: todo.start_at.map(unix),
+            notes: todo.notes.clone(),
+        }
+    }
+
+    pub fn due(&self) -> Option<Due> {
+        self.due.map(|secs| Due {
+            at: from_unix(secs),
+            has_time: self.due_has_time,
+        })
+    }
+}
+
+/// A task as the server has it.
+#[derive(Debug, Clone)]
+pub struct RemoteTask {
+    /// Path of the `.ics` resource.
+    pub href: String,
+    pub etag: Option<String>,
+    pub uid: String,
+    pub vtodo: Vtodo,
+}
+
+/// A calendar object holding one VTODO, kept line by line (unfolded) so properties koto
+/// does not map (alarms, categories, …) survive an update.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vtodo {
+    lines: Vec<String>,
+}
+
+impl Vtodo {
+    /// `None` when the object holds no VTODO.
+    pub fn parse(ics: &str) -> Option<Self> {
+        let mut lines: Vec<String> = Vec::new();
+        for raw in ics.split('\n') {
+            let raw = raw.strip_suffix('\r').unwrap_or(raw);
+            match (raw.strip_prefix([' ', '\t']), lines.last_mut()) {
+                (Some(rest), Some(last)) => last.push_str(rest),
+                _ if raw.is_empty() => {}
+                _ => lines.push(raw.to_string()),
+            }
+        }
+        let vtodo = Self { lines };
+        vtodo.body()?;
+        Some(vtodo)
+    }
+
+    /// A new task with `uid`, for todos created in koto.
+    pub fn new(uid: &str) -> Self {
+        Self {
+            lines: [
+                "BEGIN:VCALENDAR",
+                "VERSION:2.0",
+                "PRODID:-//koto//koto//EN",
+                "BEGIN:VTODO",
+                &format!("UID:{uid}"),
+                &format!("CREATED:{}", format_utc(SystemTime::now())),
+                "END:VTODO",
+                "END:VCALENDAR",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+        }
+    }
+
+    pub fn uid(&self) -> Option<String> {
+        self.get("UID").map(|(_, value)| value.to_string())
+    }
+
+    pub fn fields(&self) -> TaskFields {
+        let status = match self.get("STATUS").map(|(_, v)| v) {
+            Some("COMPLETED") => Status::Done,
+            Some("CANCELLED") => Status::Cancelled,
+            Some("IN-PROCESS") => Status::InProgress,
+            _ if self
+                .get("X-KOTO-STATUS")
+                .is_some_and(|(_, v)| v == "blocked") =>
+            {
+                Status::Blocked
+            }
+            _ => Status::Todo,
+        };
+        let priority = match self.get("PRIORITY").and_then(|(_, v)| v.parse::<u8>().ok()) {
+            Some(1..=4) => Priority::High,
+            Some(6..=9) => Priority::Low,
+            _ => Priority::Medium,
+        };
+        let due = self.get("DUE").map(|(_, v)| v);
+        TaskFields {
+            title: self
+                .get("SUMMARY")
+                .map(|(_, v)| unescape(v))
+                .unwrap_or_default(),
+            status,
+            priority,
+            due: due.and_then(|v| parse_time(v, clock::end_of_day)),
+            due_has_time: due.is_some_and(|v| parse_date(v).is_none()),
+            start: self
+                .get("DTSTART")
+                .and_then(|(_, v)| parse_time(v, |d| clock::at_local(d, Time::MIDNIGHT))),
+            notes: self
+                .get("DESCRIPTION")
+                .map(|(_, v)| unescape(v))
+                .filter(|n| !n.is_empty()),
+        }
+    }
+
+    /// Write `fields` into the VTODO, stamping it as modified now.
+    pub fn set_fields(&mut self, fields: &TaskFields) {
+        let now = format_utc(SystemTime::now());
+        self.set("SUMMARY", Some(escape(&fields.title)));
+        self.set("DESCRIPTION", fields.notes.as_deref().map(escape));
+        let status = match fields.status {
+            Status::Todo | Status::Blocked => "NEEDS-ACTION",
+            Status::InProgress => "IN-PROCESS",
+            Status::Done => "COMPLETED",
+            Status::Cancelled => "CANCELLED",
+        };
+        if fields.status == Status::Done && self.get("STATUS").map(|(_, v)| v) != Some(status) {
+            self.set("COMPLETED", Some(now.clone()));
+            self.set("PERCENT-COMPLETE", Some("100".to_string()));
+        } else if fields.status != Status::Done {
+            self.set("COMPLETED", None);
+            self.set("PERCENT-COMPLETE", None);
+        }
+        self.set("STATUS", Some(status.to_string()));
+        self.set(
+            "X-KOTO-STATUS",
+            (fields.status == Status::Blocked).then(|| "blocked".to_string()),
+        );
+        let priority = match fields.priority {
+            Priority::High => "1",
+            Priority::Medium => "5",
+            Priority::Low => "9",
+        };
+        self.set("PRIORITY", Some(priority.to_string()));
+        self.set_time("DUE", fields.due, !fields.due_has_time);
+        // Starts carry no flag: one at local midnight is taken for a date.
+        let start_is_date = fields
+            .start
+            .map(from_unix)
+            .is_some_and(|at| clock::at_local(clock::to_local(at).date(), Time::MIDNIGHT) == at);
+        self.set_time("DTSTART", fields.start, start_is_date);
+        self.set("DTSTAMP", Some(now.clone()));
+        self.set("LAST-MODIFIED", Some(now));
+    }
+
+    /// The object as sent to the server: CRLF line ends, lines folded at 75 bytes.
+    pub fn to_ics(&self) -> String {
+        let mut out = String::new();
+        for line in &self.lines {
+            let mut width = 0;
+            for c in line.chars() {
+                if width + c.len_utf8() > 75 {
+                    out.push_str("\r\n ");
+                    width = 1;
+                }
+                out.push(c);
+                width += c.len_utf8();
+            }
+            out.push_str("\r\n");
+        }
+        out
+    }
+
+    /// Index range of the VTODO's own lines, between BEGIN:VTODO and END:VTODO.
+    fn body(&self) -> Option<(usize, usize)> {
+        let begin = self.lines.iter().position(|l| l == "BEGIN:VTODO")?;
+        let end = begin + self.lines[begin..].iter().position(|l| l == "END:VTODO")?;
+        Some((begin + 1, end))
+    }
+
+    /// Index of the VTODO's own `name` property (not one of a nested VALARM), with the
+    /// position of its value.
+    fn find(&self, name: &str) -> Option<(usize, usize)> {
+        let (begin, end) = self.body()?;
+        let mut depth = 0;
+        for (i, line) in self.lines[begin..end].iter().enumerate() {
+            if line.starts_with("BEGIN:") {
+                depth += 1;
+            } else if line.starts_with("END:") {
+                depth -= 1;
+            } else if depth == 0 {
+                let colon = value_start(line)?;
+                let prop = line[..colon - 1].split(';').next().unwrap_or_default();
+                if prop.eq_ignore_ascii_case(name) {
+                    return Some((begin + i, colon));
+                }
+            }
+        }
+        None
+    }
+
+    /// Parameters (`;VALUE=DATE`) and value of `name`.
+    fn get(&self, name: &str) -> Option<(&str, &str)> {
+        let (i, colon) = self.find(name)?;
+        let line = &self.lines[i];
+        Some((&line[name.len()..colon - 1], &line[colon..]))
+    }
+
+    /// Replace, add or (with `None`) remove `name`, dropping its parameters.
+    fn set(&mut self, name: &str, value: Option<String>) {
+        self.set_line(name, value.map(|v| format!("{name}:{v}")));
+    }
+
+    fn set_line(&mut self, name: &str, line: Option<String>) {
+        match (self.find(name), line) {
+            (Some((i, _)), Some(line)) => self.lines[i] = line,
+            (Some((i, _)), None) => {
+                self.lines.remove(i);
+            }
+            (None, Some(line)) => {
+                if let Some((_, end)) = self.body() {
+                    self.lines.insert(end, line);
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    /// A date when `time` is the day boundary koto uses for date-only values (`as_date`),
+    /// else a UTC date-time.
+    /// `date_only`: write the local date of `time` as a `VALUE=DATE`.
+    fn set_time(&mut self, name: &str, time: Option<i64>, date_only: bool) {
+        let line = time.map(|secs| {
+            let at = from_unix(secs);
+            if date_only {
+                let day = clock::to_local(at)
+                    .date()
+                    .format(format_description!("[year][month][day]"))
+                    .unwrap_or_default();
+                format!("{name};VALUE=DATE:{day}")
+            } else {
+                format!("{name}:{}", format_utc(at))
+            }
+        });
+        self.set_line(name, line);
+    }
+}
+
+/// Byte offset of the value: after the first `:` outside a quoted parameter.
+fn value_start(line: &str) -> Option<usize> {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ':' if !quoted => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `20261020` (a date, placed with `date_time`), `20261020T170000Z` (UTC) or
+/// `20261020T170000` (floating or with a TZID, read as local time).
+fn parse_time(value: &str, date_time: impl Fn(Date) -> SystemTime) -> Option<i64> {
+    if let Some(date) = parse_date(value) {
+        return Some(unix(date_time(date)));
+    }
+    let (text, utc) = match value.strip_suffix('Z') {
+        Some(text) => (text, true),
+        None => (value, false),
+    };
+    let at = PrimitiveDateTime::parse(
+        text,
+        format_description!("[year][month][day]T[hour][minute][second]"),
+    )
+    .ok()?;
+    let offset = if utc {
+        time::UtcOffset::UTC
+    } else {
+        clock::local_offset()
+    };
+    Some(at.assume_offset(offset).unix_timestamp())
+}
+
+/// `20261020`: a `VALUE=DATE`.
+fn parse_date(value: &str) -> Option<Date> {
+    Date::parse(value, format_description!("[year][month][day]")).ok()
+}
+
+fn format_utc(time: SystemTime) -> String {
+    OffsetDateTime::from(time)
+        .format(format_description!(
+            "[year][month][day]T[hour][minute][second]Z"
+        ))
+        .unwrap_or_default()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn unix(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+pub fn from_unix(secs: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64)
+}
+
+/// Outcome of a write made with `If-Match` / `If-None-Match`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Written {
+    /// Stored; the new etag when the server sent one.
+    Stored(Option<String>),
+    /// The task changed (or appeared) on the server since it was read; nothing was written.
+    Changed,
+}
+
+/// A connection to one task collection.
+pub struct CaldavClient {
+    octo: Octocrab,
+    /// Absolute path of the collection, ending in `/`.
+    collection: String,
+}
+
+impl CaldavClient {
+    pub fn new(cfg: &CaldavConfig) -> Result<Self> {
+        let uri: Uri = cfg
+            .url
+            .parse()
+            .map_err(|e| anyhow!("invalid [caldav] url {:?}: {e}", cfg.url))?;
+        let (Some(scheme), Some(authority)) = (uri.scheme_str(), uri.authority()) else {
+            bail!("invalid [caldav] url {:?} (expected https://…)", cfg.url);
+        };
+        let auth = base64::engine::general_purpose::STANDARD
+            .encode(format!("{}:{}", cfg.username, cfg.password));
+        let octo = Octocrab::builder()
+            .base_uri(format!("{scheme}://{authority}"))
+            .map_err(|e| anyhow!("invalid [caldav] url: {e}"))?
+            .add_header(http::header::AUTHORIZATION, format!("Basic {auth}"))
+            .build()
+            .map_err(|e| anyhow!("failed to init CalDAV client: {e}"))?;
+        let collection = format!("{}/", uri.path().trim_end_matches('/'));
+        Ok(Self { octo, collection })
+    }
+
+    /// Where a new task with `uid` is created.
+    pub fn href_for(&self, uid: &str) -> String {
+        format!("{}{uid}.ics", self.collection)
+    }
+
+    /// Every task in the collection.
+    pub async fn fetch(&self) -> Result<Vec<RemoteTask>> {
+        let query = r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><d:getetag/><c:calendar-data/></d:prop>
+  <c:filter><c:comp-filter name="VCALENDAR"><c:comp-filter name="VTODO"/></c:comp-filter></c:filter>
+</c:calendar-query>"#;
+        let (status, _, body) = self
+            .send(
+                "REPORT",
+                &self.collection,
+                &[
+                    ("Depth", "1"),
+                    ("Content-Type", "application/xml; charset=utf-8"),
+                ],
+                query.to_string(),
+            )
+            .await?;
+        if status != StatusCode::MULTI_STATUS {
+            bail!("CalDAV query failed: {status} {}", short(&body));
+        }
+        Ok(parse_multistatus(&body))
+    }
+
+    /// Create (`etag` `None`) or update the task at `href`, unless the server's copy changed.
+    pub async fn put(&self, href: &str, etag: Option<&str>, ics: String) -> Result<Written> {
+        let condition = match etag {
+            Some(etag) => ("If-Match", etag),
+            None => ("If-None-Match", "*"),
+        };
+        let (status, headers, body) = self
+            .send(
+                "PUT",
+                href,
+                &[condition, ("Content-Type", "text/calendar; charset=utf-8")],
+                ics,
+            )
+            .await?;
+        match status {
+            StatusCode::PRECONDITION_FAILED => Ok(Written::Changed),
+            s if s.is_success() => Ok(Written::Stored(
+                headers
+                    .get(http::header::ETAG)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string),
+            )),
+            s => bail!("CalDAV update of {href} failed: {s} {}", short(&body)),
+        }
+    }
+
+    /// Delete the task at `href`, unless the server's copy changed.
+    pub async fn delete(&self, href: &str, etag: Option<&str>) -> Result<Written> {
+        let headers: Vec<(&str, &str)> = etag.map(|e| ("If-Match", e)).into_iter().collect();
+        let (status, _, body) = self.send("DELETE", href, &headers, String::new()).await?;
+        match status {
+            StatusCode::PRECONDITION_FAILED => Ok(Written::Changed),
+            // Already gone counts as deleted.
+            s if s.is_success() || s == StatusCode::NOT_FOUND => Ok(Written::Stored(None)),
+            s => bail!("CalDAV delete of {href} failed: {s} {}", short(&body)),
+        }
+    }
+
+    async fn send(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &[(&str, &str)],
+        body: String,
+    ) -> Result<(StatusCode, http::HeaderMap, String)> {
+        let mut request = http::Request::builder()
+            .method(Method::from_bytes(method.as_bytes())?)
+            .uri(path);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let response = self
+            .octo
+            .execute(request.body(body)?)
+            .await
+            .with_context(|| format!("CalDAV {method} {path} failed"))?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = self.octo.body_to_string(response).await?;
+        Ok((status, headers, body))
+    }
+}
+
+/// The first line of an error body, for messages.
+fn short(body: &str) -> &str {
+    body.trim().lines().next().unwrap_or_default()
+}
+
+/// Tasks in a REPORT response; entries without calendar data (or without a VTODO) are
+/// skipped.
+fn parse_multistatus(xml: &str) -> Vec<RemoteTask> {
+    elements(xml, "response")
+        .into_iter()
+        .filter_map(|response| {
+            let href = elements(response, "href").first().map(|h| xml_text(h))?;
+            let data = elements(response, "calendar-data")
+                .first()
+                .map(|d| xml_text(d))?;
+            let vtodo = Vtodo::parse(&data)?;
+            Some(RemoteTask {
+                href: Uri::try_from(href.as_str())
+                    .ok()
+                    .filter(|u| u.authority().is_some())
+                    .map_or(href.clone(), |u| u.path().to_string()),
+                etag: elements(response, "getetag").first().map(|e| xml_text(e)),
+                uid: vtodo.uid()?,
+                vtodo,
+            })
+        })
+        .collect()
+}
+
+/// Contents of every element named `local` (any namespace prefix), outermost first.
+fn elements<'a>(xml: &'a str, local: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(open) = rest.find('<') {
+        rest = &rest[open + 1..];
+        let Some(close) = rest.find('>') else { break };
+        let tag = &rest[..close];
+        let name = tag
+            .split(|c: char| c.is_whitespace())
+            .next()
+            .unwrap_or_default();
+        let after = &rest[close + 1..];
+        if tag.ends_with('/') || name.starts_with(['/', '?', '!']) {
+            rest = after;
+            continue;
+        }
+        if name.rsplit(':').next() != Some(local) {
+            rest = after;
+            continue;
+        }
+        let Some(end) = after.find(&format!("</{name}>")) else {
+            break;
+        };
+        found.push(&after[..end]);
+        rest = &after[end..];
+    }
+    found
+}
+
+/// Text content: CDATA as is, entities decoded.
+fn xml_text(raw: &str) -> String {
+    let raw = raw.trim();
+    if let Some(cdata) = raw
+        .strip_prefix("<![CDATA[")
+        .and_then(|r| r.strip_suffix("]]>"))
+    {
+        return cdata.to_string();
+    }
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else { break };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|d| d.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::date;
+
+    use super::*;
+
+    #[test]
+    fn vtodos_map_to_fields_and_keep_what_koto_does_not_know() {
+        let xml = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+ <d:response>
+  <d:href>/dav/calendars/me/tasks/a1.ics</d:href>
+  <d:propstat><d:prop>
+   <d:getetag>"e1"</d:getetag>
+   <cal:calendar-data>BEGIN:VCALENDAR&#13;
+VERSION:2.0&#13;
+BEGIN:VTODO&#13;
+UID:a1&#13;
+SUMMARY:Renew passport\, ID &amp; visa&#13;
+DESCRIPTION:Bring photos\nand the old one&#13;
+PRIORITY:2&#13;
+STATUS:IN-PROCESS&#13;
+DUE;VALUE=DATE:20261020&#13;
+CATEGORIES:errands&#13;
+BEGIN:VALARM&#13;
+ACTION:DISPLAY&#13;
+DESCRIPTION:reminder&#13;
+END:VALARM&#13;
+END:VTODO&#13;
+END:VCALENDAR&#13;
+</cal:calendar-data>
+  </d:prop></d:propstat>
+ </d:response>
+</d:multistatus>"#;
+        let tasks = parse_multistatus(xml);
+        assert_eq!(tasks.len(), 1);
+        let task = &tasks[0];
+        assert_eq!(task.href, "/dav/calendars/me/tasks/a1.ics");
+        assert_eq!(task.etag.as_deref(), Some("\"e1\""));
+        let mut fields = task.vtodo.fields();
+        assert_eq!(
+            fields,
+            TaskFields {
+                title: "Renew passport, ID & visa".to_string(),
+                status: Status::InProgress,
+                priority: Priority::High,
+                due: Some(unix(clock::end_of_day(date!(2026 - 10 - 20)))),
+                due_has_time: false,
+                start: None,
+                notes: Some("Bring photos\nand the old one".to_string()),
+            }
+        );
+
+        fields.status = Status::Done;
+        fields.due = None;
+        fields.title = "Renew passport".to_string();
+        let mut vtodo = task.vtodo.clone();
+        vtodo.set_fields(&fields);
+        let ics = vtodo.to_ics();
+        assert!(ics.contains("STATUS:COMPLETED\r\n"));
+        assert!(ics.contains("CATEGORIES:errands\r\n"));
+        assert!(ics.contains("DESCRIPTION:reminder\r\n"));
+        assert!(!ics.contains("DUE"));
+        assert_eq!(Vtodo::parse(&ics).unwrap().fields(), fields);
+    }
+}
@@ -0,0 +1,301 @@
+//! `--git-sync`: the todo.txt files live in a git repository; koto commits every change
+//! and, when the branch tracks a remote, pulls (rebasing local commits) and pushes, so
+//! the list syncs between machines with its history kept in git.
+//!
+//! Git runs on a background thread, so a slow network never stalls the TUI; changes
+//! made while it works are committed together afterwards. The thread and the store take
+//! turns on the files through [`GitSync::work_tree`].
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+/// How often an idle session pulls what other machines pushed.
+const PULL_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Job {
+    /// The files were written: commit them, then pull and push.
+    Commit,
+    /// Just pull and push.
+    Pull,
+}
+
+/// The last failure and whether it was already reported, so a machine that stays
+/// offline is told once rather than every minute.
+#[derive(Default)]
+struct Failure {
+    message: Option<String>,
+    reported: bool,
+}
+
+pub struct GitSync {
+    jobs: Option<Sender<Job>>,
+    worker: Option<JoinHandle<()>>,
+    failure: Arc<Mutex<Failure>>,
+    last_pull: Mutex<Instant>,
+    work_tree: Arc<Mutex<()>>,
+}
+
+impl GitSync {
+    /// Start syncing `files` (in one git work tree). Pulls once before returning, so the
+    /// caller reads the latest list; a failed pull is reported on the first `poll`.
+    pub fn start(files: Vec<PathBuf>) -> Result<Self> {
+        let dir = files
+            .first()
+            .context("no file to sync")?
+            .parent()
+            .filter(|d| !d.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf();
+        git(&dir, &["rev-parse", "--is-inside-work-tree"])
+            .map_err(|_| anyhow!("{} is not inside a git repository", dir.display()))?;
+        let work_tree = Arc::new(Mutex::new(()));
+        let repo = Repo {
+            dir,
+            files,
+            work_tree: work_tree.clone(),
+        };
+        let failure = Arc::new(Mutex::new(Failure::default()));
+        record(&failure, repo.pull_and_push());
+        let (jobs, rx) = mpsc::channel();
+        let worker_failure = failure.clone();
+        let worker = std::thread::spawn(move || repo.run(rx, &worker_failure));
+        Ok(Self {
+            jobs: Some(jobs),
+            worker: Some(worker),
+            failure,
+            last_pull: Mutex::new(Instant::now()),
+            work_tree,
+        })
+    }
+
+    /// Held by the git thread while it commits, pulls and pushes; hold it (with [`hold`])
+    /// across reading, changing and writing the files, or a pull landing in between
+    /// would be overwritten.
+    pub fn work_tree(&self) -> Arc<Mutex<()>> {
+        self.work_tree.clone()
+    }
+
+    /// The files were just written.
+    pub fn changed(&self) {
+        self.send(Job::Commit);
+    }
+
+    /// Pull now and then; returns a failure once, when it is new.
+    pub fn poll(&self) -> Result<()> {
+        if let Ok(mut last) = self.last_pull.lock()
+            && last.elapsed() >= PULL_INTERVAL
+        {
+            *last = Instant::now();
+            self.send(Job::Pull);
+        }
+        let mut failure = self
+            .failure
+            .lock()
+            .map_err(|_| anyhow!("git sync stopped"))?;
+        match failure.message.clone() {
+            Some(message) if !failure.reported => {
+                failure.reported = true;
+                bail!("git sync failed: {message}")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn send(&self, job: Job) {
+        if let Some(jobs) = &self.jobs {
+            let _ = jobs.send(job);
+        }
+    }
+}
+
+impl Drop for GitSync {
+    /// Let the last commit and push finish before the process exits.
+    fn drop(&mut self) {
+        self.jobs.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+struct Repo {
+    dir: PathBuf,
+    files: Vec<PathBuf>,
+    work_tree: Arc<Mutex<()>>,
+}
+
+impl Repo {
+    fn run(&self, jobs: Receiver<Job>, failure: &Mutex<Failure>) {
+        while let Ok(job) = jobs.recv() {
+            // Fold everything queued meanwhile into one round.
+            let commit = std::iter::once(job)
+                .chain(jobs.try_iter())
+                .any(|job| job == Job::Commit);
+            let _tree = hold(&self.work_tree);
+            let result = if commit {
+                self.commit().and_then(|()| self.pull_and_push())
+            } else {
+                self.pull_and_push()
+            };
+            record(failure, result);
+        }
+    }
+
+    fn commit(&self) -> Result<()> {
+        let mut add = vec!["add", "--"];
+        let names: Vec<&str> = self
+            .files
+            .iter()
+            .filter(|f| f.exists())
+            .filter_map(|f| f.file_name()?.to_str())
+            .collect();
+        add.extend(&names);
+        git(&self.dir, &add)?;
+        if git(&self.dir, &["diff", "--cached", "--quiet"]).is_ok() {
+            return Ok(());
+        }
+        let message = format!("koto: update {}", names.join(", "));
+        git(&self.dir, &["commit", "--quiet", "-m", &message])?;
+        Ok(())
+    }
+
+    /// Rebase onto the remote and push; only local commits when nothing is tracked. On a
+    /// conflict the rebase is undone and local commits wait for the next round.
+    fn pull_and_push(&self) -> Result<()> {
+        if git(&self.dir, &["rev-parse", "--abbrev-ref", "@{upstream}"]).is_err() {
+            return Ok(());
+        }
+        if let Err(e) = git(&self.dir, &["pull", "--rebase", "--autostash", "--quiet"]) {
+            let _ = git(&self.dir, &["rebase", "--abort"]);
+            return Err(e.context("pull"));
+        }
+        git(&self.dir, &["push", "--quiet"]).context("push")?;
+        Ok(())
+    }
+}
+
+/// Lock [`GitSync::work_tree`]; a panic elsewhere while holding it left no state behind.
+pub fn hold(work_tree: &Mutex<()>) -> MutexGuard<'_, ()> {
+    work_tree.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn record(failure: &Mutex<Failure>, result: Result<()>) {
+    let Ok(mut failure) = failure.lock() else {
+        return;
+    };
+    match result {
+        Ok(()) => *failure = Failure::default(),
+        Err(e) => {
+            let message = format!("{e:#}");
+            if failure.message.as_ref() != Some(&message) {
+                *failure = Failure {
+                    message: Some(message),
+                    reported: false,
+                };
+            }
+        }
+    }
+}
+
+/// Run git in `dir` without a terminal (no credential prompts); stderr becomes the error.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git {}: {}", args[0], stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    /// A bare remote and a clone of it (`name`) holding a pushed `todo.txt`.
+    fn clone_with_remote(dir: &Path, name: &str) -> PathBuf {
+        let clone = dir.join(name);
+        if !dir.join("remote.git").exists() {
+            git(dir, &["init", "--quiet", "--bare", "remote.git"]).unwrap();
+        }
+        git(dir, &["clone", "--quiet", "remote.git", name]).unwrap();
+        for setting in [["user.name", "koto"], ["user.email", "koto@example.com"]] {
+            git(&clone, &["config", setting[0], setting[1]]).unwrap();
+        }
+        if !clone.join("todo.txt").exists() {
+            std::fs::write(clone.join("todo.txt"), "(A) Ship it\n").unwrap();
+            git(&clone, &["add", "todo.txt"]).unwrap();
+            git(&clone, &["commit", "--quiet", "-m", "start"]).unwrap();
+            git(&clone, &["push", "--quiet", "-u", "origin", "HEAD"]).unwrap();
+        }
+        clone
+    }
+
+    #[test]
+    fn changes_are_committed_and_pushed() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = dir.path().join("remote.git");
+        let clone = clone_with_remote(dir.path(), "clone");
+        let file = clone.join("todo.txt");
+
+        let sync = GitSync::start(vec![file.clone(), clone.join("done.txt")]).unwrap();
+        std::fs::write(&file, "(A) Ship it\n(B) Write notes\n").unwrap();
+        sync.changed();
+        drop(sync);
+
+        let pushed = git(&remote, &["show", "HEAD:todo.txt"]).unwrap();
+        assert_eq!(pushed, "(A) Ship it\n(B) Write notes\n");
+        let subject = git(&remote, &["log", "-1", "--format=%s"]).unwrap();
+        assert_eq!(subject.trim(), "koto: update todo.txt");
+    }
+
+    #[test]
+    fn a_pull_waits_for_a_write_in_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let clone = clone_with_remote(dir.path(), "clone");
+        let other = clone_with_remote(dir.path(), "other");
+        let file = clone.join("todo.txt");
+        let sync = GitSync::start(vec![file.clone(), clone.join("done.txt")]).unwrap();
+
+        // Another machine archives a todo meanwhile.
+        std::fs::write(other.join("done.txt"), "x Old chore\n").unwrap();
+        git(&other, &["add", "done.txt"]).unwrap();
+        git(&other, &["commit", "--quiet", "-m", "archive"]).unwrap();
+        git(&other, &["push", "--quiet"]).unwrap();
+
+        // A write has read the files and is about to save them when a pull comes due.
+        let work_tree = sync.work_tree();
+        let held = hold(&work_tree);
+        let read = std::fs::read_to_string(&file).unwrap();
+        sync.send(Job::Pull);
+        std::thread::sleep(Duration::from_millis(300));
+        assert!(!clone.join("done.txt").exists(), "pulled during a write");
+        std::fs::write(&file, format!("{read}(B) Write notes\n")).unwrap();
+        sync.changed();
+        drop(held);
+        drop(sync);
+
+        git(&other, &["pull", "--quiet"]).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(other.join("todo.txt")).unwrap(),
+            "(A) Ship it\n(B) Write notes\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(clone.join("done.txt")).unwrap(),
+            "x Old chore\n"
+        );
+    }
+}
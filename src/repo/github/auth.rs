@@ -18,15 +18,16 @@ fn token_from_env_var(name: &str) -> Result<Option<String>> {
     }
 }
 
-fn token_from_gh_auth_token() -> Result<String> {
+fn token_from_gh_auth_token(hostname: Option<&str>) -> Result<String> {
     let mut cmd = Command::new("gh");
     cmd.args(["auth", "token"]);
 
-    if let Ok(host) = std::env::var("GH_HOST") {
-        let host = host.trim();
-        if !host.is_empty() {
-            cmd.args(["--hostname", host]);
-        }
+    let env_host = std::env::var("GH_HOST").ok();
+    let host = hostname.or(env_host.as_deref()).map(str::trim);
+    if let Some(host) = host
+        && !host.is_empty()
+    {
+        cmd.args(["--hostname", host]);
     }
 
     let output = cmd
@@ -50,14 +51,23 @@ fn token_from_gh_auth_token() -> Result<String> {
     Ok(token.to_string())
 }
 
+/// Token of an additional account: `token_env` when set, else `gh auth token --hostname`.
+pub fn resolve_account_token(token_env: Option<&str>, hostname: &str) -> Result<String> {
+    if let Some(name) = token_env {
+        return token_from_env_var(name)?
+            .ok_or_else(|| anyhow!("GitHub token env {name} is not set"));
+    }
+    token_from_gh_auth_token(Some(hostname))
+}
+
 /// Resolve GitHub token with env-first fallback to `gh auth token`.
 ///
 /// Priority:
 /// 1) `GITHUB_TOKEN`
-/// 2) `gh auth token` (optionally with `GH_HOST`)
-pub fn resolve_github_token_env_then_gh() -> Result<String> {
+/// 2) `gh auth token` for `hostname` (the API URL's host), else `GH_HOST`, else gh's default
+pub fn resolve_github_token_env_then_gh(hostname: Option<&str>) -> Result<String> {
     if let Some(token) = token_from_env_var("GITHUB_TOKEN")? {
         return Ok(token);
     }
-    token_from_gh_auth_token()
+    token_from_gh_auth_token(hostname)
 }
@@ -0,0 +1,86 @@
+use anyhow::{Result, bail};
+
+/// REST root of github.com.
+pub const GITHUB_COM_API: &str = "https://api.github.com";
+
+/// Validate a GitHub API root such as `https://ghe.example.com/api/v3`.
+///
+/// A bare GitHub Enterprise host (`https://ghe.example.com`) gets `/api/v3` appended;
+/// trailing slashes are dropped.
+pub fn normalize_api_url(raw: &str) -> Result<String> {
+    let url = raw.trim().trim_end_matches('/');
+    let Some((scheme, rest)) = url.split_once("://") else {
+        bail!("invalid GitHub API URL {raw:?}: expected e.g. https://ghe.example.com/api/v3");
+    };
+    if scheme != "https" && scheme != "http" {
+        bail!("invalid GitHub API URL {raw:?}: scheme must be https or http");
+    }
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    if authority.is_empty() || authority.contains(|c: char| c.is_whitespace() || c == '@') {
+        bail!("invalid GitHub API URL {raw:?}: missing or malformed host");
+    }
+    if path.contains(['?', '#']) || path.contains(char::is_whitespace) {
+        bail!("invalid GitHub API URL {raw:?}: must not contain a query, fragment or spaces");
+    }
+    let host = host_of(authority);
+    if host == "github.com" {
+        bail!("invalid GitHub API URL {raw:?}: use {GITHUB_COM_API} (or leave it unset)");
+    }
+    if path.is_empty() && host != "api.github.com" {
+        return Ok(format!("{scheme}://{authority}/api/v3"));
+    }
+    Ok(format!("{scheme}://{authority}{path}"))
+}
+
+/// Host to pass to `gh auth token --hostname`: `github.com` for the public API,
+/// otherwise the Enterprise host (without port).
+pub fn gh_hostname(api_url: &str) -> String {
+    let rest = api_url.split_once("://").map_or(api_url, |(_, rest)| rest);
+    let authority = rest.split('/').next().unwrap_or(rest);
+    match host_of(authority) {
+        "api.github.com" => "github.com".to_string(),
+        host => host.to_string(),
+    }
+}
+
+/// GraphQL root for an API URL: Enterprise serves it at `/api/graphql`, not under `/api/v3`.
+/// Octocrab appends `/graphql` to the base, so this returns the part before it.
+pub fn graphql_base(api_url: &str) -> &str {
+    api_url.strip_suffix("/v3").unwrap_or(api_url)
+}
+
+fn host_of(authority: &str) -> &str {
+    authority
+        .rsplit_once(':')
+        .map_or(authority, |(host, _port)| host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enterprise_urls_are_normalized_and_mapped_to_gh_host() {
+        let url = normalize_api_url("https://ghe.example.com/").unwrap();
+        assert_eq!(url, "https://ghe.example.com/api/v3");
+        assert_eq!(graphql_base(&url), "https://ghe.example.com/api");
+        assert_eq!(gh_hostname(&url), "ghe.example.com");
+
+        assert_eq!(
+            normalize_api_url(" https://api.github.com ").unwrap(),
+            GITHUB_COM_API
+        );
+        assert_eq!(gh_hostname(GITHUB_COM_API), "github.com");
+        assert_eq!(graphql_base(GITHUB_COM_API), GITHUB_COM_API);
+
+        for bad in [
+            "ghe.example.com",
+            "ftp://ghe.example.com",
+            "https://github.com",
+            "https:///api/v3",
+            "https://ghe.example.com/api/v3?x=1",
+        ] {
+            assert!(normalize_api_url(bad).is_err(), "{bad}");
+        }
+    }
+}
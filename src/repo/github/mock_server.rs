@@ -0,0 +1,249 @@
+//! A local stand-in for GitHub's GraphQL endpoint, so a whole sync can run end to end
+//! without a token: canned responses (usually the recorded ones in `testdata/`) are
+//! served per query and cursor, at an Enterprise-style `api_base`.
+
+use std::path::PathBuf;
+
+use serde_json::Value;
+use wiremock::matchers::{method, path};
+use wiremock::{Match, Mock, MockServer, Request, ResponseTemplate};
+
+use super::model::{AttentionPrs, FetchOptions, RepoFilter, RetryPolicy};
+use super::{AUTHORED_QUERY, REVIEW_REQUESTED_QUERY};
+
+/// The two attention queries every sync runs.
+#[derive(Debug, Clone, Copy)]
+pub enum Query {
+    Authored,
+    ReviewRequested,
+}
+
+impl Query {
+    fn text(self) -> &'static str {
+        match self {
+            Query::Authored => AUTHORED_QUERY,
+            Query::ReviewRequested => REVIEW_REQUESTED_QUERY,
+        }
+    }
+}
+
+/// Matches a GraphQL request by its query text and `cursor` variable.
+struct Page {
+    query: Query,
+    cursor: Option<String>,
+}
+
+impl Match for Page {
+    fn matches(&self, request: &Request) -> bool {
+        let Ok(body) = serde_json::from_slice::<Value>(&request.body) else {
+            return false;
+        };
+        body["query"].as_str() == Some(self.query.text())
+            && body["variables"]["cursor"].as_str() == self.cursor.as_deref()
+    }
+}
+
+pub struct MockGithub {
+    server: MockServer,
+}
+
+impl MockGithub {
+    pub async fn start() -> Self {
+        Self {
+            server: MockServer::start().await,
+        }
+    }
+
+    /// What `GithubConfig::api_base` / `graphql_client` take to talk to this server.
+    pub fn api_base(&self) -> String {
+        format!("{}/api/v3", self.server.uri())
+    }
+
+    /// Answer the page of `query` after `cursor` (`None`: the first page) with `body`.
+    pub async fn page(&self, query: Query, cursor: Option<&str>, body: Value) {
+        self.reply(
+            query,
+            cursor,
+            ResponseTemplate::new(200).set_body_json(body),
+        )
+        .await;
+    }
+
+    /// Fail every request for `query` with an HTTP `status` and GitHub's error body.
+    pub async fn fail(&self, query: Query, status: u16, message: &str) {
+        let body = serde_json::json!({
+            "message": message,
+            "documentation_url": "https://docs.github.com/graphql",
+        });
+        self.reply(
+            query,
+            None,
+            ResponseTemplate::new(status).set_body_json(body),
+        )
+        .await;
+    }
+
+    async fn reply(&self, query: Query, cursor: Option<&str>, response: ResponseTemplate) {
+        Mock::given(method("POST"))
+            .and(path("/api/graphql"))
+            .and(Page {
+                query,
+                cursor: cursor.map(str::to_string),
+            })
+            .respond_with(response)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Run a full sync against the server.
+    pub async fn sync(&self, opts: &FetchOptions) -> anyhow::Result<AttentionPrs> {
+        let octo = super::graphql_client("test-token".to_string(), Some(self.api_base()))?;
+        super::fetch_attention_prs(&octo, opts, &[], &RepoFilter::default(), &[], &|_| {}).await
+    }
+}
+
+/// A recorded response from `testdata/`, to serve as is or adjusted.
+pub fn fixture(name: &str) -> Value {
+    let file = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/repo/github/testdata")
+        .join(format!("{name}.json"));
+    let raw = std::fs::read_to_string(&file).unwrap_or_else(|e| panic!("{name}: {e}"));
+    serde_json::from_str(&raw).unwrap_or_else(|e| panic!("{name}: {e}"))
+}
+
+/// Options for a sync of everything updated since `cutoff` (RFC 3339), without retries.
+pub fn options(cutoff: &str) -> FetchOptions {
+    FetchOptions {
+        cutoff_ts: super::timeutil::parse_github_datetime_to_unix(cutoff).expect("cutoff"),
+        utc_offset_secs: 0,
+        include_team_requests: true,
+        page_size: 2,
+        retry: RetryPolicy {
+            attempts: 1,
+            base_delay_ms: 0,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::github::FailureKind;
+    use crate::repo::github::model::{CiState, Pr};
+    use crate::worker::block_on;
+
+    fn by_key<'a>(attention: &'a AttentionPrs, key: &str) -> &'a Pr {
+        attention
+            .prs
+            .iter()
+            .find(|pr| pr.pr_key == key)
+            .unwrap_or_else(|| panic!("{key} missing"))
+    }
+
+    /// Two authored pages, the second reaching past the cutoff, and two review-request pages.
+    async fn paged_server() -> MockGithub {
+        let github = MockGithub::start().await;
+        let mut first = fixture("authored_check_runs");
+        first["data"]["viewer"]["pullRequests"]["pageInfo"] =
+            serde_json::json!({ "hasNextPage": true, "endCursor": "a1" });
+        github.page(Query::Authored, None, first.clone()).await;
+        let mut second = first;
+        let connection = &mut second["data"]["viewer"]["pullRequests"];
+        connection["pageInfo"] = serde_json::json!({ "hasNextPage": true, "endCursor": "a2" });
+        let node = &mut connection["nodes"][0];
+        node["number"] = 41.into();
+        node["updatedAt"] = "2026-01-05T00:00:00Z".into();
+        // No page after "a2": the cutoff must end the pagination before it is asked for.
+        github.page(Query::Authored, Some("a1"), second).await;
+
+        let requested = fixture("search_team_reviewers");
+        let cursor = requested["data"]["search"]["pageInfo"]["endCursor"]
+            .as_str()
+            .map(str::to_string);
+        github.page(Query::ReviewRequested, None, requested).await;
+        github
+            .page(
+                Query::ReviewRequested,
+                cursor.as_deref(),
+                fixture("search_status_contexts"),
+            )
+            .await;
+        github
+    }
+
+    #[test]
+    fn sync_pages_to_the_cutoff_and_computes_merge_blockers() {
+        let attention = block_on(async {
+            let github = paged_server().await;
+            github.sync(&options("2026-10-01T00:00:00Z")).await
+        })
+        .unwrap()
+        .unwrap();
+
+        assert!(!attention.is_partial(), "{:?}", attention.failed_queries);
+        assert_eq!(attention.viewer_login, "octocat");
+        let mut keys: Vec<&str> = attention.prs.iter().map(|pr| pr.pr_key.as_str()).collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                "acme/api#42",
+                "acme/billing#120",
+                "acme/billing#121",
+                "acme/legacy#7"
+            ]
+        );
+        let pages: Vec<(&str, u32)> = attention
+            .stats
+            .iter()
+            .map(|st| (st.query.as_str(), st.pages))
+            .collect();
+        assert_eq!(pages, [("authored", 2), ("review_requested", 2)]);
+
+        let own = by_key(&attention, "acme/api#42");
+        assert!(own.is_viewer_author);
+        let blockers = own.merge_blockers.as_ref().unwrap();
+        assert_eq!(blockers.failing_required_checks, ["test"]);
+        assert_eq!(
+            (blockers.current_approvals, blockers.required_approvals),
+            (1, Some(2))
+        );
+        let legacy = by_key(&attention, "acme/legacy#7");
+        assert!(matches!(legacy.ci_state, CiState::Running));
+        let blockers = legacy.merge_blockers.as_ref().unwrap();
+        assert!(blockers.has_conflicts && blockers.is_behind_base);
+    }
+
+    #[test]
+    fn one_failed_query_is_partial_and_a_rejected_token_fails() {
+        let (partial, rejected) = block_on(async {
+            let github = MockGithub::start().await;
+            github.fail(Query::Authored, 502, "Bad Gateway").await;
+            github
+                .page(
+                    Query::ReviewRequested,
+                    None,
+                    fixture("search_missing_rollup"),
+                )
+                .await;
+            let partial = github.sync(&options("2026-10-01T00:00:00Z")).await;
+
+            let github = MockGithub::start().await;
+            github.fail(Query::Authored, 401, "Bad credentials").await;
+            let rejected = github.sync(&options("2026-10-01T00:00:00Z")).await;
+            (partial, rejected)
+        })
+        .unwrap();
+
+        let partial = partial.unwrap();
+        assert!(partial.is_partial());
+        assert!(
+            partial.failed_queries[0].contains("authored"),
+            "{partial:?}"
+        );
+        assert_eq!(partial.prs.len(), 2);
+
+        let err = super::super::QueryError::from_anyhow(rejected.unwrap_err());
+        assert_eq!(err.kind, FailureKind::Auth, "{err}");
+    }
+}
@@ -1,23 +1,36 @@
 pub mod auth;
+pub mod endpoint;
+#[cfg(test)]
+mod mock_server;
 pub mod model;
+pub mod notifications;
+mod retry;
 mod timeutil;
 
 use std::collections::HashMap;
+use std::time::Instant;
 
 use anyhow::{Result, anyhow};
-use model::{CiCheck, CiCheckState, CiState, MergeBlockers, Pr, ReviewState, StatusContextNode};
+use model::{
+    AttentionPrs, CiCheck, CiCheckState, CiState, DiffStats, FetchOptions, MergeBlockers,
+    Milestone, Pr, PrLabel, PrReview, QueryStats, RepoFilter, RetryPolicy, ReviewEvent,
+    ReviewState, SearchHit, SearchResults, SearchSpec, StatusContextNode, SyncProgress,
+};
 use octocrab::Octocrab;
-use timeutil::{parse_github_datetime_to_unix, unix_to_ymd};
+pub use retry::{FailureKind, QueryError};
+use timeutil::{parse_github_datetime_to_unix, search_timestamp};
 
 #[derive(Debug, serde::Serialize)]
 struct PaginationVars {
     page_size: i32,
     cursor: Option<String>,
+    /// `false` leaves out fields some orgs forbid (see `OPTIONAL_FIELDS`).
+    optional_fields: bool,
 }
 
 #[derive(Debug, serde::Serialize)]
-struct GraphQlPayload<V> {
-    query: &'static str,
+struct GraphQlPayload<'a, V> {
+    query: &'a str,
     variables: V,
 }
 
@@ -42,9 +55,18 @@ struct Repository {
 
 #[derive(Debug, serde::Deserialize)]
 struct Author {
+    /// `Bot` for GitHub Apps such as Dependabot and Renovate.
+    #[serde(rename = "__typename", default)]
+    typename: Option<String>,
     login: String,
 }
 
+impl Author {
+    fn is_bot(&self) -> bool {
+        self.typename.as_deref() == Some("Bot") || self.login.ends_with("[bot]")
+    }
+}
+
 #[derive(Debug, serde::Deserialize)]
 struct ReviewRequestConnection {
     nodes: Option<Vec<ReviewRequestNode>>,
@@ -61,6 +83,8 @@ struct RequestedReviewer {
     #[serde(rename = "__typename")]
     typename: Option<String>,
     login: Option<String>, // User
+    #[serde(rename = "combinedSlug")]
+    combined_slug: Option<String>, // Team, e.g. "org/team"
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -96,6 +120,68 @@ struct ReviewsConnection {
     total_count: Option<i32>,
 }
 
+#[derive(Debug, serde::Deserialize)]
+struct CountConnection {
+    #[serde(rename = "totalCount")]
+    total_count: Option<i32>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ReviewThreads {
+    #[serde(rename = "totalCount")]
+    total_count: Option<i32>,
+    nodes: Option<Vec<ReviewThreadNode>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ReviewThreadNode {
+    #[serde(rename = "isResolved")]
+    is_resolved: Option<bool>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct LatestReviews {
+    nodes: Option<Vec<LatestReviewNode>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct LatestReviewNode {
+    state: Option<String>,
+    author: Option<Author>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct RawMilestone {
+    title: String,
+    /// Midnight UTC of the due day, e.g. `2026-11-01T00:00:00Z`.
+    #[serde(rename = "dueOn")]
+    due_on: Option<String>,
+}
+
+impl RawMilestone {
+    fn into_milestone(self) -> Milestone {
+        Milestone {
+            title: self.title,
+            due_on: self.due_on.and_then(|d| d.get(..10).map(str::to_string)),
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Labels {
+    nodes: Option<Vec<Option<PrLabel>>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Assignees {
+    nodes: Option<Vec<Option<RepoOwner>>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ViewerReview {
+    state: Option<String>,
+}
+
 #[derive(Debug, serde::Deserialize)]
 struct BranchProtectionRule {
     #[serde(rename = "requiredApprovingReviewCount")]
@@ -115,6 +201,8 @@ struct PullRequestNode {
     number: i64,
     title: String,
     url: String,
+    #[serde(rename = "createdAt")]
+    created_at: Option<String>,
     #[serde(rename = "updatedAt")]
     updated_at: String,
     repository: Repository,
@@ -123,6 +211,10 @@ struct PullRequestNode {
     review_requests: Option<ReviewRequestConnection>,
     #[serde(rename = "headRefOid")]
     head_ref_oid: Option<String>,
+    additions: Option<u32>,
+    deletions: Option<u32>,
+    #[serde(rename = "changedFiles")]
+    changed_files: Option<u32>,
     #[serde(rename = "reviewDecision")]
     review_decision: Option<String>,
     #[serde(rename = "isDraft")]
@@ -132,6 +224,16 @@ struct PullRequestNode {
     merge_state_status: Option<String>,
     commits: Option<Commits>,
     reviews: Option<ReviewsConnection>,
+    comments: Option<CountConnection>,
+    #[serde(rename = "reviewThreads")]
+    review_threads: Option<ReviewThreads>,
+    #[serde(rename = "latestReviews")]
+    latest_reviews: Option<LatestReviews>,
+    assignees: Option<Assignees>,
+    labels: Option<Labels>,
+    milestone: Option<RawMilestone>,
+    #[serde(rename = "viewerLatestReview")]
+    viewer_latest_review: Option<ViewerReview>,
     #[serde(rename = "baseRef")]
     base_ref: Option<BaseRef>,
 }
@@ -140,7 +242,8 @@ struct PullRequestNode {
 struct ViewerPullRequests {
     #[serde(rename = "pageInfo")]
     page_info: PageInfo,
-    nodes: Option<Vec<PullRequestNode>>,
+    /// Items the token may not read (e.g. SAML-protected orgs) come back as `null`.
+    nodes: Option<Vec<Option<PullRequestNode>>>,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -150,21 +253,216 @@ struct Viewer {
     pull_requests: ViewerPullRequests,
 }
 
+#[derive(Debug, serde::Deserialize)]
+struct SavedSearchData {
+    search: SavedSearchResult,
+    #[serde(rename = "rateLimit")]
+    rate_limit: Option<RateLimit>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct SavedSearchResult {
+    #[serde(rename = "pageInfo")]
+    page_info: PageInfo,
+    nodes: Option<Vec<Option<SavedSearchNode>>>,
+}
+
+/// An Issue or PullRequest; other result types come back as empty objects.
+#[derive(Debug, serde::Deserialize)]
+struct SavedSearchNode {
+    number: Option<i64>,
+    title: Option<String>,
+    url: Option<String>,
+    repository: Option<RepositoryName>,
+    milestone: Option<RawMilestone>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct RepositoryName {
+    #[serde(rename = "nameWithOwner")]
+    name_with_owner: String,
+}
+
+impl SavedSearchNode {
+    fn into_hit(self) -> Option<SearchHit> {
+        Some(SearchHit {
+            key: format!("{}#{}", self.repository?.name_with_owner, self.number?),
+            title: self.title?,
+            url: self.url?,
+            milestone: self.milestone.map(RawMilestone::into_milestone),
+        })
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ViewerLogin {
+    login: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct RateLimit {
+    cost: Option<u32>,
+    remaining: Option<u32>,
+}
+
 #[derive(Debug, serde::Deserialize)]
 struct AuthoredData {
     viewer: Viewer,
+    #[serde(rename = "rateLimit")]
+    rate_limit: Option<RateLimit>,
 }
 
 #[derive(Debug, serde::Deserialize)]
 struct GraphQlResponse<T> {
-    data: T,
+    /// `null` when an error stopped the whole query.
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct GraphQlError {
+    message: String,
+    /// Response path of the failed field, e.g. `["search", "nodes", 3, "baseRef"]`.
+    #[serde(default)]
+    path: Vec<serde_json::Value>,
+    /// GitHub's error type (`FORBIDDEN`, `RATE_LIMITED`, `NOT_FOUND`, ...); validation
+    /// errors of the query itself have none.
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    #[serde(default)]
+    extensions: GraphQlErrorExtensions,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct GraphQlErrorExtensions {
+    /// Validation error code, e.g. `undefinedField`.
+    code: Option<String>,
+    #[serde(default)]
+    saml_failure: bool,
+}
+
+impl GraphQlError {
+    fn failure_kind(&self) -> FailureKind {
+        match self.kind.as_deref() {
+            Some("RATE_LIMITED") => FailureKind::RateLimited,
+            Some("FORBIDDEN" | "INSUFFICIENT_SCOPES") => FailureKind::Auth,
+            Some("MAX_NODE_LIMIT_EXCEEDED" | "EXCESSIVE_PAGINATION") => FailureKind::Query,
+            Some("SERVICE_UNAVAILABLE") => FailureKind::Transient,
+            Some(_) => FailureKind::Other,
+            None if self.extensions.saml_failure => FailureKind::Auth,
+            None if self.extensions.code.is_some()
+                || self.message.starts_with("Parse error")
+                || self.message.starts_with("Variable ") =>
+            {
+                FailureKind::Query
+            }
+            None => FailureKind::Other,
+        }
+    }
+}
+
+/// GitHub rejects search queries longer than this.
+const SEARCH_QUERY_MAX_LEN: usize = 256;
+
+/// Fields behind `@include(if: $optional_fields)`: branch protection needs admin-ish
+/// access and team reviewers need `read:org`, which some orgs do not grant.
+const OPTIONAL_FIELDS: &[&str] = &["baseRef", "branchProtectionRule", "requestedReviewer"];
+
+impl<T> GraphQlResponse<T> {
+    /// True when some error is about one of the `OPTIONAL_FIELDS`.
+    fn optional_fields_failed(&self) -> bool {
+        self.errors.iter().any(|e| {
+            e.path
+                .iter()
+                .any(|seg| seg.as_str().is_some_and(|s| OPTIONAL_FIELDS.contains(&s)))
+        })
+    }
+
+    /// The data, or the GraphQL errors that stopped the query, classified by the most
+    /// serious one.
+    fn into_data(self, what: &str) -> Result<T, QueryError> {
+        self.data.ok_or_else(|| QueryError {
+            kind: self
+                .errors
+                .iter()
+                .fold(FailureKind::Other, |kind, e| kind.max(e.failure_kind())),
+            message: format!(
+                "GitHub GraphQL {what} query returned no data: {}",
+                self.errors
+                    .iter()
+                    .map(|e| e.message.as_str())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ),
+        })
+    }
+}
+
+impl GraphQlResponse<serde_json::Value> {
+    /// Decode the data into `T`. When an error nulled a field `T` requires, the data is
+    /// dropped so `into_data` reports the GraphQL errors rather than a serde message.
+    fn decode<T: serde::de::DeserializeOwned>(
+        self,
+        what: &str,
+    ) -> Result<GraphQlResponse<T>, QueryError> {
+        let data = match self.data.map(serde_json::from_value::<T>) {
+            None => None,
+            Some(Ok(data)) => Some(data),
+            Some(Err(_)) if !self.errors.is_empty() => None,
+            Some(Err(e)) => {
+                return Err(QueryError {
+                    kind: FailureKind::Other,
+                    message: format!("GitHub GraphQL {what} query returned unexpected data: {e}"),
+                });
+            }
+        };
+        Ok(GraphQlResponse {
+            data,
+            errors: self.errors,
+        })
+    }
+}
+
+/// One page of an attention query. If the optional fields fail (typically FORBIDDEN),
+/// the page is fetched again without them, and `reduced` keeps later pages on the
+/// smaller query; PRs then simply lack branch protection and team reviewers.
+async fn fetch_page<T: serde::de::DeserializeOwned, V: serde::Serialize>(
+    octo: &Octocrab,
+    query: &str,
+    variables: impl Fn(bool) -> V,
+    retry: RetryPolicy,
+    what: &str,
+    reduced: &mut bool,
+) -> Result<T, QueryError> {
+    if !*reduced {
+        let payload = GraphQlPayload {
+            query,
+            variables: variables(true),
+        };
+        let resp: GraphQlResponse<serde_json::Value> =
+            retry::graphql(octo, &payload, retry, what).await?;
+        let resp: GraphQlResponse<T> = resp.decode(what)?;
+        if !resp.optional_fields_failed() {
+            return resp.into_data(what);
+        }
+        *reduced = true;
+    }
+    let payload = GraphQlPayload {
+        query,
+        variables: variables(false),
+    };
+    let resp: GraphQlResponse<serde_json::Value> =
+        retry::graphql(octo, &payload, retry, what).await?;
+    resp.decode::<T>(what)?.into_data(what)
 }
 
 #[derive(Debug, serde::Deserialize)]
 struct SearchResult {
     #[serde(rename = "pageInfo")]
     page_info: PageInfo,
-    nodes: Option<Vec<SearchNode>>,
+    /// `null` for items the token may not read, as in `ViewerPullRequests`.
+    nodes: Option<Vec<Option<SearchNode>>>,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -174,6 +472,8 @@ struct SearchNode {
     number: Option<i64>,
     title: Option<String>,
     url: Option<String>,
+    #[serde(rename = "createdAt")]
+    created_at: Option<String>,
     #[serde(rename = "updatedAt")]
     updated_at: Option<String>,
     repository: Option<Repository>,
@@ -182,6 +482,10 @@ struct SearchNode {
     review_requests: Option<ReviewRequestConnection>,
     #[serde(rename = "headRefOid")]
     head_ref_oid: Option<String>,
+    additions: Option<u32>,
+    deletions: Option<u32>,
+    #[serde(rename = "changedFiles")]
+    changed_files: Option<u32>,
     #[serde(rename = "reviewDecision")]
     review_decision: Option<String>,
     #[serde(rename = "isDraft")]
@@ -191,6 +495,16 @@ struct SearchNode {
     merge_state_status: Option<String>,
     commits: Option<Commits>,
     reviews: Option<ReviewsConnection>,
+    comments: Option<CountConnection>,
+    #[serde(rename = "reviewThreads")]
+    review_threads: Option<ReviewThreads>,
+    #[serde(rename = "latestReviews")]
+    latest_reviews: Option<LatestReviews>,
+    assignees: Option<Assignees>,
+    labels: Option<Labels>,
+    milestone: Option<RawMilestone>,
+    #[serde(rename = "viewerLatestReview")]
+    viewer_latest_review: Option<ViewerReview>,
     #[serde(rename = "baseRef")]
     base_ref: Option<BaseRef>,
 }
@@ -204,17 +518,28 @@ impl SearchNode {
             number: self.number?,
             title: self.title?,
             url: self.url?,
+            created_at: self.created_at,
             updated_at: self.updated_at?,
             repository: self.repository?,
             author: self.author,
             review_requests: self.review_requests,
             head_ref_oid: self.head_ref_oid,
+            additions: self.additions,
+            deletions: self.deletions,
+            changed_files: self.changed_files,
             review_decision: self.review_decision,
             is_draft: self.is_draft,
             mergeable: self.mergeable,
             merge_state_status: self.merge_state_status,
             commits: self.commits,
             reviews: self.reviews,
+            comments: self.comments,
+            review_threads: self.review_threads,
+            latest_reviews: self.latest_reviews,
+            assignees: self.assignees,
+            labels: self.labels,
+            milestone: self.milestone,
+            viewer_latest_review: self.viewer_latest_review,
             base_ref: self.base_ref,
         })
     }
@@ -223,28 +548,42 @@ impl SearchNode {
 #[derive(Debug, serde::Deserialize)]
 struct SearchData {
     search: SearchResult,
+    viewer: Option<ViewerLogin>,
+    #[serde(rename = "rateLimit")]
+    rate_limit: Option<RateLimit>,
 }
 
-const AUTHORED_QUERY: &str = r#"
-query ($page_size: Int!, $cursor: String) {
-  viewer {
-    login
-    pullRequests(states: OPEN, orderBy: {field: UPDATED_AT, direction: DESC}, first: $page_size, after: $cursor) {
-      pageInfo {
-        hasNextPage
-        endCursor
-      }
-      nodes {
-        ...PrFields
-      }
+impl QueryStats {
+    fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            pages: 0,
+            nodes: 0,
+            kept: 0,
+            elapsed_ms: 0,
+            cost: 0,
+            rate_remaining: None,
+            reduced: false,
+        }
+    }
+
+    fn record_rate_limit(&mut self, rate: Option<&RateLimit>) {
+        if let Some(rate) = rate {
+            self.cost += rate.cost.unwrap_or(0);
+            self.rate_remaining = rate.remaining.or(self.rate_remaining);
+        }
     }
-  }
 }
 
+/// The PR fields a sync reads, shared by the authored and the watched-PR queries.
+macro_rules! pr_fields_fragment {
+    () => {
+        r#"
 fragment PrFields on PullRequest {
   number
   title
   url
+  createdAt
   updatedAt
   repository {
     name
@@ -253,6 +592,7 @@ fragment PrFields on PullRequest {
     }
   }
   author {
+    __typename
     login
   }
   reviewRequests(first: 20) {
@@ -262,10 +602,16 @@ fragment PrFields on PullRequest {
         ... on User {
           login
         }
+        ... on Team @include(if: $optional_fields) {
+          combinedSlug
+        }
       }
     }
   }
   headRefOid
+  additions
+  deletions
+  changedFiles
   reviewDecision
   isDraft
   mergeable
@@ -298,17 +644,78 @@ fragment PrFields on PullRequest {
   reviews(states: APPROVED) {
     totalCount
   }
-  baseRef {
+  comments {
+    totalCount
+  }
+  reviewThreads(first: 100) {
+    totalCount
+    nodes {
+      isResolved
+    }
+  }
+  latestReviews(first: 20) {
+    nodes {
+      state
+      author {
+        login
+      }
+    }
+  }
+  assignees(first: 10) {
+    nodes {
+      login
+    }
+  }
+  labels(first: 20) {
+    nodes {
+      name
+      color
+    }
+  }
+  milestone {
+    title
+    dueOn
+  }
+  viewerLatestReview {
+    state
+  }
+  baseRef @include(if: $optional_fields) {
     branchProtectionRule {
       requiredApprovingReviewCount
       requiredStatusCheckContexts
     }
   }
 }
-"#;
+"#
+    };
+}
+
+const AUTHORED_QUERY: &str = concat!(
+    r#"
+query ($page_size: Int!, $cursor: String, $optional_fields: Boolean!) {
+  viewer {
+    login
+    pullRequests(states: OPEN, orderBy: {field: UPDATED_AT, direction: DESC}, first: $page_size, after: $cursor) {
+      pageInfo {
+        hasNextPage
+        endCursor
+      }
+      nodes {
+        ...PrFields
+      }
+    }
+  }
+  rateLimit {
+    cost
+    remaining
+  }
+}
+"#,
+    pr_fields_fragment!()
+);
 
 const REVIEW_REQUESTED_QUERY: &str = r#"
-query ($page_size: Int!, $cursor: String, $search_query: String!) {
+query ($page_size: Int!, $cursor: String, $search_query: String!, $optional_fields: Boolean!) {
   search(query: $search_query, type: ISSUE, first: $page_size, after: $cursor) {
     pageInfo {
       hasNextPage
@@ -320,6 +727,7 @@ query ($page_size: Int!, $cursor: String, $search_query: String!) {
         number
         title
         url
+        createdAt
         updatedAt
         repository {
           name
@@ -328,6 +736,7 @@ query ($page_size: Int!, $cursor: String, $search_query: String!) {
           }
         }
         author {
+          __typename
           login
         }
         reviewRequests(first: 20) {
@@ -337,10 +746,16 @@ query ($page_size: Int!, $cursor: String, $search_query: String!) {
               ... on User {
                 login
               }
+              ... on Team @include(if: $optional_fields) {
+                combinedSlug
+              }
             }
           }
         }
         headRefOid
+        additions
+        deletions
+        changedFiles
         reviewDecision
         isDraft
         mergeable
@@ -373,7 +788,42 @@ query ($page_size: Int!, $cursor: String, $search_query: String!) {
         reviews(states: APPROVED) {
           totalCount
         }
-        baseRef {
+        comments {
+          totalCount
+        }
+        reviewThreads(first: 100) {
+          totalCount
+          nodes {
+            isResolved
+          }
+        }
+        latestReviews(first: 20) {
+          nodes {
+            state
+            author {
+              login
+            }
+          }
+        }
+        assignees(first: 10) {
+          nodes {
+            login
+          }
+        }
+        labels(first: 20) {
+          nodes {
+            name
+            color
+          }
+        }
+        milestone {
+          title
+          dueOn
+        }
+        viewerLatestReview {
+          state
+        }
+        baseRef @include(if: $optional_fields) {
           branchProtectionRule {
             requiredApprovingReviewCount
             requiredStatusCheckContexts
@@ -382,6 +832,54 @@ query ($page_size: Int!, $cursor: String, $search_query: String!) {
       }
     }
   }
+  viewer {
+    login
+  }
+  rateLimit {
+    cost
+    remaining
+  }
+}
+"#;
+
+const SAVED_SEARCH_QUERY: &str = r#"
+query ($page_size: Int!, $cursor: String, $search_query: String!) {
+  search(query: $search_query, type: ISSUE, first: $page_size, after: $cursor) {
+    pageInfo {
+      hasNextPage
+      endCursor
+    }
+    nodes {
+      ... on PullRequest {
+        number
+        title
+        url
+        repository {
+          nameWithOwner
+        }
+        milestone {
+          title
+          dueOn
+        }
+      }
+      ... on Issue {
+        number
+        title
+        url
+        repository {
+          nameWithOwner
+        }
+        milestone {
+          title
+          dueOn
+        }
+      }
+    }
+  }
+  rateLimit {
+    cost
+    remaining
+  }
 }
 "#;
 
@@ -492,6 +990,7 @@ fn map_review_state(node: &PullRequestNode, is_requested: bool) -> ReviewState {
     }
     match node.review_decision.as_deref() {
         Some("APPROVED") => ReviewState::Approved,
+        Some("CHANGES_REQUESTED") => ReviewState::ChangesRequested,
         _ => ReviewState::None,
     }
 }
@@ -514,6 +1013,49 @@ fn is_review_requested_by_user(node: &PullRequestNode, viewer_login: &str) -> bo
     false
 }
 
+/// `org/team` slugs among the PR's pending review requests.
+fn requested_team_slugs(node: &PullRequestNode) -> Vec<String> {
+    node.review_requests
+        .iter()
+        .flat_map(|rr| rr.nodes.iter().flatten())
+        .filter_map(|n| n.requested_reviewer.as_ref())
+        .filter(|r| r.typename.as_deref() == Some("Team"))
+        .filter_map(|r| r.combined_slug.clone())
+        .collect()
+}
+
+fn requested_user_logins(node: &PullRequestNode) -> Vec<String> {
+    node.review_requests
+        .iter()
+        .flat_map(|rr| rr.nodes.iter().flatten())
+        .filter_map(|n| n.requested_reviewer.as_ref())
+        .filter(|r| r.typename.as_deref() == Some("User"))
+        .filter_map(|r| r.login.clone())
+        .collect()
+}
+
+/// Each reviewer's latest review; reviews by deleted accounts are dropped.
+fn latest_reviews(node: &PullRequestNode) -> Vec<PrReview> {
+    node.latest_reviews
+        .iter()
+        .flat_map(|r| r.nodes.iter().flatten())
+        .filter_map(|n| {
+            Some(PrReview {
+                author: n.author.as_ref()?.login.clone(),
+                state: n.state.clone()?,
+            })
+        })
+        .collect()
+}
+
+fn assignee_logins(node: &PullRequestNode) -> Vec<String> {
+    node.assignees
+        .iter()
+        .flat_map(|a| a.nodes.iter().flatten().flatten())
+        .map(|a| a.login.clone())
+        .collect()
+}
+
 fn compute_merge_blockers(node: &PullRequestNode, ci_checks: &[CiCheck]) -> MergeBlockers {
     let has_conflicts = node
         .mergeable
@@ -567,7 +1109,7 @@ fn compute_merge_blockers(node: &PullRequestNode, ci_checks: &[CiCheck]) -> Merg
     }
 }
 
-fn to_pr(node: PullRequestNode, is_requested: bool, viewer_login: &str) -> Option<Pr> {
+fn to_pr(mut node: PullRequestNode, is_requested: bool, viewer_login: &str) -> Option<Pr> {
     let ci_checks = map_ci_checks(&node);
     let ci_state = derive_ci_state(rollup_state(&node), &ci_checks);
     let last_commit_sha = node.head_ref_oid.clone();
@@ -580,6 +1122,11 @@ fn to_pr(node: PullRequestNode, is_requested: bool, viewer_login: &str) -> Optio
         .map(|a| a.login.clone())
         .unwrap_or_else(|| "unknown".to_string());
     let updated_at_unix = parse_github_datetime_to_unix(&node.updated_at)?;
+    let created_at_unix = node
+        .created_at
+        .as_deref()
+        .and_then(parse_github_datetime_to_unix)
+        .unwrap_or(updated_at_unix);
     let pr_key = format!("{owner}/{repo}#{}", node.number);
 
     let is_viewer_author = node
@@ -588,6 +1135,48 @@ fn to_pr(node: PullRequestNode, is_requested: bool, viewer_login: &str) -> Optio
         .map(|a| a.login.as_str() == viewer_login)
         .unwrap_or(false);
 
+    let viewer_approved = node
+        .viewer_latest_review
+        .as_ref()
+        .and_then(|r| r.state.as_deref())
+        == Some("APPROVED");
+
+    let requested_directly = is_review_requested_by_user(&node, viewer_login);
+    let requested_teams = requested_team_slugs(&node);
+    let requested_users = requested_user_logins(&node);
+    let reviews = latest_reviews(&node);
+    let assignees = assignee_logins(&node);
+    let labels: Vec<PrLabel> = node
+        .labels
+        .take()
+        .and_then(|l| l.nodes)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .collect();
+    let diff_stats = match (node.additions, node.deletions, node.changed_files) {
+        (Some(additions), Some(deletions), Some(changed_files)) => Some(DiffStats {
+            additions,
+            deletions,
+            changed_files,
+        }),
+        _ => None,
+    };
+    let comments = node
+        .comments
+        .as_ref()
+        .and_then(|c| c.total_count)
+        .unwrap_or(0) as u32;
+    let threads = node.review_threads.as_ref();
+    let review_threads = threads.and_then(|t| t.total_count).unwrap_or(0) as u32;
+    // Only the first 100 threads are fetched; a longer discussion undercounts.
+    let unresolved_threads = threads.and_then(|t| t.nodes.as_ref()).map_or(0, |nodes| {
+        nodes
+            .iter()
+            .filter(|n| n.is_resolved == Some(false))
+            .count()
+    }) as u32;
+
     let merge_blockers = compute_merge_blockers(&node, &ci_checks);
     let merge_blockers = if merge_blockers.is_clear() {
         None
@@ -603,6 +1192,7 @@ fn to_pr(node: PullRequestNode, is_requested: bool, viewer_login: &str) -> Optio
         author,
         title: node.title,
         url: node.url,
+        created_at_unix,
         updated_at_unix,
         last_commit_sha,
         ci_state,
@@ -612,6 +1202,20 @@ fn to_pr(node: PullRequestNode, is_requested: bool, viewer_login: &str) -> Optio
         mergeable: node.mergeable.clone(),
         merge_state_status: node.merge_state_status.clone(),
         is_viewer_author,
+        viewer_approved,
+        requested_directly,
+        requested_teams,
+        requested_users,
+        reviews,
+        assignees,
+        labels,
+        milestone: node.milestone.take().map(RawMilestone::into_milestone),
+        diff_stats,
+        comments,
+        review_threads,
+        unresolved_threads,
+        watched: false,
+        author_is_bot: node.author.as_ref().is_some_and(Author::is_bot),
         merge_blockers,
     })
 }
@@ -625,121 +1229,233 @@ fn merge_into(map: &mut HashMap<String, Pr>, mut pr: Pr) {
     map.insert(pr.pr_key.clone(), pr);
 }
 
+/// `progress` is called after every page with the totals so far. Saved `searches`
+/// run afterwards; each one's failure only affects its own results.
+///
+/// Transient failures are retried per `opts.retry`. If one of the two queries still fails,
+/// the other one's PRs are returned with the failure listed in `failed_queries`; an auth
+/// error, or both queries failing, fails the sync.
 pub async fn fetch_attention_prs(
     octo: &Octocrab,
-    cutoff_ts: i64,
-    include_team_requests: bool,
-) -> Result<Vec<Pr>> {
+    opts: &FetchOptions,
+    searches: &[SearchSpec],
+    repos: &RepoFilter,
+    watched: &[String],
+    progress: &(dyn Fn(SyncProgress) + Sync),
+) -> Result<AttentionPrs> {
+    let FetchOptions {
+        cutoff_ts,
+        utc_offset_secs,
+        include_team_requests,
+        page_size,
+        retry,
+    } = *opts;
+    let mut authored_stats = QueryStats::new("authored");
+    let started = Instant::now();
     let mut authored: Vec<PullRequestNode> = Vec::new();
-    let mut cursor: Option<String> = None;
     let mut viewer_login: Option<String> = None;
-    loop {
-        let vars = PaginationVars {
-            page_size: 50,
-            cursor: cursor.clone(),
-        };
-        let payload = GraphQlPayload {
-            query: AUTHORED_QUERY,
-            variables: vars,
-        };
-        let resp: GraphQlResponse<AuthoredData> = octo
-            .graphql(&payload)
-            .await
-            .map_err(|e| anyhow!("GitHub GraphQL authored query failed: {e:?}"))?;
-
-        if viewer_login.is_none() {
-            viewer_login = Some(resp.data.viewer.login.clone());
-        }
-
-        if let Some(nodes) = resp.data.viewer.pull_requests.nodes {
-            let mut keep = Vec::new();
-            let mut min_updated: Option<i64> = None;
-            for n in nodes {
-                if let Some(u) = parse_github_datetime_to_unix(&n.updated_at) {
-                    min_updated = Some(min_updated.map(|m| m.min(u)).unwrap_or(u));
-                    if u >= cutoff_ts {
-                        keep.push(n);
+    let authored_result: Result<(), QueryError> = async {
+        let mut cursor: Option<String> = None;
+        loop {
+            let data: AuthoredData = fetch_page(
+                octo,
+                AUTHORED_QUERY,
+                |optional_fields| PaginationVars {
+                    page_size,
+                    cursor: cursor.clone(),
+                    optional_fields,
+                },
+                retry,
+                "authored",
+                &mut authored_stats.reduced,
+            )
+            .await?;
+            authored_stats.pages += 1;
+            authored_stats.record_rate_limit(data.rate_limit.as_ref());
+
+            if viewer_login.is_none() {
+                viewer_login = Some(data.viewer.login.clone());
+            }
+
+            if let Some(nodes) = data.viewer.pull_requests.nodes {
+                authored_stats.nodes += nodes.len() as u32;
+                let mut keep = Vec::new();
+                let mut min_updated: Option<i64> = None;
+                for n in nodes.into_iter().flatten() {
+                    if let Some(u) = parse_github_datetime_to_unix(&n.updated_at) {
+                        min_updated = Some(min_updated.map(|m| m.min(u)).unwrap_or(u));
+                        if u >= cutoff_ts
+                            && repos.allows(&n.repository.owner.login, &n.repository.name)
+                        {
+                            keep.push(n);
+                        }
                     }
                 }
+                authored_stats.kept += keep.len() as u32;
+                authored.extend(keep);
+                report_progress(progress, &authored_stats, None);
+                if min_updated.is_some_and(|m| m < cutoff_ts) {
+                    break;
+                }
             }
-            authored.extend(keep);
-            if min_updated.is_some_and(|m| m < cutoff_ts) {
+            let pi = data.viewer.pull_requests.page_info;
+            if !pi.has_next_page {
+                break;
+            }
+            cursor = pi.end_cursor;
+            if cursor.is_none() {
                 break;
             }
         }
-        let pi = resp.data.viewer.pull_requests.page_info;
-        if !pi.has_next_page {
-            break;
-        }
-        cursor = pi.end_cursor;
-        if cursor.is_none() {
-            break;
-        }
+        Ok(())
     }
+    .await;
+    authored_stats.elapsed_ms = started.elapsed().as_millis() as u64;
 
-    let viewer_login = viewer_login.unwrap_or_else(|| "unknown".to_string());
+    let mut failed_queries = Vec::new();
+    if let Err(e) = authored_result {
+        if e.kind == FailureKind::Auth {
+            return Err(e.into());
+        }
+        failed_queries.push(e.to_string());
+    }
 
-    let cutoff_date = unix_to_ymd(cutoff_ts)
-        .map(|(y, m, d)| format!("{y:04}-{m:02}-{d:02}"))
-        .unwrap_or_else(|| "1970-01-01".to_string());
-    let search_query = format!(
+    let mut search_query = format!(
         "is:pr is:open review-requested:@me sort:updated-desc updated:>={}",
-        cutoff_date
+        search_timestamp(cutoff_ts, utc_offset_secs)
     );
+    // Narrowing the search is only an optimisation (`allows` filters the results anyway),
+    // so skip it rather than exceed GitHub's limit on query length.
+    let qualifiers = repos.search_qualifiers();
+    if !qualifiers.is_empty() && search_query.len() + 1 + qualifiers.len() <= SEARCH_QUERY_MAX_LEN {
+        search_query = format!("{search_query} {qualifiers}");
+    }
 
+    let mut requested_stats = QueryStats::new("review_requested");
+    let started = Instant::now();
     let mut requested_nodes: Vec<PullRequestNode> = Vec::new();
-    let mut cursor: Option<String> = None;
-    loop {
-        #[derive(Debug, serde::Serialize)]
-        struct SearchVars {
-            page_size: i32,
-            cursor: Option<String>,
-            search_query: String,
-        }
+    let requested_result: Result<(), QueryError> = async {
+        let mut cursor: Option<String> = None;
+        loop {
+            #[derive(Debug, serde::Serialize)]
+            struct SearchVars<'a> {
+                page_size: i32,
+                cursor: Option<String>,
+                search_query: &'a str,
+                optional_fields: bool,
+            }
 
-        let vars = SearchVars {
-            page_size: 50,
-            cursor: cursor.clone(),
-            search_query: search_query.clone(),
-        };
-        let payload = GraphQlPayload {
-            query: REVIEW_REQUESTED_QUERY,
-            variables: vars,
-        };
-        let resp: GraphQlResponse<SearchData> = octo
-            .graphql(&payload)
-            .await
-            .map_err(|e| anyhow!("GitHub GraphQL review-requested query failed: {e:?}"))?;
-
-        if let Some(nodes) = resp.data.search.nodes {
-            let mut min_updated: Option<i64> = None;
-            for n in nodes {
-                if let Some(pr) = n.into_pull_request() {
-                    if let Some(u) = parse_github_datetime_to_unix(&pr.updated_at) {
-                        min_updated = Some(min_updated.map(|m| m.min(u)).unwrap_or(u));
-                        if u < cutoff_ts {
-                            continue;
+            let data: SearchData = fetch_page(
+                octo,
+                REVIEW_REQUESTED_QUERY,
+                |optional_fields| SearchVars {
+                    page_size,
+                    cursor: cursor.clone(),
+                    search_query: &search_query,
+                    optional_fields,
+                },
+                retry,
+                "review-requested",
+                &mut requested_stats.reduced,
+            )
+            .await?;
+            requested_stats.pages += 1;
+            requested_stats.record_rate_limit(data.rate_limit.as_ref());
+            // The authored query normally supplies the login; it may have failed.
+            if viewer_login.is_none() {
+                viewer_login = data.viewer.map(|v| v.login);
+            }
+            let login = viewer_login.as_deref().unwrap_or_default();
+
+            if let Some(nodes) = data.search.nodes {
+                requested_stats.nodes += nodes.len() as u32;
+                let mut min_updated: Option<i64> = None;
+                for n in nodes.into_iter().flatten() {
+                    if let Some(pr) = n.into_pull_request() {
+                        if let Some(u) = parse_github_datetime_to_unix(&pr.updated_at) {
+                            min_updated = Some(min_updated.map(|m| m.min(u)).unwrap_or(u));
+                            if u < cutoff_ts {
+                                continue;
+                            }
+                        }
+                        if (include_team_requests || is_review_requested_by_user(&pr, login))
+                            && repos.allows(&pr.repository.owner.login, &pr.repository.name)
+                        {
+                            requested_stats.kept += 1;
+                            requested_nodes.push(pr);
                         }
                     }
-                    if include_team_requests || is_review_requested_by_user(&pr, &viewer_login) {
-                        requested_nodes.push(pr);
-                    }
                 }
+                report_progress(progress, &authored_stats, Some(&requested_stats));
+                if min_updated.is_some_and(|m| m < cutoff_ts) {
+                    break;
+                }
+            }
+            let pi = data.search.page_info;
+            if !pi.has_next_page {
+                break;
             }
-            if min_updated.is_some_and(|m| m < cutoff_ts) {
+            cursor = pi.end_cursor;
+            if cursor.is_none() {
                 break;
             }
         }
-        let pi = resp.data.search.page_info;
-        if !pi.has_next_page {
-            break;
+        Ok(())
+    }
+    .await;
+    requested_stats.elapsed_ms = started.elapsed().as_millis() as u64;
+
+    if let Err(e) = requested_result {
+        failed_queries.push(e.to_string());
+        // Nothing came back at all, or the token is bad: fail instead of a partial result.
+        if e.kind == FailureKind::Auth || failed_queries.len() == 2 {
+            return Err(QueryError {
+                kind: e.kind,
+                message: failed_queries.join("; "),
+            }
+            .into());
         }
-        cursor = pi.end_cursor;
-        if cursor.is_none() {
-            break;
+    }
+
+    let mut stats = vec![authored_stats, requested_stats];
+    let mut search_results = Vec::new();
+    for spec in searches {
+        let done = stats.iter().fold(SyncProgress::default(), |acc, st| {
+            acc.plus(SyncProgress {
+                pages: st.pages,
+                prs: st.kept,
+            })
+        });
+        let mut st = QueryStats::new(format!("search #{}", spec.tag));
+        let hits = fetch_saved_search(octo, spec, page_size, retry, &mut st, &|st| {
+            progress(done.plus(SyncProgress {
+                pages: st.pages,
+                prs: st.kept,
+            }))
+        })
+        .await
+        .map_err(|e| format!("#{}: {e}", spec.tag));
+        stats.push(st);
+        search_results.push(SearchResults {
+            tag: spec.tag.clone(),
+            hits,
+        });
+    }
+
+    // Watched PRs come in whatever their repository, age or review state.
+    let mut watched_prs = Vec::new();
+    if !watched.is_empty() {
+        let mut st = QueryStats::new("watched");
+        let started = Instant::now();
+        match fetch_watched(octo, watched, retry, &mut st).await {
+            Ok(found) => watched_prs = found,
+            Err(e) => failed_queries.push(e.to_string()),
         }
+        st.elapsed_ms = started.elapsed().as_millis() as u64;
+        stats.push(st);
     }
 
+    let viewer_login = viewer_login.unwrap_or_else(|| "unknown".to_string());
     let mut by_key: HashMap<String, Pr> = HashMap::new();
 
     for node in authored {
@@ -756,32 +1472,458 @@ pub async fn fetch_attention_prs(
         }
     }
 
-    Ok(by_key.into_values().collect())
+    let mut finished_watches = Vec::new();
+    for (key, watched) in watched_prs {
+        if watched.state.as_deref() != Some("OPEN") {
+            finished_watches.push(key);
+            continue;
+        }
+        let requested = by_key
+            .get(&key)
+            .is_some_and(|pr| matches!(pr.review_state, ReviewState::Requested));
+        if let Some(mut pr) = to_pr(watched.node, requested, &viewer_login) {
+            pr.watched = true;
+            merge_into(&mut by_key, pr);
+        }
+    }
+
+    Ok(AttentionPrs {
+        viewer_login,
+        prs: by_key.into_values().collect(),
+        stats,
+        incremental: false,
+        failed_queries,
+        searches: search_results,
+        finished_watches,
+    })
+}
+
+/// Every item `spec` matches (GitHub caps a search at 1000 results).
+async fn fetch_saved_search(
+    octo: &Octocrab,
+    spec: &SearchSpec,
+    page_size: i32,
+    retry: RetryPolicy,
+    stats: &mut QueryStats,
+    progress: &(dyn Fn(&QueryStats) + Sync),
+) -> Result<Vec<SearchHit>, QueryError> {
+    #[derive(Debug, serde::Serialize)]
+    struct SearchVars<'a> {
+        page_size: i32,
+        cursor: Option<String>,
+        search_query: &'a str,
+    }
+
+    let started = Instant::now();
+    let mut hits = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let payload = GraphQlPayload {
+            query: SAVED_SEARCH_QUERY,
+            variables: SearchVars {
+                page_size,
+                cursor: cursor.clone(),
+                search_query: &spec.query,
+            },
+        };
+        let resp: GraphQlResponse<serde_json::Value> =
+            retry::graphql(octo, &payload, retry, "saved search").await?;
+        let data = resp
+            .decode::<SavedSearchData>("saved search")?
+            .into_data("saved search")?;
+        stats.pages += 1;
+        stats.record_rate_limit(data.rate_limit.as_ref());
+        let nodes = data.search.nodes.unwrap_or_default();
+        stats.nodes += nodes.len() as u32;
+        hits.extend(nodes.into_iter().flatten().filter_map(|n| n.into_hit()));
+        stats.kept = hits.len() as u32;
+        progress(stats);
+        let pi = data.search.page_info;
+        cursor = pi.end_cursor.filter(|_| pi.has_next_page);
+        if cursor.is_none() {
+            break;
+        }
+    }
+    stats.elapsed_ms = started.elapsed().as_millis() as u64;
+    Ok(hits)
+}
+
+fn report_progress(
+    progress: &(dyn Fn(SyncProgress) + Sync),
+    authored: &QueryStats,
+    requested: Option<&QueryStats>,
+) {
+    let count = |st: &QueryStats| SyncProgress {
+        pages: st.pages,
+        prs: st.kept,
+    };
+    let total = requested.map_or(count(authored), |r| count(authored).plus(count(r)));
+    progress(total);
+}
+
+/// Client for the attention queries, which are GraphQL only; Enterprise serves GraphQL
+/// outside `/api/v3`.
+pub fn graphql_client(token: String, api_base: Option<String>) -> Result<Octocrab> {
+    client(
+        token,
+        api_base.map(|url| endpoint::graphql_base(&url).to_string()),
+    )
+}
+
+/// A watched PR looked up by number; `state` is `OPEN`, `MERGED` or `CLOSED`.
+#[derive(Debug, serde::Deserialize)]
+struct WatchedPr {
+    state: Option<String>,
+    #[serde(flatten)]
+    node: PullRequestNode,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct WatchedRepository {
+    #[serde(rename = "pullRequest")]
+    pull_request: Option<WatchedPr>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct WatchedData {
+    #[serde(rename = "rateLimit")]
+    rate_limit: Option<RateLimit>,
+    /// `w0`, `w1`, ...: one alias per watched PR, `null` when it does not exist.
+    #[serde(flatten)]
+    repositories: HashMap<String, Option<WatchedRepository>>,
+}
+
+/// `owner`, `repo` and number of an `owner/repo#number` key.
+fn split_pr_key(key: &str) -> Option<(&str, &str, i64)> {
+    let (repo, number) = key.split_once('#')?;
+    let (owner, name) = repo.split_once('/')?;
+    Some((owner, name, number.parse().ok()?))
+}
+
+/// One aliased `repository { pullRequest }` lookup per watched PR, in a single request.
+fn watched_query(count: usize) -> String {
+    let params: String = (0..count)
+        .map(|i| format!(", $o{i}: String!, $r{i}: String!, $n{i}: Int!"))
+        .collect();
+    let lookups: String = (0..count)
+        .map(|i| {
+            format!(
+                "  w{i}: repository(owner: $o{i}, name: $r{i}) {{\n    pullRequest(number: $n{i}) {{\n      state\n      ...PrFields\n    }}\n  }}\n"
+            )
+        })
+        .collect();
+    format!(
+        "query ($optional_fields: Boolean!{params}) {{\n{lookups}  rateLimit {{\n    cost\n    remaining\n  }}\n}}\n{}",
+        pr_fields_fragment!()
+    )
+}
+
+/// The PRs in `watched` (`owner/repo#number`) that exist, with their key; malformed
+/// keys and missing PRs are skipped.
+async fn fetch_watched(
+    octo: &Octocrab,
+    watched: &[String],
+    retry: RetryPolicy,
+    stats: &mut QueryStats,
+) -> Result<Vec<(String, WatchedPr)>, QueryError> {
+    let keys: Vec<(&String, (&str, &str, i64))> = watched
+        .iter()
+        .filter_map(|key| Some((key, split_pr_key(key)?)))
+        .collect();
+    let query = watched_query(keys.len());
+    let data: WatchedData = fetch_page(
+        octo,
+        &query,
+        |optional_fields| {
+            let mut vars = serde_json::json!({ "optional_fields": optional_fields });
+            for (i, (_, (owner, name, number))) in keys.iter().enumerate() {
+                vars[format!("o{i}")] = (*owner).into();
+                vars[format!("r{i}")] = (*name).into();
+                vars[format!("n{i}")] = (*number).into();
+            }
+            vars
+        },
+        retry,
+        "watched",
+        &mut stats.reduced,
+    )
+    .await?;
+    stats.pages += 1;
+    stats.record_rate_limit(data.rate_limit.as_ref());
+    let mut repositories = data.repositories;
+    let found: Vec<(String, WatchedPr)> = keys
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (key, _))| {
+            let pr = repositories.remove(&format!("w{i}"))??.pull_request?;
+            Some(((*key).clone(), pr))
+        })
+        .collect();
+    stats.nodes += found.len() as u32;
+    Ok(found)
+}
+
+/// Submit a review on `owner/repo#number`. GitHub requires a body for everything but approvals.
+pub async fn submit_review(octo: &Octocrab, pr: &Pr, event: ReviewEvent, body: &str) -> Result<()> {
+    let route = format!(
+        "/repos/{}/{}/pulls/{}/reviews",
+        pr.owner, pr.repo, pr.number
+    );
+    let mut payload = serde_json::json!({ "event": event.api_name() });
+    if !body.trim().is_empty() {
+        payload["body"] = serde_json::Value::String(body.to_string());
+    }
+    let _: serde_json::Value = octo
+        .post(route, Some(&payload))
+        .await
+        .map_err(|e| anyhow!("failed to submit review: {e}"))?;
+    Ok(())
+}
+
+/// Merge the base branch into the PR branch (GitHub's "Update branch" button).
+/// Fails if the head moved since the last sync, so we never update a branch we have not seen.
+pub async fn update_branch(octo: &Octocrab, pr: &Pr) -> Result<()> {
+    let route = format!(
+        "/repos/{}/{}/pulls/{}/update-branch",
+        pr.owner, pr.repo, pr.number
+    );
+    let mut payload = serde_json::json!({});
+    if let Some(sha) = &pr.last_commit_sha {
+        payload["expected_head_sha"] = serde_json::Value::String(sha.clone());
+    }
+    let _: serde_json::Value = octo
+        .put(route, Some(&payload))
+        .await
+        .map_err(|e| anyhow!("failed to update branch: {e}"))?;
+    Ok(())
 }
 
-/// Synchronous facade that owns its own Tokio runtime.
-pub fn fetch_attention_prs_sync(
-    token: &str,
-    api_base: Option<String>,
-    cutoff_ts: i64,
-    include_team_requests: bool,
-) -> Result<Vec<Pr>> {
-    let token = token.to_owned();
-    let rt = tokio::runtime::Builder::new_multi_thread()
-        .enable_all()
+/// REST / GraphQL client for `api_base` (api.github.com when `None`).
+pub fn client(token: String, api_base: Option<String>) -> Result<Octocrab> {
+    let mut builder = Octocrab::builder().personal_token(token);
+    if let Some(api) = api_base {
+        builder = builder
+            .base_uri(api)
+            .map_err(|e| anyhow!("invalid GITHUB_API_URL: {e}"))?;
+    }
+    builder
         .build()
-        .map_err(|e| anyhow!("failed to build tokio runtime: {e}"))?;
-
-    rt.block_on(async move {
-        let mut builder = Octocrab::builder().personal_token(token);
-        if let Some(api) = api_base {
-            builder = builder
-                .base_uri(api)
-                .map_err(|e| anyhow!("invalid GITHUB_API_URL: {e}"))?;
-        }
-        let octo = builder
-            .build()
-            .map_err(|e| anyhow!("failed to init GitHub client: {e}"))?;
-        fetch_attention_prs(&octo, cutoff_ts, include_team_requests).await
-    })
+        .map_err(|e| anyhow!("failed to init GitHub client: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    //! Recorded GraphQL responses in `testdata/*.json`, mapped to `Pr`s and compared with
+    //! `testdata/*.golden.json`. Regenerate the golden files after an intended mapping
+    //! change with `UPDATE_GOLDEN=1 cargo test`, then review the diff.
+    use super::*;
+    use std::path::PathBuf;
+
+    const VIEWER: &str = "octocat";
+
+    fn testdata(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/repo/github/testdata")
+            .join(name)
+    }
+
+    fn load<T: serde::de::DeserializeOwned>(fixture: &str) -> T {
+        let raw = std::fs::read_to_string(testdata(&format!("{fixture}.json"))).unwrap();
+        serde_json::from_str::<GraphQlResponse<T>>(&raw)
+            .unwrap_or_else(|e| panic!("{fixture}: {e}"))
+            .data
+            .unwrap()
+    }
+
+    /// PR nodes of a recorded response, as `fetch_attention_prs` would collect them.
+    fn nodes(fixture: &str) -> Vec<PullRequestNode> {
+        if fixture.starts_with("authored") {
+            let data: AuthoredData = load(fixture);
+            data.viewer
+                .pull_requests
+                .nodes
+                .unwrap_or_default()
+                .into_iter()
+                .flatten()
+                .collect()
+        } else {
+            let data: SearchData = load(fixture);
+            data.search
+                .nodes
+                .unwrap_or_default()
+                .into_iter()
+                .flatten()
+                .filter_map(SearchNode::into_pull_request)
+                .collect()
+        }
+    }
+
+    fn map(fixture: &str) -> Vec<Pr> {
+        let authored = fixture.starts_with("authored");
+        let mut prs: Vec<Pr> = nodes(fixture)
+            .into_iter()
+            .filter_map(|node| {
+                let requested = !authored || is_review_requested_by_user(&node, VIEWER);
+                to_pr(node, requested, VIEWER)
+            })
+            .collect();
+        prs.sort_by(|a, b| a.pr_key.cmp(&b.pr_key));
+        prs
+    }
+
+    fn assert_golden(fixture: &str) {
+        let actual = serde_json::to_string_pretty(&map(fixture)).unwrap() + "\n";
+        let path = testdata(&format!("{fixture}.golden.json"));
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, &actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("{}: {e} (run with UPDATE_GOLDEN=1)", path.display()));
+        assert!(
+            actual == expected,
+            "{fixture} no longer maps to {}:\n{actual}",
+            path.display()
+        );
+    }
+
+    #[test]
+    fn recorded_responses_match_golden_files() {
+        for fixture in [
+            "authored_check_runs",
+            "search_status_contexts",
+            "search_missing_rollup",
+            "search_team_reviewers",
+            "search_saml_error",
+        ] {
+            assert_golden(fixture);
+        }
+    }
+
+    #[test]
+    fn check_runs_feed_ci_state_and_required_checks() {
+        let node = nodes("authored_check_runs").remove(0);
+        let checks = map_ci_checks(&node);
+        let states: Vec<_> = checks
+            .iter()
+            .map(|c| (c.name.as_str(), format!("{:?}", c.state)))
+            .collect();
+        assert_eq!(
+            states,
+            [
+                ("build", "Success".to_string()),
+                ("test", "Failure".to_string()),
+                ("deploy-preview", "Running".to_string()),
+                ("lint", "Neutral".to_string()),
+            ]
+        );
+        assert!(matches!(
+            derive_ci_state(rollup_state(&node), &checks),
+            CiState::Running
+        ));
+
+        let blockers = compute_merge_blockers(&node, &checks);
+        assert_eq!(blockers.failing_required_checks, ["test"]);
+        assert_eq!(
+            (blockers.required_approvals, blockers.current_approvals),
+            (Some(2), 1)
+        );
+        assert!(!blockers.is_clear());
+    }
+
+    #[test]
+    fn missing_rollup_and_protection_mean_no_blockers() {
+        for node in nodes("search_missing_rollup") {
+            let checks = map_ci_checks(&node);
+            assert!(checks.is_empty());
+            assert!(matches!(
+                derive_ci_state(rollup_state(&node), &checks),
+                CiState::None
+            ));
+            assert!(compute_merge_blockers(&node, &checks).is_clear());
+        }
+    }
+
+    #[test]
+    fn repo_filter_matches_patterns_and_narrows_the_search() {
+        let filter = RepoFilter {
+            include: vec!["acme/*".to_string()],
+            exclude: vec!["acme/website".to_string(), "*/sandbox-*".to_string()],
+        };
+        assert!(filter.allows("acme", "api"));
+        assert!(filter.allows("ACME", "Api"));
+        assert!(!filter.allows("acme", "website"));
+        assert!(!filter.allows("acme", "sandbox-1"));
+        assert!(!filter.allows("other", "api"));
+        assert!(RepoFilter::default().allows("other", "api"));
+        // Wildcard includes cannot be expressed as `repo:` qualifiers.
+        assert_eq!(filter.search_qualifiers(), "-repo:acme/website");
+
+        let exact = RepoFilter {
+            include: vec!["acme/api".to_string(), "acme/cli".to_string()],
+            exclude: Vec::new(),
+        };
+        assert_eq!(exact.search_qualifiers(), "repo:acme/api repo:acme/cli");
+        assert!(!exact.allows("acme", "api-docs"));
+    }
+
+    fn response(fixture: &str) -> GraphQlResponse<SearchData> {
+        let raw = std::fs::read_to_string(testdata(&format!("{fixture}.json"))).unwrap();
+        serde_json::from_str(&raw).unwrap()
+    }
+
+    #[test]
+    fn only_forbidden_optional_fields_trigger_the_reduced_query() {
+        assert!(response("search_forbidden_protection").optional_fields_failed());
+        // A SAML-hidden node is not about optional fields; the reduced query would not help.
+        assert!(!response("search_saml_error").optional_fields_failed());
+        assert!(!response("search_team_reviewers").optional_fields_failed());
+
+        let halted: GraphQlResponse<SearchData> = serde_json::from_str(
+            r#"{"data": null, "errors": [{"message": "Something went wrong", "path": ["search"]}]}"#,
+        )
+        .unwrap();
+        let err = halted.into_data("review-requested").unwrap_err();
+        assert_eq!(err.kind, FailureKind::Other);
+        assert!(err.message.ends_with("Something went wrong"), "{err}");
+    }
+
+    #[test]
+    fn graphql_errors_are_classified_instead_of_failing_to_decode() {
+        let kind = |raw: serde_json::Value| {
+            serde_json::from_value::<GraphQlResponse<serde_json::Value>>(raw)
+                .unwrap()
+                .decode::<AuthoredData>("authored")
+                .and_then(|resp| resp.into_data("authored"))
+                .map(|_| ())
+                .unwrap_err()
+        };
+        // An error nulls `viewer`, which `AuthoredData` requires.
+        let err = kind(serde_json::json!({
+            "data": { "viewer": null, "rateLimit": null },
+            "errors": [{ "type": "RATE_LIMITED", "message": "API rate limit exceeded" }]
+        }));
+        assert_eq!(err.kind, FailureKind::RateLimited);
+        assert!(err.message.ends_with("API rate limit exceeded"), "{err}");
+
+        let err = kind(serde_json::json!({
+            "errors": [
+                { "message": "Field 'mergeQueue' doesn't exist on type 'Repository'",
+                  "extensions": { "code": "undefinedField" } },
+                { "type": "FORBIDDEN", "message": "Resource not accessible by integration" }
+            ]
+        }));
+        assert_eq!(err.kind, FailureKind::Auth);
+
+        let err = kind(serde_json::json!({
+            "data": null,
+            "errors": [{ "message": "Parse error on \"}\" (RCURLY) at [3, 1]" }]
+        }));
+        assert_eq!(err.kind, FailureKind::Query);
+
+        // Without GraphQL errors a shape mismatch is reported as such.
+        let err = kind(serde_json::json!({ "data": { "viewer": 1 } }));
+        assert_eq!(err.kind, FailureKind::Other);
+        assert!(err.message.contains("unexpected data"), "{err}");
+    }
 }
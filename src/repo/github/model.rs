@@ -1,4 +1,10 @@
-#[derive(Debug, Clone)]
+use time::Date;
+use time::macros::format_description;
+
+use crate::domain::due::Due;
+use crate::domain::glob;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum CiState {
     Success,
     Failure,
@@ -6,10 +12,11 @@ pub enum CiState {
     None,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum ReviewState {
     Requested,
     Approved,
+    ChangesRequested,
     None,
 }
 
@@ -60,6 +67,73 @@ impl MergeBlockers {
     }
 }
 
+/// Size of a PR's diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DiffStats {
+    pub additions: u32,
+    pub deletions: u32,
+    pub changed_files: u32,
+}
+
+impl DiffStats {
+    /// T-shirt size by changed lines, with the thresholds of the common size labelers.
+    pub fn size(&self) -> &'static str {
+        match self.additions + self.deletions {
+            0..10 => "XS",
+            10..30 => "S",
+            30..100 => "M",
+            100..500 => "L",
+            _ => "XL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PrLabel {
+    pub name: String,
+    /// Hex RGB without `#`, as GitHub reports it (e.g. `d73a4a`).
+    #[serde(default)]
+    pub color: String,
+}
+
+impl PrLabel {
+    pub fn rgb(&self) -> Option<(u8, u8, u8)> {
+        let hex = self.color.trim_start_matches('#');
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        Some((channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Milestone {
+    pub title: String,
+    /// `YYYY-MM-DD`.
+    pub due_on: Option<String>,
+}
+
+impl Milestone {
+    /// The due day, without a time.
+    pub fn due(&self) -> Option<Due> {
+        let date = Date::parse(
+            self.due_on.as_deref()?,
+            format_description!("[year]-[month]-[day]"),
+        )
+        .ok()?;
+        Some(Due::day(date))
+    }
+}
+
+/// A reviewer's latest review of a PR.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PrReview {
+    pub author: String,
+    /// GraphQL `PullRequestReviewState`: `APPROVED`, `CHANGES_REQUESTED`, `COMMENTED`, ...
+    pub state: String,
+}
+
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct StatusContextNode {
     #[serde(rename = "__typename")]
@@ -79,7 +153,7 @@ pub struct StatusContextNode {
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Pr {
     pub pr_key: String, // "{owner}/{repo}#{number}"
     pub owner: String,
@@ -89,6 +163,8 @@ pub struct Pr {
     pub title: String,
     pub url: String,
 
+    #[serde(default)]
+    pub created_at_unix: i64,
     pub updated_at_unix: i64,
     pub last_commit_sha: Option<String>,
     pub ci_state: CiState,
@@ -100,5 +176,263 @@ pub struct Pr {
     pub mergeable: Option<String>, // e.g. "MERGEABLE" | "CONFLICTING" | "UNKNOWN"
     pub merge_state_status: Option<String>, // e.g. "CLEAN" | "BLOCKED" | ...
     pub is_viewer_author: bool,    // true when this PR is authored by the signed-in user
+    #[serde(default)]
+    pub viewer_approved: bool, // the signed-in user's latest review is an approval
+    #[serde(default)]
+    pub requested_directly: bool, // the viewer (not only one of their teams) is a requested reviewer
+    #[serde(default)]
+    pub requested_teams: Vec<String>, // "org/team" slugs with a pending review request
+    #[serde(default)]
+    pub requested_users: Vec<String>, // logins with a pending review request, the viewer included
+    #[serde(default)]
+    pub reviews: Vec<PrReview>, // latest review of each reviewer
+    #[serde(default)]
+    pub assignees: Vec<String>,
+    #[serde(default)]
+    pub labels: Vec<PrLabel>,
+    #[serde(default)]
+    pub milestone: Option<Milestone>,
+    #[serde(default)]
+    pub diff_stats: Option<DiffStats>,
+    #[serde(default)]
+    pub comments: u32, // conversation comments, not counting review comments
+    #[serde(default)]
+    pub review_threads: u32, // review comment threads on the diff
+    #[serde(default)]
+    pub unresolved_threads: u32,
+    #[serde(default)]
+    pub watched: bool, // pinned with `koto watch` / `W`, synced whether or not I'm a reviewer
+    #[serde(default)]
+    pub author_is_bot: bool, // opened by a GitHub App / bot account (Dependabot, Renovate, ...)
     pub merge_blockers: Option<MergeBlockers>,
 }
+
+impl Pr {
+    /// Review was requested only through a team the viewer belongs to.
+    pub fn is_team_request(&self) -> bool {
+        !self.requested_directly && !self.requested_teams.is_empty()
+    }
+
+    /// The repository page, on the same host as the PR.
+    pub fn repo_url(&self) -> String {
+        match self.url.rsplit_once("/pull/") {
+            Some((repo, _)) => repo.to_string(),
+            None => format!("https://github.com/{}/{}", self.owner, self.repo),
+        }
+    }
+}
+
+/// Result of one attention sync: the PRs plus the account the token belongs to.
+#[derive(Debug, Clone)]
+pub struct AttentionPrs {
+    pub viewer_login: String,
+    pub prs: Vec<Pr>,
+    /// One entry per GraphQL query, for `koto sync --explain`.
+    pub stats: Vec<QueryStats>,
+    /// Only PRs updated since the last sync were fetched, so `prs` is a delta.
+    pub incremental: bool,
+    /// Queries that failed after retries; `prs` lacks their results.
+    pub failed_queries: Vec<String>,
+    /// Results of the saved searches, in configuration order.
+    pub searches: Vec<SearchResults>,
+    /// Watched PRs (`koto watch`) that were merged or closed.
+    pub finished_watches: Vec<String>,
+}
+
+/// A saved search to run alongside the attention queries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchSpec {
+    pub tag: String,
+    /// GitHub search syntax.
+    pub query: String,
+}
+
+/// `[github] include_repos` / `exclude_repos`: which repositories the attention queries
+/// keep. Patterns are `owner/repo` globs (see `glob::matches`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl RepoFilter {
+    pub fn allows(&self, owner: &str, repo: &str) -> bool {
+        let name = format!("{owner}/{repo}");
+        let matches = |pattern: &String| glob::matches(pattern, &name);
+        (self.include.is_empty() || self.include.iter().any(matches))
+            && !self.exclude.iter().any(matches)
+    }
+
+    /// Search qualifiers narrowing a query the same way, as far as search syntax can:
+    /// `repo:` when every include pattern is an exact name, `-repo:` per exact exclude.
+    /// Wildcards are left to `allows` on the results.
+    pub fn search_qualifiers(&self) -> String {
+        let exact = |p: &&String| !p.contains('*');
+        let mut qualifiers = Vec::new();
+        if self.include.iter().all(|p| exact(&p)) {
+            qualifiers.extend(self.include.iter().map(|p| format!("repo:{p}")));
+        }
+        qualifiers.extend(
+            self.exclude
+                .iter()
+                .filter(exact)
+                .map(|p| format!("-repo:{p}")),
+        );
+        qualifiers.join(" ")
+    }
+}
+
+/// What one saved search returned.
+#[derive(Debug, Clone)]
+pub struct SearchResults {
+    pub tag: String,
+    /// Err when the query failed; todos of this search are then left alone.
+    pub hits: Result<Vec<SearchHit>, String>,
+}
+
+/// An issue or PR matched by a saved search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    /// `owner/repo#number`.
+    pub key: String,
+    pub title: String,
+    pub url: String,
+    pub milestone: Option<Milestone>,
+}
+
+impl AttentionPrs {
+    /// Prefix PR keys with `host/` so PRs of an additional account never collide
+    /// with same-named repositories of the main one.
+    pub fn qualify(&mut self, host: &str) {
+        self.viewer_login = format!("{}@{host}", self.viewer_login);
+        for pr in &mut self.prs {
+            pr.pr_key = format!("{host}/{}", pr.pr_key);
+        }
+        for st in &mut self.stats {
+            st.query = format!("{host} {}", st.query);
+        }
+        for failure in &mut self.failed_queries {
+            *failure = format!("{host}: {failure}");
+        }
+        for key in &mut self.finished_watches {
+            *key = format!("{host}/{key}");
+        }
+        for search in &mut self.searches {
+            if let Ok(hits) = &mut search.hits {
+                for hit in hits {
+                    hit.key = format!("{host}/{}", hit.key);
+                }
+            }
+        }
+    }
+
+    /// Some PRs may be missing because a query failed; nothing should be pruned based on this.
+    pub fn is_partial(&self) -> bool {
+        !self.failed_queries.is_empty()
+    }
+
+    /// Fold the result of another account into this one.
+    pub fn merge(&mut self, other: AttentionPrs) {
+        if self.viewer_login.is_empty() {
+            self.viewer_login = other.viewer_login;
+        } else {
+            self.viewer_login = format!("{}, {}", self.viewer_login, other.viewer_login);
+        }
+        self.prs.extend(other.prs);
+        self.stats.extend(other.stats);
+        self.failed_queries.extend(other.failed_queries);
+        self.searches.extend(other.searches);
+        self.finished_watches.extend(other.finished_watches);
+    }
+}
+
+/// Running totals reported while a sync pages through GitHub.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncProgress {
+    pub pages: u32,
+    /// PRs kept so far (an authored PR that also requests me counts twice until merged).
+    pub prs: u32,
+}
+
+impl SyncProgress {
+    pub fn plus(self, other: SyncProgress) -> SyncProgress {
+        SyncProgress {
+            pages: self.pages + other.pages,
+            prs: self.prs + other.prs,
+        }
+    }
+}
+
+/// Knobs for one attention sync.
+#[derive(Debug, Clone, Copy)]
+pub struct FetchOptions {
+    /// PRs not updated since this unix time are skipped (and end pagination).
+    pub cutoff_ts: i64,
+    /// Local UTC offset in seconds; the cutoff is written in it in search qualifiers.
+    pub utc_offset_secs: i32,
+    pub include_team_requests: bool,
+    /// Nodes per GraphQL page (GitHub allows 1..=100).
+    pub page_size: i32,
+    pub retry: RetryPolicy,
+}
+
+/// Retries of a GraphQL request that failed with a network error, 5xx or rate limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total tries per request, including the first (at least 1).
+    pub attempts: u32,
+    /// Delay before the first retry; doubled for each further one (with jitter).
+    pub base_delay_ms: u64,
+}
+
+/// Cost of one GraphQL query during a sync, summed over its pages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryStats {
+    pub query: String,
+    pub pages: u32,
+    /// Nodes GitHub returned.
+    pub nodes: u32,
+    /// Nodes that passed the days window and filters.
+    pub kept: u32,
+    pub elapsed_ms: u64,
+    /// GraphQL rate-limit points spent.
+    pub cost: u32,
+    pub rate_remaining: Option<u32>,
+    /// Fell back to the query without branch protection / team reviewers (forbidden).
+    pub reduced: bool,
+}
+
+/// Verdict of a submitted PR review.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ReviewEvent {
+    Approve,
+    Comment,
+    RequestChanges,
+}
+
+impl ReviewEvent {
+    pub fn next(self) -> Self {
+        match self {
+            ReviewEvent::Approve => ReviewEvent::Comment,
+            ReviewEvent::Comment => ReviewEvent::RequestChanges,
+            ReviewEvent::RequestChanges => ReviewEvent::Approve,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ReviewEvent::Approve => "Approve",
+            ReviewEvent::Comment => "Comment",
+            ReviewEvent::RequestChanges => "Request changes",
+        }
+    }
+
+    /// Value of the REST API's `event` field.
+    pub fn api_name(self) -> &'static str {
+        match self {
+            ReviewEvent::Approve => "APPROVE",
+            ReviewEvent::Comment => "COMMENT",
+            ReviewEvent::RequestChanges => "REQUEST_CHANGES",
+        }
+    }
+}
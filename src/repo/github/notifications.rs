@@ -0,0 +1,185 @@
+//! The notifications inbox (REST `/notifications`): mentions, review requests, CI
+//! results and the rest of what github.com/notifications shows.
+
+use anyhow::{Result, anyhow};
+use octocrab::Octocrab;
+use serde::{Deserialize, Serialize};
+
+use super::timeutil::parse_github_datetime_to_unix;
+
+/// Notifications fetched per request; GitHub allows up to 50.
+const PAGE_SIZE: u8 = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    /// Thread id, for marking it read.
+    pub thread_id: String,
+    /// Why it was sent: `mention`, `review_requested`, `ci_activity`, `author`, ...
+    pub reason: String,
+    /// Subject type: `PullRequest`, `Issue`, `CheckSuite`, `Release`, ...
+    pub kind: String,
+    pub title: String,
+    /// `owner/repo`.
+    pub repo: String,
+    /// Browser link to the subject (the repository when there is no better one).
+    pub url: String,
+    pub updated_at_unix: i64,
+    pub unread: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ListParams {
+    per_page: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawNotification {
+    id: String,
+    reason: String,
+    unread: bool,
+    updated_at: String,
+    subject: RawSubject,
+    repository: RawRepository,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawSubject {
+    title: String,
+    /// API URL of the subject, e.g. `https://api.github.com/repos/o/r/pulls/1`.
+    url: Option<String>,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawRepository {
+    full_name: String,
+    html_url: String,
+}
+
+impl RawNotification {
+    fn into_notification(self) -> Notification {
+        Notification {
+            url: html_url(
+                self.subject.url.as_deref(),
+                &self.repository.full_name,
+                &self.repository.html_url,
+            ),
+            thread_id: self.id,
+            reason: self.reason,
+            kind: self.subject.kind,
+            title: self.subject.title,
+            repo: self.repository.full_name,
+            updated_at_unix: parse_github_datetime_to_unix(&self.updated_at).unwrap_or(0),
+            unread: self.unread,
+        }
+    }
+}
+
+/// Browser link for a subject API URL: pulls, issues and commits map onto their pages;
+/// anything else (check suites, discussions, ...) falls back to the repository.
+fn html_url(api_url: Option<&str>, full_name: &str, repo_html: &str) -> String {
+    let rest = api_url
+        .and_then(|url| url.split_once(&format!("/repos/{full_name}/")))
+        .map(|(_, rest)| rest);
+    match rest.and_then(|rest| rest.split_once('/')) {
+        Some(("pulls", number)) => format!("{repo_html}/pull/{number}"),
+        Some(("issues", number)) => format!("{repo_html}/issues/{number}"),
+        Some(("commits", sha)) => format!("{repo_html}/commit/{sha}"),
+        Some(("releases", _)) => format!("{repo_html}/releases"),
+        _ => repo_html.to_string(),
+    }
+}
+
+/// Unread notifications, newest first.
+pub async fn fetch(octo: &Octocrab) -> Result<Vec<Notification>> {
+    let raw: Vec<RawNotification> = octo
+        .get(
+            "/notifications",
+            Some(&ListParams {
+                per_page: PAGE_SIZE,
+            }),
+        )
+        .await
+        .map_err(|e| anyhow!("failed to fetch notifications: {e}"))?;
+    Ok(raw
+        .into_iter()
+        .map(RawNotification::into_notification)
+        .collect())
+}
+
+/// Mark one notification thread as read on GitHub.
+pub async fn mark_read(octo: &Octocrab, thread_id: &str) -> Result<()> {
+    // The reply (205 Reset Content) has no body, so skip `Octocrab::patch`'s JSON parsing.
+    let resp = octo
+        ._patch(
+            format!("/notifications/threads/{thread_id}").as_str(),
+            None::<&()>,
+        )
+        .await
+        .map_err(|e| anyhow!("failed to mark notification read: {e}"))?;
+    octocrab::map_github_error(resp)
+        .await
+        .map_err(|e| anyhow!("failed to mark notification read: {e}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subjects_link_to_their_pages() {
+        let raw: Vec<RawNotification> = serde_json::from_value(serde_json::json!([
+            {
+                "id": "101",
+                "reason": "review_requested",
+                "unread": true,
+                "updated_at": "2026-10-15T10:00:00Z",
+                "subject": {
+                    "title": "Rework billing exports",
+                    "url": "https://api.github.com/repos/acme/billing/pulls/120",
+                    "type": "PullRequest"
+                },
+                "repository": {
+                    "full_name": "acme/billing",
+                    "html_url": "https://github.com/acme/billing"
+                }
+            },
+            {
+                "id": "102",
+                "reason": "ci_activity",
+                "unread": false,
+                "updated_at": "2026-10-15T11:00:00Z",
+                "subject": { "title": "CI failed", "url": null, "type": "CheckSuite" },
+                "repository": {
+                    "full_name": "acme/api",
+                    "html_url": "https://ghe.example.com/acme/api"
+                }
+            }
+        ]))
+        .unwrap();
+        let notes: Vec<Notification> = raw
+            .into_iter()
+            .map(RawNotification::into_notification)
+            .collect();
+        assert_eq!(notes[0].url, "https://github.com/acme/billing/pull/120");
+        assert_eq!(
+            (
+                notes[0].reason.as_str(),
+                notes[0].kind.as_str(),
+                notes[0].unread
+            ),
+            ("review_requested", "PullRequest", true)
+        );
+        assert_eq!(notes[1].url, "https://ghe.example.com/acme/api");
+        assert_eq!(
+            html_url(
+                Some("https://ghe.example.com/api/v3/repos/acme/api/issues/7"),
+                "acme/api",
+                "https://ghe.example.com/acme/api"
+            ),
+            "https://ghe.example.com/acme/api/issues/7"
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use octocrab::Octocrab;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use super::model::RetryPolicy;
+
+/// How a failed GraphQL request should be handled, and what to tell the user about it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// Network trouble or 5xx: worth another attempt.
+    Transient,
+    /// The API rate limit is used up: HTTP 403 / 429 (retried) or a GraphQL `RATE_LIMITED`
+    /// error (not retried, as it lasts until the hourly reset).
+    RateLimited,
+    /// Bad or expired token / missing scope / SAML: retrying or running the other query
+    /// won't help.
+    Auth,
+    /// GitHub rejected the query itself (parse or validation errors, too many nodes).
+    Query,
+    /// Anything else (e.g. a response we could not decode); not retried.
+    Other,
+}
+
+impl FailureKind {
+    /// Short description for the footer, e.g. "GitHub sync failed (rate limited)".
+    pub fn label(self) -> &'static str {
+        match self {
+            FailureKind::Transient => "network error",
+            FailureKind::RateLimited => "rate limited",
+            FailureKind::Auth => "token rejected",
+            FailureKind::Query => "query rejected",
+            FailureKind::Other => "error",
+        }
+    }
+
+    /// What the user can do about it, if anything.
+    pub fn hint(self) -> Option<&'static str> {
+        match self {
+            FailureKind::Transient => Some("GitHub or the network is having trouble; try again"),
+            FailureKind::RateLimited => Some(
+                "the API rate limit is used up; sync again after it resets, or lower [github] days / page_size",
+            ),
+            FailureKind::Auth => Some(
+                "check the token (GITHUB_TOKEN or `gh auth login`): it may be expired, lack the repo / read:org scopes or need SSO authorization",
+            ),
+            FailureKind::Query => Some(
+                "GitHub did not accept the query; an older GitHub Enterprise Server may lack a field koto asks for",
+            ),
+            FailureKind::Other => None,
+        }
+    }
+
+    /// The more serious of two kinds, for a response with several errors.
+    pub fn max(self, other: FailureKind) -> FailureKind {
+        let rank = |kind| match kind {
+            FailureKind::Auth => 4,
+            FailureKind::RateLimited => 3,
+            FailureKind::Query => 2,
+            FailureKind::Transient => 1,
+            FailureKind::Other => 0,
+        };
+        if rank(other) > rank(self) {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+/// A GraphQL request that failed for good, after any retries.
+#[derive(Debug, Clone)]
+pub struct QueryError {
+    pub kind: FailureKind,
+    pub message: String,
+}
+
+impl QueryError {
+    /// The `QueryError` behind `err`, or one of kind `Other` carrying its message.
+    pub fn from_anyhow(err: anyhow::Error) -> Self {
+        match err.downcast::<QueryError>() {
+            Ok(query) => query,
+            Err(err) => QueryError {
+                kind: FailureKind::Other,
+                message: err.to_string(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// `octo.graphql` with retries on transient failures, backing off exponentially
+/// (`base_delay_ms`, doubled per attempt, with jitter). `what` names the query in errors.
+pub async fn graphql<T: DeserializeOwned>(
+    octo: &Octocrab,
+    payload: &impl Serialize,
+    policy: RetryPolicy,
+    what: &str,
+) -> Result<T, QueryError> {
+    let attempts = policy.attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        let err = match octo.graphql(payload).await {
+            Ok(resp) => return Ok(resp),
+            Err(e) => e,
+        };
+        let kind = classify(&err);
+        let retryable = matches!(kind, FailureKind::Transient | FailureKind::RateLimited);
+        if !retryable || attempt >= attempts {
+            let tries = if attempt > 1 {
+                format!(" after {attempt} attempts")
+            } else {
+                String::new()
+            };
+            return Err(QueryError {
+                kind,
+                message: format!(
+                    "GitHub GraphQL {what} query failed{tries}: {}",
+                    describe(&err)
+                ),
+            });
+        }
+        tokio::time::sleep(backoff(policy.base_delay_ms, attempt)).await;
+        attempt += 1;
+    }
+}
+
+fn classify(err: &octocrab::Error) -> FailureKind {
+    match err {
+        octocrab::Error::GitHub { source, .. } => match source.status_code.as_u16() {
+            401 => FailureKind::Auth,
+            403 if source.message.to_lowercase().contains("rate limit") => FailureKind::RateLimited,
+            403 => FailureKind::Auth,
+            429 => FailureKind::RateLimited,
+            500..=599 => FailureKind::Transient,
+            _ => FailureKind::Other,
+        },
+        octocrab::Error::Hyper { .. }
+        | octocrab::Error::Service { .. }
+        | octocrab::Error::Http { .. } => FailureKind::Transient,
+        _ => FailureKind::Other,
+    }
+}
+
+fn describe(err: &octocrab::Error) -> String {
+    match err {
+        octocrab::Error::GitHub { source, .. } => {
+            format!("{} ({})", source.message, source.status_code)
+        }
+        other => format!("{other:?}"),
+    }
+}
+
+/// Delay before retry number `attempt` (1-based): `base * 2^(attempt-1)`, drawn from its
+/// upper half so parallel syncs do not retry in lockstep.
+fn backoff(base_delay_ms: u64, attempt: u32) -> Duration {
+    let ceiling = base_delay_ms.saturating_mul(1 << (attempt - 1).min(10));
+    let half = ceiling / 2;
+    let jitter = RandomState::new().build_hasher().finish() % (half + 1);
+    Duration::from_millis(half + jitter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_within_jitter_bounds() {
+        for attempt in 1..=4 {
+            let ceiling = 100u64 << (attempt - 1);
+            let delay = backoff(100, attempt).as_millis() as u64;
+            assert!(
+                (ceiling / 2..=ceiling).contains(&delay),
+                "{attempt}: {delay}"
+            );
+        }
+        assert_eq!(backoff(0, 3), Duration::ZERO);
+    }
+}
@@ -36,16 +36,26 @@ pub fn parse_github_datetime_to_unix(s: &str) -> Option<i64> {
     let second: u32 = main.get(17..19)?.parse().ok()?;
 
     let days = days_from_civil(year, month as i32, day as i32)?;
-    let secs = (days as i64) * 86_400 + (hour as i64) * 3600 + (minute as i64) * 60 + second as i64;
+    let secs = days * 86_400 + (hour as i64) * 3600 + (minute as i64) * 60 + second as i64;
     Some(secs)
 }
 
-pub fn unix_to_ymd(ts: i64) -> Option<(i32, u32, u32)> {
-    if ts < 0 {
-        return None;
-    }
-    let days = ts / 86_400;
-    civil_from_days(days)
+/// `ts` as an ISO 8601 timestamp in `offset_secs` (e.g. `2025-01-05T09:00:00+09:00`) for
+/// `updated:>=` search qualifiers; a bare date there would be read as a UTC day.
+pub fn search_timestamp(ts: i64, offset_secs: i32) -> String {
+    let local = ts.max(0) + offset_secs as i64;
+    let (y, m, d) = civil_from_days(local.div_euclid(86_400)).unwrap_or((1970, 1, 1));
+    let secs = local.rem_euclid(86_400);
+    let sign = if offset_secs < 0 { '-' } else { '+' };
+    let offset = offset_secs.unsigned_abs();
+    format!(
+        "{y:04}-{m:02}-{d:02}T{:02}:{:02}:{:02}{sign}{:02}:{:02}",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+        offset / 3600,
+        offset % 3600 / 60
+    )
 }
 
 fn civil_from_days(days: i64) -> Option<(i32, u32, u32)> {
@@ -80,3 +90,20 @@ fn days_from_civil(year: i32, month: i32, day: i32) -> Option<i64> {
     let days = (era as i64) * 146_097 + (doe as i64) - 719_468;
     Some(days)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_timestamp_is_written_in_the_local_offset() {
+        // 2025-01-04T20:30:00Z is already the 5th in Tokyo.
+        let ts = parse_github_datetime_to_unix("2025-01-04T20:30:00Z").unwrap();
+        assert_eq!(search_timestamp(ts, 9 * 3600), "2025-01-05T05:30:00+09:00");
+        assert_eq!(search_timestamp(ts, 0), "2025-01-04T20:30:00+00:00");
+        assert_eq!(
+            search_timestamp(ts, -(5 * 3600 + 1800)),
+            "2025-01-04T15:00:00-05:30"
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! Jira Cloud / Server issue search over the REST API (v2, available on both).
+
+use anyhow::{Result, anyhow};
+use base64::Engine;
+use octocrab::Octocrab;
+use serde::{Deserialize, Serialize};
+use time::Date;
+use time::macros::format_description;
+
+use crate::domain::due::Due;
+use crate::domain::todo::Priority;
+
+/// Issues per search request; Jira caps it at 50 or 100 depending on the site.
+const PAGE_SIZE: usize = 50;
+
+#[derive(Debug, Clone)]
+pub struct JiraConfig {
+    /// Site root without a trailing slash, e.g. `https://acme.atlassian.net`.
+    pub base_url: String,
+    pub auth: JiraAuth,
+    pub jql: String,
+    /// Stop after this many issues.
+    pub max_results: usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum JiraAuth {
+    /// Jira Cloud: account email and API token.
+    Basic { email: String, token: String },
+    /// Jira Server / Data Center personal access token.
+    Bearer(String),
+}
+
+impl JiraAuth {
+    fn header(&self) -> String {
+        match self {
+            JiraAuth::Basic { email, token } => format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(format!("{email}:{token}"))
+            ),
+            JiraAuth::Bearer(token) => format!("Bearer {token}"),
+        }
+    }
+}
+
+/// An issue as a todo needs it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JiraIssue {
+    /// `PROJ-123`.
+    pub key: String,
+    pub summary: String,
+    pub priority: Priority,
+    /// The due day, without a time.
+    pub due: Option<Due>,
+    /// Browse link, `<base_url>/browse/PROJ-123`.
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct JiraIssues {
+    pub issues: Vec<JiraIssue>,
+    /// Every issue matching the JQL came back (not cut off by `max_results`), so todos of
+    /// issues missing from `issues` no longer match.
+    pub complete: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchParams<'a> {
+    jql: &'a str,
+    fields: &'a str,
+    start_at: usize,
+    max_results: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchPage {
+    #[serde(default)]
+    issues: Vec<RawIssue>,
+    #[serde(default)]
+    total: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawIssue {
+    key: String,
+    fields: RawFields,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawFields {
+    #[serde(default)]
+    summary: String,
+    priority: Option<RawPriority>,
+    /// `YYYY-MM-DD`.
+    duedate: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawPriority {
+    name: String,
+}
+
+/// Issues matching `cfg.jql`, in the order Jira returns them.
+pub async fn fetch_assigned(cfg: &JiraConfig) -> Result<JiraIssues> {
+    let octo = Octocrab::builder()
+        .base_uri(cfg.base_url.as_str())
+        .map_err(|e| anyhow!("invalid [jira] base_url: {e}"))?
+        .add_header(http::header::AUTHORIZATION, cfg.auth.header())
+        .add_header(http::header::ACCEPT, "application/json".to_string())
+        .build()
+        .map_err(|e| anyhow!("failed to init Jira client: {e}"))?;
+
+    let mut fetched = JiraIssues::default();
+    loop {
+        let params = SearchParams {
+            jql: &cfg.jql,
+            fields: "summary,priority,duedate",
+            start_at: fetched.issues.len(),
+            max_results: PAGE_SIZE.min(cfg.max_results.saturating_sub(fetched.issues.len())),
+        };
+        let page: SearchPage = octo
+            .get("/rest/api/2/search", Some(&params))
+            .await
+            .map_err(|e| anyhow!("Jira search failed: {e}"))?;
+        let total = page.total;
+        let got = page.issues.len();
+        fetched.issues.extend(
+            page.issues
+                .into_iter()
+                .map(|raw| raw.into_issue(&cfg.base_url)),
+        );
+        if fetched.issues.len() >= total {
+            fetched.complete = true;
+            break;
+        }
+        if got == 0 || fetched.issues.len() >= cfg.max_results {
+            break;
+        }
+    }
+    Ok(fetched)
+}
+
+impl RawIssue {
+    fn into_issue(self, base_url: &str) -> JiraIssue {
+        let due = self
+            .fields
+            .duedate
+            .as_deref()
+            .and_then(|raw| Date::parse(raw, format_description!("[year]-[month]-[day]")).ok())
+            .map(Due::day);
+        JiraIssue {
+            url: format!("{base_url}/browse/{}", self.key),
+            key: self.key,
+            summary: self.fields.summary,
+            priority: map_priority(self.fields.priority.as_ref().map(|p| p.name.as_str())),
+            due,
+        }
+    }
+}
+
+/// Jira's default scheme (Highest … Lowest) and the older Blocker … Trivial one; custom
+/// names count as Medium.
+fn map_priority(name: Option<&str>) -> Priority {
+    match name.map(str::to_ascii_lowercase).as_deref() {
+        Some("highest" | "high" | "blocker" | "critical") => Priority::High,
+        Some("low" | "lowest" | "minor" | "trivial") => Priority::Low,
+        _ => Priority::Medium,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::date;
+
+    use super::*;
+
+    #[test]
+    fn search_page_maps_to_issues() {
+        let page: SearchPage = serde_json::from_value(serde_json::json!({
+            "startAt": 0,
+            "maxResults": 50,
+            "total": 3,
+            "issues": [
+                {"key": "OPS-7", "fields": {
+                    "summary": "Rotate TLS certs",
+                    "priority": {"name": "Highest"},
+                    "duedate": "2025-03-04"
+                }},
+                {"key": "WEB-12", "fields": {
+                    "summary": "Fix footer links",
+                    "priority": {"name": "Trivial"},
+                    "duedate": null
+                }},
+                {"key": "WEB-13", "fields": {"summary": "Triage", "priority": null}}
+            ]
+        }))
+        .unwrap();
+        assert_eq!(page.total, 3);
+        let issues: Vec<JiraIssue> = page
+            .issues
+            .into_iter()
+            .map(|raw| raw.into_issue("https://acme.atlassian.net"))
+            .collect();
+
+        assert_eq!(issues[0].key, "OPS-7");
+        assert_eq!(issues[0].priority, Priority::High);
+        assert_eq!(issues[0].due, Some(Due::day(date!(2025 - 03 - 04))));
+        assert_eq!(issues[0].url, "https://acme.atlassian.net/browse/OPS-7");
+        assert_eq!((issues[1].priority, issues[1].due), (Priority::Low, None));
+        assert_eq!(issues[2].priority, Priority::Medium);
+    }
+}
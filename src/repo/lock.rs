@@ -0,0 +1,202 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+/// Which kind of process owns the store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockHolder {
+    Tui,
+    Daemon,
+    /// `koto serve`, which reads the store afresh on every request and so, like the
+    /// daemon, only keeps a second server out.
+    Server,
+}
+
+/// Contents of `<db>.lock` (TUI), `<db>.daemon.lock` or `<db>.serve.lock`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockInfo {
+    pub pid: u32,
+    pub holder: LockHolder,
+    pub started_at: i64,
+}
+
+/// Exclusive claim on a SQLite store by a long-running process, released on drop.
+///
+/// Two writers keeping their own in-memory view of the same store would silently overwrite
+/// each other, so a second TUI is refused instead. The daemon and `koto serve` keep no such
+/// view (they write through the store, and a running TUI reloads when it moves), so each
+/// has a lock of its own that only keeps a second one of its kind out.
+///
+/// The claim is an OS advisory lock on the file, which the OS drops when the holder exits
+/// however it ends, so a crashed holder's file is simply locked again; the JSON inside only
+/// names the holder for the "busy" message.
+#[derive(Debug)]
+pub struct StoreLock {
+    path: PathBuf,
+    file: File,
+}
+
+impl StoreLock {
+    pub fn acquire(db_path: &Path, holder: LockHolder) -> Result<Self> {
+        let path = lock_path(db_path, holder);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create db dir {}", parent.display()))?;
+        }
+        // Retried when the previous holder removed the file between our open and lock.
+        for _ in 0..3 {
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)
+                .with_context(|| format!("failed to open {}", path.display()))?;
+            match file.try_lock() {
+                Ok(()) => {}
+                Err(TryLockError::WouldBlock) => bail!(busy_message(read_lock(&path), db_path)),
+                Err(TryLockError::Error(e)) => {
+                    return Err(e).with_context(|| format!("failed to lock {}", path.display()));
+                }
+            }
+            if !is_same_file(&file, &path) {
+                continue;
+            }
+            let info = serde_json::to_string(&LockInfo {
+                pid: std::process::id(),
+                holder,
+                started_at: crate::now_unix(),
+            })?;
+            file.set_len(0)
+                .and_then(|()| file.write_all(info.as_bytes()))
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            return Ok(Self { path, file });
+        }
+        bail!("could not take the store lock {}", path.display())
+    }
+}
+
+impl Drop for StoreLock {
+    fn drop(&mut self) {
+        // Removed while still locked: a process that opened the file just before locks it
+        // next, finds it gone (`is_same_file`) and starts over on a new one.
+        let _ = std::fs::remove_file(&self.path);
+        let _ = self.file.unlock();
+    }
+}
+
+pub fn lock_path(db_path: &Path, holder: LockHolder) -> PathBuf {
+    let mut name = db_path.as_os_str().to_owned();
+    name.push(match holder {
+        LockHolder::Tui => ".lock",
+        LockHolder::Daemon => ".daemon.lock",
+        LockHolder::Server => ".serve.lock",
+    });
+    PathBuf::from(name)
+}
+
+fn read_lock(path: &Path) -> Option<LockInfo> {
+    let raw = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+/// Whether `path` still names the file `file` has open.
+#[cfg(unix)]
+fn is_same_file(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (file.metadata(), std::fs::metadata(path)) {
+        (Ok(open), Ok(named)) => (open.dev(), open.ino()) == (named.dev(), named.ino()),
+        _ => false,
+    }
+}
+
+/// Open files cannot be removed here, so the path always names the file we opened.
+#[cfg(not(unix))]
+fn is_same_file(_file: &File, _path: &Path) -> bool {
+    true
+}
+
+fn busy_message(info: Option<LockInfo>, db_path: &Path) -> String {
+    let Some(info) = info else {
+        // Locked, but the holder has not written its details yet.
+        return format!(
+            "another koto process is using {}; close it first, or pass --db-path to use a separate store",
+            db_path.display()
+        );
+    };
+    let who = match info.holder {
+        LockHolder::Tui => "another koto window",
+        LockHolder::Daemon => "the koto daemon",
+        LockHolder::Server => "koto serve",
+    };
+    format!(
+        "{who} (pid {}) is using {}; close it first, or pass --db-path to use a separate store",
+        info.pid,
+        db_path.display()
+    )
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_live_holder_is_refused_and_a_dead_one_taken_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("todos.sqlite");
+        let lock = StoreLock::acquire(&db, LockHolder::Tui).unwrap();
+        assert!(lock_path(&db, LockHolder::Tui).exists());
+        // The daemon's and the server's locks are separate from the TUI's.
+        drop(StoreLock::acquire(&db, LockHolder::Daemon).unwrap());
+        let server = StoreLock::acquire(&db, LockHolder::Server).unwrap();
+        assert!(dir.path().join("todos.sqlite.serve.lock").exists());
+        drop(server);
+        drop(lock);
+        assert!(!lock_path(&db, LockHolder::Tui).exists());
+
+        let lock = StoreLock::acquire(&db, LockHolder::Tui).unwrap();
+        let err = StoreLock::acquire(&db, LockHolder::Tui).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains(&format!("another koto window (pid {})", std::process::id()))
+        );
+        drop(lock);
+
+        // A crashed holder leaves its file behind, but not its lock.
+        let info = LockInfo {
+            pid: u32::MAX,
+            holder: LockHolder::Tui,
+            started_at: 0,
+        };
+        let path = lock_path(&db, LockHolder::Tui);
+        std::fs::write(&path, serde_json::to_string(&info).unwrap()).unwrap();
+        let lock = StoreLock::acquire(&db, LockHolder::Tui).unwrap();
+        assert_eq!(read_lock(&path).unwrap().pid, std::process::id());
+        drop(lock);
+    }
+
+    #[test]
+    fn only_one_of_many_racing_processes_gets_the_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("todos.sqlite");
+        // A stale file from a crash, which every contender tries to take over at once.
+        std::fs::write(lock_path(&db, LockHolder::Tui), "{}").unwrap();
+        let start = std::sync::Barrier::new(8);
+        let locks: Vec<_> = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    s.spawn(|| {
+                        start.wait();
+                        StoreLock::acquire(&db, LockHolder::Tui).ok()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(locks.iter().flatten().count(), 1);
+    }
+}
@@ -1,11 +1,24 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+
+use anyhow::Result;
 
 use super::TodoRepository;
-use crate::domain::todo::{Priority, Todo, TodoId};
+use crate::domain::activity::{self, Activity, ActivityKind};
+use crate::domain::dependency;
+use crate::domain::due::Due;
+use crate::domain::query::TodoQuery;
+use crate::domain::time_entry::TimeEntry;
+use crate::domain::todo::{Priority, Status, Todo, TodoId};
+use crate::repo::github::model::{Pr, QueryStats};
 
 #[derive(Default)]
 pub struct InMemoryTodoRepo {
     items: VecDeque<Todo>,
+    time_entries: Vec<TimeEntry>,
+    meta: HashMap<String, String>,
+    prs: Vec<Pr>,
+    activity: Vec<Activity>,
+    dependencies: Vec<(TodoId, TodoId)>,
 }
 
 impl InMemoryTodoRepo {
@@ -14,75 +27,428 @@ impl InMemoryTodoRepo {
         repo.items.extend(seed);
         repo
     }
+
+    /// Every todo, archived ones included, in insertion order.
+    pub fn todos(&self) -> impl Iterator<Item = &Todo> {
+        self.items.iter()
+    }
+
+    /// Swap in a new set of todos (a file store re-read from disk); the activity log,
+    /// timers and settings stay.
+    pub fn replace_todos(&mut self, todos: impl IntoIterator<Item = Todo>) {
+        self.items = todos.into_iter().collect();
+    }
+
+    fn log(&mut self, kind: ActivityKind, todo_id: TodoId, detail: impl Into<String>) {
+        self.activity
+            .push(Activity::now(kind, Some(todo_id), detail));
+    }
 }
 
 impl TodoRepository for InMemoryTodoRepo {
-    fn all(&self) -> Vec<Todo> {
-        self.items.iter().cloned().collect()
+    fn all(&self) -> Result<Vec<Todo>> {
+        Ok(self.items.iter().filter(|t| !t.archived).cloned().collect())
+    }
+
+    fn search(&self, query: &TodoQuery) -> Result<Vec<Todo>> {
+        Ok(self
+            .items
+            .iter()
+            .filter(|t| {
+                query.matches_scope(t.is_closed(), t.archived)
+                    && query.matches_text(&t.title, t.notes.as_deref())
+            })
+            .cloned()
+            .collect())
     }
 
     fn add(
         &mut self,
         title: String,
         priority: Priority,
-        due: Option<std::time::SystemTime>,
+        due: Option<Due>,
         external_url: Option<String>,
         external_key: Option<String>,
-    ) -> Todo {
+    ) -> Result<Todo> {
         if let Some(ref key) = external_key
             && let Some(existing) = self
                 .items
                 .iter_mut()
                 .find(|t| t.external_key.as_deref() == Some(key.as_str()))
         {
-            existing.title = title;
+            existing.apply_remote_title(title);
             existing.external_url = external_url;
-            return existing.clone();
+            return Ok(existing.clone());
         }
 
         let mut todo = Todo::with_meta(title, priority, due);
+        if external_key.is_some() {
+            todo.remote_title = Some(todo.title.clone());
+        }
         todo.external_url = external_url;
         todo.external_key = external_key;
         self.items.push_back(todo.clone());
-        todo
+        self.log(ActivityKind::Add, todo.id, &todo.title);
+        Ok(todo)
     }
 
     fn update_meta(
         &mut self,
         id: TodoId,
         priority: Priority,
-        due: Option<std::time::SystemTime>,
-    ) -> Option<Todo> {
-        for todo in &mut self.items {
-            if todo.id == id {
-                todo.priority = priority;
-                todo.due = due;
-                return Some(todo.clone());
+        due: Option<Due>,
+    ) -> Result<Option<Todo>> {
+        let Some(todo) = self.items.iter_mut().find(|t| t.id == id) else {
+            return Ok(None);
+        };
+        todo.priority = priority;
+        todo.set_due(due);
+        let todo = todo.clone();
+        self.log(
+            ActivityKind::Edit,
+            id,
+            activity::describe_meta(priority, todo.due),
+        );
+        Ok(Some(todo))
+    }
+
+    fn set_title(&mut self, id: TodoId, title: String) -> Result<Option<Todo>> {
+        let Some(todo) = self.items.iter_mut().find(|t| t.id == id) else {
+            return Ok(None);
+        };
+        todo.title = title;
+        let todo = todo.clone();
+        self.log(ActivityKind::Edit, id, format!("title: {}", todo.title));
+        Ok(Some(todo))
+    }
+
+    fn resolve_title(&mut self, id: TodoId, title: String) -> Result<Option<Todo>> {
+        let Some(todo) = self.items.iter_mut().find(|t| t.id == id) else {
+            return Ok(None);
+        };
+        if let Some(incoming) = todo.incoming_title.take() {
+            todo.remote_title = Some(incoming);
+        }
+        todo.title = title;
+        let todo = todo.clone();
+        self.log(
+            ActivityKind::Edit,
+            id,
+            format!("title (conflict resolved): {}", todo.title),
+        );
+        Ok(Some(todo))
+    }
+
+    fn set_notes(&mut self, id: TodoId, notes: Option<String>) -> Result<Option<Todo>> {
+        let Some(todo) = self.items.iter_mut().find(|t| t.id == id) else {
+            return Ok(None);
+        };
+        todo.notes = notes;
+        let todo = todo.clone();
+        let detail = match todo.notes {
+            Some(_) => "notes updated",
+            None => "notes cleared",
+        };
+        self.log(ActivityKind::Edit, id, detail);
+        Ok(Some(todo))
+    }
+
+    fn set_start(
+        &mut self,
+        id: TodoId,
+        start: Option<std::time::SystemTime>,
+    ) -> Result<Option<Todo>> {
+        let Some(todo) = self.items.iter_mut().find(|t| t.id == id) else {
+            return Ok(None);
+        };
+        todo.start_at = start;
+        let todo = todo.clone();
+        let detail = match todo.start_at {
+            Some(_) => "start date set",
+            None => "start date cleared",
+        };
+        self.log(ActivityKind::Edit, id, detail);
+        Ok(Some(todo))
+    }
+
+    fn set_status(&mut self, id: TodoId, status: Status) -> Result<Option<Todo>> {
+        let Some(todo) = self.items.iter_mut().find(|t| t.id == id) else {
+            return Ok(None);
+        };
+        let closing = status.is_closed() != todo.is_closed();
+        if closing {
+            todo.completed_at = status.is_closed().then(std::time::SystemTime::now);
+        }
+        todo.status = status;
+        let todo = todo.clone();
+        if closing {
+            self.log(ActivityKind::toggled(status.is_closed()), id, &todo.title);
+        } else {
+            self.log(ActivityKind::Edit, id, format!("status {}", status.label()));
+        }
+        Ok(Some(todo))
+    }
+
+    fn set_estimate(&mut self, id: TodoId, minutes: Option<u32>) -> Result<Option<Todo>> {
+        let Some(todo) = self.items.iter_mut().find(|t| t.id == id) else {
+            return Ok(None);
+        };
+        todo.estimate_minutes = minutes;
+        let todo = todo.clone();
+        let detail = match todo.estimate_minutes {
+            Some(_) => "estimate set",
+            None => "estimate cleared",
+        };
+        self.log(ActivityKind::Edit, id, detail);
+        Ok(Some(todo))
+    }
+
+    fn mark_seen(
+        &mut self,
+        id: TodoId,
+        sha: Option<String>,
+        at: std::time::SystemTime,
+    ) -> Result<Option<Todo>> {
+        let Some(todo) = self.items.iter_mut().find(|t| t.id == id) else {
+            return Ok(None);
+        };
+        todo.last_seen_sha = sha;
+        todo.last_seen_at = Some(at);
+        Ok(Some(todo.clone()))
+    }
+
+    fn add_dependency(&mut self, id: TodoId, blocked_by: TodoId) -> Result<()> {
+        if dependency::creates_cycle(&self.dependencies, id, blocked_by) {
+            anyhow::bail!("{blocked_by} already waits for {id}; the link would make a cycle");
+        }
+        if !self.dependencies.contains(&(id, blocked_by)) {
+            self.dependencies.push((id, blocked_by));
+        }
+        self.log(ActivityKind::Edit, id, format!("blocked by {blocked_by}"));
+        Ok(())
+    }
+
+    fn clear_dependencies(&mut self, id: TodoId) -> Result<usize> {
+        let before = self.dependencies.len();
+        self.dependencies.retain(|(todo, _)| *todo != id);
+        let removed = before - self.dependencies.len();
+        if removed > 0 {
+            self.log(ActivityKind::Edit, id, "no longer blocked");
+        }
+        Ok(removed)
+    }
+
+    fn dependencies(&self) -> Result<Vec<(TodoId, TodoId)>> {
+        Ok(self.dependencies.clone())
+    }
+
+    fn set_parent(&mut self, id: TodoId, parent: Option<TodoId>) -> Result<Option<Todo>> {
+        let Some(todo) = self.items.iter_mut().find(|t| t.id == id) else {
+            return Ok(None);
+        };
+        todo.parent_id = parent;
+        let todo = todo.clone();
+        let detail = match parent {
+            Some(parent) => format!("subtask of {parent}"),
+            None => "detached from its parent".to_string(),
+        };
+        self.log(ActivityKind::Edit, id, detail);
+        Ok(Some(todo))
+    }
+
+    fn toggle(&mut self, id: TodoId) -> Result<Option<Todo>> {
+        let Some(todo) = self.items.iter_mut().find(|t| t.id == id) else {
+            return Ok(None);
+        };
+        todo.status = if todo.is_closed() {
+            Status::Todo
+        } else {
+            Status::Done
+        };
+        todo.completed_at = todo.is_closed().then(std::time::SystemTime::now);
+        let todo = todo.clone();
+        self.log(ActivityKind::toggled(todo.is_closed()), id, &todo.title);
+        Ok(Some(todo))
+    }
+
+    fn delete(&mut self, id: TodoId) -> Result<Option<Todo>> {
+        let Some(pos) = self.items.iter().position(|t| t.id == id) else {
+            return Ok(None);
+        };
+        for child in self.items.iter_mut().filter(|t| t.parent_id == Some(id)) {
+            child.parent_id = None;
+        }
+        self.dependencies
+            .retain(|(todo, blocker)| *todo != id && *blocker != id);
+        self.time_entries.retain(|entry| entry.todo_id != id);
+        let Some(todo) = self.items.remove(pos) else {
+            return Ok(None);
+        };
+        self.log(ActivityKind::Delete, id, &todo.title);
+        Ok(Some(todo))
+    }
+
+    fn clear_done(&mut self) -> Result<usize> {
+        let mut archived = Vec::new();
+        for todo in self
+            .items
+            .iter_mut()
+            .filter(|t| t.is_closed() && !t.archived)
+        {
+            todo.archived = true;
+            archived.push((todo.id, todo.title.clone()));
+        }
+        for (id, title) in &archived {
+            self.log(ActivityKind::Archive, *id, title);
+        }
+        Ok(archived.len())
+    }
+
+    fn archive(&mut self, id: TodoId) -> Result<Option<Todo>> {
+        let Some(todo) = self.items.iter_mut().find(|t| t.id == id) else {
+            return Ok(None);
+        };
+        todo.archived = true;
+        let todo = todo.clone();
+        self.log(ActivityKind::Archive, id, &todo.title);
+        Ok(Some(todo))
+    }
+
+    fn delete_many(&mut self, ids: &[TodoId]) -> Result<Vec<Todo>> {
+        let mut deleted = Vec::new();
+        for id in ids {
+            deleted.extend(self.delete(*id)?);
+        }
+        Ok(deleted)
+    }
+
+    fn archive_many(&mut self, ids: &[TodoId]) -> Result<usize> {
+        let mut archived = 0;
+        for id in ids {
+            archived += usize::from(self.archive(*id)?.is_some());
+        }
+        Ok(archived)
+    }
+
+    fn set_priority_many(&mut self, ids: &[TodoId], priority: Priority) -> Result<usize> {
+        let mut changed = Vec::new();
+        for todo in self.items.iter_mut().filter(|t| ids.contains(&t.id)) {
+            todo.priority = priority;
+            changed.push(todo.id);
+        }
+        for id in &changed {
+            self.log(ActivityKind::Edit, *id, format!("priority {priority:?}"));
+        }
+        Ok(changed.len())
+    }
+
+    fn set_due_many(&mut self, dues: &[(TodoId, Option<Due>)]) -> Result<usize> {
+        let mut changed = 0;
+        for (id, due) in dues {
+            if let Some(todo) = self.items.iter_mut().find(|t| t.id == *id) {
+                todo.set_due(*due);
+                changed += 1;
+                self.log(
+                    ActivityKind::Edit,
+                    *id,
+                    format!("due {}", activity::describe_due(due.map(|d| d.at))),
+                );
             }
         }
-        None
+        Ok(changed)
     }
 
-    fn toggle(&mut self, id: TodoId) -> Option<Todo> {
-        for todo in &mut self.items {
-            if todo.id == id {
-                todo.done = !todo.done;
-                return Some(todo.clone());
+    fn set_title_many(&mut self, titles: &[(TodoId, String)]) -> Result<usize> {
+        let mut changed = 0;
+        for (id, title) in titles {
+            changed += usize::from(self.set_title(*id, title.clone())?.is_some());
+        }
+        Ok(changed)
+    }
+
+    fn set_sort_orders(&mut self, orders: &[(TodoId, i64)]) -> Result<usize> {
+        let mut changed = 0;
+        for (id, order) in orders {
+            if let Some(todo) = self.items.iter_mut().find(|t| t.id == *id)
+                && todo.sort_order != Some(*order)
+            {
+                todo.sort_order = Some(*order);
+                changed += 1;
+                self.log(ActivityKind::Edit, *id, format!("position {order}"));
             }
         }
-        None
+        Ok(changed)
+    }
+
+    fn meta(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.meta.get(key).cloned())
+    }
+
+    fn set_meta(&mut self, key: &str, value: &str) -> Result<()> {
+        self.meta.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn replace_prs(&mut self, prs: &[Pr]) -> Result<()> {
+        self.prs = prs.to_vec();
+        Ok(())
+    }
+
+    fn cached_prs(&self) -> Result<Vec<Pr>> {
+        Ok(self.prs.clone())
     }
 
-    fn delete(&mut self, id: TodoId) -> Option<Todo> {
-        if let Some(pos) = self.items.iter().position(|t| t.id == id) {
-            return self.items.remove(pos);
+    fn record_sync_stats(&mut self, _synced_at: i64, _stats: &[QueryStats]) -> Result<()> {
+        // Nothing to tune from a throwaway store.
+        Ok(())
+    }
+
+    fn start_timer(&mut self, id: TodoId) -> Result<Option<TimeEntry>> {
+        if !self.items.iter().any(|t| t.id == id) {
+            return Ok(None);
         }
-        None
+        self.stop_timer()?;
+        let entry = TimeEntry::start(id);
+        self.time_entries.push(entry.clone());
+        Ok(Some(entry))
+    }
+
+    fn stop_timer(&mut self) -> Result<Option<TimeEntry>> {
+        let Some(entry) = self.time_entries.iter_mut().find(|e| e.is_running()) else {
+            return Ok(None);
+        };
+        entry.ended_at = Some(std::time::SystemTime::now());
+        Ok(Some(entry.clone()))
+    }
+
+    fn log_activity(
+        &mut self,
+        kind: ActivityKind,
+        todo_id: Option<TodoId>,
+        detail: &str,
+    ) -> Result<()> {
+        self.activity.push(Activity::now(kind, todo_id, detail));
+        Ok(())
+    }
+
+    fn activity(&self, limit: usize) -> Result<Vec<Activity>> {
+        let skip = self.activity.len().saturating_sub(limit);
+        Ok(self.activity[skip..].to_vec())
+    }
+
+    fn data_version(&self) -> Result<Option<i64>> {
+        Ok(None)
     }
 
-    fn clear_done(&mut self) -> usize {
-        let before = self.items.len();
-        self.items.retain(|t| !t.done);
-        before - self.items.len()
+    fn time_entries(&self, since: Option<std::time::SystemTime>) -> Result<Vec<TimeEntry>> {
+        Ok(self
+            .time_entries
+            .iter()
+            .filter(|e| match (since, e.ended_at) {
+                (Some(since), Some(ended)) => ended >= since,
+                _ => true,
+            })
+            .cloned()
+            .collect())
     }
 }
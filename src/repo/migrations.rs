@@ -0,0 +1,460 @@
+//! Versioned schema of the SQLite store. `PRAGMA user_version` holds the number of the
+//! last applied migration; `migrate` runs the ones after it, in order, each in its own
+//! transaction. Add a change by appending a migration, never by editing an applied one.
+
+use anyhow::{Context, Result, bail};
+use rusqlite::{Connection, params};
+use time::{OffsetDateTime, UtcOffset};
+
+use crate::domain::clock;
+
+struct Migration {
+    version: u32,
+    description: &'static str,
+    up: fn(&Connection) -> Result<()>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "baseline: todos, time entries, meta, PR cache, activity log, sync stats",
+        up: baseline,
+    },
+    Migration {
+        version: 2,
+        description: "todos.last_seen_sha / last_seen_at for the updated-PR badge",
+        up: last_seen,
+    },
+    Migration {
+        version: 3,
+        description: "todos.start_at for todos scheduled to appear later",
+        up: start_at,
+    },
+    Migration {
+        version: 4,
+        description: "todos.estimate_minutes for capacity planning",
+        up: estimate,
+    },
+    Migration {
+        version: 5,
+        description: "todo_deps: \"blocked by\" links between todos",
+        up: dependencies,
+    },
+    Migration {
+        version: 6,
+        description: "todos.state (backlog / doing) for the board view",
+        up: board_state,
+    },
+    Migration {
+        version: 7,
+        description: "todos.status replaces done / state",
+        up: status,
+    },
+    Migration {
+        version: 8,
+        description: "drop time entries left behind by deleted todos",
+        up: orphaned_time_entries,
+    },
+    Migration {
+        version: 9,
+        description: "todos.due_has_time; UTC end-of-day dues moved to the local end of day",
+        up: due_has_time,
+    },
+];
+
+/// Schema version this build writes.
+pub fn latest() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+fn current(conn: &Connection) -> Result<u32> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+        .context("failed to read the schema version")
+}
+
+/// Whether an existing store (not a brand-new file) is behind `latest`.
+pub fn pending(conn: &Connection) -> Result<bool> {
+    let has_tables: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table')",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(has_tables && current(conn)? < latest())
+}
+
+/// Bring the store up to `latest`. A store written by a newer koto is refused rather
+/// than guessed at.
+pub fn migrate(conn: &Connection) -> Result<()> {
+    let from = current(conn)?;
+    if from > latest() {
+        bail!(
+            "the store has schema version {from}, newer than this koto understands ({}); upgrade koto",
+            latest()
+        );
+    }
+    for migration in MIGRATIONS.iter().filter(|m| m.version > from) {
+        let tx = conn.unchecked_transaction()?;
+        (migration.up)(&tx).with_context(|| {
+            format!(
+                "schema migration {} ({}) failed",
+                migration.version, migration.description
+            )
+        })?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// Schema as it stood when versioning was introduced. Stores from before then may be at
+/// any earlier layout, so every step is idempotent: missing tables are created and
+/// missing columns added.
+fn baseline(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+CREATE TABLE IF NOT EXISTS todos (
+  id TEXT PRIMARY KEY,
+  title TEXT NOT NULL,
+  done INTEGER NOT NULL DEFAULT 0,
+  archived INTEGER NOT NULL DEFAULT 0,
+  priority INTEGER NOT NULL DEFAULT 2,
+  due INTEGER NULL,
+  created_at INTEGER NOT NULL,
+  external_url TEXT NULL,
+  external_key TEXT NULL,
+  notes TEXT NULL,
+  parent_id TEXT NULL,
+  remote_title TEXT NULL,
+  incoming_title TEXT NULL,
+  sort_order INTEGER NULL,
+  completed_at INTEGER NULL
+);
+CREATE TABLE IF NOT EXISTS time_entries (
+  id TEXT PRIMARY KEY,
+  todo_id TEXT NOT NULL,
+  started_at INTEGER NOT NULL,
+  ended_at INTEGER NULL
+);
+CREATE INDEX IF NOT EXISTS idx_time_entries_todo ON time_entries(todo_id);
+CREATE TABLE IF NOT EXISTS meta (
+  key TEXT PRIMARY KEY,
+  value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS prs (
+  pr_key TEXT PRIMARY KEY,
+  data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS activity (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  at INTEGER NOT NULL,
+  kind TEXT NOT NULL,
+  todo_id TEXT NULL,
+  detail TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS sync_stats (
+  synced_at INTEGER NOT NULL,
+  query TEXT NOT NULL,
+  pages INTEGER NOT NULL,
+  nodes INTEGER NOT NULL,
+  kept INTEGER NOT NULL,
+  elapsed_ms INTEGER NOT NULL,
+  cost INTEGER NOT NULL
+);
+"#,
+    )
+    .context("failed to initialize schema")?;
+
+    ensure_column(
+        conn,
+        "priority",
+        "ALTER TABLE todos ADD COLUMN priority INTEGER NOT NULL DEFAULT 2",
+    )?;
+    ensure_column(
+        conn,
+        "archived",
+        "ALTER TABLE todos ADD COLUMN archived INTEGER NOT NULL DEFAULT 0",
+    )?;
+    ensure_column(conn, "due", "ALTER TABLE todos ADD COLUMN due INTEGER NULL")?;
+    ensure_column(
+        conn,
+        "external_url",
+        "ALTER TABLE todos ADD COLUMN external_url TEXT NULL",
+    )?;
+    ensure_column(
+        conn,
+        "external_key",
+        "ALTER TABLE todos ADD COLUMN external_key TEXT NULL",
+    )?;
+
+    ensure_column(
+        conn,
+        "notes",
+        "ALTER TABLE todos ADD COLUMN notes TEXT NULL",
+    )?;
+    ensure_column(
+        conn,
+        "parent_id",
+        "ALTER TABLE todos ADD COLUMN parent_id TEXT NULL",
+    )?;
+    ensure_column(
+        conn,
+        "remote_title",
+        "ALTER TABLE todos ADD COLUMN remote_title TEXT NULL",
+    )?;
+    ensure_column(
+        conn,
+        "incoming_title",
+        "ALTER TABLE todos ADD COLUMN incoming_title TEXT NULL",
+    )?;
+    ensure_column(
+        conn,
+        "sort_order",
+        "ALTER TABLE todos ADD COLUMN sort_order INTEGER NULL",
+    )?;
+    ensure_column(
+        conn,
+        "completed_at",
+        "ALTER TABLE todos ADD COLUMN completed_at INTEGER NULL",
+    )?;
+
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_todos_external_key ON todos(external_key)",
+        [],
+    )
+    .context("failed to create external key index")?;
+    Ok(())
+}
+
+fn last_seen(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE todos ADD COLUMN last_seen_sha TEXT NULL;
+         ALTER TABLE todos ADD COLUMN last_seen_at INTEGER NULL;",
+    )?;
+    Ok(())
+}
+
+fn start_at(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE todos ADD COLUMN start_at INTEGER NULL;")?;
+    Ok(())
+}
+
+fn estimate(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE todos ADD COLUMN estimate_minutes INTEGER NULL;")?;
+    Ok(())
+}
+
+fn dependencies(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS todo_deps (
+           todo_id TEXT NOT NULL,
+           blocked_by TEXT NOT NULL,
+           PRIMARY KEY (todo_id, blocked_by)
+         );",
+    )?;
+    Ok(())
+}
+
+fn board_state(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE todos ADD COLUMN state TEXT NOT NULL DEFAULT 'backlog';")?;
+    Ok(())
+}
+
+/// Folds the done flag and the board state into one status column.
+fn status(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE todos ADD COLUMN status TEXT NOT NULL DEFAULT 'todo';
+         UPDATE todos SET status = CASE
+           WHEN done = 1 THEN 'done'
+           WHEN state = 'doing' THEN 'in_progress'
+           ELSE 'todo'
+         END;
+         ALTER TABLE todos DROP COLUMN done;
+         ALTER TABLE todos DROP COLUMN state;",
+    )?;
+    Ok(())
+}
+
+/// Deleting a todo used to keep its time entries, which still counted in reports.
+fn orphaned_time_entries(conn: &Connection) -> Result<()> {
+    conn.execute_batch("DELETE FROM time_entries WHERE todo_id NOT IN (SELECT id FROM todos);")?;
+    Ok(())
+}
+
+/// Whether a due has a time of day used to be read off its timestamp: date-only dues were
+/// the day's last second, at first in UTC and later in local time. Record it instead, and
+/// move the UTC ones to the local end of their day so they stay on the same date.
+fn due_has_time(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE todos ADD COLUMN due_has_time INTEGER NOT NULL DEFAULT 0;")?;
+    let dues = conn
+        .prepare("SELECT id, due FROM todos WHERE due IS NOT NULL")?
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let offset = clock::local_offset();
+    for (id, due) in dues {
+        let (due, has_time) = legacy_due(due, offset);
+        conn.execute(
+            "UPDATE todos SET due = ?1, due_has_time = ?2 WHERE id = ?3",
+            params![due, has_time, id],
+        )?;
+    }
+    Ok(())
+}
+
+/// A due stored before `due_has_time`, in unix seconds, read in `offset`: the due to
+/// keep and whether it has a time of day.
+fn legacy_due(due: i64, offset: UtcOffset) -> (i64, bool) {
+    let Ok(utc) = OffsetDateTime::from_unix_timestamp(due) else {
+        return (due, true);
+    };
+    let end_of_day = |at: OffsetDateTime| (at.hour(), at.minute(), at.second()) == (23, 59, 59);
+    if end_of_day(utc) {
+        let local_end = utc
+            .date()
+            .with_hms(23, 59, 59)
+            .map(|at| at.assume_offset(offset));
+        return local_end.map_or((due, false), |at| (at.unix_timestamp(), false));
+    }
+    (due, !end_of_day(utc.to_offset(offset)))
+}
+
+fn ensure_column(conn: &Connection, name: &str, alter_sql: &str) -> Result<()> {
+    let mut stmt = conn.prepare("PRAGMA table_info(todos)")?;
+    let cols = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    if !cols.iter().any(|c| c == name) {
+        conn.execute(alter_sql, [])
+            .context("failed to add column")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn.prepare("PRAGMA table_info(todos)").unwrap();
+        let mut cols: Vec<String> = stmt
+            .query_map([], |row| row.get(1))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        cols.sort();
+        cols
+    }
+
+    #[test]
+    fn migrations_are_numbered_in_order() {
+        for (idx, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(
+                migration.version as usize,
+                idx + 1,
+                "{}",
+                migration.description
+            );
+        }
+    }
+
+    #[test]
+    fn unversioned_store_is_brought_up_to_date_once() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE todos (id TEXT PRIMARY KEY, title TEXT NOT NULL, done INTEGER NOT NULL DEFAULT 0, created_at INTEGER NOT NULL);
+             INSERT INTO todos VALUES ('a', 'old', 0, 0), ('b', 'shipped', 1, 0);",
+        )
+        .unwrap();
+        assert!(pending(&conn).unwrap());
+
+        migrate(&conn).unwrap();
+        assert_eq!(current(&conn).unwrap(), latest());
+        assert!(!pending(&conn).unwrap());
+        assert!(columns(&conn).iter().any(|c| c == "completed_at"));
+        assert!(columns(&conn).iter().any(|c| c == "last_seen_at"));
+        assert!(columns(&conn).iter().any(|c| c == "start_at"));
+        assert!(columns(&conn).iter().any(|c| c == "estimate_minutes"));
+        assert!(columns(&conn).iter().all(|c| c != "done" && c != "state"));
+        let status: String = conn
+            .query_row("SELECT status FROM todos WHERE id = 'b'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(status, "done");
+        let title: String = conn
+            .query_row("SELECT title FROM todos WHERE id = 'a'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(title, "old");
+        migrate(&conn).unwrap();
+
+        let fresh = Connection::open_in_memory().unwrap();
+        assert!(!pending(&fresh).unwrap());
+        migrate(&fresh).unwrap();
+        assert_eq!(columns(&fresh), columns(&conn));
+
+        conn.pragma_update(None, "user_version", latest() + 1)
+            .unwrap();
+        assert!(migrate(&conn).is_err());
+    }
+
+    fn unix(at: time::PrimitiveDateTime, offset: UtcOffset) -> i64 {
+        at.assume_offset(offset).unix_timestamp()
+    }
+
+    #[test]
+    fn legacy_dues_keep_their_day_and_tell_date_from_time() {
+        let tokyo = UtcOffset::from_hms(9, 0, 0).unwrap();
+        let new_york = UtcOffset::from_hms(-5, 0, 0).unwrap();
+        let day = time::macros::date!(2026 - 03 - 10);
+        let end = day.with_hms(23, 59, 59).unwrap();
+        let utc_end = unix(end, UtcOffset::UTC);
+
+        assert_eq!(legacy_due(utc_end, tokyo), (unix(end, tokyo), false));
+        assert_eq!(legacy_due(utc_end, new_york), (unix(end, new_york), false));
+        assert_eq!(legacy_due(utc_end, UtcOffset::UTC), (utc_end, false));
+        assert_eq!(
+            legacy_due(unix(end, tokyo), tokyo),
+            (unix(end, tokyo), false)
+        );
+
+        let meeting = unix(day.with_hms(9, 30, 0).unwrap(), tokyo);
+        assert_eq!(legacy_due(meeting, tokyo), (meeting, true));
+    }
+
+    #[test]
+    fn utc_end_of_day_dues_move_to_the_local_end_of_day() {
+        let conn = Connection::open_in_memory().unwrap();
+        for migration in &MIGRATIONS[..8] {
+            (migration.up)(&conn).unwrap();
+        }
+        conn.pragma_update(None, "user_version", 8).unwrap();
+        let day = time::macros::date!(2026 - 03 - 10);
+        let utc_end = unix(day.with_hms(23, 59, 59).unwrap(), UtcOffset::UTC);
+        let meeting = unix(day.with_hms(9, 30, 0).unwrap(), UtcOffset::UTC);
+        conn.execute(
+            "INSERT INTO todos (id, title, created_at, due) VALUES ('a', 'day', 0, ?1), ('b', 'meeting', 0, ?2), ('c', 'someday', 0, NULL)",
+            params![utc_end, meeting],
+        )
+        .unwrap();
+
+        migrate(&conn).unwrap();
+        let due = |id: &str| -> (Option<i64>, bool) {
+            conn.query_row(
+                "SELECT due, due_has_time FROM todos WHERE id = ?1",
+                [id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap()
+        };
+        let local_end = clock::end_of_day(day)
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        assert_eq!(due("a"), (Some(local_end), false));
+        assert_eq!(due("b"), (Some(meeting), true));
+        assert_eq!(due("c"), (None, false));
+    }
+}
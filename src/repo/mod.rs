@@ -1,26 +1,149 @@
-use crate::domain::todo::{Priority, Todo, TodoId};
+use anyhow::Result;
 
+use crate::domain::activity::{Activity, ActivityKind};
+use crate::domain::due::Due;
+use crate::domain::query::TodoQuery;
+use crate::domain::time_entry::TimeEntry;
+use crate::domain::todo::{Priority, Status, Todo, TodoId};
+use crate::repo::github::model::{Pr, QueryStats};
+
+pub mod archive;
+pub mod backup;
+pub mod caldav;
+pub mod crypto;
+pub mod git_sync;
 pub mod github;
+pub mod jira;
+pub mod lock;
 pub mod memory;
+pub mod migrations;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod session;
 pub mod sqlite;
+pub mod todotxt;
+
+/// A write gave up because another process (a second TUI, `koto add`, the daemon) kept
+/// the store locked; nothing was changed, and trying again later may work.
+#[derive(Debug, Clone, Copy)]
+pub struct StoreBusy;
+
+impl std::fmt::Display for StoreBusy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("store busy: another koto process is writing to it")
+    }
+}
+
+impl std::error::Error for StoreBusy {}
 
+/// Storage of todos and everything around them. Every method can fail (a locked or
+/// damaged SQLite file, a full disk); callers report the error instead of panicking.
 pub trait TodoRepository {
-    fn all(&self) -> Vec<Todo>;
+    /// Todos that are not archived.
+    fn all(&self) -> Result<Vec<Todo>>;
+    /// Title search within the query's scope (archived items included when asked for).
+    fn search(&self, query: &TodoQuery) -> Result<Vec<Todo>>;
     fn add(
         &mut self,
         title: String,
         priority: Priority,
-        due: Option<std::time::SystemTime>,
+        due: Option<Due>,
         external_url: Option<String>,
         external_key: Option<String>,
-    ) -> Todo;
+    ) -> Result<Todo>;
     fn update_meta(
         &mut self,
         id: TodoId,
         priority: Priority,
-        due: Option<std::time::SystemTime>,
-    ) -> Option<Todo>;
-    fn toggle(&mut self, id: TodoId) -> Option<Todo>;
-    fn delete(&mut self, id: TodoId) -> Option<Todo>;
-    fn clear_done(&mut self) -> usize;
+        due: Option<Due>,
+    ) -> Result<Option<Todo>>;
+    /// Rename locally; provider syncs will not silently overwrite this.
+    fn set_title(&mut self, id: TodoId, title: String) -> Result<Option<Todo>>;
+    /// Settle a title conflict: use `title` and accept the incoming remote title as the new base.
+    fn resolve_title(&mut self, id: TodoId, title: String) -> Result<Option<Todo>>;
+    /// Replace the notes; `None` clears them.
+    fn set_notes(&mut self, id: TodoId, notes: Option<String>) -> Result<Option<Todo>>;
+    /// Set the start date before which the todo stays hidden; `None` clears it.
+    fn set_start(
+        &mut self,
+        id: TodoId,
+        start: Option<std::time::SystemTime>,
+    ) -> Result<Option<Todo>>;
+    /// Set the status. Closing (done / cancelled) stamps `completed_at` and logs like a
+    /// toggle; reopening clears it.
+    fn set_status(&mut self, id: TodoId, status: Status) -> Result<Option<Todo>>;
+    /// Set the effort estimate in minutes; `None` clears it.
+    fn set_estimate(&mut self, id: TodoId, minutes: Option<u32>) -> Result<Option<Todo>>;
+    /// Remember that the todo's PR was looked at, at head `sha`, at `at`. Not logged.
+    fn mark_seen(
+        &mut self,
+        id: TodoId,
+        sha: Option<String>,
+        at: std::time::SystemTime,
+    ) -> Result<Option<Todo>>;
+    /// Make `id` wait for `blocked_by`. Refused when `blocked_by` already waits for `id`,
+    /// directly or through other links.
+    fn add_dependency(&mut self, id: TodoId, blocked_by: TodoId) -> Result<()>;
+    /// Drop everything `id` waits for; returns how many links went away.
+    fn clear_dependencies(&mut self, id: TodoId) -> Result<usize>;
+    /// Every `(todo, blocked_by)` link.
+    fn dependencies(&self) -> Result<Vec<(TodoId, TodoId)>>;
+    /// Make `id` a subtask of `parent` (`None` detaches it).
+    fn set_parent(&mut self, id: TodoId, parent: Option<TodoId>) -> Result<Option<Todo>>;
+    /// Close an open todo as done; reopen a done or cancelled one as todo.
+    fn toggle(&mut self, id: TodoId) -> Result<Option<Todo>>;
+    /// Delete `id`; its subtasks become top-level todos.
+    fn delete(&mut self, id: TodoId) -> Result<Option<Todo>>;
+    /// Archive done and cancelled todos and return how many were archived.
+    fn clear_done(&mut self) -> Result<usize>;
+    /// Archive one todo, done or not.
+    fn archive(&mut self, id: TodoId) -> Result<Option<Todo>>;
+
+    // Batch edits of the todos marked with `v`. SQLite applies each batch in one
+    // transaction, so it lands completely or not at all; unknown ids are skipped.
+
+    /// Delete every todo in `ids` (subtasks become top-level) and return the deleted ones.
+    fn delete_many(&mut self, ids: &[TodoId]) -> Result<Vec<Todo>>;
+    /// Archive every todo in `ids`; returns how many were archived.
+    fn archive_many(&mut self, ids: &[TodoId]) -> Result<usize>;
+    fn set_priority_many(&mut self, ids: &[TodoId], priority: Priority) -> Result<usize>;
+    /// Set each todo's due date (`None` clears it).
+    fn set_due_many(&mut self, dues: &[(TodoId, Option<Due>)]) -> Result<usize>;
+    /// Rename several todos locally, as `set_title` does.
+    fn set_title_many(&mut self, titles: &[(TodoId, String)]) -> Result<usize>;
+    /// Store manual positions (`J` / `K`); the whole group is rewritten at once.
+    fn set_sort_orders(&mut self, orders: &[(TodoId, i64)]) -> Result<usize>;
+
+    /// Start tracking time on `id`, stopping any session that is still running.
+    fn start_timer(&mut self, id: TodoId) -> Result<Option<TimeEntry>>;
+    /// Stop the running session, if any, and return it.
+    fn stop_timer(&mut self) -> Result<Option<TimeEntry>>;
+    /// Small persistent key/value settings (e.g. the last GitHub viewer login).
+    fn meta(&self, key: &str) -> Result<Option<String>>;
+    fn set_meta(&mut self, key: &str, value: &str) -> Result<()>;
+
+    /// Replace the cached snapshot of PRs from the last GitHub sync.
+    fn replace_prs(&mut self, prs: &[Pr]) -> Result<()>;
+    /// PRs from the last GitHub sync, so reports work offline.
+    fn cached_prs(&self) -> Result<Vec<Pr>>;
+    /// Append per-query cost figures of a sync that finished at `synced_at` (unix seconds).
+    fn record_sync_stats(&mut self, synced_at: i64, stats: &[QueryStats]) -> Result<()>;
+
+    /// Sessions overlapping the period after `since` (all sessions when `None`).
+    fn time_entries(&self, since: Option<std::time::SystemTime>) -> Result<Vec<TimeEntry>>;
+
+    /// Append to the activity log. Todo mutations above log themselves; this is for
+    /// events outside them, such as a finished sync.
+    fn log_activity(
+        &mut self,
+        kind: ActivityKind,
+        todo_id: Option<TodoId>,
+        detail: &str,
+    ) -> Result<()>;
+    /// The newest `limit` activity log entries, oldest first.
+    fn activity(&self, limit: usize) -> Result<Vec<Activity>>;
+
+    /// A counter that moves whenever another process commits to the store (a CLI add, the
+    /// daemon); this repo's own writes leave it alone. `None` when nothing else can write.
+    fn data_version(&self) -> Result<Option<i64>>;
 }
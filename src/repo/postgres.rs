@@ -0,0 +1,1167 @@
+//! Shared store on PostgreSQL (`--db-url postgres://…`, cargo feature `postgres`), so
+//! several machines can work on one list. Same tables and behaviour as the SQLite store,
+//! without encryption or ranked search: search matches every word with `ILIKE`.
+//!
+//! Each write runs in one transaction that locks the rows it reads (`FOR UPDATE`), so two
+//! machines editing the same todo apply one after the other. Synced todos are upserted on
+//! `external_key`, which is unique, so two machines syncing at once still end up with one
+//! todo per PR or issue.
+
+use std::cell::RefCell;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, bail};
+use postgres::{Client, GenericClient, NoTls, Row, Transaction};
+use uuid::Uuid;
+
+use super::TodoRepository;
+use crate::domain::activity::{self, Activity, ActivityKind};
+use crate::domain::dependency;
+use crate::domain::due::Due;
+use crate::domain::query::{SearchScope, TodoQuery};
+use crate::domain::time_entry::TimeEntry;
+use crate::domain::todo::{Priority, Status, Todo, TodoId};
+use crate::repo::github::model::{Pr, QueryStats};
+
+const TODO_COLUMNS: &str = "id, title, status, archived, priority, due, due_has_time, created_at, external_url, external_key, notes, parent_id, remote_title, incoming_title, sort_order, completed_at, last_seen_sha, last_seen_at, start_at, estimate_minutes";
+
+/// Created on first connect. Times are unix seconds, as in the SQLite store.
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS todos (
+  id TEXT PRIMARY KEY,
+  title TEXT NOT NULL,
+  status TEXT NOT NULL DEFAULT 'todo',
+  archived BOOLEAN NOT NULL DEFAULT FALSE,
+  priority INTEGER NOT NULL DEFAULT 2,
+  due BIGINT NULL,
+  due_has_time BOOLEAN NOT NULL DEFAULT FALSE,
+  created_at BIGINT NOT NULL,
+  external_url TEXT NULL,
+  external_key TEXT NULL UNIQUE,
+  notes TEXT NULL,
+  parent_id TEXT NULL,
+  remote_title TEXT NULL,
+  incoming_title TEXT NULL,
+  sort_order BIGINT NULL,
+  completed_at BIGINT NULL,
+  last_seen_sha TEXT NULL,
+  last_seen_at BIGINT NULL,
+  start_at BIGINT NULL,
+  estimate_minutes INTEGER NULL
+);
+CREATE TABLE IF NOT EXISTS todo_deps (
+  todo_id TEXT NOT NULL,
+  blocked_by TEXT NOT NULL,
+  PRIMARY KEY (todo_id, blocked_by)
+);
+CREATE TABLE IF NOT EXISTS time_entries (
+  id TEXT PRIMARY KEY,
+  todo_id TEXT NOT NULL,
+  started_at BIGINT NOT NULL,
+  ended_at BIGINT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_time_entries_todo ON time_entries(todo_id);
+CREATE TABLE IF NOT EXISTS meta (
+  key TEXT PRIMARY KEY,
+  value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS prs (
+  pr_key TEXT PRIMARY KEY,
+  data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS activity (
+  id BIGSERIAL PRIMARY KEY,
+  at BIGINT NOT NULL,
+  kind TEXT NOT NULL,
+  todo_id TEXT NULL,
+  detail TEXT NOT NULL,
+  origin TEXT NULL
+);
+CREATE TABLE IF NOT EXISTS sync_stats (
+  synced_at BIGINT NOT NULL,
+  query TEXT NOT NULL,
+  pages INTEGER NOT NULL,
+  nodes INTEGER NOT NULL,
+  kept INTEGER NOT NULL,
+  elapsed_ms BIGINT NOT NULL,
+  cost INTEGER NOT NULL
+);
+ALTER TABLE todos ADD COLUMN IF NOT EXISTS due_has_time BOOLEAN NOT NULL DEFAULT FALSE;
+"#;
+
+pub struct PostgresTodoRepo {
+    /// Reads take `&self`, but every query needs the client mutably.
+    client: RefCell<Client>,
+    /// Marks this connection's activity entries, so `data_version` only moves for
+    /// changes made elsewhere.
+    origin: String,
+}
+
+impl PostgresTodoRepo {
+    pub fn connect(url: &str) -> Result<Self> {
+        let mut client = Client::connect(url, NoTls).context("failed to connect to PostgreSQL")?;
+        client
+            .batch_execute(SCHEMA)
+            .context("failed to create the schema")?;
+        Ok(Self {
+            client: RefCell::new(client),
+            origin: Uuid::new_v4().to_string(),
+        })
+    }
+
+    fn query(
+        &self,
+        sql: &str,
+        params: &[&(dyn postgres::types::ToSql + Sync)],
+    ) -> Result<Vec<Row>> {
+        Ok(self.client.borrow_mut().query(sql, params)?)
+    }
+
+    /// Run `write` in one transaction; the second argument tags its activity entries.
+    fn write<T>(&mut self, write: impl FnOnce(&mut Transaction, &str) -> Result<T>) -> Result<T> {
+        let mut tx = self
+            .client
+            .get_mut()
+            .transaction()
+            .context("failed to begin transaction")?;
+        let value = write(&mut tx, &self.origin)?;
+        tx.commit().context("failed to commit")?;
+        Ok(value)
+    }
+}
+
+impl TodoRepository for PostgresTodoRepo {
+    fn all(&self) -> Result<Vec<Todo>> {
+        self.query(
+            &format!("SELECT {TODO_COLUMNS} FROM todos WHERE NOT archived ORDER BY created_at ASC"),
+            &[],
+        )
+        .context("failed to load todos")?
+        .iter()
+        .map(row_to_todo)
+        .collect()
+    }
+
+    fn search(&self, query: &TodoQuery) -> Result<Vec<Todo>> {
+        let (sql, words) = search_sql(query);
+        let params: Vec<&(dyn postgres::types::ToSql + Sync)> = words
+            .iter()
+            .map(|w| w as &(dyn postgres::types::ToSql + Sync))
+            .collect();
+        self.query(&sql, &params)
+            .context("failed to search todos")?
+            .iter()
+            .map(row_to_todo)
+            .collect()
+    }
+
+    fn add(
+        &mut self,
+        title: String,
+        priority: Priority,
+        due: Option<Due>,
+        external_url: Option<String>,
+        external_key: Option<String>,
+    ) -> Result<Todo> {
+        self.write(|tx, origin| {
+            let mut todo = Todo::with_meta(title.clone(), priority, due);
+            if external_key.is_some() {
+                todo.remote_title = Some(todo.title.clone());
+            }
+            todo.external_url = external_url.clone();
+            todo.external_key = external_key;
+            let inserted = tx
+                .execute(
+                    "INSERT INTO todos (id, title, status, priority, due, due_has_time, created_at, external_url, external_key, remote_title) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) ON CONFLICT (external_key) DO NOTHING",
+                    &[
+                        &todo.id.to_string(),
+                        &todo.title,
+                        &todo.status.label(),
+                        &(todo.priority as i32),
+                        &todo.due.map(to_unix),
+                        &todo.due_has_time,
+                        &to_unix(todo.created_at),
+                        &todo.external_url,
+                        &todo.external_key,
+                        &todo.remote_title,
+                    ],
+                )
+                .context("failed to insert todo")?;
+            if inserted == 0 {
+                // The key is taken: refresh that todo instead, as a sync does.
+                let key = todo.external_key.as_deref().unwrap_or_default();
+                let Some(mut existing) = fetch_todo_by_external_key(tx, key)? else {
+                    bail!("todo {key} vanished while it was being updated");
+                };
+                existing.apply_remote_title(title);
+                existing.external_url = external_url;
+                tx.execute(
+                    "UPDATE todos SET title = $1, external_url = $2, remote_title = $3, incoming_title = $4 WHERE id = $5",
+                    &[
+                        &existing.title,
+                        &existing.external_url,
+                        &existing.remote_title,
+                        &existing.incoming_title,
+                        &existing.id.to_string(),
+                    ],
+                )
+                .context("failed to update external todo")?;
+                return Ok(existing);
+            }
+            log(tx, origin, ActivityKind::Add, Some(todo.id), &todo.title)?;
+            Ok(todo)
+        })
+    }
+
+    fn update_meta(
+        &mut self,
+        id: TodoId,
+        priority: Priority,
+        due: Option<Due>,
+    ) -> Result<Option<Todo>> {
+        self.write(|tx, origin| {
+            let Some(mut todo) = fetch_todo(tx, id)? else {
+                return Ok(None);
+            };
+            todo.priority = priority;
+            todo.set_due(due);
+            tx.execute(
+                "UPDATE todos SET priority = $1, due = $2, due_has_time = $3 WHERE id = $4",
+                &[
+                    &(priority as i32),
+                    &todo.due.map(to_unix),
+                    &todo.due_has_time,
+                    &id.to_string(),
+                ],
+            )
+            .context("failed to update meta")?;
+            log(
+                tx,
+                origin,
+                ActivityKind::Edit,
+                Some(id),
+                &activity::describe_meta(priority, todo.due),
+            )?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn set_title(&mut self, id: TodoId, title: String) -> Result<Option<Todo>> {
+        self.write(|tx, origin| {
+            let Some(mut todo) = fetch_todo(tx, id)? else {
+                return Ok(None);
+            };
+            todo.title = title;
+            tx.execute(
+                "UPDATE todos SET title = $1 WHERE id = $2",
+                &[&todo.title, &id.to_string()],
+            )
+            .context("failed to update title")?;
+            log(
+                tx,
+                origin,
+                ActivityKind::Edit,
+                Some(id),
+                &format!("title: {}", todo.title),
+            )?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn resolve_title(&mut self, id: TodoId, title: String) -> Result<Option<Todo>> {
+        self.write(|tx, origin| {
+            let Some(mut todo) = fetch_todo(tx, id)? else {
+                return Ok(None);
+            };
+            if let Some(incoming) = todo.incoming_title.take() {
+                todo.remote_title = Some(incoming);
+            }
+            todo.title = title;
+            tx.execute(
+                "UPDATE todos SET title = $1, remote_title = $2, incoming_title = NULL WHERE id = $3",
+                &[&todo.title, &todo.remote_title, &id.to_string()],
+            )
+            .context("failed to resolve title")?;
+            log(
+                tx,
+                origin,
+                ActivityKind::Edit,
+                Some(id),
+                &format!("title (conflict resolved): {}", todo.title),
+            )?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn set_notes(&mut self, id: TodoId, notes: Option<String>) -> Result<Option<Todo>> {
+        self.write(|tx, origin| {
+            let Some(mut todo) = fetch_todo(tx, id)? else {
+                return Ok(None);
+            };
+            todo.notes = notes;
+            tx.execute(
+                "UPDATE todos SET notes = $1 WHERE id = $2",
+                &[&todo.notes, &id.to_string()],
+            )
+            .context("failed to update notes")?;
+            let detail = match todo.notes {
+                Some(_) => "notes updated",
+                None => "notes cleared",
+            };
+            log(tx, origin, ActivityKind::Edit, Some(id), detail)?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn set_start(&mut self, id: TodoId, start: Option<SystemTime>) -> Result<Option<Todo>> {
+        self.write(|tx, origin| {
+            let Some(mut todo) = fetch_todo(tx, id)? else {
+                return Ok(None);
+            };
+            todo.start_at = start;
+            tx.execute(
+                "UPDATE todos SET start_at = $1 WHERE id = $2",
+                &[&start.map(to_unix), &id.to_string()],
+            )
+            .context("failed to update start date")?;
+            let detail = match start {
+                Some(_) => "start date set",
+                None => "start date cleared",
+            };
+            log(tx, origin, ActivityKind::Edit, Some(id), detail)?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn set_status(&mut self, id: TodoId, status: Status) -> Result<Option<Todo>> {
+        self.write(|tx, origin| {
+            let Some(mut todo) = fetch_todo(tx, id)? else {
+                return Ok(None);
+            };
+            let closing = status.is_closed() != todo.is_closed();
+            if closing {
+                todo.completed_at = status.is_closed().then(SystemTime::now);
+            }
+            todo.status = status;
+            tx.execute(
+                "UPDATE todos SET status = $1, completed_at = $2 WHERE id = $3",
+                &[
+                    &status.label(),
+                    &todo.completed_at.map(to_unix),
+                    &id.to_string(),
+                ],
+            )
+            .context("failed to update status")?;
+            let (kind, detail) = if closing {
+                (
+                    ActivityKind::toggled(status.is_closed()),
+                    todo.title.clone(),
+                )
+            } else {
+                (ActivityKind::Edit, format!("status {}", status.label()))
+            };
+            log(tx, origin, kind, Some(id), &detail)?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn set_estimate(&mut self, id: TodoId, minutes: Option<u32>) -> Result<Option<Todo>> {
+        self.write(|tx, origin| {
+            let Some(mut todo) = fetch_todo(tx, id)? else {
+                return Ok(None);
+            };
+            todo.estimate_minutes = minutes;
+            tx.execute(
+                "UPDATE todos SET estimate_minutes = $1 WHERE id = $2",
+                &[&minutes.map(|m| m as i32), &id.to_string()],
+            )
+            .context("failed to update estimate")?;
+            let detail = match minutes {
+                Some(_) => "estimate set",
+                None => "estimate cleared",
+            };
+            log(tx, origin, ActivityKind::Edit, Some(id), detail)?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn mark_seen(
+        &mut self,
+        id: TodoId,
+        sha: Option<String>,
+        at: SystemTime,
+    ) -> Result<Option<Todo>> {
+        self.write(|tx, _| {
+            let Some(mut todo) = fetch_todo(tx, id)? else {
+                return Ok(None);
+            };
+            todo.last_seen_sha = sha;
+            todo.last_seen_at = Some(at);
+            tx.execute(
+                "UPDATE todos SET last_seen_sha = $1, last_seen_at = $2 WHERE id = $3",
+                &[&todo.last_seen_sha, &to_unix(at), &id.to_string()],
+            )
+            .context("failed to update last seen")?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn add_dependency(&mut self, id: TodoId, blocked_by: TodoId) -> Result<()> {
+        self.write(|tx, origin| {
+            // Serialise link changes, so two machines cannot close a cycle between them.
+            tx.batch_execute("LOCK TABLE todo_deps IN EXCLUSIVE MODE")?;
+            if dependency::creates_cycle(&load_dependencies(tx)?, id, blocked_by) {
+                bail!("{blocked_by} already waits for {id}; the link would make a cycle");
+            }
+            tx.execute(
+                "INSERT INTO todo_deps (todo_id, blocked_by) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+                &[&id.to_string(), &blocked_by.to_string()],
+            )
+            .context("failed to add dependency")?;
+            log(
+                tx,
+                origin,
+                ActivityKind::Edit,
+                Some(id),
+                &format!("blocked by {blocked_by}"),
+            )?;
+            Ok(())
+        })
+    }
+
+    fn clear_dependencies(&mut self, id: TodoId) -> Result<usize> {
+        self.write(|tx, origin| {
+            let removed = tx
+                .execute(
+                    "DELETE FROM todo_deps WHERE todo_id = $1",
+                    &[&id.to_string()],
+                )
+                .context("failed to clear dependencies")?;
+            if removed > 0 {
+                log(
+                    tx,
+                    origin,
+                    ActivityKind::Edit,
+                    Some(id),
+                    "no longer blocked",
+                )?;
+            }
+            Ok(removed as usize)
+        })
+    }
+
+    fn dependencies(&self) -> Result<Vec<(TodoId, TodoId)>> {
+        load_dependencies(&mut *self.client.borrow_mut())
+    }
+
+    fn set_parent(&mut self, id: TodoId, parent: Option<TodoId>) -> Result<Option<Todo>> {
+        self.write(|tx, origin| {
+            let Some(mut todo) = fetch_todo(tx, id)? else {
+                return Ok(None);
+            };
+            todo.parent_id = parent;
+            tx.execute(
+                "UPDATE todos SET parent_id = $1 WHERE id = $2",
+                &[&parent.map(|p| p.to_string()), &id.to_string()],
+            )
+            .context("failed to update parent")?;
+            let detail = match parent {
+                Some(parent) => format!("subtask of {parent}"),
+                None => "detached from its parent".to_string(),
+            };
+            log(tx, origin, ActivityKind::Edit, Some(id), &detail)?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn toggle(&mut self, id: TodoId) -> Result<Option<Todo>> {
+        self.write(|tx, origin| {
+            let Some(mut todo) = fetch_todo(tx, id)? else {
+                return Ok(None);
+            };
+            todo.status = if todo.is_closed() {
+                Status::Todo
+            } else {
+                Status::Done
+            };
+            todo.completed_at = todo.is_closed().then(SystemTime::now);
+            tx.execute(
+                "UPDATE todos SET status = $1, completed_at = $2 WHERE id = $3",
+                &[
+                    &todo.status.label(),
+                    &todo.completed_at.map(to_unix),
+                    &id.to_string(),
+                ],
+            )
+            .context("failed to update todo")?;
+            log(
+                tx,
+                origin,
+                ActivityKind::toggled(todo.is_closed()),
+                Some(id),
+                &todo.title,
+            )?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn delete(&mut self, id: TodoId) -> Result<Option<Todo>> {
+        self.write(|tx, origin| delete_todo(tx, origin, id))
+    }
+
+    fn clear_done(&mut self) -> Result<usize> {
+        self.write(|tx, origin| {
+            let done = tx
+                .query(
+                    "UPDATE todos SET archived = TRUE WHERE status IN ('done', 'cancelled') AND NOT archived RETURNING id, title",
+                    &[],
+                )
+                .context("failed to archive done")?;
+            for row in &done {
+                let id: String = row.try_get(0)?;
+                let title: String = row.try_get(1)?;
+                log(
+                    tx,
+                    origin,
+                    ActivityKind::Archive,
+                    Uuid::parse_str(&id).ok(),
+                    &title,
+                )?;
+            }
+            Ok(done.len())
+        })
+    }
+
+    fn archive(&mut self, id: TodoId) -> Result<Option<Todo>> {
+        self.write(|tx, origin| {
+            let Some(mut todo) = fetch_todo(tx, id)? else {
+                return Ok(None);
+            };
+            todo.archived = true;
+            tx.execute(
+                "UPDATE todos SET archived = TRUE WHERE id = $1",
+                &[&id.to_string()],
+            )
+            .context("failed to archive todo")?;
+            log(tx, origin, ActivityKind::Archive, Some(id), &todo.title)?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn delete_many(&mut self, ids: &[TodoId]) -> Result<Vec<Todo>> {
+        self.write(|tx, origin| {
+            let mut deleted = Vec::new();
+            for id in ids {
+                deleted.extend(delete_todo(tx, origin, *id)?);
+            }
+            Ok(deleted)
+        })
+    }
+
+    fn archive_many(&mut self, ids: &[TodoId]) -> Result<usize> {
+        self.write(|tx, origin| {
+            let mut changed = 0;
+            for id in ids {
+                let Some(todo) = fetch_todo(tx, *id)? else {
+                    continue;
+                };
+                changed += tx
+                    .execute(
+                        "UPDATE todos SET archived = TRUE WHERE id = $1",
+                        &[&id.to_string()],
+                    )
+                    .context("failed to archive todo")? as usize;
+                log(tx, origin, ActivityKind::Archive, Some(*id), &todo.title)?;
+            }
+            Ok(changed)
+        })
+    }
+
+    fn set_priority_many(&mut self, ids: &[TodoId], priority: Priority) -> Result<usize> {
+        self.write(|tx, origin| {
+            let mut changed = 0;
+            for id in ids {
+                changed += tx
+                    .execute(
+                        "UPDATE todos SET priority = $1 WHERE id = $2",
+                        &[&(priority as i32), &id.to_string()],
+                    )
+                    .context("failed to update priority")? as usize;
+                log(
+                    tx,
+                    origin,
+                    ActivityKind::Edit,
+                    Some(*id),
+                    &format!("priority {priority:?}"),
+                )?;
+            }
+            Ok(changed)
+        })
+    }
+
+    fn set_due_many(&mut self, dues: &[(TodoId, Option<Due>)]) -> Result<usize> {
+        self.write(|tx, origin| {
+            let mut changed = 0;
+            for (id, due) in dues {
+                changed += tx
+                    .execute(
+                        "UPDATE todos SET due = $1, due_has_time = $2 WHERE id = $3",
+                        &[
+                            &due.map(|d| to_unix(d.at)),
+                            &due.is_some_and(|d| d.has_time),
+                            &id.to_string(),
+                        ],
+                    )
+                    .context("failed to update due")? as usize;
+                log(
+                    tx,
+                    origin,
+                    ActivityKind::Edit,
+                    Some(*id),
+                    &format!("due {}", activity::describe_due(due.map(|d| d.at))),
+                )?;
+            }
+            Ok(changed)
+        })
+    }
+
+    fn set_title_many(&mut self, titles: &[(TodoId, String)]) -> Result<usize> {
+        self.write(|tx, origin| {
+            let mut changed = 0;
+            for (id, title) in titles {
+                changed += tx
+                    .execute(
+                        "UPDATE todos SET title = $1 WHERE id = $2",
+                        &[title, &id.to_string()],
+                    )
+                    .context("failed to update title")? as usize;
+                log(
+                    tx,
+                    origin,
+                    ActivityKind::Edit,
+                    Some(*id),
+                    &format!("title: {title}"),
+                )?;
+            }
+            Ok(changed)
+        })
+    }
+
+    fn set_sort_orders(&mut self, orders: &[(TodoId, i64)]) -> Result<usize> {
+        self.write(|tx, origin| {
+            let mut changed = 0;
+            for (id, order) in orders {
+                // Unchanged positions are skipped, so only the moved todos are logged.
+                let updated = tx
+                    .execute(
+                        "UPDATE todos SET sort_order = $1 WHERE id = $2 AND sort_order IS DISTINCT FROM $1",
+                        &[order, &id.to_string()],
+                    )
+                    .context("failed to update sort order")?;
+                if updated > 0 {
+                    log(
+                        tx,
+                        origin,
+                        ActivityKind::Edit,
+                        Some(*id),
+                        &format!("position {order}"),
+                    )?;
+                }
+                changed += updated as usize;
+            }
+            Ok(changed)
+        })
+    }
+
+    fn start_timer(&mut self, id: TodoId) -> Result<Option<TimeEntry>> {
+        self.write(|tx, _| {
+            if fetch_todo(tx, id)?.is_none() {
+                return Ok(None);
+            }
+            stop_running_timer(tx)?;
+            let entry = TimeEntry::start(id);
+            tx.execute(
+                "INSERT INTO time_entries (id, todo_id, started_at, ended_at) VALUES ($1, $2, $3, NULL)",
+                &[
+                    &entry.id.to_string(),
+                    &entry.todo_id.to_string(),
+                    &to_unix(entry.started_at),
+                ],
+            )
+            .context("failed to insert time entry")?;
+            Ok(Some(entry))
+        })
+    }
+
+    fn stop_timer(&mut self) -> Result<Option<TimeEntry>> {
+        self.write(|tx, _| stop_running_timer(tx))
+    }
+
+    fn meta(&self, key: &str) -> Result<Option<String>> {
+        let rows = self
+            .query("SELECT value FROM meta WHERE key = $1", &[&key])
+            .context("failed to load meta")?;
+        rows.first()
+            .map(|row| row.try_get(0))
+            .transpose()
+            .context("failed to load meta")
+    }
+
+    fn set_meta(&mut self, key: &str, value: &str) -> Result<()> {
+        self.client
+            .get_mut()
+            .execute(
+                "INSERT INTO meta (key, value) VALUES ($1, $2) ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+                &[&key, &value],
+            )
+            .context("failed to save meta")?;
+        Ok(())
+    }
+
+    fn replace_prs(&mut self, prs: &[Pr]) -> Result<()> {
+        self.write(|tx, _| {
+            tx.execute("DELETE FROM prs", &[])
+                .context("failed to clear cached prs")?;
+            for pr in prs {
+                let data = serde_json::to_string(pr).context("failed to encode pr")?;
+                tx.execute(
+                    "INSERT INTO prs (pr_key, data) VALUES ($1, $2)",
+                    &[&pr.pr_key, &data],
+                )
+                .context("failed to cache pr")?;
+            }
+            Ok(())
+        })
+    }
+
+    fn cached_prs(&self) -> Result<Vec<Pr>> {
+        let rows = self
+            .query("SELECT data FROM prs ORDER BY pr_key ASC", &[])
+            .context("failed to load cached prs")?;
+        // Rows written by an older, incompatible layout are skipped until the next sync.
+        let mut prs = Vec::new();
+        for row in rows {
+            let data: String = row.try_get(0).context("failed to read cached pr")?;
+            prs.extend(serde_json::from_str(&data).ok());
+        }
+        Ok(prs)
+    }
+
+    fn record_sync_stats(&mut self, synced_at: i64, stats: &[QueryStats]) -> Result<()> {
+        self.write(|tx, _| {
+            for st in stats {
+                tx.execute(
+                    "INSERT INTO sync_stats (synced_at, query, pages, nodes, kept, elapsed_ms, cost) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                    &[
+                        &synced_at,
+                        &st.query,
+                        &(st.pages as i32),
+                        &(st.nodes as i32),
+                        &(st.kept as i32),
+                        &(st.elapsed_ms as i64),
+                        &(st.cost as i32),
+                    ],
+                )
+                .context("failed to record sync stats")?;
+            }
+            Ok(())
+        })
+    }
+
+    fn time_entries(&self, since: Option<SystemTime>) -> Result<Vec<TimeEntry>> {
+        let since = since.map(to_unix).unwrap_or(i64::MIN);
+        self.query(
+            "SELECT id, todo_id, started_at, ended_at FROM time_entries WHERE ended_at IS NULL OR ended_at >= $1 ORDER BY started_at ASC",
+            &[&since],
+        )
+        .context("failed to load time entries")?
+        .iter()
+        .map(row_to_time_entry)
+        .collect()
+    }
+
+    fn log_activity(
+        &mut self,
+        kind: ActivityKind,
+        todo_id: Option<TodoId>,
+        detail: &str,
+    ) -> Result<()> {
+        self.write(|tx, origin| log(tx, origin, kind, todo_id, detail))
+    }
+
+    fn activity(&self, limit: usize) -> Result<Vec<Activity>> {
+        let rows = self
+            .query(
+                "SELECT at, kind, todo_id, detail FROM activity ORDER BY id DESC LIMIT $1",
+                &[&(limit as i64)],
+            )
+            .context("failed to load activity")?;
+        let mut entries = Vec::new();
+        for row in rows.iter().rev() {
+            let kind: String = row.try_get(1)?;
+            let todo_id: Option<String> = row.try_get(2)?;
+            entries.push(Activity {
+                at: row.try_get(0)?,
+                kind: ActivityKind::parse(&kind).unwrap_or(ActivityKind::Edit),
+                todo_id: todo_id.and_then(|id| Uuid::parse_str(&id).ok()),
+                detail: row.try_get(3)?,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// The newest activity entry written through another connection; every logged change
+    /// (including a finished sync) adds one.
+    fn data_version(&self) -> Result<Option<i64>> {
+        let rows = self
+            .query(
+                "SELECT COALESCE(MAX(id), 0) FROM activity WHERE origin IS DISTINCT FROM $1",
+                &[&self.origin],
+            )
+            .context("failed to read the data version")?;
+        Ok(rows.first().map(|row| row.try_get(0)).transpose()?)
+    }
+}
+
+/// Append one activity log entry in the caller's transaction.
+fn log(
+    tx: &mut Transaction,
+    origin: &str,
+    kind: ActivityKind,
+    todo_id: Option<TodoId>,
+    detail: &str,
+) -> Result<()> {
+    let entry = Activity::now(kind, todo_id, detail);
+    tx.execute(
+        "INSERT INTO activity (at, kind, todo_id, detail, origin) VALUES ($1, $2, $3, $4, $5)",
+        &[
+            &entry.at,
+            &entry.kind.as_str(),
+            &entry.todo_id.map(|id| id.to_string()),
+            &entry.detail,
+            &origin,
+        ],
+    )
+    .context("failed to append activity")?;
+    Ok(())
+}
+
+/// Load and lock a todo for the rest of the transaction.
+fn fetch_todo(tx: &mut Transaction, id: TodoId) -> Result<Option<Todo>> {
+    let rows = tx
+        .query(
+            &format!("SELECT {TODO_COLUMNS} FROM todos WHERE id = $1 FOR UPDATE"),
+            &[&id.to_string()],
+        )
+        .context("failed to load todo")?;
+    rows.first().map(row_to_todo).transpose()
+}
+
+fn fetch_todo_by_external_key(tx: &mut Transaction, external_key: &str) -> Result<Option<Todo>> {
+    let rows = tx
+        .query(
+            &format!("SELECT {TODO_COLUMNS} FROM todos WHERE external_key = $1 FOR UPDATE"),
+            &[&external_key],
+        )
+        .context("failed to load todo by external_key")?;
+    rows.first().map(row_to_todo).transpose()
+}
+
+/// Delete a todo with its tracked time, detach its subtasks and drop its links.
+fn delete_todo(tx: &mut Transaction, origin: &str, id: TodoId) -> Result<Option<Todo>> {
+    let Some(todo) = fetch_todo(tx, id)? else {
+        return Ok(None);
+    };
+    let id_text = id.to_string();
+    tx.execute("DELETE FROM todos WHERE id = $1", &[&id_text])
+        .context("failed to delete todo")?;
+    tx.execute(
+        "UPDATE todos SET parent_id = NULL WHERE parent_id = $1",
+        &[&id_text],
+    )
+    .context("failed to detach subtasks")?;
+    tx.execute(
+        "DELETE FROM todo_deps WHERE todo_id = $1 OR blocked_by = $1",
+        &[&id_text],
+    )
+    .context("failed to drop dependencies")?;
+    tx.execute("DELETE FROM time_entries WHERE todo_id = $1", &[&id_text])
+        .context("failed to drop time entries")?;
+    log(tx, origin, ActivityKind::Delete, Some(id), &todo.title)?;
+    Ok(Some(todo))
+}
+
+fn load_dependencies(client: &mut impl GenericClient) -> Result<Vec<(TodoId, TodoId)>> {
+    let rows = client
+        .query("SELECT todo_id, blocked_by FROM todo_deps", &[])
+        .context("failed to load dependencies")?;
+    let mut links = Vec::new();
+    for row in rows {
+        let (todo, blocker): (String, String) = (row.try_get(0)?, row.try_get(1)?);
+        if let (Ok(todo), Ok(blocker)) = (Uuid::parse_str(&todo), Uuid::parse_str(&blocker)) {
+            links.push((todo, blocker));
+        }
+    }
+    Ok(links)
+}
+
+/// End the running timer session, if any.
+fn stop_running_timer(tx: &mut Transaction) -> Result<Option<TimeEntry>> {
+    let rows = tx
+        .query(
+            "SELECT id, todo_id, started_at, ended_at FROM time_entries WHERE ended_at IS NULL FOR UPDATE",
+            &[],
+        )
+        .context("failed to load running time entry")?;
+    let Some(mut entry) = rows.first().map(row_to_time_entry).transpose()? else {
+        return Ok(None);
+    };
+    let now = SystemTime::now();
+    tx.execute(
+        "UPDATE time_entries SET ended_at = $1 WHERE ended_at IS NULL",
+        &[&to_unix(now)],
+    )
+    .context("failed to stop time entry")?;
+    entry.ended_at = Some(now);
+    Ok(Some(entry))
+}
+
+/// Query for `search` and its `ILIKE` patterns, one per word: every word has to appear in
+/// the title or the notes.
+fn search_sql(query: &TodoQuery) -> (String, Vec<String>) {
+    let scope = match query.scope {
+        SearchScope::Open => "status NOT IN ('done', 'cancelled') AND NOT archived",
+        SearchScope::All => "TRUE",
+        SearchScope::Archived => "archived",
+    };
+    let words: Vec<String> = query
+        .text
+        .split_whitespace()
+        .map(|word| format!("%{}%", escape_like(word)))
+        .collect();
+    let mut sql = format!("SELECT {TODO_COLUMNS} FROM todos WHERE {scope}");
+    for n in 1..=words.len() {
+        sql.push_str(&format!(
+            " AND (title ILIKE ${n} OR COALESCE(notes, '') ILIKE ${n})"
+        ));
+    }
+    sql.push_str(" ORDER BY created_at ASC");
+    (sql, words)
+}
+
+fn row_to_todo(row: &Row) -> Result<Todo> {
+    let id: String = row.try_get("id")?;
+    let status: String = row.try_get("status")?;
+    let priority: i32 = row.try_get("priority")?;
+    let uuid = |text: Option<String>| text.and_then(|t| Uuid::parse_str(&t).ok());
+    let time = |name: &str| -> Result<Option<SystemTime>> {
+        Ok(row.try_get::<_, Option<i64>>(name)?.map(from_unix))
+    };
+    Ok(Todo {
+        id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::nil()),
+        title: row.try_get("title")?,
+        status: Status::from_label(&status),
+        archived: row.try_get("archived")?,
+        priority: Priority::from_level(priority as u8),
+        due: time("due")?,
+        due_has_time: row.try_get("due_has_time")?,
+        created_at: from_unix(row.try_get("created_at")?),
+        external_url: row.try_get("external_url")?,
+        external_key: row.try_get("external_key")?,
+        notes: row.try_get("notes")?,
+        parent_id: uuid(row.try_get("parent_id")?),
+        remote_title: row.try_get("remote_title")?,
+        incoming_title: row.try_get("incoming_title")?,
+        sort_order: row.try_get("sort_order")?,
+        completed_at: time("completed_at")?,
+        last_seen_sha: row.try_get("last_seen_sha")?,
+        last_seen_at: time("last_seen_at")?,
+        start_at: time("start_at")?,
+        estimate_minutes: row
+            .try_get::<_, Option<i32>>("estimate_minutes")?
+            .map(|m| m as u32),
+    })
+}
+
+fn row_to_time_entry(row: &Row) -> Result<TimeEntry> {
+    let id: String = row.try_get("id")?;
+    let todo_id: String = row.try_get("todo_id")?;
+    Ok(TimeEntry {
+        id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::nil()),
+        todo_id: Uuid::parse_str(&todo_id).unwrap_or_else(|_| Uuid::nil()),
+        started_at: from_unix(row.try_get("started_at")?),
+        ended_at: row.try_get::<_, Option<i64>>("ended_at")?.map(from_unix),
+    })
+}
+
+fn escape_like(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn to_unix(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+fn from_unix(secs: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_needs_every_word_taken_literally() {
+        let (sql, words) = search_sql(&TodoQuery {
+            text: "50% off_line".to_string(),
+            scope: SearchScope::Open,
+        });
+        assert_eq!(words, ["%50\\%%", "%off\\_line%"]);
+        assert!(sql.ends_with(
+            "WHERE status NOT IN ('done', 'cancelled') AND NOT archived \
+             AND (title ILIKE $1 OR COALESCE(notes, '') ILIKE $1) \
+             AND (title ILIKE $2 OR COALESCE(notes, '') ILIKE $2) ORDER BY created_at ASC"
+        ));
+
+        let (sql, words) = search_sql(&TodoQuery {
+            text: "  ".to_string(),
+            scope: SearchScope::Archived,
+        });
+        assert!(words.is_empty());
+        assert!(sql.ends_with("WHERE archived ORDER BY created_at ASC"));
+    }
+
+    /// Runs in a scratch schema of the database at `KOTO_TEST_POSTGRES_URL`, e.g.
+    /// `postgres://postgres@localhost/postgres`; skipped when that is unset.
+    #[test]
+    fn behaves_like_the_sqlite_store() {
+        let Ok(url) = std::env::var("KOTO_TEST_POSTGRES_URL") else {
+            return;
+        };
+        let schema = format!("koto_test_{}", Uuid::new_v4().simple());
+        let mut admin = Client::connect(&url, NoTls).unwrap();
+        admin
+            .batch_execute(&format!("CREATE SCHEMA {schema}"))
+            .unwrap();
+        let sep = if url.contains('?') { '&' } else { '?' };
+        let scratch = format!("{url}{sep}options=-c%20search_path%3D{schema}");
+        let outcome = std::panic::catch_unwind(|| round_trip(&scratch));
+        admin
+            .batch_execute(&format!("DROP SCHEMA {schema} CASCADE"))
+            .unwrap();
+        if let Err(panic) = outcome {
+            std::panic::resume_unwind(panic);
+        }
+    }
+
+    fn round_trip(url: &str) {
+        let mut repo = PostgresTodoRepo::connect(url).unwrap();
+        let other = PostgresTodoRepo::connect(url).unwrap();
+        let version = other.data_version().unwrap();
+
+        let day = Due::day(time::macros::date!(2026 - 03 - 10));
+        let timed = Due::timed(UNIX_EPOCH + Duration::from_secs(1_773_135_000));
+        let report = repo
+            .add(
+                "Write report".to_string(),
+                Priority::High,
+                Some(day),
+                None,
+                None,
+            )
+            .unwrap();
+        let deploy = repo
+            .add(
+                "Deploy 50% rollout".to_string(),
+                Priority::Low,
+                Some(timed),
+                None,
+                None,
+            )
+            .unwrap();
+        assert_ne!(other.data_version().unwrap(), version);
+        assert_eq!(repo.data_version().unwrap(), Some(0));
+
+        let all = repo.all().unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].due_value(), Some(day));
+        assert_eq!(all[1].due_value(), Some(timed));
+        assert_eq!(all[1].priority, Priority::Low);
+
+        let search = |repo: &PostgresTodoRepo, text: &str, scope| {
+            repo.search(&TodoQuery {
+                text: text.to_string(),
+                scope,
+            })
+            .unwrap()
+            .into_iter()
+            .map(|t| t.id)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(search(&repo, "50%", SearchScope::Open), [deploy.id]);
+        assert_eq!(
+            search(&repo, "REPORT write", SearchScope::Open),
+            [report.id]
+        );
+        assert!(search(&repo, "0%r", SearchScope::Open).is_empty());
+
+        let done = repo.toggle(report.id).unwrap().unwrap();
+        assert_eq!(done.status, Status::Done);
+        assert!(done.completed_at.is_some());
+        assert!(search(&repo, "report", SearchScope::Open).is_empty());
+        assert_eq!(search(&repo, "report", SearchScope::All), [report.id]);
+
+        assert_eq!(repo.set_due_many(&[(deploy.id, Some(day))]).unwrap(), 1);
+        assert_eq!(fetch(&repo, deploy.id).due_value(), Some(day));
+
+        // A synced todo is upserted on its key instead of added twice.
+        let key = Some("github:acme/api#7".to_string());
+        let pr = repo
+            .add(
+                "Fix login".to_string(),
+                Priority::Medium,
+                None,
+                None,
+                key.clone(),
+            )
+            .unwrap();
+        let again = repo
+            .add(
+                "Fix login flow".to_string(),
+                Priority::Medium,
+                None,
+                None,
+                key,
+            )
+            .unwrap();
+        assert_eq!(again.id, pr.id);
+        assert_eq!(fetch(&repo, pr.id).title, "Fix login flow");
+
+        repo.start_timer(deploy.id).unwrap();
+        let entry = repo.stop_timer().unwrap().unwrap();
+        assert_eq!(entry.todo_id, deploy.id);
+        assert_eq!(repo.time_entries(None).unwrap().len(), 1);
+
+        repo.set_meta("k", "v").unwrap();
+        assert_eq!(repo.meta("k").unwrap().as_deref(), Some("v"));
+        assert_eq!(repo.delete(deploy.id).unwrap().unwrap().id, deploy.id);
+        assert_eq!(repo.all().unwrap().len(), 2);
+    }
+
+    fn fetch(repo: &PostgresTodoRepo, id: TodoId) -> Todo {
+        repo.all()
+            .unwrap()
+            .into_iter()
+            .find(|t| t.id == id)
+            .unwrap()
+    }
+}
@@ -0,0 +1,133 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::todo::TodoId;
+use crate::repo::github::model::ReviewEvent;
+
+/// Typed-but-unsent work in the TUI, the part of the session a crash would lose.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Draft {
+    /// `a` / `A`: a new todo (title and notes lines), optionally a subtask of `parent`.
+    NewTodo {
+        text: String,
+        parent: Option<TodoId>,
+    },
+    /// `t`: a due date being typed for `todo_id`.
+    Due { todo_id: TodoId, text: String },
+    /// `e` (or the conflict chooser's merge): a new title for `todo_id`.
+    Rename {
+        todo_id: TodoId,
+        resolving: bool,
+        text: String,
+    },
+    /// `#`: a tag for the marked todos.
+    Tag { marked: Vec<TodoId>, text: String },
+    /// `R`: a PR review not submitted yet.
+    Review {
+        todo_id: TodoId,
+        pr_key: String,
+        event: ReviewEvent,
+        body: String,
+    },
+}
+
+impl Draft {
+    /// Short description for the restore prompt.
+    pub fn summary(&self) -> String {
+        let (what, text) = match self {
+            Draft::NewTodo { text, .. } => ("new task", text),
+            Draft::Due { text, .. } => ("due date", text),
+            Draft::Rename { text, .. } => ("rename", text),
+            Draft::Tag { text, .. } => ("tag", text),
+            Draft::Review { body, .. } => ("review", body),
+        };
+        let first_line = text.lines().next().unwrap_or_default();
+        let preview: String = first_line.chars().take(60).collect();
+        let more = if preview.len() < text.len() {
+            "…"
+        } else {
+            ""
+        };
+        format!("{what}: \"{preview}{more}\"")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    /// Unix seconds.
+    pub saved_at: i64,
+    pub draft: Draft,
+}
+
+/// `<db>.session.json`: the latest draft, rewritten while one exists and removed on a
+/// clean exit, so a file found at startup means the last session ended abruptly.
+#[derive(Debug, Clone)]
+pub struct SessionFile {
+    path: PathBuf,
+}
+
+impl SessionFile {
+    /// Snapshot file belonging to the SQLite store at `db_path`.
+    pub fn for_db(db_path: &Path) -> Self {
+        Self {
+            path: db_path.with_extension("session.json"),
+        }
+    }
+
+    /// Replace the snapshot; written to a temp file first so a crash mid-write keeps
+    /// the previous one.
+    pub fn save(&self, draft: &Draft) -> Result<()> {
+        let snapshot = SessionSnapshot {
+            saved_at: crate::now_unix(),
+            draft: draft.clone(),
+        };
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec(&snapshot)?)
+            .with_context(|| format!("failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.path)
+            .with_context(|| format!("failed to replace {}", self.path.display()))
+    }
+
+    /// Snapshot left by the previous session; unreadable files count as none.
+    pub fn load(&self) -> Option<SessionSnapshot> {
+        let raw = std::fs::read(&self.path).ok()?;
+        serde_json::from_slice(&raw).ok()
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("failed to remove {}", self.path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_survives_until_cleared() {
+        let dir = tempfile::tempdir().unwrap();
+        let session = SessionFile::for_db(&dir.path().join("todos.sqlite"));
+        assert!(session.load().is_none());
+
+        let draft = Draft::NewTodo {
+            text: "buy milk p:1\nfrom the corner shop".to_string(),
+            parent: None,
+        };
+        session.save(&draft).unwrap();
+        let restored = session.load().unwrap();
+        assert_eq!(restored.draft, draft);
+        assert_eq!(restored.draft.summary(), "new task: \"buy milk p:1…\"");
+
+        session.clear().unwrap();
+        session.clear().unwrap();
+        assert!(session.load().is_none());
+    }
+}
@@ -1,15 +1,42 @@
 use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use anyhow::{Context, Result};
-use rusqlite::{Connection, OptionalExtension, Row, params};
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use rusqlite::{
+    Connection, ErrorCode, OpenFlags, OptionalExtension, Row, TransactionBehavior, params,
+};
 use uuid::Uuid;
 
-use super::TodoRepository;
-use crate::domain::todo::{Priority, Todo, TodoId};
+use super::{StoreBusy, TodoRepository};
+use crate::domain::activity::{self, Activity, ActivityKind};
+use crate::domain::dependency;
+use crate::domain::due::Due;
+use crate::domain::query::{SearchScope, TodoQuery};
+use crate::domain::time_entry::TimeEntry;
+use crate::domain::todo::{Priority, Status, Todo, TodoId};
+use crate::repo::backup::Backups;
+use crate::repo::crypto::Cipher;
+use crate::repo::github::model::{Pr, QueryStats};
+use crate::repo::migrations;
+
+const TODO_COLUMNS: &str = "id, title, status, archived, priority, due, due_has_time, created_at, external_url, external_key, notes, parent_id, remote_title, incoming_title, sort_order, completed_at, last_seen_sha, last_seen_at, start_at, estimate_minutes";
+
+/// How long a statement waits for another process (TUI, CLI, daemon) to release the
+/// store before failing with `SQLITE_BUSY`; the only wait, so it bounds how long a key
+/// press can stall the TUI.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Salt of an encrypted store (base64); its presence marks the store as encrypted.
+const META_CRYPTO_SALT: &str = "crypto.salt";
+/// `CRYPTO_CHECK` sealed with the store's key, to reject a wrong passphrase up front.
+const META_CRYPTO_CHECK: &str = "crypto.check";
+const CRYPTO_CHECK: &str = "koto";
 
 pub struct SqliteTodoRepo {
     conn: Connection,
+    codec: Codec,
 }
 
 impl SqliteTodoRepo {
@@ -20,159 +47,1101 @@ impl SqliteTodoRepo {
 
     pub fn open(path: impl AsRef<Path>) -> Result<Self> {
         let path = path.as_ref();
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)
-                .with_context(|| format!("failed to create db dir {}", parent.display()))?;
+        let conn = open_connection(path, false)?;
+        if encryption_salt(&conn)?.is_some() {
+            bail!("{} is encrypted; start koto with --encrypt", path.display());
+        }
+        init_search_index(&conn)?;
+        Ok(Self {
+            conn,
+            codec: Codec::default(),
+        })
+    }
+
+    /// Open a store whose titles, notes, activity details, PR cache and settings are
+    /// encrypted with `passphrase`. A plaintext store is encrypted in place the first
+    /// time; the search index is dropped, since it would keep the text in the clear, and
+    /// the file is rewritten so no old page still holds plaintext.
+    pub fn open_encrypted(path: impl AsRef<Path>, passphrase: &str) -> Result<Self> {
+        let path = path.as_ref();
+        let mut conn = open_connection(path, true)?;
+        // Freed pages are zeroed, so what a later write replaces is gone from the file.
+        conn.execute_batch("PRAGMA secure_delete = ON;")
+            .context("failed to enable secure_delete")?;
+        let mut rewrite = search_index_exists(&conn)?;
+        let cipher = match encryption_salt(&conn)? {
+            Some(salt) => {
+                let cipher = Cipher::derive(passphrase, &salt);
+                let check: Option<String> = conn
+                    .query_row(
+                        "SELECT value FROM meta WHERE key = ?1",
+                        params![META_CRYPTO_CHECK],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                if check.and_then(|c| cipher.open(&c).ok()).as_deref() != Some(CRYPTO_CHECK) {
+                    bail!("wrong passphrase for {}", path.display());
+                }
+                cipher
+            }
+            None => {
+                let salt = Cipher::new_salt()?;
+                let cipher = Cipher::derive(passphrase, &salt);
+                encrypt_in_place(&mut conn, &cipher, &salt)?;
+                rewrite = true;
+                cipher
+            }
+        };
+        conn.execute_batch(
+            r#"
+DROP TRIGGER IF EXISTS todos_fts_insert;
+DROP TRIGGER IF EXISTS todos_fts_update;
+DROP TRIGGER IF EXISTS todos_fts_delete;
+DROP TABLE IF EXISTS todos_fts;
+"#,
+        )
+        .context("failed to drop the search index")?;
+        if rewrite {
+            // VACUUM copies only live rows into a fresh file; the checkpoint then empties
+            // the WAL, which still holds the pages as they were.
+            conn.execute_batch("VACUUM; PRAGMA wal_checkpoint(TRUNCATE);")
+                .context("failed to rewrite the store after encrypting it")?;
+        }
+        Ok(Self {
+            conn,
+            codec: Codec(Some(cipher)),
+        })
+    }
+
+    /// Whether the store at `path` was encrypted by `open_encrypted` (false when it
+    /// does not exist yet). Only reads: the store is not migrated (or backed up).
+    pub fn is_encrypted(path: impl AsRef<Path>) -> Result<bool> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(false);
         }
-        let conn = Connection::open(path)
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
             .with_context(|| format!("failed to open db {}", path.display()))?;
-        init_schema(&conn)?;
-        Ok(Self { conn })
+        // A store too old to have `meta` was never encrypted.
+        Ok(encryption_salt(&conn).ok().flatten().is_some())
+    }
+
+    /// Run `write` in one immediate transaction: it takes the write lock up front, so what
+    /// it reads cannot change under it, and its changes and their activity entries land
+    /// together or not at all. A store still busy after [`BUSY_TIMEOUT`] fails with
+    /// [`StoreBusy`].
+    fn write<T>(&mut self, write: impl FnOnce(&Connection, &Codec) -> Result<T>) -> Result<T> {
+        let tx = match self
+            .conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+        {
+            Ok(tx) => tx,
+            Err(e) if is_busy(&e) => return Err(e).context(StoreBusy),
+            Err(e) => return Err(e).context("failed to begin transaction"),
+        };
+        let value = write(&tx, &self.codec)?;
+        tx.commit().context("failed to commit")?;
+        Ok(value)
+    }
+}
+
+fn is_busy(e: &rusqlite::Error) -> bool {
+    e.sqlite_error_code()
+        .is_some_and(|code| matches!(code, ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked))
+}
+
+/// Seals the text columns of an encrypted store (titles, notes, activity details) on the
+/// way in and opens them on the way out; passes text through for a plaintext store.
+#[derive(Clone, Default)]
+struct Codec(Option<Cipher>);
+
+impl Codec {
+    fn is_encrypted(&self) -> bool {
+        self.0.is_some()
+    }
+
+    fn seal(&self, text: &str) -> Result<String> {
+        match &self.0 {
+            Some(cipher) => cipher.seal(text),
+            None => Ok(text.to_string()),
+        }
+    }
+
+    fn seal_opt(&self, text: Option<&str>) -> Result<Option<String>> {
+        text.map(|t| self.seal(t)).transpose()
+    }
+
+    fn reveal(&self, text: String) -> Result<String> {
+        match &self.0 {
+            Some(cipher) => cipher.open(&text).context("failed to decrypt"),
+            None => Ok(text),
+        }
+    }
+
+    fn reveal_opt(&self, text: Option<String>) -> Result<Option<String>> {
+        text.map(|t| self.reveal(t)).transpose()
+    }
+
+    fn reveal_todo(&self, mut todo: Todo) -> Result<Todo> {
+        if self.is_encrypted() {
+            todo.title = self.reveal(todo.title)?;
+            todo.notes = self.reveal_opt(todo.notes)?;
+            todo.remote_title = self.reveal_opt(todo.remote_title)?;
+            todo.incoming_title = self.reveal_opt(todo.incoming_title)?;
+        }
+        Ok(todo)
+    }
+}
+
+impl SqliteTodoRepo {
+    /// Todos in `scope` matching an FTS5 query, ranked with title hits weighted above
+    /// notes.
+    fn fts_search(&self, scope: &str, fts: &str) -> Result<Vec<Todo>> {
+        let sql = format!(
+            "SELECT {TODO_COLUMNS} FROM todos JOIN (SELECT id AS hit, bm25(todos_fts, 0.0, 10.0, 1.0) AS rank FROM todos_fts WHERE todos_fts MATCH ?1) ON hit = id WHERE {scope} ORDER BY rank"
+        );
+        let mut stmt = self
+            .conn
+            .prepare(&sql)
+            .context("failed to prepare full-text search")?;
+        stmt.query_map(params![fts], row_to_todo)
+            .context("failed to run full-text search")?
+            .map(|r| r.context("failed to decode todo"))
+            .collect()
     }
 }
 
 impl TodoRepository for SqliteTodoRepo {
-    fn all(&self) -> Vec<Todo> {
+    fn all(&self) -> Result<Vec<Todo>> {
         let mut stmt = self
             .conn
-            .prepare(
-                "SELECT id, title, done, priority, due, created_at, external_url, external_key FROM todos ORDER BY created_at ASC",
-            )
-            .expect("failed to prepare select");
+            .prepare(&format!(
+                "SELECT {TODO_COLUMNS} FROM todos WHERE archived = 0 ORDER BY created_at ASC"
+            ))
+            .context("failed to prepare select")?;
         let iter = stmt
             .query_map([], row_to_todo)
-            .expect("failed to iterate todos");
-        iter.map(|r| r.expect("failed to decode todo")).collect()
+            .context("failed to iterate todos")?;
+        iter.map(|r| self.codec.reveal_todo(r.context("failed to decode todo")?))
+            .collect()
+    }
+
+    fn search(&self, query: &TodoQuery) -> Result<Vec<Todo>> {
+        let scope = match query.scope {
+            SearchScope::Open => "status NOT IN ('done', 'cancelled') AND archived = 0",
+            SearchScope::All => "1 = 1",
+            SearchScope::Archived => "archived = 1",
+        };
+        if self.codec.is_encrypted() {
+            // Neither the index nor LIKE can see sealed text: match after decrypting.
+            let sql =
+                format!("SELECT {TODO_COLUMNS} FROM todos WHERE {scope} ORDER BY created_at ASC");
+            let mut stmt = self
+                .conn
+                .prepare(&sql)
+                .context("failed to prepare search")?;
+            let iter = stmt
+                .query_map([], row_to_todo)
+                .context("failed to iterate search results")?;
+            let mut todos = Vec::new();
+            for row in iter {
+                let todo = self
+                    .codec
+                    .reveal_todo(row.context("failed to decode todo")?)?;
+                if query.matches_text(&todo.title, todo.notes.as_deref()) {
+                    todos.push(todo);
+                }
+            }
+            return Ok(todos);
+        }
+        // Full-text hits first, best match first; then substring matches the word
+        // index misses (`ploy` in "deploy"), oldest first.
+        let mut todos = match fts_query(&query.text) {
+            Some(fts) => self.fts_search(scope, &fts)?,
+            None => Vec::new(),
+        };
+        let words: Vec<String> = query
+            .text
+            .split_whitespace()
+            .map(|word| format!("%{}%", escape_like(word)))
+            .collect();
+        let mut sql = format!("SELECT {TODO_COLUMNS} FROM todos WHERE {scope}");
+        for n in 1..=words.len() {
+            sql.push_str(&format!(
+                " AND (title LIKE ?{n} ESCAPE '\\' OR notes LIKE ?{n} ESCAPE '\\')"
+            ));
+        }
+        sql.push_str(" ORDER BY created_at ASC");
+        let mut stmt = self
+            .conn
+            .prepare(&sql)
+            .context("failed to prepare search")?;
+        let iter = stmt
+            .query_map(rusqlite::params_from_iter(&words), row_to_todo)
+            .context("failed to iterate search results")?;
+        for row in iter {
+            let todo = row.context("failed to decode todo")?;
+            if todos.iter().all(|t| t.id != todo.id) {
+                todos.push(todo);
+            }
+        }
+        Ok(todos)
     }
 
     fn add(
         &mut self,
         title: String,
         priority: Priority,
-        due: Option<std::time::SystemTime>,
+        due: Option<Due>,
         external_url: Option<String>,
         external_key: Option<String>,
-    ) -> Todo {
-        if let Some(ref key) = external_key
-            && let Some(mut existing) = fetch_todo_by_external_key(&self.conn, key)
-        {
-            self.conn
-                .execute(
-                    "UPDATE todos SET title = ?1, external_url = ?2 WHERE id = ?3",
-                    params![title, external_url, existing.id.to_string()],
+    ) -> Result<Todo> {
+        self.write(|tx, codec| {
+            if let Some(ref key) = external_key
+                && let Some(mut existing) = fetch_todo_by_external_key(tx, codec, key)?
+            {
+                existing.apply_remote_title(title);
+                existing.external_url = external_url;
+                tx.execute(
+                    "UPDATE todos SET title = ?1, external_url = ?2, remote_title = ?3, incoming_title = ?4 WHERE id = ?5",
+                    params![
+                        codec.seal(&existing.title)?,
+                        existing.external_url,
+                        codec.seal_opt(existing.remote_title.as_deref())?,
+                        codec.seal_opt(existing.incoming_title.as_deref())?,
+                        existing.id.to_string()
+                    ],
                 )
-                .expect("failed to update external todo");
-            existing.title = title;
-            existing.external_url = external_url;
-            return existing;
-        }
+                .context("failed to update external todo")?;
+                return Ok(existing);
+            }
 
-        let mut todo = Todo::with_meta(title, priority, due);
-        todo.external_url = external_url;
-        todo.external_key = external_key;
-        self.conn
-            .execute(
-                "INSERT INTO todos (id, title, done, priority, due, created_at, external_url, external_key) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            let mut todo = Todo::with_meta(title, priority, due);
+            if external_key.is_some() {
+                todo.remote_title = Some(todo.title.clone());
+            }
+            todo.external_url = external_url;
+            todo.external_key = external_key;
+            tx.execute(
+                "INSERT INTO todos (id, title, status, priority, due, due_has_time, created_at, external_url, external_key, remote_title) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
                 params![
                     todo.id.to_string(),
-                    &todo.title,
-                    todo.done as i32,
+                    codec.seal(&todo.title)?,
+                    todo.status.label(),
                     todo.priority as i32,
                     todo.due.map(to_unix),
+                    todo.due_has_time,
                     to_unix(todo.created_at),
                     todo.external_url,
-                    todo.external_key
+                    todo.external_key,
+                    codec.seal_opt(todo.remote_title.as_deref())?
                 ],
             )
-            .expect("failed to insert todo");
-        todo
+            .context("failed to insert todo")?;
+            log(tx, codec, ActivityKind::Add, Some(todo.id), &todo.title)?;
+            Ok(todo)
+        })
     }
 
     fn update_meta(
         &mut self,
         id: TodoId,
         priority: Priority,
-        due: Option<std::time::SystemTime>,
-    ) -> Option<Todo> {
-        let mut todo = fetch_todo(&self.conn, id)?;
-        todo.priority = priority;
-        todo.due = due;
-        self.conn
-            .execute(
-                "UPDATE todos SET priority = ?1, due = ?2 WHERE id = ?3",
-                params![priority as i32, todo.due.map(to_unix), todo.id.to_string()],
+        due: Option<Due>,
+    ) -> Result<Option<Todo>> {
+        self.write(|tx, codec| {
+            let Some(mut todo) = fetch_todo(tx, codec, id)? else {
+                return Ok(None);
+            };
+            todo.priority = priority;
+            todo.set_due(due);
+            tx.execute(
+                "UPDATE todos SET priority = ?1, due = ?2, due_has_time = ?3 WHERE id = ?4",
+                params![
+                    priority as i32,
+                    todo.due.map(to_unix),
+                    todo.due_has_time,
+                    todo.id.to_string()
+                ],
+            )
+            .context("failed to update meta")?;
+            log(
+                tx,
+                codec,
+                ActivityKind::Edit,
+                Some(id),
+                &activity::describe_meta(priority, todo.due),
+            )?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn set_title(&mut self, id: TodoId, title: String) -> Result<Option<Todo>> {
+        self.write(|tx, codec| {
+            let Some(mut todo) = fetch_todo(tx, codec, id)? else {
+                return Ok(None);
+            };
+            todo.title = title;
+            tx.execute(
+                "UPDATE todos SET title = ?1 WHERE id = ?2",
+                params![codec.seal(&todo.title)?, todo.id.to_string()],
+            )
+            .context("failed to update title")?;
+            log(
+                tx,
+                codec,
+                ActivityKind::Edit,
+                Some(id),
+                &format!("title: {}", todo.title),
+            )?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn resolve_title(&mut self, id: TodoId, title: String) -> Result<Option<Todo>> {
+        self.write(|tx, codec| {
+            let Some(mut todo) = fetch_todo(tx, codec, id)? else {
+                return Ok(None);
+            };
+            if let Some(incoming) = todo.incoming_title.take() {
+                todo.remote_title = Some(incoming);
+            }
+            todo.title = title;
+            tx.execute(
+                "UPDATE todos SET title = ?1, remote_title = ?2, incoming_title = NULL WHERE id = ?3",
+                params![
+                    codec.seal(&todo.title)?,
+                    codec.seal_opt(todo.remote_title.as_deref())?,
+                    todo.id.to_string()
+                ],
+            )
+            .context("failed to resolve title")?;
+            log(
+                tx,
+                codec,
+                ActivityKind::Edit,
+                Some(id),
+                &format!("title (conflict resolved): {}", todo.title),
+            )?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn set_notes(&mut self, id: TodoId, notes: Option<String>) -> Result<Option<Todo>> {
+        self.write(|tx, codec| {
+            let Some(mut todo) = fetch_todo(tx, codec, id)? else {
+                return Ok(None);
+            };
+            todo.notes = notes;
+            tx.execute(
+                "UPDATE todos SET notes = ?1 WHERE id = ?2",
+                params![codec.seal_opt(todo.notes.as_deref())?, todo.id.to_string()],
+            )
+            .context("failed to update notes")?;
+            let detail = match todo.notes {
+                Some(_) => "notes updated",
+                None => "notes cleared",
+            };
+            log(tx, codec, ActivityKind::Edit, Some(id), detail)?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn set_start(&mut self, id: TodoId, start: Option<SystemTime>) -> Result<Option<Todo>> {
+        self.write(|tx, codec| {
+            let Some(mut todo) = fetch_todo(tx, codec, id)? else {
+                return Ok(None);
+            };
+            todo.start_at = start;
+            tx.execute(
+                "UPDATE todos SET start_at = ?1 WHERE id = ?2",
+                params![todo.start_at.map(to_unix), todo.id.to_string()],
+            )
+            .context("failed to update start date")?;
+            let detail = match todo.start_at {
+                Some(_) => "start date set",
+                None => "start date cleared",
+            };
+            log(tx, codec, ActivityKind::Edit, Some(id), detail)?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn set_status(&mut self, id: TodoId, status: Status) -> Result<Option<Todo>> {
+        self.write(|tx, codec| {
+            let Some(mut todo) = fetch_todo(tx, codec, id)? else {
+                return Ok(None);
+            };
+            let closing = status.is_closed() != todo.is_closed();
+            if closing {
+                todo.completed_at = status.is_closed().then(SystemTime::now);
+            }
+            todo.status = status;
+            tx.execute(
+                "UPDATE todos SET status = ?1, completed_at = ?2 WHERE id = ?3",
+                params![
+                    status.label(),
+                    todo.completed_at.map(to_unix),
+                    todo.id.to_string()
+                ],
+            )
+            .context("failed to update status")?;
+            let (kind, detail) = if closing {
+                (
+                    ActivityKind::toggled(status.is_closed()),
+                    todo.title.clone(),
+                )
+            } else {
+                (ActivityKind::Edit, format!("status {}", status.label()))
+            };
+            log(tx, codec, kind, Some(id), &detail)?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn set_estimate(&mut self, id: TodoId, minutes: Option<u32>) -> Result<Option<Todo>> {
+        self.write(|tx, codec| {
+            let Some(mut todo) = fetch_todo(tx, codec, id)? else {
+                return Ok(None);
+            };
+            todo.estimate_minutes = minutes;
+            tx.execute(
+                "UPDATE todos SET estimate_minutes = ?1 WHERE id = ?2",
+                params![todo.estimate_minutes, todo.id.to_string()],
+            )
+            .context("failed to update estimate")?;
+            let detail = match todo.estimate_minutes {
+                Some(_) => "estimate set",
+                None => "estimate cleared",
+            };
+            log(tx, codec, ActivityKind::Edit, Some(id), detail)?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn mark_seen(
+        &mut self,
+        id: TodoId,
+        sha: Option<String>,
+        at: SystemTime,
+    ) -> Result<Option<Todo>> {
+        self.write(|tx, codec| {
+            let Some(mut todo) = fetch_todo(tx, codec, id)? else {
+                return Ok(None);
+            };
+            todo.last_seen_sha = sha;
+            todo.last_seen_at = Some(at);
+            tx.execute(
+                "UPDATE todos SET last_seen_sha = ?1, last_seen_at = ?2 WHERE id = ?3",
+                params![todo.last_seen_sha, to_unix(at), todo.id.to_string()],
+            )
+            .context("failed to update last seen")?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn add_dependency(&mut self, id: TodoId, blocked_by: TodoId) -> Result<()> {
+        self.write(|tx, codec| {
+            if dependency::creates_cycle(&load_dependencies(tx)?, id, blocked_by) {
+                bail!("{blocked_by} already waits for {id}; the link would make a cycle");
+            }
+            tx.execute(
+                "INSERT OR IGNORE INTO todo_deps (todo_id, blocked_by) VALUES (?1, ?2)",
+                params![id.to_string(), blocked_by.to_string()],
+            )
+            .context("failed to add dependency")?;
+            log(
+                tx,
+                codec,
+                ActivityKind::Edit,
+                Some(id),
+                &format!("blocked by {blocked_by}"),
+            )?;
+            Ok(())
+        })
+    }
+
+    fn clear_dependencies(&mut self, id: TodoId) -> Result<usize> {
+        self.write(|tx, codec| {
+            let removed = tx
+                .execute(
+                    "DELETE FROM todo_deps WHERE todo_id = ?1",
+                    params![id.to_string()],
+                )
+                .context("failed to clear dependencies")?;
+            if removed > 0 {
+                log(tx, codec, ActivityKind::Edit, Some(id), "no longer blocked")?;
+            }
+            Ok(removed)
+        })
+    }
+
+    fn dependencies(&self) -> Result<Vec<(TodoId, TodoId)>> {
+        load_dependencies(&self.conn)
+    }
+
+    fn set_parent(&mut self, id: TodoId, parent: Option<TodoId>) -> Result<Option<Todo>> {
+        self.write(|tx, codec| {
+            let Some(mut todo) = fetch_todo(tx, codec, id)? else {
+                return Ok(None);
+            };
+            todo.parent_id = parent;
+            tx.execute(
+                "UPDATE todos SET parent_id = ?1 WHERE id = ?2",
+                params![parent.map(|p| p.to_string()), todo.id.to_string()],
+            )
+            .context("failed to update parent")?;
+            let detail = match parent {
+                Some(parent) => format!("subtask of {parent}"),
+                None => "detached from its parent".to_string(),
+            };
+            log(tx, codec, ActivityKind::Edit, Some(id), &detail)?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn toggle(&mut self, id: TodoId) -> Result<Option<Todo>> {
+        self.write(|tx, codec| {
+            let Some(mut todo) = fetch_todo(tx, codec, id)? else {
+                return Ok(None);
+            };
+            todo.status = if todo.is_closed() {
+                Status::Todo
+            } else {
+                Status::Done
+            };
+            todo.completed_at = todo.is_closed().then(SystemTime::now);
+            tx.execute(
+                "UPDATE todos SET status = ?1, completed_at = ?2 WHERE id = ?3",
+                params![
+                    todo.status.label(),
+                    todo.completed_at.map(to_unix),
+                    todo.id.to_string()
+                ],
+            )
+            .context("failed to update todo")?;
+            log(
+                tx,
+                codec,
+                ActivityKind::toggled(todo.is_closed()),
+                Some(id),
+                &todo.title,
+            )?;
+            Ok(Some(todo))
+        })
+    }
+
+    fn delete(&mut self, id: TodoId) -> Result<Option<Todo>> {
+        self.write(|tx, codec| delete_todo(tx, codec, id))
+    }
+
+    fn clear_done(&mut self) -> Result<usize> {
+        self.write(|tx, codec| {
+            let done: Vec<(String, String)> = {
+                let mut stmt = tx
+                    .prepare(
+                        "SELECT id, title FROM todos WHERE status IN ('done', 'cancelled') AND archived = 0",
+                    )
+                    .context("failed to prepare done select")?;
+                stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                    .context("failed to iterate done todos")?
+                    .collect::<rusqlite::Result<_>>()
+                    .context("failed to decode done todo")?
+            };
+            tx.execute(
+                "UPDATE todos SET archived = 1 WHERE status IN ('done', 'cancelled') AND archived = 0",
+                [],
+            )
+            .context("failed to archive done")?;
+            for (id, title) in done.iter().cloned() {
+                let title = codec.reveal(title)?;
+                log(
+                    tx,
+                    codec,
+                    ActivityKind::Archive,
+                    Uuid::parse_str(&id).ok(),
+                    &title,
+                )?;
+            }
+            Ok(done.len())
+        })
+    }
+
+    fn archive(&mut self, id: TodoId) -> Result<Option<Todo>> {
+        self.write(|tx, codec| {
+            let Some(mut todo) = fetch_todo(tx, codec, id)? else {
+                return Ok(None);
+            };
+            todo.archived = true;
+            tx.execute(
+                "UPDATE todos SET archived = 1 WHERE id = ?1",
+                params![id.to_string()],
             )
-            .expect("failed to update meta");
-        Some(todo)
+            .context("failed to archive todo")?;
+            log(tx, codec, ActivityKind::Archive, Some(id), &todo.title)?;
+            Ok(Some(todo))
+        })
     }
 
-    fn toggle(&mut self, id: TodoId) -> Option<Todo> {
-        let mut todo = fetch_todo(&self.conn, id)?;
-        todo.done = !todo.done;
+    fn delete_many(&mut self, ids: &[TodoId]) -> Result<Vec<Todo>> {
+        self.write(|tx, codec| {
+            let mut deleted = Vec::new();
+            for id in ids {
+                deleted.extend(delete_todo(tx, codec, *id)?);
+            }
+            Ok(deleted)
+        })
+    }
+
+    fn archive_many(&mut self, ids: &[TodoId]) -> Result<usize> {
+        self.write(|tx, codec| {
+            let mut changed = 0;
+            for id in ids {
+                let Some(todo) = fetch_todo(tx, codec, *id)? else {
+                    continue;
+                };
+                changed += tx
+                    .execute(
+                        "UPDATE todos SET archived = 1 WHERE id = ?1",
+                        params![id.to_string()],
+                    )
+                    .context("failed to archive todo")?;
+                log(tx, codec, ActivityKind::Archive, Some(*id), &todo.title)?;
+            }
+            Ok(changed)
+        })
+    }
+
+    fn set_priority_many(&mut self, ids: &[TodoId], priority: Priority) -> Result<usize> {
+        self.write(|tx, codec| {
+            let mut changed = 0;
+            for id in ids {
+                changed += tx
+                    .execute(
+                        "UPDATE todos SET priority = ?1 WHERE id = ?2",
+                        params![priority as i32, id.to_string()],
+                    )
+                    .context("failed to update priority")?;
+                log(
+                    tx,
+                    codec,
+                    ActivityKind::Edit,
+                    Some(*id),
+                    &format!("priority {priority:?}"),
+                )?;
+            }
+            Ok(changed)
+        })
+    }
+
+    fn set_due_many(&mut self, dues: &[(TodoId, Option<Due>)]) -> Result<usize> {
+        self.write(|tx, codec| {
+            let mut changed = 0;
+            for (id, due) in dues {
+                changed += tx
+                    .execute(
+                        "UPDATE todos SET due = ?1, due_has_time = ?2 WHERE id = ?3",
+                        params![
+                            due.map(|d| to_unix(d.at)),
+                            due.is_some_and(|d| d.has_time),
+                            id.to_string()
+                        ],
+                    )
+                    .context("failed to update due")?;
+                log(
+                    tx,
+                    codec,
+                    ActivityKind::Edit,
+                    Some(*id),
+                    &format!("due {}", activity::describe_due(due.map(|d| d.at))),
+                )?;
+            }
+            Ok(changed)
+        })
+    }
+
+    fn set_title_many(&mut self, titles: &[(TodoId, String)]) -> Result<usize> {
+        self.write(|tx, codec| {
+            let mut changed = 0;
+            for (id, title) in titles {
+                changed += tx
+                    .execute(
+                        "UPDATE todos SET title = ?1 WHERE id = ?2",
+                        params![codec.seal(title)?, id.to_string()],
+                    )
+                    .context("failed to update title")?;
+                log(
+                    tx,
+                    codec,
+                    ActivityKind::Edit,
+                    Some(*id),
+                    &format!("title: {title}"),
+                )?;
+            }
+            Ok(changed)
+        })
+    }
+
+    fn set_sort_orders(&mut self, orders: &[(TodoId, i64)]) -> Result<usize> {
+        self.write(|tx, codec| {
+            let mut changed = 0;
+            for (id, order) in orders {
+                // Unchanged positions are skipped, so only the moved todos are logged.
+                let updated = tx
+                    .execute(
+                        "UPDATE todos SET sort_order = ?1 WHERE id = ?2 AND sort_order IS NOT ?1",
+                        params![order, id.to_string()],
+                    )
+                    .context("failed to update sort order")?;
+                if updated > 0 {
+                    log(
+                        tx,
+                        codec,
+                        ActivityKind::Edit,
+                        Some(*id),
+                        &format!("position {order}"),
+                    )?;
+                }
+                changed += updated;
+            }
+            Ok(changed)
+        })
+    }
+
+    fn meta(&self, key: &str) -> Result<Option<String>> {
+        let value = self
+            .conn
+            .query_row(
+                "SELECT value FROM meta WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("failed to load meta")?;
+        self.codec.reveal_opt(value)
+    }
+
+    fn set_meta(&mut self, key: &str, value: &str) -> Result<()> {
         self.conn
             .execute(
-                "UPDATE todos SET done = ?1 WHERE id = ?2",
-                params![todo.done as i32, todo.id.to_string()],
+                "INSERT INTO meta (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                params![key, self.codec.seal(value)?],
             )
-            .expect("failed to update todo");
-        Some(todo)
+            .context("failed to save meta")?;
+        Ok(())
     }
 
-    fn delete(&mut self, id: TodoId) -> Option<Todo> {
-        let todo = fetch_todo(&self.conn, id)?;
-        self.conn
-            .execute("DELETE FROM todos WHERE id = ?1", params![id.to_string()])
-            .expect("failed to delete todo");
-        Some(todo)
+    fn replace_prs(&mut self, prs: &[Pr]) -> Result<()> {
+        self.write(|tx, codec| {
+            tx.execute("DELETE FROM prs", [])
+                .context("failed to clear cached prs")?;
+            for pr in prs {
+                let data = serde_json::to_string(pr).context("failed to encode pr")?;
+                tx.execute(
+                    "INSERT INTO prs (pr_key, data) VALUES (?1, ?2)",
+                    params![pr.pr_key, codec.seal(&data)?],
+                )
+                .context("failed to cache pr")?;
+            }
+            Ok(())
+        })
     }
 
-    fn clear_done(&mut self) -> usize {
-        self.conn
-            .execute("DELETE FROM todos WHERE done = 1", [])
-            .expect("failed to clear done")
+    fn record_sync_stats(&mut self, synced_at: i64, stats: &[QueryStats]) -> Result<()> {
+        self.write(|tx, _| {
+            for st in stats {
+                tx.execute(
+                    "INSERT INTO sync_stats (synced_at, query, pages, nodes, kept, elapsed_ms, cost) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        synced_at,
+                        st.query,
+                        st.pages,
+                        st.nodes,
+                        st.kept,
+                        st.elapsed_ms as i64,
+                        st.cost
+                    ],
+                )
+                .context("failed to record sync stats")?;
+            }
+            Ok(())
+        })
+    }
+
+    fn cached_prs(&self) -> Result<Vec<Pr>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT data FROM prs ORDER BY pr_key ASC")
+            .context("failed to prepare pr select")?;
+        let iter = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .context("failed to iterate cached prs")?;
+        // Rows written by an older, incompatible layout are skipped until the next sync.
+        let mut prs = Vec::new();
+        for row in iter {
+            let data = self
+                .codec
+                .reveal(row.context("failed to read cached pr")?)?;
+            prs.extend(serde_json::from_str(&data).ok());
+        }
+        Ok(prs)
+    }
+
+    fn start_timer(&mut self, id: TodoId) -> Result<Option<TimeEntry>> {
+        self.write(|tx, codec| {
+            if fetch_todo(tx, codec, id)?.is_none() {
+                return Ok(None);
+            }
+            stop_running_timer(tx)?;
+            let entry = TimeEntry::start(id);
+            tx.execute(
+                "INSERT INTO time_entries (id, todo_id, started_at, ended_at) VALUES (?1, ?2, ?3, NULL)",
+                params![
+                    entry.id.to_string(),
+                    entry.todo_id.to_string(),
+                    to_unix(entry.started_at)
+                ],
+            )
+            .context("failed to insert time entry")?;
+            Ok(Some(entry))
+        })
+    }
+
+    fn stop_timer(&mut self) -> Result<Option<TimeEntry>> {
+        self.write(|tx, _| stop_running_timer(tx))
+    }
+
+    fn time_entries(&self, since: Option<std::time::SystemTime>) -> Result<Vec<TimeEntry>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, todo_id, started_at, ended_at FROM time_entries WHERE ended_at IS NULL OR ended_at >= ?1 ORDER BY started_at ASC",
+            )
+            .context("failed to prepare time entry select")?;
+        let since = since.map(to_unix).unwrap_or(i64::MIN);
+        let iter = stmt
+            .query_map(params![since], row_to_time_entry)
+            .context("failed to iterate time entries")?;
+        iter.collect::<rusqlite::Result<_>>()
+            .context("failed to decode time entry")
+    }
+
+    fn log_activity(
+        &mut self,
+        kind: ActivityKind,
+        todo_id: Option<TodoId>,
+        detail: &str,
+    ) -> Result<()> {
+        log(&self.conn, &self.codec, kind, todo_id, detail)
+    }
+
+    fn activity(&self, limit: usize) -> Result<Vec<Activity>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT at, kind, todo_id, detail FROM activity ORDER BY id DESC LIMIT ?1")
+            .context("failed to prepare activity select")?;
+        let iter = stmt
+            .query_map(params![limit as i64], |row| {
+                let kind: String = row.get(1)?;
+                let todo_id: Option<String> = row.get(2)?;
+                Ok(Activity {
+                    at: row.get(0)?,
+                    kind: ActivityKind::parse(&kind).unwrap_or(ActivityKind::Edit),
+                    todo_id: todo_id.and_then(|id| Uuid::parse_str(&id).ok()),
+                    detail: row.get(3)?,
+                })
+            })
+            .context("failed to iterate activity")?;
+        let mut entries = Vec::new();
+        for row in iter {
+            let mut entry = row.context("failed to decode activity")?;
+            entry.detail = self.codec.reveal(entry.detail)?;
+            entries.push(entry);
+        }
+        entries.reverse();
+        Ok(entries)
+    }
+
+    fn data_version(&self) -> Result<Option<i64>> {
+        let version = self
+            .conn
+            .pragma_query_value(None, "data_version", |row| row.get(0))
+            .context("failed to read the data version")?;
+        Ok(Some(version))
     }
 }
 
-fn init_schema(conn: &Connection) -> Result<()> {
-    conn.execute_batch(
-        r#"
-PRAGMA journal_mode=WAL;
-CREATE TABLE IF NOT EXISTS todos (
-  id TEXT PRIMARY KEY,
-  title TEXT NOT NULL,
-  done INTEGER NOT NULL DEFAULT 0,
-  priority INTEGER NOT NULL DEFAULT 2,
-  due INTEGER NULL,
-  created_at INTEGER NOT NULL,
-  external_url TEXT NULL,
-  external_key TEXT NULL
-);
-"#,
+fn load_dependencies(conn: &Connection) -> Result<Vec<(TodoId, TodoId)>> {
+    let mut stmt = conn.prepare("SELECT todo_id, blocked_by FROM todo_deps")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    let mut links = Vec::new();
+    for row in rows {
+        let (todo, blocker) = row?;
+        if let (Ok(todo), Ok(blocker)) = (Uuid::parse_str(&todo), Uuid::parse_str(&blocker)) {
+            links.push((todo, blocker));
+        }
+    }
+    Ok(links)
+}
+
+/// End the running timer session, if any.
+fn stop_running_timer(conn: &Connection) -> Result<Option<TimeEntry>> {
+    let Some(mut entry) = conn
+        .query_row(
+            "SELECT id, todo_id, started_at, ended_at FROM time_entries WHERE ended_at IS NULL",
+            [],
+            row_to_time_entry,
+        )
+        .optional()
+        .context("failed to load running time entry")?
+    else {
+        return Ok(None);
+    };
+    let now = SystemTime::now();
+    conn.execute(
+        "UPDATE time_entries SET ended_at = ?1 WHERE ended_at IS NULL",
+        params![to_unix(now)],
     )
-    .context("failed to initialize schema")?;
-
-    ensure_column(
-        conn,
-        "priority",
-        "ALTER TABLE todos ADD COLUMN priority INTEGER NOT NULL DEFAULT 2",
-    )?;
-    ensure_column(conn, "due", "ALTER TABLE todos ADD COLUMN due INTEGER NULL")?;
-    ensure_column(
-        conn,
-        "external_url",
-        "ALTER TABLE todos ADD COLUMN external_url TEXT NULL",
-    )?;
-    ensure_column(
-        conn,
-        "external_key",
-        "ALTER TABLE todos ADD COLUMN external_key TEXT NULL",
-    )?;
+    .context("failed to stop time entry")?;
+    entry.ended_at = Some(now);
+    Ok(Some(entry))
+}
 
+/// Append one activity log entry; runs on the caller's connection or transaction, so it
+/// lands together with the change it describes.
+fn log(
+    conn: &Connection,
+    codec: &Codec,
+    kind: ActivityKind,
+    todo_id: Option<TodoId>,
+    detail: &str,
+) -> Result<()> {
+    let entry = Activity::now(kind, todo_id, codec.seal(detail)?);
     conn.execute(
-        "CREATE UNIQUE INDEX IF NOT EXISTS idx_todos_external_key ON todos(external_key)",
+        "INSERT INTO activity (at, kind, todo_id, detail) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            entry.at,
+            entry.kind.as_str(),
+            entry.todo_id.map(|id| id.to_string()),
+            entry.detail
+        ],
+    )
+    .context("failed to append activity")?;
+    Ok(())
+}
+
+/// Open, configure and migrate the store at `path`. `encrypted`: it is opened with a
+/// passphrase, so a plaintext store is not copied before migrating (the copy would keep
+/// what the encryption is about to seal).
+fn open_connection(path: &Path, encrypted: bool) -> Result<Connection> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create db dir {}", parent.display()))?;
+    }
+    let conn =
+        Connection::open(path).with_context(|| format!("failed to open db {}", path.display()))?;
+    conn.busy_timeout(BUSY_TIMEOUT)
+        .context("failed to set the busy timeout")?;
+    conn.execute_batch("PRAGMA journal_mode=WAL;")
+        .context("failed to enable WAL")?;
+    if migrations::pending(&conn)?
+        && (!encrypted || encryption_salt(&conn).ok().flatten().is_some())
+    {
+        Backups::for_db(path)
+            .before_migration(&conn)
+            .context("failed to back up the store before migrating it")?;
+    }
+    migrations::migrate(&conn)?;
+    Ok(conn)
+}
+
+fn encryption_salt(conn: &Connection) -> Result<Option<Vec<u8>>> {
+    let salt: Option<String> = conn
+        .query_row(
+            "SELECT value FROM meta WHERE key = ?1",
+            params![META_CRYPTO_SALT],
+            |row| row.get(0),
+        )
+        .optional()?;
+    salt.map(|s| STANDARD.decode(s).context("corrupt encryption salt"))
+        .transpose()
+}
+
+/// Seal the text columns of a plaintext store and mark it encrypted, in one transaction.
+fn encrypt_in_place(conn: &mut Connection, cipher: &Cipher, salt: &[u8]) -> Result<()> {
+    let tx = conn.transaction()?;
+    for (table, key, column) in [
+        ("todos", "id", "title"),
+        ("todos", "id", "notes"),
+        ("todos", "id", "remote_title"),
+        ("todos", "id", "incoming_title"),
+        ("activity", "id", "detail"),
+        ("prs", "pr_key", "data"),
+        ("meta", "key", "value"),
+    ] {
+        let rows: Vec<(rusqlite::types::Value, String)> = tx
+            .prepare(&format!(
+                "SELECT {key}, {column} FROM {table} WHERE {column} IS NOT NULL"
+            ))?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        for (id, text) in rows {
+            tx.execute(
+                &format!("UPDATE {table} SET {column} = ?1 WHERE {key} = ?2"),
+                params![cipher.seal(&text)?, id],
+            )?;
+        }
+    }
+    for (key, value) in [
+        (META_CRYPTO_SALT, STANDARD.encode(salt)),
+        (META_CRYPTO_CHECK, cipher.seal(CRYPTO_CHECK)?),
+    ] {
+        tx.execute(
+            "INSERT INTO meta (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+    }
+    tx.commit().context("failed to encrypt the store")
+}
+
+/// `todos_fts`: an FTS5 index of titles and notes, kept current by triggers. It holds
+/// its own copy keyed by `id`, since the rowids of `todos` may change on VACUUM.
+fn search_index_exists(conn: &Connection) -> Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE name = 'todos_fts')",
         [],
+        |row| row.get(0),
+    )
+    .context("failed to look up the search index")
+}
+
+fn init_search_index(conn: &Connection) -> Result<()> {
+    let exists = search_index_exists(conn)?;
+    conn.execute_batch(
+        r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS todos_fts USING fts5(id UNINDEXED, title, notes);
+CREATE TRIGGER IF NOT EXISTS todos_fts_insert AFTER INSERT ON todos BEGIN
+  INSERT INTO todos_fts (id, title, notes) VALUES (new.id, new.title, new.notes);
+END;
+CREATE TRIGGER IF NOT EXISTS todos_fts_update AFTER UPDATE OF title, notes ON todos BEGIN
+  UPDATE todos_fts SET title = new.title, notes = new.notes WHERE id = old.id;
+END;
+CREATE TRIGGER IF NOT EXISTS todos_fts_delete AFTER DELETE ON todos BEGIN
+  DELETE FROM todos_fts WHERE id = old.id;
+END;
+"#,
     )
-    .context("failed to create external key index")?;
+    .context("failed to create the search index")?;
+    if !exists {
+        // Stores from before the index existed.
+        conn.execute(
+            "INSERT INTO todos_fts (id, title, notes) SELECT id, title, notes FROM todos",
+            [],
+        )
+        .context("failed to fill the search index")?;
+    }
     Ok(())
 }
 
@@ -183,36 +1152,134 @@ fn row_to_todo(row: &Row) -> rusqlite::Result<Todo> {
     Ok(Todo {
         id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::nil()),
         title: row.get("title")?,
-        done: row.get::<_, i32>("done")? != 0,
+        status: Status::from_label(&row.get::<_, String>("status")?),
+        archived: row.get::<_, i32>("archived").unwrap_or(0) != 0,
         priority: Priority::from_level(priority_val as u8),
         due: row
             .get::<_, Option<i64>>("due")
             .unwrap_or(None)
             .map(from_unix),
+        due_has_time: row.get::<_, i32>("due_has_time").unwrap_or(0) != 0,
         created_at: from_unix(created_at),
         external_url: row.get::<_, Option<String>>("external_url").unwrap_or(None),
         external_key: row.get::<_, Option<String>>("external_key").unwrap_or(None),
+        notes: row.get::<_, Option<String>>("notes").unwrap_or(None),
+        parent_id: row
+            .get::<_, Option<String>>("parent_id")
+            .unwrap_or(None)
+            .and_then(|p| Uuid::parse_str(&p).ok()),
+        remote_title: row.get::<_, Option<String>>("remote_title").unwrap_or(None),
+        incoming_title: row
+            .get::<_, Option<String>>("incoming_title")
+            .unwrap_or(None),
+        sort_order: row.get::<_, Option<i64>>("sort_order").unwrap_or(None),
+        completed_at: row
+            .get::<_, Option<i64>>("completed_at")
+            .unwrap_or(None)
+            .map(from_unix),
+        last_seen_sha: row
+            .get::<_, Option<String>>("last_seen_sha")
+            .unwrap_or(None),
+        last_seen_at: row
+            .get::<_, Option<i64>>("last_seen_at")
+            .unwrap_or(None)
+            .map(from_unix),
+        start_at: row
+            .get::<_, Option<i64>>("start_at")
+            .unwrap_or(None)
+            .map(from_unix),
+        estimate_minutes: row
+            .get::<_, Option<u32>>("estimate_minutes")
+            .unwrap_or(None),
+    })
+}
+
+fn row_to_time_entry(row: &Row) -> rusqlite::Result<TimeEntry> {
+    let id: String = row.get("id")?;
+    let todo_id: String = row.get("todo_id")?;
+    Ok(TimeEntry {
+        id: Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::nil()),
+        todo_id: Uuid::parse_str(&todo_id).unwrap_or_else(|_| Uuid::nil()),
+        started_at: from_unix(row.get("started_at")?),
+        ended_at: row.get::<_, Option<i64>>("ended_at")?.map(from_unix),
     })
 }
 
-fn fetch_todo(conn: &Connection, id: TodoId) -> Option<Todo> {
+fn fetch_todo(conn: &Connection, codec: &Codec, id: TodoId) -> Result<Option<Todo>> {
     conn.query_row(
-        "SELECT id, title, done, priority, due, created_at, external_url, external_key FROM todos WHERE id = ?1",
+        &format!("SELECT {TODO_COLUMNS} FROM todos WHERE id = ?1"),
         params![id.to_string()],
         row_to_todo,
     )
     .optional()
-    .expect("failed to load todo")
+    .context("failed to load todo")?
+    .map(|todo| codec.reveal_todo(todo))
+    .transpose()
+}
+
+/// Delete a todo with its tracked time, detach its subtasks and drop its links.
+fn delete_todo(tx: &Connection, codec: &Codec, id: TodoId) -> Result<Option<Todo>> {
+    let Some(todo) = fetch_todo(tx, codec, id)? else {
+        return Ok(None);
+    };
+    let id_text = id.to_string();
+    tx.execute("DELETE FROM todos WHERE id = ?1", params![id_text])
+        .context("failed to delete todo")?;
+    tx.execute(
+        "UPDATE todos SET parent_id = NULL WHERE parent_id = ?1",
+        params![id_text],
+    )
+    .context("failed to detach subtasks")?;
+    tx.execute(
+        "DELETE FROM todo_deps WHERE todo_id = ?1 OR blocked_by = ?1",
+        params![id_text],
+    )
+    .context("failed to drop dependencies")?;
+    tx.execute(
+        "DELETE FROM time_entries WHERE todo_id = ?1",
+        params![id_text],
+    )
+    .context("failed to drop time entries")?;
+    log(tx, codec, ActivityKind::Delete, Some(id), &todo.title)?;
+    Ok(Some(todo))
 }
 
-fn fetch_todo_by_external_key(conn: &Connection, external_key: &str) -> Option<Todo> {
+fn fetch_todo_by_external_key(
+    conn: &Connection,
+    codec: &Codec,
+    external_key: &str,
+) -> Result<Option<Todo>> {
     conn.query_row(
-        "SELECT id, title, done, priority, due, created_at, external_url, external_key FROM todos WHERE external_key = ?1",
+        &format!("SELECT {TODO_COLUMNS} FROM todos WHERE external_key = ?1"),
         params![external_key],
         row_to_todo,
     )
     .optional()
-    .expect("failed to load todo by external_key")
+    .context("failed to load todo by external_key")?
+    .map(|todo| codec.reveal_todo(todo))
+    .transpose()
+}
+
+/// Every word of `text` as a quoted prefix term (`"deplo"*`), so user input never reaches
+/// FTS5 as query syntax. `None` when no word contains a letter or digit.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+fn escape_like(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
 }
 
 fn to_unix(time: SystemTime) -> i64 {
@@ -225,39 +1292,480 @@ fn from_unix(secs: i64) -> SystemTime {
     UNIX_EPOCH + Duration::from_secs(secs as u64)
 }
 
-fn default_db_path() -> Result<PathBuf> {
+pub fn default_db_path() -> Result<PathBuf> {
     let base = dirs::data_dir().context("failed to resolve data dir")?;
     Ok(base.join("koto").join("todos.sqlite"))
 }
 
-fn ensure_column(conn: &Connection, name: &str, alter_sql: &str) -> Result<()> {
-    let mut stmt = conn.prepare("PRAGMA table_info(todos)")?;
-    let cols = stmt
-        .query_map([], |row| row.get::<_, String>(1))?
-        .collect::<rusqlite::Result<Vec<_>>>()?;
-    if !cols.iter().any(|c| c == name) {
-        conn.execute(alter_sql, [])
-            .context("failed to add column")?;
-    }
-    Ok(())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn encrypted_store_seals_text_and_needs_the_passphrase() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let mut plain = SqliteTodoRepo::open(tmp.path()).unwrap();
+        let todo = plain
+            .add(
+                "Salary review".to_string(),
+                Priority::High,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        plain
+            .set_notes(todo.id, Some("bands for 2025".to_string()))
+            .unwrap();
+        drop(plain);
+
+        let mut repo = SqliteTodoRepo::open_encrypted(tmp.path(), "hunter2").unwrap();
+        repo.add("Layoff list".to_string(), Priority::Low, None, None, None)
+            .unwrap();
+        let raw: Vec<String> = repo
+            .conn
+            .prepare("SELECT title || coalesce(notes, '') FROM todos UNION ALL SELECT detail FROM activity")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert!(
+            raw.iter()
+                .all(|text| !text.contains("Salary") && !text.contains("Layoff"))
+        );
+        let hits = repo
+            .search(&TodoQuery {
+                text: "salary bands".to_string(),
+                scope: SearchScope::All,
+            })
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].notes.as_deref(), Some("bands for 2025"));
+        assert_eq!(
+            repo.activity(10).unwrap().last().unwrap().detail,
+            "Layoff list"
+        );
+        drop(repo);
+
+        assert!(SqliteTodoRepo::is_encrypted(tmp.path()).unwrap());
+        assert!(SqliteTodoRepo::open(tmp.path()).is_err());
+        assert!(SqliteTodoRepo::open_encrypted(tmp.path(), "hunter3").is_err());
+        let repo = SqliteTodoRepo::open_encrypted(tmp.path(), "hunter2").unwrap();
+        assert_eq!(repo.all().unwrap()[0].title, "Salary review");
+    }
+
+    #[test]
+    fn encrypted_store_leaves_no_plaintext_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todos.sqlite");
+        let mut plain = SqliteTodoRepo::open(&path).unwrap();
+        let gone = plain
+            .add(
+                "Merger codename".to_string(),
+                Priority::High,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        plain.delete(gone.id).unwrap();
+        plain
+            .add(
+                "Merger timeline".to_string(),
+                Priority::Low,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        plain.set_meta("sync.note", "Merger notes").unwrap();
+        let pr: Pr = serde_json::from_value(serde_json::json!({
+            "pr_key": "acme/api#1", "owner": "acme", "repo": "api", "number": 1,
+            "author": "octo", "title": "Merger data room", "url": "https://github.com/acme/api/pull/1",
+            "updated_at_unix": 0, "ci_state": "None", "ci_checks": [], "review_state": "Requested",
+            "is_draft": false, "is_viewer_author": false,
+        }))
+        .unwrap();
+        plain.replace_prs(&[pr]).unwrap();
+        drop(plain);
+
+        let mut repo = SqliteTodoRepo::open_encrypted(&path, "hunter2").unwrap();
+        let todo = repo
+            .add(
+                "Merger press release".to_string(),
+                Priority::Low,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        repo.set_title(todo.id, "Merger announcement".to_string())
+            .unwrap();
+        assert_eq!(repo.cached_prs().unwrap()[0].title, "Merger data room");
+        assert_eq!(
+            repo.meta("sync.note").unwrap().as_deref(),
+            Some("Merger notes")
+        );
+        drop(repo);
+
+        let mut files = vec![dir.path().to_path_buf()];
+        while let Some(file) = files.pop() {
+            if file.is_dir() {
+                files.extend(std::fs::read_dir(&file).unwrap().map(|e| e.unwrap().path()));
+                continue;
+            }
+            let bytes = std::fs::read(&file).unwrap();
+            assert!(
+                !bytes.windows(6).any(|w| w == b"Merger"),
+                "plaintext in {}",
+                file.display()
+            );
+        }
+    }
+
+    #[test]
+    fn a_locked_store_fails_once_with_store_busy() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let mut repo = SqliteTodoRepo::open(tmp.path()).unwrap();
+        let other = Connection::open(tmp.path()).unwrap();
+        other.execute_batch("BEGIN IMMEDIATE").unwrap();
+
+        let started = std::time::Instant::now();
+        let err = repo
+            .add("blocked".to_string(), Priority::Low, None, None, None)
+            .unwrap_err();
+        assert!(err.downcast_ref::<StoreBusy>().is_some());
+        assert!(started.elapsed() < BUSY_TIMEOUT + Duration::from_secs(1));
+
+        other.execute_batch("COMMIT").unwrap();
+        repo.add("unblocked".to_string(), Priority::Low, None, None, None)
+            .unwrap();
+    }
+
+    #[test]
+    fn store_failures_are_errors_not_panics() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let mut repo = SqliteTodoRepo::open(tmp.path()).unwrap();
+        let todo = repo
+            .add("kept".to_string(), Priority::Low, None, None, None)
+            .unwrap();
+        repo.conn.execute_batch("DROP TABLE activity").unwrap();
+
+        let err = repo.toggle(todo.id).unwrap_err();
+        assert!(format!("{err:#}").contains("failed to append activity"));
+        assert!(repo.activity(10).is_err());
+        assert_eq!(repo.all().unwrap().len(), 1);
+
+        repo.conn.execute_batch("DROP TABLE todos_fts").unwrap();
+        let query = TodoQuery {
+            text: "kept".to_string(),
+            scope: SearchScope::All,
+        };
+        let err = repo.search(&query).unwrap_err();
+        assert!(format!("{err:#}").contains("full-text search"));
+    }
+
     #[test]
     fn sqlite_repo_round_trip() {
         let tmp = tempfile::NamedTempFile::new().unwrap();
         let mut repo = SqliteTodoRepo::open(tmp.path()).unwrap();
 
-        let todo = repo.add("hello".to_string(), Priority::Medium, None, None, None);
-        assert_eq!(repo.all().len(), 1);
+        let todo = repo
+            .add("hello".to_string(), Priority::Medium, None, None, None)
+            .unwrap();
+        assert_eq!(repo.all().unwrap().len(), 1);
+
+        let toggled = repo.toggle(todo.id).unwrap().unwrap();
+        assert_eq!(toggled.status, Status::Done);
+
+        let dropped = repo
+            .add("dropped".to_string(), Priority::Low, None, None, None)
+            .unwrap();
+        let started = repo
+            .add("started".to_string(), Priority::Low, None, None, None)
+            .unwrap();
+        let cancelled = repo
+            .set_status(dropped.id, Status::Cancelled)
+            .unwrap()
+            .unwrap();
+        assert!(cancelled.completed_at.is_some());
+        repo.set_status(started.id, Status::InProgress).unwrap();
+
+        assert_eq!(repo.clear_done().unwrap(), 2);
+        let left = repo.all().unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].status, Status::InProgress);
+        assert_eq!(left[0].completed_at, None);
+
+        let archived = TodoQuery {
+            text: "HEL".to_string(),
+            scope: SearchScope::Archived,
+        };
+        assert_eq!(repo.search(&archived).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn sqlite_repo_keeps_multiline_notes() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let mut repo = SqliteTodoRepo::open(tmp.path()).unwrap();
+        let todo = repo
+            .add("hello".to_string(), Priority::Medium, None, None, None)
+            .unwrap();
+
+        repo.set_notes(todo.id, Some("line 1\nline 2".to_string()))
+            .unwrap();
+        drop(repo);
+        let repo = SqliteTodoRepo::open(tmp.path()).unwrap();
+        assert_eq!(
+            repo.all().unwrap()[0].notes.as_deref(),
+            Some("line 1\nline 2")
+        );
+    }
+
+    #[test]
+    fn sqlite_repo_records_completion_time() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let mut repo = SqliteTodoRepo::open(tmp.path()).unwrap();
+        let todo = repo
+            .add("hello".to_string(), Priority::Medium, None, None, None)
+            .unwrap();
+        assert_eq!(todo.completed_at, None);
+
+        let before = SystemTime::now() - Duration::from_secs(1);
+        let done = repo.toggle(todo.id).unwrap().unwrap();
+        assert!(done.completed_at.is_some_and(|at| at >= before));
+        assert_eq!(
+            repo.all().unwrap()[0].completed_at.map(to_unix),
+            done.completed_at.map(to_unix)
+        );
+
+        assert_eq!(repo.toggle(todo.id).unwrap().unwrap().completed_at, None);
+        assert_eq!(repo.all().unwrap()[0].completed_at, None);
+    }
+
+    #[test]
+    fn data_version_moves_on_writes_from_other_connections_only() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let mut tui = SqliteTodoRepo::open(tmp.path()).unwrap();
+        let mut cli = SqliteTodoRepo::open(tmp.path()).unwrap();
+        let before = tui.data_version().unwrap();
+        tui.add("mine".to_string(), Priority::Medium, None, None, None)
+            .unwrap();
+        assert_eq!(tui.data_version().unwrap(), before);
+        cli.add("captured".to_string(), Priority::Medium, None, None, None)
+            .unwrap();
+        assert_ne!(tui.data_version().unwrap(), before);
+    }
+
+    #[test]
+    fn writes_wait_for_another_process_to_finish() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let mut repo = SqliteTodoRepo::open(tmp.path()).unwrap();
+        let todo = repo
+            .add("shared".to_string(), Priority::Medium, None, None, None)
+            .unwrap();
+
+        let other = Connection::open(tmp.path()).unwrap();
+        other.execute_batch("BEGIN IMMEDIATE").unwrap();
+        let (locked, wait) = std::sync::mpsc::channel();
+        let holder = std::thread::spawn(move || {
+            locked.send(()).unwrap();
+            std::thread::sleep(Duration::from_millis(300));
+            other.execute("UPDATE todos SET priority = 1", []).unwrap();
+            other.execute_batch("COMMIT").unwrap();
+        });
+        wait.recv().unwrap();
+        let toggled = repo.toggle(todo.id).unwrap().unwrap();
+        holder.join().unwrap();
+
+        assert_eq!(toggled.priority, Priority::High);
+        assert_eq!(repo.all().unwrap()[0].status, Status::Done);
+    }
+
+    #[test]
+    fn sqlite_repo_logs_every_mutation() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let mut repo = SqliteTodoRepo::open(tmp.path()).unwrap();
+        let a = repo
+            .add("write docs".to_string(), Priority::Low, None, None, None)
+            .unwrap();
+        let b = repo
+            .add("fix bug".to_string(), Priority::Low, None, None, None)
+            .unwrap();
+        repo.set_priority_many(&[a.id, b.id], Priority::High)
+            .unwrap();
+        repo.toggle(a.id).unwrap();
+        repo.clear_done().unwrap();
+        repo.delete(b.id).unwrap();
+        repo.log_activity(ActivityKind::Sync, None, "GitHub as me: 0 PRs")
+            .unwrap();
+
+        let kinds: Vec<&str> = repo
+            .activity(100)
+            .unwrap()
+            .iter()
+            .map(|e| e.kind.as_str())
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "add", "add", "edit", "edit", "done", "archive", "delete", "sync"
+            ]
+        );
+        let last_two = repo.activity(2).unwrap();
+        assert_eq!(last_two[0].detail, "fix bug");
+        assert_eq!(last_two[0].todo_id, Some(b.id));
+        assert_eq!(last_two[1].todo_id, None);
+    }
+
+    #[test]
+    fn sqlite_search_ranks_title_hits_and_falls_back_to_substrings() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let mut repo = SqliteTodoRepo::open(tmp.path()).unwrap();
+        let in_notes = repo
+            .add("Weekly sync".to_string(), Priority::Low, None, None, None)
+            .unwrap();
+        repo.set_notes(in_notes.id, Some("discuss the deploy freeze".to_string()))
+            .unwrap();
+        let in_title = repo
+            .add("Deploy API".to_string(), Priority::Low, None, None, None)
+            .unwrap();
+        let renamed = repo
+            .add("Draft".to_string(), Priority::Low, None, None, None)
+            .unwrap();
+        repo.set_title(renamed.id, "Deployment checklist".to_string())
+            .unwrap();
+        repo.add("Unrelated".to_string(), Priority::Low, None, None, None)
+            .unwrap();
+
+        let search = |repo: &SqliteTodoRepo, text: &str| {
+            let query = TodoQuery {
+                text: text.to_string(),
+                scope: SearchScope::All,
+            };
+            repo.search(&query)
+                .unwrap()
+                .into_iter()
+                .map(|t| t.title)
+                .collect::<Vec<_>>()
+        };
+        let hits = search(&repo, "deplo");
+        assert_eq!(hits.len(), 3);
+        assert_eq!(hits[2], "Weekly sync");
+        assert_eq!(search(&repo, "ploy freeze"), ["Weekly sync"]);
+        assert_eq!(search(&repo, "\"deploy* OR"), Vec::<String>::new());
+        assert_eq!(search(&repo, "").len(), 4);
+
+        repo.delete(in_title.id).unwrap();
+        assert_eq!(search(&repo, "api"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn sqlite_repo_timer_sessions() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let mut repo = SqliteTodoRepo::open(tmp.path()).unwrap();
+        let a = repo
+            .add("a".to_string(), Priority::Medium, None, None, None)
+            .unwrap();
+        let b = repo
+            .add("b".to_string(), Priority::Medium, None, None, None)
+            .unwrap();
+
+        repo.start_timer(a.id).unwrap().unwrap();
+        repo.start_timer(b.id).unwrap().unwrap();
+        let entries = repo.time_entries(None).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries.iter().filter(|e| e.is_running()).count(), 1);
+
+        let stopped = repo.stop_timer().unwrap().unwrap();
+        assert_eq!(stopped.todo_id, b.id);
+        assert!(repo.stop_timer().unwrap().is_none());
+    }
+
+    #[test]
+    fn deleting_a_todo_drops_its_time_entries() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let mut repo = SqliteTodoRepo::open(tmp.path()).unwrap();
+        let gone = repo
+            .add("gone".to_string(), Priority::Medium, None, None, None)
+            .unwrap();
+        let kept = repo
+            .add("kept".to_string(), Priority::Medium, None, None, None)
+            .unwrap();
+        repo.start_timer(gone.id).unwrap();
+        repo.start_timer(kept.id).unwrap();
+        repo.stop_timer().unwrap();
+        repo.start_timer(gone.id).unwrap();
+
+        repo.delete(gone.id).unwrap();
+        let entries = repo.time_entries(None).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].todo_id, kept.id);
+        // No timer is left running for a todo that no longer exists.
+        assert!(repo.stop_timer().unwrap().is_none());
+    }
+
+    #[test]
+    fn sqlite_repo_batch_edits() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let mut repo = SqliteTodoRepo::open(tmp.path()).unwrap();
+        let parent = repo
+            .add("parent".to_string(), Priority::Low, None, None, None)
+            .unwrap();
+        let child = repo
+            .add("child".to_string(), Priority::Low, None, None, None)
+            .unwrap();
+        repo.set_parent(child.id, Some(parent.id)).unwrap();
+        let other = repo
+            .add("other".to_string(), Priority::Low, None, None, None)
+            .unwrap();
+        let ids = [parent.id, child.id, other.id];
 
-        let toggled = repo.toggle(todo.id).unwrap();
-        assert!(toggled.done);
+        assert_eq!(repo.set_priority_many(&ids, Priority::High).unwrap(), 3);
+        let due = UNIX_EPOCH + Duration::from_secs(1_800_000_000);
+        assert_eq!(
+            repo.set_due_many(&[(child.id, Some(Due::timed(due)))])
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            repo.set_title_many(&[(other.id, "#ops other".to_string())])
+                .unwrap(),
+            1
+        );
+        let all = repo.all().unwrap();
+        assert!(all.iter().all(|t| t.priority == Priority::High));
+        let find = |id: TodoId| all.iter().find(|t| t.id == id).unwrap().clone();
+        assert_eq!(find(child.id).due, Some(due));
+        assert_eq!(find(other.id).title, "#ops other");
 
-        assert_eq!(repo.clear_done(), 1);
-        assert!(repo.all().is_empty());
+        let deleted = repo.delete_many(&[parent.id, Uuid::new_v4()]).unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(
+            repo.all()
+                .unwrap()
+                .iter()
+                .find(|t| t.id == child.id)
+                .unwrap()
+                .parent_id,
+            None
+        );
+        assert_eq!(
+            repo.set_sort_orders(&[(other.id, 0), (child.id, 1)])
+                .unwrap(),
+            2
+        );
+        let find = |id: TodoId| {
+            repo.all()
+                .unwrap()
+                .into_iter()
+                .find(|t| t.id == id)
+                .unwrap()
+        };
+        assert_eq!(find(child.id).sort_order, Some(1));
+        assert_eq!(find(other.id).sort_order, Some(0));
+        assert_eq!(repo.archive_many(&[child.id, other.id]).unwrap(), 2);
+        assert!(repo.all().unwrap().is_empty());
     }
 }
@@ -0,0 +1,665 @@
+//! Plain-text store in the todo.txt format (`--todo-txt <file>`), for lists kept in git or
+//! edited by other tools. Open and done todos live in the given file; archived ones move
+//! to a `done.txt` next to it, as todo.txt tools do.
+//!
+//! A line is `[x <completed> ][(A) ]<created> <title> [due:…] [t:…] [key:value…]`:
+//! priority maps to `(A)` / `(B)` / `(C)` (`pri:A` on done lines; a line without one reads
+//! as medium and is written back without one until its priority changes), the due date to
+//! `due:YYYY-MM-DD` (`due:YYYY-MM-DDTHH:MM` with a time) and the start date to the `t:`
+//! threshold. koto adds `status:`,
+//! `est:`, `url:` and `key:` (the sync key, so synced PRs are updated instead of
+//! duplicated). Anything else, `+project` and `@context` included, stays in the title.
+//!
+//! Notes, subtasks, dependencies, timers and the activity log are not part of the format
+//! and only last for the session.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, bail};
+use time::macros::format_description;
+use time::{Date, Time};
+
+use super::TodoRepository;
+use super::git_sync::{self, GitSync};
+use super::memory::InMemoryTodoRepo;
+use crate::domain::activity::{Activity, ActivityKind};
+use crate::domain::clock;
+use crate::domain::due::Due;
+use crate::domain::query::TodoQuery;
+use crate::domain::time_entry::TimeEntry;
+use crate::domain::todo::{Priority, Status, Todo, TodoId};
+use crate::repo::github::model::{Pr, QueryStats};
+
+/// Content hash of both files (`None`: missing), to notice edits made elsewhere; a
+/// modification time can miss a same-length edit within its resolution.
+type Stamp = [Option<u64>; 2];
+
+pub struct TodoTxtRepo {
+    path: PathBuf,
+    done_path: PathBuf,
+    /// The parsed files; re-read (keeping ids) when another program changes them.
+    inner: RefCell<InMemoryTodoRepo>,
+    stamp: Cell<Stamp>,
+    /// Todos whose line had no priority; written back without one while still medium.
+    unprioritized: RefCell<HashSet<TodoId>>,
+    /// Bumped on every re-read caused by another program.
+    external_changes: Cell<i64>,
+    git: Option<GitSync>,
+}
+
+impl TodoTxtRepo {
+    /// Open `path` (created on the first write) and the `done.txt` beside it.
+    pub fn open(path: &Path) -> Result<Self> {
+        let repo = Self {
+            path: path.to_path_buf(),
+            done_path: done_path(path),
+            inner: RefCell::new(InMemoryTodoRepo::default()),
+            stamp: Cell::new([None, None]),
+            unprioritized: RefCell::new(HashSet::new()),
+            external_changes: Cell::new(0),
+            git: None,
+        };
+        repo.refresh()?;
+        Ok(repo)
+    }
+
+    /// Commit, pull and push the files in their git repository (`--git-sync`).
+    pub fn with_git_sync(mut self) -> Result<Self> {
+        self.git = Some(GitSync::start(vec![
+            self.path.clone(),
+            self.done_path.clone(),
+        ])?);
+        self.refresh()?;
+        Ok(self)
+    }
+
+    fn current_stamp(&self) -> Stamp {
+        let stamp = |path: &Path| {
+            let text = std::fs::read(path).ok()?;
+            let mut hasher = DefaultHasher::new();
+            text.hash(&mut hasher);
+            Some(hasher.finish())
+        };
+        [stamp(&self.path), stamp(&self.done_path)]
+    }
+
+    /// Re-read the files if they changed since the last read or write; `true` when they did.
+    fn refresh(&self) -> Result<bool> {
+        let work_tree = self.git.as_ref().map(GitSync::work_tree);
+        let _tree = work_tree.as_deref().map(git_sync::hold);
+        self.reload()
+    }
+
+    /// [`Self::refresh`] with the work tree already held.
+    fn reload(&self) -> Result<bool> {
+        let stamp = self.current_stamp();
+        if stamp == self.stamp.get() {
+            return Ok(false);
+        }
+        let mut lines = read_file(&self.path, false)?;
+        lines.extend(read_file(&self.done_path, true)?);
+        let mut inner = self.inner.borrow_mut();
+        let mut previous: Vec<Todo> = inner.todos().cloned().collect();
+        let mut unprioritized = self.unprioritized.borrow_mut();
+        unprioritized.clear();
+        let todos: Vec<Todo> = lines
+            .into_iter()
+            .map(|(todo, prioritized)| {
+                let todo = keep_identity(todo, &mut previous);
+                if !prioritized {
+                    unprioritized.insert(todo.id);
+                }
+                todo
+            })
+            .collect();
+        inner.replace_todos(todos);
+        self.stamp.set(stamp);
+        Ok(true)
+    }
+
+    /// Pick up outside edits, apply `write` and save both files, with git kept off the
+    /// files from the read to the save.
+    fn write<T>(&mut self, write: impl FnOnce(&mut InMemoryTodoRepo) -> Result<T>) -> Result<T> {
+        let work_tree = self.git.as_ref().map(GitSync::work_tree);
+        let _tree = work_tree.as_deref().map(git_sync::hold);
+        self.reload()?;
+        let value = write(self.inner.get_mut())?;
+        // A todo given a priority keeps it, even when that is medium again later.
+        let medium: HashSet<TodoId> = self
+            .inner
+            .get_mut()
+            .todos()
+            .filter(|t| t.priority == Priority::Medium)
+            .map(|t| t.id)
+            .collect();
+        self.unprioritized
+            .get_mut()
+            .retain(|id| medium.contains(id));
+        self.save()?;
+        Ok(value)
+    }
+
+    fn save(&mut self) -> Result<()> {
+        let (mut open, mut archived) = (String::new(), String::new());
+        let unprioritized = self.unprioritized.get_mut();
+        for todo in self.inner.get_mut().todos() {
+            let out = if todo.archived {
+                &mut archived
+            } else {
+                &mut open
+            };
+            out.push_str(&format_line(todo, !unprioritized.contains(&todo.id)));
+            out.push('\n');
+        }
+        write_file(&self.path, &open)?;
+        if !archived.is_empty() || self.done_path.exists() {
+            write_file(&self.done_path, &archived)?;
+        }
+        self.stamp.set(self.current_stamp());
+        if let Some(git) = &self.git {
+            git.changed();
+        }
+        Ok(())
+    }
+}
+
+impl TodoRepository for TodoTxtRepo {
+    fn all(&self) -> Result<Vec<Todo>> {
+        self.refresh()?;
+        self.inner.borrow().all()
+    }
+
+    fn search(&self, query: &TodoQuery) -> Result<Vec<Todo>> {
+        self.refresh()?;
+        self.inner.borrow().search(query)
+    }
+
+    fn add(
+        &mut self,
+        title: String,
+        priority: Priority,
+        due: Option<Due>,
+        external_url: Option<String>,
+        external_key: Option<String>,
+    ) -> Result<Todo> {
+        if title.contains('\n') {
+            bail!("a todo.txt title must fit on one line");
+        }
+        self.write(|repo| repo.add(title, priority, due, external_url, external_key))
+    }
+
+    fn update_meta(
+        &mut self,
+        id: TodoId,
+        priority: Priority,
+        due: Option<Due>,
+    ) -> Result<Option<Todo>> {
+        self.write(|repo| repo.update_meta(id, priority, due))
+    }
+
+    fn set_title(&mut self, id: TodoId, title: String) -> Result<Option<Todo>> {
+        self.write(|repo| repo.set_title(id, title))
+    }
+
+    fn resolve_title(&mut self, id: TodoId, title: String) -> Result<Option<Todo>> {
+        self.write(|repo| repo.resolve_title(id, title))
+    }
+
+    fn set_notes(&mut self, id: TodoId, notes: Option<String>) -> Result<Option<Todo>> {
+        self.inner.get_mut().set_notes(id, notes)
+    }
+
+    fn set_start(&mut self, id: TodoId, start: Option<SystemTime>) -> Result<Option<Todo>> {
+        self.write(|repo| repo.set_start(id, start))
+    }
+
+    fn set_status(&mut self, id: TodoId, status: Status) -> Result<Option<Todo>> {
+        self.write(|repo| repo.set_status(id, status))
+    }
+
+    fn set_estimate(&mut self, id: TodoId, minutes: Option<u32>) -> Result<Option<Todo>> {
+        self.write(|repo| repo.set_estimate(id, minutes))
+    }
+
+    fn mark_seen(
+        &mut self,
+        id: TodoId,
+        sha: Option<String>,
+        at: SystemTime,
+    ) -> Result<Option<Todo>> {
+        self.inner.get_mut().mark_seen(id, sha, at)
+    }
+
+    fn add_dependency(&mut self, id: TodoId, blocked_by: TodoId) -> Result<()> {
+        self.inner.get_mut().add_dependency(id, blocked_by)
+    }
+
+    fn clear_dependencies(&mut self, id: TodoId) -> Result<usize> {
+        self.inner.get_mut().clear_dependencies(id)
+    }
+
+    fn dependencies(&self) -> Result<Vec<(TodoId, TodoId)>> {
+        self.inner.borrow().dependencies()
+    }
+
+    fn set_parent(&mut self, id: TodoId, parent: Option<TodoId>) -> Result<Option<Todo>> {
+        self.inner.get_mut().set_parent(id, parent)
+    }
+
+    fn toggle(&mut self, id: TodoId) -> Result<Option<Todo>> {
+        self.write(|repo| repo.toggle(id))
+    }
+
+    fn delete(&mut self, id: TodoId) -> Result<Option<Todo>> {
+        self.write(|repo| repo.delete(id))
+    }
+
+    fn clear_done(&mut self) -> Result<usize> {
+        self.write(|repo| repo.clear_done())
+    }
+
+    fn archive(&mut self, id: TodoId) -> Result<Option<Todo>> {
+        self.write(|repo| repo.archive(id))
+    }
+
+    fn delete_many(&mut self, ids: &[TodoId]) -> Result<Vec<Todo>> {
+        self.write(|repo| repo.delete_many(ids))
+    }
+
+    fn archive_many(&mut self, ids: &[TodoId]) -> Result<usize> {
+        self.write(|repo| repo.archive_many(ids))
+    }
+
+    fn set_priority_many(&mut self, ids: &[TodoId], priority: Priority) -> Result<usize> {
+        self.write(|repo| repo.set_priority_many(ids, priority))
+    }
+
+    fn set_due_many(&mut self, dues: &[(TodoId, Option<Due>)]) -> Result<usize> {
+        self.write(|repo| repo.set_due_many(dues))
+    }
+
+    fn set_title_many(&mut self, titles: &[(TodoId, String)]) -> Result<usize> {
+        self.write(|repo| repo.set_title_many(titles))
+    }
+
+    fn set_sort_orders(&mut self, orders: &[(TodoId, i64)]) -> Result<usize> {
+        self.inner.get_mut().set_sort_orders(orders)
+    }
+
+    fn start_timer(&mut self, id: TodoId) -> Result<Option<TimeEntry>> {
+        self.inner.get_mut().start_timer(id)
+    }
+
+    fn stop_timer(&mut self) -> Result<Option<TimeEntry>> {
+        self.inner.get_mut().stop_timer()
+    }
+
+    fn meta(&self, key: &str) -> Result<Option<String>> {
+        self.inner.borrow().meta(key)
+    }
+
+    fn set_meta(&mut self, key: &str, value: &str) -> Result<()> {
+        self.inner.get_mut().set_meta(key, value)
+    }
+
+    fn replace_prs(&mut self, prs: &[Pr]) -> Result<()> {
+        self.inner.get_mut().replace_prs(prs)
+    }
+
+    fn cached_prs(&self) -> Result<Vec<Pr>> {
+        self.inner.borrow().cached_prs()
+    }
+
+    fn record_sync_stats(&mut self, synced_at: i64, stats: &[QueryStats]) -> Result<()> {
+        self.inner.get_mut().record_sync_stats(synced_at, stats)
+    }
+
+    fn time_entries(&self, since: Option<SystemTime>) -> Result<Vec<TimeEntry>> {
+        self.inner.borrow().time_entries(since)
+    }
+
+    fn log_activity(
+        &mut self,
+        kind: ActivityKind,
+        todo_id: Option<TodoId>,
+        detail: &str,
+    ) -> Result<()> {
+        self.inner.get_mut().log_activity(kind, todo_id, detail)
+    }
+
+    fn activity(&self, limit: usize) -> Result<Vec<Activity>> {
+        self.inner.borrow().activity(limit)
+    }
+
+    fn data_version(&self) -> Result<Option<i64>> {
+        if self.refresh()? {
+            self.external_changes.set(self.external_changes.get() + 1);
+        }
+        if let Some(git) = &self.git {
+            git.poll()?;
+        }
+        Ok(Some(self.external_changes.get()))
+    }
+}
+
+/// `todo.txt` → `done.txt`, `work.txt` → `work.done.txt`.
+fn done_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("todo");
+    let name = if stem == "todo" {
+        "done.txt".to_string()
+    } else {
+        format!("{stem}.done.txt")
+    };
+    path.with_file_name(name)
+}
+
+/// Todos in `path`, each with whether its line had a priority; a missing file is an
+/// empty list.
+fn read_file(path: &Path, archived: bool) -> Result<Vec<(Todo, bool)>> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+    Ok(text
+        .lines()
+        .filter_map(parse_line)
+        .map(|(mut todo, prioritized)| {
+            todo.archived = archived;
+            (todo, prioritized)
+        })
+        .collect())
+}
+
+/// Written to a temp file first, so a crash mid-write keeps the previous list.
+fn write_file(path: &Path, text: &str) -> Result<()> {
+    let tmp = path.with_extension("txt.tmp");
+    std::fs::write(&tmp, text).with_context(|| format!("failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("failed to replace {}", path.display()))
+}
+
+/// Give a re-read todo the id and session-only fields of the todo it was before: the
+/// same sync key, else the first one with the same title.
+fn keep_identity(mut todo: Todo, previous: &mut Vec<Todo>) -> Todo {
+    let position = match &todo.external_key {
+        Some(key) => previous
+            .iter()
+            .position(|p| p.external_key.as_ref() == Some(key)),
+        None => previous
+            .iter()
+            .position(|p| p.external_key.is_none() && p.title == todo.title),
+    };
+    if let Some(position) = position {
+        let before = previous.swap_remove(position);
+        todo.id = before.id;
+        todo.notes = before.notes;
+        todo.parent_id = before.parent_id;
+        todo.remote_title = before.remote_title;
+        todo.incoming_title = before.incoming_title;
+        todo.sort_order = before.sort_order;
+        todo.last_seen_sha = before.last_seen_sha;
+        todo.last_seen_at = before.last_seen_at;
+        if todo.completed_at.map(local_date) == before.completed_at.map(local_date) {
+            todo.completed_at = before.completed_at;
+        }
+    }
+    todo
+}
+
+/// One todo.txt line and whether it gave a priority (without one the todo is medium);
+/// `None` for blank lines.
+fn parse_line(line: &str) -> Option<(Todo, bool)> {
+    let mut words = line.split_whitespace().peekable();
+    words.peek()?;
+    let mut todo = Todo::with_meta("", Priority::Medium, None);
+    let mut prioritized = false;
+    if words.next_if_eq(&"x").is_some() {
+        todo.status = Status::Done;
+        todo.completed_at = words
+            .next_if(|w| parse_date(w).is_some())
+            .and_then(parse_date)
+            .map(|d| clock::at_local(d, Time::MIDNIGHT));
+    } else if let Some(priority) = words.next_if(|w| parse_priority(w).is_some()) {
+        todo.priority = parse_priority(priority)?;
+        prioritized = true;
+    }
+    if let Some(created) = words.next_if(|w| parse_date(w).is_some()) {
+        todo.created_at = parse_date(created).map(|d| clock::at_local(d, Time::MIDNIGHT))?;
+    }
+    let mut title = Vec::new();
+    for word in words {
+        let Some((key, value)) = word.split_once(':') else {
+            title.push(word);
+            continue;
+        };
+        match key {
+            "due" if parse_due(value).is_some() => todo.set_due(parse_due(value)),
+            "t" if parse_date(value).is_some() => {
+                todo.start_at = parse_date(value).map(|d| clock::at_local(d, Time::MIDNIGHT));
+            }
+            "pri" if parse_priority(&format!("({value})")).is_some() => {
+                todo.priority = parse_priority(&format!("({value})"))?;
+                prioritized = true;
+            }
+            "est" if value.parse::<u32>().is_ok() => todo.estimate_minutes = value.parse().ok(),
+            "status" if Status::from_label(value).label() == value => {
+                // `x` settles done-ness; `status:` only tells the open (or closed) kinds apart.
+                let status = Status::from_label(value);
+                if status.is_closed() == todo.is_closed() {
+                    todo.status = status;
+                }
+            }
+            "url" if !value.is_empty() => todo.external_url = Some(value.to_string()),
+            "key" if !value.is_empty() => todo.external_key = Some(value.to_string()),
+            _ => title.push(word),
+        }
+    }
+    todo.title = title.join(" ");
+    Some((todo, prioritized))
+}
+
+/// `prioritized`: write the priority (`false` keeps a line that had none that way).
+fn format_line(todo: &Todo, prioritized: bool) -> String {
+    let mut words = Vec::new();
+    if todo.is_closed() {
+        words.push("x".to_string());
+        // The format needs a completion date before the creation date.
+        let completed = todo.completed_at.unwrap_or(todo.created_at);
+        words.push(format_date(completed));
+    } else if prioritized {
+        words.push(format!("({})", priority_letter(todo.priority)));
+    }
+    words.push(format_date(todo.created_at));
+    words.push(todo.title.clone());
+    if todo.is_closed() && prioritized {
+        words.push(format!("pri:{}", priority_letter(todo.priority)));
+    }
+    if !matches!(todo.status, Status::Todo | Status::Done) {
+        words.push(format!("status:{}", todo.status.label()));
+    }
+    if let Some(due) = todo.due_value() {
+        words.push(format!("due:{}", format_due(due)));
+    }
+    if let Some(start) = todo.start_at {
+        words.push(format!("t:{}", format_date(start)));
+    }
+    if let Some(minutes) = todo.estimate_minutes {
+        words.push(format!("est:{minutes}"));
+    }
+    if let Some(url) = &todo.external_url {
+        words.push(format!("url:{url}"));
+    }
+    if let Some(key) = &todo.external_key {
+        words.push(format!("key:{key}"));
+    }
+    words.join(" ")
+}
+
+fn priority_letter(priority: Priority) -> char {
+    match priority {
+        Priority::High => 'A',
+        Priority::Medium => 'B',
+        Priority::Low => 'C',
+    }
+}
+
+/// `(A)` is high, `(B)` medium, `(C)` and anything lower low.
+fn parse_priority(word: &str) -> Option<Priority> {
+    let letter = word.strip_prefix('(')?.strip_suffix(')')?;
+    match letter {
+        "A" => Some(Priority::High),
+        "B" => Some(Priority::Medium),
+        _ if letter.len() == 1 && letter.chars().all(|c| c.is_ascii_uppercase()) => {
+            Some(Priority::Low)
+        }
+        _ => None,
+    }
+}
+
+fn parse_date(word: &str) -> Option<Date> {
+    Date::parse(word, format_description!("[year]-[month]-[day]")).ok()
+}
+
+/// `YYYY-MM-DD` (due that day) or `YYYY-MM-DDTHH:MM`.
+fn parse_due(value: &str) -> Option<Due> {
+    match value.split_once('T') {
+        Some((date, at)) => {
+            let at = Time::parse(at, format_description!("[hour]:[minute]")).ok()?;
+            Some(Due::timed(clock::at_local(parse_date(date)?, at)))
+        }
+        None => parse_date(value).map(Due::day),
+    }
+}
+
+/// The date alone for a date-only due, else with the time.
+fn format_due(due: Due) -> String {
+    if !due.has_time {
+        return format_date(due.at);
+    }
+    clock::to_local(due.at.max(UNIX_EPOCH))
+        .format(format_description!("[year]-[month]-[day]T[hour]:[minute]"))
+        .unwrap_or_default()
+}
+
+fn local_date(time: SystemTime) -> Date {
+    clock::to_local(time.max(UNIX_EPOCH)).date()
+}
+
+fn format_date(time: SystemTime) -> String {
+    local_date(time)
+        .format(format_description!("[year]-[month]-[day]"))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_keep_priority_dates_and_tags() {
+        let (todo, prioritized) =
+            parse_line("(A) 2026-10-01 Call the bank +home @phone due:2026-10-20 key:jira:OPS-1")
+                .unwrap();
+        assert!(prioritized);
+        assert_eq!(todo.priority, Priority::High);
+        assert_eq!(todo.title, "Call the bank +home @phone");
+        assert_eq!(todo.external_key.as_deref(), Some("jira:OPS-1"));
+        assert_eq!(todo.due.map(format_date).as_deref(), Some("2026-10-20"));
+        assert_eq!(
+            format_line(&todo, true),
+            "(A) 2026-10-01 Call the bank +home @phone due:2026-10-20 key:jira:OPS-1"
+        );
+
+        let (done, _) = parse_line("x 2026-10-05 2026-10-01 Ship it pri:C due:nope").unwrap();
+        assert_eq!(done.status, Status::Done);
+        assert_eq!(done.priority, Priority::Low);
+        assert_eq!(done.title, "Ship it due:nope");
+        assert_eq!(
+            format_line(&done, true),
+            "x 2026-10-05 2026-10-01 Ship it due:nope pri:C"
+        );
+    }
+
+    #[test]
+    fn lines_round_trip_due_times_and_missing_priorities() {
+        for line in [
+            "2026-10-01 Dentist due:2026-10-20T14:30",
+            "(C) 2026-10-01 Pay rent due:2026-11-01",
+            "x 2026-10-05 2026-10-01 Renew the lease due:2026-10-04T09:05",
+        ] {
+            let (todo, prioritized) = parse_line(line).unwrap();
+            assert_eq!(format_line(&todo, prioritized), line);
+        }
+        let (todo, prioritized) = parse_line("2026-10-01 Dentist due:2026-10-20T14:30").unwrap();
+        assert!(!prioritized);
+        assert_eq!(todo.priority, Priority::Medium);
+        let due = clock::to_local(todo.due.unwrap());
+        assert_eq!((due.hour(), due.minute()), (14, 30));
+    }
+
+    #[test]
+    fn unprioritized_lines_stay_so_until_a_priority_is_set() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todo.txt");
+        std::fs::write(&path, "2026-10-01 Buy stamps\n2026-10-01 Post the letter\n").unwrap();
+        let mut repo = TodoTxtRepo::open(&path).unwrap();
+        let todos = repo.all().unwrap();
+        repo.set_title(todos[0].id, "Buy more stamps".to_string())
+            .unwrap();
+        repo.set_priority_many(&[todos[1].id], Priority::High)
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "2026-10-01 Buy more stamps\n(A) 2026-10-01 Post the letter\n"
+        );
+        repo.set_priority_many(&[todos[1].id], Priority::Medium)
+            .unwrap();
+        assert!(
+            std::fs::read_to_string(&path)
+                .unwrap()
+                .contains("(B) 2026-10-01 Post the letter")
+        );
+    }
+
+    #[test]
+    fn edits_from_other_programs_are_picked_up_with_stable_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todo.txt");
+        let mut repo = TodoTxtRepo::open(&path).unwrap();
+        let todo = repo
+            .add("Water plants".into(), Priority::Low, None, None, None)
+            .unwrap();
+        repo.toggle(todo.id).unwrap();
+        repo.clear_done().unwrap();
+        assert!(
+            std::fs::read_to_string(dir.path().join("done.txt"))
+                .unwrap()
+                .contains("Water plants")
+        );
+        let version = repo.data_version().unwrap();
+        assert_eq!(repo.data_version().unwrap(), version);
+
+        let kept = repo
+            .add("Renew passport".into(), Priority::Medium, None, None, None)
+            .unwrap();
+        let mut text = std::fs::read_to_string(&path).unwrap();
+        text.push_str("(A) 2026-10-16 Fix the bike\n");
+        std::fs::write(&path, text).unwrap();
+        assert_ne!(repo.data_version().unwrap(), version);
+        let todos = repo.all().unwrap();
+        assert_eq!(todos.len(), 2);
+        assert_eq!(todos[0].id, kept.id);
+        assert_eq!(todos[1].priority, Priority::High);
+
+        // A same-length edit is noticed even within the file time's resolution.
+        let text = std::fs::read_to_string(&path).unwrap();
+        let version = repo.data_version().unwrap();
+        std::fs::write(&path, text.replace("Fix the bike", "Fix the kite")).unwrap();
+        assert_ne!(repo.data_version().unwrap(), version);
+        assert_eq!(repo.all().unwrap()[1].title, "Fix the kite");
+    }
+}
@@ -0,0 +1,76 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState},
+};
+
+use super::{render_due, text};
+use crate::app::App;
+use crate::domain::todo::{Priority, Todo};
+
+/// The board: one bordered list per column, the selected todo highlighted in its column.
+pub fn render(f: &mut Frame, app: &App, area: Rect) {
+    let columns = app.board_columns();
+    let areas = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![
+            Constraint::Ratio(1, columns.len() as u32);
+            columns.len()
+        ])
+        .split(area);
+    for ((title, items), area) in columns.into_iter().zip(areas.iter()) {
+        let selected = items.iter().position(|&idx| idx == app.selected);
+        let list = List::new(
+            items
+                .iter()
+                // Inside the borders.
+                .map(|&idx| card(app, &app.todos[idx], area.width.saturating_sub(2) as usize))
+                .collect::<Vec<_>>(),
+        )
+        .block(
+            Block::default()
+                .title(format!("{title} ({})", items.len()))
+                .borders(Borders::ALL)
+                .border_style(match selected {
+                    Some(_) => Style::default().fg(Color::Cyan),
+                    None => Style::default(),
+                }),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected(selected);
+        f.render_stateful_widget(list, *area, &mut state);
+    }
+}
+
+/// A todo on the board: priority, title and badges, with its due date below; the title
+/// is shortened to fit `width` columns.
+fn card<'a>(app: &App, todo: &'a Todo, width: usize) -> ListItem<'a> {
+    let (glyph, color) = match todo.priority {
+        Priority::High => ("▲", Color::Red),
+        Priority::Medium => ("△", Color::Yellow),
+        Priority::Low => ("▽", Color::Blue),
+    };
+    let mut title = vec![
+        Span::styled(glyph, Style::default().fg(color)),
+        Span::raw(" "),
+        Span::raw(todo.title.as_str()),
+    ];
+    if !todo.is_closed() && app.blockers.contains_key(&todo.id) {
+        title.push(Span::styled(" 🔒", Style::default().fg(Color::Yellow)));
+    }
+    let mut lines = vec![Line::from(text::fit_spans(title, 2, width))];
+    if let Some(due) = app.effective_due(todo).filter(|_| !todo.is_closed()) {
+        let (text, style) = render_due(Some(due.due()), app.today);
+        lines.push(Line::from(Span::styled(format!("  {text}"), style)));
+    }
+    let style = if todo.is_closed() {
+        Style::default()
+            .fg(Color::DarkGray)
+            .add_modifier(Modifier::CROSSED_OUT)
+    } else {
+        Style::default()
+    };
+    ListItem::new(lines).style(style)
+}
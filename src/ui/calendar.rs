@@ -0,0 +1,93 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+use time::{Date, Duration};
+
+use crate::app::{App, CalendarState};
+
+/// Width of the calendar pane including borders: 7 cells of 4 columns.
+pub const WIDTH: u16 = 7 * 4 + 2;
+
+const SUPERSCRIPT: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+pub fn render(app: &App, cal: &CalendarState) -> Paragraph<'static> {
+    let cursor = cal.cursor;
+    let first = cursor.replace_day(1).unwrap_or(cursor);
+    let lead = first.weekday().number_days_from_monday() as i64;
+    let days = cursor.month().length(cursor.year());
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("{} {}", cursor.month(), cursor.year()),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(Span::styled(
+            "Mo  Tu  We  Th  Fr  Sa  Su",
+            Style::default().fg(Color::Gray),
+        )),
+    ];
+
+    let mut week: Vec<Span> = Vec::new();
+    for _ in 0..lead {
+        week.push(Span::raw("    "));
+    }
+    for day in 1..=days {
+        let date = first.saturating_add(Duration::days(day as i64 - 1));
+        week.push(render_day(app, cal, date));
+        if week.len() == 7 {
+            lines.push(Line::from(std::mem::take(&mut week)));
+        }
+    }
+    if !week.is_empty() {
+        lines.push(Line::from(week));
+    }
+
+    lines.push(Line::from(""));
+    let count = app.due_counts.get(&cursor).copied().unwrap_or(0);
+    lines.push(Line::from(format!("{cursor}: {count} due")));
+
+    let title = if cal.focused {
+        "Calendar (Enter filter ; Esc close)"
+    } else {
+        "Calendar (M focus)"
+    };
+    let border = if cal.focused {
+        Style::default().fg(Color::Cyan)
+    } else {
+        Style::default()
+    };
+    Paragraph::new(lines).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(border),
+    )
+}
+
+fn render_day(app: &App, cal: &CalendarState, date: Date) -> Span<'static> {
+    let count = app.due_counts.get(&date).copied().unwrap_or(0);
+    let badge = match count {
+        0 => ' ',
+        1..=9 => SUPERSCRIPT[count],
+        _ => '⁺',
+    };
+    let mut style = Style::default();
+    if count > 0 {
+        style = style.fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    }
+    if date < app.today && count > 0 {
+        style = style.fg(Color::Red);
+    }
+    if date == app.today {
+        style = style.add_modifier(Modifier::UNDERLINED);
+    }
+    if app.day_filter == Some(date) {
+        style = style.fg(Color::Cyan);
+    }
+    if date == cal.cursor {
+        style = style.add_modifier(Modifier::REVERSED);
+    }
+    Span::styled(format!("{:>2}{badge} ", date.day()), style)
+}
@@ -0,0 +1,63 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use time::{Duration, macros::format_description};
+
+use crate::app::App;
+
+/// Days covered, starting today.
+const DAYS: i64 = 14;
+/// A day with at least this many open items is drawn as overloaded.
+const HEAVY_DAY: usize = 5;
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One bar per day for the next two weeks, scaled to the busiest day, plus the peak.
+pub fn line(app: &App) -> Line<'static> {
+    let days: Vec<_> = (0..DAYS)
+        .map(|offset| {
+            let date = app.today.saturating_add(Duration::days(offset));
+            (date, app.due_counts.get(&date).copied().unwrap_or(0))
+        })
+        .collect();
+    let max = days.iter().map(|(_, n)| *n).max().unwrap_or(0);
+
+    let mut spans = vec![Span::styled("Due 14d ", Style::default().fg(Color::Gray))];
+    for (date, count) in &days {
+        let bar = match count {
+            0 => '·',
+            n => {
+                BARS[(n * (BARS.len() - 1))
+                    .div_ceil(max.max(1))
+                    .min(BARS.len() - 1)]
+            }
+        };
+        let mut style = match count {
+            0 => Style::default().fg(Color::DarkGray),
+            n if *n >= HEAVY_DAY => Style::default().fg(Color::Red),
+            _ => Style::default().fg(Color::Green),
+        };
+        if *date == app.today {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        }
+        // A gap after each Sunday keeps the weeks apart.
+        let sep = if date.weekday() == time::Weekday::Sunday {
+            " "
+        } else {
+            ""
+        };
+        spans.push(Span::styled(format!("{bar}{sep}"), style));
+    }
+
+    if let Some((date, count)) = days.iter().filter(|(_, n)| *n > 0).max_by_key(|(_, n)| *n) {
+        let label = date
+            .format(format_description!("[weekday repr:short] [month]-[day]"))
+            .unwrap_or_default();
+        spans.push(Span::styled(
+            format!("  peak {count} on {label}"),
+            Style::default().fg(Color::Gray),
+        ));
+    }
+    Line::from(spans)
+}
@@ -0,0 +1,591 @@
+//! Key bindings: which `Action` a key press means given what is on screen. Overlays
+//! take keys before the list, in the order they are drawn on top of each other.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::help_matches;
+use crate::app::{Action, App, ConflictChoice, HelpMode, InputMode, SyncSource, View};
+use crate::domain::todo::Priority;
+
+/// What `key` does right now; several actions when a press also ends a pending `g` or
+/// delete, none for an unbound key.
+pub fn actions(app: &App, key: KeyEvent) -> Vec<Action> {
+    let code = key.code;
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let normal = app.mode == InputMode::Normal;
+    let overlay = if app.locked {
+        Some(lock_screen(code))
+    } else if app.restore_offer.is_some() {
+        Some(restore_offer(code))
+    } else if app.error_view.is_some() {
+        Some(error_view(code))
+    } else if normal && app.help_mode != HelpMode::None {
+        Some(help(app, code))
+    } else if app.review.is_some() {
+        Some(review(code))
+    } else if app.jump.is_some() {
+        Some(jump(code, ctrl))
+    } else if normal && let Some(conflict) = &app.conflict {
+        Some(match code {
+            KeyCode::Esc => Some(Action::PostponeConflict),
+            KeyCode::Tab | KeyCode::Right | KeyCode::Char('j') => {
+                Some(Action::CycleConflictChoice { forward: true })
+            }
+            KeyCode::BackTab | KeyCode::Left | KeyCode::Char('k') => {
+                Some(Action::CycleConflictChoice { forward: false })
+            }
+            KeyCode::Enter => Some(Action::ResolveConflict(conflict.choice)),
+            KeyCode::Char('l') => Some(Action::ResolveConflict(ConflictChoice::KeepLocal)),
+            KeyCode::Char('r') => Some(Action::ResolveConflict(ConflictChoice::TakeRemote)),
+            KeyCode::Char('m') => Some(Action::ResolveConflict(ConflictChoice::Merge)),
+            _ => None,
+        })
+    } else if normal && app.sync_menu.is_some() {
+        Some(match code {
+            KeyCode::Esc | KeyCode::Char('S') => Some(Action::CloseSyncMenu),
+            KeyCode::Char('q') => Some(Action::Quit),
+            KeyCode::Char('j') | KeyCode::Down => Some(Action::MoveSyncMenu(1)),
+            KeyCode::Char('k') | KeyCode::Up => Some(Action::MoveSyncMenu(-1)),
+            KeyCode::Char(' ') => Some(Action::ToggleSyncSource),
+            KeyCode::Enter => Some(Action::RunSyncMenu),
+            _ => None,
+        })
+    } else if normal && app.status_log_view.is_some() {
+        Some(match code {
+            KeyCode::Esc | KeyCode::Char('L') => Some(Action::ToggleStatusLog),
+            KeyCode::Char('q') => Some(Action::Quit),
+            KeyCode::Char('j') | KeyCode::Down => Some(Action::ScrollStatusLog(1)),
+            KeyCode::Char('k') | KeyCode::Up => Some(Action::ScrollStatusLog(-1)),
+            _ => None,
+        })
+    } else if normal && app.ci_checks.is_some() {
+        Some(match code {
+            KeyCode::Esc | KeyCode::Char('C') => Some(Action::CloseCiChecks),
+            KeyCode::Char('q') => Some(Action::Quit),
+            KeyCode::Char('j') | KeyCode::Down => Some(Action::MoveCiCheck(1)),
+            KeyCode::Char('k') | KeyCode::Up => Some(Action::MoveCiCheck(-1)),
+            KeyCode::Enter => Some(Action::OpenCiCheck),
+            _ => None,
+        })
+    } else if normal && app.link_picker.is_some() {
+        Some(match code {
+            KeyCode::Esc => Some(Action::CloseLinkPicker),
+            KeyCode::Char('q') => Some(Action::Quit),
+            KeyCode::Char('j') | KeyCode::Down => Some(Action::MoveLinkPicker(1)),
+            KeyCode::Char('k') | KeyCode::Up => Some(Action::MoveLinkPicker(-1)),
+            KeyCode::Enter => Some(Action::OpenPickedLink),
+            _ => None,
+        })
+    } else if normal && app.show_detail {
+        Some(detail(code))
+    } else if normal && app.calendar.is_some_and(|c| c.focused) {
+        Some(calendar(code))
+    } else if normal
+        && app.view == View::Inbox
+        // The inbox lists notifications, not todos: only view switches and the global
+        // keys reach the list bindings.
+        && !matches!(
+            code,
+            KeyCode::Char('q' | '1' | '2' | '3' | '5' | 'h' | '?' | 'H' | 'L' | 'E' | 'S')
+        )
+    {
+        Some(inbox(code))
+    } else {
+        None
+    };
+    if let Some(action) = overlay {
+        return action.into_iter().collect();
+    }
+
+    let mut actions = Vec::new();
+    let mut delete_pending = normal && app.delete_pending();
+    if delete_pending
+        && (ctrl
+            || !matches!(
+                code,
+                KeyCode::Char('d') | KeyCode::Delete | KeyCode::Char('y')
+            ))
+    {
+        actions.push(Action::CancelPendingDelete);
+        delete_pending = false;
+    }
+    if normal && code != KeyCode::Char('g') {
+        actions.push(Action::FlushPendingG);
+    }
+    if normal && app.pending_priority {
+        if let KeyCode::Char(c @ '1'..='3') = code {
+            actions.push(Action::SetPriority(Priority::from_level(c as u8 - b'0')));
+            return actions;
+        }
+        actions.push(Action::CancelPriorityPrefix);
+    }
+    let action = match app.mode {
+        InputMode::Normal => list(app, code, ctrl, delete_pending),
+        InputMode::Search => match code {
+            KeyCode::Esc => Some(Action::CancelInput),
+            KeyCode::Enter => Some(Action::SubmitInput),
+            KeyCode::Tab => Some(Action::CycleSearchScope),
+            KeyCode::Down => Some(Action::SelectNext),
+            KeyCode::Up => Some(Action::SelectPrevious),
+            KeyCode::Backspace => Some(Action::InputPop),
+            KeyCode::Char(c) => Some(Action::InputPush(c)),
+            _ => None,
+        },
+        InputMode::Editing
+        | InputMode::EditingDue
+        | InputMode::EditingTitle
+        | InputMode::Tagging
+        | InputMode::Watching => match code {
+            KeyCode::Esc => Some(Action::CancelInput),
+            KeyCode::Enter
+                if app.mode == InputMode::Editing && key.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                Some(Action::InputNewline)
+            }
+            KeyCode::Enter => Some(Action::SubmitInput),
+            KeyCode::Backspace => Some(Action::InputPop),
+            KeyCode::Delete => Some(Action::InputDelete),
+            KeyCode::Char('w') if ctrl => Some(Action::InputDeleteWord),
+            KeyCode::Left => Some(Action::InputLeft),
+            KeyCode::Right => Some(Action::InputRight),
+            KeyCode::Home => Some(Action::InputHome),
+            KeyCode::End => Some(Action::InputEnd),
+            KeyCode::Char(c) => Some(Action::InputPush(c)),
+            _ => None,
+        },
+    };
+    actions.extend(action);
+    actions
+}
+
+/// Pasted text (bracketed paste) typed into the open prompt: line breaks start notes
+/// lines when adding and become spaces elsewhere; other control chars are dropped.
+pub fn paste(app: &App, text: &str) -> Vec<Action> {
+    if app.locked || app.mode == InputMode::Normal {
+        return Vec::new();
+    }
+    let text = text.replace("\r\n", "\n");
+    text.trim_end_matches('\n')
+        .chars()
+        .filter_map(|c| match c {
+            '\n' | '\r' if app.mode == InputMode::Editing => Some(Action::InputNewline),
+            '\n' | '\r' | '\t' => Some(Action::InputPush(' ')),
+            c if c.is_control() => None,
+            c => Some(Action::InputPush(c)),
+        })
+        .collect()
+}
+
+/// Keys only spell out the unlock phrase; nothing reaches the list.
+fn lock_screen(code: KeyCode) -> Option<Action> {
+    match code {
+        KeyCode::Enter => Some(Action::ConfirmUnlock),
+        KeyCode::Backspace => Some(Action::UnlockPop),
+        KeyCode::Char(c) if !c.is_control() => Some(Action::UnlockPush(c)),
+        _ => None,
+    }
+}
+
+fn restore_offer(code: KeyCode) -> Option<Action> {
+    match code {
+        KeyCode::Char('y') | KeyCode::Enter => Some(Action::RestoreSession),
+        KeyCode::Char('n') | KeyCode::Esc => Some(Action::DiscardSession),
+        _ => None,
+    }
+}
+
+fn error_view(code: KeyCode) -> Option<Action> {
+    match code {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('E') => Some(Action::ToggleErrorView),
+        KeyCode::Char('q') => Some(Action::Quit),
+        KeyCode::Char('j') | KeyCode::Down => Some(Action::ScrollErrorView(1)),
+        KeyCode::Char('k') | KeyCode::Up => Some(Action::ScrollErrorView(-1)),
+        KeyCode::PageDown => Some(Action::ScrollErrorView(10)),
+        KeyCode::PageUp => Some(Action::ScrollErrorView(-10)),
+        KeyCode::Char('y') => Some(Action::CopyError),
+        _ => None,
+    }
+}
+
+fn help(app: &App, code: KeyCode) -> Option<Action> {
+    let full = app.help_mode == HelpMode::Full;
+    if full && app.help_searching {
+        return match code {
+            KeyCode::Esc => Some(Action::StopHelpSearch),
+            KeyCode::Enter => Some(Action::ConfirmHelpSearch {
+                first_match: help_matches(&app.help_search_query).first().copied(),
+            }),
+            KeyCode::Backspace => Some(Action::HelpSearchPop),
+            KeyCode::Char(c) if !c.is_control() => Some(Action::HelpSearchPush(c)),
+            _ => None,
+        };
+    }
+    match code {
+        KeyCode::Char('h') | KeyCode::Char('?') => Some(Action::ToggleHelpQuick),
+        KeyCode::Char('H') => Some(Action::ToggleHelpFull),
+        KeyCode::Esc => Some(Action::CloseHelp),
+        KeyCode::Char('q') => Some(Action::Quit),
+        KeyCode::Char('/') if full => Some(Action::StartHelpSearch),
+        KeyCode::Char('n') if full => next_help_match(app, true),
+        KeyCode::Char('N') if full => next_help_match(app, false),
+        KeyCode::Char('g') | KeyCode::Home => Some(Action::ScrollHelpTop),
+        KeyCode::Char('G') | KeyCode::End => Some(Action::ScrollHelp(10_000)),
+        KeyCode::Char('j') | KeyCode::Down => Some(Action::ScrollHelp(1)),
+        KeyCode::Char('k') | KeyCode::Up => Some(Action::ScrollHelp(-1)),
+        KeyCode::PageDown => Some(Action::ScrollHelp(10)),
+        KeyCode::PageUp => Some(Action::ScrollHelp(-10)),
+        _ => None,
+    }
+}
+
+/// `n` / `N` in the manual: the match after (or before) the current one, wrapping around.
+fn next_help_match(app: &App, forward: bool) -> Option<Action> {
+    let matches = help_matches(&app.help_search_query);
+    if matches.is_empty() {
+        return None;
+    }
+    let len = matches.len();
+    let cur = app.help_search_match.min(len - 1);
+    let index = if forward {
+        (cur + 1) % len
+    } else {
+        (cur + len - 1) % len
+    };
+    Some(Action::ShowHelpMatch {
+        index,
+        line: matches[index],
+    })
+}
+
+fn review(code: KeyCode) -> Option<Action> {
+    match code {
+        KeyCode::Esc => Some(Action::CancelReview),
+        KeyCode::Enter => Some(Action::SubmitReview),
+        KeyCode::Tab => Some(Action::CycleReviewEvent),
+        KeyCode::Backspace => Some(Action::ReviewPop),
+        KeyCode::Char(c) => Some(Action::ReviewPush(c)),
+        _ => None,
+    }
+}
+
+fn jump(code: KeyCode, ctrl: bool) -> Option<Action> {
+    match code {
+        KeyCode::Esc => Some(Action::CloseJump),
+        KeyCode::Enter => Some(Action::ConfirmJump),
+        KeyCode::Down => Some(Action::MoveJump(1)),
+        KeyCode::Up => Some(Action::MoveJump(-1)),
+        KeyCode::Char('n' | 'j') if ctrl => Some(Action::MoveJump(1)),
+        KeyCode::Char('p' | 'k') if ctrl => Some(Action::MoveJump(-1)),
+        KeyCode::Backspace => Some(Action::JumpPop),
+        KeyCode::Char(c) if !ctrl => Some(Action::JumpPush(c)),
+        _ => None,
+    }
+}
+
+fn detail(code: KeyCode) -> Option<Action> {
+    match code {
+        KeyCode::Esc | KeyCode::Char('i') => Some(Action::ToggleDetail),
+        KeyCode::Char('q') => Some(Action::Quit),
+        KeyCode::Char('j') | KeyCode::Down => Some(Action::SelectNext),
+        KeyCode::Char('k') | KeyCode::Up => Some(Action::SelectPrevious),
+        KeyCode::Char('T') => Some(Action::ToggleTimer),
+        KeyCode::Char('U') => Some(Action::UpdateBranch),
+        KeyCode::Char('C') => Some(Action::OpenCiChecks),
+        KeyCode::Char('R') => Some(Action::OpenReview),
+        KeyCode::Char('y') => Some(Action::Copy { title_only: false }),
+        KeyCode::Char('Y') => Some(Action::Copy { title_only: true }),
+        _ => None,
+    }
+}
+
+fn calendar(code: KeyCode) -> Option<Action> {
+    match code {
+        KeyCode::Esc => Some(Action::CloseCalendar),
+        KeyCode::Char('M') => Some(Action::OpenCalendar),
+        KeyCode::Char('q') => Some(Action::Quit),
+        KeyCode::Left | KeyCode::Char('h') => Some(Action::MoveCalendar(-1)),
+        KeyCode::Right | KeyCode::Char('l') => Some(Action::MoveCalendar(1)),
+        KeyCode::Up | KeyCode::Char('k') => Some(Action::MoveCalendar(-7)),
+        KeyCode::Down | KeyCode::Char('j') => Some(Action::MoveCalendar(7)),
+        KeyCode::Char('<') | KeyCode::PageUp => Some(Action::MoveCalendarMonth { forward: false }),
+        KeyCode::Char('>') | KeyCode::PageDown => Some(Action::MoveCalendarMonth { forward: true }),
+        KeyCode::Enter => Some(Action::ApplyCalendarDay),
+        _ => None,
+    }
+}
+
+fn inbox(code: KeyCode) -> Option<Action> {
+    match code {
+        KeyCode::Esc => Some(Action::SetView(View::List)),
+        KeyCode::Char('j') | KeyCode::Down => Some(Action::MoveInbox(1)),
+        KeyCode::Char('k') | KeyCode::Up => Some(Action::MoveInbox(-1)),
+        KeyCode::Char('g') | KeyCode::Home => Some(Action::InboxTop),
+        KeyCode::Char('G') | KeyCode::End => Some(Action::MoveInbox(isize::MAX)),
+        KeyCode::Enter => Some(Action::OpenNotification),
+        KeyCode::Char('m') => Some(Action::MarkNotificationRead),
+        KeyCode::Char('a') => Some(Action::AddNotificationTodo),
+        KeyCode::Char('r') | KeyCode::Char('4') => Some(Action::RefreshInbox),
+        _ => None,
+    }
+}
+
+/// `delete_pending`: a delete waits for `y`, and this key does not cancel it.
+fn list(app: &App, code: KeyCode, ctrl: bool, delete_pending: bool) -> Option<Action> {
+    let board = app.view == View::Board;
+    Some(match code {
+        KeyCode::Char('q') => Action::Quit,
+        KeyCode::Char('h') | KeyCode::Left if board => Action::MoveBoardColumn(-1),
+        KeyCode::Char('l') | KeyCode::Right if board => Action::MoveBoardColumn(1),
+        KeyCode::Char('H') if board => Action::ShiftBoard(-1),
+        KeyCode::Char('L') if board => Action::ShiftBoard(1),
+        KeyCode::Tab if board => Action::ToggleBoardLayout,
+        KeyCode::Char('j') | KeyCode::Down => Action::SelectNext,
+        KeyCode::Char('k') | KeyCode::Up => Action::SelectPrevious,
+        KeyCode::Char('d') if ctrl => Action::ScrollPage {
+            down: true,
+            half: true,
+        },
+        KeyCode::Char('u') if ctrl => Action::ScrollPage {
+            down: false,
+            half: true,
+        },
+        KeyCode::PageDown => Action::ScrollPage {
+            down: true,
+            half: false,
+        },
+        KeyCode::PageUp => Action::ScrollPage {
+            down: false,
+            half: false,
+        },
+        KeyCode::Char('G') | KeyCode::End => Action::SelectLast,
+        KeyCode::Home => Action::SelectFirst,
+        KeyCode::Char('p') if ctrl => Action::OpenJump,
+        KeyCode::Char('P') => Action::CyclePriority,
+        KeyCode::Char('p') => Action::StartPriorityPrefix,
+        KeyCode::Char('b') => Action::BlockSelected,
+        KeyCode::Char(']') => Action::ShiftDue(1),
+        KeyCode::Char('[') => Action::ShiftDue(-1),
+        KeyCode::Char('D') => Action::ClearDue,
+        KeyCode::Char('t') => Action::EditDue,
+        KeyCode::Char('T') => Action::ToggleTimer,
+        KeyCode::Char('i') => Action::ToggleDetail,
+        KeyCode::Char('C') => Action::OpenCiChecks,
+        KeyCode::Char('R') => Action::OpenReview,
+        KeyCode::Char('1') => Action::SetView(View::List),
+        KeyCode::Char('2') => Action::SetView(View::Agenda),
+        KeyCode::Char('3') => Action::SetView(View::Repos),
+        KeyCode::Char('4') => Action::SetView(View::Inbox),
+        KeyCode::Char('5') => Action::SetView(View::Board),
+        KeyCode::Char('z') => Action::CollapseGroup,
+        KeyCode::Char('Z') => Action::ToggleAllGroups,
+        KeyCode::Char('/') => Action::StartSearch,
+        KeyCode::Char('M') => Action::OpenCalendar,
+        KeyCode::Char('f') => Action::CycleRequestFilter,
+        KeyCode::Char('B') => Action::ToggleHideBots,
+        KeyCode::Char('s') => Action::ToggleShowScheduled,
+        KeyCode::Char('u') => Action::MarkSeen,
+        KeyCode::Char('o') => Action::ToggleSort,
+        KeyCode::Char('J') => Action::MoveSelected { down: true },
+        KeyCode::Char('K') => Action::MoveSelected { down: false },
+        KeyCode::Char('L') => Action::ToggleStatusLog,
+        KeyCode::Char('E') => Action::ToggleErrorView,
+        KeyCode::Char('e') => Action::EditTitle,
+        KeyCode::Char('x') => Action::ReviewConflicts,
+        KeyCode::Char('S') => Action::OpenSyncMenu,
+        KeyCode::Char('I') => Action::StartSync(SyncSource::Jira),
+        KeyCode::Char('v') => Action::ToggleMark,
+        KeyCode::Char('V') => Action::ToggleMarkAll,
+        KeyCode::Char('#') => Action::StartTagging,
+        KeyCode::Char('W') => Action::StartWatching,
+        KeyCode::Esc if !app.marked.is_empty() => Action::ClearMarks,
+        KeyCode::Esc if app.search.is_some() => Action::ClearSearch,
+        KeyCode::Esc if app.day_filter.is_some() => Action::ClearDayFilter,
+        KeyCode::Char('h') | KeyCode::Char('?') => Action::ToggleHelpQuick,
+        KeyCode::Char('H') => Action::ToggleHelpFull,
+        KeyCode::Char('a') | KeyCode::Char('n') => Action::StartAdd,
+        KeyCode::Char('A') => Action::StartAddSubtask,
+        KeyCode::Enter => Action::Activate,
+        KeyCode::Char(' ') => Action::ToggleSelected,
+        KeyCode::Char('w') => Action::CycleStatus,
+        KeyCode::Char('d') | KeyCode::Delete => Action::RequestDelete,
+        KeyCode::Char('y') if delete_pending => Action::ConfirmDelete,
+        KeyCode::Char('y') => Action::Copy { title_only: false },
+        KeyCode::Char('Y') => Action::Copy { title_only: true },
+        KeyCode::Char('c') => Action::ClearDone,
+        KeyCode::Char('r') => Action::Reload,
+        KeyCode::Char('g') => Action::PressG,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::repo::memory::InMemoryTodoRepo;
+    use crate::worker::Worker;
+
+    fn press(app: &App, code: KeyCode, modifiers: KeyModifiers) -> Vec<Action> {
+        actions(app, KeyEvent::new(code, modifiers))
+    }
+
+    fn typed(text: &str) -> Vec<Action> {
+        text.chars().map(Action::InputPush).collect()
+    }
+
+    #[test]
+    fn a_key_means_what_the_screen_shows() {
+        let mut app = App::new(
+            Box::new(InMemoryTodoRepo::default()),
+            Config::default(),
+            Vec::new(),
+            Worker::start().unwrap(),
+        );
+        let j = |app: &App| press(app, KeyCode::Char('j'), KeyModifiers::NONE);
+        assert_eq!(j(&app), [Action::FlushPendingG, Action::SelectNext]);
+        assert_eq!(
+            press(&app, KeyCode::Char('g'), KeyModifiers::NONE),
+            [Action::PressG]
+        );
+
+        app.update(Action::ToggleHelpFull);
+        assert_eq!(j(&app), [Action::ScrollHelp(1)]);
+        app.update(Action::CloseHelp);
+
+        app.update(Action::StartAdd);
+        assert_eq!(j(&app), [Action::InputPush('j')]);
+        assert_eq!(
+            press(&app, KeyCode::Enter, KeyModifiers::ALT),
+            [Action::InputNewline]
+        );
+        assert_eq!(
+            press(&app, KeyCode::Char('w'), KeyModifiers::CONTROL),
+            [Action::InputDeleteWord]
+        );
+        assert_eq!(
+            press(&app, KeyCode::Left, KeyModifiers::NONE),
+            [Action::InputLeft]
+        );
+        assert_eq!(
+            paste(&app, "buy\r\nmilk\n"),
+            [typed("buy"), vec![Action::InputNewline], typed("milk")].concat()
+        );
+        assert_eq!(
+            press(&app, KeyCode::Esc, KeyModifiers::NONE),
+            [Action::CancelInput]
+        );
+    }
+
+    #[test]
+    fn p_then_a_digit_sets_the_priority() {
+        let mut app = App::new(
+            Box::new(InMemoryTodoRepo::default()),
+            Config::default(),
+            Vec::new(),
+            Worker::start().unwrap(),
+        );
+        app.update(Action::StartAdd);
+        "ship it".chars().for_each(|c| {
+            app.update(Action::InputPush(c));
+        });
+        app.update(Action::SubmitInput);
+        let key = |app: &mut App, c: char| {
+            for action in press(app, KeyCode::Char(c), KeyModifiers::NONE) {
+                app.update(action);
+            }
+        };
+
+        key(&mut app, 'p');
+        assert_eq!(
+            press(&app, KeyCode::Char('3'), KeyModifiers::NONE),
+            [Action::FlushPendingG, Action::SetPriority(Priority::Low)]
+        );
+        key(&mut app, '3');
+        assert_eq!(app.todos[0].priority, Priority::Low);
+        assert_eq!(app.view, View::List);
+
+        // Any other key drops the prefix: `2` switches views again.
+        key(&mut app, 'p');
+        key(&mut app, 'j');
+        assert_eq!(
+            press(&app, KeyCode::Char('2'), KeyModifiers::NONE),
+            [Action::FlushPendingG, Action::SetView(View::Agenda)]
+        );
+    }
+
+    #[test]
+    fn only_the_unlock_phrase_leaves_the_lock_screen() {
+        let mut app = App::new(
+            Box::new(InMemoryTodoRepo::default()),
+            Config::default(),
+            Vec::new(),
+            Worker::start().unwrap(),
+        );
+        app.locked = true;
+        let key = |app: &mut App, code: KeyCode| {
+            for action in press(app, code, KeyModifiers::NONE) {
+                app.update(action);
+            }
+        };
+
+        assert_eq!(
+            press(&app, KeyCode::Char('q'), KeyModifiers::NONE),
+            [Action::UnlockPush('q')]
+        );
+        assert!(paste(&app, "unlock").is_empty());
+        for code in [KeyCode::Char('x'), KeyCode::Esc, KeyCode::Enter] {
+            key(&mut app, code);
+            assert!(app.locked);
+        }
+
+        for c in "unlocx".chars() {
+            key(&mut app, KeyCode::Char(c));
+        }
+        key(&mut app, KeyCode::Backspace);
+        key(&mut app, KeyCode::Char('k'));
+        assert!(app.locked);
+        key(&mut app, KeyCode::Enter);
+        assert!(!app.locked);
+        assert!(app.unlock_input.is_empty());
+    }
+
+    #[test]
+    fn ctrl_p_jumps_to_a_fuzzy_match() {
+        let mut app = App::new(
+            Box::new(InMemoryTodoRepo::default()),
+            Config::default(),
+            Vec::new(),
+            Worker::start().unwrap(),
+        );
+        for title in ["Write release notes", "Reply to Sam", "Renew the domain"] {
+            app.update(Action::StartAdd);
+            title.chars().for_each(|c| {
+                app.update(Action::InputPush(c));
+            });
+            app.update(Action::SubmitInput);
+        }
+        let press_all = |app: &mut App, code: KeyCode, modifiers: KeyModifiers| {
+            for action in press(app, code, modifiers) {
+                app.update(action);
+            }
+        };
+
+        press_all(&mut app, KeyCode::Char('p'), KeyModifiers::CONTROL);
+        assert!(app.jump.is_some());
+        for c in "rnotes".chars() {
+            press_all(&mut app, KeyCode::Char(c), KeyModifiers::NONE);
+        }
+        assert_eq!(app.jump_matches().len(), 1);
+        press_all(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert!(app.jump.is_none());
+        assert_eq!(app.todos[app.selected].title, "Write release notes");
+
+        // Letters are typed into the finder, not taken as list keys.
+        press_all(&mut app, KeyCode::Char('p'), KeyModifiers::CONTROL);
+        press_all(&mut app, KeyCode::Char('d'), KeyModifiers::NONE);
+        assert_eq!(app.jump.as_ref().unwrap().query, "d");
+        assert_eq!(app.todos.len(), 3);
+        press_all(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+        assert!(app.jump.is_none());
+    }
+}
@@ -1,208 +1,289 @@
+mod board;
+mod calendar;
+mod histogram;
+mod keys;
+mod text;
+
 use std::io::{Stdout, stdout};
-use std::time::{Duration, Instant};
+use std::sync::{Arc, mpsc};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::Result;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyEvent, KeyEventKind},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::{
     Terminal,
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout, Margin, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState, Wrap},
+    widgets::{
+        Block, Borders, Cell, Clear, Paragraph, Row, Scrollbar, ScrollbarOrientation,
+        ScrollbarState, Table, TableState, Wrap,
+    },
 };
 
-use crate::app::{App, HelpMode, InputMode};
-use crate::domain::todo::{Priority, Todo};
-use time::{OffsetDateTime, macros::format_description};
+use crate::app::{
+    App, ConflictChoice, ErrorReport, HelpMode, InputMode, JumpFinder, LinkPicker, RequestFilter,
+    ReviewDraft, SyncSource, TitleConflict, View, format_minutes,
+};
+use crate::domain::clock;
+use crate::domain::due::{self, Due, DueBucket};
+use crate::domain::time_entry;
+use crate::domain::todo::{Priority, Status, Todo};
+use crate::repo::github::model::{CiCheckState, CiState, MergeBlockers, Pr, PrLabel, ReviewEvent};
+use crate::repo::session::SessionSnapshot;
+use crate::usecase::attention;
+use time::{Date, macros::format_description};
+use unicode_segmentation::UnicodeSegmentation;
 
 pub fn run(mut app: App, tick_rate: Duration) -> Result<()> {
+    restore_terminal_on_panic();
     enable_raw_mode()?;
     let mut stdout = stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
+    // Whatever ends the loop, the terminal is handed back before the error is printed.
+    let res = event_loop(&mut terminal, &mut app, tick_rate);
+    if res.is_ok() {
+        app.end_session();
+    }
+    cleanup_terminal(&mut terminal)?;
+    res
+}
+
+fn event_loop(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    app: &mut App,
+    tick_rate: Duration,
+) -> Result<()> {
+    // Key presses, resizes and background-job notifications all arrive on one channel,
+    // so the loop sleeps until something happens and redraws only then.
+    let (tx, rx) = mpsc::channel();
+    spawn_input_reader(tx.clone());
+    app.set_notify(Arc::new(move || {
+        let _ = tx.send(UiEvent::Wake);
+    }));
+
+    let mut dirty = true;
     let mut last_tick = Instant::now();
-    let res = loop {
-        app.poll_sync();
-        terminal.draw(|f| draw(f, &app))?;
+    let mut last_draw = Instant::now();
+    loop {
+        dirty |= app.poll_sync();
+        dirty |= app.poll_pr_action();
+        dirty |= app.poll_inbox();
+        dirty |= app.poll_daemon();
+        let animating = app.is_animating();
+        // Relative times ("3m ago") go stale slowly; refresh them now and then.
+        if dirty
+            || (animating && last_draw.elapsed() >= tick_rate)
+            || last_draw.elapsed() >= IDLE_REDRAW
+        {
+            terminal.draw(|f| draw(f, app))?;
+            dirty = false;
+            last_draw = Instant::now();
+        }
 
-        let timeout = tick_rate
+        let tick = if animating {
+            tick_rate
+        } else {
+            IDLE_TICK.max(tick_rate)
+        };
+        let timeout = tick
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| Duration::from_secs(0));
-
-        if event::poll(timeout)?
-            && let Event::Key(key) = event::read()?
-            && key.kind == KeyEventKind::Press
-            && handle_key(&mut app, key.code)?
-        {
-            break Ok(());
+        match rx.recv_timeout(timeout) {
+            Ok(UiEvent::Input(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                dirty = true;
+                app.register_input();
+                if handle_key(app, key) {
+                    return Ok(());
+                }
+            }
+            Ok(UiEvent::Input(Event::Paste(text))) => {
+                dirty = true;
+                app.register_input();
+                for action in keys::paste(app, &text) {
+                    app.update(action);
+                }
+            }
+            Ok(UiEvent::Input(Event::Resize(..))) => dirty = true,
+            Ok(UiEvent::Input(_) | UiEvent::Wake) => {}
+            Ok(UiEvent::InputError(e)) => return Err(e.into()),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
         }
 
-        if last_tick.elapsed() >= tick_rate {
+        if last_tick.elapsed() >= tick {
+            dirty |= app.on_tick();
             last_tick = Instant::now();
         }
-    };
+    }
+}
 
-    cleanup_terminal(&mut terminal)?;
-    res
+/// Leave raw mode and the alternate screen before a panic message is printed, so it is
+/// readable and the shell is usable afterwards.
+fn restore_terminal_on_panic() {
+    let default = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        let _ = execute!(
+            stdout(),
+            DisableBracketedPaste,
+            LeaveAlternateScreen,
+            crossterm::cursor::Show
+        );
+        default(info);
+    }));
 }
 
-fn handle_key(app: &mut App, code: KeyCode) -> Result<bool> {
-    if app.mode == InputMode::Normal && app.help_mode != HelpMode::None {
-        if app.help_mode == HelpMode::Full && app.help_searching {
-            match code {
-                KeyCode::Esc => {
-                    app.help_searching = false;
-                }
-                KeyCode::Enter => {
-                    if let Some(line) = help_matches(&app.help_search_query).first().copied() {
-                        app.help_scroll = (line.saturating_sub(1)) as u16;
-                        app.help_search_match = 0;
-                    }
-                    app.help_searching = false;
-                }
-                KeyCode::Backspace => {
-                    app.help_search_query.pop();
-                    app.help_search_match = 0;
-                }
-                KeyCode::Char(c) => {
-                    if !c.is_control() {
-                        app.help_search_query.push(c);
-                        app.help_search_match = 0;
-                    }
-                }
-                _ => {}
-            }
-            return Ok(false);
-        }
-
-        match code {
-            KeyCode::Char('h') | KeyCode::Char('?') => app.toggle_help_quick(),
-            KeyCode::Char('H') => app.toggle_help_full(),
-            KeyCode::Esc => app.close_help(),
-            KeyCode::Char('q') => return Ok(true),
-            KeyCode::Char('/') if app.help_mode == HelpMode::Full => {
-                app.help_searching = true;
-                app.help_search_query.clear();
-                app.help_search_match = 0;
-            }
-            KeyCode::Char('n') if app.help_mode == HelpMode::Full => {
-                jump_to_next_match(app, true);
-            }
-            KeyCode::Char('N') if app.help_mode == HelpMode::Full => {
-                jump_to_next_match(app, false);
-            }
-            KeyCode::Char('g') | KeyCode::Home => app.help_scroll = 0,
-            KeyCode::Char('G') | KeyCode::End => {
-                app.help_scroll = app.help_scroll.saturating_add(10_000)
-            }
-            KeyCode::Char('j') | KeyCode::Down => {
-                app.help_scroll = app.help_scroll.saturating_add(1)
-            }
-            KeyCode::Char('k') | KeyCode::Up => app.help_scroll = app.help_scroll.saturating_sub(1),
-            KeyCode::PageDown => app.help_scroll = app.help_scroll.saturating_add(10),
-            KeyCode::PageUp => app.help_scroll = app.help_scroll.saturating_sub(10),
-            _ => {}
-        }
-        return Ok(false);
-    }
+/// Tick while nothing on screen moves by itself (idle lock, day rollover).
+const IDLE_TICK: Duration = Duration::from_secs(1);
+/// Redraw at least this often, for relative times.
+const IDLE_REDRAW: Duration = Duration::from_secs(30);
 
-    match app.mode {
-        InputMode::Normal => match code {
-            KeyCode::Char('q') => return Ok(true),
-            KeyCode::Char('j') | KeyCode::Down => app.select_next(),
-            KeyCode::Char('k') | KeyCode::Up => app.select_previous(),
-            KeyCode::Char('P') => app.cycle_priority_selected(),
-            KeyCode::Char(']') => app.shift_due_selected(1),
-            KeyCode::Char('[') => app.shift_due_selected(-1),
-            KeyCode::Char('D') => app.clear_due_selected(),
-            KeyCode::Char('t') => app.edit_due(),
-            KeyCode::Char('h') | KeyCode::Char('?') => app.toggle_help_quick(),
-            KeyCode::Char('H') => app.toggle_help_full(),
-            KeyCode::Char('a') | KeyCode::Char('n') => {
-                app.mode = InputMode::Editing;
-                app.input.clear();
-                app.set_status("Type new task and press Enter");
-            }
-            KeyCode::Enter => {
-                if !app.open_selected_link() {
-                    app.toggle_selected();
+enum UiEvent {
+    Input(Event),
+    InputError(std::io::Error),
+    /// A background job sent progress or a result.
+    Wake,
+}
+
+/// Blocking terminal reads on their own thread, forwarded to the UI loop.
+fn spawn_input_reader(tx: mpsc::Sender<UiEvent>) {
+    std::thread::spawn(move || {
+        loop {
+            let event = match event::read() {
+                Ok(event) => UiEvent::Input(event),
+                Err(e) => {
+                    let _ = tx.send(UiEvent::InputError(e));
+                    return;
                 }
+            };
+            if tx.send(event).is_err() {
+                return;
             }
-            KeyCode::Char(' ') => app.toggle_selected(),
-            KeyCode::Char('d') | KeyCode::Delete => app.delete_selected(),
-            KeyCode::Char('c') => app.clear_done(),
-            KeyCode::Char('r') => {
-                app.reload();
-                app.set_status("Reloaded");
-            }
-            KeyCode::Char('g') => {
-                app.start_sync_github();
-            }
-            _ => {}
-        },
-        InputMode::Editing => match code {
-            KeyCode::Esc => {
-                app.mode = InputMode::Normal;
-                app.input.clear();
-                app.set_status("Canceled");
-            }
-            KeyCode::Enter => app.add_todo(),
-            KeyCode::Backspace => {
-                app.input.pop();
-            }
-            KeyCode::Char(c) => app.input.push(c),
-            _ => {}
-        },
-        InputMode::EditingDue => match code {
-            KeyCode::Esc => {
-                app.mode = InputMode::Normal;
-                app.input.clear();
-                app.set_status("Canceled");
-            }
-            KeyCode::Enter => app.apply_due_edit(),
-            KeyCode::Backspace => {
-                app.input.pop();
-            }
-            KeyCode::Char(c) => app.input.push(c),
-            _ => {}
-        },
-    }
+        }
+    });
+}
 
-    Ok(false)
+/// Apply what `key` means; true when the app should exit.
+fn handle_key(app: &mut App, key: KeyEvent) -> bool {
+    keys::actions(app, key)
+        .into_iter()
+        .any(|action| app.update(action))
 }
 
-fn draw(f: &mut ratatui::Frame, app: &App) {
+fn draw(f: &mut ratatui::Frame, app: &mut App) {
     let size = f.area();
+    if app.locked {
+        f.render_widget(render_lock_screen(app, size.height), size);
+        return;
+    }
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3),
+            Constraint::Length(4),
             Constraint::Min(1),
-            Constraint::Length(3),
+            Constraint::Length(hint_height(app)),
+            Constraint::Length(footer_height(app, size.width)),
         ])
         .split(size);
 
     let header = render_header(app);
     f.render_widget(header, chunks[0]);
 
-    let mut table_state = TableState::default();
-    if !app.todos.is_empty() {
-        table_state.select(Some(app.selected));
-    }
+    let main_area = match app.calendar.as_ref() {
+        Some(cal) => {
+            let cols = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(20), Constraint::Length(calendar::WIDTH)])
+                .split(chunks[1]);
+            f.render_widget(calendar::render(app, cal), cols[1]);
+            cols[0]
+        }
+        None => chunks[1],
+    };
 
-    let table = render_table(&app.todos);
-    f.render_stateful_widget(table, chunks[1], &mut table_state);
+    render_main(f, app, main_area);
 
+    f.render_widget(render_hints(app), chunks[2]);
     let footer = render_footer(app);
-    f.render_widget(footer, chunks[2]);
+    f.render_widget(footer, chunks[3]);
+
+    if app.show_detail
+        && let Some(todo) = app.todos.get(app.selected)
+    {
+        let area = centered_rect(70, 60, size);
+        f.render_widget(Clear, area);
+        f.render_widget(render_detail(app, todo), area);
+    }
+
+    if let Some(view) = &app.ci_checks {
+        let area = centered_rect(80, 60, size);
+        let mut state = TableState::default();
+        state.select(Some(view.selected));
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(render_ci_checks(app, &view.pr_key), area, &mut state);
+    }
+
+    if let Some(picker) = &app.link_picker {
+        let area = centered_rect(70, 40, size);
+        let mut state = TableState::default();
+        state.select(Some(picker.selected));
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(render_link_picker(picker), area, &mut state);
+    }
+
+    if let Some(jump) = &app.jump {
+        let area = centered_rect(60, 50, size);
+        f.render_widget(Clear, area);
+        f.render_widget(render_jump(app, jump, area), area);
+    }
+
+    if let Some(row) = app.sync_menu {
+        let area = centered_rect(60, 40, size);
+        let mut state = TableState::default();
+        state.select(Some(row));
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(render_sync_menu(app), area, &mut state);
+    }
+
+    if let Some(scroll) = app.status_log_view {
+        let area = centered_rect(70, 60, size);
+        f.render_widget(Clear, area);
+        f.render_widget(render_status_log(app, scroll), area);
+    }
+
+    if let Some(snapshot) = &app.restore_offer {
+        let area = centered_rect(60, 30, size);
+        f.render_widget(Clear, area);
+        f.render_widget(render_restore_offer(snapshot), area);
+    }
+
+    if let Some(conflict) = &app.conflict {
+        let area = centered_rect(70, 40, size);
+        f.render_widget(Clear, area);
+        f.render_widget(render_conflict(conflict), area);
+    }
+
+    if let Some(draft) = &app.review {
+        let area = centered_rect(70, 40, size);
+        f.render_widget(Clear, area);
+        f.render_widget(render_review(draft), area);
+    }
+
+    if let (Some(scroll), Some(report)) = (app.error_view, &app.last_error) {
+        let area = centered_rect(70, 50, size);
+        f.render_widget(Clear, area);
+        f.render_widget(render_error(report, scroll), area);
+    }
 
     if app.help_mode != HelpMode::None {
         // Keep a consistent 1-cell padding around the help modal, since percentage-based centering
@@ -216,120 +297,1309 @@ fn draw(f: &mut ratatui::Frame, app: &App) {
     }
 }
 
+/// The table of the current view, scrolled to keep the selection visible, or the board.
+fn render_main(f: &mut ratatui::Frame, app: &mut App, main_area: Rect) {
+    // Borders and the column header take three rows.
+    let visible_rows = main_area.height.saturating_sub(3) as usize;
+    let width = title_width(main_area, TODO_COLUMNS);
+    let (table, selected_row, row_count) = match app.view {
+        View::List => (
+            render_table(app, width),
+            (!app.todos.is_empty()).then_some(app.selected),
+            app.todos.len(),
+        ),
+        View::Agenda => render_agenda(app, width),
+        View::Repos => render_repo_groups(app, width),
+        View::Inbox => render_inbox(app, title_width(main_area, INBOX_COLUMNS)),
+        View::Board => {
+            // Borders take two rows.
+            app.page_rows = main_area.height.saturating_sub(2) as usize;
+            board::render(f, app, main_area);
+            return;
+        }
+    };
+    // Never scroll past the last full page; ratatui then moves the offset just enough to
+    // keep the selection visible.
+    let mut table_state = TableState::default()
+        .with_offset(app.table_offset.min(row_count.saturating_sub(visible_rows)))
+        .with_selected(selected_row);
+    f.render_stateful_widget(table, main_area, &mut table_state);
+    app.table_offset = table_state.offset();
+    app.page_rows = visible_rows;
+    if row_count > visible_rows {
+        let mut scrollbar = ScrollbarState::new(row_count - visible_rows + 1)
+            .position(app.table_offset)
+            .viewport_content_length(visible_rows);
+        f.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+            main_area.inner(Margin::new(0, 1)),
+            &mut scrollbar,
+        );
+    }
+}
+
 fn render_header(app: &App) -> Paragraph<'static> {
     let total = app.todos.len();
-    let done = app.todos.iter().filter(|t| t.done).count();
+    let done = app.todos.iter().filter(|t| t.is_closed()).count();
     let summary = format!("Open: {} / All: {}", total.saturating_sub(done), total);
     let mut spans = vec![
         Span::styled("koto - todo", Style::default().fg(Color::Cyan)),
         Span::raw("  |  "),
         Span::styled(summary, Style::default().fg(Color::Yellow)),
     ];
-    if app.is_syncing {
+    if app.planned_today > 0 {
+        let capacity = app.config.time.daily_capacity_minutes;
+        let mut text = format!("Today: {}", format_minutes(app.planned_today));
+        if capacity > 0 {
+            text.push_str(&format!(" / {}", format_minutes(capacity)));
+        }
+        let style = match app.over_capacity() {
+            Some(over) => {
+                text.push_str(&format!(" ⚠ {} over capacity", format_minutes(over)));
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+            }
+            None => Style::default().fg(Color::Green),
+        };
+        spans.push(Span::raw("  |  "));
+        spans.push(Span::styled(text, style));
+    }
+    if !app.github.is_empty() {
+        spans.push(Span::raw("  |  "));
+        spans.push(Span::styled(
+            format!(
+                "gh: {}",
+                app.github_login.as_deref().unwrap_or("(not synced yet)")
+            ),
+            Style::default().fg(Color::Blue),
+        ));
+    }
+    if let Some(entry) = app.running_entry() {
+        let title = app
+            .todos
+            .iter()
+            .find(|t| t.id == entry.todo_id)
+            .map(|t| t.title.as_str())
+            .unwrap_or("?");
         spans.push(Span::raw("  |  "));
         spans.push(Span::styled(
-            "⏳ Syncing GitHub...",
+            format!(
+                "⏱ {} {}",
+                time_entry::format_duration(entry.duration(SystemTime::now())),
+                title
+            ),
+            Style::default().fg(Color::Green),
+        ));
+    }
+    if app.is_syncing() {
+        let labels: Vec<String> = app
+            .syncing_sources()
+            .into_iter()
+            .map(|source| match app.sync_progress.get(&source) {
+                Some(p) => format!("{}: {} pages, {} PRs", source.label(), p.pages, p.prs),
+                None => format!("{}...", source.label()),
+            })
+            .collect();
+        spans.push(Span::raw("  |  "));
+        spans.push(Span::styled(
+            format!("⏳ Syncing {}", labels.join(" ; ")),
             Style::default().fg(Color::Magenta),
         ));
     }
-    let line = Line::from(spans);
-    Paragraph::new(line)
+    let lines = vec![Line::from(spans), histogram::line(app)];
+    Paragraph::new(lines)
         .block(Block::default().title("Overview").borders(Borders::ALL))
         .wrap(Wrap { trim: true })
 }
 
-fn render_table(todos: &[Todo]) -> Table<'_> {
+/// `title_width`: columns of the title cell; longer titles end in "…" and keep their badges.
+fn render_todo_row<'a>(app: &App, todo: &'a Todo, title_width: usize) -> Row<'a> {
+    let pri = render_priority(todo.priority);
+    let effective_due = app.effective_due(todo);
+    let (mut due_text, mut due_style) = render_due(effective_due.map(|d| d.due()), app.today);
+    if effective_due.is_some_and(|d| d.inherited) {
+        due_style = due_style.add_modifier(Modifier::DIM);
+    } else if !todo.is_closed() && app.hierarchy.due_after_parent(todo.id) {
+        due_text.push_str(" ⚠");
+        due_style = due_style.add_modifier(Modifier::BOLD);
+    }
+    let symbol = if todo.archived {
+        "▣"
+    } else {
+        match todo.status {
+            Status::Todo => "•",
+            Status::InProgress => "▶",
+            Status::Blocked => "⊘",
+            Status::Done => "✔",
+            Status::Cancelled => "✗",
+        }
+    };
+    let indent = "  ".repeat(app.hierarchy.depth(todo.id));
+    let branch = if indent.is_empty() { "" } else { "↳ " };
+    let mut title = Vec::new();
+    let marked = app.marked.contains(&todo.id);
+    if marked {
+        title.push(Span::styled(
+            "◆ ",
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    title.push(Span::raw(format!(
+        "{indent}{branch}{symbol} {}",
+        todo.title
+    )));
+    if !todo.is_closed() && app.blockers.contains_key(&todo.id) {
+        title.push(Span::styled(" 🔒", Style::default().fg(Color::Yellow)));
+    }
+    if todo.notes.is_some() {
+        title.push(Span::styled(" ✎", Style::default().fg(Color::DarkGray)));
+    }
+    if todo.incoming_title.is_some() {
+        title.push(Span::styled(" ⇄", Style::default().fg(Color::Yellow)));
+    }
+    if let Some(minutes) = todo.estimate_minutes {
+        title.push(Span::styled(
+            format!(" ~{}", format_minutes(minutes)),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    if let Some(start) = todo.start_at
+        && todo.is_scheduled(SystemTime::now())
+    {
+        let date = clock::to_local(start)
+            .format(format_description!("[year]-[month]-[day]"))
+            .unwrap_or_default();
+        title.push(Span::styled(
+            format!(" ⏳ {date}"),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    if let Some(pr) = app.pr_for(todo)
+        && !todo.is_closed()
+    {
+        if attention::is_unread(todo, pr) {
+            title.push(Span::styled(
+                " ●",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        if attention::score(pr, crate::now_unix()) >= attention::URGENT_SCORE {
+            title.push(Span::styled(
+                " ⚑",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ));
+        }
+        if pr.is_draft {
+            title.push(Span::styled(
+                " draft",
+                Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::DIM),
+            ));
+        }
+        if let Some(stats) = pr.diff_stats {
+            let size = stats.size();
+            let color = match size {
+                "XS" | "S" => Color::Green,
+                "M" => Color::Yellow,
+                _ => Color::Red,
+            };
+            title.push(Span::styled(format!(" {size}"), Style::default().fg(color)));
+        }
+        if pr.is_team_request() {
+            title.push(Span::styled(
+                format!(" @{}", pr.requested_teams.join(",@")),
+                Style::default().fg(Color::Cyan),
+            ));
+        }
+        if let Some(blockers) = pr.merge_blockers.as_ref() {
+            title.extend(merge_blocker_badges(blockers));
+        }
+    }
+
+    let row_style = if todo.archived {
+        Style::default()
+            .fg(Color::DarkGray)
+            .add_modifier(Modifier::ITALIC)
+    } else if todo.is_closed() {
+        Style::default()
+            .fg(Color::DarkGray)
+            .add_modifier(Modifier::CROSSED_OUT)
+    } else if app.is_flashing(todo.id) {
+        Style::default()
+            .fg(Color::Black)
+            .bg(Color::Green)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    };
+
+    let age = match app.pr_for(todo) {
+        Some(pr) if !todo.is_closed() => {
+            render_pr_age(pr.updated_at_unix, app.config.github.stale_after_days)
+        }
+        _ => Cell::from(""),
+    };
+
+    Row::new(vec![
+        Cell::from(pri),
+        Cell::from(due_text).style(due_style),
+        age,
+        Cell::from(Line::from(text::fit_spans(
+            title,
+            marked as usize,
+            title_width,
+        ))),
+    ])
+    .style(row_style)
+}
+
+/// Time since the PR was last updated; stale PRs (`[github] stale_after_days`) stand out.
+fn render_pr_age(updated_at_unix: i64, stale_after_days: u64) -> Cell<'static> {
+    let secs = (crate::now_unix() - updated_at_unix).max(0) as u64;
+    let days = secs / 86_400;
+    let text = if days == 0 {
+        format!("{}h", secs / 3600)
+    } else {
+        format!("{days}d")
+    };
+    if stale_after_days > 0 && days >= stale_after_days {
+        Cell::from(format!("⌛{text}"))
+            .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+    } else {
+        Cell::from(text).style(Style::default().fg(Color::Gray))
+    }
+}
+
+/// Compact badges such as `✗CI`, `⚠conflict`, `0/2 approvals` for a PR's merge blockers.
+fn merge_blocker_badges(blockers: &MergeBlockers) -> Vec<Span<'static>> {
+    let mut badges = Vec::new();
+    let mut push = |text: String, color: Color| {
+        badges.push(Span::raw(" "));
+        badges.push(Span::styled(text, Style::default().fg(color)));
+    };
+    if !blockers.failing_required_checks.is_empty() {
+        push("✗CI".to_string(), Color::Red);
+    }
+    if blockers.has_conflicts {
+        push("⚠conflict".to_string(), Color::Yellow);
+    }
+    if let Some(required) = blockers.required_approvals
+        && blockers.current_approvals < required
+    {
+        push(
+            format!("{}/{required} approvals", blockers.current_approvals),
+            Color::Magenta,
+        );
+    }
+    if blockers.is_behind_base {
+        push("↓behind".to_string(), Color::Blue);
+    }
+    badges
+}
+
+/// Widths of the priority, due and age columns; the title takes the rest.
+const TODO_COLUMNS: [u16; 3] = [10, 26, 6];
+/// Reason, kind and age in the inbox.
+const INBOX_COLUMNS: [u16; 3] = [18, 12, 6];
+const COLUMN_SPACING: u16 = 2;
+
+/// Columns left for the last (title) column of a table drawn in `area` after `fixed`:
+/// minus the borders, the highlight symbol and the gaps between columns.
+fn title_width(area: Rect, fixed: [u16; 3]) -> usize {
+    let taken: u16 = fixed.iter().sum::<u16>() + 3 * COLUMN_SPACING;
+    area.width.saturating_sub(2 + 2 + taken).max(20) as usize
+}
+
+fn todo_table<'a>(rows: Vec<Row<'a>>, block_title: String) -> Table<'a> {
+    let [priority, due, age] = TODO_COLUMNS;
+    Table::new(
+        rows,
+        [
+            Constraint::Length(priority),
+            Constraint::Length(due),
+            Constraint::Length(age),
+            Constraint::Min(20),
+        ],
+    )
+    .header(
+        Row::new(vec!["Priority", "Due", "Age", "Title"])
+            .style(Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED)),
+    )
+    .block(Block::default().title(block_title).borders(Borders::ALL))
+    .column_spacing(COLUMN_SPACING)
+    .highlight_symbol("➤ ")
+    .row_highlight_style(
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+    )
+}
+
+fn render_table(app: &App, title_width: usize) -> Table<'_> {
+    let todos = &app.todos;
     let rows: Vec<Row> = todos
         .iter()
-        .map(|todo| {
-            let pri = render_priority(todo.priority);
-            let (due_text, due_style) = render_due(todo.due);
-            let symbol = if todo.done { "✔" } else { "•" };
-            let title = format!("{symbol} {}", todo.title);
+        .map(|todo| render_todo_row(app, todo, title_width))
+        .collect();
 
-            let row_style = if todo.done {
+    let block_title = match (app.search.as_ref(), app.day_filter) {
+        (_, Some(day)) => format!("Due {day} — {} item(s) (Esc clear ; M calendar)", todos.len()),
+        (Some(q), None) => format!(
+            "Search [{}] \"{}\" — {} match(es) (Tab scope ; Esc clear)",
+            q.scope.label(),
+            q.text,
+            todos.len()
+        ),
+        (None, None) if app.request_filter != RequestFilter::All => format!(
+            "Todos — {} ({} item(s) ; f cycle filter)",
+            app.request_filter.label(),
+            todos.len()
+        ),
+        (None, None) => "Todos (h help ; H manual ; j/k move ; PgUp/PgDn ^u/^d gg/G scroll ; a/n add ; A subtask ; e rename ; / search ; Enter open link ; Space toggle ; w status ; P cycle prio ; p1/p2/p3 set prio ; t set due ; [/ ] shift due ; T timer ; i detail ; C CI checks ; R review ; y/Y copy ; D clear due ; d delete ; c archive done ; g sync ; S sync menu ; I Jira ; f direct/team filter ; o sort ; J/K reorder ; L messages)".to_string(),
+    };
+
+    todo_table(rows, block_title)
+}
+
+/// Agenda table, the row index of the selected todo (section headers take rows too) and
+/// the number of rows.
+fn render_agenda(app: &App, title_width: usize) -> (Table<'_>, Option<usize>, usize) {
+    let mut rows = Vec::new();
+    let mut selected_row = None;
+    for (bucket, items) in app.agenda() {
+        if items.is_empty() {
+            continue;
+        }
+        let color = match bucket {
+            DueBucket::Overdue => Color::Red,
+            DueBucket::Today | DueBucket::Tomorrow => Color::Yellow,
+            DueBucket::ThisWeek | DueBucket::Later => Color::Green,
+        };
+        rows.push(
+            Row::new(vec![
+                Cell::from(""),
+                Cell::from(""),
+                Cell::from(""),
+                Cell::from(format!("── {} ({}) ──", bucket.label(), items.len())),
+            ])
+            .style(Style::default().fg(color).add_modifier(Modifier::BOLD)),
+        );
+        for idx in items {
+            if idx == app.selected {
+                selected_row = Some(rows.len());
+            }
+            rows.push(render_todo_row(app, &app.todos[idx], title_width));
+        }
+    }
+    let title = "Agenda (1 list ; 2 agenda ; open items due within a week)".to_string();
+    let row_count = rows.len();
+    (todo_table(rows, title), selected_row, row_count)
+}
+
+/// Repository view: a header per group, folded groups reduced to their header. Returns
+/// what `render_agenda` does.
+fn render_repo_groups(app: &App, title_width: usize) -> (Table<'_>, Option<usize>, usize) {
+    let mut rows = Vec::new();
+    let mut selected_row = None;
+    for (group, items) in app.repo_groups() {
+        let folded = app.collapsed_groups.contains(&group);
+        let open = items.iter().filter(|&&i| !app.todos[i].is_closed()).count();
+        let marker = if folded { "▸" } else { "▾" };
+        rows.push(
+            Row::new(vec![
+                Cell::from(""),
+                Cell::from(""),
+                Cell::from(""),
+                Cell::from(format!("{marker} {group} ({open} open / {})", items.len())),
+            ])
+            .style(
                 Style::default()
-                    .fg(Color::DarkGray)
-                    .add_modifier(Modifier::CROSSED_OUT)
+                    .fg(if folded { Color::DarkGray } else { Color::Cyan })
+                    .add_modifier(Modifier::BOLD),
+            ),
+        );
+        if folded {
+            continue;
+        }
+        for idx in items {
+            if idx == app.selected {
+                selected_row = Some(rows.len());
+            }
+            rows.push(render_todo_row(app, &app.todos[idx], title_width));
+        }
+    }
+    let title = "By repository (1 list ; 3 repos ; z fold group ; Z fold / unfold all)".to_string();
+    let row_count = rows.len();
+    (todo_table(rows, title), selected_row, row_count)
+}
+
+/// Notifications inbox: unread ones bold, ones marked read dimmed until the next refresh.
+fn render_inbox(app: &App, title_width: usize) -> (Table<'_>, Option<usize>, usize) {
+    let notifications = app.inbox.as_deref().unwrap_or_default();
+    let rows: Vec<Row> = notifications
+        .iter()
+        .map(|n| {
+            let style = if n.unread {
+                Style::default().add_modifier(Modifier::BOLD)
             } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            Row::new(vec![
+                Cell::from(n.reason.replace('_', " ")),
+                Cell::from(n.kind.as_str()),
+                render_pr_age(n.updated_at_unix, 0),
+                Cell::from(
+                    text::truncate(&format!("{}: {}", n.repo, n.title), title_width).into_owned(),
+                ),
+            ])
+            .style(style)
+        })
+        .collect();
+    let state = if app.inbox_loading {
+        " ⏳ loading".to_string()
+    } else {
+        format!(
+            " — {} unread",
+            notifications.iter().filter(|n| n.unread).count()
+        )
+    };
+    let title = format!(
+        "Inbox{state} (4 inbox ; Enter open ; m mark read ; a add as todo ; r refresh ; Esc list)"
+    );
+    let row_count = rows.len();
+    let [reason, kind, age] = INBOX_COLUMNS;
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(reason),
+            Constraint::Length(kind),
+            Constraint::Length(age),
+            Constraint::Min(20),
+        ],
+    )
+    .header(
+        Row::new(vec!["Reason", "Type", "Age", "Subject"])
+            .style(Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED)),
+    )
+    .block(Block::default().title(title).borders(Borders::ALL))
+    .column_spacing(COLUMN_SPACING)
+    .highlight_symbol("➤ ")
+    .row_highlight_style(
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+    );
+    let selected = (row_count > 0).then_some(app.inbox_selected);
+    (table, selected, row_count)
+}
+
+fn render_review(draft: &ReviewDraft) -> Paragraph<'_> {
+    let choices: Vec<Span> = [
+        ReviewEvent::Approve,
+        ReviewEvent::Comment,
+        ReviewEvent::RequestChanges,
+    ]
+    .into_iter()
+    .flat_map(|event| {
+        let style = if event == draft.event {
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        [
+            Span::styled(format!(" {} ", event.label()), style),
+            Span::raw(" "),
+        ]
+    })
+    .collect();
+
+    let lines = vec![
+        Line::from(choices),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(draft.body.as_str(), Style::default().fg(Color::Yellow)),
+            Span::raw("█"),
+        ]),
+    ];
+    Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default().borders(Borders::ALL).title(format!(
+            "Review {} (Tab verdict ; type message ; Enter submit ; Esc cancel)",
+            draft.pr_key
+        )),
+    )
+}
+
+fn render_conflict(conflict: &TitleConflict) -> Paragraph<'_> {
+    let choices: Vec<Span> = ConflictChoice::ALL
+        .into_iter()
+        .flat_map(|choice| {
+            let style = if choice == conflict.choice {
                 Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD | Modifier::REVERSED)
+            } else {
+                Style::default().fg(Color::Gray)
             };
+            [
+                Span::styled(format!(" {} ", choice.label()), style),
+                Span::raw(" "),
+            ]
+        })
+        .collect();
+    let lines = vec![
+        Line::from("Renamed here and on the remote since the last sync."),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Local:  ", Style::default().fg(Color::Gray)),
+            Span::raw(conflict.local.as_str()),
+        ]),
+        Line::from(vec![
+            Span::styled("Remote: ", Style::default().fg(Color::Gray)),
+            Span::styled(conflict.remote.as_str(), Style::default().fg(Color::Yellow)),
+        ]),
+        Line::from(""),
+        Line::from(choices),
+    ];
+    Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Title conflict (l local ; r remote ; m merge ; Tab + Enter ; Esc later)"),
+    )
+}
+
+fn render_restore_offer(snapshot: &SessionSnapshot) -> Paragraph<'static> {
+    let saved = clock::to_local(UNIX_EPOCH + Duration::from_secs(snapshot.saved_at.max(0) as u64))
+        .format(format_description!("[year]-[month]-[day] [hour]:[minute]"))
+        .unwrap_or_else(|_| "?".into());
+    let lines = vec![
+        Line::from("The last session ended without quitting; it left unsent input:"),
+        Line::from(""),
+        Line::from(Span::styled(
+            snapshot.draft.summary(),
+            Style::default().fg(Color::Yellow),
+        )),
+        Line::from(Span::styled(
+            format!("saved {saved}"),
+            Style::default().fg(Color::Gray),
+        )),
+    ];
+    Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Restore? (y / Enter restore ; n / Esc discard)"),
+    )
+}
 
+fn render_ci_checks<'a>(app: &'a App, pr_key: &str) -> Table<'a> {
+    let now = crate::now_unix();
+    let rows: Vec<Row> = app
+        .current_ci_checks()
+        .iter()
+        .map(|check| {
+            let (label, color) = match check.state {
+                CiCheckState::Success => ("✔ pass", Color::Green),
+                CiCheckState::Failure => ("✗ fail", Color::Red),
+                CiCheckState::Running => ("⏳ run", Color::Yellow),
+                CiCheckState::Neutral => ("– skip", Color::DarkGray),
+                CiCheckState::None => ("? none", Color::DarkGray),
+            };
+            let elapsed = check
+                .started_at_unix
+                .map(|started| {
+                    let secs = (now - started).max(0) as u64;
+                    time_entry::format_duration(Duration::from_secs(secs))
+                })
+                .unwrap_or_else(|| "-".to_string());
             Row::new(vec![
-                Cell::from(pri),
-                Cell::from(due_text).style(due_style),
-                Cell::from(title),
+                Cell::from(label).style(Style::default().fg(color)),
+                Cell::from(check.name.clone()),
+                Cell::from(elapsed),
             ])
-            .style(row_style)
         })
         .collect();
 
     Table::new(
         rows,
         [
+            Constraint::Length(8),
+            Constraint::Min(20),
             Constraint::Length(10),
-            Constraint::Length(22),
+        ],
+    )
+    .header(
+        Row::new(vec!["State", "Check", "Since start"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("CI checks — {pr_key} (Enter open ; Esc close)")),
+    )
+    .highlight_symbol("➤ ")
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+}
+
+fn render_link_picker(picker: &LinkPicker) -> Table<'_> {
+    let rows: Vec<Row> = picker
+        .links
+        .iter()
+        .map(|(label, url)| {
+            Row::new(vec![
+                Cell::from(label.as_str()),
+                Cell::from(url.as_str()).style(Style::default().fg(Color::Gray)),
+            ])
+        })
+        .collect();
+    Table::new(rows, [Constraint::Length(28), Constraint::Min(20)])
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Open link (j/k move ; Enter open ; Esc close)"),
+        )
+        .highlight_symbol("➤ ")
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+}
+
+/// `Ctrl-p`: the query, then the matching titles with the matched letters highlighted,
+/// scrolled to keep the chosen one in view.
+fn render_jump<'a>(app: &'a App, jump: &'a JumpFinder, area: Rect) -> Paragraph<'a> {
+    let matches = app.jump_matches();
+    // Borders and the query line.
+    let rows = (area.height as usize).saturating_sub(3).max(1);
+    let offset = (jump.selected + 1).saturating_sub(rows);
+    let hit = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+    let mut lines = vec![Line::from(vec![
+        Span::styled("> ", Style::default().fg(Color::Cyan)),
+        Span::raw(jump.query.as_str()),
+        Span::raw("█"),
+    ])];
+    for (row, (idx, positions)) in matches.iter().enumerate().skip(offset).take(rows) {
+        let chosen = row == jump.selected;
+        let mut spans = vec![Span::raw(if chosen { "➤ " } else { "  " })];
+        for (at, c) in app.todos[*idx].title.chars().enumerate() {
+            let style = if positions.contains(&at) {
+                hit
+            } else {
+                Style::default()
+            };
+            spans.push(Span::styled(c.to_string(), style));
+        }
+        let mut line = Line::from(spans);
+        if app.todos[*idx].is_closed() {
+            line = line.style(Style::default().fg(Color::DarkGray));
+        }
+        if chosen {
+            line = line.patch_style(Style::default().add_modifier(Modifier::REVERSED));
+        }
+        lines.push(line);
+    }
+    if matches.is_empty() {
+        lines.push(Line::styled(
+            "  no matching todo",
+            Style::default().fg(Color::Gray),
+        ));
+    }
+    Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(format!(
+        "Jump to ({} ; type to filter ; ↑/↓ move ; Enter jump ; Esc close)",
+        matches.len()
+    )))
+}
+
+/// `S`: "all enabled" plus one row per source with its `g` toggle and last sync.
+fn render_sync_menu(app: &App) -> Table<'static> {
+    let now = crate::now_unix();
+    let syncing = app.syncing_sources();
+    let mut rows = vec![Row::new(vec![
+        Cell::from(""),
+        Cell::from("All enabled sources"),
+        Cell::from(""),
+    ])];
+    for source in SyncSource::ALL {
+        let (mark, last) = if !app.source_configured(source) {
+            ("   ", "not configured".to_string())
+        } else {
+            let mark = if app.source_enabled(source) {
+                "[x]"
+            } else {
+                "[ ]"
+            };
+            let last = match app.source_synced_at(source) {
+                _ if syncing.contains(&source) => "⏳ syncing".to_string(),
+                Some(at) => {
+                    let secs = (now - at).max(0) as u64;
+                    format!(
+                        "{} ago",
+                        time_entry::format_duration(Duration::from_secs(secs))
+                    )
+                }
+                None => "never".to_string(),
+            };
+            (mark, last)
+        };
+        rows.push(Row::new(vec![
+            Cell::from(mark),
+            Cell::from(source.label()),
+            Cell::from(last).style(Style::default().fg(Color::Gray)),
+        ]));
+    }
+    Table::new(
+        rows,
+        [
+            Constraint::Length(4),
             Constraint::Min(20),
+            Constraint::Length(16),
         ],
     )
-        .header(
-            Row::new(vec!["Priority", "Due", "Title"]).style(
-                Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+    .header(
+        Row::new(vec!["g", "Source", "Last sync"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Sync (Enter sync now ; Space include in g ; Esc close)"),
+    )
+    .highlight_symbol("➤ ")
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+}
+
+/// Nothing from the store is shown while locked, not even counts in the header.
+fn render_lock_screen(app: &App, height: u16) -> Paragraph<'static> {
+    let mut lines = vec![Line::from(""); (height / 2).saturating_sub(1) as usize];
+    lines.push(Line::from(Span::styled(
+        "koto is locked",
+        Style::default().add_modifier(Modifier::BOLD),
+    )));
+    lines.push(Line::from(Span::styled(
+        "type the unlock phrase and press Enter",
+        Style::default().fg(Color::DarkGray),
+    )));
+    lines.push(Line::from("•".repeat(app.unlock_input.chars().count())));
+    Paragraph::new(lines).alignment(Alignment::Center)
+}
+
+/// Recent status messages, newest first.
+fn render_status_log(app: &App, scroll: u16) -> Paragraph<'_> {
+    let fmt = format_description!("[hour]:[minute]:[second]");
+    let lines: Vec<Line> = if app.status_log.is_empty() {
+        vec![Line::from("No messages yet")]
+    } else {
+        app.status_log
+            .iter()
+            .rev()
+            .map(|(at, msg)| {
+                let stamp = clock::to_local(*at).format(&fmt).unwrap_or_default();
+                Line::from(vec![
+                    Span::styled(format!("{stamp}  "), Style::default().fg(Color::DarkGray)),
+                    Span::raw(msg.as_str()),
+                ])
+            })
+            .collect()
+    };
+    Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Messages (j/k scroll ; Esc close)"),
+        )
+}
+
+fn render_error(report: &ErrorReport, scroll: u16) -> Paragraph<'_> {
+    let stamp = clock::to_local(report.at)
+        .format(format_description!("[hour]:[minute]:[second]"))
+        .unwrap_or_default();
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled(format!("{stamp}  "), Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                report.message.as_str(),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
             ),
+        ]),
+        Line::from(""),
+    ];
+    if report.details != report.message {
+        lines.extend(report.details.lines().map(Line::from));
+    }
+    Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Red))
+                .title("Error (j/k scroll ; y copy ; Esc close ; E reopens)"),
+        )
+}
+
+fn render_detail<'a>(app: &App, todo: &'a Todo) -> Paragraph<'a> {
+    let now = SystemTime::now();
+    let label = |name: &'static str| {
+        Span::styled(
+            format!("{name:<10}"),
+            Style::default().add_modifier(Modifier::BOLD),
         )
+    };
+    let effective_due = app.effective_due(todo);
+    let (mut due_text, due_style) = render_due(effective_due.map(|d| d.due()), app.today);
+    if effective_due.is_some_and(|d| d.inherited) {
+        due_text.push_str(" — from parent");
+    } else if app.hierarchy.due_after_parent(todo.id) {
+        due_text.push_str(" — ⚠ later than the parent's");
+    }
+    let timestamp = |t: SystemTime| {
+        clock::to_local(t)
+            .format(format_description!("[year]-[month]-[day] [hour]:[minute]"))
+            .unwrap_or_else(|_| "invalid".into())
+    };
+    let created = timestamp(todo.created_at);
+
+    let sessions: Vec<_> = app
+        .time_entries
+        .iter()
+        .filter(|e| e.todo_id == todo.id)
+        .collect();
+    let tracked = time_entry::total_for(&app.time_entries, todo.id, now);
+    let mut tracked_text = format!(
+        "{} ({} session{})",
+        time_entry::format_duration(tracked),
+        sessions.len(),
+        if sessions.len() == 1 { "" } else { "s" }
+    );
+    if sessions.iter().any(|e| e.is_running()) {
+        tracked_text.push_str(" — running");
+    }
+
+    let mut lines = vec![
+        Line::from(vec![label("Title"), Span::raw(todo.title.as_str())]),
+        Line::from(vec![
+            label("Status"),
+            Span::raw(if todo.archived {
+                "archived".to_string()
+            } else {
+                todo.status.label().replace('_', " ")
+            }),
+        ]),
+        Line::from(vec![label("Priority"), render_priority(todo.priority)]),
+        Line::from(vec![label("Due"), Span::styled(due_text, due_style)]),
+        Line::from(vec![label("Created"), Span::raw(created)]),
+    ];
+    if let Some(blockers) = app.blockers.get(&todo.id) {
+        let titles: Vec<&str> = blockers
+            .iter()
+            .filter_map(|id| app.todos.iter().find(|t| t.id == *id))
+            .map(|t| t.title.as_str())
+            .collect();
+        lines.push(Line::from(vec![
+            label("Blocked by"),
+            Span::styled(titles.join(", "), Style::default().fg(Color::Yellow)),
+        ]));
+    }
+    if let Some(minutes) = todo.estimate_minutes {
+        lines.push(Line::from(vec![
+            label("Estimate"),
+            Span::raw(format_minutes(minutes)),
+        ]));
+    }
+    if let Some(start) = todo.start_at {
+        let mut text = timestamp(start);
+        if todo.is_scheduled(now) {
+            text.push_str(" — hidden until then (s shows it)");
+        }
+        lines.push(Line::from(vec![label("Starts"), Span::raw(text)]));
+    }
+    if todo.is_closed() {
+        let completed = todo
+            .completed_at
+            .map_or_else(|| "unknown".to_string(), timestamp);
+        lines.push(Line::from(vec![label("Completed"), Span::raw(completed)]));
+    }
+    lines.push(Line::from(vec![label("Tracked"), Span::raw(tracked_text)]));
+    if let Some(url) = todo.external_url.as_deref() {
+        lines.push(Line::from(vec![label("Link"), Span::raw(url)]));
+    }
+    if let Some(key) = todo.external_key.as_deref() {
+        lines.push(Line::from(vec![label("Source"), Span::raw(key)]));
+    }
+    if let Some(pr) = app.pr_for(todo) {
+        let draft = if pr.is_draft { " (draft)" } else { "" };
+        lines.push(Line::from(vec![
+            label("PR"),
+            Span::raw(format!("{} by {}{draft}", pr.pr_key, pr.author)),
+        ]));
+        if !pr.labels.is_empty() {
+            let mut chips = vec![label("Labels")];
+            for l in &pr.labels {
+                chips.push(label_chip(l));
+                chips.push(Span::raw(" "));
+            }
+            lines.push(Line::from(chips));
+        }
+        if let Some(milestone) = &pr.milestone {
+            let due = milestone
+                .due_on
+                .as_deref()
+                .map_or_else(String::new, |d| format!(" (due {d})"));
+            lines.push(Line::from(vec![
+                label("Milestone"),
+                Span::raw(format!("{}{due}", milestone.title)),
+            ]));
+        }
+        if let Some(stats) = pr.diff_stats {
+            lines.push(Line::from(vec![
+                label("Size"),
+                Span::raw(format!(
+                    "{} (+{} −{} in {} file{})",
+                    stats.size(),
+                    stats.additions,
+                    stats.deletions,
+                    stats.changed_files,
+                    if stats.changed_files == 1 { "" } else { "s" }
+                )),
+            ]));
+        }
+        let (ci, ci_color) = match pr.ci_state {
+            CiState::Success => ("passing", Color::Green),
+            CiState::Failure => ("failing", Color::Red),
+            CiState::Running => ("running", Color::Yellow),
+            CiState::None => ("no checks", Color::Gray),
+        };
+        lines.push(Line::from(vec![
+            label("CI"),
+            Span::styled(ci, Style::default().fg(ci_color)),
+            Span::raw(format!(" ({} checks, C to list)", pr.ci_checks.len())),
+        ]));
+        let mut blockers = vec![label("Blockers")];
+        match &pr.merge_blockers {
+            Some(b) => {
+                blockers.extend(merge_blocker_badges(b));
+                if b.is_behind_base {
+                    blockers.push(Span::styled(
+                        "  (U update branch)",
+                        Style::default().fg(Color::Gray),
+                    ));
+                }
+            }
+            None => blockers.push(Span::styled(
+                "none — ready to merge",
+                Style::default().fg(Color::Green),
+            )),
+        }
+        lines.push(Line::from(blockers));
+        lines.push(Line::from(
+            [label("Reviewers")]
+                .into_iter()
+                .chain(reviewer_spans(pr))
+                .collect::<Vec<_>>(),
+        ));
+        let assignees = if pr.assignees.is_empty() {
+            "none".to_string()
+        } else {
+            pr.assignees.join(", ")
+        };
+        lines.push(Line::from(vec![label("Assignees"), Span::raw(assignees)]));
+        let mut discussion = vec![label("Threads")];
+        if pr.unresolved_threads > 0 {
+            discussion.push(Span::styled(
+                format!("{} unresolved", pr.unresolved_threads),
+                Style::default().fg(Color::Yellow),
+            ));
+        } else if pr.review_threads > 0 {
+            discussion.push(Span::styled(
+                "all resolved",
+                Style::default().fg(Color::Green),
+            ));
+        } else {
+            discussion.push(Span::raw("none"));
+        }
+        discussion.push(Span::raw(format!(
+            " ({} threads, {} comments)",
+            pr.review_threads, pr.comments
+        )));
+        lines.push(Line::from(discussion));
+        let now = crate::now_unix();
+        let parts: Vec<String> = attention::score_parts(pr, now)
+            .iter()
+            .map(|(reason, points)| format!("{reason} {points:+}"))
+            .collect();
+        lines.push(Line::from(vec![
+            label("Attention"),
+            Span::raw(format!(
+                "{} ({})",
+                attention::score(pr, now),
+                parts.join(", ")
+            )),
+        ]));
+    }
+    if let Some(notes) = todo.notes.as_deref() {
+        lines.push(Line::from(label("Notes")));
+        lines.extend(notes.lines().map(|l| Line::from(format!("  {l}"))));
+    }
+
+    Paragraph::new(lines)
         .block(
             Block::default()
-                .title("Todos (h help ; H manual ; j/k move ; a/n add ; Enter open link ; Space toggle ; P cycle prio ; t set due ; [/ ] shift due ; D clear due ; d delete ; c clear done ; g sync GitHub)")
+                .title("Detail (i/Esc close ; j/k move ; y/Y copy link/title ; T timer ; C checks ; R review ; U update branch)")
                 .borders(Borders::ALL),
         )
-        .column_spacing(2)
-        .highlight_symbol("➤ ")
-        .row_highlight_style(
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD | Modifier::REVERSED),
-        )
+        .wrap(Wrap { trim: false })
+        .style(Style::default().bg(Color::Black).fg(Color::White))
+}
+
+/// A label in its GitHub color, with dark or light text for contrast.
+fn label_chip(label: &PrLabel) -> Span<'static> {
+    let text = format!(" {} ", label.name);
+    match label.rgb() {
+        Some((r, g, b)) => {
+            let luma = 299 * r as u32 + 587 * g as u32 + 114 * b as u32;
+            let fg = if luma > 128_000 {
+                Color::Black
+            } else {
+                Color::White
+            };
+            Span::styled(text, Style::default().bg(Color::Rgb(r, g, b)).fg(fg))
+        }
+        None => Span::styled(text, Style::default().add_modifier(Modifier::REVERSED)),
+    }
+}
+
+/// Pending review requests (users, then teams) followed by the reviews already given.
+fn reviewer_spans(pr: &Pr) -> Vec<Span<'static>> {
+    let pending = pr
+        .requested_users
+        .iter()
+        .chain(&pr.requested_teams)
+        .map(|who| (who.clone(), "pending", Color::Gray));
+    let reviewed = pr.reviews.iter().map(|r| {
+        let (state, color) = match r.state.as_str() {
+            "APPROVED" => ("approved", Color::Green),
+            "CHANGES_REQUESTED" => ("changes requested", Color::Red),
+            "COMMENTED" => ("commented", Color::Gray),
+            "DISMISSED" => ("dismissed", Color::Gray),
+            _ => ("pending", Color::Gray),
+        };
+        (r.author.clone(), state, color)
+    });
+    let mut spans = Vec::new();
+    for (who, state, color) in pending.chain(reviewed) {
+        if !spans.is_empty() {
+            spans.push(Span::raw(", "));
+        }
+        spans.push(Span::raw(who));
+        spans.push(Span::styled(
+            format!(" ({state})"),
+            Style::default().fg(color),
+        ));
+    }
+    if spans.is_empty() {
+        spans.push(Span::raw("none"));
+    }
+    spans
 }
 
 fn render_footer(app: &App) -> Paragraph<'_> {
     match app.mode {
         InputMode::Normal => {
-            let msg = app
-                .status
-                .as_deref()
-                .unwrap_or("q quit ; h help ; H manual ; a add ; c clear done ; r reload");
-            Paragraph::new(msg).block(Block::default().title("Normal").borders(Borders::ALL))
+            let msg = app.status.as_deref().unwrap_or(
+                "q quit ; h help ; H manual ; a add ; / search ; c archive done ; r reload",
+            );
+            let (style, title) = if app.status_is_error && app.status.is_some() {
+                (Style::default().fg(Color::Red), "Error (E details)")
+            } else {
+                (Style::default(), "Normal")
+            };
+            Paragraph::new(msg)
+                .style(style)
+                .block(Block::default().title(title).borders(Borders::ALL))
         }
-        InputMode::Editing => {
+        InputMode::Editing => Paragraph::new(input_lines(app))
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::default()
+                    .title("Input (e.g. \"buy milk p:1 d:+2\" / Alt-Enter notes line / Enter to add / Esc to cancel)")
+                    .borders(Borders::ALL),
+            ),
+        InputMode::Search => {
+            let (scope, text) = app
+                .search
+                .as_ref()
+                .map(|q| (q.scope.label(), q.text.as_str()))
+                .unwrap_or(("open", ""));
             let line = Line::from(vec![
-                Span::raw("New task: "),
-                Span::styled(&app.input, Style::default().fg(Color::Yellow)),
+                Span::raw(format!("Search [{scope}]: ")),
+                Span::styled(text, Style::default().fg(Color::Yellow)),
                 Span::raw("█"),
             ]);
             Paragraph::new(line).block(
                 Block::default()
-                    .title("Input (e.g. \"buy milk p:1 d:+2\" / Enter to add / Esc to cancel)")
+                    .title("Search (Tab open/all/archived ; Up/Down move ; Enter keep results ; Esc clear)")
+                    .borders(Borders::ALL),
+            )
+        }
+        InputMode::EditingTitle => {
+            let line = input_line(INPUT_PREFIX_RENAME, app);
+            Paragraph::new(line).wrap(Wrap { trim: false }).block(
+                Block::default()
+                    .title("Edit title (Enter to save / Esc to cancel)")
                     .borders(Borders::ALL),
             )
         }
         InputMode::EditingDue => {
-            let line = Line::from(vec![
-                Span::raw("Set due: "),
-                Span::styled(&app.input, Style::default().fg(Color::Yellow)),
-                Span::raw("█"),
-            ]);
-            Paragraph::new(line).block(
+            let line = input_line(INPUT_PREFIX_DUE, app);
+            Paragraph::new(line).wrap(Wrap { trim: false }).block(
+                Block::default()
+                    .title("Set due (e.g. fri / next monday@9am / in 2 weeks / 2025-01-05 / Enter to confirm / Esc to cancel)")
+                    .borders(Borders::ALL),
+            )
+        }
+        InputMode::Tagging => {
+            let line = input_line(INPUT_PREFIX_TAG, app);
+            Paragraph::new(line).wrap(Wrap { trim: false }).block(
+                Block::default()
+                    .title(format!(
+                        "Tag {} marked todo(s) (Enter to apply / Esc to cancel)",
+                        app.marked.len()
+                    ))
+                    .borders(Borders::ALL),
+            )
+        }
+        InputMode::Watching => {
+            let line = input_line(INPUT_PREFIX_WATCH, app);
+            Paragraph::new(line).wrap(Wrap { trim: false }).block(
                 Block::default()
-                    .title("Set due (e.g. d:+3 / today / 2025-01-05 / Enter to confirm / Esc to cancel)")
+                    .title("Watch a PR until it is merged or closed (owner/repo#123 or URL / Enter to watch / Esc to cancel)")
                     .borders(Borders::ALL),
             )
         }
     }
 }
 
+const INPUT_PREFIX_TITLE: &str = "New task: ";
+const INPUT_PREFIX_NOTES: &str = "Notes:    ";
+const INPUT_PREFIX_DUE: &str = "Set due: ";
+const INPUT_PREFIX_RENAME: &str = "Title: ";
+const INPUT_PREFIX_TAG: &str = "Tag: #";
+const INPUT_PREFIX_WATCH: &str = "Watch PR: ";
+const MAX_FOOTER_ROWS: u16 = 8;
+
+/// Add input: the first line is the title, further lines (Alt-Enter) become notes.
+fn input_lines(app: &App) -> Vec<Line<'_>> {
+    let mut start = 0;
+    let mut cursor = Some(app.input.cursor());
+    app.input
+        .as_str()
+        .split('\n')
+        .enumerate()
+        .map(|(i, text)| {
+            let prefix = match i {
+                0 => INPUT_PREFIX_TITLE,
+                1 => INPUT_PREFIX_NOTES,
+                _ => "          ",
+            };
+            // The cursor is on the first line reaching it (a line's end comes before
+            // the next line's start).
+            let at = cursor
+                .filter(|&at| at <= start + text.len())
+                .map(|at| at - start);
+            if at.is_some() {
+                cursor = None;
+            }
+            start += text.len() + 1;
+            let mut spans = vec![Span::raw(prefix)];
+            spans.extend(with_cursor(text, at));
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// A one-line prompt: `prefix`, then the input with its cursor.
+fn input_line<'a>(prefix: &'static str, app: &'a App) -> Line<'a> {
+    let mut spans = vec![Span::raw(prefix)];
+    spans.extend(with_cursor(app.input.as_str(), Some(app.input.cursor())));
+    Line::from(spans)
+}
+
+/// `text` with the cursor at byte `cursor`, when it is in this text: the char under it
+/// reversed, or a block after the last one.
+fn with_cursor(text: &str, cursor: Option<usize>) -> Vec<Span<'_>> {
+    let style = Style::default().fg(Color::Yellow);
+    let Some(at) = cursor else {
+        return vec![Span::styled(text, style)];
+    };
+    let (before, after) = text.split_at(at);
+    match after.graphemes(true).next() {
+        Some(under) => vec![
+            Span::styled(before, style),
+            Span::styled(under, style.add_modifier(Modifier::REVERSED)),
+            Span::styled(&after[under.len()..], style),
+        ],
+        None => vec![Span::styled(before, style), Span::raw("█")],
+    }
+}
+
+/// Footer grows with the soft-wrapped input so long titles stay visible.
+/// The hint line is only shown while browsing the list.
+fn hint_height(app: &App) -> u16 {
+    (app.mode == InputMode::Normal) as u16
+}
+
+/// One line of actions for the selected row ("Enter open PR · R review · …").
+fn render_hints(app: &App) -> Paragraph<'static> {
+    let key_style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let mut spans = vec![Span::raw(" ")];
+    for (i, (key, action)) in app.selected_hints().into_iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::styled(" · ", Style::default().fg(Color::DarkGray)));
+        }
+        spans.push(Span::styled(key, key_style));
+        spans.push(Span::styled(
+            format!(" {action}"),
+            Style::default().fg(Color::Gray),
+        ));
+    }
+    Paragraph::new(Line::from(spans))
+}
+
+fn footer_height(app: &App, width: u16) -> u16 {
+    let inner = width.saturating_sub(2).max(1) as usize;
+    let rows = |text_len: usize| text_len.div_ceil(inner).max(1);
+    let content: usize = match app.mode {
+        InputMode::Editing => app
+            .input
+            .as_str()
+            .split('\n')
+            .map(|line| rows(INPUT_PREFIX_TITLE.len() + text::width(line) + 1))
+            .sum(),
+        InputMode::EditingDue => rows(INPUT_PREFIX_DUE.len() + text::width(app.input.as_str()) + 1),
+        InputMode::EditingTitle => {
+            rows(INPUT_PREFIX_RENAME.len() + text::width(app.input.as_str()) + 1)
+        }
+        InputMode::Tagging => rows(INPUT_PREFIX_TAG.len() + text::width(app.input.as_str()) + 1),
+        InputMode::Watching => rows(INPUT_PREFIX_WATCH.len() + text::width(app.input.as_str()) + 1),
+        InputMode::Normal | InputMode::Search => 1,
+    };
+    (content as u16).min(MAX_FOOTER_ROWS) + 2
+}
+
 fn cleanup_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(
+        terminal.backend_mut(),
+        DisableBracketedPaste,
+        LeaveAlternateScreen
+    )?;
     terminal.show_cursor()?;
     Ok(())
 }
@@ -342,18 +1612,34 @@ fn render_priority(priority: Priority) -> Span<'static> {
     }
 }
 
-fn render_due(due: Option<std::time::SystemTime>) -> (String, Style) {
+fn render_due(due: Option<Due>, today: Date) -> (String, Style) {
     let fmt = format_description!("[year]-[month]-[day]");
     match due {
         None => ("No due".to_string(), Style::default().fg(Color::Gray)),
-        Some(t) => {
-            let odt: OffsetDateTime = t.into();
-            let date_str = odt.format(&fmt).unwrap_or_else(|_| "invalid".into());
-
-            // Compute calendar-day difference (UTC) to avoid today becoming tomorrow around midnight.
-            let today_date = OffsetDateTime::now_utc().date();
-            let due_date = odt.date();
-            let days_diff = (due_date.to_julian_day() - today_date.to_julian_day()) as i64;
+        Some(Due { at: t, has_time }) => {
+            let mut date_str = clock::to_local(t)
+                .format(&fmt)
+                .unwrap_or_else(|_| "invalid".into());
+            let days_diff = due::days_until(t, today);
+            if has_time {
+                let hm = clock::to_local(t)
+                    .format(format_description!("[hour]:[minute]"))
+                    .unwrap_or_default();
+                if days_diff == 0 {
+                    // Same day: count down to the hour instead of showing the date.
+                    return match t.duration_since(SystemTime::now()) {
+                        Ok(left) => (
+                            format!("today {hm} (in {})", countdown(left)),
+                            Style::default().fg(Color::Yellow),
+                        ),
+                        Err(e) => (
+                            format!("today {hm} ({} overdue)", countdown(e.duration())),
+                            Style::default().fg(Color::Red),
+                        ),
+                    };
+                }
+                date_str = format!("{date_str} {hm}");
+            }
 
             let (label, color) = match days_diff {
                 d if d < 0 => (format!("{date_str} ({:>2}d overdue)", -d), Color::Red),
@@ -366,6 +1652,16 @@ fn render_due(due: Option<std::time::SystemTime>) -> (String, Style) {
     }
 }
 
+/// `2h` from an hour on, else minutes (at least `1m`).
+fn countdown(left: Duration) -> String {
+    let minutes = left.as_secs() / 60;
+    if minutes >= 60 {
+        format!("{}h", minutes / 60)
+    } else {
+        format!("{}m", minutes.max(1))
+    }
+}
+
 fn render_help<'a>(mode: HelpMode, scroll: u16, title: String) -> Paragraph<'a> {
     let (title, text) = match mode {
         HelpMode::None => (title, Text::from("")),
@@ -409,14 +1705,26 @@ fn help_text_quick() -> Text<'static> {
         ]),
         Line::from(""),
         Line::from("Navigation: j/k or Up/Down"),
-        Line::from("Add task: a or n"),
-        Line::from("Toggle done: Space or Enter"),
+        Line::from("Scroll: PgUp/PgDn, Ctrl-u/Ctrl-d (half page), gg/G (top/bottom)"),
+        Line::from("Add task: a or n (A: subtask)"),
+        Line::from("Toggle done: Space or Enter ; next status: w"),
         Line::from("Delete task: d or Delete"),
-        Line::from("Clear done: c"),
-        Line::from("Priority: P (cycle)"),
+        Line::from("Archive done: c"),
+        Line::from("Mark: v (V all / none), then P, p1-3, [ / ], #, c, d act on the marked"),
+        Line::from("Search: / (Tab open/all/archived, Esc clear) ; jump to a todo: Ctrl-p"),
+        Line::from("Rename: e ; title conflicts after sync: x"),
+        Line::from("Priority: P (cycle), p then 1 / 2 / 3 (high / medium / low)"),
+        Line::from("Blocked by: mark blockers with v, then b on the waiting task"),
         Line::from("Due date: t (edit), [ / ] (shift), D (clear)"),
+        Line::from("Scheduled: s:+5 when adding hides a task until then; s shows them"),
+        Line::from("Time tracking: T (start/stop)"),
+        Line::from("Details: i"),
+        Line::from(
+            "Views: 1 (list), 2 (agenda), 3 (by repository; z / Z fold), 5 (board), M (calendar)",
+        ),
         Line::from("Reload: r"),
-        Line::from("GitHub sync: g"),
+        Line::from("Recent messages: L"),
+        Line::from("Sync: g (enabled sources), S (menu)"),
         Line::from("Quit: q"),
         Line::from(""),
         Line::from(vec![
@@ -444,16 +1752,91 @@ fn help_text_full() -> Text<'static> {
             Style::default().add_modifier(Modifier::BOLD),
         )]),
         Line::from("  j / k, Up / Down        Move selection (or scroll in this manual)"),
-        Line::from("  a / n                   Add a new todo (type, then Enter)"),
+        Line::from("  PgUp / PgDn             Move a page up / down"),
+        Line::from("  Ctrl-u / Ctrl-d         Move half a page up / down"),
+        Line::from("  gg / G, Home / End      First / last todo"),
+        Line::from(
+            "  Ctrl-p                  Jump to a todo: type letters of its title (fzf-style)",
+        ),
+        Line::from(
+            "  a / n                   Add a new todo (type, then Enter; Alt-Enter starts notes)",
+        ),
+        Line::from(
+            "  Left / Right, Home/End  In a prompt: move the cursor ; Ctrl-w deletes a word",
+        ),
+        Line::from(
+            "  A                       Add a subtask of the selected todo (inherits its due date)",
+        ),
         Line::from("  Enter / Space           Toggle done"),
-        Line::from("  d / Delete              Delete selected"),
-        Line::from("  c                       Clear all completed"),
+        Line::from(
+            "  w                       Next status: todo, in progress, blocked, done, cancelled",
+        ),
+        Line::from(
+            "  Enter                   Open the link; a synced PR lists its repo and CI check links",
+        ),
+        Line::from(
+            "  d / Delete              Delete selected (guarded against key repeat, see CONFIG)",
+        ),
+        Line::from("  c                       Archive all completed"),
+        Line::from(
+            "  v / V                   Mark / unmark selected ; mark all listed (or clear marks)",
+        ),
+        Line::from(
+            "                          With marks: P / p1-3 priority, [ / ] due, # tag, c archive, d delete (asks)",
+        ),
+        Line::from("  /                       Search titles (Tab: open / all / archived)"),
         Line::from("  r                       Reload from storage"),
         Line::from("  P                       Cycle priority (High → Med → Low)"),
+        Line::from("  p 1 / p 2 / p 3         Set priority to High / Med / Low"),
+        Line::from(
+            "  b                       Selected waits for the marked todos (🔒); unmarked: unblock",
+        ),
+        Line::from("  e                       Edit the title of the selected todo"),
+        Line::from(
+            "  x                       Resolve title conflicts from sync (local / remote / merge)",
+        ),
         Line::from("  t                       Edit due date for selected"),
         Line::from("  [ / ]                   Shift due date by -1 / +1 day"),
         Line::from("  D                       Clear due date"),
-        Line::from("  g                       Sync GitHub review-requested PRs"),
+        Line::from("  T                       Start / stop the timer on selected"),
+        Line::from("  i                       Show details (tracked time, link, source)"),
+        Line::from(
+            "  1 / 2                   List view / agenda view (Overdue, Today, Tomorrow, This week)",
+        ),
+        Line::from(
+            "  3                       By repository / source; z folds the selected group, Z all",
+        ),
+        Line::from(
+            "  4                       GitHub notifications inbox; m marks read, a adds as todo, r refreshes",
+        ),
+        Line::from(
+            "  5                       Board: h / l column, H / L move the todo, Tab by state / priority",
+        ),
+        Line::from(
+            "  g                       Sync enabled sources (GitHub review-requested PRs; starts after a short pause for gg)",
+        ),
+        Line::from("  S                       Sync menu: pick a source, Space toggles it for g"),
+        Line::from("  I                       Sync Jira issues now ([jira] in config.toml)"),
+        Line::from("  f                       Filter PRs: all / direct requests / team requests"),
+        Line::from("  B                       Hide / show PRs opened by bots"),
+        Line::from("  s                       Show / hide todos whose start date is ahead (⏳)"),
+        Line::from(
+            "  W                       Watch a PR: sync it until merged or closed, review request or not",
+        ),
+        Line::from(
+            "  u                       Mark the selected PR seen (clears ●: new commits / activity)",
+        ),
+        Line::from(
+            "  o                       Sort by due date / priority / PR attention score / manual order",
+        ),
+        Line::from(
+            "  J / K                   Move selected down / up among its siblings (manual order)",
+        ),
+        Line::from("  L                       Recent status messages (last 50)"),
+        Line::from("  E                       Last error in full (y copies it)"),
+        Line::from(
+            "  C                       CI checks of the selected PR (Enter opens the check)",
+        ),
         Line::from("  h / ?                   Quick help"),
         Line::from("  H                       This manual"),
         Line::from("  q                       Quit"),
@@ -465,7 +1848,11 @@ fn help_text_full() -> Text<'static> {
         Line::from("You can type inline meta when adding a task:"),
         Line::from("  \"buy milk p:1 d:+2\""),
         Line::from("Priority tokens: p:1 / p:2 / p:3 (also: high/medium/low)"),
-        Line::from("Due tokens: d:+N, today, tomorrow, YYYY-MM-DD"),
+        Line::from(
+            "Due tokens: d:+N, today, tomorrow, d:fri, next monday, in 2 weeks, end of month, YYYY-MM-DD",
+        ),
+        Line::from("Start tokens: s:+5, s:mon, s:2025-01-05 (hidden from the list until then)"),
+        Line::from("Estimate tokens: e:30, e:2h, e:1h30 (summed up for today in the header)"),
         Line::from(""),
         Line::from(vec![Span::styled(
             "GITHUB SYNC",
@@ -482,6 +1869,36 @@ fn help_text_full() -> Text<'static> {
         Line::from("  gh auth token --hostname $GH_HOST"),
         Line::from("is used."),
         Line::from(""),
+        Line::from(vec![Span::styled(
+            "CONFIG",
+            Style::default().add_modifier(Modifier::BOLD),
+        )]),
+        Line::from("Settings live in <config dir>/koto/config.toml (override with --config)."),
+        Line::from("  [keys] delete_guard = \"window\" | \"double\" | \"off\""),
+        Line::from("    window: a second delete within the window asks for y first (default)"),
+        Line::from("    double: deleting requires dd ; off: delete immediately"),
+        Line::from("  [keys] delete_guard_window_ms = 1000"),
+        Line::from("  [open] command = \"wslview\" ; schemes = { https = \"firefox --new-tab\" }"),
+        Line::from(
+            "  [clipboard] backend = \"auto\" | \"osc52\" | \"native\" (osc52 works over SSH/tmux)",
+        ),
+        Line::from("  [github] follow_up_on_approve = true ; follow_up_due_days = 1"),
+        Line::from(
+            "  [github] include_team_requests = true (also sync PRs requested from your teams)",
+        ),
+        Line::from("  [github] stale_after_days = 7 (PR age turns red; 0 disables)"),
+        Line::from(
+            "  [[github.accounts]] api_url = \"https://ghe.example.com/api/v3\" (extra host)",
+        ),
+        Line::from(""),
+        Line::from(vec![Span::styled(
+            "TIME TRACKING",
+            Style::default().add_modifier(Modifier::BOLD),
+        )]),
+        Line::from("Press 'T' to start tracking the selected todo; press again to stop."),
+        Line::from("Only one timer runs at a time; starting another stops the current one."),
+        Line::from("Summaries: koto report time [--week]"),
+        Line::from(""),
         Line::from(vec![Span::styled(
             "NOTES",
             Style::default().add_modifier(Modifier::BOLD),
@@ -552,21 +1969,399 @@ fn help_matches(query: &str) -> Vec<usize> {
         .collect()
 }
 
-fn jump_to_next_match(app: &mut App, forward: bool) {
-    let matches = help_matches(&app.help_search_query);
-    if matches.is_empty() {
-        return;
+#[cfg(test)]
+mod tests {
+    //! Rendering snapshots: `draw` on a `TestBackend` for typical screens, compared with
+    //! `snapshots/<name>.txt` (text only, no colors). Regenerate after an intended change
+    //! with `UPDATE_GOLDEN=1 cargo test`, then review the diff.
+
+    use std::path::PathBuf;
+
+    use ratatui::backend::TestBackend;
+    use ratatui::buffer::Buffer;
+    use ratatui::widgets::Widget;
+    use serde_json::json;
+    use time::macros::date;
+
+    use super::*;
+    use crate::app::{Action, GithubConfig};
+    use crate::config::Config;
+    use crate::domain::due::Due;
+    use crate::domain::todo::GITHUB_PR_KEY_PREFIX;
+    use crate::repo::TodoRepository;
+    use crate::repo::github::model::{DiffStats, RepoFilter, RetryPolicy};
+    use crate::repo::memory::InMemoryTodoRepo;
+    use crate::usecase::widget;
+    use crate::worker::Worker;
+
+    const WIDTH: u16 = 100;
+    const HEIGHT: u16 = 24;
+
+    /// An app whose "today" is fixed, so due labels do not change from day to day.
+    fn app(repo: InMemoryTodoRepo, github: Vec<GithubConfig>) -> App {
+        let mut app = App::new(
+            Box::new(repo),
+            Config::default(),
+            github,
+            Worker::start().unwrap(),
+        );
+        app.today = date!(2026 - 03 - 11);
+        app.reload();
+        app.status = None;
+        app
     }
 
-    let len = matches.len();
-    let cur = app.help_search_match.min(len.saturating_sub(1));
-    let next = if forward {
-        (cur + 1) % len
-    } else {
-        (cur + len - 1) % len
-    };
+    fn due_in(days: i64) -> Option<Due> {
+        Some(Due::day(
+            date!(2026 - 03 - 11).saturating_add(time::Duration::days(days)),
+        ))
+    }
+
+    /// PR `acme/api#7`, a plain review request apart from `fields`.
+    fn pr(fields: serde_json::Value) -> Pr {
+        let mut pr = json!({
+            "pr_key": "acme/api#7", "owner": "acme", "repo": "api", "number": 7,
+            "author": "octocat", "title": "Add rate limits",
+            "url": "https://github.com/acme/api/pull/7", "updated_at_unix": 0,
+            "ci_state": "Success", "ci_checks": [], "review_state": "None",
+            "is_draft": false, "is_viewer_author": false
+        });
+        pr.as_object_mut()
+            .unwrap()
+            .extend(fields.as_object().unwrap().clone());
+        serde_json::from_value(pr).unwrap()
+    }
+
+    /// An app listing one review request, for `acme/api#7` with `fields` set on the PR.
+    fn review_request(fields: serde_json::Value) -> App {
+        let pr = pr(fields);
+        let mut repo = InMemoryTodoRepo::default();
+        repo.add(
+            format!("Review: {}", pr.title),
+            Priority::Medium,
+            None,
+            Some(pr.url.clone()),
+            Some(format!("{GITHUB_PR_KEY_PREFIX}{}", pr.pr_key)),
+        )
+        .unwrap();
+        let mut app = app(repo, Vec::new());
+        app.prs.insert(pr.pr_key.clone(), pr);
+        app
+    }
+
+    /// The detail pane of the selected todo, one line per row without the borders.
+    fn detail(app: &App) -> Vec<String> {
+        let area = Rect::new(0, 0, WIDTH, 40);
+        let mut buffer = Buffer::empty(area);
+        render_detail(app, &app.todos[app.selected]).render(area, &mut buffer);
+        (0..area.height)
+            .map(|y| {
+                let row: String = (0..area.width).map(|x| buffer[(x, y)].symbol()).collect();
+                row.trim_matches(['│', ' ']).to_string()
+            })
+            .collect()
+    }
+
+    /// The screen as text, one line per row with trailing blanks trimmed.
+    fn screen(app: &mut App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+        terminal.draw(|f| draw(f, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let mut out = String::new();
+        for y in 0..HEIGHT {
+            let mut line = String::new();
+            let mut skip = 0;
+            for x in 0..WIDTH {
+                let symbol = buffer[(x, y)].symbol();
+                // Cells covered by a wide character before them are not drawn.
+                if skip == 0 {
+                    line.push_str(symbol);
+                }
+                skip = skip.max(Span::raw(symbol).width()).saturating_sub(1);
+            }
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+
+    fn assert_screen(name: &str, app: &mut App) {
+        let actual = screen(app);
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/ui/snapshots")
+            .join(format!("{name}.txt"));
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, &actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("{}: {e} (run with UPDATE_GOLDEN=1)", path.display()));
+        assert!(
+            actual == expected,
+            "{name} no longer renders as {}:\n{actual}",
+            path.display()
+        );
+    }
+
+    #[test]
+    fn empty_list() {
+        assert_screen(
+            "empty_list",
+            &mut app(InMemoryTodoRepo::default(), Vec::new()),
+        );
+    }
+
+    #[test]
+    fn overdue_items() {
+        let mut repo = InMemoryTodoRepo::default();
+        for (title, priority, due) in [
+            ("Renew passport", Priority::High, due_in(-2)),
+            ("Pay rent", Priority::Medium, due_in(0)),
+            ("Book dentist", Priority::Low, due_in(5)),
+            ("Water plants", Priority::Medium, None),
+        ] {
+            repo.add(title.to_string(), priority, due, None, None)
+                .unwrap();
+        }
+        let done = repo
+            .add(
+                "Send invoice".to_string(),
+                Priority::Medium,
+                due_in(-1),
+                None,
+                None,
+            )
+            .unwrap();
+        repo.toggle(done.id).unwrap();
+        assert_screen("overdue_items", &mut app(repo, Vec::new()));
+    }
+
+    #[test]
+    fn status_line_counts_what_the_list_shows() {
+        let mut repo = InMemoryTodoRepo::default();
+        for (title, due) in [("Renew passport", due_in(-2)), ("Pay rent", due_in(0))] {
+            repo.add(title.to_string(), Priority::Medium, due, None, None)
+                .unwrap();
+        }
+        let mut app = app(repo, Vec::new());
+        let prs = [
+            pr(json!({"review_state": "Requested"})),
+            pr(json!({"pr_key": "acme/api#8", "ci_state": "Failure", "is_viewer_author": true})),
+        ];
+        let shown = screen(&mut app);
+        assert!(shown.contains("2026-03-09 ( 2d overdue)"), "{shown}");
+        assert!(shown.contains("2026-03-11 (today)"), "{shown}");
+
+        let noon = clock::at_local(app.today, time::macros::time!(12:00));
+        assert_eq!(
+            widget::status_line(&app.todos, Some(&prs), noon, app.today),
+            "todos: 1 due today, 1 overdue • 1 PR reviews • CI: 1 failing"
+        );
+    }
+
+    #[test]
+    fn sync_in_progress() {
+        // Nothing listens on the discard port; the sync fails in the background, but the
+        // outcome is never polled, so the screen keeps showing it running.
+        let github = GithubConfig {
+            token: "test-token".to_string(),
+            api_base: Some("http://127.0.0.1:9/api/v3".to_string()),
+            days: 14,
+            include_team_requests: false,
+            page_size: 50,
+            retry: RetryPolicy {
+                attempts: 1,
+                base_delay_ms: 0,
+            },
+            searches: Vec::new(),
+            repos: RepoFilter::default(),
+            key_host: None,
+            since: None,
+            watched: Vec::new(),
+        };
+        let mut app = app(InMemoryTodoRepo::default(), vec![github]);
+        app.update(Action::StartSync(SyncSource::Github));
+        assert_screen("sync_in_progress", &mut app);
+    }
+
+    #[test]
+    fn board() {
+        let mut repo = InMemoryTodoRepo::default();
+        let mut add = |title: &str, priority, due| {
+            repo.add(title.to_string(), priority, due, None, None)
+                .unwrap()
+        };
+        add("Draft proposal", Priority::High, due_in(1));
+        let doing = add("Fix flaky test", Priority::Medium, None);
+        let done = add("Update docs", Priority::Low, None);
+        add("Plan retro", Priority::Low, None);
+        repo.set_status(doing.id, Status::InProgress).unwrap();
+        repo.toggle(done.id).unwrap();
+        let mut app = app(repo, Vec::new());
+        app.update(Action::SetView(View::Board));
+        app.update(Action::MoveBoardColumn(1));
+        assert_screen("board", &mut app);
+
+        app.update(Action::ShiftBoard(1));
+        let moved = &app.todos[app.selected];
+        assert!(
+            moved.title == "Fix flaky test" && moved.is_closed(),
+            "{moved:?}"
+        );
+    }
+
+    #[test]
+    fn help_modal() {
+        let mut app = app(InMemoryTodoRepo::default(), Vec::new());
+        app.update(Action::ToggleHelpQuick);
+        assert_screen("help_modal", &mut app);
+    }
+
+    #[test]
+    fn pr_detail_counts_review_threads() {
+        let line = |fields| {
+            detail(&review_request(fields))
+                .into_iter()
+                .find(|l| l.starts_with("Threads"))
+                .unwrap()
+        };
+        assert_eq!(
+            line(json!({"review_threads": 3, "unresolved_threads": 2, "comments": 5})),
+            "Threads   2 unresolved (3 threads, 5 comments)"
+        );
+        assert_eq!(
+            line(json!({"review_threads": 1, "comments": 1})),
+            "Threads   all resolved (1 threads, 1 comments)"
+        );
+        assert_eq!(line(json!({})), "Threads   none (0 threads, 0 comments)");
+    }
 
-    app.help_search_match = next;
-    let line = matches[next];
-    app.help_scroll = (line.saturating_sub(1)) as u16;
+    #[test]
+    fn pr_detail_lists_reviewers_and_assignees() {
+        let lines = detail(&review_request(json!({
+            "requested_users": ["alice"],
+            "requested_teams": ["acme/backend"],
+            "reviews": [
+                {"author": "bob", "state": "APPROVED"},
+                {"author": "carol", "state": "CHANGES_REQUESTED"}
+            ],
+            "assignees": ["octocat", "dave"]
+        })));
+        assert!(
+            lines.contains(
+                &"Reviewers alice (pending), acme/backend (pending), bob (approved), \
+              carol (changes requested)"
+                    .to_string()
+            )
+        );
+        assert!(lines.contains(&"Assignees octocat, dave".to_string()));
+
+        let lines = detail(&review_request(json!({})));
+        assert!(lines.contains(&"Reviewers none".to_string()));
+        assert!(lines.contains(&"Assignees none".to_string()));
+    }
+
+    #[test]
+    fn pr_size_badges_follow_changed_lines() {
+        let size = |additions, deletions| {
+            DiffStats {
+                additions,
+                deletions,
+                changed_files: 1,
+            }
+            .size()
+        };
+        assert_eq!(
+            [
+                size(9, 0),
+                size(5, 5),
+                size(29, 0),
+                size(60, 40),
+                size(499, 0)
+            ],
+            ["XS", "S", "S", "L", "L"]
+        );
+        assert_eq!((size(30, 0), size(400, 100)), ("M", "XL"));
+
+        let stats = json!({"additions": 120, "deletions": 30, "changed_files": 4});
+        let mut app = review_request(json!({ "diff_stats": stats }));
+        assert!(screen(&mut app).contains("Review: Add rate limits L"));
+        assert!(detail(&app).contains(&"Size      L (+120 −30 in 4 files)".to_string()));
+        let app = review_request(json!({}));
+        assert!(!detail(&app).iter().any(|l| l.starts_with("Size")));
+    }
+
+    #[test]
+    fn pr_labels_show_as_chips_in_their_colors() {
+        let chip = |color: &str| {
+            label_chip(&PrLabel {
+                name: "bug".to_string(),
+                color: color.to_string(),
+            })
+            .style
+        };
+        // Dark text on a light label, light text on a dark one.
+        let light = chip("fbca04");
+        assert_eq!(
+            (light.bg, light.fg),
+            (Some(Color::Rgb(251, 202, 4)), Some(Color::Black))
+        );
+        let dark = chip("#5319e7");
+        assert_eq!(
+            (dark.bg, dark.fg),
+            (Some(Color::Rgb(83, 25, 231)), Some(Color::White))
+        );
+        // Without a usable color the chip is drawn reversed.
+        let plain = chip("red");
+        assert_eq!(plain.bg, None);
+        assert!(plain.add_modifier.contains(Modifier::REVERSED));
+
+        let app = review_request(json!({"labels": [
+            {"name": "bug", "color": "d73a4a"},
+            {"name": "priority/high"}
+        ]}));
+        assert!(detail(&app).contains(&"Labels     bug   priority/high".to_string()));
+        let app = review_request(json!({}));
+        assert!(!detail(&app).iter().any(|l| l.starts_with("Labels")));
+    }
+
+    #[test]
+    fn due_times_count_down_on_the_day() {
+        let today = date!(2026 - 03 - 11);
+        let label = |due| render_due(Some(due), today).0;
+        assert_eq!(label(due_in(1).unwrap()), "2026-03-12 (tomorrow)");
+        let nine_thirty = clock::at_local(date!(2026 - 03 - 13), time::macros::time!(9:30));
+        assert_eq!(label(Due::timed(nine_thirty)), "2026-03-13 09:30 (in 2d)");
+
+        // The flag, not the instant, decides: a due set for the day's last second keeps
+        // its time, and a date-only due stored at the UTC end of day shows no time.
+        let last_second = clock::at_local(date!(2026 - 03 - 13), time::macros::time!(23:59:59));
+        assert_eq!(label(Due::timed(last_second)), "2026-03-13 23:59 (in 2d)");
+        let utc_end = date!(2026 - 03 - 13)
+            .with_hms(23, 59, 59)
+            .unwrap()
+            .assume_utc();
+        let legacy = Due {
+            at: utc_end.into(),
+            has_time: false,
+        };
+        assert!(!label(legacy).contains(':'));
+
+        // On the day itself the label counts down to (or up from) the hour.
+        let on_the_day = |due: SystemTime| {
+            let (label, style) = render_due(Some(Due::timed(due)), clock::to_local(due).date());
+            (label.split_once(" (").unwrap().1.to_string(), style.fg)
+        };
+        let later = SystemTime::now() + Duration::from_secs(150 * 60);
+        assert_eq!(
+            on_the_day(later),
+            ("in 2h)".to_string(), Some(Color::Yellow))
+        );
+        let earlier = SystemTime::now() - Duration::from_secs(5 * 60);
+        assert_eq!(
+            on_the_day(earlier),
+            ("5m overdue)".to_string(), Some(Color::Red))
+        );
+    }
 }
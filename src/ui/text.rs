@@ -0,0 +1,92 @@
+//! Terminal column widths: CJK characters and most emoji take two cells, combining marks
+//! none, so lengths are measured in columns and text is cut between grapheme clusters.
+
+use std::borrow::Cow;
+
+use ratatui::text::Span;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+const ELLIPSIS: &str = "…";
+/// Columns of a shortened title worth showing.
+const MIN_FLEX: usize = 12;
+
+/// Columns `text` takes on screen.
+pub fn width(text: &str) -> usize {
+    text.width()
+}
+
+/// `text` cut to at most `max` columns, ending in "…" when something was left out.
+pub fn truncate(text: &str, max: usize) -> Cow<'_, str> {
+    if width(text) <= max {
+        return Cow::Borrowed(text);
+    }
+    let Some(room) = max.checked_sub(ELLIPSIS.width()) else {
+        return Cow::Borrowed("");
+    };
+    let mut used = 0;
+    let mut end = 0;
+    for (at, grapheme) in text.grapheme_indices(true) {
+        let w = grapheme.width();
+        if used + w > room {
+            break;
+        }
+        used += w;
+        end = at + grapheme.len();
+    }
+    Cow::Owned(format!("{}{ELLIPSIS}", text[..end].trim_end()))
+}
+
+/// Fit a line into `max` columns by shortening span `flex` (a title, keeping the badges
+/// after it); when the other spans alone do not fit, the whole line is cut instead.
+pub fn fit_spans(mut spans: Vec<Span<'_>>, flex: usize, max: usize) -> Vec<Span<'_>> {
+    let total: usize = spans.iter().map(|s| width(&s.content)).sum();
+    if total <= max {
+        return spans;
+    }
+    let flex_width = spans.get(flex).map_or(0, |s| width(&s.content));
+    let others = total - flex_width;
+    // Keep a readable piece of the title before sacrificing badges.
+    if flex < spans.len() && others + MIN_FLEX <= max {
+        let span = &mut spans[flex];
+        span.content = Cow::Owned(truncate(&span.content, max - others).into_owned());
+        return spans;
+    }
+    let mut left = max;
+    let mut fitted = Vec::new();
+    for mut span in spans {
+        let w = width(&span.content);
+        if w <= left {
+            left -= w;
+            fitted.push(span);
+            continue;
+        }
+        span.content = Cow::Owned(truncate(&span.content, left).into_owned());
+        fitted.push(span);
+        break;
+    }
+    fitted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_characters_are_cut_by_columns_with_an_ellipsis() {
+        assert_eq!(width("日本語"), 6);
+        assert_eq!(truncate("日本語のタスク", 7), "日本語…");
+        assert_eq!(truncate("short", 10), "short");
+        // "が" written as か + a combining mark stays whole.
+        assert_eq!(
+            truncate("か\u{3099}か\u{3099}か\u{3099}", 5),
+            "か\u{3099}か\u{3099}…"
+        );
+
+        let spans = vec![Span::raw("• 週次レポートを書いて送る"), Span::raw(" ✎")];
+        let fitted = fit_spans(spans, 0, 16);
+        let text: String = fitted.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, "• 週次レポー… ✎");
+        assert!(width(&text) <= 16);
+    }
+}
@@ -1,7 +1,294 @@
-use crate::repo::github::model::{Pr, ReviewState};
+use std::time::UNIX_EPOCH;
+
+use time::Duration;
+
+use crate::config::PrRule;
+use crate::domain::clock;
+use crate::domain::due::Due;
+use crate::domain::glob;
+use crate::domain::todo::{Priority, Todo};
+use crate::repo::github::model::{CiState, Pr, ReviewState};
 
 /// Decide whether a PR should be added as a todo.
-/// Current rule: add when the viewer is explicitly requested as a reviewer.
-pub fn should_add_todo(pr: &Pr) -> bool {
-    matches!(pr.review_state, ReviewState::Requested)
+/// Current rule: add when the viewer is explicitly requested as a reviewer, unless the
+/// PR is a draft and `ignore_drafts` is set.
+pub fn should_add_todo(pr: &Pr, ignore_drafts: bool) -> bool {
+    matches!(pr.review_state, ReviewState::Requested) && !(ignore_drafts && pr.is_draft)
+}
+
+/// Why my own PR needs me (`[github] track_own_prs`); empty when it does not.
+pub fn own_pr_issues(pr: &Pr) -> Vec<&'static str> {
+    let mut issues = Vec::new();
+    if !pr.is_viewer_author || pr.is_draft {
+        return issues;
+    }
+    if matches!(pr.ci_state, CiState::Failure) {
+        issues.push("CI failing");
+    }
+    if pr.merge_blockers.as_ref().is_some_and(|b| b.has_conflicts) {
+        issues.push("conflicts");
+    }
+    if matches!(pr.review_state, ReviewState::ChangesRequested) {
+        issues.push("changes requested");
+    }
+    issues
+}
+
+/// The PR got a new commit or other activity since I last looked at `todo` (●). Todos
+/// never looked at have no baseline yet and count as seen.
+pub fn is_unread(todo: &Todo, pr: &Pr) -> bool {
+    let Some(seen_at) = todo.last_seen_at else {
+        return false;
+    };
+    let new_commit = todo.last_seen_sha.is_some()
+        && pr.last_commit_sha.is_some()
+        && todo.last_seen_sha != pr.last_commit_sha;
+    let seen_at = seen_at
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    new_commit || pr.updated_at_unix > seen_at
+}
+
+/// Scores from here on get a ⚑ in the list.
+pub const URGENT_SCORE: u32 = 60;
+
+/// What makes a PR urgent, as (reason, points); `score` adds them up. Higher means it
+/// should be looked at sooner.
+pub fn score_parts(pr: &Pr, now: i64) -> Vec<(&'static str, i32)> {
+    let mut parts = Vec::new();
+    if pr.requested_directly {
+        parts.push(("review requested", 40));
+    } else if pr.is_team_request() {
+        parts.push(("team review requested", 25));
+    }
+    if pr.is_viewer_author {
+        let failing_required = pr
+            .merge_blockers
+            .as_ref()
+            .is_some_and(|b| !b.failing_required_checks.is_empty());
+        if failing_required {
+            parts.push(("required checks failing", 35));
+        } else if matches!(pr.ci_state, CiState::Failure) {
+            parts.push(("CI failing", 20));
+        }
+    }
+    if pr.merge_blockers.as_ref().is_some_and(|b| b.has_conflicts) {
+        parts.push(("conflicts", 20));
+    }
+    // Two points per day open, for at most 15 days.
+    let days = (now - pr.created_at_unix).max(0) / 86_400;
+    if days > 0 {
+        parts.push(("age", (days.min(15) * 2) as i32));
+    }
+    if pr.is_draft {
+        parts.push(("draft", -20));
+    }
+    parts
+}
+
+pub fn score(pr: &Pr, now: i64) -> u32 {
+    score_parts(pr, now)
+        .iter()
+        .map(|(_, points)| points)
+        .sum::<i32>()
+        .max(0) as u32
+}
+
+/// What the first matching `[[github.rules]]` entry made of a PR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Triage {
+    pub priority: Priority,
+    pub due: Option<Due>,
+    /// Without `#`.
+    pub tags: Vec<String>,
+}
+
+/// Priority, due date and tags for the todo of `pr`; `None` when a rule skips it.
+pub fn classify(pr: &Pr, rules: &[PrRule]) -> Option<Triage> {
+    let rule = rules.iter().find(|rule| rule_matches(rule, pr));
+    if rule.is_some_and(|r| r.skip) {
+        return None;
+    }
+    let due_days = rule.and_then(|r| r.due_days).unwrap_or(0);
+    let due =
+        (due_days >= 0).then(|| Due::day(clock::today().saturating_add(Duration::days(due_days))));
+    Some(Triage {
+        priority: rule.and_then(|r| r.priority).unwrap_or(Priority::High),
+        due,
+        tags: rule
+            .map(|r| {
+                r.tags
+                    .iter()
+                    .map(|t| t.trim().trim_start_matches('#').replace(' ', "-"))
+                    .filter(|t| !t.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    })
+}
+
+fn rule_matches(rule: &PrRule, pr: &Pr) -> bool {
+    let pattern = |pattern: &Option<String>, text: &str| {
+        pattern.as_deref().is_none_or(|p| glob::matches(p, text))
+    };
+    pattern(&rule.author, &pr.author)
+        && pattern(&rule.repo, &format!("{}/{}", pr.owner, pr.repo))
+        && pattern(&rule.title, &pr.title)
+        && rule.draft.is_none_or(|draft| draft == pr.is_draft)
+        && rule
+            .label
+            .as_deref()
+            .is_none_or(|p| pr.labels.iter().any(|label| glob::matches(p, &label.name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GithubSettings;
+    use crate::repo::github::model::{MergeBlockers, PrLabel};
+
+    fn pr(author: &str, title: &str, is_draft: bool) -> Pr {
+        Pr {
+            pr_key: "acme/api#1".to_string(),
+            owner: "acme".to_string(),
+            repo: "api".to_string(),
+            number: 1,
+            author: author.to_string(),
+            title: title.to_string(),
+            url: "https://github.com/acme/api/pull/1".to_string(),
+            created_at_unix: 0,
+            updated_at_unix: 0,
+            last_commit_sha: None,
+            ci_state: CiState::None,
+            ci_checks: Vec::new(),
+            review_state: ReviewState::Requested,
+            is_draft,
+            mergeable: None,
+            merge_state_status: None,
+            is_viewer_author: false,
+            viewer_approved: false,
+            requested_directly: true,
+            requested_teams: Vec::new(),
+            requested_users: Vec::new(),
+            reviews: Vec::new(),
+            assignees: Vec::new(),
+            labels: Vec::new(),
+            milestone: None,
+            diff_stats: None,
+            comments: 0,
+            review_threads: 0,
+            unresolved_threads: 0,
+            watched: false,
+            author_is_bot: false,
+            merge_blockers: None,
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_decides() {
+        let today = Some(Due::day(clock::today()));
+        let defaults = GithubSettings::default().rules;
+        let renovate = classify(&pr("renovate[bot]", "Update serde", false), &defaults).unwrap();
+        assert_eq!(renovate.priority, Priority::Medium);
+        assert_ne!(renovate.due, today);
+        let human = classify(&pr("octocat", "Fix login", false), &defaults).unwrap();
+        assert_eq!((human.priority, human.due), (Priority::High, today));
+
+        let rules = toml::from_str::<GithubSettings>(
+            r##"
+            [[rules]]
+            draft = true
+            skip = true
+            [[rules]]
+            label = "urgent"
+            priority = "high"
+            tags = ["urgent"]
+            [[rules]]
+            repo = "acme/*"
+            title = "chore(deps)*"
+            priority = "low"
+            due_days = -1
+            tags = ["#deps"]
+            "##,
+        )
+        .unwrap()
+        .rules;
+        assert_eq!(classify(&pr("octocat", "WIP", true), &rules), None);
+        let mut labelled = pr("octocat", "chore(deps): bump openssl", false);
+        labelled.labels.push(PrLabel {
+            name: "Urgent".to_string(),
+            color: "d73a4a".to_string(),
+        });
+        assert_eq!(
+            classify(&labelled, &rules).map(|t| (t.priority, t.tags)),
+            Some((Priority::High, vec!["urgent".to_string()]))
+        );
+        assert_eq!(
+            classify(&pr("octocat", "chore(deps): bump tokio", false), &rules),
+            Some(Triage {
+                priority: Priority::Low,
+                due: None,
+                tags: vec!["deps".to_string()],
+            })
+        );
+        assert_eq!(
+            classify(&pr("octocat", "feat: x", false), &rules).map(|t| t.priority),
+            Some(Priority::High)
+        );
+    }
+
+    #[test]
+    fn score_ranks_blocked_own_prs_and_old_requests_first() {
+        let now = 20 * 86_400;
+        let fresh = pr("octocat", "Fix", false);
+        assert_eq!(score(&fresh, 0), 40);
+        // 20 days old: the age bonus stops at 30.
+        assert_eq!(score(&fresh, now), 70);
+
+        let mut mine = pr("me", "Mine", false);
+        mine.requested_directly = false;
+        mine.is_viewer_author = true;
+        mine.created_at_unix = now;
+        mine.merge_blockers = Some(MergeBlockers {
+            has_conflicts: true,
+            failing_required_checks: vec!["test".to_string()],
+            ..MergeBlockers::default()
+        });
+        assert_eq!(score(&mine, now), 55);
+
+        let mut draft = pr("octocat", "WIP", true);
+        draft.requested_directly = false;
+        assert_eq!(score(&draft, now), 10);
+        draft.created_at_unix = now;
+        assert_eq!(score(&draft, now), 0);
+    }
+
+    #[test]
+    fn drafts_are_kept_unless_ignored() {
+        let draft = pr("octocat", "WIP", true);
+        assert!(should_add_todo(&draft, false));
+        assert!(!should_add_todo(&draft, true));
+        assert!(should_add_todo(&pr("octocat", "Ready", false), true));
+    }
+
+    #[test]
+    fn new_commits_and_activity_after_the_last_look_are_unread() {
+        let mut pr = pr("octocat", "Fix", false);
+        pr.last_commit_sha = Some("aaa".to_string());
+        pr.updated_at_unix = 1_000;
+        let mut todo = Todo::with_meta("Review", Priority::Medium, None);
+        assert!(
+            !is_unread(&todo, &pr),
+            "never looked at: nothing to compare"
+        );
+
+        todo.last_seen_sha = Some("aaa".to_string());
+        todo.last_seen_at = Some(UNIX_EPOCH + std::time::Duration::from_secs(1_000));
+        assert!(!is_unread(&todo, &pr));
+        pr.updated_at_unix = 1_001;
+        assert!(is_unread(&todo, &pr));
+        pr.updated_at_unix = 1_000;
+        pr.last_commit_sha = Some("bbb".to_string());
+        assert!(is_unread(&todo, &pr));
+    }
 }
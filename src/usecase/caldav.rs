@@ -0,0 +1,504 @@
+//! Two-way sync with a CalDAV task list. Each linked task remembers the server's etag and
+//! the fields both sides agreed on at the last sync; a sync merges field by field from
+//! there, so an edit on either side survives. When both sides changed the same field,
+//! the server's value wins and the sync reports a conflict.
+//!
+//! Writes go out with `If-Match`, so a task edited on the server after it was fetched is
+//! left alone and merged again on the next sync instead of being overwritten.
+//!
+//! Tasks created on the server become todos keyed `caldav:<uid>`; todos created in koto
+//! (not GitHub or Jira ones) are uploaded with their id as UID.
+
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::activity::ActivityKind;
+use crate::domain::query::{SearchScope, TodoQuery};
+use crate::domain::todo::{CALDAV_KEY_PREFIX, Todo, TodoId};
+use crate::repo::TodoRepository;
+use crate::repo::caldav::{
+    CaldavClient, CaldavConfig, RemoteTask, TaskFields, Vtodo, Written, from_unix,
+};
+
+/// Unix time of the last successful CalDAV sync.
+pub const META_CALDAV_SYNCED_AT: &str = "caldav.synced_at";
+/// `SyncState` as JSON.
+const META_CALDAV_STATE: &str = "caldav.state";
+
+/// What the last sync left each linked task at, by UID.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncState {
+    tasks: BTreeMap<String, Linked>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Linked {
+    todo_id: TodoId,
+    href: String,
+    etag: Option<String>,
+    /// The fields as both sides had them after the last sync.
+    base: TaskFields,
+}
+
+/// A change the sync makes to the local store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocalChange {
+    /// A task new on the server.
+    Create {
+        uid: String,
+        href: String,
+        etag: Option<String>,
+        fields: TaskFields,
+    },
+    Update {
+        id: TodoId,
+        fields: TaskFields,
+    },
+    /// The task was deleted on the server.
+    Archive {
+        id: TodoId,
+    },
+}
+
+#[derive(Debug, Clone)]
+enum Push {
+    Put {
+        uid: String,
+        todo_id: TodoId,
+        href: String,
+        etag: Option<String>,
+        vtodo: Vtodo,
+        fields: TaskFields,
+    },
+    Delete {
+        href: String,
+        etag: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CaldavSummary {
+    /// Todos added for tasks new on the server.
+    pub added: usize,
+    /// Todos changed to match the server.
+    pub updated: usize,
+    /// Todos archived because their task was deleted on the server.
+    pub archived: usize,
+    /// Tasks created or changed on the server.
+    pub pushed: usize,
+    /// Tasks deleted on the server because their todo was deleted.
+    pub deleted: usize,
+    /// Fields changed on both sides; the server's value was kept.
+    pub conflicts: usize,
+    /// Writes refused because the task changed on the server meanwhile; retried next sync.
+    pub skipped: usize,
+}
+
+/// Result of the network half of a sync, applied to the store with `apply`.
+#[derive(Debug, Clone)]
+pub struct CaldavOutcome {
+    pub tasks: usize,
+    pub local: Vec<LocalChange>,
+    pub state: SyncState,
+    pub summary: CaldavSummary,
+}
+
+/// What a sync starts from: every todo, archived ones included, and the last state.
+pub fn snapshot(repo: &dyn TodoRepository) -> Result<(Vec<Todo>, SyncState)> {
+    let todos = repo.search(&TodoQuery {
+        text: String::new(),
+        scope: SearchScope::All,
+    })?;
+    // A state that no longer parses is rebuilt: linked todos are matched again by key.
+    let state = repo
+        .meta(META_CALDAV_STATE)?
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+    Ok((todos, state))
+}
+
+/// Fetch the tasks, merge them with `todos` and write the server's side back.
+pub async fn exchange(
+    cfg: &CaldavConfig,
+    todos: Vec<Todo>,
+    state: SyncState,
+) -> Result<CaldavOutcome> {
+    let client = CaldavClient::new(cfg)?;
+    let remote = client.fetch().await?;
+    let tasks = remote.len();
+    let mut plan = plan(&todos, state, remote, |uid| client.href_for(uid));
+    for push in plan.pushes {
+        match push {
+            Push::Put {
+                uid,
+                todo_id,
+                href,
+                etag,
+                vtodo,
+                fields,
+            } => match client.put(&href, etag.as_deref(), vtodo.to_ics()).await? {
+                Written::Stored(etag) => {
+                    plan.summary.pushed += 1;
+                    plan.state.tasks.insert(
+                        uid,
+                        Linked {
+                            todo_id,
+                            href,
+                            etag,
+                            base: fields,
+                        },
+                    );
+                }
+                Written::Changed => plan.summary.skipped += 1,
+            },
+            Push::Delete { href, etag } => match client.delete(&href, etag.as_deref()).await? {
+                Written::Stored(_) => plan.summary.deleted += 1,
+                Written::Changed => plan.summary.skipped += 1,
+            },
+        }
+    }
+    Ok(CaldavOutcome {
+        tasks,
+        local: plan.local,
+        state: plan.state,
+        summary: plan.summary,
+    })
+}
+
+struct Plan {
+    local: Vec<LocalChange>,
+    pushes: Vec<Push>,
+    state: SyncState,
+    summary: CaldavSummary,
+}
+
+/// Decide every change on both sides. Tasks to write are left out of the returned state
+/// until the server accepts them, so a refused write is merged again next time.
+fn plan(
+    todos: &[Todo],
+    mut previous: SyncState,
+    remote: Vec<RemoteTask>,
+    href_for: impl Fn(&str) -> String,
+) -> Plan {
+    let mut plan = Plan {
+        local: Vec::new(),
+        pushes: Vec::new(),
+        state: SyncState::default(),
+        summary: CaldavSummary::default(),
+    };
+    let find = |id: TodoId| todos.iter().find(|t| t.id == id);
+    let mut linked: HashSet<TodoId> = HashSet::new();
+    for task in remote {
+        let last = previous.tasks.remove(&task.uid);
+        let key = format!("{CALDAV_KEY_PREFIX}{}", task.uid);
+        let todo = match &last {
+            Some(last) => find(last.todo_id),
+            None => todos.iter().find(|t| {
+                t.external_key.as_deref() == Some(key.as_str()) || t.id.to_string() == task.uid
+            }),
+        };
+        let remote_fields = task.vtodo.fields();
+        let Some(todo) = todo else {
+            if last.is_some() {
+                // Deleted here since the last sync.
+                plan.pushes.push(Push::Delete {
+                    href: task.href,
+                    etag: task.etag,
+                });
+            } else if !remote_fields.status.is_closed() {
+                plan.local.push(LocalChange::Create {
+                    uid: task.uid,
+                    href: task.href,
+                    etag: task.etag,
+                    fields: remote_fields,
+                });
+                plan.summary.added += 1;
+            }
+            continue;
+        };
+        linked.insert(todo.id);
+        let local = TaskFields::of(todo);
+        // Linked for the first time (or the state was lost): the server's copy is the base.
+        let base = last.map_or_else(|| remote_fields.clone(), |l| l.base);
+        let (merged, conflicts) = merge(&base, &local, &remote_fields);
+        plan.summary.conflicts += conflicts;
+        // Archived todos are left as they are, and so is their task.
+        if todo.archived {
+            plan.state.tasks.insert(
+                task.uid,
+                Linked {
+                    todo_id: todo.id,
+                    href: task.href,
+                    etag: task.etag,
+                    base,
+                },
+            );
+            continue;
+        }
+        if merged != local {
+            plan.local.push(LocalChange::Update {
+                id: todo.id,
+                fields: merged.clone(),
+            });
+            plan.summary.updated += 1;
+        }
+        if merged == remote_fields {
+            plan.state.tasks.insert(
+                task.uid,
+                Linked {
+                    todo_id: todo.id,
+                    href: task.href,
+                    etag: task.etag,
+                    base: merged,
+                },
+            );
+        } else {
+            let mut vtodo = task.vtodo;
+            vtodo.set_fields(&merged);
+            plan.pushes.push(Push::Put {
+                uid: task.uid,
+                todo_id: todo.id,
+                href: task.href,
+                etag: task.etag,
+                vtodo,
+                fields: merged,
+            });
+        }
+    }
+
+    for (_, last) in previous.tasks {
+        // Deleted on the server.
+        if let Some(todo) = find(last.todo_id).filter(|t| !t.archived) {
+            linked.insert(todo.id);
+            plan.local.push(LocalChange::Archive { id: todo.id });
+            plan.summary.archived += 1;
+        }
+    }
+    for todo in todos {
+        if linked.contains(&todo.id) || todo.archived || todo.is_closed() {
+            continue;
+        }
+        match todo.external_key.as_deref() {
+            // Its task is gone and the state that linked them was lost.
+            Some(key) if key.starts_with(CALDAV_KEY_PREFIX) => {
+                plan.local.push(LocalChange::Archive { id: todo.id });
+                plan.summary.archived += 1;
+            }
+            Some(_) => {}
+            None => {
+                let uid = todo.id.to_string();
+                let fields = TaskFields::of(todo);
+                let mut vtodo = Vtodo::new(&uid);
+                vtodo.set_fields(&fields);
+                plan.pushes.push(Push::Put {
+                    href: href_for(&uid),
+                    uid,
+                    todo_id: todo.id,
+                    etag: None,
+                    vtodo,
+                    fields,
+                });
+            }
+        }
+    }
+    plan
+}
+
+/// Three-way merge of each field; returns the merge and how many fields conflicted.
+fn merge(base: &TaskFields, local: &TaskFields, remote: &TaskFields) -> (TaskFields, usize) {
+    let mut conflicts = 0;
+    // A due and whether it has a time of day change together.
+    let (due, due_has_time) = *pick(
+        &(base.due, base.due_has_time),
+        &(local.due, local.due_has_time),
+        &(remote.due, remote.due_has_time),
+        &mut conflicts,
+    );
+    let merged = TaskFields {
+        title: pick(&base.title, &local.title, &remote.title, &mut conflicts).clone(),
+        status: *pick(&base.status, &local.status, &remote.status, &mut conflicts),
+        priority: *pick(
+            &base.priority,
+            &local.priority,
+            &remote.priority,
+            &mut conflicts,
+        ),
+        due,
+        due_has_time,
+        start: *pick(&base.start, &local.start, &remote.start, &mut conflicts),
+        notes: pick(&base.notes, &local.notes, &remote.notes, &mut conflicts).clone(),
+    };
+    (merged, conflicts)
+}
+
+/// The side that changed `base`; the server's when both did.
+fn pick<'a, T: PartialEq>(base: &T, local: &'a T, remote: &'a T, conflicts: &mut usize) -> &'a T {
+    if local == base || remote == local {
+        remote
+    } else if remote == base {
+        local
+    } else {
+        *conflicts += 1;
+        remote
+    }
+}
+
+/// Apply the local half of a sync and store the new state.
+pub fn apply(repo: &mut dyn TodoRepository, outcome: &CaldavOutcome) -> Result<CaldavSummary> {
+    let mut state = outcome.state.clone();
+    for change in &outcome.local {
+        match change {
+            LocalChange::Create {
+                uid,
+                href,
+                etag,
+                fields,
+            } => {
+                let todo = repo.add(
+                    fields.title.clone(),
+                    fields.priority,
+                    fields.due(),
+                    None,
+                    Some(format!("{CALDAV_KEY_PREFIX}{uid}")),
+                )?;
+                update(repo, &todo, fields)?;
+                state.tasks.insert(
+                    uid.clone(),
+                    Linked {
+                        todo_id: todo.id,
+                        href: href.clone(),
+                        etag: etag.clone(),
+                        base: fields.clone(),
+                    },
+                );
+            }
+            LocalChange::Update { id, fields } => {
+                if let Some(todo) = repo.all()?.into_iter().find(|t| t.id == *id) {
+                    update(repo, &todo, fields)?;
+                }
+            }
+            LocalChange::Archive { id } => {
+                repo.archive(*id)?;
+            }
+        }
+    }
+    repo.set_meta(META_CALDAV_STATE, &serde_json::to_string(&state)?)?;
+    repo.set_meta(META_CALDAV_SYNCED_AT, &crate::now_unix().to_string())?;
+    let summary = &outcome.summary;
+    repo.log_activity(
+        ActivityKind::Sync,
+        None,
+        &format!(
+            "CalDAV: {} tasks, {} pushed{}",
+            outcome.tasks,
+            summary.pushed,
+            match summary.conflicts {
+                0 => String::new(),
+                n => format!(", {n} conflicts (server kept)"),
+            }
+        ),
+    )?;
+    Ok(summary.clone())
+}
+
+/// Bring `todo` to `fields`, touching only what differs.
+fn update(repo: &mut dyn TodoRepository, todo: &Todo, fields: &TaskFields) -> Result<()> {
+    let current = TaskFields::of(todo);
+    if current.title != fields.title {
+        repo.set_title(todo.id, fields.title.clone())?;
+    }
+    if (current.priority, current.due()) != (fields.priority, fields.due()) {
+        repo.update_meta(todo.id, fields.priority, fields.due())?;
+    }
+    if current.status != fields.status {
+        repo.set_status(todo.id, fields.status)?;
+    }
+    if current.start != fields.start {
+        repo.set_start(todo.id, fields.start.map(from_unix))?;
+    }
+    if current.notes != fields.notes {
+        repo.set_notes(todo.id, fields.notes.clone())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::todo::{Priority, Status};
+    use crate::repo::memory::InMemoryTodoRepo;
+
+    fn task(uid: &str, etag: &str, fields: &TaskFields) -> RemoteTask {
+        let mut vtodo = Vtodo::new(uid);
+        vtodo.set_fields(fields);
+        RemoteTask {
+            href: format!("/tasks/{uid}.ics"),
+            etag: Some(etag.to_string()),
+            uid: uid.to_string(),
+            vtodo,
+        }
+    }
+
+    #[test]
+    fn edits_on_both_sides_merge_by_field() {
+        let mut repo = InMemoryTodoRepo::default();
+        let fields = TaskFields {
+            title: "Book flights".to_string(),
+            status: Status::Todo,
+            priority: Priority::Medium,
+            due: None,
+            due_has_time: false,
+            start: None,
+            notes: None,
+        };
+        let href = |uid: &str| format!("/tasks/{uid}.ics");
+        let mine = repo
+            .add("Write agenda".into(), Priority::Low, None, None, None)
+            .unwrap();
+
+        // First sync: the server's task comes in, the local todo goes up.
+        let (todos, state) = snapshot(&repo).unwrap();
+        let first = plan(&todos, state, vec![task("t1", "\"1\"", &fields)], href);
+        assert_eq!(first.summary.added, 1);
+        assert!(
+            matches!(&first.pushes[..], [Push::Put { uid, etag: None, .. }] if *uid == mine.id.to_string())
+        );
+        let outcome = CaldavOutcome {
+            tasks: 1,
+            local: first.local,
+            state: first.state,
+            summary: first.summary,
+        };
+        apply(&mut repo, &outcome).unwrap();
+        let flights = repo.all().unwrap()[1].clone();
+        assert_eq!(flights.external_key.as_deref(), Some("caldav:t1"));
+
+        // Priority changed here, title on the server: both survive.
+        repo.update_meta(flights.id, Priority::High, None).unwrap();
+        let renamed = TaskFields {
+            title: "Book flights to Lisbon".to_string(),
+            ..fields.clone()
+        };
+        let (todos, state) = snapshot(&repo).unwrap();
+        let second = plan(&todos, state, vec![task("t1", "\"2\"", &renamed)], href);
+        assert_eq!(second.summary.conflicts, 0);
+        let merged = TaskFields {
+            priority: Priority::High,
+            ..renamed
+        };
+        assert_eq!(
+            second.local,
+            [LocalChange::Update {
+                id: flights.id,
+                fields: merged.clone()
+            }]
+        );
+        assert!(matches!(&second.pushes[..], [
+            Push::Put { etag: Some(etag), fields, .. },
+            Push::Put { etag: None, .. },
+        ] if etag == "\"2\"" && *fields == merged));
+        assert!(!second.state.tasks.contains_key("t1"));
+    }
+}
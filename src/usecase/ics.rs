@@ -0,0 +1,152 @@
+use std::time::SystemTime;
+
+use time::{OffsetDateTime, macros::format_description};
+
+use crate::domain::clock;
+use crate::domain::due::Due;
+use crate::domain::todo::{Priority, Todo};
+
+/// Render todos that have a due date as an iCalendar document.
+/// With `as_events`, each item becomes a VEVENT (for calendar apps without task support);
+/// otherwise a VTODO. High-priority items carry a display alarm.
+pub fn to_ics(todos: &[Todo], as_events: bool, now: SystemTime) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//koto//koto todo export//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    let stamp = utc_stamp(now);
+
+    for todo in todos.iter().filter(|t| !t.archived) {
+        let Some(Due { at: due, has_time }) = todo.due_value() else {
+            continue;
+        };
+        let component = if as_events { "VEVENT" } else { "VTODO" };
+        lines.push(format!("BEGIN:{component}"));
+        lines.push(format!("UID:{}@koto", todo.id));
+        lines.push(format!("DTSTAMP:{stamp}"));
+        lines.push(format!("CREATED:{}", utc_stamp(todo.created_at)));
+        lines.push(format!("SUMMARY:{}", escape_text(&todo.title)));
+        lines.push(format!("PRIORITY:{}", ics_priority(todo.priority)));
+
+        let all_day = !has_time;
+        if as_events {
+            if all_day {
+                lines.push(format!("DTSTART;VALUE=DATE:{}", local_date(due)));
+            } else {
+                lines.push(format!("DTSTART:{}", utc_stamp(due)));
+                lines.push("DURATION:PT30M".to_string());
+            }
+        } else {
+            if all_day {
+                lines.push(format!("DUE;VALUE=DATE:{}", local_date(due)));
+            } else {
+                lines.push(format!("DUE:{}", utc_stamp(due)));
+            }
+            let status = if todo.is_closed() {
+                "COMPLETED"
+            } else {
+                "NEEDS-ACTION"
+            };
+            lines.push(format!("STATUS:{status}"));
+        }
+        if let Some(url) = todo.external_url.as_deref() {
+            lines.push(format!("URL:{url}"));
+        }
+
+        if todo.priority == Priority::High && !todo.is_closed() {
+            lines.push("BEGIN:VALARM".to_string());
+            lines.push("ACTION:DISPLAY".to_string());
+            lines.push(format!("DESCRIPTION:{}", escape_text(&todo.title)));
+            let trigger = match (as_events, all_day) {
+                // All-day items: remind at 09:00 on the day.
+                (_, true) => "TRIGGER:PT9H",
+                (true, false) => "TRIGGER:-PT1H",
+                (false, false) => "TRIGGER;RELATED=END:-PT1H",
+            };
+            lines.push(trigger.to_string());
+            lines.push("END:VALARM".to_string());
+        }
+        lines.push(format!("END:{component}"));
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+    let mut out = String::new();
+    for line in lines {
+        out.push_str(&fold(&line));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// RFC 5545 priority: 1 is highest, 9 lowest.
+fn ics_priority(priority: Priority) -> u8 {
+    match priority {
+        Priority::High => 1,
+        Priority::Medium => 5,
+        Priority::Low => 9,
+    }
+}
+
+fn local_date(t: SystemTime) -> String {
+    clock::to_local(t)
+        .format(format_description!("[year][month][day]"))
+        .unwrap_or_default()
+}
+
+fn utc_stamp(t: SystemTime) -> String {
+    OffsetDateTime::from(t)
+        .format(format_description!(
+            "[year][month][day]T[hour][minute][second]Z"
+        ))
+        .unwrap_or_default()
+}
+
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Fold content lines longer than 75 octets, never splitting a UTF-8 character.
+fn fold(line: &str) -> String {
+    let mut out = String::with_capacity(line.len() + line.len() / 74 * 3);
+    let mut width = 0;
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if width + len > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += len;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_and_folds_long_summaries() {
+        assert_eq!(escape_text("a,b;c\\d\ne"), "a\\,b\\;c\\\\d\\ne");
+
+        let long = format!("SUMMARY:{}", "あ".repeat(40));
+        let folded = fold(&long);
+        for part in folded.split("\r\n") {
+            assert!(part.len() <= 75, "{} octets", part.len());
+        }
+        assert_eq!(folded.replace("\r\n ", ""), long);
+    }
+}
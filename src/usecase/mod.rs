@@ -1 +1,6 @@
 pub mod attention;
+pub mod caldav;
+pub mod ics;
+pub mod report;
+pub mod sync;
+pub mod widget;
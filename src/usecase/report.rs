@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use time::macros::format_description;
+
+use crate::domain::clock;
+use crate::domain::time_entry::{TimeEntry, format_duration};
+use crate::domain::todo::{Todo, TodoId};
+use crate::repo::github::model::{CiState, Pr, ReviewState};
+
+/// Plain-text summary of tracked time per todo, longest first.
+/// Only the part of each session after `since` is counted.
+pub fn time_report(
+    todos: &[Todo],
+    entries: &[TimeEntry],
+    since: Option<SystemTime>,
+    now: SystemTime,
+) -> String {
+    let mut totals: HashMap<TodoId, Duration> = HashMap::new();
+    for entry in entries {
+        let spent = entry.duration_since(since, now);
+        if !spent.is_zero() {
+            *totals.entry(entry.todo_id).or_default() += spent;
+        }
+    }
+
+    let mut rows: Vec<(Duration, &str)> = totals
+        .iter()
+        .map(|(id, spent)| {
+            let title = todos
+                .iter()
+                .find(|t| t.id == *id)
+                .map(|t| t.title.as_str())
+                .unwrap_or("(deleted todo)");
+            (*spent, title)
+        })
+        .collect();
+    rows.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+
+    if rows.is_empty() {
+        return "No tracked time.\n".to_string();
+    }
+
+    let mut out = String::new();
+    for (spent, title) in &rows {
+        out.push_str(&format!("{:>9}  {title}\n", format_duration(*spent)));
+    }
+    let total: Duration = rows.iter().map(|(d, _)| *d).sum();
+    out.push_str(&format!("{:>9}\n", "-------"));
+    out.push_str(&format!("{:>9}  total\n", format_duration(total)));
+    out
+}
+
+/// Markdown table of PRs waiting on the viewer's review, longest-waiting first.
+pub fn reviews_markdown(prs: &[Pr], now_unix: i64) -> String {
+    let mut pending: Vec<&Pr> = prs
+        .iter()
+        .filter(|pr| matches!(pr.review_state, ReviewState::Requested))
+        .collect();
+    pending.sort_by_key(|pr| (pr.created_at_unix, pr.pr_key.clone()));
+
+    if pending.is_empty() {
+        return "No pending review requests.\n".to_string();
+    }
+
+    let mut out = String::from("| Repo | PR | Author | Waiting | CI |\n|---|---|---|---:|---|\n");
+    for pr in pending {
+        let days = (now_unix - pr.created_at_unix).max(0) / 86_400;
+        out.push_str(&format!(
+            "| {}/{} | [#{} {}]({}) | @{} | {}d | {} |\n",
+            pr.owner,
+            pr.repo,
+            pr.number,
+            escape_cell(&pr.title),
+            pr.url,
+            pr.author,
+            days,
+            ci_label(&pr.ci_state),
+        ));
+    }
+    out
+}
+
+/// Markdown for a weekly update: todos completed after `since` (PR todos listed as
+/// reviews), and todos whose due date passed in the period while open, or that were
+/// finished late. `todos` should include archived ones.
+pub fn weekly_markdown(todos: &[Todo], since: SystemTime, now: SystemTime) -> String {
+    let in_period = |t: SystemTime| t >= since && t <= now;
+    let mut completed: Vec<&Todo> = todos
+        .iter()
+        .filter(|t| t.is_closed() && t.completed_at.is_some_and(in_period))
+        .collect();
+    completed.sort_by_key(|t| t.completed_at);
+    let (reviewed, completed): (Vec<&Todo>, Vec<&Todo>) = completed
+        .into_iter()
+        .partition(|t| t.github_pr_key().is_some());
+
+    let mut slipped: Vec<(&Todo, SystemTime)> = todos
+        .iter()
+        .filter_map(|t| t.due.filter(|due| in_period(*due)).map(|due| (t, due)))
+        .filter(|(t, due)| match t.completed_at {
+            Some(at) if t.is_closed() => at > *due,
+            _ => !t.is_closed(),
+        })
+        .collect();
+    slipped.sort_by_key(|(_, due)| *due);
+
+    let mut out = format!("## Weekly review ({} – {})\n", date(since), date(now));
+    out.push_str(&format!("\n### Completed ({})\n\n", completed.len()));
+    if completed.is_empty() {
+        out.push_str("Nothing completed.\n");
+    }
+    for todo in &completed {
+        out.push_str(&format!("- {}\n", linked_title(todo)));
+    }
+    out.push_str(&format!("\n### Reviewed PRs ({})\n\n", reviewed.len()));
+    if reviewed.is_empty() {
+        out.push_str("No PR todos completed.\n");
+    }
+    for todo in &reviewed {
+        out.push_str(&format!("- {}\n", linked_title(todo)));
+    }
+    out.push_str(&format!("\n### Slipped ({})\n\n", slipped.len()));
+    if slipped.is_empty() {
+        out.push_str("Nothing slipped past its due date.\n");
+    }
+    for (todo, due) in &slipped {
+        let state = match todo.completed_at {
+            Some(at) if todo.is_closed() => format!("done {}", date(at)),
+            _ => "still open".to_string(),
+        };
+        out.push_str(&format!(
+            "- {} — due {}, {state}\n",
+            linked_title(todo),
+            date(*due)
+        ));
+    }
+    out
+}
+
+fn linked_title(todo: &Todo) -> String {
+    match todo.external_url.as_deref() {
+        Some(url) => format!("[{}]({url})", todo.title.replace(['[', ']'], "")),
+        None => todo.title.clone(),
+    }
+}
+
+fn date(t: SystemTime) -> String {
+    clock::to_local(t)
+        .format(format_description!("[year]-[month]-[day]"))
+        .unwrap_or_else(|_| "?".into())
+}
+
+fn ci_label(state: &CiState) -> &'static str {
+    match state {
+        CiState::Success => "✅ passing",
+        CiState::Failure => "❌ failing",
+        CiState::Running => "⏳ running",
+        CiState::None => "–",
+    }
+}
+
+/// Keep titles from breaking the table layout.
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['[', ']'], "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::due::Due;
+    use crate::domain::todo::{GITHUB_PR_KEY_PREFIX, Priority, Status};
+
+    #[test]
+    fn weekly_review_sorts_todos_into_sections() {
+        let day = Duration::from_secs(86_400);
+        let now = SystemTime::now();
+        let since = now - day * 7;
+
+        let mut shipped = Todo::with_meta("Ship release", Priority::High, None);
+        shipped.status = Status::Done;
+        shipped.completed_at = Some(now - day);
+        let mut review = Todo::with_meta("acme/api#7 by bob: Fix login", Priority::Medium, None);
+        review.status = Status::Done;
+        review.completed_at = Some(now - day * 2);
+        review.external_key = Some(format!("{GITHUB_PR_KEY_PREFIX}acme/api#7"));
+        review.external_url = Some("https://github.com/acme/api/pull/7".to_string());
+        let mut old = Todo::with_meta("Done last month", Priority::Low, None);
+        old.status = Status::Done;
+        old.completed_at = Some(now - day * 30);
+        let overdue = Todo::with_meta(
+            "Write RFC",
+            Priority::Medium,
+            Some(Due::timed(now - day * 3)),
+        );
+        let mut late = Todo::with_meta(
+            "Renew cert",
+            Priority::High,
+            Some(Due::timed(now - day * 5)),
+        );
+        late.status = Status::Done;
+        late.completed_at = Some(now - day * 4);
+        let future = Todo::with_meta("Plan offsite", Priority::Low, Some(Due::timed(now + day)));
+
+        let md = weekly_markdown(&[shipped, review, old, overdue, late, future], since, now);
+        assert!(md.contains("### Completed (2)\n\n- Renew cert\n- Ship release\n"));
+        assert!(md.contains(
+            "### Reviewed PRs (1)\n\n- [acme/api#7 by bob: Fix login](https://github.com/acme/api/pull/7)\n"
+        ));
+        assert!(md.contains("### Slipped (2)\n\n- Renew cert — due "));
+        assert!(md.contains("- Write RFC — due "));
+        assert!(md.contains(", still open\n"));
+        assert!(!md.contains("Done last month") && !md.contains("Plan offsite"));
+    }
+
+    #[test]
+    fn review_report_lists_pending_requests_longest_waiting_first() {
+        let day = 86_400;
+        let pr = |key: &str, title: &str, created: i64, review: &str, ci: &str| -> Pr {
+            let (repo, number) = key.split_once('#').unwrap();
+            let (owner, name) = repo.split_once('/').unwrap();
+            serde_json::from_value(serde_json::json!({
+                "pr_key": key, "owner": owner, "repo": name,
+                "number": number.parse::<i64>().unwrap(), "author": "bob", "title": title,
+                "url": format!("https://github.com/{owner}/{name}/pull/{number}"),
+                "updated_at_unix": 0, "created_at_unix": created, "ci_state": ci,
+                "ci_checks": [], "review_state": review, "is_draft": false,
+                "is_viewer_author": false
+            }))
+            .unwrap()
+        };
+        let now = 100 * day;
+        let prs = [
+            pr(
+                "acme/web#3",
+                "Dark | light [theme]",
+                now - day / 2,
+                "Requested",
+                "Running",
+            ),
+            pr(
+                "acme/api#7",
+                "Fix login",
+                now - 3 * day,
+                "Requested",
+                "Failure",
+            ),
+            pr(
+                "acme/api#9",
+                "Already reviewed",
+                now - 9 * day,
+                "Approved",
+                "Success",
+            ),
+        ];
+
+        assert_eq!(
+            reviews_markdown(&prs, now),
+            "| Repo | PR | Author | Waiting | CI |\n\
+             |---|---|---|---:|---|\n\
+             | acme/api | [#7 Fix login](https://github.com/acme/api/pull/7) | @bob | 3d | ❌ failing |\n\
+             | acme/web | [#3 Dark \\| light theme](https://github.com/acme/web/pull/3) | @bob | 0d | ⏳ running |\n"
+        );
+        assert_eq!(
+            reviews_markdown(&prs[2..], now),
+            "No pending review requests.\n"
+        );
+    }
+
+    #[test]
+    fn time_report_totals_sessions_since_the_cutoff() {
+        let t = |mins: u64| SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 + mins * 60);
+        let entry = |todo: &Todo, from: u64, to: Option<u64>| TimeEntry {
+            id: uuid::Uuid::new_v4(),
+            todo_id: todo.id,
+            started_at: t(from),
+            ended_at: to.map(t),
+        };
+        let write = Todo::with_meta("Write RFC", Priority::Medium, None);
+        let review = Todo::with_meta("Review PRs", Priority::Low, None);
+        let gone = Todo::with_meta("Deleted", Priority::Low, None);
+        let entries = [
+            entry(&write, 0, Some(90)),
+            entry(&review, 100, Some(130)),
+            // Still running: counted up to `now`.
+            entry(&write, 200, None),
+            entry(&gone, 10, Some(20)),
+        ];
+        let todos = [write, review];
+
+        assert_eq!(
+            time_report(&todos, &entries, None, t(215)),
+            "   1h 45m  Write RFC\n   0h 30m  Review PRs\n   0h 10m  (deleted todo)\n  -------\n   2h 25m  total\n"
+        );
+        // Only the part after the cutoff counts.
+        assert_eq!(
+            time_report(&todos, &entries, Some(t(120)), t(215)),
+            "   0h 15m  Write RFC\n   0h 10m  Review PRs\n  -------\n   0h 25m  total\n"
+        );
+        assert_eq!(time_report(&todos, &[], None, t(0)), "No tracked time.\n");
+    }
+}
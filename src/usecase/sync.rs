@@ -0,0 +1,859 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use time::Duration;
+
+use crate::config::{GithubSettings, UnmatchAction};
+use crate::domain::activity::ActivityKind;
+use crate::domain::clock;
+use crate::domain::due::Due;
+use crate::domain::todo::{
+    GITHUB_FOLLOW_UP_KEY_PREFIX, GITHUB_OWN_PR_KEY_PREFIX, GITHUB_PR_KEY_PREFIX,
+    GITHUB_SEARCH_KEY_PREFIX, JIRA_KEY_PREFIX, Priority,
+};
+use crate::repo::TodoRepository;
+use crate::repo::github::model::{AttentionPrs, Pr, QueryStats, SearchResults};
+use crate::repo::jira::JiraIssues;
+use crate::usecase::attention;
+
+/// Account the GitHub token belongs to, as reported by the last successful sync.
+pub const META_GITHUB_LOGIN: &str = "github.viewer_login";
+/// Unix time of the last successful sync, i.e. the age of the cached PR snapshot.
+pub const META_PRS_SYNCED_AT: &str = "github.prs_synced_at";
+/// Unix time of the last successful Jira sync.
+pub const META_JIRA_SYNCED_AT: &str = "jira.synced_at";
+/// Per-host prefix of the incremental sync watermark (`github.watermark:<host>`).
+const META_WATERMARK_PREFIX: &str = "github.watermark:";
+/// PRs pinned with `koto watch` / `W`, one key (`owner/repo#N`) per line.
+const META_WATCHED_PRS: &str = "github.watched_prs";
+/// Incremental syncs re-fetch this much before the watermark, since GitHub's search
+/// index trails PR updates a little.
+pub const WATERMARK_OVERLAP_SECS: i64 = 300;
+/// Unix time of the last full (not incremental) sync.
+const META_FULL_SYNCED_AT: &str = "github.full_synced_at";
+/// An incremental sync never sees a PR leave the results (merged, closed, review
+/// given), so at least this often a sync is full and replaces the cached PRs.
+pub const FULL_SYNC_EVERY_SECS: i64 = 3600;
+
+/// Whether the next sync has to be full: the last one is older than
+/// [`FULL_SYNC_EVERY_SECS`], or there never was one.
+pub fn full_sync_due(repo: &dyn TodoRepository, now: i64) -> Result<bool> {
+    Ok(repo
+        .meta(META_FULL_SYNCED_AT)?
+        .and_then(|raw| raw.parse::<i64>().ok())
+        .is_none_or(|at| now - at >= FULL_SYNC_EVERY_SECS))
+}
+
+/// When the last successful sync of `host` started (unix seconds).
+pub fn watermark(repo: &dyn TodoRepository, host: &str) -> Result<Option<i64>> {
+    Ok(repo
+        .meta(&format!("{META_WATERMARK_PREFIX}{host}"))?
+        .and_then(|raw| raw.parse().ok()))
+}
+
+/// Remember that every update on `hosts` before `started_at` has been fetched.
+pub fn record_watermarks(
+    repo: &mut dyn TodoRepository,
+    hosts: &[String],
+    started_at: i64,
+) -> Result<()> {
+    for host in hosts {
+        repo.set_meta(
+            &format!("{META_WATERMARK_PREFIX}{host}"),
+            &started_at.to_string(),
+        )?;
+    }
+    Ok(())
+}
+
+/// PRs pinned into every sync, whether or not a review is requested.
+pub fn watched_prs(repo: &dyn TodoRepository) -> Result<Vec<String>> {
+    Ok(repo
+        .meta(META_WATCHED_PRS)?
+        .map(|raw| raw.lines().map(str::to_string).collect())
+        .unwrap_or_default())
+}
+
+/// Pin `key` into the sync; false when it was already watched.
+pub fn watch(repo: &mut dyn TodoRepository, key: &str) -> Result<bool> {
+    let mut keys = watched_prs(repo)?;
+    if keys.iter().any(|k| k == key) {
+        return Ok(false);
+    }
+    keys.push(key.to_string());
+    repo.set_meta(META_WATCHED_PRS, &keys.join("\n"))?;
+    Ok(true)
+}
+
+/// Stop watching `key`; false when it was not watched.
+pub fn unwatch(repo: &mut dyn TodoRepository, key: &str) -> Result<bool> {
+    let mut keys = watched_prs(repo)?;
+    let before = keys.len();
+    keys.retain(|k| k != key);
+    if keys.len() == before {
+        return Ok(false);
+    }
+    repo.set_meta(META_WATCHED_PRS, &keys.join("\n"))?;
+    Ok(true)
+}
+
+/// The PR key for `owner/repo#N`, `host/owner/repo#N` or a PR URL; a github.com host is
+/// dropped, like from the keys of PRs synced from github.com.
+pub fn parse_pr_ref(input: &str) -> Option<String> {
+    let input = input.trim();
+    let (path, number) = match input
+        .strip_prefix("https://")
+        .or_else(|| input.strip_prefix("http://"))
+    {
+        Some(url) => {
+            let url = url.split(['?', '#']).next().unwrap_or(url);
+            let (path, number) = url.trim_end_matches('/').split_once("/pull/")?;
+            let number = number.split('/').next().unwrap_or(number);
+            (path, number)
+        }
+        None => input.rsplit_once('#')?,
+    };
+    let path = path.strip_prefix("github.com/").unwrap_or(path);
+    let segments: Vec<&str> = path.split('/').collect();
+    let valid = matches!(segments.len(), 2 | 3)
+        && segments.iter().all(|s| !s.is_empty() && !s.contains(' '))
+        && number.parse::<u64>().is_ok_and(|n| n > 0);
+    valid.then(|| format!("{path}#{number}"))
+}
+
+#[derive(Debug, Default, Clone, Copy, serde::Serialize)]
+pub struct SyncSummary {
+    pub added: usize,
+    pub follow_ups: usize,
+    /// New todos for my own PRs needing action.
+    pub own_prs: usize,
+    /// New todos from saved searches.
+    pub search_added: usize,
+    /// Saved-search todos closed because their item no longer matches.
+    pub unmatched: usize,
+    /// New todos for watched PRs that are not review requests.
+    pub watched: usize,
+    /// Watched PRs that were merged or closed, so are no longer watched.
+    pub watches_finished: usize,
+}
+
+/// Store one sync result: PR todos, the PR cache, the viewer login and the query stats.
+/// Shared by the TUI (`g`) and `koto sync`.
+pub fn apply_attention(
+    repo: &mut dyn TodoRepository,
+    settings: &GithubSettings,
+    attention: &AttentionPrs,
+) -> Result<SyncSummary> {
+    let now = crate::now_unix();
+    if repo.meta(META_GITHUB_LOGIN)?.as_deref() != Some(attention.viewer_login.as_str()) {
+        repo.set_meta(META_GITHUB_LOGIN, &attention.viewer_login)?;
+    }
+    if attention.incremental || attention.is_partial() {
+        // Only changed PRs came back (or a query failed): fold them into the cache and
+        // drop what fell out of the sync window. PRs that left the results stay until the
+        // next full sync (`full_sync_due`).
+        let cutoff = now.saturating_sub((settings.days as i64) * 86_400);
+        let mut cached: Vec<Pr> = repo
+            .cached_prs()?
+            .into_iter()
+            .filter(|old| old.updated_at_unix >= cutoff)
+            .filter(|old| attention.prs.iter().all(|pr| pr.pr_key != old.pr_key))
+            .collect();
+        cached.extend(attention.prs.iter().cloned());
+        repo.replace_prs(&cached)?;
+    } else {
+        repo.replace_prs(&attention.prs)?;
+        repo.set_meta(META_FULL_SYNCED_AT, &now.to_string())?;
+    }
+    repo.set_meta(META_PRS_SYNCED_AT, &now.to_string())?;
+    repo.record_sync_stats(now, &attention.stats)?;
+
+    let mut summary = SyncSummary::default();
+    for pr in &attention.prs {
+        let requested = attention::should_add_todo(pr, settings.ignore_drafts);
+        if requested && let Some(triage) = attention::classify(pr, &settings.rules) {
+            let mut title = format!(
+                "{}/{}#{} by {}: {}",
+                pr.owner, pr.repo, pr.number, pr.author, pr.title
+            );
+            for tag in triage.tags.iter().rev() {
+                title = format!("#{tag} {title}");
+            }
+            let milestone_due = pr.milestone.as_ref().and_then(|m| m.due());
+            repo.add(
+                title,
+                triage.priority,
+                triage
+                    .due
+                    .or(milestone_due.filter(|_| settings.milestone_due)),
+                Some(pr.url.clone()),
+                Some(format!("{GITHUB_PR_KEY_PREFIX}{}", pr.pr_key)),
+            )?;
+            summary.added += 1;
+        } else if pr.watched && !requested {
+            let before = SystemTime::now();
+            let todo = repo.add(
+                format!(
+                    "#watch {}/{}#{} by {}: {}",
+                    pr.owner, pr.repo, pr.number, pr.author, pr.title
+                ),
+                Priority::Medium,
+                None,
+                Some(pr.url.clone()),
+                Some(format!("{GITHUB_PR_KEY_PREFIX}{}", pr.pr_key)),
+            )?;
+            if todo.created_at >= before {
+                summary.watched += 1;
+            }
+        }
+        if pr.viewer_approved && add_follow_up(repo, settings, pr)? {
+            summary.follow_ups += 1;
+        }
+        if settings.track_own_prs && pr.is_viewer_author && track_own_pr(repo, pr)? {
+            summary.own_prs += 1;
+        }
+    }
+    for key in &attention.finished_watches {
+        unwatch(repo, key)?;
+        let todo_key = format!("{GITHUB_PR_KEY_PREFIX}{key}");
+        if let Some(todo) = repo
+            .all()?
+            .into_iter()
+            .find(|t| !t.is_closed() && t.external_key.as_deref() == Some(todo_key.as_str()))
+        {
+            repo.toggle(todo.id)?;
+        }
+        summary.watches_finished += 1;
+    }
+    baseline_seen(repo, &attention.prs)?;
+    apply_searches(repo, settings, &attention.searches, &mut summary)?;
+    repo.log_activity(
+        ActivityKind::Sync,
+        None,
+        &format!(
+            "GitHub as {}: {} PRs{}",
+            attention.viewer_login,
+            attention.prs.len(),
+            if attention.is_partial() {
+                " (partial)"
+            } else {
+                ""
+            }
+        ),
+    )?;
+    Ok(summary)
+}
+
+#[derive(Debug, Default, Clone, Copy, serde::Serialize)]
+pub struct JiraSummary {
+    pub added: usize,
+    /// Todos completed because their issue no longer matches the JQL.
+    pub closed: usize,
+}
+
+/// One todo per Jira issue, titled `PROJ-123: summary` with the issue's priority and due
+/// date. When the whole result came back, open todos of issues that dropped out of it
+/// (resolved, reassigned) are completed. Shared by the TUI and `koto sync`.
+pub fn apply_jira(repo: &mut dyn TodoRepository, fetched: &JiraIssues) -> Result<JiraSummary> {
+    let mut summary = JiraSummary::default();
+    let mut seen = HashSet::new();
+    for issue in &fetched.issues {
+        seen.insert(format!("{JIRA_KEY_PREFIX}{}", issue.key));
+        let before = SystemTime::now();
+        let todo = repo.add(
+            format!("{}: {}", issue.key, issue.summary),
+            issue.priority,
+            issue.due,
+            Some(issue.url.clone()),
+            Some(format!("{JIRA_KEY_PREFIX}{}", issue.key)),
+        )?;
+        if todo.created_at >= before {
+            summary.added += 1;
+        }
+    }
+    if fetched.complete {
+        for todo in repo.all()? {
+            let Some(key) = todo.external_key.as_deref() else {
+                continue;
+            };
+            if key.starts_with(JIRA_KEY_PREFIX) && !todo.is_closed() && !seen.contains(key) {
+                repo.toggle(todo.id)?;
+                summary.closed += 1;
+            }
+        }
+    }
+    repo.set_meta(META_JIRA_SYNCED_AT, &crate::now_unix().to_string())?;
+    repo.log_activity(
+        ActivityKind::Sync,
+        None,
+        &format!(
+            "Jira: {} issues{}",
+            fetched.issues.len(),
+            if fetched.complete { "" } else { " (truncated)" }
+        ),
+    )?;
+    Ok(summary)
+}
+
+/// One todo per saved-search hit, tagged `#<tag>`; todos whose item stopped matching are
+/// completed or archived (`on_unmatch`). An item listed by several searches, or already
+/// tracked as a review-request PR, gets a single todo (from the first search).
+fn apply_searches(
+    repo: &mut dyn TodoRepository,
+    settings: &GithubSettings,
+    searches: &[SearchResults],
+    summary: &mut SyncSummary,
+) -> Result<()> {
+    let todos = repo.all()?;
+    let mut claimed: HashSet<String> = todos
+        .iter()
+        .filter_map(|t| t.github_pr_key())
+        .map(str::to_string)
+        .collect();
+    // A failed search keeps its items, so no later search takes them over.
+    for failed in searches.iter().filter(|s| s.hits.is_err()) {
+        claimed.extend(
+            todos
+                .iter()
+                .filter_map(|t| t.github_search_source())
+                .filter(|(tag, _)| *tag == failed.tag)
+                .map(|(_, key)| key.to_string()),
+        );
+    }
+
+    for search in searches {
+        let Ok(hits) = &search.hits else { continue };
+        let mut matched = HashSet::new();
+        for hit in hits {
+            if !claimed.insert(hit.key.clone()) {
+                continue;
+            }
+            matched.insert(hit.key.as_str());
+            let before = SystemTime::now();
+            let due = hit
+                .milestone
+                .as_ref()
+                .and_then(|m| m.due())
+                .filter(|_| settings.milestone_due);
+            let todo = repo.add(
+                format!("#{} {}: {}", search.tag, hit.key, hit.title),
+                Priority::Medium,
+                due,
+                Some(hit.url.clone()),
+                Some(format!(
+                    "{GITHUB_SEARCH_KEY_PREFIX}{}:{}",
+                    search.tag, hit.key
+                )),
+            )?;
+            if todo.created_at >= before {
+                summary.search_added += 1;
+            }
+        }
+
+        let action = settings
+            .searches
+            .iter()
+            .find(|s| s.tag() == search.tag)
+            .map_or(UnmatchAction::Complete, |s| s.on_unmatch);
+        for todo in &todos {
+            let Some((tag, key)) = todo.github_search_source() else {
+                continue;
+            };
+            if tag != search.tag || todo.is_closed() || matched.contains(key) {
+                continue;
+            }
+            match action {
+                UnmatchAction::Complete => repo.toggle(todo.id)?,
+                UnmatchAction::Archive => repo.archive(todo.id)?,
+            };
+            summary.unmatched += 1;
+        }
+    }
+    Ok(())
+}
+
+/// "merge owner/repo#N when green" after an approval, if enabled in `[github]`.
+/// Returns true when a new todo was created (re-adding an existing follow-up is a no-op).
+pub fn add_follow_up(
+    repo: &mut dyn TodoRepository,
+    settings: &GithubSettings,
+    pr: &Pr,
+) -> Result<bool> {
+    if !settings.follow_up_on_approve || pr.is_viewer_author {
+        return Ok(false);
+    }
+    let due =
+        Due::day(clock::today().saturating_add(Duration::days(settings.follow_up_due_days.max(0))));
+    let before = SystemTime::now();
+    let todo = repo.add(
+        format!("merge {} when green", pr.pr_key),
+        Priority::Medium,
+        Some(due),
+        Some(pr.url.clone()),
+        Some(format!("{GITHUB_FOLLOW_UP_KEY_PREFIX}{}", pr.pr_key)),
+    )?;
+    Ok(todo.created_at >= before)
+}
+
+/// PR todos not looked at yet start out as seen at the PR's current state, so only
+/// later activity marks them updated.
+fn baseline_seen(repo: &mut dyn TodoRepository, prs: &[Pr]) -> Result<()> {
+    let by_key: HashMap<&str, &Pr> = prs.iter().map(|pr| (pr.pr_key.as_str(), pr)).collect();
+    for todo in repo.all()? {
+        if todo.last_seen_at.is_some() {
+            continue;
+        }
+        let Some(pr) = todo
+            .github_pr_key()
+            .or_else(|| todo.own_pr_key())
+            .and_then(|key| by_key.get(key))
+        else {
+            continue;
+        };
+        let updated = UNIX_EPOCH + std::time::Duration::from_secs(pr.updated_at_unix.max(0) as u64);
+        repo.mark_seen(todo.id, pr.last_commit_sha.clone(), updated)?;
+    }
+    Ok(())
+}
+
+/// Keep the todo of my own PR in step with it: add (or reopen) "fix owner/repo#N: title"
+/// while it needs action, complete it once it is clear. Returns true when the todo was
+/// added or reopened.
+fn track_own_pr(repo: &mut dyn TodoRepository, pr: &Pr) -> Result<bool> {
+    let key = format!("{GITHUB_OWN_PR_KEY_PREFIX}{}", pr.pr_key);
+    let existing = repo
+        .all()?
+        .into_iter()
+        .find(|t| t.external_key.as_deref() == Some(key.as_str()));
+    let needs_action = !attention::own_pr_issues(pr).is_empty();
+    match existing {
+        Some(todo) if todo.is_closed() == needs_action => {
+            repo.toggle(todo.id)?;
+            Ok(needs_action)
+        }
+        Some(_) => Ok(false),
+        None if needs_action => {
+            repo.add(
+                format!("fix {}: {}", pr.pr_key, pr.title),
+                Priority::High,
+                Some(Due::day(clock::today())),
+                Some(pr.url.clone()),
+                Some(key),
+            )?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Table of per-query costs plus hints for `koto sync --explain`.
+pub fn explain(stats: &[QueryStats], days: u64, page_size: i32) -> String {
+    let mut out = format!(
+        "{:<18} {:>5} {:>6} {:>5} {:>5} {:>9}\n",
+        "query", "pages", "nodes", "kept", "cost", "elapsed"
+    );
+    for st in stats {
+        out.push_str(&format!(
+            "{:<18} {:>5} {:>6} {:>5} {:>5} {:>7}ms\n",
+            st.query, st.pages, st.nodes, st.kept, st.cost, st.elapsed_ms
+        ));
+    }
+    let sum = |f: fn(&QueryStats) -> u64| stats.iter().map(f).sum::<u64>();
+    let (pages, nodes, kept) = (
+        sum(|s| s.pages.into()),
+        sum(|s| s.nodes.into()),
+        sum(|s| s.kept.into()),
+    );
+    out.push_str(&format!(
+        "{:<18} {:>5} {:>6} {:>5} {:>5} {:>7}ms\n",
+        "total",
+        pages,
+        nodes,
+        kept,
+        sum(|s| s.cost.into()),
+        sum(|s| s.elapsed_ms)
+    ));
+    if let Some(remaining) = stats.iter().rev().find_map(|s| s.rate_remaining) {
+        out.push_str(&format!("rate limit remaining: {remaining}\n"));
+    }
+    out.push_str(&format!(
+        "settings: days = {days}, page_size = {page_size}\n"
+    ));
+
+    let mut hints = Vec::new();
+    if nodes > 0 && kept * 2 < nodes {
+        hints.push(format!(
+            "only {kept} of {nodes} fetched PRs were inside the {days}-day window; a smaller page_size wastes less"
+        ));
+    }
+    if pages as usize > stats.len() * 3 && page_size < 100 {
+        hints
+            .push("many pages per query; raise page_size (max 100) to cut round trips".to_string());
+    }
+    if kept > 200 {
+        hints.push("lots of PRs in the window; lower days to speed up syncs".to_string());
+    }
+    for st in stats.iter().filter(|s| s.reduced) {
+        hints.push(format!(
+            "{}: branch protection / team reviewers are forbidden for this token, so merge blockers may be missing",
+            st.query
+        ));
+    }
+    for hint in hints {
+        out.push_str(&format!("hint: {hint}\n"));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SavedSearch;
+    use crate::repo::github::model::{CiState, Milestone, ReviewState, SearchHit};
+    use crate::repo::jira::JiraIssue;
+    use crate::repo::memory::InMemoryTodoRepo;
+
+    fn results(tag: &str, keys: &[&str]) -> SearchResults {
+        SearchResults {
+            tag: tag.to_string(),
+            hits: Ok(keys
+                .iter()
+                .map(|key| SearchHit {
+                    key: key.to_string(),
+                    title: "t".to_string(),
+                    url: format!("https://github.com/{key}"),
+                    milestone: None,
+                })
+                .collect()),
+        }
+    }
+
+    #[test]
+    fn saved_searches_dedupe_and_close_unmatched() {
+        let mut repo = InMemoryTodoRepo::default();
+        let settings = GithubSettings {
+            searches: vec![SavedSearch {
+                query: "label:infra".into(),
+                tag: "#infra".into(),
+                on_unmatch: UnmatchAction::Archive,
+            }],
+            ..GithubSettings::default()
+        };
+        let mut summary = SyncSummary::default();
+        let first = [
+            results("oncall", &["a/b#1", "a/b#2"]),
+            results("infra", &["a/b#2", "a/b#3"]),
+        ];
+        apply_searches(&mut repo, &settings, &first, &mut summary).unwrap();
+        assert_eq!(summary.search_added, 3);
+        let sources = |repo: &InMemoryTodoRepo| {
+            let mut keys: Vec<String> = repo
+                .all()
+                .unwrap()
+                .iter()
+                .filter(|t| !t.is_closed())
+                .filter_map(|t| t.external_key.clone())
+                .collect();
+            keys.sort();
+            keys
+        };
+        assert_eq!(
+            sources(&repo),
+            [
+                "github_search:infra:a/b#3",
+                "github_search:oncall:a/b#1",
+                "github_search:oncall:a/b#2"
+            ]
+        );
+
+        // #1 leaves oncall (completed); #3 leaves infra (archived); a failed search is left alone.
+        let mut summary = SyncSummary::default();
+        let mut failed = results("infra", &[]);
+        failed.hits = Err("boom".into());
+        apply_searches(
+            &mut repo,
+            &settings,
+            &[results("oncall", &["a/b#2"]), failed],
+            &mut summary,
+        )
+        .unwrap();
+        assert_eq!(summary.unmatched, 1);
+        apply_searches(
+            &mut repo,
+            &settings,
+            &[results("oncall", &["a/b#2"]), results("infra", &[])],
+            &mut summary,
+        )
+        .unwrap();
+        assert_eq!(summary.unmatched, 2);
+        assert_eq!(sources(&repo), ["github_search:oncall:a/b#2"]);
+        assert!(
+            repo.all()
+                .unwrap()
+                .iter()
+                .any(|t| t.is_closed() && t.title.starts_with("#oncall a/b#1"))
+        );
+    }
+
+    #[test]
+    fn milestone_due_dates_are_adopted_when_enabled() {
+        let mut hits = results("oncall", &["a/b#1"]);
+        if let Ok(hits) = &mut hits.hits {
+            hits[0].milestone = Some(Milestone {
+                title: "v2.0".to_string(),
+                due_on: Some("2030-03-31".to_string()),
+            });
+        }
+        let dues = |milestone_due: bool| {
+            let mut repo = InMemoryTodoRepo::default();
+            let settings = GithubSettings {
+                milestone_due,
+                ..GithubSettings::default()
+            };
+            let mut summary = SyncSummary::default();
+            apply_searches(&mut repo, &settings, &[hits.clone()], &mut summary).unwrap();
+            repo.all().unwrap()[0].due
+        };
+        assert_eq!(dues(false), None);
+        assert_eq!(
+            dues(true),
+            Some(clock::end_of_day(time::macros::date!(2030 - 03 - 31)))
+        );
+    }
+
+    #[test]
+    fn own_prs_needing_action_come_and_go() {
+        let mut repo = InMemoryTodoRepo::default();
+        let settings = GithubSettings {
+            track_own_prs: true,
+            ..GithubSettings::default()
+        };
+        let mut pr: Pr = serde_json::from_value(serde_json::json!({
+            "pr_key": "me/app#5", "owner": "me", "repo": "app", "number": 5,
+            "author": "me", "title": "Add login", "url": "https://github.com/me/app/pull/5",
+            "updated_at_unix": 0, "ci_state": "Failure", "ci_checks": [],
+            "review_state": "None", "is_draft": false, "is_viewer_author": true
+        }))
+        .unwrap();
+        let mut attention = AttentionPrs {
+            viewer_login: "me".to_string(),
+            prs: vec![pr.clone()],
+            stats: Vec::new(),
+            incremental: false,
+            failed_queries: Vec::new(),
+            searches: Vec::new(),
+            finished_watches: Vec::new(),
+        };
+        let open = |repo: &InMemoryTodoRepo| {
+            repo.all()
+                .unwrap()
+                .iter()
+                .filter(|t| !t.is_closed() && t.own_pr_key() == Some("me/app#5"))
+                .count()
+        };
+
+        let summary = apply_attention(&mut repo, &settings, &attention).unwrap();
+        assert_eq!((summary.own_prs, open(&repo)), (1, 1));
+        pr.ci_state = CiState::Success;
+        attention.prs = vec![pr.clone()];
+        apply_attention(&mut repo, &settings, &attention).unwrap();
+        assert_eq!(open(&repo), 0);
+        pr.review_state = ReviewState::ChangesRequested;
+        attention.prs = vec![pr];
+        let summary = apply_attention(&mut repo, &settings, &attention).unwrap();
+        assert_eq!((summary.own_prs, open(&repo)), (1, 1));
+        assert_eq!(repo.all().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn prs_gone_from_incremental_results_leave_at_the_next_full_sync() {
+        let mut repo = InMemoryTodoRepo::default();
+        let settings = GithubSettings::default();
+        let now = crate::now_unix();
+        let pr = |number: i64| -> Pr {
+            serde_json::from_value(serde_json::json!({
+                "pr_key": format!("acme/api#{number}"), "owner": "acme", "repo": "api",
+                "number": number, "author": "bob", "title": "t",
+                "url": format!("https://github.com/acme/api/pull/{number}"),
+                "updated_at_unix": now, "ci_state": "Success", "ci_checks": [],
+                "review_state": "Requested", "is_draft": false, "is_viewer_author": false
+            }))
+            .unwrap()
+        };
+        let cached = |repo: &InMemoryTodoRepo| -> Vec<String> {
+            let mut keys: Vec<_> = repo
+                .cached_prs()
+                .unwrap()
+                .into_iter()
+                .map(|p| p.pr_key)
+                .collect();
+            keys.sort();
+            keys
+        };
+        let mut attention = AttentionPrs {
+            viewer_login: "me".to_string(),
+            prs: vec![pr(1), pr(2)],
+            stats: Vec::new(),
+            incremental: false,
+            failed_queries: Vec::new(),
+            searches: Vec::new(),
+            finished_watches: Vec::new(),
+        };
+        assert!(full_sync_due(&repo, now).unwrap());
+        apply_attention(&mut repo, &settings, &attention).unwrap();
+        assert!(!full_sync_due(&repo, now).unwrap());
+
+        // #1 was merged: an incremental sync only returns what is still open.
+        attention.incremental = true;
+        attention.prs = vec![pr(2)];
+        apply_attention(&mut repo, &settings, &attention).unwrap();
+        assert_eq!(cached(&repo), ["acme/api#1", "acme/api#2"]);
+        assert!(!full_sync_due(&repo, now + 60).unwrap());
+
+        // An hour later the sync is full again, and the merged PR leaves the cache.
+        assert!(full_sync_due(&repo, now + FULL_SYNC_EVERY_SECS).unwrap());
+        attention.incremental = false;
+        apply_attention(&mut repo, &settings, &attention).unwrap();
+        assert_eq!(cached(&repo), ["acme/api#2"]);
+    }
+
+    #[test]
+    fn watched_prs_sync_until_they_finish() {
+        assert_eq!(
+            parse_pr_ref("https://github.com/acme/api/pull/42/files").as_deref(),
+            Some("acme/api#42")
+        );
+        assert_eq!(
+            parse_pr_ref("https://ghe.example.com/acme/api/pull/7").as_deref(),
+            Some("ghe.example.com/acme/api#7")
+        );
+        assert_eq!(
+            parse_pr_ref(" acme/api#42 ").as_deref(),
+            Some("acme/api#42")
+        );
+        assert_eq!(parse_pr_ref("acme#42"), None);
+        assert_eq!(parse_pr_ref("acme/api#x"), None);
+
+        let mut repo = InMemoryTodoRepo::default();
+        assert!(watch(&mut repo, "acme/api#42").unwrap());
+        assert!(!watch(&mut repo, "acme/api#42").unwrap());
+        let mut pr: Pr = serde_json::from_value(serde_json::json!({
+            "pr_key": "acme/api#42", "owner": "acme", "repo": "api", "number": 42,
+            "author": "bob", "title": "Rate limits", "url": "https://github.com/acme/api/pull/42",
+            "updated_at_unix": 0, "ci_state": "Success", "ci_checks": [],
+            "review_state": "None", "is_draft": false, "is_viewer_author": false
+        }))
+        .unwrap();
+        pr.watched = true;
+        let mut attention = AttentionPrs {
+            viewer_login: "me".to_string(),
+            prs: vec![pr],
+            stats: Vec::new(),
+            incremental: false,
+            failed_queries: Vec::new(),
+            searches: Vec::new(),
+            finished_watches: Vec::new(),
+        };
+        let settings = GithubSettings::default();
+        let summary = apply_attention(&mut repo, &settings, &attention).unwrap();
+        assert_eq!((summary.added, summary.watched), (0, 1));
+        assert_eq!(
+            repo.all().unwrap()[0].title,
+            "#watch acme/api#42 by bob: Rate limits"
+        );
+
+        attention.prs.clear();
+        attention.finished_watches = vec!["acme/api#42".to_string()];
+        let summary = apply_attention(&mut repo, &settings, &attention).unwrap();
+        assert_eq!(summary.watches_finished, 1);
+        assert!(repo.all().unwrap()[0].is_closed());
+        assert!(watched_prs(&repo).unwrap().is_empty());
+    }
+
+    #[test]
+    fn host_prefixed_refs_name_the_same_pr_as_synced_keys() {
+        // PRs synced from github.com are keyed without a host.
+        for input in [
+            "acme/api#7",
+            "github.com/acme/api#7",
+            "https://github.com/acme/api/pull/7",
+        ] {
+            assert_eq!(
+                parse_pr_ref(input).as_deref(),
+                Some("acme/api#7"),
+                "{input}"
+            );
+        }
+
+        // Those of another account carry its host, and stay apart from github.com's.
+        let pr: Pr = serde_json::from_value(serde_json::json!({
+            "pr_key": "acme/api#7", "owner": "acme", "repo": "api", "number": 7,
+            "author": "bob", "title": "t", "url": "https://ghe.example.com/acme/api/pull/7",
+            "updated_at_unix": 0, "ci_state": "Success", "ci_checks": [],
+            "review_state": "Requested", "is_draft": false, "is_viewer_author": false
+        }))
+        .unwrap();
+        let mut attention = AttentionPrs {
+            viewer_login: "me".to_string(),
+            prs: vec![pr],
+            stats: Vec::new(),
+            incremental: false,
+            failed_queries: Vec::new(),
+            searches: Vec::new(),
+            finished_watches: Vec::new(),
+        };
+        attention.qualify("ghe.example.com");
+        let key = attention.prs[0].pr_key.clone();
+        for input in [
+            "ghe.example.com/acme/api#7",
+            "https://ghe.example.com/acme/api/pull/7",
+        ] {
+            assert_eq!(parse_pr_ref(input).as_ref(), Some(&key), "{input}");
+        }
+        assert_ne!(key, "acme/api#7");
+    }
+
+    #[test]
+    fn jira_issues_become_todos_and_close_when_gone() {
+        let issue = |key: &str| JiraIssue {
+            key: key.to_string(),
+            summary: "fix it".to_string(),
+            priority: Priority::High,
+            due: None,
+            url: format!("https://acme.atlassian.net/browse/{key}"),
+        };
+        let mut repo = InMemoryTodoRepo::default();
+        let mut fetched = JiraIssues {
+            issues: vec![issue("OPS-1"), issue("OPS-2")],
+            complete: true,
+        };
+        let summary = apply_jira(&mut repo, &fetched).unwrap();
+        assert_eq!((summary.added, summary.closed), (2, 0));
+        assert!(
+            repo.all()
+                .unwrap()
+                .iter()
+                .all(|t| t.priority == Priority::High)
+        );
+
+        // A truncated result never closes anything.
+        fetched.issues.pop();
+        fetched.complete = false;
+        let summary = apply_jira(&mut repo, &fetched).unwrap();
+        assert_eq!((summary.added, summary.closed), (0, 0));
+
+        fetched.complete = true;
+        let summary = apply_jira(&mut repo, &fetched).unwrap();
+        assert_eq!(summary.closed, 1);
+        let done: Vec<String> = repo
+            .all()
+            .unwrap()
+            .into_iter()
+            .filter(|t| t.is_closed())
+            .map(|t| t.title)
+            .collect();
+        assert_eq!(done, ["OPS-2: fix it"]);
+        assert!(repo.meta(META_JIRA_SYNCED_AT).unwrap().is_some());
+    }
+}
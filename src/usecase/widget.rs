@@ -0,0 +1,222 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use time::Date;
+
+use crate::domain::due::DueBucket;
+use crate::domain::subtask::Hierarchy;
+use crate::domain::todo::{Priority, Todo};
+use crate::repo::github::model::{CiState, Pr, ReviewState};
+
+/// Bump only for breaking changes; new fields may be added within a version.
+pub const WIDGET_FORMAT_VERSION: u32 = 1;
+/// Items listed in `top`.
+const TOP_ITEMS: usize = 5;
+
+/// `koto widget --format json`: a compact summary for menu-bar and desktop widgets
+/// (xbar, Übersicht, Raycast). Documented in the README; keep it stable.
+#[derive(Debug, Serialize)]
+pub struct Widget {
+    pub version: u32,
+    /// Unix seconds.
+    pub generated_at: i64,
+    pub counts: WidgetCounts,
+    /// Earliest due date that has not passed yet (unix seconds), if any.
+    pub next_due: Option<i64>,
+    /// Open todos ordered like the agenda: by due date (undated last), then priority.
+    pub top: Vec<WidgetItem>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct WidgetCounts {
+    pub open: usize,
+    pub overdue: usize,
+    /// Due later today (local time); not included in `overdue`.
+    pub today: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WidgetItem {
+    pub id: String,
+    pub title: String,
+    /// `"high"`, `"medium"` or `"low"`.
+    pub priority: &'static str,
+    /// Unix seconds; a subtask without its own due date reports its parent's.
+    pub due: Option<i64>,
+    pub overdue: bool,
+    pub url: Option<String>,
+}
+
+/// Summarise open (not done, not archived) todos as of `now` / `today`.
+pub fn widget(todos: &[Todo], now: SystemTime, today: Date) -> Widget {
+    let hierarchy = Hierarchy::new(todos);
+    let mut open: Vec<(Option<SystemTime>, &Todo)> = todos
+        .iter()
+        .filter(|t| !t.is_closed() && !t.archived)
+        .map(|t| (hierarchy.effective_due(t.id).map(|d| d.at), t))
+        .collect();
+    open.sort_by(|(a_due, a), (b_due, b)| {
+        (a_due.is_none(), a_due, a.priority, a.created_at).cmp(&(
+            b_due.is_none(),
+            b_due,
+            b.priority,
+            b.created_at,
+        ))
+    });
+
+    let mut counts = WidgetCounts {
+        open: open.len(),
+        ..WidgetCounts::default()
+    };
+    for due in open.iter().filter_map(|(due, _)| *due) {
+        if due < now {
+            counts.overdue += 1;
+        } else if DueBucket::of(due, today) == DueBucket::Today {
+            counts.today += 1;
+        }
+    }
+    let next_due = open
+        .iter()
+        .filter_map(|(due, _)| *due)
+        .filter(|due| *due >= now)
+        .min()
+        .map(unix);
+
+    let top = open
+        .iter()
+        .take(TOP_ITEMS)
+        .map(|(due, todo)| WidgetItem {
+            id: todo.id.to_string(),
+            title: todo.title.clone(),
+            priority: priority_name(todo.priority),
+            due: due.map(unix),
+            overdue: due.is_some_and(|d| d < now),
+            url: todo.external_url.clone(),
+        })
+        .collect();
+
+    Widget {
+        version: WIDGET_FORMAT_VERSION,
+        generated_at: unix(now),
+        counts,
+        next_due,
+        top,
+    }
+}
+
+/// `koto status`: one line for tmux / starship / polybar, e.g.
+/// `todos: 3 due today • 2 PR reviews • CI: 1 failing`. The PR parts come from the last
+/// sync's cache and are left out before the first one.
+pub fn status_line(todos: &[Todo], prs: Option<&[Pr]>, now: SystemTime, today: Date) -> String {
+    let counts = widget(todos, now, today).counts;
+    let mut todo_part = format!("todos: {} due today", counts.today);
+    if counts.overdue > 0 {
+        todo_part += &format!(", {} overdue", counts.overdue);
+    }
+    let mut parts = vec![todo_part];
+    if let Some(prs) = prs {
+        let reviews = prs
+            .iter()
+            .filter(|pr| matches!(pr.review_state, ReviewState::Requested))
+            .count();
+        let failing = prs
+            .iter()
+            .filter(|pr| pr.is_viewer_author && matches!(pr.ci_state, CiState::Failure))
+            .count();
+        parts.push(format!("{reviews} PR reviews"));
+        parts.push(format!("CI: {failing} failing"));
+    }
+    parts.join(" • ")
+}
+
+fn priority_name(priority: Priority) -> &'static str {
+    match priority {
+        Priority::High => "high",
+        Priority::Medium => "medium",
+        Priority::Low => "low",
+    }
+}
+
+fn unix(t: SystemTime) -> i64 {
+    match t.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::domain::clock;
+    use crate::domain::due::Due;
+    use crate::domain::todo::Status;
+
+    #[test]
+    fn counts_and_orders_open_todos() {
+        let today = clock::today();
+        let noon = today
+            .with_hms(12, 0, 0)
+            .unwrap()
+            .assume_offset(clock::local_offset())
+            .unix_timestamp();
+        let now = UNIX_EPOCH + Duration::from_secs(noon as u64);
+        let hour = Duration::from_secs(3600);
+
+        let late = Todo::with_meta("Late", Priority::Low, Some(Due::timed(now - hour)));
+        let soon = Todo::with_meta("Soon", Priority::High, Some(Due::timed(now + hour)));
+        let mut child = Todo::with_meta("Child of soon", Priority::Medium, None);
+        child.parent_id = Some(soon.id);
+        let undated = Todo::with_meta("Someday", Priority::High, None);
+        let mut done = Todo::with_meta("Done", Priority::High, Some(Due::timed(now - hour)));
+        done.status = Status::Done;
+        let next_week = Todo::with_meta(
+            "Next week",
+            Priority::High,
+            Some(Due::timed(now + hour * 24 * 7)),
+        );
+
+        let w = widget(
+            &[undated, next_week, child, done, soon.clone(), late.clone()],
+            now,
+            today,
+        );
+        assert_eq!(w.version, 1);
+        assert_eq!((w.counts.open, w.counts.overdue, w.counts.today), (5, 1, 2));
+        assert_eq!(w.next_due, Some(unix(now + hour)));
+        let titles: Vec<&str> = w.top.iter().map(|i| i.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            ["Late", "Soon", "Child of soon", "Next week", "Someday"]
+        );
+        assert!(w.top[0].overdue);
+        assert_eq!(w.top[2].due, Some(unix(now + hour)));
+
+        let json = serde_json::to_value(&w).unwrap();
+        assert_eq!(json["top"][1]["priority"], "high");
+        assert_eq!(json["counts"]["today"], 2);
+
+        let todos = [late, soon];
+        assert_eq!(
+            status_line(&todos, None, now, today),
+            "todos: 1 due today, 1 overdue"
+        );
+        let pr = |requested: bool, own_failing: bool| -> Pr {
+            serde_json::from_value(serde_json::json!({
+                "pr_key": "acme/api#1", "owner": "acme", "repo": "api", "number": 1,
+                "author": "bob", "title": "t", "url": "https://github.com/acme/api/pull/1",
+                "updated_at_unix": 0, "ci_checks": [], "is_draft": false,
+                "ci_state": if own_failing { "Failure" } else { "Success" },
+                "review_state": if requested { "Requested" } else { "None" },
+                "is_viewer_author": own_failing
+            }))
+            .unwrap()
+        };
+        let prs = [pr(true, false), pr(true, false), pr(false, true)];
+        assert_eq!(
+            status_line(&todos, Some(&prs), now, today),
+            "todos: 1 due today, 1 overdue • 2 PR reviews • CI: 1 failing"
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use std::future::Future;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, OnceLock};
+
+use anyhow::{Result, anyhow};
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+
+use crate::app::{
+    GithubConfig, InboxEvent, PrAction, PrActionOutcome, SyncEvent, SyncOutcome,
+    fetch_attention_all,
+};
+use crate::domain::todo::Todo;
+use crate::domain::todo::TodoId;
+use crate::repo::caldav::CaldavConfig;
+use crate::repo::github::notifications::{self, Notification};
+use crate::repo::github::{self, QueryError, model::Pr};
+use crate::repo::jira::{self, JiraConfig};
+use crate::usecase::caldav::{self, SyncState};
+
+/// Called after a job sent something, so the UI loop wakes up and polls it.
+pub type Notify = Arc<dyn Fn() + Send + Sync>;
+
+/// Network work the TUI hands to the background runtime. Results come back on the
+/// std channel in the job, which the UI loop polls without blocking.
+pub enum SyncJob {
+    /// Fetch attention PRs of every account; progress events, then the outcome.
+    Github {
+        accounts: Vec<GithubConfig>,
+        started_at: i64,
+        events: Sender<SyncEvent>,
+    },
+    /// Fetch the issues matching `[jira] jql`.
+    Jira {
+        cfg: JiraConfig,
+        events: Sender<SyncEvent>,
+    },
+    /// Merge `todos` with the `[caldav]` list and write the server's side.
+    Caldav {
+        cfg: CaldavConfig,
+        todos: Vec<Todo>,
+        state: SyncState,
+        events: Sender<SyncEvent>,
+    },
+    /// Fetch the unread notifications of `cfg`'s account.
+    Notifications {
+        cfg: GithubConfig,
+        reply: Sender<InboxEvent>,
+    },
+    /// Mark one notification thread read.
+    MarkNotificationRead {
+        cfg: GithubConfig,
+        thread_id: String,
+        reply: Sender<InboxEvent>,
+    },
+    /// Submit a review or update the branch of `pr`.
+    PrAction {
+        cfg: GithubConfig,
+        pr: Box<Pr>,
+        todo_id: TodoId,
+        action: PrAction,
+        reply: Sender<PrActionOutcome>,
+    },
+}
+
+impl SyncJob {
+    async fn run(self, notify: impl Fn() + Send + Sync + 'static) {
+        let notify = Arc::new(notify);
+        match self {
+            SyncJob::Github {
+                accounts,
+                started_at,
+                events,
+            } => {
+                let progress = events.clone();
+                let progress_notify = Arc::clone(&notify);
+                let result = fetch_attention_all(
+                    accounts,
+                    Arc::new(move |p| {
+                        let _ = progress.send(SyncEvent::Progress(p));
+                        progress_notify();
+                    }),
+                )
+                .await
+                .map_err(QueryError::from_anyhow);
+                let _ = events.send(SyncEvent::Done(SyncOutcome { result, started_at }));
+            }
+            SyncJob::Jira { cfg, events } => {
+                let result = jira::fetch_assigned(&cfg).await.map_err(|e| e.to_string());
+                let _ = events.send(SyncEvent::Jira(result));
+            }
+            SyncJob::Caldav {
+                cfg,
+                todos,
+                state,
+                events,
+            } => {
+                let result = caldav::exchange(&cfg, todos, state)
+                    .await
+                    .map_err(|e| format!("{e:#}"));
+                let _ = events.send(SyncEvent::Caldav(result));
+            }
+            SyncJob::Notifications { cfg, reply } => {
+                let result = fetch_notifications(&cfg).await.map_err(|e| e.to_string());
+                let _ = reply.send(InboxEvent::Fetched(result));
+            }
+            SyncJob::MarkNotificationRead {
+                cfg,
+                thread_id,
+                reply,
+            } => {
+                let result = mark_notification_read(&cfg, &thread_id)
+                    .await
+                    .map_err(|e| e.to_string());
+                let _ = reply.send(InboxEvent::MarkedRead { thread_id, result });
+            }
+            SyncJob::PrAction {
+                cfg,
+                pr,
+                todo_id,
+                action,
+                reply,
+            } => {
+                let result = run_pr_action(&cfg, &pr, &action)
+                    .await
+                    .map_err(|e| e.to_string());
+                let _ = reply.send(PrActionOutcome {
+                    todo_id,
+                    pr_key: pr.pr_key,
+                    action,
+                    result,
+                });
+            }
+        }
+        notify();
+    }
+}
+
+async fn fetch_notifications(cfg: &GithubConfig) -> Result<Vec<Notification>> {
+    let octo = github::client(cfg.token.clone(), cfg.api_base.clone())?;
+    notifications::fetch(&octo).await
+}
+
+async fn mark_notification_read(cfg: &GithubConfig, thread_id: &str) -> Result<()> {
+    let octo = github::client(cfg.token.clone(), cfg.api_base.clone())?;
+    notifications::mark_read(&octo, thread_id).await
+}
+
+async fn run_pr_action(cfg: &GithubConfig, pr: &Pr, action: &PrAction) -> Result<()> {
+    let octo = github::client(cfg.token.clone(), cfg.api_base.clone())?;
+    match action {
+        PrAction::Review { event, body } => github::submit_review(&octo, pr, *event, body).await,
+        PrAction::UpdateBranch => github::update_branch(&octo, pr).await,
+    }
+}
+
+/// The one Tokio runtime of the TUI, shared by every sync source and PR action.
+/// Jobs are queued on a channel and each runs as its own task, so a slow source
+/// never holds up another.
+pub struct Worker {
+    jobs: UnboundedSender<SyncJob>,
+    notify: Arc<OnceLock<Notify>>,
+    _runtime: Runtime,
+}
+
+impl Worker {
+    pub fn start() -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("koto-worker")
+            .enable_all()
+            .build()
+            .map_err(|e| anyhow!("failed to build tokio runtime: {e}"))?;
+        let (jobs, mut queue) = unbounded_channel::<SyncJob>();
+        let notify: Arc<OnceLock<Notify>> = Arc::default();
+        let slot = Arc::clone(&notify);
+        runtime.spawn(async move {
+            while let Some(job) = queue.recv().await {
+                let slot = Arc::clone(&slot);
+                tokio::spawn(job.run(move || {
+                    if let Some(notify) = slot.get() {
+                        notify();
+                    }
+                }));
+            }
+        });
+        Ok(Self {
+            jobs,
+            notify,
+            _runtime: runtime,
+        })
+    }
+
+    /// Install the wake-up hook; only the first call has an effect.
+    pub fn set_notify(&self, notify: Notify) {
+        let _ = self.notify.set(notify);
+    }
+
+    pub fn submit(&self, job: SyncJob) {
+        // The dispatcher only stops when the runtime is dropped together with `self`.
+        let _ = self.jobs.send(job);
+    }
+}
+
+/// Run network work to completion on a current-thread runtime, for one-shot commands.
+pub fn block_on<F: Future>(future: F) -> Result<F::Output> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| anyhow!("failed to build tokio runtime: {e}"))?;
+    Ok(runtime.block_on(future))
+}